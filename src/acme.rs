@@ -0,0 +1,184 @@
+use color_eyre::eyre::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// An ACME order (RFC 8555 section 7.1.3), the fields worth showing when
+/// debugging issuance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(default)]
+    pub identifiers: Vec<Identifier>,
+    #[serde(default)]
+    pub authorizations: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finalize: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Problem>,
+}
+
+impl Order {
+    /// What the order is waiting on, going by its status.
+    pub fn progress(&self) -> &'static str {
+        match self.status.as_str() {
+            "pending" => "waiting for the authorizations to be validated",
+            "ready" => "authorized, waiting for a CSR to be sent to `finalize`",
+            "processing" => "the CA is issuing the certificate",
+            "valid" => "the certificate has been issued",
+            "invalid" => "an authorization failed or the order expired",
+            _ => "unknown status",
+        }
+    }
+}
+
+/// An ACME authorization (RFC 8555 section 7.1.4): the proof of control of
+/// one identifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Authorization {
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    pub identifier: Identifier,
+    #[serde(default)]
+    pub challenges: Vec<Challenge>,
+    #[serde(default)]
+    pub wildcard: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Challenge {
+    /// E.g. `http-01`, `dns-01` or `tls-alpn-01`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validated: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Problem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identifier {
+    /// `dns` or `ip`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: String,
+}
+
+impl std::fmt::Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.kind, self.value)
+    }
+}
+
+/// An RFC 7807 problem document, as the `error` of an order or challenge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Problem {
+    /// E.g. `urn:ietf:params:acme:error:unauthorized`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subproblems: Vec<Problem>,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = self.kind.as_deref().unwrap_or("error");
+        let kind = kind
+            .strip_prefix("urn:ietf:params:acme:error:")
+            .unwrap_or(kind);
+        match &self.detail {
+            Some(detail) => write!(f, "{kind}: {detail}"),
+            None => write!(f, "{kind}"),
+        }
+    }
+}
+
+/// An ACME resource given to `pls acme`.
+#[derive(Debug, Clone)]
+pub enum Resource {
+    Order(Order),
+    Authorization(Authorization),
+}
+
+impl Resource {
+    /// Parse an order or authorization, told apart by their `authorizations`
+    /// and `challenges` fields.
+    pub fn from_json(data: &[u8]) -> Result<Self> {
+        let value: serde_json::Value =
+            serde_json::from_slice(data).context("parsing ACME resource as JSON")?;
+
+        if value.get("authorizations").is_some() {
+            Ok(Self::Order(
+                serde_json::from_value(value).context("parsing ACME order")?,
+            ))
+        } else if value.get("challenges").is_some() {
+            Ok(Self::Authorization(
+                serde_json::from_value(value).context("parsing ACME authorization")?,
+            ))
+        } else {
+            bail!("not an ACME order or authorization: no `authorizations` or `challenges`")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order() {
+        let order = br#"{
+            "status": "invalid",
+            "expires": "2025-01-08T00:00:00Z",
+            "identifiers": [{"type": "dns", "value": "example.com"}],
+            "authorizations": ["https://ca.example/acme/authz/1"],
+            "finalize": "https://ca.example/acme/order/1/finalize",
+            "error": {
+                "type": "urn:ietf:params:acme:error:unauthorized",
+                "detail": "Invalid response from http://example.com/.well-known/acme-challenge/x: 404"
+            }
+        }"#;
+
+        let Resource::Order(order) = Resource::from_json(order).unwrap() else {
+            panic!("expected an order");
+        };
+        assert_eq!(order.identifiers[0].to_string(), "dns:example.com");
+        assert_eq!(order.authorizations.len(), 1);
+        assert!(order.certificate.is_none());
+        assert_eq!(
+            order.error.unwrap().to_string(),
+            "unauthorized: Invalid response from http://example.com/.well-known/acme-challenge/x: 404"
+        );
+        assert_eq!(
+            order.progress(),
+            "an authorization failed or the order expired"
+        );
+    }
+
+    #[test]
+    fn authorization() {
+        let authz = br#"{
+            "status": "pending",
+            "identifier": {"type": "dns", "value": "example.com"},
+            "wildcard": true,
+            "challenges": [
+                {"type": "dns-01", "url": "https://ca.example/acme/chall/1", "status": "pending", "token": "abc"}
+            ]
+        }"#;
+
+        let Resource::Authorization(authz) = Resource::from_json(authz).unwrap() else {
+            panic!("expected an authorization");
+        };
+        assert!(authz.wildcard);
+        assert_eq!(authz.challenges[0].kind, "dns-01");
+
+        assert!(Resource::from_json(br#"{"status": "valid"}"#).is_err());
+    }
+}
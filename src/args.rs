@@ -1,5 +1,19 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
 use crate::{
-    commands::{connect::Connect, parse::Parse, Format},
+    cache,
+    commands::{
+        acme::Acme, caa::Caa, capabilities::Capabilities, check_pair::CheckPair, connect::Connect,
+        connect::Target, convert::Convert, csr::Csr, doctor::Doctor, expiry::Expiry,
+        explain_expr::ExplainExpr, find::Find, inspect::Inspect, jwt::Jwt, mask::Mask,
+        match_hostname::MatchHostname, parse::Parse, pin::Pin, random::Random, same_key::SameKey,
+        schema::Schema, serve::Serve, trust::Trust, verify::Verify, watch::Watch, ColorChoice,
+        Format, NameFormat, RenderOptions, SerialFormat,
+    },
+    config::Config,
+    progress,
+    theme::{Theme, ThemeName},
     CommandExt,
 };
 use clap::{Parser, Subcommand};
@@ -15,6 +29,14 @@ use clap::{Parser, Subcommand};
 /// pls parse ./cert.pem | jq
 ///
 /// pls connect https://example.com
+///
+/// Without a command, a file is parsed and a host connected to:
+///
+/// pls ./cert.pem
+///
+/// pls example.com
+///
+/// pls -- connect (a file named `connect`)
 #[derive(Default, Debug, Parser)]
 #[command(version, name = "pls", author = "Fisher")]
 pub struct Cli {
@@ -22,6 +44,13 @@ pub struct Cli {
     #[command(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 
+    /// How to write the `-v` diagnostics to stderr. `json` writes one object
+    /// per line for every event and every finished stage (DNS, connect,
+    /// handshake, parse, ...) with its fields and timings, to triage failures
+    /// in automation.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    pub trace_output: TraceOutput,
+
     /// Output the results as JSON. Defaults to `true` if stdout is not a TTY.
     #[arg(long, global = true, conflicts_with = "text", conflicts_with = "pem")]
     json: bool,
@@ -31,17 +60,123 @@ pub struct Cli {
     #[arg(long, global = true, conflicts_with = "json", conflicts_with = "pem")]
     text: bool,
 
+    /// Output the results as JSON on a single line.
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["json", "text", "pem", "ndjson"]
+    )]
+    json_compact: bool,
+
+    /// Output the results as newline delimited JSON, one object per cert, key
+    /// or host.
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["json", "text", "pem", "json_compact"]
+    )]
+    ndjson: bool,
+
     /// Output the results as PEM encoded data. Defaults to `false`.
     #[arg(long, global = true, conflicts_with = "json", conflicts_with = "text")]
     pem: bool,
 
+    /// Put a comment before each PEM of the `--pem` output, e.g. `# 1:
+    /// CN=example.com, expires 2025-06-01`. Most tools skip text between
+    /// PEMs, but not all of them.
+    #[arg(long, global = true, requires = "pem")]
+    pem_headers: bool,
+
+    /// A line to print between the PEMs of the `--pem` output, so scripts
+    /// can split it, e.g. `---`.
+    #[arg(long, global = true, requires = "pem", value_name = "LINE")]
+    separator: Option<String>,
+
+    /// Write the output to this file instead of stdout. The file is only
+    /// replaced, atomically, when the command succeeds, and is made private
+    /// (0600) when there's a private key in it. Without a format flag the
+    /// format comes from the extension: `.json`, `.ndjson`, `.pem` or text.
+    #[arg(short = 'o', long, global = true, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Write unencrypted private keys without a warning, e.g. from `convert`
+    /// or `parse --pem`, when the output is protected some other way.
+    #[arg(long, global = true)]
+    insecure_output: bool,
+
+    /// Don't show progress (spinners, the file being read) or status on
+    /// stderr. Errors and warnings are still printed.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Deterministic output for snapshot tests and diffs: no relative times or
+    /// timings, sorted collections and no color.
+    #[arg(long, global = true)]
+    stable: bool,
+
+    /// When to color the output. `auto` colors when stdout is a TTY and the
+    /// `NO_COLOR` environment variable isn't set.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    color: ColorChoice,
+
+    /// The color theme. Defaults to `theme.name` from the config file, or
+    /// `dark`.
+    #[arg(long, global = true, value_enum)]
+    theme: Option<ThemeName>,
+
+    /// Print long hex fields (moduli, keys, signatures) in full instead of
+    /// shortening them to fit the terminal.
+    #[arg(long, global = true, visible_alias = "wide")]
+    full: bool,
+
+    /// Show each cert in a few lines: its subject, issuer, validity, key and
+    /// SHA-256 fingerprint. For skimming many certs at once.
+    #[arg(short, long, global = true)]
+    short: bool,
+
+    /// How to show subject and issuer names in text output. The JSON output
+    /// always has every form under `dn`.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    name_format: NameFormat,
+
+    /// Only show the common name of subjects and issuers. Shorthand for
+    /// `--name-format cn`.
+    #[arg(long, global = true, conflicts_with = "name_format")]
+    short_names: bool,
+
+    /// How to show serial numbers in text output. The JSON output has all of
+    /// `serial`, `serial_hex` (colon delimited) and `serial_decimal`.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    serial_format: SerialFormat,
+
+    /// Show internationalized DNS names (`xn--bcher-kva.example`) in SANs
+    /// and subject CNs as Unicode, with the punycode alongside. The JSON
+    /// output keeps the names as they're in the cert.
+    #[arg(long, global = true)]
+    unicode_names: bool,
+
+    /// Annotate the fields of the text output with short plain-language
+    /// notes on what they mean, for people learning PKI.
+    #[arg(long, global = true)]
+    explain: bool,
+
+    /// Don't read or write the cache of network lookups (issuer certs
+    /// fetched via AIA, ...) in `~/.cache/pls`.
+    #[arg(long, global = true, conflicts_with = "refresh")]
+    no_cache: bool,
+
+    /// Fetch everything again rather than using cached lookups, updating the
+    /// cache with the results.
+    #[arg(long, global = true)]
+    refresh: bool,
+
     #[command(subcommand)]
     command: Command, // the default command is `cert`
 }
 
 impl Cli {
     pub fn parse() -> Self {
-        <Self as Parser>::parse()
+        <Self as Parser>::parse_from(infer_command(std::env::args_os().collect()))
     }
 
     pub fn command(&self) -> Command {
@@ -49,24 +184,270 @@ impl Cli {
     }
 
     pub fn format(&self) -> Format {
-        Format::from_args(self.text, self.json, self.pem)
+        let explicit = self.text || self.json || self.json_compact || self.ndjson || self.pem;
+        if let Some(output) = self.output.as_deref().filter(|_| !explicit) {
+            return Format::for_file(output);
+        }
+        Format::from_args(
+            self.text,
+            self.json,
+            self.json_compact,
+            self.ndjson,
+            self.pem,
+        )
+    }
+
+    /// The `--output` file, if the output goes to one.
+    pub fn output(&self) -> Option<&Path> {
+        self.output.as_deref()
+    }
+
+    /// Turn off progress and status on stderr with `--quiet`.
+    pub fn init_progress(&self) {
+        progress::set_quiet(self.quiet);
+    }
+
+    /// Set how network lookups use the on-disk cache, from `--no-cache` and
+    /// `--refresh`.
+    pub fn init_cache(&self) {
+        cache::set_mode(if self.no_cache {
+            cache::Mode::Off
+        } else if self.refresh {
+            cache::Mode::Refresh
+        } else {
+            cache::Mode::Use
+        });
+    }
+
+    pub fn render_options(&self) -> color_eyre::Result<RenderOptions> {
+        let config = Config::load()?;
+        let name = self.theme.or(config.theme.name).unwrap_or_default();
+        let theme = Theme::named(name).with_overrides(&config.theme)?;
+
+        Ok(RenderOptions {
+            stable: self.stable,
+            // a file only gets color when it's asked for
+            color: !self.stable
+                && match self.output {
+                    Some(_) => self.color == ColorChoice::Always,
+                    None => self.color.enabled(),
+                },
+            theme,
+            // a fixed width keeps `--stable` and `--output` the same on every
+            // terminal
+            width: (!self.stable && self.output.is_none())
+                .then(terminal_width)
+                .flatten(),
+            full: self.full,
+            short: self.short,
+            name_format: if self.short_names {
+                NameFormat::Cn
+            } else {
+                self.name_format
+            },
+            serial_format: self.serial_format,
+            unicode_names: self.unicode_names,
+            explain: self.explain,
+            pem_headers: self.pem_headers,
+            pem_separator: self.separator.clone(),
+            insecure_output: self.insecure_output,
+            ..Default::default()
+        })
     }
 }
 
+/// File extensions that make an argument a file that's missing rather than a
+/// host, for [`infer_command`].
+const FILE_EXTENSIONS: &[&str] = &[
+    "pem", "crt", "cer", "der", "key", "csr", "crl", "p7b", "p7c", "p12", "pfx", "jwk", "json",
+    "txt", "log",
+];
+
+/// Put `parse` or `connect` in `args` when there's no command: `pls
+/// cert.pem` parses the file and `pls example.com` connects to the host. A
+/// file wins over a host of the same name. After `--` the argument is never
+/// a command, so `pls -- connect` parses a file named `connect`, and is a
+/// file unless it looks like a host.
+fn infer_command(mut args: Vec<OsString>) -> Vec<OsString> {
+    let cli = <Cli as clap::CommandFactory>::command();
+    let mut i = 1;
+    while let Some(arg) = args.get(i).and_then(|arg| arg.to_str()) {
+        if arg == "--" {
+            let Some(target) = args.get(i + 1).and_then(|arg| arg.to_str()) else {
+                return args;
+            };
+            let command = match !is_file(target) && looks_like_host(target) {
+                true => "connect",
+                false => "parse",
+            };
+            args.insert(i, command.into());
+            return args;
+        }
+        if let Some(flag) = arg.strip_prefix('-').filter(|flag| !flag.is_empty()) {
+            // skip the value of global options like `--output FILE`
+            if takes_value(&cli, flag) {
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+
+        let is_command = arg == "help"
+            || cli.get_subcommands().any(|command| {
+                command.get_name() == arg || command.get_all_aliases().any(|alias| alias == arg)
+            });
+        if !is_command {
+            if arg == "-" || is_file(arg) {
+                args.insert(i, "parse".into());
+            } else if looks_like_host(arg) {
+                args.insert(i, "connect".into());
+            }
+        }
+        return args;
+    }
+    args
+}
+
+/// Whether `flag`, an option without its first `-`, needs the next
+/// argument as its value: `-o FILE` and `--output FILE`, but not
+/// `--output=FILE` or `-oFILE`.
+fn takes_value(cli: &clap::Command, flag: &str) -> bool {
+    let arg = match flag.strip_prefix('-') {
+        Some(long) if !long.contains('=') => {
+            cli.get_arguments().find(|arg| arg.get_long() == Some(long))
+        }
+        Some(_) => None,
+        None => {
+            let mut shorts = flag.chars();
+            let short = shorts.next();
+            cli.get_arguments()
+                .find(|arg| arg.get_short().is_some() && arg.get_short() == short)
+                .filter(|_| shorts.next().is_none())
+        }
+    };
+    arg.is_some_and(|arg| arg.get_action().takes_values())
+}
+
+/// Whether `path` is something to parse: a file, FIFO or device, but not a
+/// directory.
+fn is_file(path: &str) -> bool {
+    let path = Path::new(path);
+    path.exists() && !path.is_dir()
+}
+
+/// Whether `arg` looks like a host `connect` takes, e.g. `example.com`,
+/// `10.0.0.1`, `localhost:8443` or `ldaps://ldap.example.com`, rather than a
+/// file.
+fn looks_like_host(arg: &str) -> bool {
+    if arg.contains("://") {
+        return true;
+    }
+    if arg.contains(['/', '\\']) {
+        return false;
+    }
+    let extension = arg.rsplit_once('.').map(|(_, extension)| extension);
+    if extension.is_some_and(|extension| {
+        FILE_EXTENSIONS
+            .iter()
+            .any(|file| file.eq_ignore_ascii_case(extension))
+    }) {
+        return false;
+    }
+    let has_port = arg
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    match Target::parse(arg, None) {
+        Ok(target) => {
+            target.ip().is_some()
+                || target.host == "localhost"
+                || target.host.contains('.')
+                || has_port
+        }
+        Err(_) => false,
+    }
+}
+
+/// The format of the diagnostics on stderr.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TraceOutput {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// Newline delimited JSON.
+    Json,
+}
+
+/// The width of the terminal stdout is attached to, or `$COLUMNS`.
+fn terminal_width() -> Option<usize> {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return Some(width as usize);
+    }
+
+    std::env::var("COLUMNS").ok()?.parse().ok()
+}
+
 #[derive(Default, Debug, Clone, Subcommand)]
 pub enum Command {
     Parse(Parse),
     Connect(Connect),
+    Jwt(Jwt),
+    Schema(Schema),
+    SameKey(SameKey),
+    Acme(Acme),
+    Csr(Csr),
+    Caa(Caa),
+    Convert(Convert),
+    Random(Random),
+    Mask(Mask),
+    Expiry(Expiry),
+    Doctor(Doctor),
+    Pin(Pin),
+    Trust(Trust),
+    Inspect(Inspect),
+    Capabilities(Capabilities),
+    Find(Find),
+    ExplainExpr(ExplainExpr),
+    Watch(Watch),
+    MatchHostname(MatchHostname),
+    Serve(Serve),
+    Verify(Verify),
+    CheckPair(CheckPair),
+    #[cfg(feature = "k8s")]
+    K8s(crate::commands::k8s::K8s),
     #[default]
     #[clap(skip)]
     NoCommand,
 }
 
 impl Command {
-    pub async fn run(self, format: Format) -> color_eyre::Result<()> {
+    pub async fn run(self, format: Format, options: RenderOptions) -> color_eyre::Result<()> {
         match self {
-            Command::Parse(cert) => cert.run(format).await,
-            Command::Connect(connect) => connect.run(format).await,
+            Command::Parse(cert) => cert.run(format, options).await,
+            Command::Connect(connect) => connect.run(format, options).await,
+            Command::Jwt(jwt) => jwt.run(format, options).await,
+            Command::Schema(schema) => schema.run(format, options).await,
+            Command::SameKey(same_key) => same_key.run(format, options).await,
+            Command::Acme(acme) => acme.run(format, options).await,
+            Command::Csr(csr) => csr.run(format, options).await,
+            Command::Caa(caa) => caa.run(format, options).await,
+            Command::Convert(convert) => convert.run(format, options).await,
+            Command::Random(random) => random.run(format, options).await,
+            Command::Mask(mask) => mask.run(format, options).await,
+            Command::Expiry(expiry) => expiry.run(format, options).await,
+            Command::Doctor(doctor) => doctor.run(format, options).await,
+            Command::Pin(pin) => pin.run(format, options).await,
+            Command::Trust(trust) => trust.run(format, options).await,
+            Command::Inspect(inspect) => inspect.run(format, options).await,
+            Command::Capabilities(capabilities) => capabilities.run(format, options).await,
+            Command::Find(find) => find.run(format, options).await,
+            Command::ExplainExpr(explain) => explain.run(format, options).await,
+            Command::Watch(watch) => watch.run(format, options).await,
+            Command::MatchHostname(match_hostname) => match_hostname.run(format, options).await,
+            Command::Serve(serve) => serve.run(format, options).await,
+            Command::Verify(verify) => verify.run(format, options).await,
+            Command::CheckPair(check_pair) => check_pair.run(format, options).await,
+            #[cfg(feature = "k8s")]
+            Command::K8s(k8s) => k8s.run(format, options).await,
             Command::NoCommand => {
                 let mut clap_command = <Cli as clap::CommandFactory>::command();
                 clap_command.print_long_help()?;
@@ -75,3 +456,71 @@ impl Command {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inferred(args: &[&str]) -> Vec<String> {
+        let args = std::iter::once("pls").chain(args.iter().copied());
+        infer_command(args.map(OsString::from).collect())
+            .into_iter()
+            .skip(1)
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn infers_the_command() {
+        let chain = "test-data/certs/chain.pem";
+        assert_eq!(inferred(&[chain]), ["parse", chain]);
+        assert_eq!(
+            inferred(&["--json", "-o", "out.json", chain]),
+            ["--json", "-o", "out.json", "parse", chain]
+        );
+        assert_eq!(inferred(&["-"]), ["parse", "-"]);
+        assert_eq!(inferred(&["example.com"]), ["connect", "example.com"]);
+        assert_eq!(
+            inferred(&["--output=out.json", "https://example.com", "--chain"]),
+            [
+                "--output=out.json",
+                "connect",
+                "https://example.com",
+                "--chain"
+            ]
+        );
+        assert_eq!(inferred(&["10.0.0.1"]), ["connect", "10.0.0.1"]);
+        assert_eq!(inferred(&["localhost:8443"]), ["connect", "localhost:8443"]);
+
+        // commands and what's neither a file nor a host are left to clap
+        assert_eq!(inferred(&["parse", chain]), ["parse", chain]);
+        assert_eq!(inferred(&["missing.pem"]), ["missing.pem"]);
+        assert_eq!(inferred(&["conect"]), ["conect"]);
+        assert_eq!(inferred(&[]), Vec::<String>::new());
+
+        // `--` makes the next argument a file or host, never a command
+        assert_eq!(inferred(&["--", "connect"]), ["parse", "--", "connect"]);
+        assert_eq!(
+            inferred(&["--", "example.com"]),
+            ["connect", "--", "example.com"]
+        );
+    }
+
+    #[test]
+    fn hosts_and_files() {
+        for host in [
+            "example.com",
+            "[::1]:443",
+            "ldaps://ldap.example.com",
+            "localhost",
+        ] {
+            assert!(looks_like_host(host), "{host}");
+        }
+        for file in ["cert.pem", "./example.com", "bundle.P12", "certs", "a b"] {
+            assert!(!looks_like_host(file), "{file}");
+        }
+        // a directory named like a host is still the host
+        assert!(!is_file("src"));
+        assert!(is_file("Cargo.toml"));
+    }
+}
@@ -0,0 +1,318 @@
+//! An ASN.1 tree of any DER, for `parse --asn1`: like `openssl asn1parse`,
+//! but with OID names, decoded strings and the DER wrapped in OCTET and BIT
+//! STRINGs (extension values, EC signatures, ...) expanded.
+
+use serde::Serialize;
+
+use crate::{
+    der::{self, tag, Tlv},
+    oid,
+};
+
+/// Deeper nesting than this is shown as bytes, so crafted input can't
+/// exhaust the stack.
+const MAX_DEPTH: usize = 64;
+
+/// One element of the tree.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Asn1Node {
+    /// Where the element starts in the DER.
+    pub offset: usize,
+    /// The length of the tag and length bytes.
+    pub header_len: usize,
+    /// The length of the contents.
+    pub len: usize,
+    /// `SEQUENCE`, `INTEGER`, `[0]`, ...
+    pub tag: String,
+    /// The decoded value of a primitive element: a number, string, time,
+    /// named OID or hex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The elements inside a constructed element, or the DER an OCTET or BIT
+    /// STRING wraps.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Asn1Node>,
+    /// Why the contents couldn't be read, after the children that could.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The tree of one DER entity, e.g. one PEM of the input.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Asn1Dump {
+    /// The PEM label, e.g. `CERTIFICATE`, or `DER` for binary input.
+    pub label: String,
+    pub nodes: Vec<Asn1Node>,
+    /// Why the rest of the input couldn't be read, after `nodes`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Asn1Dump {
+    pub fn new(label: String, der: &[u8]) -> Self {
+        let (nodes, error) = parse_nodes(der, 0, 0);
+        Asn1Dump {
+            label,
+            nodes,
+            error,
+        }
+    }
+
+    /// The first thing that couldn't be read, for `--strict`.
+    pub fn first_error(&self) -> Option<&str> {
+        self.flatten()
+            .into_iter()
+            .find_map(|(_, node)| node.error.as_deref())
+            .or(self.error.as_deref())
+    }
+
+    /// Every node with its depth, in the order they appear.
+    pub fn flatten(&self) -> Vec<(usize, &Asn1Node)> {
+        fn walk<'a>(nodes: &'a [Asn1Node], depth: usize, out: &mut Vec<(usize, &'a Asn1Node)>) {
+            for node in nodes {
+                out.push((depth, node));
+                walk(&node.children, depth + 1, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(&self.nodes, 0, &mut out);
+        out
+    }
+}
+
+/// Read the TLVs in `data`, which starts at `offset` in the whole DER,
+/// returning the ones read and why the rest couldn't be.
+fn parse_nodes(data: &[u8], offset: usize, depth: usize) -> (Vec<Asn1Node>, Option<String>) {
+    let mut nodes = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let (tlv, len) = match der::read_tlv(&data[pos..]) {
+            Ok(read) => read,
+            Err(err) => return (nodes, Some(format!("at offset {}: {err}", offset + pos))),
+        };
+        nodes.push(node(tlv, offset + pos, depth));
+        pos += len;
+    }
+    (nodes, None)
+}
+
+fn node(tlv: Tlv, offset: usize, depth: usize) -> Asn1Node {
+    let header_len = tlv.raw.len() - tlv.value.len();
+    let mut node = Asn1Node {
+        offset,
+        header_len,
+        len: tlv.value.len(),
+        tag: tag_name(tlv.tag),
+        ..Default::default()
+    };
+    let contents = offset + header_len;
+
+    if depth >= MAX_DEPTH {
+        node.value = Some(hex::encode(tlv.value));
+        node.error = Some("nested too deep".to_string());
+        return node;
+    }
+
+    // constructed
+    if tlv.tag & 0x20 != 0 {
+        (node.children, node.error) = parse_nodes(tlv.value, contents, depth + 1);
+        return node;
+    }
+
+    // DER wrapped in a string, e.g. extension values and EC signatures
+    let wrapped = match tlv.tag {
+        tag::OCTET_STRING => Some((tlv.value, contents)),
+        tag::BIT_STRING => match tlv.value {
+            [0, rest @ ..] => Some((rest, contents + 1)),
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some((inner, inner_offset)) = wrapped {
+        if looks_like_der(inner) {
+            (node.children, _) = parse_nodes(inner, inner_offset, depth + 1);
+            return node;
+        }
+    }
+
+    node.value = value(tlv);
+    node
+}
+
+/// Whether `data` is a whole number of well-formed TLVs starting with a tag
+/// that's likely to be wrapped, rather than bytes that happen to parse.
+fn looks_like_der(data: &[u8]) -> bool {
+    let likely = matches!(
+        data.first(),
+        Some(
+            &(tag::SEQUENCE
+                | tag::SET
+                | tag::INTEGER
+                | tag::OCTET_STRING
+                | tag::BIT_STRING
+                | tag::OID)
+        )
+    );
+    likely && matches!(parse_nodes(data, 0, MAX_DEPTH - 1), (nodes, None) if !nodes.is_empty())
+}
+
+/// The name of a tag, e.g. `SEQUENCE` or `[0]` for a context-specific one.
+fn tag_name(tag: u8) -> String {
+    let number = tag & 0x1f;
+    match tag & 0xc0 {
+        0x00 => match number {
+            1 => "BOOLEAN",
+            2 => "INTEGER",
+            3 => "BIT STRING",
+            4 => "OCTET STRING",
+            5 => "NULL",
+            6 => "OBJECT IDENTIFIER",
+            10 => "ENUMERATED",
+            12 => "UTF8String",
+            16 => "SEQUENCE",
+            17 => "SET",
+            18 => "NumericString",
+            19 => "PrintableString",
+            20 => "T61String",
+            22 => "IA5String",
+            23 => "UTCTime",
+            24 => "GeneralizedTime",
+            26 => "VisibleString",
+            28 => "UniversalString",
+            30 => "BMPString",
+            number => return format!("UNIVERSAL {number}"),
+        }
+        .to_string(),
+        0x40 => format!("[APPLICATION {number}]"),
+        0x80 => format!("[{number}]"),
+        _ => format!("[PRIVATE {number}]"),
+    }
+}
+
+/// The value of a primitive element, for display.
+fn value(tlv: Tlv) -> Option<String> {
+    let value = tlv.value;
+    Some(match tlv.tag {
+        tag::BOOLEAN => (value.first() != Some(&0)).to_string(),
+        tag::INTEGER | tag::ENUMERATED => integer(value),
+        0x05 => return None,
+        tag::OID => match tlv.oid() {
+            Ok(dotted) => match oid_name(&dotted) {
+                Some(name) => format!("{name} ({dotted})"),
+                None => dotted,
+            },
+            Err(err) => format!("invalid OID: {err}"),
+        },
+        tag::UTC_TIME | tag::GENERALIZED_TIME => String::from_utf8_lossy(value).into_owned(),
+        // e.g. the dNSName [2] and uniformResourceIdentifier [6] of SANs
+        _ if tlv.tag & 0xc0 == 0x80 && is_printable(value) => {
+            String::from_utf8_lossy(value).into_owned()
+        }
+        _ => tlv.string().unwrap_or_else(|| hex::encode(value)),
+    })
+}
+
+/// Small integers in decimal, the rest (serials, moduli) in hex.
+fn integer(value: &[u8]) -> String {
+    if value.is_empty() || value.len() > 8 {
+        return hex::encode(value);
+    }
+    let negative = value[0] & 0x80 != 0;
+    let n = value
+        .iter()
+        .fold(if negative { -1i64 } else { 0 }, |n, &byte| {
+            (n << 8) | byte as i64
+        });
+    n.to_string()
+}
+
+fn is_printable(value: &[u8]) -> bool {
+    !value.is_empty() && value.iter().all(|&byte| (0x20..0x7f).contains(&byte))
+}
+
+/// A name for `oid`: the ones [`oid::name`] knows, attribute types and the
+/// everyday algorithms and curves.
+fn oid_name(oid: &str) -> Option<&'static str> {
+    oid::name(oid)
+        .or_else(|| der::attribute_short_name(oid))
+        .or(Some(match oid {
+            "1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+            "1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+            "1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+            "1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+            "1.2.840.10045.4.3.2" => "ecdsa-with-SHA256",
+            "1.2.840.10045.4.3.3" => "ecdsa-with-SHA384",
+            "1.2.840.10045.4.3.4" => "ecdsa-with-SHA512",
+            "1.2.840.10045.3.1.7" => "prime256v1",
+            "1.3.132.0.34" => "secp384r1",
+            "1.3.132.0.35" => "secp521r1",
+            "1.3.14.3.2.26" => "sha1",
+            "2.16.840.1.101.3.4.2.1" => "sha256",
+            "2.16.840.1.101.3.4.2.2" => "sha384",
+            "2.16.840.1.101.3.4.2.3" => "sha512",
+            "1.2.840.113549.1.7.1" => "pkcs7-data",
+            "1.2.840.113549.1.7.2" => "pkcs7-signedData",
+            "1.2.840.113549.1.9.14" => "extensionRequest",
+            "1.2.840.113549.1.9.7" => "challengePassword",
+            "1.3.6.1.5.5.7.48.1" => "OCSP",
+            "1.3.6.1.5.5.7.48.2" => "caIssuers",
+            "1.3.6.1.4.1.11129.2.4.3" => "CT precertificate poison",
+            "2.5.29.20" => "cRLNumber",
+            "2.5.29.21" => "reasonCode",
+            _ => return None,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cert_tree() {
+        let pem = include_bytes!("../test-data/certs/letsencrypt.org.pem");
+        let der = boring::x509::X509::from_pem(pem).unwrap().to_der().unwrap();
+        let dump = Asn1Dump::new("CERTIFICATE".to_string(), &der);
+        assert!(dump.error.is_none());
+
+        let [cert] = &dump.nodes[..] else {
+            panic!("expected one top-level element, got {}", dump.nodes.len());
+        };
+        assert_eq!((cert.tag.as_str(), cert.offset), ("SEQUENCE", 0));
+        assert_eq!(cert.header_len + cert.len, der.len());
+        let tags: Vec<&str> = cert.children.iter().map(|node| node.tag.as_str()).collect();
+        assert_eq!(tags, ["SEQUENCE", "SEQUENCE", "BIT STRING"]);
+
+        let flat = dump.flatten();
+        assert!(flat
+            .iter()
+            .any(|(_, node)| node.value.as_deref()
+                == Some("ecdsa-with-SHA384 (1.2.840.10045.4.3.3)")));
+        // the subjectAltName value is expanded, its dNSNames shown as text
+        assert!(flat.iter().any(|(depth, node)| *depth > 4
+            && node.tag == "[2]"
+            && node.value.as_deref() == Some("letsencrypt.org")));
+    }
+
+    #[test]
+    fn values_and_errors() {
+        assert_eq!(integer(&[0x01, 0x00]), "256");
+        assert_eq!(integer(&[0xff]), "-1");
+        assert_eq!(integer(&[0x00; 9]), "000000000000000000");
+        assert_eq!(tag_name(0xa3), "[3]");
+        assert_eq!(tag_name(0x61), "[APPLICATION 1]");
+
+        // a SEQUENCE whose second element overruns it
+        let dump = Asn1Dump::new(
+            "DER".to_string(),
+            &[0x30, 0x05, 0x02, 0x01, 0x07, 0x04, 0x09],
+        );
+        let sequence = &dump.nodes[0];
+        assert_eq!(sequence.children[0].value.as_deref(), Some("7"));
+        assert!(sequence
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("at offset 5"));
+    }
+}
@@ -1,30 +1,72 @@
 use std::io::IsTerminal;
 
-use pls_cli::Cli;
+use pls_cli::{commands::Format, print_error, progress_stderr, Cli, Output, TraceOutput};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
 
-#[tokio::main]
-async fn main() -> color_eyre::Result<()> {
-    color_eyre::install()?;
+fn main() -> color_eyre::Result<()> {
     let args = Cli::parse();
 
+    // full reports with backtraces are for debugging pls itself, everyone
+    // else gets a short message. The runtime is started after this, so no
+    // other thread can be reading the environment while it's changed.
+    let debug = args.verbose.tracing_level_filter() >= LevelFilter::INFO;
+    if debug && std::env::var_os("RUST_LIB_BACKTRACE").is_none() {
+        std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    }
+    color_eyre::install()?;
+
     init_tracing(&args)?;
     tracing::debug!("args: {args:?}");
 
     let format = args.format();
-    args.command().run(format).await?;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    match runtime.block_on(run(&args, format)) {
+        Err(err) if !debug => {
+            let options = args.render_options().unwrap_or_default();
+            print_error(&err, format, &options);
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
 
-    Ok(())
+async fn run(args: &Cli, format: Format) -> color_eyre::Result<()> {
+    args.init_cache();
+    args.init_progress();
+    let options = args.render_options()?;
+    let Some(path) = args.output() else {
+        return args.command().run(format, options).await;
+    };
+
+    let output = Output::redirect(path)?;
+    let result = args.command().run(format, options).await;
+    output.finish(result.is_ok())?;
+    result
 }
 
 fn init_tracing(args: &Cli) -> color_eyre::Result<()> {
     let enable_ansi = std::io::stderr().is_terminal();
 
-    let subscriber = tracing_subscriber::fmt()
+    let builder = tracing_subscriber::fmt()
         .with_max_level(args.verbose.tracing_level_filter())
-        .with_ansi(enable_ansi)
-        .with_writer(std::io::stderr)
-        .finish();
+        .with_writer(progress_stderr);
 
-    tracing::subscriber::set_global_default(subscriber)?;
+    match args.trace_output {
+        TraceOutput::Text => {
+            tracing::subscriber::set_global_default(builder.with_ansi(enable_ansi).finish())?
+        }
+        // closing spans are events too, with how long each stage took
+        TraceOutput::Json => tracing::subscriber::set_global_default(
+            builder
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_span_events(FmtSpan::CLOSE)
+                .finish(),
+        )?,
+    }
     Ok(())
 }
@@ -0,0 +1,299 @@
+//! CAA records (RFC 8659): which CAs a domain allows to issue for it.
+
+use std::fmt;
+
+use color_eyre::eyre::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::dns::{RecordData, RecordType, Resolver};
+use crate::x509::SimpleCert;
+
+/// A CAA record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct CaaRecord {
+    /// CAs that don't understand the tag must not issue.
+    pub critical: bool,
+    /// `issue`, `issuewild`, `iodef`, ...
+    pub tag: String,
+    pub value: String,
+}
+
+impl CaaRecord {
+    /// The CA an `issue` or `issuewild` record names, e.g. `letsencrypt.org`
+    /// for `letsencrypt.org; validationmethods=dns-01`. `None` for `;`,
+    /// which forbids issuance.
+    fn issuer(&self) -> Option<&str> {
+        let domain = self.value.split(';').next()?.trim();
+        (!domain.is_empty()).then_some(domain)
+    }
+
+    fn is_known_tag(&self) -> bool {
+        [
+            "issue",
+            "issuewild",
+            "iodef",
+            "issuemail",
+            "contactemail",
+            "contactphone",
+        ]
+        .iter()
+        .any(|tag| self.tag.eq_ignore_ascii_case(tag))
+    }
+}
+
+impl fmt::Display for CaaRecord {
+    /// The zone file form, e.g. `0 issue "letsencrypt.org"`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = if self.critical { 128 } else { 0 };
+        write!(f, "{flags} {} {:?}", self.tag, self.value)
+    }
+}
+
+/// The CAA records that apply to a domain.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct CaaSet {
+    /// The domain asked about, e.g. `www.example.com` or `*.example.com`.
+    pub domain: String,
+    /// Where the records were found, climbing from `domain` towards the root
+    /// as CAs do. `None` when no name on the way has any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found_at: Option<String>,
+    /// The names that were looked up, most specific first.
+    pub checked: Vec<String>,
+    pub resolver: String,
+    /// The resolver validated the records with DNSSEC.
+    pub authenticated: bool,
+    pub records: Vec<CaaRecord>,
+}
+
+/// Which CAs may issue for a domain.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issuers {
+    /// No records restrict issuance.
+    #[default]
+    Any,
+    /// Only these CAs, identified by their CAA domain. None at all when
+    /// empty.
+    Only { cas: Vec<String> },
+    /// A critical record with a tag CAs don't know, which stops every CA.
+    UnknownCritical { tag: String },
+}
+
+impl CaaSet {
+    /// Whether the domain is a wildcard, which `issuewild` records are for.
+    pub fn is_wildcard(&self) -> bool {
+        self.domain.starts_with("*.")
+    }
+
+    /// The CAs allowed to issue for the domain (RFC 8659 section 4).
+    pub fn issuers(&self) -> Issuers {
+        if let Some(record) = self
+            .records
+            .iter()
+            .find(|record| record.critical && !record.is_known_tag())
+        {
+            return Issuers::UnknownCritical {
+                tag: record.tag.clone(),
+            };
+        }
+
+        let tagged = |tag: &str| -> Vec<&CaaRecord> {
+            self.records
+                .iter()
+                .filter(|record| record.tag.eq_ignore_ascii_case(tag))
+                .collect()
+        };
+        // issuewild overrides issue for wildcards, when there is one
+        let mut records = tagged("issue");
+        if self.is_wildcard() && !tagged("issuewild").is_empty() {
+            records = tagged("issuewild");
+        }
+        if records.is_empty() {
+            return Issuers::Any;
+        }
+
+        let mut cas: Vec<String> = Vec::new();
+        for ca in records.iter().filter_map(|record| record.issuer()) {
+            let ca = ca.to_ascii_lowercase();
+            if !cas.contains(&ca) {
+                cas.push(ca);
+            }
+        }
+        Issuers::Only { cas }
+    }
+
+    /// Whether the CA with the CAA domain `ca` may issue for the domain.
+    pub fn authorizes(&self, ca: &str) -> bool {
+        match self.issuers() {
+            Issuers::Any => true,
+            Issuers::Only { cas } => cas.iter().any(|allowed| allowed.eq_ignore_ascii_case(ca)),
+            Issuers::UnknownCritical { .. } => false,
+        }
+    }
+}
+
+/// Look up the CAA records for `domain`, climbing the tree from it to the
+/// first name that has some: `www.example.com`, then `example.com`, then
+/// `com`.
+pub async fn lookup(resolver: &Resolver, domain: &str) -> Result<CaaSet> {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    let mut set = CaaSet {
+        domain: domain.clone(),
+        resolver: resolver.to_string(),
+        ..Default::default()
+    };
+
+    for name in climb(domain.trim_start_matches("*.")) {
+        let answer = resolver.query(name, RecordType::Caa).await?;
+        set.checked.push(name.to_string());
+        let records: Vec<CaaRecord> = answer
+            .records
+            .into_iter()
+            .filter_map(|record| match record.data {
+                RecordData::Caa {
+                    critical,
+                    tag,
+                    value,
+                } => Some(CaaRecord {
+                    critical,
+                    tag,
+                    value,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        if !records.is_empty() {
+            set.found_at = Some(name.to_string());
+            set.authenticated = answer.authenticated;
+            set.records = records;
+            break;
+        }
+    }
+    Ok(set)
+}
+
+/// `domain` and each of its parents, leaving out the root.
+fn climb(domain: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(domain), |name| {
+        name.split_once('.').map(|(_, parent)| parent)
+    })
+    .filter(|name| !name.is_empty())
+}
+
+/// The CAA domain of the CA that issued `cert`, going by its issuer's
+/// organization. Only the bigger public CAs are known.
+pub fn ca_domain(cert: &SimpleCert) -> Option<&'static str> {
+    const CAS: &[(&str, &str)] = &[
+        ("Let's Encrypt", "letsencrypt.org"),
+        ("Google Trust Services", "pki.goog"),
+        ("DigiCert", "digicert.com"),
+        ("Sectigo", "sectigo.com"),
+        ("COMODO", "sectigo.com"),
+        ("ZeroSSL", "sectigo.com"),
+        ("Amazon", "amazon.com"),
+        ("GlobalSign", "globalsign.com"),
+        ("SSL Corporation", "ssl.com"),
+        ("Entrust", "entrust.net"),
+        ("GoDaddy", "godaddy.com"),
+        ("Starfield", "starfieldtech.com"),
+        ("Buypass", "buypass.com"),
+        ("Microsoft", "microsoft.com"),
+        ("Certainly", "certainly.com"),
+    ];
+
+    let organizations = cert.issuer.dn.attributes.get("O")?;
+    CAS.iter()
+        .find(|(name, _)| organizations.iter().any(|o| o.contains(name)))
+        .map(|(_, domain)| *domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(domain: &str, records: &[(&str, &str)]) -> CaaSet {
+        CaaSet {
+            domain: domain.to_string(),
+            records: records
+                .iter()
+                .map(|(tag, value)| CaaRecord {
+                    critical: false,
+                    tag: tag.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn only(cas: &[&str]) -> Issuers {
+        Issuers::Only {
+            cas: cas.iter().map(|ca| ca.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn climbs_to_the_tld() {
+        let names: Vec<_> = climb("www.example.com").collect();
+        assert_eq!(names, ["www.example.com", "example.com", "com"]);
+    }
+
+    #[test]
+    fn issuers() {
+        let records = [
+            ("issue", "letsencrypt.org"),
+            ("issue", "DigiCert.com; cansignhttpexchanges=yes"),
+            ("issuewild", ";"),
+            ("iodef", "mailto:security@example.com"),
+        ];
+        let names = set("example.com", &records);
+        assert_eq!(names.issuers(), only(&["letsencrypt.org", "digicert.com"]));
+        assert!(names.authorizes("digicert.com"));
+        assert!(!names.authorizes("pki.goog"));
+
+        // `issuewild ;` forbids wildcards
+        let wildcards = set("*.example.com", &records);
+        assert_eq!(wildcards.issuers(), only(&[]));
+        assert!(!wildcards.authorizes("letsencrypt.org"));
+
+        // without issuewild, wildcards follow issue
+        assert_eq!(
+            set("*.example.com", &records[..2]).issuers(),
+            only(&["letsencrypt.org", "digicert.com"])
+        );
+        // iodef alone doesn't restrict anything
+        assert_eq!(set("example.com", &records[3..]).issuers(), Issuers::Any);
+        assert_eq!(set("example.com", &[]).issuers(), Issuers::Any);
+    }
+
+    #[test]
+    fn unknown_critical_tags() {
+        let mut records = set(
+            "example.com",
+            &[("issue", "letsencrypt.org"), ("future", "x")],
+        );
+        assert!(records.authorizes("letsencrypt.org"));
+
+        records.records[1].critical = true;
+        assert_eq!(
+            records.issuers(),
+            Issuers::UnknownCritical {
+                tag: "future".to_string()
+            }
+        );
+        assert!(!records.authorizes("letsencrypt.org"));
+        assert_eq!(records.records[1].to_string(), "128 future \"x\"");
+    }
+
+    #[test]
+    fn known_cas() {
+        let cert = SimpleCert::from(
+            boring::x509::X509::from_pem(include_bytes!("../test-data/certs/letsencrypt.org.pem"))
+                .unwrap(),
+        );
+        assert_eq!(ca_domain(&cert), Some("letsencrypt.org"));
+    }
+}
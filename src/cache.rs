@@ -0,0 +1,174 @@
+//! An on-disk cache for network lookups (issuer certs, ...), so repeated runs
+//! in CI don't hit the CA's servers every time.
+//!
+//! Bodies are stored once under `objects/`, named by their SHA-256. Each URL
+//! fetched gets a file under `urls/`, named by the SHA-256 of the URL, that
+//! holds the hash of its body. Its mtime is when it was fetched.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use color_eyre::eyre::{Context, Result};
+use url::Url;
+
+use crate::http;
+
+/// How lookups use the cache, from `--no-cache` and `--refresh`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Use fresh entries, and stale ones when the network fails.
+    #[default]
+    Use,
+    /// Always fetch, but store what was fetched.
+    Refresh,
+    /// Neither read nor write the cache.
+    Off,
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+/// Set how the cache is used for the rest of the process. Only the first call
+/// counts.
+pub fn set_mode(mode: Mode) {
+    let _ = MODE.set(mode);
+}
+
+fn mode() -> Mode {
+    MODE.get().copied().unwrap_or_default()
+}
+
+/// GET `url` through the cache, returning the body. A cached body younger
+/// than `ttl` is used as is; only successful responses are stored.
+pub(crate) async fn get(url: &Url, ttl: Duration) -> Result<Vec<u8>> {
+    let mode = mode();
+    let cache = match mode {
+        Mode::Off => None,
+        _ => Cache::open(),
+    };
+
+    let cached = cache
+        .as_ref()
+        .filter(|_| mode == Mode::Use)
+        .and_then(|cache| cache.lookup(url, ttl));
+    if let Some(entry) = &cached {
+        if entry.fresh {
+            tracing::debug!("{url} is cached");
+            return Ok(entry.body.clone());
+        }
+    }
+
+    let body = match http::get(url).await.and_then(|r| r.error_for_status(url)) {
+        Ok(response) => response.body,
+        Err(err) => match cached {
+            Some(entry) => {
+                tracing::warn!("{err:#}, using the stale cached copy");
+                return Ok(entry.body);
+            }
+            None => return Err(err),
+        },
+    };
+
+    if let Some(cache) = &cache {
+        if let Err(err) = cache.store(url, &body) {
+            tracing::warn!("caching {url}: {err:#}");
+        }
+    }
+    Ok(body)
+}
+
+/// A cached body, and whether it's younger than the TTL it was looked up
+/// with.
+#[derive(Debug)]
+struct Entry {
+    body: Vec<u8>,
+    fresh: bool,
+}
+
+#[derive(Debug)]
+struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// The cache in `$PLS_CACHE_DIR` or `$XDG_CACHE_HOME/pls`
+    /// (`~/.cache/pls`), or `None` without a home directory.
+    fn open() -> Option<Self> {
+        if let Some(dir) = std::env::var_os("PLS_CACHE_DIR") {
+            return Some(Self { dir: dir.into() });
+        }
+
+        let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+        Some(Self {
+            dir: cache_dir.join("pls"),
+        })
+    }
+
+    fn url_path(&self, url: &Url) -> PathBuf {
+        self.dir
+            .join("urls")
+            .join(hex::encode(boring::sha::sha256(url.as_str().as_bytes())))
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.dir.join("objects").join(hash)
+    }
+
+    fn lookup(&self, url: &Url, ttl: Duration) -> Option<Entry> {
+        let index = self.url_path(url);
+        let fetched = fs::metadata(&index).and_then(|meta| meta.modified()).ok()?;
+        let hash = fs::read_to_string(&index).ok()?;
+        let body = fs::read(self.object_path(hash.trim())).ok()?;
+
+        // a clock that went backwards counts as fresh
+        let age = SystemTime::now()
+            .duration_since(fetched)
+            .unwrap_or_default();
+        Some(Entry {
+            body,
+            fresh: age < ttl,
+        })
+    }
+
+    fn store(&self, url: &Url, body: &[u8]) -> Result<()> {
+        let hash = hex::encode(boring::sha::sha256(body));
+        let object = self.object_path(&hash);
+        let index = self.url_path(url);
+        for dir in [object.parent(), index.parent()].into_iter().flatten() {
+            fs::create_dir_all(dir).with_context(|| format!("Creating {}", dir.display()))?;
+        }
+
+        if !object.exists() {
+            fs::write(&object, body).with_context(|| format!("Writing {}", object.display()))?;
+        }
+        fs::write(&index, hash).with_context(|| format!("Writing {}", index.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_by_content() {
+        let dir = std::env::temp_dir().join(format!("pls-cache-{}", std::process::id()));
+        let cache = Cache { dir: dir.clone() };
+        let a = Url::parse("http://a.example/issuer.crt").unwrap();
+        let b = Url::parse("http://b.example/issuer.crt").unwrap();
+
+        assert!(cache.lookup(&a, Duration::MAX).is_none());
+        cache.store(&a, b"issuer").unwrap();
+        cache.store(&b, b"issuer").unwrap();
+
+        let entry = cache.lookup(&a, Duration::MAX).unwrap();
+        assert_eq!((entry.body.as_slice(), entry.fresh), (&b"issuer"[..], true));
+        assert!(!cache.lookup(&b, Duration::ZERO).unwrap().fresh);
+        // both URLs share the one body
+        assert_eq!(fs::read_dir(dir.join("objects")).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
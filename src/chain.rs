@@ -0,0 +1,848 @@
+//! Putting certs in chain order, whatever order they were sent or bundled
+//! in, and filling in what was left out. Every command that works on a chain
+//! orders it with [`order`] first.
+
+use std::time::Duration;
+
+use boring::x509::X509;
+use color_eyre::eyre::{bail, Context, Result};
+use jiff::Timestamp;
+use schemars::JsonSchema;
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    cache, der, verifier,
+    x509::{NotAfter, SimpleCert},
+};
+
+/// What boring reports when the chain stops at a cert whose issuer isn't in
+/// the trust store: the server left an intermediate out.
+const MISSING_ISSUER: &str = "unable to get local issuer certificate";
+
+/// How many issuers are followed up a chain before giving up.
+const MAX_FETCHED: usize = 4;
+
+/// How many certs long a chain is built before giving up, unless
+/// `--max-depth` says otherwise. Real chains are 2 to 4 certs.
+pub const DEFAULT_MAX_DEPTH: usize = 10;
+
+/// How many of the paths through cross-signed certs are followed.
+const MAX_PATHS: usize = 8;
+
+/// How long a fetched issuer is cached for. Intermediates are reissued every
+/// few years at most.
+const ISSUER_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Something off about the chain a server sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ChainIssue {
+    /// The certs weren't sent leaf first, each followed by its issuer.
+    /// `sent` is where each cert of the chain was in what was sent, from 1,
+    /// leaf first.
+    OutOfOrder { sent: Vec<usize> },
+    /// The self-signed root was sent. Clients need to have it already, so
+    /// it's only extra bytes in every handshake.
+    RootIncluded,
+    /// Certs that aren't part of the leaf's chain.
+    Unrelated { count: usize },
+    /// The chain stops short of the trust store. `fetched` is how many
+    /// issuers were downloaded with `--fetch-missing`.
+    MissingIntermediate { fetched: usize },
+    /// A cert has issuers that lead to different roots. Each path is the
+    /// subjects from the leaf up to its root.
+    CrossSigned { paths: Vec<Vec<String>> },
+    /// Following issuers led back to a CA already in the chain. `subjects`
+    /// is the path up to and including it.
+    Loop { subjects: Vec<String> },
+    /// The chain has more certs than `--max-depth`, so building stopped.
+    TooDeep { max_depth: usize },
+}
+
+impl std::fmt::Display for ChainIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfOrder { sent }
+                if sent.len() > 1 && sent.windows(2).all(|pair| pair[0] == pair[1] + 1) =>
+            {
+                write!(
+                    f,
+                    "chain sent in reverse, root first (reordered leaf first)"
+                )
+            }
+            Self::OutOfOrder { sent } => {
+                let sent: Vec<String> = sent.iter().map(|i| format!("#{i}")).collect();
+                write!(
+                    f,
+                    "chain sent out of order (reordered leaf first as {})",
+                    sent.join(", ")
+                )
+            }
+            Self::RootIncluded => write!(f, "root included (unnecessary)"),
+            Self::Unrelated { count: 1 } => write!(f, "1 unrelated cert sent"),
+            Self::Unrelated { count } => write!(f, "{count} unrelated certs sent"),
+            Self::MissingIntermediate { fetched: 0 } => write!(f, "missing intermediate"),
+            Self::MissingIntermediate { .. } => {
+                write!(f, "missing intermediate (fetched via AIA)")
+            }
+            Self::CrossSigned { paths } => {
+                write!(
+                    f,
+                    "cross-signature detected: {} paths to different roots",
+                    paths.len()
+                )?;
+                for (i, path) in paths.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { ";" }, path.join(" → "))?;
+                }
+                Ok(())
+            }
+            Self::Loop { subjects } => {
+                write!(f, "issuer loop detected: {}", subjects.join(" → "))
+            }
+            Self::TooDeep { max_depth } => write!(
+                f,
+                "chain is longer than {max_depth} certs, stopped building (see --max-depth)"
+            ),
+        }
+    }
+}
+
+/// Reorder `certs`, as sent by a server or found in a bundle, leaf first, so
+/// each cert is followed by its issuer. The leaf is the cert that issued none
+/// of the others, so bundles that are root first or shuffled come out the
+/// same. Certs that aren't on the leaf's path go at the end.
+///
+/// A missing intermediate is only reported when the leaf failed verification
+/// because of it, see [`SimpleCert::apply_verify_result`].
+pub fn order(certs: Vec<SimpleCert>) -> (Vec<SimpleCert>, Vec<ChainIssue>) {
+    order_to(certs, DEFAULT_MAX_DEPTH)
+}
+
+/// [`order`], building a chain of at most `max_depth` certs.
+///
+/// When a cert has more than one issuer among `certs`, e.g. a cross-signed
+/// intermediate sent with both its versions, every path is followed and the
+/// first that ends at a root is kept. Paths end at an issuer that's already
+/// on them under the same name and key, so CAs that cross-signed each other
+/// and self-issued intermediates can't loop.
+pub fn order_to(certs: Vec<SimpleCert>, max_depth: usize) -> (Vec<SimpleCert>, Vec<ChainIssue>) {
+    if certs.is_empty() {
+        return (certs, Vec::new());
+    }
+
+    let leaf = leaf(&certs);
+    let mut paths = Vec::new();
+    walk(&certs, &mut vec![leaf], max_depth.max(1), &mut paths);
+    let chosen = paths
+        .iter()
+        .position(|path| path.end == End::Top && certs[path.top()].is_self_signed)
+        .unwrap_or(0);
+    let path = paths[chosen].certs.clone();
+
+    let mut issues = Vec::new();
+    if path.iter().enumerate().any(|(i, &sent)| i != sent) {
+        issues.push(ChainIssue::OutOfOrder {
+            sent: path.iter().map(|i| i + 1).collect(),
+        });
+    }
+    let top = &certs[*path.last().unwrap()];
+    if path.len() > 1 && top.is_self_signed {
+        issues.push(ChainIssue::RootIncluded);
+    }
+
+    let mut roots: Vec<String> = Vec::new();
+    for path in paths.iter().filter(|path| path.end == End::Top) {
+        let root = path.root(&certs);
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    if roots.len() > 1 {
+        issues.push(ChainIssue::CrossSigned {
+            paths: paths
+                .iter()
+                .filter(|path| path.end == End::Top)
+                .map(|path| path.subjects(&certs))
+                .collect(),
+        });
+    }
+    if let Some(looped) = paths.iter().find(|path| matches!(path.end, End::Loop(_))) {
+        issues.push(ChainIssue::Loop {
+            subjects: looped.subjects(&certs),
+        });
+    }
+    match paths[chosen].end {
+        End::TooDeep => issues.push(ChainIssue::TooDeep { max_depth }),
+        // the rest of the chain is cut off rather than unrelated
+        _ => {
+            let unrelated = certs.len() - reachable(&certs, leaf).len();
+            if unrelated > 0 {
+                issues.push(ChainIssue::Unrelated { count: unrelated });
+            }
+        }
+    }
+    if !top.is_self_signed && certs[leaf].validity.verify_result.as_deref() == Some(MISSING_ISSUER)
+    {
+        issues.push(ChainIssue::MissingIntermediate { fetched: 0 });
+    }
+
+    let rest: Vec<usize> = (0..certs.len()).filter(|i| !path.contains(i)).collect();
+    let mut certs: Vec<Option<SimpleCert>> = certs.into_iter().map(Some).collect();
+    let ordered = path
+        .into_iter()
+        .chain(rest)
+        .filter_map(|i| certs[i].take())
+        .collect();
+    (ordered, issues)
+}
+
+/// One way up from the leaf, as indices into the certs being ordered.
+struct Path {
+    certs: Vec<usize>,
+    end: End,
+}
+
+/// Why a [`Path`] stops where it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum End {
+    /// At a self-signed cert, or one whose issuer wasn't given.
+    Top,
+    /// At the maximum depth, with issuers left to follow.
+    TooDeep,
+    /// At a cert whose issuer, the cert at this index, is already on the
+    /// path under the same name and key.
+    Loop(usize),
+}
+
+impl Path {
+    fn top(&self) -> usize {
+        *self.certs.last().unwrap()
+    }
+
+    /// The root the path leads to: its top when that's self-signed,
+    /// otherwise the top's issuer.
+    fn root(&self, certs: &[SimpleCert]) -> String {
+        let top = &certs[self.top()];
+        match top.is_self_signed {
+            true => top.subject.name.clone(),
+            false => top.issuer.name.clone(),
+        }
+    }
+
+    /// The subjects along the path, ending with the root when it wasn't
+    /// given or the cert the path looped back to.
+    fn subjects(&self, certs: &[SimpleCert]) -> Vec<String> {
+        let mut subjects: Vec<String> = self
+            .certs
+            .iter()
+            .map(|&i| certs[i].subject.name.clone())
+            .collect();
+        match self.end {
+            End::Loop(issuer) => subjects.push(certs[issuer].subject.name.clone()),
+            End::Top if !certs[self.top()].is_self_signed => subjects.push(self.root(certs)),
+            _ => {}
+        }
+        subjects
+    }
+}
+
+/// Follow every issuer of the last cert of `path`, adding each path that
+/// ends to `paths`, at most [`MAX_PATHS`] of them.
+fn walk(certs: &[SimpleCert], path: &mut Vec<usize>, max_depth: usize, paths: &mut Vec<Path>) {
+    let current = &certs[*path.last().unwrap()];
+    let issuers: Vec<usize> = match current.is_self_signed {
+        true => Vec::new(),
+        false => (0..certs.len())
+            .filter(|&i| issued_by(current, &certs[i]))
+            .collect(),
+    };
+    if issuers.is_empty() || path.len() >= max_depth {
+        let end = match issuers.is_empty() {
+            true => End::Top,
+            false => End::TooDeep,
+        };
+        paths.push(Path {
+            certs: path.clone(),
+            end,
+        });
+        return;
+    }
+
+    for issuer in issuers {
+        if paths.len() >= MAX_PATHS {
+            return;
+        }
+        // e.g. two CAs that cross-signed each other, or a self-issued
+        // intermediate from a key rollover
+        if path.iter().any(|&i| same_entity(&certs[i], &certs[issuer])) {
+            paths.push(Path {
+                certs: path.clone(),
+                end: End::Loop(issuer),
+            });
+            continue;
+        }
+        path.push(issuer);
+        walk(certs, path, max_depth, paths);
+        path.pop();
+    }
+}
+
+/// The indices of `leaf` and every cert above it, however deep.
+fn reachable(certs: &[SimpleCert], leaf: usize) -> Vec<usize> {
+    let mut reached = vec![leaf];
+    let mut next = 0;
+    while let Some(&current) = reached.get(next) {
+        for i in 0..certs.len() {
+            if !reached.contains(&i) && issued_by(&certs[current], &certs[i]) {
+                reached.push(i);
+            }
+        }
+        next += 1;
+    }
+    reached
+}
+
+/// A cert in the tree of every path from a leaf up to its roots, for
+/// `--paths`. The leaf is the top of the tree and each cert's issuers are
+/// below it, so a cross-signed intermediate branches into one path per root.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PathNode {
+    pub subject: String,
+    pub sha256: String,
+    /// `None` when the cert has no expiry.
+    #[schemars(with = "Option<String>")]
+    pub not_after: Option<Timestamp>,
+    /// A root in the bundled Mozilla trust store, so clients have it
+    /// without it being sent.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub trusted: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub issuers: Vec<PathNode>,
+    /// Why a path ends at this cert, for the last cert of each path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<PathEnd>,
+    /// When the first cert on the path from the leaf to this one expires,
+    /// which is when the whole path stops working. Only for the last cert
+    /// of each path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub path_expires: Option<Timestamp>,
+}
+
+/// Why a path up from the leaf ends where it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PathEnd {
+    /// At a self-signed root.
+    Root,
+    /// At a cert whose issuer wasn't given or in the trust store.
+    IssuerNotFound,
+    /// At a cert issued by a CA already on the path.
+    Loop,
+    /// At `--max-depth`.
+    TooDeep,
+}
+
+/// Every path from the leaf of `certs` up to a root, through `certs` and
+/// the roots in the bundled trust store, as a tree. Clients build one of
+/// these paths, usually to a trusted root, so each path's expiry is when
+/// clients taking it start to fail.
+pub fn paths(certs: &[SimpleCert], max_depth: usize) -> Result<Option<PathNode>> {
+    if certs.is_empty() {
+        return Ok(None);
+    }
+    let leaf = leaf(certs);
+    let trusted = trusted_issuers(certs)?;
+    let all: Vec<SimpleCert> = certs
+        .iter()
+        .cloned()
+        .chain(
+            trusted
+                .iter()
+                .filter(|root| !contains(certs, root))
+                .cloned(),
+        )
+        .collect();
+
+    let mut found = Vec::new();
+    walk(&all, &mut vec![leaf], max_depth.max(1), &mut found);
+
+    let node = |cert: &SimpleCert| PathNode {
+        subject: cert.subject.name.clone(),
+        sha256: cert.fingerprints.sha256.clone(),
+        not_after: cert.validity.not_after.and_then(NotAfter::timestamp),
+        trusted: contains(&trusted, cert),
+        issuers: Vec::new(),
+        end: None,
+        path_expires: None,
+    };
+    let mut tree = node(&all[leaf]);
+    for path in &found {
+        let mut current = &mut tree;
+        for &i in &path.certs[1..] {
+            let cert = &all[i];
+            let pos = match current
+                .issuers
+                .iter()
+                .position(|issuer| issuer.sha256 == cert.fingerprints.sha256)
+            {
+                Some(pos) => pos,
+                None => {
+                    current.issuers.push(node(cert));
+                    current.issuers.len() - 1
+                }
+            };
+            current = &mut current.issuers[pos];
+        }
+        current.end = Some(match path.end {
+            End::Top if all[path.top()].is_self_signed => PathEnd::Root,
+            End::Top => PathEnd::IssuerNotFound,
+            End::TooDeep => PathEnd::TooDeep,
+            End::Loop(_) => PathEnd::Loop,
+        });
+        current.path_expires = path
+            .certs
+            .iter()
+            .filter_map(|&i| all[i].validity.not_after.and_then(NotAfter::timestamp))
+            .min();
+    }
+    Ok(Some(tree))
+}
+
+/// The roots in the bundled trust store that issued any of `certs`.
+fn trusted_issuers(certs: &[SimpleCert]) -> Result<Vec<SimpleCert>> {
+    Ok(verifier::bundled_roots()?
+        .into_iter()
+        // converting every root is slow, so only the issuers are
+        .filter(|root| certs.iter().any(|cert| root.issued(&cert._cert).is_ok()))
+        .map(SimpleCert::from)
+        .collect())
+}
+
+fn contains(certs: &[SimpleCert], cert: &SimpleCert) -> bool {
+    certs
+        .iter()
+        .any(|other| other.fingerprints.sha256 == cert.fingerprints.sha256)
+}
+
+/// Whether two certs are for the same CA: the same subject and key, e.g. a
+/// root and a cross-signed version of it.
+fn same_entity(a: &SimpleCert, b: &SimpleCert) -> bool {
+    a.subject.name == b.subject.name
+        && a.public_key.spki.spki_sha256 == b.public_key.spki.spki_sha256
+}
+
+/// Whether `issuer` issued `cert`: the names match, the authority key
+/// identifier matches the issuer's subject key identifier when both are
+/// there, and the issuer may sign certs. The signature isn't checked.
+pub fn issued_by(cert: &SimpleCert, issuer: &SimpleCert) -> bool {
+    issuer.fingerprints.sha256 != cert.fingerprints.sha256
+        && issuer._cert.issued(&cert._cert).is_ok()
+}
+
+/// The index of the leaf in `certs`: the first that issued none of the
+/// others, preferring one that isn't a CA, e.g. over an unrelated root.
+fn leaf(certs: &[SimpleCert]) -> usize {
+    let issues_none = |cert: &SimpleCert| !certs.iter().any(|other| issued_by(other, cert));
+    let candidates: Vec<usize> = (0..certs.len())
+        .filter(|&i| issues_none(&certs[i]))
+        .collect();
+    candidates
+        .iter()
+        .copied()
+        .find(|&i| !certs[i].is_ca)
+        .or(candidates.first().copied())
+        .unwrap_or(0)
+}
+
+/// How the chain a server serves differs from the one it's expected to, see
+/// [`compare`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Difference {
+    /// The server serves another leaf cert.
+    OtherLeaf,
+    /// Certs of the expected chain the server doesn't send, by subject. The
+    /// root isn't counted, servers don't have to send it.
+    Missing { subjects: Vec<String> },
+    /// Certs the server sends that aren't in the expected chain.
+    Extra { subjects: Vec<String> },
+    /// The same certs, in another order.
+    OtherOrder,
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OtherLeaf => write!(f, "the server serves another leaf cert"),
+            Self::Missing { subjects } => write!(f, "not served: {}", subjects.join(", ")),
+            Self::Extra { subjects } => write!(f, "not expected: {}", subjects.join(", ")),
+            Self::OtherOrder => write!(f, "the certs are served in another order"),
+        }
+    }
+}
+
+/// Compare the certs a server `deployed` against the `expected` ones, e.g.
+/// the `fullchain.pem` it's configured with, by fingerprint. Nothing
+/// differs when the server sends the expected certs in the same order, with
+/// or without the self-signed root.
+pub fn compare(expected: &[SimpleCert], deployed: &[SimpleCert]) -> Vec<Difference> {
+    let has = |certs: &[SimpleCert], cert: &SimpleCert| {
+        certs
+            .iter()
+            .any(|other| other.fingerprints.sha256 == cert.fingerprints.sha256)
+    };
+    let subjects = |certs: Vec<&SimpleCert>| -> Vec<String> {
+        certs
+            .into_iter()
+            .map(|cert| cert.subject.name.clone())
+            .collect()
+    };
+
+    let mut differences = Vec::new();
+    match (expected.first(), deployed.first()) {
+        (Some(expected), Some(deployed))
+            if expected.fingerprints.sha256 == deployed.fingerprints.sha256 => {}
+        _ => differences.push(Difference::OtherLeaf),
+    }
+
+    let missing: Vec<&SimpleCert> = expected
+        .iter()
+        .filter(|cert| !cert.is_self_signed && !has(deployed, cert))
+        .collect();
+    if !missing.is_empty() {
+        differences.push(Difference::Missing {
+            subjects: subjects(missing),
+        });
+    }
+    let extra: Vec<&SimpleCert> = deployed
+        .iter()
+        .filter(|cert| !has(expected, cert))
+        .collect();
+    if !extra.is_empty() {
+        differences.push(Difference::Extra {
+            subjects: subjects(extra),
+        });
+    }
+
+    // the certs both have, in the order each has them
+    let common = |certs: &[SimpleCert], other: &[SimpleCert]| -> Vec<String> {
+        certs
+            .iter()
+            .filter(|cert| has(other, cert))
+            .map(|cert| cert.fingerprints.sha256.clone())
+            .collect()
+    };
+    if common(expected, deployed) != common(deployed, expected) {
+        differences.push(Difference::OtherOrder);
+    }
+    differences
+}
+
+/// Complete an [`order`]ed chain by following the caIssuers URLs up from
+/// its last cert, appending the intermediates found, until the chain is
+/// `max_depth` certs long. Stops at the first self-signed cert, which is left
+/// out as the trust store has to have it.
+///
+/// Returns how many certs were added. Failures are logged rather than
+/// returned: a partial chain is still worth showing.
+pub async fn fetch_missing(certs: &mut Vec<SimpleCert>, max_depth: usize) -> usize {
+    let mut top = 0;
+    while certs
+        .get(top + 1)
+        .is_some_and(|issuer| issued_by(&certs[top], issuer))
+    {
+        top += 1;
+    }
+    let Some(last) = certs.get(top) else {
+        return 0;
+    };
+
+    let limit = MAX_FETCHED.min(max_depth.saturating_sub(top + 1));
+    let fetched = fetch_up(last, &certs[..=top], limit, |_| true).await;
+    let count = fetched.len();
+    certs.splice(top + 1..top + 1, fetched);
+    count
+}
+
+/// Fetch the issuers of `certs` that can't be found among them or in
+/// `known`, following the caIssuers URLs up each chain. As with
+/// [`fetch_missing`], self-signed roots aren't fetched.
+#[tracing::instrument(name = "aia", skip_all, fields(certs = certs.len()))]
+pub async fn fetch_issuers(certs: &[SimpleCert], known: &[SimpleCert]) -> Vec<SimpleCert> {
+    let mut fetched: Vec<SimpleCert> = Vec::new();
+    for cert in certs {
+        let missing = |child: &SimpleCert| {
+            !certs
+                .iter()
+                .chain(known)
+                .chain(&fetched)
+                .any(|issuer| issued_by(child, issuer))
+        };
+        let more = fetch_up(cert, &[], MAX_FETCHED, missing).await;
+        fetched.extend(more);
+    }
+    fetched
+}
+
+/// Follow caIssuers URLs up from `cert` for as long as `missing` says the
+/// issuer of the last cert isn't already at hand, up to `limit` certs.
+/// Failures are logged and end the walk, as does an issuer that's already in
+/// `chain` or was fetched, e.g. from CAs whose caIssuers URLs point at each
+/// other's cross-signs.
+async fn fetch_up(
+    cert: &SimpleCert,
+    chain: &[SimpleCert],
+    limit: usize,
+    missing: impl Fn(&SimpleCert) -> bool,
+) -> Vec<SimpleCert> {
+    let mut fetched: Vec<SimpleCert> = Vec::new();
+    while fetched.len() < limit {
+        let child = fetched.last().unwrap_or(cert);
+        if child.is_self_signed || !missing(child) {
+            break;
+        }
+
+        match fetch_issuer(child).await {
+            Ok(Some(issuer))
+                if std::iter::once(cert)
+                    .chain(chain)
+                    .chain(&fetched)
+                    .any(|seen| same_entity(seen, &issuer)) =>
+            {
+                tracing::warn!(
+                    "issuer loop detected: {} is already in the chain",
+                    issuer.subject.name
+                );
+                break;
+            }
+            Ok(Some(issuer)) if !issuer.is_self_signed => fetched.push(issuer),
+            Ok(_) => break,
+            Err(err) => {
+                tracing::warn!("fetching the issuer of {}: {err:#}", child.subject.name);
+                break;
+            }
+        }
+    }
+    fetched
+}
+
+/// Download the issuer of `cert` from the first of its caIssuers URLs that
+/// serves one.
+pub async fn fetch_issuer(cert: &SimpleCert) -> Result<Option<SimpleCert>> {
+    let urls = der::ca_issuers(&cert._cert.to_der()?).context("parsing authorityInfoAccess")?;
+    if urls.is_empty() {
+        tracing::debug!("{} has no caIssuers URL", cert.subject.name);
+    }
+
+    for url in urls {
+        let parsed = match Url::parse(&url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+            // e.g. `ldap://` URLs, which are still found in some enterprise CAs
+            _ => {
+                tracing::debug!("skipping caIssuers URL {url}");
+                continue;
+            }
+        };
+
+        let body = cache::get(&parsed, ISSUER_TTL).await?;
+        let issuer = parse_issuer(&body).with_context(|| format!("parsing {url}"))?;
+        if issuer.issued(&cert._cert).is_err() {
+            tracing::warn!("{url} didn't serve the issuer of {}", cert.subject.name);
+            continue;
+        }
+
+        let mut issuer = SimpleCert::from(issuer);
+        issuer.fetched_from = Some(url);
+        return Ok(Some(issuer));
+    }
+    Ok(None)
+}
+
+/// caIssuers URLs serve a DER cert, though some serve PEM.
+fn parse_issuer(body: &[u8]) -> Result<X509> {
+    if let Ok(cert) = X509::from_der(body) {
+        return Ok(cert);
+    }
+    if let Ok(cert) = X509::from_pem(body) {
+        return Ok(cert);
+    }
+    bail!("not a DER or PEM certificate (PKCS#7 bundles aren't supported)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::chain;
+
+    fn names(certs: &[SimpleCert]) -> Vec<String> {
+        certs.iter().map(|cert| cert.subject.name.clone()).collect()
+    }
+
+    #[test]
+    fn in_order() {
+        let sent = chain();
+        let (ordered, issues) = order(sent.clone());
+        assert_eq!(names(&ordered), names(&sent));
+        assert_eq!(issues, [ChainIssue::RootIncluded]);
+
+        let (_, issues) = order(sent[..2].to_vec());
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn out_of_order() {
+        let sent = chain();
+        let shuffled = vec![sent[2].clone(), sent[0].clone(), sent[1].clone()];
+        let (ordered, issues) = order(shuffled);
+        assert_eq!(names(&ordered), names(&sent));
+        assert_eq!(
+            issues,
+            [
+                ChainIssue::OutOfOrder {
+                    sent: vec![2, 3, 1]
+                },
+                ChainIssue::RootIncluded
+            ]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "chain sent out of order (reordered leaf first as #2, #3, #1)"
+        );
+
+        let reversed: Vec<SimpleCert> = sent.iter().rev().cloned().collect();
+        let (ordered, issues) = order(reversed);
+        assert_eq!(names(&ordered), names(&sent));
+        assert_eq!(
+            issues[0].to_string(),
+            "chain sent in reverse, root first (reordered leaf first)"
+        );
+    }
+
+    #[test]
+    fn unrelated_and_missing() {
+        let sent = chain();
+        let mut leaf = sent[0].clone();
+        leaf.validity.verify_result = Some(MISSING_ISSUER.to_string());
+        let unrelated = SimpleCert::from(
+            X509::from_pem(include_bytes!("../test-data/certs/rsa-self-signed.pem")).unwrap(),
+        );
+
+        let (ordered, issues) = order(vec![leaf, unrelated]);
+        assert_eq!(ordered.len(), 2);
+        assert_eq!(
+            issues,
+            [
+                ChainIssue::Unrelated { count: 1 },
+                ChainIssue::MissingIntermediate { fetched: 0 }
+            ]
+        );
+    }
+
+    #[test]
+    fn cross_signs_and_loops() {
+        let certs = |pem: &[u8]| -> Vec<SimpleCert> {
+            X509::stack_from_pem(pem)
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect()
+        };
+
+        // the intermediate was signed by both roots, and the path to the
+        // first root given is kept
+        let sent = certs(include_bytes!("../test-data/certs/cross-signed.pem"));
+        let (ordered, issues) = order(sent.clone());
+        assert_eq!(names(&ordered), names(&sent));
+        let [ChainIssue::RootIncluded, cross @ ChainIssue::CrossSigned { paths }] = &issues[..]
+        else {
+            panic!("expected a cross-signature, got {issues:?}");
+        };
+        assert_eq!(paths.len(), 2);
+        assert!(cross
+            .to_string()
+            .starts_with("cross-signature detected: 2 paths to different roots: "));
+
+        // two CAs that signed each other
+        let sent = certs(include_bytes!("../test-data/certs/cross-signed-loop.pem"));
+        let (ordered, issues) = order(sent.clone());
+        assert_eq!(names(&ordered), names(&sent));
+        let [ChainIssue::Loop { subjects }] = &issues[..] else {
+            panic!("expected a loop, got {issues:?}");
+        };
+        assert_eq!(subjects.len(), 4);
+        assert_eq!(subjects[1], subjects[3]);
+
+        let (ordered, issues) = order_to(chain(), 2);
+        assert_eq!(ordered.len(), 3);
+        assert_eq!(issues, [ChainIssue::TooDeep { max_depth: 2 }]);
+    }
+
+    #[test]
+    fn path_tree() {
+        let sent: Vec<SimpleCert> =
+            X509::stack_from_pem(include_bytes!("../test-data/certs/cross-signed.pem"))
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect();
+        let tree = paths(&sent, DEFAULT_MAX_DEPTH).unwrap().unwrap();
+        assert_eq!(tree.subject, sent[0].subject.name);
+        // the intermediate, once under each root
+        assert_eq!(tree.issuers.len(), 2);
+        for intermediate in &tree.issuers {
+            let [root] = &intermediate.issuers[..] else {
+                panic!("expected one root, got {:?}", intermediate.issuers);
+            };
+            assert_eq!(root.end, Some(PathEnd::Root));
+            assert!(!root.trusted);
+            assert!(root.path_expires.is_some());
+        }
+
+        // E6 leads to the bundled ISRG Root X2 rather than stopping
+        let tree = paths(&chain()[..2], DEFAULT_MAX_DEPTH).unwrap().unwrap();
+        let root = &tree.issuers[0].issuers[0];
+        assert!(root.trusted);
+        assert_eq!(root.end, Some(PathEnd::Root));
+    }
+
+    #[tokio::test]
+    async fn complete_chains_fetch_nothing() {
+        // every issuer is at hand, so no caIssuers URL is followed
+        let sent = chain();
+        assert!(fetch_issuers(&sent, &[]).await.is_empty());
+        assert!(fetch_issuers(&sent[..1], &sent[1..]).await.is_empty());
+    }
+
+    #[test]
+    fn compares_deployed_chains() {
+        let expected = chain();
+        assert!(compare(&expected, &expected).is_empty());
+        // the root can be left out
+        assert!(compare(&expected, &expected[..2]).is_empty());
+
+        assert_eq!(
+            compare(&expected, &expected[..1]),
+            [Difference::Missing {
+                subjects: vec![expected[1].subject.name.clone()]
+            }]
+        );
+        assert_eq!(
+            compare(&expected, &[expected[1].clone(), expected[0].clone()]),
+            [Difference::OtherLeaf, Difference::OtherOrder]
+        );
+
+        let other = SimpleCert::from(
+            X509::from_pem(include_bytes!("../test-data/certs/rsa-self-signed.pem")).unwrap(),
+        );
+        let differences = compare(&expected[..2], &[other.clone(), expected[1].clone()]);
+        assert_eq!(differences[0], Difference::OtherLeaf);
+        assert_eq!(
+            differences[2],
+            Difference::Extra {
+                subjects: vec![other.subject.name.clone()]
+            }
+        );
+    }
+}
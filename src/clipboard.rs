@@ -0,0 +1,120 @@
+//! The system clipboard, for `parse --from-clipboard` and `--copy`, through
+//! the tools each platform has for it: `pbcopy` and `pbpaste` on macOS,
+//! `wl-clipboard`, `xclip` or `xsel` on Linux, and `clip.exe` and PowerShell
+//! on Windows and WSL.
+
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+
+/// What `--copy` puts on the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Artifact {
+    /// The SHA-256 fingerprint of each cert, one per line.
+    Fingerprint,
+    /// The certs as PEM. Private keys are never copied.
+    Pem,
+    /// The `--json` output.
+    Json,
+}
+
+impl Artifact {
+    pub fn label(self) -> &'static str {
+        match self {
+            Artifact::Fingerprint => "fingerprint",
+            Artifact::Pem => "PEM",
+            Artifact::Json => "JSON",
+        }
+    }
+}
+
+/// The commands that print the clipboard, in the order they're tried.
+#[cfg(target_os = "macos")]
+const PASTE: &[&[&str]] = &[&["pbpaste"]];
+#[cfg(windows)]
+const PASTE: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const PASTE: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-out"],
+    &["xsel", "--clipboard", "--output"],
+    // WSL
+    &["powershell.exe", "-NoProfile", "-Command", "Get-Clipboard"],
+];
+
+/// The commands that replace the clipboard with their stdin.
+#[cfg(target_os = "macos")]
+const COPY: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(windows)]
+const COPY: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const COPY: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard", "-in"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip.exe"],
+];
+
+/// What's on the clipboard.
+pub fn read() -> Result<Vec<u8>> {
+    for command in PASTE {
+        let output = match Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .output()
+        {
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            output => output.with_context(|| format!("running {}", command[0]))?,
+        };
+        if !output.status.success() {
+            bail!(
+                "{} failed: {}",
+                command[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        return Ok(output.stdout);
+    }
+    Err(no_tool())
+}
+
+/// Put `text` on the clipboard, or `false` when there's no tool to do it
+/// with, see [`no_tool`].
+pub fn write(text: &str) -> Result<bool> {
+    for command in COPY {
+        // xclip and wl-copy stay around to serve the clipboard, so they
+        // mustn't hold on to stdout and keep a pipe open
+        let mut child = match Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            child => child.with_context(|| format!("running {}", command[0]))?,
+        };
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| eyre!("no stdin for {}", command[0]))?
+            .write_all(text.as_bytes())
+            .with_context(|| format!("writing to {}", command[0]))?;
+        let status = child.wait()?;
+        if !status.success() {
+            bail!("{} failed with {status}", command[0]);
+        }
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// The error for a clipboard that can't be used, naming the tools to install.
+pub(crate) fn no_tool() -> color_eyre::Report {
+    let tools: Vec<&str> = COPY.iter().map(|command| command[0]).collect();
+    eyre!(
+        "no clipboard tool found, install one of {}",
+        tools.join(", ")
+    )
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+
+use boring::x509::X509;
+use clap::Parser;
+use color_eyre::eyre::{bail, Context, Result};
+use url::Url;
+
+use crate::{
+    acme::{Authorization, Resource},
+    components::acme::{print_acme, AcmeReport},
+    http,
+    x509::SimpleCert,
+};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Show where an ACME (e.g. Let's Encrypt) order is stuck: its status, the
+/// status of each authorization and challenge with their errors, and the
+/// issued certificate once there is one.
+///
+/// The order's authorizations and certificate are fetched with plain GETs,
+/// which some CAs (Let's Encrypt included) allow. For CAs that insist on
+/// authenticated POST-as-GET requests, save the resources with your ACME
+/// client and pass them in with `--offline`.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct Acme {
+    /// An order or authorization: its URL, or a file with its JSON. Defaults
+    /// to reading the JSON from `stdin`.
+    source: Option<String>,
+
+    /// Don't fetch the authorizations and certificate an order links to.
+    #[arg(long)]
+    offline: bool,
+}
+
+impl CommandExt for Acme {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let resource = match &self.source {
+            Some(source) if source.starts_with("https://") || source.starts_with("http://") => {
+                let url = Url::parse(source).with_context(|| format!("parsing URL {source}"))?;
+                Resource::from_json(&fetch(&url).await?)?
+            }
+            Some(path) => {
+                Resource::from_json(&fs::read(path).with_context(|| format!("Reading {path}"))?)?
+            }
+            None => {
+                if io::stdin().is_terminal() {
+                    bail!("provide an order URL or file, or pipe the JSON into stdin");
+                }
+
+                let mut data = Vec::new();
+                io::stdin()
+                    .read_to_end(&mut data)
+                    .context("Reading stdin")?;
+                Resource::from_json(&data)?
+            }
+        };
+
+        let report = match resource {
+            Resource::Authorization(authorization) => AcmeReport {
+                authorizations: vec![authorization],
+                ..Default::default()
+            },
+            Resource::Order(order) if self.offline => AcmeReport {
+                order: Some(order),
+                ..Default::default()
+            },
+            Resource::Order(order) => {
+                let mut authorizations = Vec::with_capacity(order.authorizations.len());
+                for url in &order.authorizations {
+                    // one unreachable authorization shouldn't hide the rest
+                    match fetch_authorization(url).await {
+                        Ok(authorization) => authorizations.push(authorization),
+                        Err(err) => tracing::warn!("skipping authorization {url}: {err:#}"),
+                    }
+                }
+
+                let certs = match &order.certificate {
+                    Some(url) => fetch_certs(url).await?,
+                    None => Vec::new(),
+                };
+
+                AcmeReport {
+                    order: Some(order),
+                    authorizations,
+                    certs,
+                }
+            }
+        };
+
+        print_acme(report, format, &options)
+    }
+}
+
+async fn fetch(url: &Url) -> Result<Vec<u8>> {
+    Ok(http::get(url).await?.error_for_status(url)?.body)
+}
+
+async fn fetch_authorization(url: &str) -> Result<Authorization> {
+    let url = Url::parse(url).with_context(|| format!("parsing URL {url}"))?;
+    match Resource::from_json(&fetch(&url).await?)? {
+        Resource::Authorization(authorization) => Ok(authorization),
+        Resource::Order(_) => bail!("{url} is an order, not an authorization"),
+    }
+}
+
+/// Fetch an order's `certificate`, an `application/pem-certificate-chain`.
+async fn fetch_certs(url: &str) -> Result<Vec<SimpleCert>> {
+    let url = Url::parse(url).with_context(|| format!("parsing URL {url}"))?;
+    let chain = X509::stack_from_pem(&fetch(&url).await?)
+        .with_context(|| format!("parsing the certificate chain from {url}"))?;
+    Ok(chain.into_iter().map(SimpleCert::from).collect())
+}
@@ -0,0 +1,71 @@
+use clap::Parser;
+use color_eyre::eyre::{Context, Result};
+
+use crate::{
+    caa,
+    components::caa::{print_caa, CaaReport, CurrentIssuer},
+};
+
+use super::{
+    connect::{fetch_leaf, Target},
+    CommandExt, DnsArgs, Format, RenderOptions,
+};
+
+/// Show the CAA records that say which CAs may issue certs for a domain,
+/// climbing the domain tree as CAs do, and whether the CA of the cert the
+/// domain serves now is one of them. Useful when an issuance or renewal is
+/// refused.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct Caa {
+    /// The domain, e.g. `example.com`, or `*.example.com` for a wildcard
+    /// cert.
+    domain: String,
+
+    /// Don't connect to the domain to check the issuer of its current cert.
+    #[arg(long)]
+    no_connect: bool,
+
+    /// The port to get the current cert from.
+    #[arg(long, short, default_value_t = 443)]
+    port: u16,
+
+    #[command(flatten)]
+    dns: DnsArgs,
+}
+
+impl CommandExt for Caa {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let resolver = self.dns.resolver();
+        let set = caa::lookup(&resolver, &self.domain)
+            .await
+            .context("looking up CAA records")?;
+
+        let cert = if self.no_connect || set.is_wildcard() {
+            None
+        } else {
+            let target = Target::parse(&set.domain, Some(self.port))?;
+            // the records are still worth showing without the cert
+            match fetch_leaf(&target, &resolver).await {
+                Ok(cert) => {
+                    let ca = caa::ca_domain(&cert);
+                    Some(CurrentIssuer {
+                        name: cert.issuer.name.clone(),
+                        ca: ca.map(str::to_string),
+                        authorized: ca.map(|ca| set.authorizes(ca)),
+                    })
+                }
+                Err(err) => {
+                    tracing::warn!("getting the current cert of {target}: {err:#}");
+                    None
+                }
+            }
+        };
+
+        let report = CaaReport {
+            issuers: set.issuers(),
+            caa: set,
+            cert,
+        };
+        print_caa(report, format, &options)
+    }
+}
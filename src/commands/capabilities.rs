@@ -0,0 +1,128 @@
+use boring::ssl::{SslContextBuilder, SslMethod, SslVersion};
+use clap::Parser;
+use color_eyre::eyre::Result;
+
+use crate::{
+    components::capabilities::{print_capabilities, CapabilitiesReport, Curve, Feature},
+    connection::version_name,
+};
+
+use super::{
+    connect::{curves, DEFAULT_CURVES, TLS13_CIPHERS},
+    CommandExt, Format, RenderOptions,
+};
+
+/// List what the TLS library `pls` is built with supports: the protocol
+/// versions, the curves (including the post-quantum hybrids) for `connect
+/// --curves`, the cipher suites and the optional features, like raw public
+/// keys and QUIC.
+#[derive(Clone, Debug, Parser)]
+pub struct Capabilities {}
+
+impl CommandExt for Capabilities {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        print_capabilities(capabilities()?, format, &options)
+    }
+}
+
+const VERSIONS: [SslVersion; 5] = [
+    SslVersion::SSL3,
+    SslVersion::TLS1,
+    SslVersion::TLS1_1,
+    SslVersion::TLS1_2,
+    SslVersion::TLS1_3,
+];
+
+/// Ask the linked library what it supports, by configuring a client
+/// context with each version, curve and cipher.
+fn capabilities() -> Result<CapabilitiesReport> {
+    let builder = || SslContextBuilder::new(SslMethod::tls_client());
+
+    let mut versions = Vec::new();
+    for version in VERSIONS {
+        let mut builder = builder()?;
+        if builder.set_min_proto_version(Some(version)).is_ok()
+            && builder.set_max_proto_version(Some(version)).is_ok()
+        {
+            versions.push(version_name(version));
+        }
+    }
+
+    let defaults: Vec<&str> = DEFAULT_CURVES.split(':').collect();
+    let mut curves = Vec::new();
+    // only the ones this build accepts are listed
+    for (name, _) in curves::CURVES {
+        if curves::supported(name) {
+            curves.push(Curve {
+                name,
+                post_quantum: name.contains("MLKEM") || name.contains("Kyber"),
+                default: defaults.contains(&name),
+            });
+        }
+    }
+
+    let mut tls12 = builder()?;
+    tls12.set_cipher_list("ALL")?;
+    let tls12_ciphers = tls12
+        .ciphers()
+        .into_iter()
+        .flatten()
+        .map(|cipher| cipher.name().to_string())
+        .collect();
+
+    let features = vec![
+        Feature {
+            name: "post-quantum key exchange",
+            enabled: curves.iter().any(|curve| curve.post_quantum),
+            detail: "connect --pqc",
+        },
+        // both are compiled in unconditionally, see Cargo.toml
+        Feature {
+            name: "raw public keys (RFC 7250)",
+            enabled: true,
+            detail: "connect --rpk",
+        },
+        Feature {
+            name: "QUIC",
+            enabled: true,
+            detail: "connect --quic",
+        },
+    ];
+
+    Ok(CapabilitiesReport {
+        versions,
+        curves,
+        tls13_ciphers: TLS13_CIPHERS.to_vec(),
+        tls12_ciphers,
+        features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_what_connect_uses() {
+        let report = capabilities().unwrap();
+        assert!(report.versions.contains(&"TLSv1.3"));
+        assert!(!report.versions.contains(&"SSLv3"));
+
+        // every default curve is supported
+        let supported: Vec<&str> = report.curves.iter().map(|curve| curve.name).collect();
+        for curve in DEFAULT_CURVES.split(':') {
+            assert!(supported.contains(&curve), "{curve} isn't supported");
+        }
+        let mlkem = report
+            .curves
+            .iter()
+            .find(|curve| curve.name == "X25519MLKEM768")
+            .unwrap();
+        assert!(mlkem.post_quantum && mlkem.default);
+
+        assert!(report
+            .tls12_ciphers
+            .iter()
+            .any(|cipher| cipher.starts_with("ECDHE-")));
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs;
+use std::path::PathBuf;
+
+use boring::x509::store::X509Store;
+use clap::Parser;
+use color_eyre::eyre::{bail, Context, Result};
+use jiff::Timestamp;
+
+use crate::{
+    chain::{self, ChainIssue},
+    components::{
+        doctor::{print_doctor, Check, DoctorReport},
+        expiry::ExpiryEntry,
+        same_key::KeyUserKind,
+        verify::VerifyStatus,
+    },
+    secret::Zeroizing,
+    verifier,
+    x509::SimpleCert,
+};
+
+use super::{
+    doctor::expiration_check, expiry::cert_file, same_key::key_users, CommandExt, Format,
+    RenderOptions,
+};
+
+/// Check a cert and its key before deploying them to a web server: that the
+/// key is the leaf's, the chain is in order and complete, nothing has
+/// expired and the chain verifies to the trust store. Prints a pass or fail
+/// for each.
+///
+/// Fails when any check fails.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct CheckPair {
+    /// The certs the server will send, leaf first, e.g. `fullchain.pem`
+    /// (PEM, DER or PKCS#7).
+    pub certs: PathBuf,
+
+    /// The private key of the leaf, e.g. `privkey.pem`.
+    pub key: PathBuf,
+
+    /// A PEM bundle of the roots the chain has to verify to, e.g. a private
+    /// CA's. Defaults to the bundled Mozilla roots.
+    #[arg(long, value_name = "FILE")]
+    pub trust_store: Option<PathBuf>,
+
+    /// Also check the leaf covers this hostname.
+    #[arg(long)]
+    pub host: Option<String>,
+}
+
+impl CommandExt for CheckPair {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let certs =
+            cert_file(&self.certs).with_context(|| format!("Reading {}", self.certs.display()))?;
+        let key = Zeroizing::new(
+            fs::read(&self.key).with_context(|| format!("Reading {}", self.key.display()))?,
+        );
+        let (store, trusted_by) = match &self.trust_store {
+            Some(path) => {
+                let roots = cert_file(path)
+                    .with_context(|| format!("Reading {}", path.display()))?
+                    .into_iter()
+                    .map(|root| root._cert);
+                (verifier::store_of(roots)?, path.display().to_string())
+            }
+            None => (
+                verifier::store_of(verifier::bundled_roots()?)?,
+                "the Mozilla roots".to_string(),
+            ),
+        };
+
+        let checks = check_pair(
+            certs,
+            &self.key.display().to_string(),
+            &key,
+            (&store, &trusted_by),
+            self.host.as_deref(),
+            options.now.timestamp(),
+        );
+        let report = DoctorReport {
+            heading: "check-pair",
+            target: self.certs.display().to_string(),
+            checks,
+        };
+
+        let failed = report.failed();
+        print_doctor(report, format, &options)?;
+        if failed > 0 {
+            bail!("{failed} of the checks failed");
+        }
+        Ok(())
+    }
+}
+
+/// How many of the ordered `certs` are the leaf's chain, as the server sends
+/// it once it's in order: each cert issued by the next, up to a root. Other
+/// paths of a cross-signed chain and unrelated certs are after it.
+fn chain_len(certs: &[SimpleCert]) -> usize {
+    1 + certs
+        .windows(2)
+        .take_while(|pair| !pair[0].is_self_signed && chain::issued_by(&pair[0], &pair[1]))
+        .count()
+}
+
+/// Check `certs` and the key in `key_data`, read from `key_file`, as a pair
+/// for a web server. `trust` is the store to verify with and what to call it.
+fn check_pair(
+    certs: Vec<SimpleCert>,
+    key_file: &str,
+    key_data: &[u8],
+    trust: (&X509Store, &str),
+    host: Option<&str>,
+    now: Timestamp,
+) -> Vec<Check> {
+    // `certs` has at least one cert, see [`cert_file`]
+    let (certs, issues) = chain::order(certs);
+    let leaf = &certs[0];
+    let mut checks = vec![
+        key_check(leaf, key_file, key_data),
+        order_check(leaf, &issues),
+    ];
+
+    let (store, trusted_by) = trust;
+    let chain_len = chain_len(&certs);
+    checks.push(
+        match verifier::verify_result(store, None, &certs[..chain_len]) {
+            Ok(Ok(())) => Check::pass(
+                "chain",
+                format!("{chain_len} certs, trusted by {trusted_by}"),
+            ),
+            Ok(result) => {
+                let status = VerifyStatus::of(&result);
+                let error = result.err().map(|err| err.to_string()).unwrap_or_default();
+                let hint = match status {
+                    VerifyStatus::UnknownIssuer => format!(
+                        "add the intermediates between the leaf and {trusted_by}, e.g. use fullchain.pem rather than cert.pem"
+                    ),
+                    VerifyStatus::Expired | VerifyStatus::NotYetValid => {
+                        "renew the expired cert, or replace it with the CA's current one".to_string()
+                    }
+                    _ => "see the chain with `pls parse --paths`".to_string(),
+                };
+                Check::fail("chain", error, hint)
+            }
+            Err(err) => Check::skip("chain", format!("{err:#}")),
+        },
+    );
+
+    checks.push(expiration_check(&ExpiryEntry::new(
+        &leaf.subject.name,
+        leaf,
+        now,
+    )));
+
+    if let Some(host) = host {
+        checks.push(if leaf.matches_host(host) {
+            Check::pass("hostname", format!("the cert covers {host}"))
+        } else {
+            Check::fail(
+                "hostname",
+                format!("the cert is for {}", leaf.subject.sans.dns.join(", ")),
+                format!("get a cert with {host} in its SANs"),
+            )
+        });
+    }
+    checks
+}
+
+/// Whether the private key in `key_data` is the key of `leaf`.
+fn key_check(leaf: &SimpleCert, key_file: &str, key_data: &[u8]) -> Check {
+    let keys = match key_users(key_file, key_data) {
+        Ok(users) => users,
+        Err(err) => {
+            return Check::fail(
+                "key",
+                format!("{err:#}"),
+                "give the unencrypted PEM private key",
+            )
+        }
+    };
+    let Some((spki, _)) = keys
+        .iter()
+        .find(|(_, user)| user.kind == KeyUserKind::PrivateKey)
+    else {
+        return Check::fail(
+            "key",
+            format!("no private key in {key_file}"),
+            "give the unencrypted PEM private key, e.g. privkey.pem",
+        );
+    };
+
+    if *spki == leaf.public_key.spki {
+        Check::pass(
+            "key",
+            format!("{key_file} is the key of {}", leaf.subject.name),
+        )
+    } else {
+        Check::fail(
+            "key",
+            format!("{key_file} isn't the key of {}", leaf.subject.name),
+            "find the key the cert was issued for, `pls same-key` groups files by key",
+        )
+    }
+}
+
+/// Whether the certs are sent leaf first, each followed by its issuer, with
+/// nothing extra.
+fn order_check(leaf: &SimpleCert, issues: &[ChainIssue]) -> Check {
+    let mut warnings = Vec::new();
+    for issue in issues {
+        match issue {
+            ChainIssue::OutOfOrder { .. }
+            | ChainIssue::Loop { .. }
+            | ChainIssue::TooDeep { .. } => return Check::fail(
+                "order",
+                issue.to_string(),
+                "put the leaf first, then each cert's issuer, as servers send the file as it is",
+            ),
+            ChainIssue::RootIncluded | ChainIssue::Unrelated { .. } => {
+                warnings.push(issue.to_string())
+            }
+            ChainIssue::CrossSigned { .. } | ChainIssue::MissingIntermediate { .. } => {}
+        }
+    }
+    match warnings.is_empty() {
+        true => Check::pass(
+            "order",
+            format!("leaf first ({}), then its issuers", leaf.subject.name),
+        ),
+        false => Check::warn(
+            "order",
+            warnings.join(", "),
+            "leave out the root and certs of other chains, clients don't use them",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::doctor::Status;
+
+    use super::*;
+
+    const CA: &[u8] = include_bytes!("../../test-data/certs.d/registry.example/ca.crt");
+    const CLIENT: &[u8] = include_bytes!("../../test-data/certs.d/registry.example/client.cert");
+    const CLIENT_KEY: &[u8] = include_bytes!("../../test-data/certs.d/registry.example/client.key");
+
+    fn certs(pems: &[&[u8]]) -> Vec<SimpleCert> {
+        pems.iter()
+            .flat_map(|pem| boring::x509::X509::stack_from_pem(pem).unwrap())
+            .map(SimpleCert::from)
+            .collect()
+    }
+
+    fn statuses(checks: &[Check]) -> Vec<(&'static str, Status)> {
+        checks
+            .iter()
+            .map(|check| (check.name, check.status))
+            .collect()
+    }
+
+    #[test]
+    fn checks_the_pair() {
+        let store = verifier::store_of(certs(&[CA]).into_iter().map(|ca| ca._cert)).unwrap();
+        let trust = (&store, "ca.crt");
+        let now = Timestamp::now();
+
+        let checks = check_pair(certs(&[CLIENT]), "client.key", CLIENT_KEY, trust, None, now);
+        assert_eq!(
+            statuses(&checks),
+            [
+                ("key", Status::Pass),
+                ("order", Status::Pass),
+                ("chain", Status::Pass),
+                ("expiration", Status::Pass),
+            ]
+        );
+
+        // root first, with another key and host
+        let ed25519 = include_bytes!("../../test-data/private-keys/ed25519.pem");
+        let checks = check_pair(
+            certs(&[CA, CLIENT]),
+            "ed25519.pem",
+            ed25519,
+            trust,
+            Some("registry.example"),
+            now,
+        );
+        assert_eq!(
+            statuses(&checks),
+            [
+                ("key", Status::Fail),
+                ("order", Status::Fail),
+                ("chain", Status::Pass),
+                ("expiration", Status::Pass),
+                ("hostname", Status::Fail),
+            ]
+        );
+
+        // without its CA, the chain doesn't verify
+        let bundled = verifier::store_of(verifier::bundled_roots().unwrap()).unwrap();
+        let checks = check_pair(
+            certs(&[CLIENT]),
+            "client.key",
+            b"not a key",
+            (&bundled, "the Mozilla roots"),
+            None,
+            now,
+        );
+        assert_eq!(checks[0].status, Status::Fail);
+        assert_eq!(checks[2].status, Status::Fail);
+        assert!(checks[2].hint.as_ref().unwrap().contains("intermediates"));
+    }
+
+    #[test]
+    fn cross_signed_chain_len() {
+        // leaf, the intermediate under root A, root A, then the intermediate
+        // under root B and root B
+        let pem: &[u8] = include_bytes!("../../test-data/certs/cross-signed.pem");
+        let (ordered, _) = chain::order(certs(&[pem]));
+        assert_eq!(ordered.len(), 5);
+        assert_eq!(chain_len(&ordered), 3);
+        assert_eq!(chain_len(&ordered[..1]), 1);
+    }
+}
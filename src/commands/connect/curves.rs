@@ -0,0 +1,143 @@
+//! The names `--curves` takes. BoringSSL only knows a group by one name, e.g.
+//! `P-256`, so the other names people know it by are mapped to that.
+
+use boring::ssl::{SslContextBuilder, SslMethod};
+
+/// Every group BoringSSL has had, newest first, with the other names it goes
+/// by. Names are compared without case, `-` and `_`, so `p256` and `P_256`
+/// need no alias.
+pub(crate) const CURVES: [(&str, &[&str]); 9] = [
+    ("X25519MLKEM768", &["mlkem768", "mlkem"]),
+    ("MLKEM1024", &[]),
+    (
+        "X25519Kyber768Draft00",
+        &["x25519kyber768", "kyber768", "kyber"],
+    ),
+    ("P256Kyber768Draft00", &["p256kyber768"]),
+    ("X25519", &["curve25519"]),
+    ("P-256", &["prime256v1", "secp256r1"]),
+    ("P-384", &["secp384r1"]),
+    ("P-521", &["secp521r1"]),
+    ("P-224", &["secp224r1"]),
+];
+
+/// Whether the linked BoringSSL accepts the curve named `name`.
+pub(crate) fn supported(name: &str) -> bool {
+    SslContextBuilder::new(SslMethod::tls_client())
+        .is_ok_and(|mut builder| builder.set_curves_list(name).is_ok())
+}
+
+/// Parse a `--curves` list: curve names or aliases separated by `:`, `,` or
+/// spaces. Returns the `:` separated list BoringSSL takes, or an error naming
+/// the curve that's unknown or unsupported.
+pub(crate) fn parse_curves(value: &str) -> Result<String, String> {
+    let mut curves: Vec<&str> = Vec::new();
+    for name in value
+        .split([':', ',', ' ', '\t'])
+        .filter(|name| !name.is_empty())
+    {
+        let Some(curve) = canonical(name) else {
+            return Err(match suggest(name) {
+                Some(suggestion) => {
+                    format!("unknown curve {name:?}, did you mean {suggestion}?")
+                }
+                None => format!("unknown curve {name:?}, `pls capabilities` lists them"),
+            });
+        };
+        if !supported(curve) {
+            return Err(format!("{curve} isn't supported by this build of pls"));
+        }
+        if !curves.contains(&curve) {
+            curves.push(curve);
+        }
+    }
+
+    if curves.is_empty() {
+        return Err("no curves given".to_string());
+    }
+    Ok(curves.join(":"))
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_'))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// The BoringSSL name of the curve called `name`.
+fn canonical(name: &str) -> Option<&'static str> {
+    let name = normalize(name);
+    CURVES
+        .iter()
+        .find(|(curve, aliases)| {
+            normalize(curve) == name || aliases.iter().any(|alias| normalize(alias) == name)
+        })
+        .map(|(curve, _)| *curve)
+}
+
+/// The curve `name` is probably a typo of, if any is close enough.
+fn suggest(name: &str) -> Option<&'static str> {
+    let name = normalize(name);
+    CURVES
+        .iter()
+        .flat_map(|(curve, aliases)| {
+            std::iter::once(*curve)
+                .chain(aliases.iter().copied())
+                .map(move |alias| (distance(&name, &normalize(alias)), *curve))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .filter(|(distance, _)| *distance <= 2.max(name.len() / 3))
+        .map(|(_, curve)| curve)
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous + usize::from(a != *b);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn friendly_names() {
+        assert_eq!(parse_curves("X25519:P-256").unwrap(), "X25519:P-256");
+        assert_eq!(parse_curves("x25519, p256").unwrap(), "X25519:P-256");
+        assert_eq!(
+            parse_curves("mlkem768 secp384r1 P_521").unwrap(),
+            "X25519MLKEM768:P-384:P-521"
+        );
+        // listed twice
+        assert_eq!(parse_curves("p256,P-256").unwrap(), "P-256");
+        assert!(parse_curves(" , ").is_err());
+    }
+
+    #[test]
+    fn suggests_close_names() {
+        assert_eq!(
+            parse_curves("X25519MLKEM786").unwrap_err(),
+            "unknown curve \"X25519MLKEM786\", did you mean X25519MLKEM768?"
+        );
+        assert_eq!(
+            parse_curves("x25519:p265").unwrap_err(),
+            "unknown curve \"p265\", did you mean P-256?"
+        );
+        assert_eq!(
+            parse_curves("brainpoolP256r1").unwrap_err(),
+            "unknown curve \"brainpoolP256r1\", `pls capabilities` lists them"
+        );
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+}
@@ -1,15 +1,35 @@
-use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use boring::ssl::SslContextBuilder;
 use clap::Parser;
-use color_eyre::eyre::{eyre, Context};
-use url::Url;
+use color_eyre::eyre::{bail, Context};
+use tracing::Instrument;
 
-use super::{CommandExt, Format};
+use crate::chain::{self, ChainIssue};
+use crate::components::connection::ConnectionWithCerts;
+use crate::connection::{version_name, Offered, Transport};
+use crate::dane;
+use crate::registry;
+use crate::revocation::{self, RevocationMethod};
+use crate::state::{Snapshot, State};
+use crate::verifier::{self, Verifier};
+use crate::x509::SimpleCert;
 
+use super::{CommandExt, DnsArgs, Format, RenderOptions};
+
+pub(crate) mod curves;
+mod proxy;
 mod quic;
+mod target;
 mod tcp;
 
+pub use proxy::Proxy;
+pub(crate) use target::Target;
+pub(crate) use tcp::{fetch_chain, fetch_leaf};
+
 pub(crate) const DEFAULT_CURVES: &str =
     "X25519MLKEM768:X25519Kyber768Draft00:P256Kyber768Draft00:X25519:P-256:P-384:P-521";
 
@@ -27,17 +47,146 @@ pub(crate) fn set_curves(
         .with_context(|| format!("Setting curve list to: {curves:?}"))
 }
 
+/// A `--keylog` file, shared between the connection and its key log callback.
+pub(crate) type KeyLog = Arc<Mutex<File>>;
+
+/// Open `path` for appending session secrets to. Done before connecting so a
+/// bad path fails the command instead of silently logging nothing.
+pub(crate) fn open_keylog(path: &Path) -> color_eyre::Result<KeyLog> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Opening key log file {}", path.display()))?;
+    Ok(Arc::new(Mutex::new(file)))
+}
+
+/// Append the session secrets of every connection made with `builder` to
+/// `keylog` in the NSS key log format (the same one `SSLKEYLOGFILE` produces),
+/// so captures of the connection can be decrypted in Wireshark.
+pub(crate) fn set_keylog(builder: &mut SslContextBuilder, keylog: KeyLog) {
+    builder.set_keylog_callback(move |_, line| {
+        let mut file = keylog.lock().unwrap();
+        if let Err(err) = writeln!(file, "{line}") {
+            tracing::warn!("failed to write to key log file: {err}");
+        }
+    });
+}
+
+/// Encode `protocols` in the wire format expected by
+/// [`SslContextBuilder::set_alpn_protos`]: each protocol prefixed by its length.
+pub(crate) fn alpn_wire_format(protocols: &[String]) -> color_eyre::Result<Vec<u8>> {
+    let mut wire = Vec::new();
+    for protocol in protocols {
+        let Ok(len @ 1..) = u8::try_from(protocol.len()) else {
+            bail!("ALPN protocol names must be 1 to 255 bytes long, got {protocol:?}");
+        };
+        wire.push(len);
+        wire.extend_from_slice(protocol.as_bytes());
+    }
+    Ok(wire)
+}
+
+/// The TLS 1.3 cipher suites. BoringSSL doesn't make them configurable, so
+/// they are missing from the context's cipher list but always offered.
+pub(crate) const TLS13_CIPHERS: [&str; 3] = [
+    "TLS_AES_128_GCM_SHA256",
+    "TLS_AES_256_GCM_SHA384",
+    "TLS_CHACHA20_POLY1305_SHA256",
+];
+
+/// Describe what the ClientHello built from `builder` offers, for the
+/// `--transcript` output.
+pub(crate) fn offered(builder: &mut SslContextBuilder, curves: &str, alpn: &[String]) -> Offered {
+    let min = builder.min_proto_version();
+    let max = builder.max_proto_version();
+    let versions = ["TLSv1", "TLSv1.1", "TLSv1.2", "TLSv1.3"];
+    let start = min
+        .map(version_name)
+        .and_then(|v| versions.iter().position(|&x| x == v));
+    let end = max
+        .map(version_name)
+        .and_then(|v| versions.iter().position(|&x| x == v));
+
+    let versions: Vec<String> = versions[start.unwrap_or(0)..=end.unwrap_or(versions.len() - 1)]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+
+    let mut ciphers = Vec::new();
+    if versions.iter().any(|v| v == "TLSv1.3") {
+        ciphers.extend(TLS13_CIPHERS.map(str::to_string));
+    }
+    if versions.iter().any(|v| v != "TLSv1.3") {
+        ciphers.extend(
+            builder
+                .ciphers()
+                .into_iter()
+                .flatten()
+                .map(|cipher| cipher.name().to_string()),
+        );
+    }
+
+    Offered {
+        versions,
+        ciphers,
+        curves: curves.split(':').map(str::to_string).collect(),
+        alpn: alpn.to_vec(),
+    }
+}
+
+/// The request `connect --http` sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HttpMethod {
+    Get,
+    Head,
+}
+
+impl HttpMethod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+        }
+    }
+}
+
 /// Connect to the given host and print information about the TLS connection.
 /// Supports both TCP/TLS and QUIC.
 #[derive(Default, Clone, Debug, Parser)]
 pub struct Connect {
-    /// The host to connect to. Can be a hostname, IP address or URL.
+    /// The host to connect to. Can be a hostname or IP address, optionally
+    /// followed by `:port`, or a URL like `https://example.com` or
+    /// `ldaps://ldap.example.com`.
     host: String,
 
-    /// Outputs the certificate chain.
+    /// The port to connect to. Defaults to the port in the host, the default
+    /// port of its URL scheme (`smtps` 465, `ldaps` 636, `imaps` 993, ...),
+    /// or 443.
+    #[arg(long, short)]
+    port: Option<u16>,
+
+    /// Outputs the certificate chain, in chain order. Chains sent out of
+    /// order, with the root or without an intermediate are flagged.
     #[arg(long)]
     chain: bool,
 
+    /// With `--chain`, download intermediates the server left out from the
+    /// caIssuers URLs (authorityInfoAccess) in its certs.
+    #[arg(long, requires = "chain")]
+    fetch_missing: bool,
+
+    /// The most certs a chain is built to, counting the leaf, before giving
+    /// up, e.g. on a loop of cross-signed intermediates.
+    #[arg(long, default_value_t = chain::DEFAULT_MAX_DEPTH, value_name = "CERTS")]
+    max_depth: usize,
+
+    /// With `--chain`, also show every path from the leaf up to a root, as a
+    /// tree with each cert's expiry and when each path stops working, e.g.
+    /// when an intermediate is cross-signed by an old and a new root.
+    #[arg(long, requires = "chain")]
+    paths: bool,
+
     /// Do not print out any certificates.
     #[arg(long)]
     no_cert: bool,
@@ -47,10 +196,11 @@ pub struct Connect {
     #[arg(long)]
     rpk: bool,
 
-    /// The curves to use when connecting to the server. Curves must be `:`
-    /// separated. Defaults to a PQC-preferring.
-    // todo: combine the curves for the user. Users should be able to input a simple list.
-    #[arg(long)]
+    /// The curves to offer, most preferred first, separated by `:`, `,` or
+    /// spaces. Names are case-insensitive and the usual aliases work, e.g.
+    /// `p256`, `secp384r1` or `mlkem768`; `pls capabilities` lists the curves.
+    /// Defaults to a PQC-preferring list.
+    #[arg(long, value_parser = curves::parse_curves)]
     curves: Option<String>,
 
     /// Offer only post-quantum (PQC) curves, dropping classical fallbacks.
@@ -60,6 +210,116 @@ pub struct Connect {
     /// Connect over QUIC (HTTP/3, ALPN `h3`) instead of TCP+TLS.
     #[arg(long, conflicts_with = "rpk")]
     quic: bool,
+
+    /// The ALPN protocols to offer, comma separated, e.g. `h2,http/1.1`. No
+    /// ALPN extension is sent unless this is given. Ignored with `--quic`,
+    /// which always offers `h3`.
+    #[arg(long, value_delimiter = ',')]
+    alpn: Vec<String>,
+
+    /// Append the TLS session secrets to this file in the `SSLKEYLOGFILE`
+    /// format, for decrypting packet captures with Wireshark.
+    #[arg(long, value_name = "FILE")]
+    keylog: Option<PathBuf>,
+
+    /// After the handshake, send a `HEAD /` (or `--http=get` for `GET /`)
+    /// over the connection and show the status, the HTTP version and the
+    /// `Strict-Transport-Security`, `Expect-CT` and `Alt-Svc` headers.
+    /// Offers ALPN `http/1.1` unless `--alpn` is given.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "METHOD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "head",
+        conflicts_with_all = ["quic", "rpk"]
+    )]
+    http: Option<HttpMethod>,
+
+    /// After the handshake, complete the HTTP/2 connection preface and show
+    /// the server's SETTINGS, to check an endpoint that selects ALPN `h2`
+    /// (e.g. a gRPC load balancer) actually speaks it. Offers ALPN `h2`
+    /// unless `--alpn` is given.
+    #[arg(long, conflicts_with_all = ["http", "quic", "rpk"])]
+    h2: bool,
+
+    /// Treat the host as a container registry: without a port, connect to
+    /// 5000 for `localhost`, IP addresses and names without a dot (where
+    /// `docker run registry:2` listens) and to 443 otherwise, then send
+    /// `GET /v2/` over the connection to check it speaks the registry API.
+    /// Offers ALPN `http/1.1` unless `--alpn` is given.
+    #[arg(long, conflicts_with_all = ["http", "h2", "quic", "rpk"])]
+    registry: bool,
+
+    /// With `--registry`, check the certs Docker uses for the registry, in
+    /// `/etc/docker/certs.d/<host>[:<port>]/` or `DIR`: that CAs (`*.crt`)
+    /// are CAs, that each client cert (`*.cert`) has its key (`*.key`) and
+    /// allows client auth, expiry, and that the registry's chain verifies
+    /// against the CAs and the system roots as it does for Docker.
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        require_equals = true,
+        requires = "registry"
+    )]
+    docker_certs: Option<Option<PathBuf>>,
+
+    /// Record the versions, curves and ALPN protocols offered in the
+    /// ClientHello next to what the server selected.
+    #[arg(long)]
+    transcript: bool,
+
+    /// Tunnel the connection through a proxy: `http://host:3128` (HTTP
+    /// CONNECT) or `socks5://host:1080`. Credentials can be given as
+    /// `user:pass@`. The target host is resolved by the proxy.
+    #[arg(long, value_name = "URL", conflicts_with = "quic")]
+    proxy: Option<Proxy>,
+
+    /// Who decides whether the chain is trusted: `boring` (the handshake's
+    /// verdict), `bundled` (Mozilla's roots, built into `pls`) or `platform`
+    /// (the OS verifier on macOS and Windows, as browsers there use).
+    #[arg(long, value_enum, default_value_t)]
+    verifier: Verifier,
+
+    #[command(flatten)]
+    dns: DnsArgs,
+
+    /// Look up the TLSA records of the service (`_443._tcp.host`) and check
+    /// the chain against them (DANE). The records only count when the
+    /// resolver validated them with DNSSEC.
+    #[arg(long)]
+    dane: bool,
+
+    /// Check whether the leaf has been revoked. `crl` downloads the CRL from
+    /// the leaf's cRLDistributionPoints, cached like other lookups, checks
+    /// it's signed by the leaf's issuer and looks for the leaf's serial.
+    #[arg(long, value_enum, value_name = "METHOD")]
+    check_revocation: Option<RevocationMethod>,
+
+    /// Write the certs the server sent to this directory in chain order:
+    /// `leaf.pem`, `intermediate-1.pem`, ... and `root.pem` for a
+    /// self-signed cert. The whole chain is saved, even without `--chain`.
+    #[arg(long, value_name = "DIR")]
+    save_chain: Option<PathBuf>,
+
+    /// With `--save-chain`, also write DER copies of the certs (`leaf.der`,
+    /// ...).
+    #[arg(long, requires = "save_chain")]
+    save_der: bool,
+
+    /// With `--save-chain`, also write `fullchain.pem`: the leaf followed by
+    /// the intermediates, as most servers want it configured.
+    #[arg(long, requires = "save_chain")]
+    fullchain: bool,
+
+    /// Compare the leaf with the one this host served on the last run with
+    /// the same state file, e.g. `~/.cache/pls/state.json`, and show what
+    /// changed: fingerprint, serial, issuer, SANs, ... The file is created if
+    /// needed and updated with the leaf.
+    #[arg(long, value_name = "FILE")]
+    state: Option<PathBuf>,
 }
 
 impl Connect {
@@ -72,95 +332,279 @@ impl Connect {
             self.curves.as_deref()
         }
     }
-}
 
-impl CommandExt for Connect {
-    async fn run(self, format: Format) -> color_eyre::Result<()> {
-        if self.quic {
-            quic::run(&self, format).await
-        } else {
-            tcp::run(&self, format).await
+    /// Where to connect to, from the host and `--port`.
+    pub(crate) fn target(&self) -> color_eyre::Result<Target> {
+        match self.registry {
+            true => Target::parse_with_default(&self.host, self.port, registry::default_port),
+            false => Target::parse(&self.host, self.port),
         }
     }
-}
 
-/// Parse the host string into a hostname and SocketAddr.
-pub(crate) fn parse_host(host: &str) -> color_eyre::Result<(String, SocketAddr)> {
-    if let Ok(addr) = host.parse::<SocketAddr>() {
-        // If the host is already a valid IP address, return it as-is
-        tracing::debug!("parsed {host} as socket address");
-        return Ok((addr.ip().to_string(), addr));
-    }
-
-    let bare = host
-        .strip_prefix('[')
-        .and_then(|h| h.strip_suffix(']'))
-        .unwrap_or(host);
-    if let Ok(ip) = bare.parse::<IpAddr>() {
-        tracing::debug!("parsed {host} as bare IP address");
-        return Ok((ip.to_string(), SocketAddr::new(ip, 443)));
-    }
-
-    if let Ok(url) = host.parse::<Url>() {
-        // If the host is a valid URL, extract the host and port
-
-        // `cloudflare.com:443` parses as a url with no host and a scheme of
-        // `cloudflare.com`. This check is to ensure that the host exists
-        if url.host().is_some() {
-            tracing::debug!("parsed {host} as URL");
-            let addrs = url
-                .socket_addrs(|| Some(443))
-                .with_context(|| format!("resolving URL host {host:?}"))?;
-            let addr = addrs
-                .into_iter()
-                .next()
-                .ok_or_else(|| eyre!("URL host {host:?} resolved to no addresses"))?;
-            // host_str() is Some whenever host() is Some (checked above).
-            return Ok((url.host_str().unwrap().to_string(), addr));
+    /// The curve list that will actually be sent, with defaults applied.
+    pub(crate) fn curves_or_default(&self) -> &str {
+        self.curves().unwrap_or(DEFAULT_CURVES)
+    }
+
+    /// The ALPN protocols to offer: `--alpn`, or `http/1.1` for `--http` and
+    /// `--registry` and `h2` for `--h2`.
+    pub(crate) fn alpn(&self) -> Vec<String> {
+        match &self.alpn[..] {
+            [] if self.http.is_some() || self.registry => vec!["http/1.1".to_string()],
+            [] if self.h2 => vec!["h2".to_string()],
+            alpn => alpn.to_vec(),
+        }
+    }
+
+    /// Whether the server's whole chain is needed, rather than just its leaf.
+    pub(crate) fn wants_chain(&self) -> bool {
+        self.chain
+            || self.dane
+            || self.check_revocation.is_some()
+            || self.save_chain.is_some()
+            || self.docker_certs.is_some()
+            || self.verifier != Verifier::Boring
+    }
+
+    /// Put the certs the server sent in chain order, completing it with
+    /// `--fetch-missing`, verify them with `--verifier`, check them with
+    /// `--dane`, `--check-revocation` and `--docker-certs` and save them if
+    /// asked to.
+    /// Then trim them down to the ones that are printed.
+    pub(crate) async fn finish(
+        &self,
+        connection: &mut ConnectionWithCerts,
+    ) -> color_eyre::Result<()> {
+        if self.wants_chain() {
+            let (mut certs, mut issues) =
+                chain::order_to(std::mem::take(&mut connection.certs), self.max_depth);
+            if self.fetch_missing {
+                if let Some(ChainIssue::MissingIntermediate { fetched }) = issues
+                    .iter_mut()
+                    .find(|issue| matches!(issue, ChainIssue::MissingIntermediate { .. }))
+                {
+                    *fetched = chain::fetch_missing(&mut certs, self.max_depth).await;
+                }
+            }
+            if self.paths {
+                connection.paths = chain::paths(&certs, self.max_depth)?;
+            }
+            connection.certs = certs;
+            if self.chain {
+                connection.chain_issues = issues;
+            }
+        }
+
+        if let Some(verification) =
+            verifier::verify(self.verifier, &self.target()?.host, &connection.certs)
+                .with_context(|| format!("verifying with the {} verifier", self.verifier))?
+        {
+            connection
+                .tls
+                .apply_verification(self.verifier, &verification);
+            if let Some(leaf) = connection.certs.first_mut() {
+                leaf.apply_verification(&verification);
+            }
+        }
+
+        if let Some(dir) = &self.docker_certs {
+            let target = self.target()?;
+            let dir = dir
+                .clone()
+                .unwrap_or_else(|| registry::docker_certs_dir(&target.host, target.port));
+            let docker_certs = registry::check_docker_certs(
+                &dir,
+                &target.host,
+                &connection.certs,
+                jiff::Timestamp::now(),
+            )
+            .context("checking the Docker certs")?;
+            connection.docker_certs = Some(docker_certs);
+        }
+
+        if self.dane {
+            let target = self.target()?;
+            let protocol = match connection.tls.transport {
+                Transport::TCP => "tcp",
+                Transport::QUIC => "udp",
+            };
+            let name = dane::tlsa_name(&target.host, target.port, protocol);
+            let dane = dane::check(
+                &self.dns.resolver(),
+                name,
+                &connection.certs,
+                connection.tls.valid,
+            )
+            .await
+            .context("checking DANE")?;
+            connection.dane = Some(dane);
+        }
+
+        if let (Some(method), Some(leaf)) = (self.check_revocation, connection.certs.first()) {
+            let issuer = match connection
+                .certs
+                .iter()
+                .skip(1)
+                .find(|issuer| issuer._cert.issued(&leaf._cert).is_ok())
+            {
+                Some(issuer) => Some(issuer.clone()),
+                None if leaf.is_self_signed => None,
+                // a server that left out the intermediate
+                None => chain::fetch_issuer(leaf).await.unwrap_or_else(|err| {
+                    tracing::warn!("fetching the issuer of the leaf: {err:#}");
+                    None
+                }),
+            };
+            connection.revocation = Some(revocation::check(method, leaf, issuer.as_ref()).await);
+        }
+
+        if let (Some(path), Some(leaf)) = (&self.state, connection.certs.first()) {
+            let mut state = State::load(path)?;
+            let snapshot = Snapshot::new(leaf, jiff::Timestamp::now());
+            connection.changes = state.record(&self.target()?.to_string(), snapshot);
+            state.save(path)?;
         }
+
+        let certs = &mut connection.certs;
+        if let Some(dir) = &self.save_chain {
+            save_chain(dir, certs, self.save_der, self.fullchain)?;
+        }
+
+        if self.no_cert {
+            certs.clear();
+        } else if !self.chain {
+            certs.truncate(1);
+        }
+        Ok(())
     }
+}
 
-    // If the host is not a valid IP address or URL, assume it is a hostname
-    let (hostname, port) = if let Some((hostname, port)) = host.split_once(':') {
-        let port = port
-            .parse::<u16>()
-            .with_context(|| format!("parsing port {port:?} in host {host:?}"))?;
-        (hostname, port)
-    } else {
-        (host, 443)
+/// Write `certs` to `dir`, for `--save-chain`.
+fn save_chain(
+    dir: &Path,
+    certs: &[SimpleCert],
+    der: bool,
+    fullchain: bool,
+) -> color_eyre::Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Creating {}", dir.display()))?;
+    let write = |name: String, contents: &[u8]| {
+        let path = dir.join(name);
+        fs::write(&path, contents).with_context(|| format!("Writing {}", path.display()))
     };
 
-    // Resolve the hostname to an IP address
-    tracing::debug!("parsed {host} as hostname:port ({hostname}:{port})");
-    let addr = (hostname, port)
-        .to_socket_addrs()
-        .with_context(|| format!("resolving {hostname}:{port}"))?
-        .next()
-        .ok_or_else(|| eyre!("{hostname}:{port} resolved to no addresses"))?;
-    Ok((hostname.to_string(), addr))
+    for (i, cert) in certs.iter().enumerate() {
+        let name = match i {
+            0 => "leaf".to_string(),
+            _ if cert.is_self_signed => "root".to_string(),
+            i => format!("intermediate-{i}"),
+        };
+        write(format!("{name}.pem"), cert.pem.as_bytes())?;
+        if der {
+            write(format!("{name}.der"), &cert._cert.to_der()?)?;
+        }
+    }
+
+    if fullchain {
+        let pem: String = certs
+            .iter()
+            .enumerate()
+            .filter(|(i, cert)| *i == 0 || !cert.is_self_signed)
+            .map(|(_, cert)| cert.pem.as_str())
+            .collect();
+        write("fullchain.pem".to_string(), pem.as_bytes())?;
+    }
+
+    tracing::info!("saved {} certs to {}", certs.len(), dir.display());
+    Ok(())
+}
+
+impl CommandExt for Connect {
+    async fn run(self, format: Format, options: RenderOptions) -> color_eyre::Result<()> {
+        let span = tracing::info_span!("connect", host = %self.host, quic = self.quic);
+        if self.quic {
+            quic::run(&self, format, &options).instrument(span).await
+        } else {
+            tcp::run(&self, format, &options).instrument(span).await
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_host;
+    use boring::ssl::{SslMethod, SslVersion};
+
+    use super::*;
 
     #[test]
-    fn parses_ipv6() {
-        // Bare IPv6, default port.
-        let (host, addr) = parse_host("::1").unwrap();
-        assert_eq!(host, "::1");
-        assert_eq!(addr.to_string(), "[::1]:443");
-
-        // Bracketed IPv6, default port.
-        let (_, addr) = parse_host("[2001:db8::1]").unwrap();
-        assert_eq!(addr.to_string(), "[2001:db8::1]:443");
-
-        // Bracketed IPv6 with explicit port.
-        let (_, addr) = parse_host("[::1]:8443").unwrap();
-        assert_eq!(addr.to_string(), "[::1]:8443");
-
-        // IPv4 still works.
-        let (_, addr) = parse_host("1.2.3.4:80").unwrap();
-        assert_eq!(addr.to_string(), "1.2.3.4:80");
+    fn alpn_wire_format_prefixes_lengths() {
+        let wire = alpn_wire_format(&["h2".to_string(), "http/1.1".to_string()]).unwrap();
+        assert_eq!(wire, b"\x02h2\x08http/1.1");
+
+        assert!(alpn_wire_format(&[String::new()]).is_err());
+        assert!(alpn_wire_format(&["a".repeat(256)]).is_err());
+        assert_eq!(alpn_wire_format(&["a".repeat(255)]).unwrap().len(), 256);
+    }
+
+    #[test]
+    fn offered_follows_the_context() {
+        let mut builder = SslContextBuilder::new(SslMethod::tls_client()).unwrap();
+        builder
+            .set_min_proto_version(Some(SslVersion::TLS1_2))
+            .unwrap();
+        let hello = offered(&mut builder, "X25519:P-256", &["h2".to_string()]);
+
+        assert_eq!(hello.versions, ["TLSv1.2", "TLSv1.3"]);
+        assert_eq!(hello.curves, ["X25519", "P-256"]);
+        assert_eq!(hello.alpn, ["h2"]);
+        assert!(hello
+            .ciphers
+            .starts_with(&TLS13_CIPHERS.map(str::to_string)));
+        assert!(hello.ciphers.len() > TLS13_CIPHERS.len());
+
+        builder
+            .set_min_proto_version(Some(SslVersion::TLS1_3))
+            .unwrap();
+        let hello = offered(&mut builder, DEFAULT_CURVES, &[]);
+        assert_eq!(hello.versions, ["TLSv1.3"]);
+        assert_eq!(hello.ciphers, TLS13_CIPHERS.map(str::to_string));
+        assert!(hello.alpn.is_empty());
+    }
+
+    #[test]
+    fn saves_chain() {
+        let certs: Vec<SimpleCert> = boring::x509::X509::stack_from_pem(include_bytes!(
+            "../../../test-data/certs/chain.pem"
+        ))
+        .unwrap()
+        .into_iter()
+        .map(SimpleCert::from)
+        .collect();
+
+        let dir = std::env::temp_dir().join(format!("pls-save-chain-{}", std::process::id()));
+        save_chain(&dir, &certs, true, true).unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                "fullchain.pem",
+                "intermediate-1.der",
+                "intermediate-1.pem",
+                "leaf.der",
+                "leaf.pem",
+                "root.der",
+                "root.pem"
+            ]
+        );
+
+        // the root isn't part of the full chain
+        let fullchain = fs::read_to_string(dir.join("fullchain.pem")).unwrap();
+        assert_eq!(fullchain, format!("{}{}", certs[0].pem, certs[1].pem));
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -10,20 +10,34 @@ use tokio_quiche::quic::{ConnectionHook, HandshakeInfo, QuicheConnection};
 use tokio_quiche::settings::{CertificateKind, Hooks, QuicSettings, TlsCertificatePaths};
 use tokio_quiche::socket::Socket;
 use tokio_quiche::{ApplicationOverQuic, ConnectionParams, QuicResult};
+use tracing::Instrument;
 
 use crate::components::connection::{print_tls_connection_with_certs, ConnectionWithCerts};
-use crate::connection::{Connection, Time, Transport};
+use crate::connection::{Connection, Offered, Time, Transcript, Transport};
+use crate::progress::Progress;
 use crate::x509::SimpleCert;
 
-use crate::commands::Format;
+use crate::commands::{Format, RenderOptions};
 
-use super::{parse_host, Connect};
+use super::Connect;
 
-pub(super) async fn run(cmd: &Connect, format: Format) -> color_eyre::Result<()> {
+pub(super) async fn run(
+    cmd: &Connect,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    let keylog = cmd.keylog.as_deref().map(super::open_keylog).transpose()?;
+
+    let target = cmd.target()?;
     let dns_start = Instant::now();
-    let (hostname, addr) = parse_host(&cmd.host)?;
+    let hostname = target.host.clone();
+    let progress = Progress::spinner(format!("connecting to {hostname} over QUIC"));
+    let (addr, resolution) = target
+        .resolve(&cmd.dns.resolver())
+        .instrument(tracing::info_span!("resolve", host = %hostname))
+        .await?;
     let time_dns = dns_start.elapsed();
-    tracing::info!("resolved {hostname} -> {addr} in {time_dns:?}, connecting via QUIC");
+    tracing::info!(host = %hostname, %addr, ?time_dns, "resolved, connecting via QUIC");
 
     let handshake_start = Instant::now();
     let bind = if addr.is_ipv6() {
@@ -45,6 +59,7 @@ pub(super) async fn run(cmd: &Connect, format: Format) -> color_eyre::Result<()>
     // The hook only fires when `tls_cert` is `Some`, so pass placeholder paths.
     let hook: Arc<dyn ConnectionHook + Send + Sync> = Arc::new(TlsHook {
         curves: cmd.curves().map(str::to_owned),
+        keylog,
     });
     let hooks = Hooks {
         connection_hook: Some(hook),
@@ -59,10 +74,19 @@ pub(super) async fn run(cmd: &Connect, format: Format) -> color_eyre::Result<()>
     let (tx, rx) = oneshot::channel();
     let app = InspectApp {
         tx: Some(tx),
-        want_chain: cmd.chain,
-        no_cert: cmd.no_cert,
+        want_chain: cmd.wants_chain(),
         time_dns,
         handshake_start,
+        offered: cmd.transcript.then(|| Offered {
+            versions: vec!["TLSv1.3".to_string()],
+            ciphers: super::TLS13_CIPHERS.map(str::to_string).to_vec(),
+            curves: cmd
+                .curves_or_default()
+                .split(':')
+                .map(str::to_string)
+                .collect(),
+            alpn: vec!["h3".to_string()],
+        }),
         buf: vec![0u8; 64 * 1024],
     };
 
@@ -71,15 +95,22 @@ pub(super) async fn run(cmd: &Connect, format: Format) -> color_eyre::Result<()>
         .await
         .map_err(|e| eyre!("QUIC connection to {hostname} failed: {e}"))?;
 
-    let connection = rx.await.map_err(|_| {
+    let mut connection = rx
+        .instrument(tracing::info_span!("handshake", host = %hostname, %addr))
+        .await
+        .map_err(|_| {
         eyre!("QUIC handshake to {hostname} did not complete; the server may not support HTTP/3 (ALPN h3)")
     })?;
+    connection.tls.dns = resolution;
+    cmd.finish(&mut connection).await?;
 
-    print_tls_connection_with_certs(connection, format)
+    drop(progress);
+    print_tls_connection_with_certs(connection, format, options)
 }
 
 struct TlsHook {
     curves: Option<String>,
+    keylog: Option<super::KeyLog>,
 }
 
 impl ConnectionHook for TlsHook {
@@ -93,6 +124,10 @@ impl ConnectionHook for TlsHook {
 
         super::set_curves(&mut builder, self.curves.as_deref()).ok()?;
 
+        if let Some(keylog) = &self.keylog {
+            super::set_keylog(&mut builder, keylog.clone());
+        }
+
         Some(builder)
     }
 }
@@ -100,9 +135,9 @@ impl ConnectionHook for TlsHook {
 struct InspectApp {
     tx: Option<oneshot::Sender<ConnectionWithCerts>>,
     want_chain: bool,
-    no_cert: bool,
     time_dns: Duration,
     handshake_start: Instant,
+    offered: Option<Offered>,
     buf: Vec<u8>,
 }
 
@@ -127,14 +162,20 @@ impl ApplicationOverQuic for InspectApp {
 
         let time = Time {
             dns: self.time_dns,
+            proxy: None,
             connect: None,
             tls: self.handshake_start.elapsed(),
+            ..Default::default()
         };
-        tracing::debug!("QUIC handshake completed in {:?}", time.tls);
+        tracing::debug!(time_tls = ?time.tls, "QUIC handshake completed");
 
         let ssl = qconn.as_mut();
         let verify_result = ssl.verify_result();
-        let tls = Connection::from((Transport::QUIC, time, &*ssl));
+        let mut tls = Connection::from((Transport::QUIC, time, &*ssl));
+        tls.transcript = self
+            .offered
+            .take()
+            .map(|offered| Transcript::new(offered, ssl));
 
         let mut certs: Vec<SimpleCert> = der_chain
             .iter()
@@ -144,12 +185,13 @@ impl ApplicationOverQuic for InspectApp {
         if let Some(cert) = certs.first_mut() {
             cert.apply_verify_result(verify_result);
         }
-        if self.no_cert {
-            certs.clear();
-        }
 
         if let Some(tx) = self.tx.take() {
-            let _ = tx.send(ConnectionWithCerts { tls, certs });
+            let _ = tx.send(ConnectionWithCerts {
+                tls,
+                certs,
+                ..Default::default()
+            });
         }
 
         Ok(())
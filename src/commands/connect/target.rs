@@ -0,0 +1,357 @@
+use std::ffi::CString;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use url::Url;
+
+use crate::{
+    dns::{Resolution, Resolver},
+    idna,
+};
+
+/// The port used when neither the host, its URL scheme nor `--port` give one.
+const DEFAULT_PORT: u16 = 443;
+
+/// The implicit TLS port of a URL scheme, for the schemes that have one.
+fn scheme_port(scheme: &str) -> Option<u16> {
+    Some(match scheme {
+        "https" | "wss" => 443,
+        "smtps" => 465,
+        "ldaps" => 636,
+        "ftps" => 990,
+        "imaps" => 993,
+        "pop3s" => 995,
+        _ => return None,
+    })
+}
+
+/// Where to connect to: the `host` argument of `connect` and `--port`.
+///
+/// The host can be a hostname, an IP address (IPv6 with or without
+/// brackets, and with a scope like `fe80::1%eth0`), either followed by
+/// `:port`, or a URL such as `ldaps://ldap.example.com`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Target {
+    /// The hostname or IP address, without brackets or scope. Sent as the
+    /// SNI when it's a name, with its Unicode labels IDNA-encoded.
+    pub host: String,
+    pub port: u16,
+    /// The scope (zone) of a link-local IPv6 address: an interface name or
+    /// index, e.g. `eth0` in `fe80::1%eth0`.
+    pub scope: Option<String>,
+}
+
+impl Target {
+    /// Parse `input`. `port` is `--port`, which can't be combined with a
+    /// port in `input`.
+    pub fn parse(input: &str, port: Option<u16>) -> Result<Self> {
+        Self::parse_with_default(input, port, |_| DEFAULT_PORT)
+    }
+
+    /// [`Self::parse`], with `default_port` picking the port from the host
+    /// when nothing gives one, e.g. for `--registry`.
+    pub fn parse_with_default(
+        input: &str,
+        port: Option<u16>,
+        default_port: impl FnOnce(&str) -> u16,
+    ) -> Result<Self> {
+        let (host, scope, input_port) = split(input)?;
+        if host.is_empty() {
+            bail!("no host in {input:?}");
+        }
+        // `bücher.example` goes in the SNI and DNS as `xn--bcher-kva.example`
+        let host = idna::to_ascii(&host)?;
+
+        let port = match (input_port, port) {
+            (Port::Explicit(_), Some(_)) => {
+                bail!("{input:?} has a port, it can't be combined with --port")
+            }
+            (_, Some(port)) => port,
+            (Port::Explicit(port) | Port::Scheme(port), None) => port,
+            (Port::Default, None) => default_port(&host),
+            (Port::UnknownScheme(scheme), None) => {
+                bail!("no default port for {scheme}://, give one with --port")
+            }
+        };
+
+        let target = Self { host, port, scope };
+        tracing::debug!("parsed {input} as {target}");
+        Ok(target)
+    }
+
+    /// The host as an IP address, when it is one rather than a name.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.host.parse().ok()
+    }
+
+    /// The address to connect to when the host is an IP address, with its
+    /// scope.
+    pub fn socket_addr(&self) -> Result<Option<SocketAddr>> {
+        Ok(match (self.ip(), &self.scope) {
+            (Some(IpAddr::V6(ip)), Some(scope)) => {
+                Some(SocketAddrV6::new(ip, self.port, 0, scope_id(scope)?).into())
+            }
+            (Some(ip), _) => Some(SocketAddr::new(ip, self.port)),
+            (None, _) => None,
+        })
+    }
+
+    /// Resolve the host with `resolver`, taking the first address. IP
+    /// addresses are used as is, without a [`Resolution`].
+    pub async fn resolve(&self, resolver: &Resolver) -> Result<(SocketAddr, Option<Resolution>)> {
+        if let Some(addr) = self.socket_addr()? {
+            return Ok((addr, None));
+        }
+
+        let (addr, resolution) = resolver.lookup_host(&self.host, self.port).await?;
+        Ok((addr, Some(resolution)))
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.ip(), &self.scope) {
+            (Some(IpAddr::V6(_)), Some(scope)) => {
+                write!(f, "[{}%{scope}]:{}", self.host, self.port)
+            }
+            (Some(IpAddr::V6(_)), None) => write!(f, "[{}]:{}", self.host, self.port),
+            _ => write!(f, "{}:{}", self.host, self.port),
+        }
+    }
+}
+
+/// Where the port of a [`Target`] comes from.
+enum Port {
+    /// Written out, e.g. `example.com:8443`.
+    Explicit(u16),
+    /// The default of the URL scheme, e.g. `ldaps://`.
+    Scheme(u16),
+    /// A URL with a scheme that has no known TLS port.
+    UnknownScheme(String),
+    /// Not given, so [`DEFAULT_PORT`] or the caller's default.
+    Default,
+}
+
+/// Split `input` into a host, its IPv6 scope and where its port comes from.
+fn split(input: &str) -> Result<(String, Option<String>, Port)> {
+    if input.contains("://") {
+        let url = Url::parse(input).with_context(|| format!("parsing URL {input:?}"))?;
+        let host = match url.host() {
+            Some(url::Host::Ipv6(ip)) => ip.to_string(),
+            Some(host) => host.to_string(),
+            None => bail!("no host in {input:?}"),
+        };
+        // `url` drops the port when it's the default of a special scheme,
+        // e.g. `https://example.com:443`
+        let port = match (url.port(), scheme_port(url.scheme())) {
+            (Some(port), _) => Port::Explicit(port),
+            (None, Some(port)) => Port::Scheme(port),
+            (None, None) => Port::UnknownScheme(url.scheme().to_string()),
+        };
+        return Ok((host, None, port));
+    }
+
+    if let Some(rest) = input.strip_prefix('[') {
+        let (inside, after) = rest
+            .split_once(']')
+            .ok_or_else(|| eyre!("missing `]` in {input:?}"))?;
+        let (ip, scope) =
+            parse_ipv6(inside).ok_or_else(|| eyre!("invalid IPv6 address in {input:?}"))?;
+        let port = match after {
+            "" => Port::Default,
+            after => {
+                let port = after
+                    .strip_prefix(':')
+                    .ok_or_else(|| eyre!("unexpected {after:?} after the address in {input:?}"))?;
+                Port::Explicit(parse_port(port, input)?)
+            }
+        };
+        return Ok((ip.to_string(), scope, port));
+    }
+
+    if let Some((ip, scope)) = parse_ipv6(input) {
+        return Ok((ip.to_string(), scope, Port::Default));
+    }
+    if input.contains('%') {
+        bail!("invalid IPv6 address in {input:?}");
+    }
+    if let Ok(ip) = input.parse::<Ipv4Addr>() {
+        return Ok((ip.to_string(), None, Port::Default));
+    }
+
+    match input.rsplit_once(':') {
+        Some((host, port)) => Ok((
+            host.to_string(),
+            None,
+            Port::Explicit(parse_port(port, input)?),
+        )),
+        None => Ok((input.to_string(), None, Port::Default)),
+    }
+}
+
+fn parse_port(port: &str, input: &str) -> Result<u16> {
+    port.parse()
+        .map_err(|_| eyre!("invalid port {port:?} in {input:?}"))
+}
+
+/// Parse an IPv6 address with an optional `%scope`.
+fn parse_ipv6(input: &str) -> Option<(Ipv6Addr, Option<String>)> {
+    match input.split_once('%') {
+        Some((_, "")) => None,
+        Some((ip, scope)) => Some((ip.parse().ok()?, Some(scope.to_string()))),
+        None => Some((input.parse().ok()?, None)),
+    }
+}
+
+/// The interface index of an IPv6 scope, given as a number or an interface
+/// name.
+fn scope_id(scope: &str) -> Result<u32> {
+    if let Ok(index) = scope.parse() {
+        return Ok(index);
+    }
+
+    let name = CString::new(scope).map_err(|_| eyre!("invalid interface name {scope:?}"))?;
+    // SAFETY: `name` is a valid NUL terminated string that outlives the call
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => bail!("no network interface named {scope:?}"),
+        index => Ok(index),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(input: &str, port: Option<u16>) -> (String, u16) {
+        let target = Target::parse(input, port).unwrap();
+        (target.host, target.port)
+    }
+
+    #[test]
+    fn hosts_and_ports() {
+        let cases = [
+            ("example.com", ("example.com", 443)),
+            ("example.com:8443", ("example.com", 8443)),
+            ("1.2.3.4:80", ("1.2.3.4", 80)),
+            ("::1", ("::1", 443)),
+            ("[2001:db8::1]", ("2001:db8::1", 443)),
+            ("[::1]:8443", ("::1", 8443)),
+            ("bücher.example:8443", ("xn--bcher-kva.example", 8443)),
+        ];
+        for (input, (host, port)) in cases {
+            assert_eq!(target(input, None), (host.to_string(), port), "{input}");
+        }
+
+        // the default only applies when nothing else gives a port
+        let port = |input| {
+            Target::parse_with_default(input, None, |_| 5000)
+                .unwrap()
+                .port
+        };
+        assert_eq!(
+            (
+                port("localhost"),
+                port("localhost:443"),
+                port("https://localhost")
+            ),
+            (5000, 443, 443)
+        );
+    }
+
+    #[test]
+    fn url_schemes() {
+        let cases = [
+            ("https://example.com/path?q", 443),
+            ("https://example.com:443", 443),
+            ("https://example.com:8443", 8443),
+            ("smtps://mail.example.com", 465),
+            ("ldaps://ldap.example.com", 636),
+            ("imaps://mail.example.com", 993),
+        ];
+        for (input, port) in cases {
+            assert_eq!(target(input, None).1, port, "{input}");
+        }
+        assert_eq!(target("https://[::1]", None), ("::1".to_string(), 443));
+        assert!(Target::parse("gopher://example.com", None).is_err());
+        assert_eq!(target("gopher://example.com", Some(70)).1, 70);
+    }
+
+    #[test]
+    fn port_flag() {
+        assert_eq!(target("example.com", Some(8443)).1, 8443);
+        assert_eq!(target("::1", Some(8443)), ("::1".to_string(), 8443));
+        // the scheme's default gives way to --port
+        assert_eq!(target("ldaps://ldap.example.com", Some(3269)).1, 3269);
+
+        let err = Target::parse("example.com:443", Some(8443)).unwrap_err();
+        assert!(err.to_string().contains("--port"), "{err}");
+    }
+
+    #[test]
+    fn errors() {
+        for input in [
+            "",
+            ":443",
+            "example.com:https",
+            "example.com:70000",
+            "[::1",
+            "[::1]443",
+            "fe80::1%",
+            "1.2.3.4%eth0",
+        ] {
+            assert!(Target::parse(input, None).is_err(), "{input}");
+        }
+        let err = Target::parse("example.com:x", None).unwrap_err();
+        assert_eq!(err.to_string(), "invalid port \"x\" in \"example.com:x\"");
+    }
+
+    #[test]
+    fn display() {
+        let target = Target::parse("[::1]:8443", None).unwrap();
+        assert_eq!(target.to_string(), "[::1]:8443");
+        assert_eq!(
+            target.socket_addr().unwrap().unwrap().to_string(),
+            "[::1]:8443"
+        );
+        let target = Target::parse("1.2.3.4", None).unwrap();
+        assert_eq!(target.to_string(), "1.2.3.4:443");
+    }
+
+    #[test]
+    fn ipv6_literals() {
+        let target = Target::parse("[2606:4700::6810:84e5]:443", None).unwrap();
+        assert_eq!(target.host, "2606:4700::6810:84e5");
+        assert_eq!(target.scope, None);
+        assert_eq!(
+            target.socket_addr().unwrap().unwrap().to_string(),
+            "[2606:4700::6810:84e5]:443"
+        );
+
+        // an unbracketed address isn't split at its last `:`
+        assert_eq!(
+            target("2001:db8::443", None),
+            ("2001:db8::443".to_string(), 443)
+        );
+    }
+
+    #[test]
+    fn ipv6_scopes() {
+        for (input, port) in [("fe80::1%eth0", 443), ("[fe80::1%eth0]:8443", 8443)] {
+            let target = Target::parse(input, None).unwrap();
+            assert_eq!(target.host, "fe80::1");
+            assert_eq!(target.scope.as_deref(), Some("eth0"));
+            assert_eq!(target.port, port);
+        }
+
+        let target = Target::parse("[fe80::1%3]:443", None).unwrap();
+        assert_eq!(target.to_string(), "[fe80::1%3]:443");
+        let Some(SocketAddr::V6(addr)) = target.socket_addr().unwrap() else {
+            panic!("expected an IPv6 address");
+        };
+        assert_eq!(addr.scope_id(), 3);
+
+        let target = Target::parse("fe80::1%not-an-interface", None).unwrap();
+        assert!(target.socket_addr().is_err());
+    }
+}
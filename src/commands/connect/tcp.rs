@@ -1,29 +1,78 @@
+use std::net::ToSocketAddrs;
 use std::time::Instant;
 
 use boring::ssl::{SslConnector, SslMethod, SslVerifyMode};
-use color_eyre::eyre::Context;
+use color_eyre::eyre::{bail, eyre, Context};
+use tokio::net::TcpStream;
+use tokio_boring::SslStream;
+use tracing::Instrument;
 
-use crate::commands::Format;
+use crate::commands::{Format, RenderOptions};
 use crate::components::connection::{print_tls_connection_with_certs, ConnectionWithCerts};
-use crate::connection::{Connection, Time, Transport};
+use crate::connection::{Connection, H2Probe, HttpProbe, Time, Transcript, Transport};
+use crate::dns::Resolver;
+use crate::progress::{self, Progress};
+use crate::registry::RegistryProbe;
 use crate::x509::SimpleCert;
+use crate::{h2, http};
 
-use super::{parse_host, Connect};
+use super::{Connect, HttpMethod, Target};
 
 /// Connect to `cmd.host` over TCP, complete the TLS handshake, and print the
 /// connection + certificate information.
-pub(super) async fn run(cmd: &Connect, format: Format) -> color_eyre::Result<()> {
+pub(super) async fn run(
+    cmd: &Connect,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    let keylog = cmd.keylog.as_deref().map(super::open_keylog).transpose()?;
+
+    let target = cmd.target()?;
+    let hostname = target.host.clone();
+    let progress = Progress::spinner(format!("resolving {hostname}"));
     let dns_start = Instant::now();
-    let (hostname, addr) = parse_host(&cmd.host)?;
+    let (addr, resolution) = match &cmd.proxy {
+        Some(proxy) => {
+            let addr = proxy
+                .addr()
+                .to_socket_addrs()
+                .with_context(|| format!("resolving proxy {proxy}"))?
+                .next()
+                .ok_or_else(|| eyre!("proxy {proxy} resolved to no addresses"))?;
+            (addr, None)
+        }
+        None => {
+            target
+                .resolve(&cmd.dns.resolver())
+                .instrument(tracing::info_span!("resolve", host = %hostname))
+                .await?
+        }
+    };
     let time_dns = dns_start.elapsed();
-    tracing::info!("resolved {hostname} -> {addr} in {time_dns:?}, connecting via TCP");
+    tracing::info!(host = %hostname, %addr, ?time_dns, "resolved, connecting via TCP");
 
+    progress.set(format!("connecting to {hostname} ({addr})"));
     let connect_start = Instant::now();
-    let stream = tokio::net::TcpStream::connect(addr)
+    let mut stream = tokio::net::TcpStream::connect(addr)
+        .instrument(tracing::info_span!("tcp_connect", %addr))
         .await
         .with_context(|| format!("TCP connect to {hostname} ({addr})"))?;
     let time_connect = connect_start.elapsed();
-    tracing::debug!("TCP established in {time_connect:?}");
+    tracing::debug!(?time_connect, "TCP established");
+
+    let time_proxy = match &cmd.proxy {
+        Some(proxy) => {
+            let proxy_start = Instant::now();
+            proxy
+                .tunnel(&mut stream, &target.host, target.port)
+                .instrument(tracing::info_span!("proxy", %proxy))
+                .await?;
+            let time_proxy = proxy_start.elapsed();
+            tracing::debug!(%proxy, ?time_proxy, "tunnel established");
+            Some(time_proxy)
+        }
+        None => None,
+    };
 
     let mut connector_builder = if cmd.rpk {
         SslConnector::rpk_builder().context("building RPK SSL connector")?
@@ -37,62 +86,255 @@ pub(super) async fn run(cmd: &Connect, format: Format) -> color_eyre::Result<()>
 
     super::set_curves(&mut connector_builder, cmd.curves())?;
 
+    let alpn = cmd.alpn();
+    if !alpn.is_empty() {
+        connector_builder
+            .set_alpn_protos(&super::alpn_wire_format(&alpn)?)
+            .context("setting ALPN protocols")?;
+    }
+
+    if let Some(keylog) = keylog {
+        super::set_keylog(&mut connector_builder, keylog);
+    }
+
+    let offered = cmd
+        .transcript
+        .then(|| super::offered(&mut connector_builder, cmd.curves_or_default(), &alpn));
+
     let connector = connector_builder.build();
 
     // handle connection failure and print error to user:
     // todo(fisher): fix RPK connections. Are we required to set the raw public key?
+    progress.set(format!("TLS handshake with {hostname}"));
     let tls_start = Instant::now();
     let config = connector
         .configure()
         .context("configuring TLS connection")?;
-    let tls = tokio_boring::connect(config, &hostname, stream)
+    let mut tls = tokio_boring::connect(config, &hostname, stream)
+        .instrument(tracing::info_span!("handshake", host = %hostname, %addr))
         .await
         .with_context(|| format!("TLS handshake with {hostname} ({addr})"))?;
     let time_tls = tls_start.elapsed();
     tracing::debug!(
-        "TLS handshake completed in {time_tls:?}: {:?}, {}",
-        tls.ssl().version_str(),
-        tls.ssl().current_cipher().map(|c| c.name()).unwrap_or("?"),
+        ?time_tls,
+        version = tls.ssl().version_str(),
+        cipher = tls.ssl().current_cipher().map(|c| c.name()).unwrap_or("?"),
+        "TLS handshake completed",
     );
 
     let time = Time {
         dns: time_dns,
+        proxy: time_proxy,
         connect: Some(time_connect),
         tls: time_tls,
+        ..Default::default()
+    };
+
+    let mut tls_connection = Connection::from((Transport::TCP, time, tls.ssl()));
+    tls_connection.dns = resolution;
+    tls_connection.transcript = offered.map(|offered| Transcript::new(offered, tls.ssl()));
+    let http = match cmd.http {
+        Some(method) => Some(
+            http_probe(&mut tls, method, &target)
+                .instrument(tracing::info_span!("http", method = method.as_str()))
+                .await?,
+        ),
+        None => None,
+    };
+    let h2 = match cmd.h2 {
+        true => Some(
+            h2_probe(&mut tls)
+                .instrument(tracing::info_span!("h2"))
+                .await?,
+        ),
+        false => None,
+    };
+    let registry = match cmd.registry {
+        true => Some(
+            registry_probe(&mut tls, &target)
+                .instrument(tracing::info_span!("registry"))
+                .await?,
+        ),
+        false => None,
     };
 
-    let tls_connection = Connection::from((Transport::TCP, time, tls.ssl()));
     if !cmd.rpk {
-        let mut certs = if cmd.chain {
-            let chain = tls.ssl().peer_cert_chain().unwrap();
+        let mut certs = if cmd.wants_chain() {
+            let chain = tls
+                .ssl()
+                .peer_cert_chain()
+                .ok_or_else(|| eyre!("{hostname} sent no certificate chain"))?;
             chain
                 .into_iter()
                 .map(ToOwned::to_owned)
                 .map(SimpleCert::from)
                 .collect()
         } else {
-            vec![SimpleCert::from(tls.ssl().peer_certificate().unwrap())]
+            let cert = tls
+                .ssl()
+                .peer_certificate()
+                .ok_or_else(|| eyre!("{hostname} sent no certificate"))?;
+            vec![SimpleCert::from(cert)]
         };
 
         if let Some(cert) = certs.first_mut() {
             cert.apply_verify_result(tls.ssl().verify_result());
         }
 
-        if cmd.no_cert {
-            certs.clear();
-        }
+        let mut connection = ConnectionWithCerts {
+            tls: tls_connection,
+            certs,
+            http,
+            h2,
+            registry,
+            ..Default::default()
+        };
+        cmd.finish(&mut connection).await?;
 
+        drop(progress);
         // todo: combine into a single function / output struct
-        print_tls_connection_with_certs(
-            ConnectionWithCerts {
-                tls: tls_connection,
-                certs,
-            },
-            format,
-        )?;
+        print_tls_connection_with_certs(connection, format, options)?;
     } else {
-        println!("Connected to {}", hostname);
+        drop(progress);
+        // stdout is only for data with `--json` and `--pem`
+        match format {
+            Format::Text => println!("Connected to {hostname}"),
+            _ => progress::status(format!("Connected to {hostname}")),
+        }
     }
 
     Ok(())
 }
+
+/// Send `method` over the established connection, for `--http`.
+async fn http_probe(
+    tls: &mut SslStream<TcpStream>,
+    method: HttpMethod,
+    target: &Target,
+) -> color_eyre::Result<HttpProbe> {
+    let alpn = http1_alpn(tls, "--http")?;
+    let response = http::request(tls, method.as_str(), "/", &host_header(target)).await?;
+
+    Ok(HttpProbe {
+        method: method.as_str().to_string(),
+        alpn,
+        version: response.version.clone(),
+        status: response.status,
+        strict_transport_security: response
+            .header("strict-transport-security")
+            .map(str::to_string),
+        expect_ct: response.header("expect-ct").map(str::to_string),
+        alt_svc: response.header("alt-svc").map(str::to_string),
+    })
+}
+
+/// Send `GET /v2/` over the established connection, for `--registry`.
+async fn registry_probe(
+    tls: &mut SslStream<TcpStream>,
+    target: &Target,
+) -> color_eyre::Result<RegistryProbe> {
+    http1_alpn(tls, "--registry")?;
+    let response = http::request(tls, "GET", "/v2/", &host_header(target)).await?;
+    Ok(RegistryProbe::new(&response))
+}
+
+/// The protocol the server selected with ALPN, failing unless it's HTTP/1.1
+/// or none, which is all `flag` speaks.
+fn http1_alpn(tls: &SslStream<TcpStream>, flag: &str) -> color_eyre::Result<Option<String>> {
+    let alpn = tls
+        .ssl()
+        .selected_alpn_protocol()
+        .map(|protocol| String::from_utf8_lossy(protocol).into_owned());
+    if let Some(protocol) = alpn.as_deref().filter(|&protocol| protocol != "http/1.1") {
+        bail!("the server selected {protocol:?} with ALPN, but {flag} only speaks HTTP/1.1");
+    }
+    Ok(alpn)
+}
+
+/// The `Host` header for `target`, without the port when it's the default.
+fn host_header(target: &Target) -> String {
+    let authority = target.to_string();
+    match authority.strip_suffix(":443") {
+        Some(host) => host.to_string(),
+        None => authority,
+    }
+}
+
+/// Complete the HTTP/2 connection preface, for `--h2`.
+async fn h2_probe(tls: &mut SslStream<TcpStream>) -> color_eyre::Result<H2Probe> {
+    let alpn = match tls.ssl().selected_alpn_protocol() {
+        Some(b"h2") => "h2".to_string(),
+        Some(protocol) => bail!(
+            "the server selected {:?} with ALPN, not h2",
+            String::from_utf8_lossy(protocol)
+        ),
+        None => bail!("the server selected no protocol with ALPN, so it doesn't speak h2 here"),
+    };
+    let settings = h2::handshake(tls)
+        .await
+        .context("HTTP/2 connection preface")?;
+    Ok(H2Probe { alpn, settings })
+}
+
+/// Connect to `target` and return the leaf cert it sends, with its verify
+/// result, for commands that only need the cert.
+#[tracing::instrument(skip(resolver), fields(%target))]
+pub(crate) async fn fetch_leaf(
+    target: &Target,
+    resolver: &Resolver,
+) -> color_eyre::Result<SimpleCert> {
+    let tls = handshake(target, resolver).await?;
+    let cert = tls
+        .ssl()
+        .peer_certificate()
+        .ok_or_else(|| eyre!("{target} sent no certificate"))?;
+    let mut cert = SimpleCert::from(cert);
+    cert.apply_verify_result(tls.ssl().verify_result());
+    Ok(cert)
+}
+
+/// Connect to `target` and return the certs it sends, as sent, with the
+/// leaf's verify result.
+#[tracing::instrument(skip(resolver), fields(%target))]
+pub(crate) async fn fetch_chain(
+    target: &Target,
+    resolver: &Resolver,
+) -> color_eyre::Result<Vec<SimpleCert>> {
+    let tls = handshake(target, resolver).await?;
+    let mut certs: Vec<SimpleCert> = tls
+        .ssl()
+        .peer_cert_chain()
+        .ok_or_else(|| eyre!("{target} sent no certificate chain"))?
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .map(SimpleCert::from)
+        .collect();
+    if let Some(leaf) = certs.first_mut() {
+        leaf.apply_verify_result(tls.ssl().verify_result());
+    }
+    Ok(certs)
+}
+
+/// Complete a handshake with `target` with the default curves, without
+/// verifying the chain.
+async fn handshake(
+    target: &Target,
+    resolver: &Resolver,
+) -> color_eyre::Result<SslStream<TcpStream>> {
+    let (addr, _) = target.resolve(resolver).await?;
+    let stream = tokio::net::TcpStream::connect(addr)
+        .await
+        .with_context(|| format!("TCP connect to {target} ({addr})"))?;
+
+    let mut builder =
+        SslConnector::builder(SslMethod::tls_client()).context("building SSL connector")?;
+    builder.set_verify(SslVerifyMode::NONE);
+    super::set_curves(&mut builder, None)?;
+    let config = builder
+        .build()
+        .configure()
+        .context("configuring TLS connection")?;
+    tokio_boring::connect(config, &target.host, stream)
+        .await
+        .with_context(|| format!("TLS handshake with {target} ({addr})"))
+}
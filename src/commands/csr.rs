@@ -0,0 +1,103 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::{bail, Context, Result};
+
+use crate::{
+    components::csr::{print_verified_csrs, VerifiedCsr},
+    pem::{parse_pems, ParsedPem},
+    x509::SimpleCsr,
+};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Work with certificate signing requests.
+#[derive(Clone, Debug, Parser)]
+pub struct Csr {
+    #[command(subcommand)]
+    pub command: CsrCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum CsrCommand {
+    Verify(Verify),
+}
+
+/// Check CSRs before submitting them to a CA: the self-signature, the key
+/// strength and signature hash, and the requested extensions.
+///
+/// Fails when a CSR has an invalid signature or a key public CAs reject, so
+/// it can gate CSR submission in CI.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct Verify {
+    /// File with the PEM encoded CSRs. Defaults to `stdin`.
+    pub file: Option<PathBuf>,
+
+    /// Also fail on warnings, e.g. a SHA-1 signature.
+    #[arg(long)]
+    pub deny_warnings: bool,
+}
+
+impl CommandExt for Csr {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        match self.command {
+            CsrCommand::Verify(verify) => verify.run(format, options).await,
+        }
+    }
+}
+
+impl CommandExt for Verify {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let data = match &self.file {
+            Some(path) => fs::read(path).with_context(|| format!("Reading {}", path.display()))?,
+            None => {
+                if io::stdin().is_terminal() {
+                    bail!("provide a CSR file or pipe one into stdin");
+                }
+
+                let mut data = Vec::new();
+                io::stdin()
+                    .read_to_end(&mut data)
+                    .context("Reading stdin")?;
+                data
+            }
+        };
+
+        let mut csrs = Vec::new();
+        for pem in parse_pems(&data) {
+            match pem {
+                Ok(pem) => csrs.extend(pem.into_parsed_pem().into_cert_req()),
+                Err(err) => tracing::warn!("skipping PEM: {err:#}"),
+            }
+        }
+        if csrs.is_empty() {
+            bail!("no CSR found in the input");
+        }
+
+        let verified: Vec<VerifiedCsr> = csrs
+            .into_iter()
+            .map(|csr| {
+                let csr = SimpleCsr::from(csr);
+                VerifiedCsr {
+                    subject: csr.subject.name.clone(),
+                    check: csr.check(),
+                    csr,
+                }
+            })
+            .collect();
+        let failed = verified
+            .iter()
+            .filter(|verified| !verified.check.passed(self.deny_warnings))
+            .count();
+        let total = verified.len();
+
+        print_verified_csrs(verified, format, &options)?;
+
+        if failed > 0 {
+            bail!("{failed} of {total} CSRs failed verification");
+        }
+        Ok(())
+    }
+}
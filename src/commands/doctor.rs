@@ -0,0 +1,429 @@
+use std::time::Duration;
+
+use boring::ssl::{SslConnector, SslMethod, SslVerifyMode, StatusType};
+use clap::Parser;
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use jiff::Timestamp;
+use tokio::net::TcpStream;
+use url::Url;
+
+use crate::{
+    chain::{self, ChainIssue},
+    components::{
+        doctor::{print_doctor, Check, DoctorReport},
+        expiry::{ExpiryEntry, Severity},
+    },
+    der,
+    dns::Resolver,
+    http,
+    verifier::{self, Verifier},
+    x509::SimpleCert,
+};
+
+use super::{connect::Target, CommandExt, DnsArgs, Format, RenderOptions};
+
+/// How long to wait for the TCP connection and the TLS handshake.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The checks, in the order they run.
+const CHECKS: [&str; 9] = [
+    "dns",
+    "tcp",
+    "tls",
+    "hostname",
+    "chain",
+    "ocsp stapling",
+    "expiration",
+    "hsts",
+    "https redirect",
+];
+
+/// Diagnose an HTTPS endpoint end to end: DNS, TCP, the TLS handshake, the
+/// cert's names, chain and expiration, OCSP stapling, and the HSTS header
+/// and redirect to HTTPS of the web server. Prints a scorecard with a hint
+/// for everything that needs fixing.
+///
+/// Fails when any check fails.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct Doctor {
+    /// The endpoint, as `connect` takes it: `example.com`,
+    /// `https://example.com` or `example.com:8443`.
+    host: String,
+
+    /// The port to connect to. Defaults to the port in the host, or 443.
+    #[arg(long, short)]
+    port: Option<u16>,
+
+    #[command(flatten)]
+    dns: DnsArgs,
+}
+
+impl CommandExt for Doctor {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let target = Target::parse(&self.host, self.port)?;
+        let checks = diagnose(&target, &self.dns.resolver(), options.now.timestamp()).await;
+        let report = DoctorReport {
+            heading: "doctor",
+            target: target.to_string(),
+            checks: with_skipped(checks),
+        };
+
+        let failed = report.failed();
+        print_doctor(report, format, &options)?;
+        if failed > 0 {
+            bail!("{failed} of the checks failed");
+        }
+        Ok(())
+    }
+}
+
+/// Run the checks until one fails in a way that leaves nothing to check
+/// after it.
+async fn diagnose(target: &Target, resolver: &Resolver, now: Timestamp) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let host = &target.host;
+
+    let addr = match target.resolve(resolver).await {
+        Ok((addr, Some(_))) => {
+            checks.push(Check::pass(
+                "dns",
+                format!("{host} resolves to {}", addr.ip()),
+            ));
+            addr
+        }
+        Ok((addr, None)) => {
+            checks.push(Check::pass("dns", "an IP address, nothing to resolve"));
+            addr
+        }
+        Err(err) => {
+            checks.push(Check::fail(
+                "dns",
+                format!("{err:#}"),
+                "check the name is spelled right and has A or AAAA records",
+            ));
+            return checks;
+        }
+    };
+
+    let stream = match tokio::time::timeout(TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(stream)) => {
+            checks.push(Check::pass("tcp", format!("connected to {addr}")));
+            stream
+        }
+        Ok(Err(err)) => {
+            checks.push(Check::fail(
+                "tcp",
+                format!("connecting to {addr}: {err}"),
+                format!(
+                    "nothing is listening on port {}, or a firewall rejects the connection",
+                    target.port
+                ),
+            ));
+            return checks;
+        }
+        Err(_) => {
+            checks.push(Check::fail(
+                "tcp",
+                format!("connecting to {addr} timed out"),
+                "the host may be down, or a firewall drops the connection",
+            ));
+            return checks;
+        }
+    };
+
+    let (certs, stapled) = match handshake(target, stream).await {
+        Ok((version, cipher, certs, stapled)) => {
+            let detail = format!("{version} with {cipher}");
+            checks.push(if matches!(version.as_str(), "TLSv1.2" | "TLSv1.3") {
+                Check::pass("tls", detail)
+            } else {
+                Check::warn(
+                    "tls",
+                    detail,
+                    "turn off TLS 1.1 and older, clients no longer accept them",
+                )
+            });
+            (certs, stapled)
+        }
+        Err(err) => {
+            checks.push(Check::fail(
+                "tls",
+                format!("{err:#}"),
+                "the server may not speak TLS on this port, run `pls connect -vvv` for details",
+            ));
+            return checks;
+        }
+    };
+
+    let leaf = &certs[0];
+    checks.push(if leaf.matches_host(host) {
+        Check::pass("hostname", format!("the cert covers {host}"))
+    } else {
+        let names = leaf.subject.sans.dns.join(", ");
+        Check::fail(
+            "hostname",
+            format!("the cert is for {names}"),
+            format!("get a cert with {host} in its SANs"),
+        )
+    });
+
+    checks.push(chain_check(host, certs.clone()));
+
+    let responders =
+        der::ocsp_responders(&leaf._cert.to_der().unwrap_or_default()).unwrap_or_default();
+    checks.push(match (stapled, responders.is_empty()) {
+        (true, _) => Check::pass("ocsp stapling", "the server staples an OCSP response"),
+        (false, true) => Check::pass(
+            "ocsp stapling",
+            "the cert has no OCSP responder to staple from",
+        ),
+        (false, false) => Check::warn(
+            "ocsp stapling",
+            "no OCSP response stapled",
+            "turn on OCSP stapling, so clients don't have to ask the CA about the cert",
+        ),
+    });
+
+    checks.push(expiration_check(&ExpiryEntry::new(
+        &target.to_string(),
+        leaf,
+        now,
+    )));
+
+    checks.push(hsts_check(target).await);
+    if target.port == 443 {
+        checks.push(redirect_check(host).await);
+    } else {
+        checks.push(Check::skip("https redirect", "not the default HTTPS port"));
+    }
+
+    checks
+}
+
+/// Whether the leaf of `expiry` has expired or expires soon.
+pub(crate) fn expiration_check(expiry: &ExpiryEntry) -> Check {
+    let until = expiry
+        .not_after
+        .map(|not_after| not_after.strftime("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    match expiry.severity {
+        Severity::Expired => Check::fail(
+            "expiration",
+            format!("expired on {until}"),
+            "renew the cert",
+        ),
+        Severity::Critical | Severity::Warning => Check::warn(
+            "expiration",
+            format!(
+                "expires on {until}, in {} days",
+                expiry.days_left.unwrap_or_default()
+            ),
+            "renew the cert soon, and check automatic renewal works",
+        ),
+        Severity::Ok => Check::pass("expiration", format!("valid until {until}")),
+        Severity::Unknown => Check::warn(
+            "expiration",
+            "the cert has no usable expiry",
+            "reissue the cert",
+        ),
+    }
+}
+
+/// Complete the handshake, returning the version, cipher, the certs the
+/// server sent and whether it stapled an OCSP response.
+async fn handshake(
+    target: &Target,
+    stream: TcpStream,
+) -> Result<(String, String, Vec<SimpleCert>, bool)> {
+    let mut builder =
+        SslConnector::builder(SslMethod::tls_client()).context("building SSL connector")?;
+    builder.set_verify(SslVerifyMode::NONE);
+    super::connect::set_curves(&mut builder, None)?;
+    let mut config = builder
+        .build()
+        .configure()
+        .context("configuring TLS connection")?;
+    config
+        .set_status_type(StatusType::OCSP)
+        .context("asking for OCSP stapling")?;
+
+    let tls = tokio::time::timeout(TIMEOUT, tokio_boring::connect(config, &target.host, stream))
+        .await
+        .map_err(|_| eyre!("TLS handshake with {target} timed out"))?
+        .with_context(|| format!("TLS handshake with {target}"))?;
+
+    let ssl = tls.ssl();
+    let mut certs: Vec<SimpleCert> = ssl
+        .peer_cert_chain()
+        .ok_or_else(|| eyre!("{target} sent no certificate chain"))?
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .map(SimpleCert::from)
+        .collect();
+    let Some(leaf) = certs.first_mut() else {
+        bail!("{target} sent no certificate");
+    };
+    leaf.apply_verify_result(ssl.verify_result());
+
+    let stapled = ssl
+        .ocsp_status()
+        .is_some_and(|response| !response.is_empty());
+    let cipher = ssl
+        .current_cipher()
+        .map_or("an unknown cipher", |cipher| cipher.name());
+    Ok((
+        ssl.version_str().to_string(),
+        cipher.to_string(),
+        certs,
+        stapled,
+    ))
+}
+
+/// Whether the chain is complete and trusted by the bundled Mozilla roots.
+fn chain_check(host: &str, certs: Vec<SimpleCert>) -> Check {
+    let (certs, issues) = chain::order(certs);
+    let missing = issues
+        .iter()
+        .any(|issue| matches!(issue, ChainIssue::MissingIntermediate { .. }));
+    let hint = if missing {
+        "serve the intermediates along with the cert, e.g. fullchain.pem rather than cert.pem"
+            .to_string()
+    } else {
+        format!("see the chain with `pls connect {host} --chain`")
+    };
+
+    match verifier::verify(Verifier::Bundled, host, &certs) {
+        Ok(Some(verification)) if !verification.valid => Check::fail(
+            "chain",
+            verification
+                .error
+                .unwrap_or_else(|| "untrusted".to_string()),
+            hint,
+        ),
+        Ok(_) if !issues.is_empty() => {
+            let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+            Check::warn("chain", issues.join(", "), hint)
+        }
+        Ok(_) => Check::pass(
+            "chain",
+            format!("{} certs, trusted by the Mozilla roots", certs.len()),
+        ),
+        Err(err) => Check::skip("chain", format!("{err:#}")),
+    }
+}
+
+/// A year, the least `max-age` HSTS preload lists accept.
+const HSTS_MIN_MAX_AGE: u64 = 365 * 24 * 60 * 60;
+
+/// Whether the web server sends a long-lived `Strict-Transport-Security`
+/// header.
+async fn hsts_check(target: &Target) -> Check {
+    let Ok(url) = Url::parse(&format!("https://{target}/")) else {
+        return Check::skip("hsts", "no URL for the target");
+    };
+    let response = match http::get_once(&url).await {
+        Ok(response) => response,
+        Err(err) => return Check::skip("hsts", format!("{err:#}")),
+    };
+
+    let Some(hsts) = response.header("strict-transport-security") else {
+        return Check::warn(
+            "hsts",
+            "no Strict-Transport-Security header",
+            format!("send `Strict-Transport-Security: max-age={HSTS_MIN_MAX_AGE}` so browsers only use HTTPS"),
+        );
+    };
+    let max_age = hsts
+        .split(';')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|age| age.trim_matches('"').parse::<u64>().ok());
+    match max_age {
+        Some(age) if age >= HSTS_MIN_MAX_AGE => Check::pass("hsts", hsts.to_string()),
+        _ => Check::warn(
+            "hsts",
+            hsts.to_string(),
+            format!("raise max-age to at least {HSTS_MIN_MAX_AGE} (a year)"),
+        ),
+    }
+}
+
+/// Whether plain HTTP on port 80 redirects to HTTPS.
+async fn redirect_check(host: &str) -> Check {
+    let Ok(url) = Url::parse(&format!("http://{host}/")) else {
+        return Check::skip("https redirect", "no URL for the host");
+    };
+    match http::get_once(&url).await {
+        Ok(response) if matches!(response.status, 301 | 302 | 303 | 307 | 308) => {
+            match response.header("location") {
+                Some(location) if location.starts_with("https://") => Check::pass(
+                    "https redirect",
+                    format!("HTTP {} to {location}", response.status),
+                ),
+                location => Check::warn(
+                    "https redirect",
+                    format!(
+                        "HTTP {} to {}",
+                        response.status,
+                        location.unwrap_or("nowhere")
+                    ),
+                    "redirect plain HTTP straight to https://",
+                ),
+            }
+        }
+        Ok(response) => Check::warn(
+            "https redirect",
+            format!("plain HTTP is served with HTTP {}", response.status),
+            "redirect plain HTTP to https:// with a 301",
+        ),
+        Err(err) if refused(&err) => {
+            Check::pass("https redirect", "nothing listens for plain HTTP")
+        }
+        Err(err) => Check::skip("https redirect", format!("{err:#}")),
+    }
+}
+
+fn refused(err: &color_eyre::Report) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|err| err.kind() == std::io::ErrorKind::ConnectionRefused)
+}
+
+/// `checks` with the checks that didn't run after a failure marked as
+/// skipped.
+fn with_skipped(mut checks: Vec<Check>) -> Vec<Check> {
+    for name in CHECKS.iter().skip(checks.len()) {
+        checks.push(Check::skip(name, "an earlier check failed"));
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::doctor::Status;
+
+    #[test]
+    fn skips_after_a_failure() {
+        let checks = with_skipped(vec![
+            Check::pass("dns", "example.com resolves to 192.0.2.1"),
+            Check::fail("tcp", "connection refused", "start the server"),
+        ]);
+        let names: Vec<_> = checks.iter().map(|check| check.name).collect();
+        assert_eq!(names, CHECKS);
+        assert!(checks[2..].iter().all(|check| check.status == Status::Skip));
+    }
+
+    #[test]
+    fn chain_without_its_root() {
+        let certs: Vec<SimpleCert> =
+            boring::x509::X509::stack_from_pem(include_bytes!("../../test-data/certs/chain.pem"))
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect();
+        // lan.fish expired in 2025, so the bundled roots reject it
+        let check = chain_check("lan.fish", certs);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.hint.is_some());
+    }
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use boring::x509::X509;
+use clap::Parser;
+use color_eyre::eyre::{bail, eyre, Context, Result};
+
+use crate::{
+    components::expiry::{self, print_expiry, ExpiryEntry, Status},
+    jobs::Outcome,
+    pem::{parse_pems, ParsedPem},
+    pkcs7::Pkcs7,
+    progress::Progress,
+    x509::SimpleCert,
+};
+
+use super::{
+    connect::{fetch_leaf, Target},
+    is_input_file, parse_duration, CommandExt, DnsArgs, Format, JobArgs, RenderOptions,
+};
+
+/// Show when certs expire, soonest first: every cert in the given files and
+/// the cert each given host serves. Certs expiring within 7 days are
+/// critical, and within 30 days need renewing soon.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct Expiry {
+    /// Files with certs (PEM, DER or PKCS#7), or hosts to get the cert from,
+    /// as `connect` takes them: `example.com`, `example.com:8443`,
+    /// `ldaps://ldap.example.com`, ... Arguments that are existing files,
+    /// FIFOs or process substitutions like `<(...)` are read as files.
+    #[arg(required = true, value_name = "FILE|HOST")]
+    pub inputs: Vec<String>,
+
+    /// Only show the certs that expire within this long from now, e.g.
+    /// `30d`, `2w` or `12h`. Expired certs and inputs that failed are always
+    /// shown.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub within: Option<Duration>,
+
+    #[command(flatten)]
+    dns: DnsArgs,
+
+    #[command(flatten)]
+    jobs: JobArgs,
+}
+
+impl CommandExt for Expiry {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let now = options.now.timestamp();
+        let mut entries = Vec::new();
+
+        let mut hosts = Vec::new();
+        for input in &self.inputs {
+            let path = Path::new(input);
+            if is_input_file(path) {
+                entries.extend(match cert_file(path) {
+                    Ok(certs) => certs
+                        .iter()
+                        .map(|cert| ExpiryEntry::new(input, cert, now))
+                        .collect(),
+                    Err(err) => vec![ExpiryEntry::failed(input, format!("{err:#}"))],
+                });
+                continue;
+            }
+
+            match Target::parse(input, None) {
+                Ok(target) => hosts.push((input.clone(), target)),
+                Err(err) => entries.push(ExpiryEntry::failed(input, format!("{err:#}"))),
+            }
+        }
+
+        // hosts are connected to at the same time, each with its own timeout
+        let progress = match hosts.len() {
+            0 => Progress::new(),
+            1 => Progress::spinner(format!("checking {}", hosts[0].0)),
+            count => Progress::spinner(format!("checking {count} hosts")),
+        };
+        let outcomes = self
+            .jobs
+            .runner()
+            .run(
+                hosts,
+                |(input, target)| {
+                    let (input, target) = (input.clone(), target.clone());
+                    let resolver = self.dns.resolver();
+                    async move {
+                        match fetch_leaf(&target, &resolver).await {
+                            Ok(cert) => ExpiryEntry::new(&target.to_string(), &cert, now),
+                            Err(err) => ExpiryEntry::failed(&input, format!("{err:#}")),
+                        }
+                    }
+                },
+                |entry: &ExpiryEntry| entry.error.is_some(),
+            )
+            .await?;
+        drop(progress);
+        for ((input, target), outcome) in outcomes {
+            entries.push(match outcome {
+                Outcome::Done(entry) => entry,
+                Outcome::TimedOut(timeout) => ExpiryEntry::timed_out(&target.to_string(), timeout),
+                Outcome::Skipped => ExpiryEntry::skipped(&input),
+            });
+        }
+
+        let failed = entries
+            .iter()
+            .find(|entry| entry.status != Status::Ok)
+            .map(|entry| entry.input.clone());
+
+        if let Some(within) = self.within {
+            let seconds = within.as_secs() as i64;
+            entries.retain(|entry| entry.error.is_some() || entry.expires_within(seconds, now));
+        }
+        expiry::sort(&mut entries);
+        print_expiry(entries, format, &options)?;
+
+        match failed {
+            Some(input) if self.jobs.fail_fast => bail!("{input} failed, stopping (--fail-fast)"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Every cert in the file at `path`.
+pub(crate) fn cert_file(path: &Path) -> Result<Vec<SimpleCert>> {
+    let data = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+    certs_in(&data, &path.display().to_string())
+}
+
+/// Every cert in `data`: PEMs, a DER cert or a PKCS#7 bundle. `name` is
+/// where it came from, for warnings.
+pub(crate) fn certs_in(data: &[u8], name: &str) -> Result<Vec<SimpleCert>> {
+    if Pkcs7::sniff(data) {
+        let bundle = Pkcs7::from_der(data)?;
+        return Ok(bundle.certs.into_iter().map(SimpleCert::from).collect());
+    }
+    if data.first() == Some(&0x30) {
+        if let Ok(cert) = X509::from_der(data) {
+            return Ok(vec![SimpleCert::from(cert)]);
+        }
+    }
+
+    let mut certs = Vec::new();
+    for pem in parse_pems(data) {
+        match pem.map(|pem| pem.into_parsed_pem()) {
+            Ok(ParsedPem::Cert(cert)) => certs.push(SimpleCert::from(cert)),
+            Ok(ParsedPem::Pkcs7(bundle)) => {
+                certs.extend(bundle.certs.into_iter().map(SimpleCert::from));
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!("skipping PEM in {name}: {err:#}"),
+        }
+    }
+    if certs.is_empty() {
+        return Err(eyre!("no certs found"));
+    }
+    Ok(certs)
+}
@@ -0,0 +1,22 @@
+use clap::Parser;
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{components::explain_expr::print_expr, filter::Expr};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Show how an expression for `find -e` or `parse --fail-if` is read: the
+/// expression with its implied parentheses, as a tree, and what each value
+/// was taken to mean. Fails when it doesn't parse.
+#[derive(Clone, Debug, Parser)]
+pub struct ExplainExpr {
+    /// The expression, e.g. `'key = rsa and (bits < 2048 or expires < 30d)'`.
+    pub expr: String,
+}
+
+impl CommandExt for ExplainExpr {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let expr = Expr::parse(&self.expr).map_err(|err| eyre!("{err}"))?;
+        print_expr(&expr, format, &options)
+    }
+}
@@ -0,0 +1,261 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use boring::{pkcs12::Pkcs12, x509::X509};
+use clap::Parser;
+use color_eyre::eyre::{bail, Result};
+use memchr::memmem;
+
+use crate::{
+    components::find::{print_found, print_found_list, FoundCert},
+    filter::Expr,
+    pem::{parse_pems, ParsedPem, Source},
+    pkcs7::Pkcs7,
+    progress::Progress,
+    x509::SimpleCert,
+};
+
+use super::{is_input_file, CommandExt, Format, RenderOptions};
+
+/// Search files and directories for certs, like a cert-aware grep: every
+/// PEM, DER, PKCS#7 and (passwordless) PKCS#12 file is read, and the certs
+/// that pass all the filters are listed with where they are, as they're
+/// found. Fails when no cert matches.
+///
+/// Expressions are filters, `FIELD OP VALUE`, combined with `and`, `or`,
+/// `not` and parentheses:
+///
+/// ```text
+/// san contains internal.corp    subject matches '^CN=.*\.corp$'
+/// expires < 60d                 issued > 2025-01-01
+/// key = rsa                     bits < 2048
+/// ca                            self-signed = false
+/// key = rsa and (bits < 2048 or expires < 30d)
+/// ```
+///
+/// Text fields are subject, issuer, san, serial, sha256, key and signature;
+/// times are expires and issued, with durations counted from now (negative
+/// for the past); ca, self-signed and expired are flags. `pls explain-expr`
+/// shows how an expression is read.
+#[derive(Clone, Debug, Parser)]
+pub struct Find {
+    /// Files and directories to search. Directories are searched
+    /// recursively, without following symlinks to directories.
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Only list the certs that pass this expression. Repeat it to require
+    /// all of them, e.g. `-e 'san contains internal.corp' -e 'expires < 60d'`.
+    #[arg(short = 'e', long = "expr", value_name = "EXPR", value_parser = Expr::parse)]
+    pub exprs: Vec<Expr>,
+}
+
+impl CommandExt for Find {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let now = options.now.timestamp();
+        let mut found = Vec::new();
+        let mut matched = 0;
+        let mut read = 0;
+        let progress = Progress::new();
+
+        let mut on_file = |file: &Path| {
+            read += 1;
+            progress.set(format!(
+                "{read} files, {matched} matches: {}",
+                file.display()
+            ));
+            for cert in certs_in(file) {
+                if !self.exprs.iter().all(|expr| expr.matches(&cert, now)) {
+                    continue;
+                }
+                matched += 1;
+                // a JSON list has to be printed whole, the rest as found
+                match format {
+                    Format::Json | Format::JsonCompact => found.push(FoundCert::new(&cert)),
+                    _ => {
+                        progress.clear();
+                        print_found(&cert, format, &options);
+                    }
+                }
+            }
+        };
+        for path in &self.paths {
+            if path.is_dir() {
+                walk(path, &mut on_file);
+            } else if is_input_file(path) {
+                // given by name, so FIFOs and `<(...)` are read too, unlike
+                // in a directory, where reading one could block forever
+                on_file(path);
+            } else {
+                bail!("{} doesn't exist", path.display());
+            }
+        }
+
+        drop(progress);
+        if format.is_json() && format != Format::Ndjson {
+            print_found_list(&found, format)?;
+        }
+        if matched == 0 {
+            bail!("no certs matched");
+        }
+        Ok(())
+    }
+}
+
+/// Call `on_file` with every file under `path`, in name order.
+fn walk(path: &Path, on_file: &mut impl FnMut(&Path)) {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        tracing::warn!("skipping {}: can't read it", path.display());
+        return;
+    };
+    // symlinks to files are read, symlinks to directories could loop
+    if metadata.is_file() || (metadata.is_symlink() && path.is_file()) {
+        on_file(path);
+        return;
+    }
+    if !metadata.is_dir() {
+        return;
+    }
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            tracing::warn!("skipping {}: {err}", path.display());
+            return;
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    paths.sort();
+    for path in paths {
+        walk(&path, on_file);
+    }
+}
+
+/// Every cert in the file at `path`, with where it is. Files that aren't
+/// certs are skipped quietly.
+fn certs_in(path: &Path) -> Vec<SimpleCert> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) => {
+            tracing::warn!("skipping {}: {err}", path.display());
+            return Vec::new();
+        }
+    };
+    let file = path.display().to_string();
+    let whole = |certs: Vec<X509>| -> Vec<SimpleCert> {
+        let source = Source {
+            file: file.clone(),
+            byte_start: 0,
+            byte_end: data.len(),
+            line_start: None,
+            line_end: None,
+        };
+        let mut certs: Vec<SimpleCert> = certs.into_iter().map(SimpleCert::from).collect();
+        for cert in &mut certs {
+            cert.source = Some(source.clone());
+        }
+        certs
+    };
+
+    if Pkcs7::sniff(&data) {
+        return match Pkcs7::from_der(&data) {
+            Ok(bundle) => whole(bundle.certs),
+            Err(_) => Vec::new(),
+        };
+    }
+    if data.first() == Some(&0x30) {
+        if let Ok(cert) = X509::from_der(&data) {
+            return whole(vec![cert]);
+        }
+        if let Ok(pkcs12) = Pkcs12::from_der(&data) {
+            return match pkcs12.parse("") {
+                Ok(parsed) => {
+                    let mut certs = vec![parsed.cert];
+                    certs.extend(parsed.chain.into_iter().flatten());
+                    whole(certs)
+                }
+                Err(_) => {
+                    tracing::warn!("skipping {file}: the PKCS#12 file has a password");
+                    Vec::new()
+                }
+            };
+        }
+    }
+    if memmem::find(&data, b"-----BEGIN").is_none() {
+        return Vec::new();
+    }
+
+    let mut certs = Vec::new();
+    for pem in parse_pems(&data) {
+        let pem = match pem {
+            Ok(pem) => pem,
+            Err(err) => {
+                tracing::debug!("skipping PEM in {file}: {err:#}");
+                continue;
+            }
+        };
+        let source = pem.source(&file);
+        let found: Vec<X509> = match pem.into_parsed_pem() {
+            ParsedPem::Cert(cert) => vec![cert],
+            ParsedPem::Pkcs7(bundle) => bundle.certs,
+            _ => continue,
+        };
+        for cert in found {
+            let mut cert = SimpleCert::from(cert);
+            cert.source = Some(source.clone());
+            certs.push(cert);
+        }
+    }
+    certs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_certs_in_a_tree() {
+        let dir = std::env::temp_dir().join(format!("pls-find-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(
+            dir.join("nested/chain.pem"),
+            include_bytes!("../../test-data/certs/chain.pem"),
+        )
+        .unwrap();
+        let der = X509::from_pem(include_bytes!("../../test-data/certs/lan-fish.pem"))
+            .unwrap()
+            .to_der()
+            .unwrap();
+        fs::write(dir.join("leaf.der"), der).unwrap();
+        fs::write(dir.join("notes.txt"), "not a cert").unwrap();
+
+        let mut certs = Vec::new();
+        walk(&dir, &mut |file| certs.extend(certs_in(file)));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let sources: Vec<String> = certs
+            .iter()
+            .map(|cert| {
+                let source = cert.source.as_ref().unwrap();
+                let file = Path::new(&source.file).strip_prefix(&dir).unwrap();
+                format!("{} {:?}", file.display(), source.line_start)
+            })
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                "leaf.der None",
+                "nested/chain.pem Some(1)",
+                "nested/chain.pem Some(22)",
+                "nested/chain.pem Some(39)",
+            ]
+        );
+
+        let expr = Expr::parse("san contains lan.fish").unwrap();
+        let now = jiff::Timestamp::now();
+        let matched = certs.iter().filter(|cert| expr.matches(cert, now));
+        assert_eq!(matched.count(), 2);
+    }
+}
@@ -0,0 +1,374 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::{bail, Context, Result};
+use jiff::Timestamp;
+
+use crate::{
+    chain,
+    components::{
+        doctor::{print_doctor, Check, DoctorReport},
+        expiry::ExpiryEntry,
+        same_key::KeyUserKind,
+    },
+    tls_config::{Site, TlsConfig},
+    verifier,
+    x509::SimpleCert,
+};
+
+use super::{
+    doctor::expiration_check, expiry::cert_file, same_key::key_users, CommandExt, Format,
+    RenderOptions,
+};
+
+/// Inspect the files a piece of software is configured with.
+#[derive(Clone, Debug, Parser)]
+pub struct Inspect {
+    #[command(subcommand)]
+    pub command: InspectCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum InspectCommand {
+    TlsConfig(InspectTlsConfig),
+}
+
+/// Read the TLS directives of an nginx, Apache httpd or HAProxy config and
+/// check the files they point at: the cert parses and hasn't expired, the
+/// key matches it, the chain is complete and covers the server's names. The
+/// allowed protocols and ciphers are checked for old and weak ones.
+///
+/// Fails when any check fails.
+#[derive(Clone, Debug, Parser)]
+pub struct InspectTlsConfig {
+    /// The config file, e.g. `/etc/nginx/sites-enabled/example.conf`.
+    pub config: PathBuf,
+
+    /// The directory relative paths in the config are relative to. Defaults
+    /// to the config's directory; nginx's is its prefix, e.g. `/etc/nginx`.
+    #[arg(long, value_name = "DIR")]
+    pub root: Option<PathBuf>,
+}
+
+impl CommandExt for Inspect {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        match self.command {
+            InspectCommand::TlsConfig(config) => config.run(format, options).await,
+        }
+    }
+}
+
+impl CommandExt for InspectTlsConfig {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let config = fs::read_to_string(&self.config)
+            .with_context(|| format!("Reading {}", self.config.display()))?;
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => self
+                .config
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+        let tls = TlsConfig::parse(&config, &root);
+        if tls.sites.is_empty() {
+            bail!("no certs configured in {}", self.config.display());
+        }
+
+        let now = options.now.timestamp();
+        let mut checks = Vec::new();
+        for site in &tls.sites {
+            checks.extend(check_site(site, now));
+        }
+        checks.push(check_protocols(&tls.protocols));
+        checks.push(check_ciphers(&tls.ciphers));
+
+        let report = DoctorReport {
+            heading: "tls config",
+            target: self.config.display().to_string(),
+            checks,
+        };
+        let failed = report.failed();
+        print_doctor(report, format, &options)?;
+        if failed > 0 {
+            bail!("{failed} of the checks failed");
+        }
+        Ok(())
+    }
+}
+
+/// Check the files one cert directive points at.
+fn check_site(site: &Site, now: Timestamp) -> Vec<Check> {
+    let line = site.line;
+    let cert_path = site.cert.display();
+    let mut certs = match cert_file(&site.cert) {
+        Ok(certs) => certs,
+        Err(err) => {
+            return vec![Check::fail(
+                "cert",
+                format!("line {line}: {cert_path}: {err:#}"),
+                "point the directive at the PEM file with the cert",
+            )]
+        }
+    };
+    let leaf_name = certs[0].subject.name.clone();
+    let mut checks = vec![Check::pass(
+        "cert",
+        format!("line {line}: {cert_path}: {leaf_name}"),
+    )];
+
+    checks.push(check_key(site, &certs[0]));
+
+    if let Some(chain) = &site.chain {
+        match cert_file(chain) {
+            Ok(chain) => certs.extend(chain),
+            Err(err) => checks.push(Check::fail(
+                "chain",
+                format!("{}: {err:#}", chain.display()),
+                "point the directive at the PEM file with the intermediates",
+            )),
+        }
+    }
+    checks.push(check_chain(site, certs.clone()));
+
+    let leaf = &certs[0];
+    let names: Vec<&String> = site
+        .names
+        .iter()
+        // catch-alls, wildcards and regexes aren't hosts to check
+        .filter(|name| !name.starts_with(['_', '*', '~', '.']) && !name.is_empty())
+        .collect();
+    let uncovered: Vec<&str> = names
+        .iter()
+        .filter(|name| !leaf.matches_host(name))
+        .map(|name| name.as_str())
+        .collect();
+    checks.push(match (names.is_empty(), uncovered.is_empty()) {
+        (true, _) => Check::skip("names", "no server names next to the cert"),
+        (false, true) => Check::pass("names", format!("the cert covers {}", join(&names))),
+        (false, false) => Check::fail(
+            "names",
+            format!("the cert doesn't cover {}", uncovered.join(", ")),
+            format!(
+                "get a cert for {}, or fix the server names",
+                uncovered.join(", ")
+            ),
+        ),
+    });
+
+    checks.push(expiration_check(&ExpiryEntry::new(
+        &cert_path.to_string(),
+        leaf,
+        now,
+    )));
+
+    checks
+}
+
+fn join(names: &[&String]) -> String {
+    names
+        .iter()
+        .map(|name| name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether the configured key is the leaf's. Without a key directive, the
+/// key has to be in the cert file, as HAProxy wants it.
+fn check_key(site: &Site, leaf: &SimpleCert) -> Check {
+    let path = site.key.as_deref().unwrap_or(&site.cert);
+    let keys = fs::read(path)
+        .map_err(color_eyre::Report::from)
+        .and_then(|data| key_users(&path.display().to_string(), &data));
+    let keys = match keys {
+        Ok(keys) => keys,
+        Err(err) => {
+            return Check::fail(
+                "key",
+                format!("{}: {err:#}", path.display()),
+                "point the key directive at the PEM file with the private key",
+            )
+        }
+    };
+
+    let private: Vec<_> = keys
+        .iter()
+        .filter(|(_, user)| user.kind == KeyUserKind::PrivateKey)
+        .collect();
+    match private.first() {
+        None => Check::fail(
+            "key",
+            format!("no private key in {}", path.display()),
+            match site.key {
+                Some(_) => "point the key directive at the PEM file with the private key",
+                None => "add the private key to the cert file, or configure it separately",
+            },
+        ),
+        Some((spki, _)) if *spki == leaf.public_key.spki => {
+            Check::pass("key", format!("{} matches the cert", path.display()))
+        }
+        Some(_) => Check::fail(
+            "key",
+            format!("{} is for another cert", path.display()),
+            "configure the key the cert was issued for, `pls same-key` finds it",
+        ),
+    }
+}
+
+/// Whether the chain is in order and completes to a Mozilla root.
+fn check_chain(site: &Site, certs: Vec<SimpleCert>) -> Check {
+    let (certs, issues) = chain::order(certs);
+    let verification = match verifier::verify_chain(&certs) {
+        Ok(verification) => verification,
+        Err(err) => return Check::skip("chain", format!("{err:#}")),
+    };
+
+    match verification.error {
+        Some(error) if error.contains("local issuer") => Check::fail(
+            "chain",
+            format!("{error}: the intermediates are missing"),
+            format!(
+                "append the intermediates to {}, e.g. use fullchain.pem rather than cert.pem",
+                site.cert.display()
+            ),
+        ),
+        // expiry has a check of its own
+        Some(error) if !error.contains("expired") => Check::fail(
+            "chain",
+            error,
+            "check the chain with `pls parse --aia` on the cert file",
+        ),
+        _ if !issues.is_empty() => {
+            let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+            Check::warn(
+                "chain",
+                issues.join(", "),
+                "put the leaf first, each cert followed by its issuer, without the root",
+            )
+        }
+        _ => Check::pass(
+            "chain",
+            format!("{} certs, complete to a Mozilla root", certs.len()),
+        ),
+    }
+}
+
+/// Protocol versions clients and the Baseline Requirements have dropped.
+const OLD_PROTOCOLS: [&str; 3] = ["SSLv3", "TLSv1", "TLSv1.1"];
+
+fn check_protocols(protocols: &[String]) -> Check {
+    if protocols.is_empty() {
+        return Check::skip("protocols", "not configured, the server's defaults apply");
+    }
+
+    let old: Vec<&str> = protocols
+        .iter()
+        .map(String::as_str)
+        .filter(|protocol| OLD_PROTOCOLS.contains(protocol))
+        .collect();
+    match old.is_empty() {
+        true => Check::pass("protocols", protocols.join(", ")),
+        false => Check::warn(
+            "protocols",
+            format!("{} allowed", old.join(", ")),
+            "only allow TLSv1.2 and TLSv1.3",
+        ),
+    }
+}
+
+/// Parts of OpenSSL cipher names and aliases that mean a broken or
+/// unauthenticated cipher.
+const WEAK_CIPHERS: [&str; 11] = [
+    "RC4", "DES", "3DES", "NULL", "ENULL", "ANULL", "EXPORT", "EXP", "MD5", "ADH", "AECDH",
+];
+
+fn check_ciphers(lists: &[String]) -> Check {
+    if lists.is_empty() {
+        return Check::skip("ciphers", "not configured, the server's defaults apply");
+    }
+
+    let mut weak: Vec<&str> = lists
+        .iter()
+        .flat_map(|list| list.split([':', ',', ' ']))
+        // `!RC4` and `-RC4` take ciphers away
+        .filter(|cipher| !cipher.starts_with(['!', '-']))
+        .filter(|cipher| {
+            cipher
+                .trim_start_matches('+')
+                .to_ascii_uppercase()
+                .split('-')
+                .any(|part| WEAK_CIPHERS.contains(&part))
+        })
+        .collect();
+    weak.dedup();
+    match weak.is_empty() {
+        true => Check::pass("ciphers", lists.join(" ")),
+        false => Check::warn(
+            "ciphers",
+            format!("weak ciphers allowed: {}", weak.join(", ")),
+            "use Mozilla's intermediate cipher list, https://ssl-config.mozilla.org",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::doctor::Status;
+
+    #[test]
+    fn old_protocols_and_weak_ciphers() {
+        let protocols = ["TLSv1.2".to_string(), "TLSv1.3".to_string()];
+        assert_eq!(check_protocols(&protocols).status, Status::Pass);
+        let check = check_protocols(&["TLSv1".to_string(), "TLSv1.2".to_string()]);
+        assert_eq!(check.status, Status::Warn);
+        assert_eq!(check.detail, "TLSv1 allowed");
+
+        let ciphers = ["HIGH:!aNULL:!MD5:!RC4".to_string()];
+        assert_eq!(check_ciphers(&ciphers).status, Status::Pass);
+        let check =
+            check_ciphers(&["ECDHE-RSA-AES128-GCM-SHA256:DES-CBC3-SHA:RC4-SHA".to_string()]);
+        assert_eq!(check.status, Status::Warn);
+        assert_eq!(check.detail, "weak ciphers allowed: DES-CBC3-SHA, RC4-SHA");
+    }
+
+    #[test]
+    fn key_and_chain() {
+        let dir = std::env::temp_dir().join(format!("pls-tls-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cert = dir.join("cert.pem");
+        let key = dir.join("key.pem");
+        fs::write(
+            &cert,
+            include_bytes!("../../test-data/certs/rsa-self-signed.pem"),
+        )
+        .unwrap();
+        fs::write(
+            &key,
+            include_bytes!("../../test-data/private-keys/begin-rsa-private-key.pem"),
+        )
+        .unwrap();
+
+        let site = Site {
+            line: 1,
+            cert: cert.clone(),
+            key: Some(key),
+            ..Default::default()
+        };
+        let leaf = cert_file(&cert).unwrap().remove(0);
+        assert_eq!(check_key(&site, &leaf).status, Status::Pass);
+
+        // no key in the cert file
+        let haproxy = Site {
+            key: None,
+            ..site.clone()
+        };
+        assert_eq!(check_key(&haproxy, &leaf).status, Status::Fail);
+
+        // a self-signed cert nobody trusts
+        assert_eq!(check_chain(&site, vec![leaf]).status, Status::Fail);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
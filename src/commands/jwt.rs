@@ -0,0 +1,90 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+use boring::pkey::{PKey, Public};
+use clap::Parser;
+use color_eyre::eyre::{bail, Context, Result};
+
+use crate::{
+    components::jwt::{print_jwt, JwtWithCerts},
+    jose::{self, DecodedJwt},
+    pem::{parse_pems, ParsedPem},
+    x509::SimpleCert,
+};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Decode a compact JWT/JWS and show its header, claims and `x5c` certificate
+/// chain. The signature is only checked when `--key` is given.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct Jwt {
+    /// The token to inspect. Defaults to reading it from `stdin`. A leading
+    /// `Bearer ` is ignored.
+    token: Option<String>,
+
+    /// Verify the signature with this key: a PEM public key or certificate,
+    /// or a JWK/JWKS JSON document (the key is picked by the token's `kid`).
+    #[arg(long)]
+    key: Option<PathBuf>,
+}
+
+impl CommandExt for Jwt {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let token = match self.token {
+            Some(token) => token,
+            None => {
+                if io::stdin().is_terminal() {
+                    bail!("provide a token or pipe one into stdin");
+                }
+
+                let mut token = String::new();
+                io::stdin()
+                    .read_to_string(&mut token)
+                    .context("Reading stdin")?;
+                token
+            }
+        };
+
+        let mut jwt = DecodedJwt::decode(&token)?;
+        if let Some(path) = &self.key {
+            let key = load_key(path, jwt.kid.as_deref())?;
+            jwt.verify(&key);
+        }
+
+        // a JWS header carries at most one chain
+        let certs = jose::x5c_chains(&jwt.header)?
+            .into_iter()
+            .next()
+            .map(|chain| chain.certs.into_iter().map(SimpleCert::from).collect())
+            .unwrap_or_default();
+
+        print_jwt(JwtWithCerts { jwt, certs }, format, &options)
+    }
+}
+
+/// Load the verification key from a JWK/JWKS document or the first public key
+/// or certificate in a PEM file.
+fn load_key(path: &Path, kid: Option<&str>) -> Result<PKey<Public>> {
+    let data = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+
+    if let Ok(document) = serde_json::from_slice(&data) {
+        let jwk = jose::select_jwk(&document, kid)?;
+        return jose::jwk_to_pkey(jwk)
+            .with_context(|| format!("loading JWK from {}", path.display()));
+    }
+
+    for pem in parse_pems(&data).flatten() {
+        match pem.into_parsed_pem() {
+            ParsedPem::PublicKey(key) => return Ok(key),
+            ParsedPem::RsaPublicKey(rsa) => return Ok(PKey::from_rsa(rsa)?),
+            ParsedPem::Cert(cert) => return Ok(cert.public_key()?),
+            _ => {}
+        }
+    }
+
+    bail!(
+        "no public key, certificate or JWK found in {}",
+        path.display()
+    )
+}
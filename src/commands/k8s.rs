@@ -0,0 +1,206 @@
+use std::process::Command;
+use std::time::Duration;
+
+use clap::Parser;
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use jiff::Timestamp;
+use serde_json::Value;
+
+use crate::{
+    components::expiry::{self, print_expiry, ExpiryEntry},
+    x509::SimpleCert,
+};
+
+use super::{expiry::certs_in, parse_duration, CommandExt, Format, RenderOptions};
+
+/// The `type` of the secrets cert-manager, ingress controllers and `kubectl
+/// create secret tls` make.
+const TLS_SECRET: &str = "kubernetes.io/tls";
+
+/// Read certs straight from a Kubernetes cluster, with the current
+/// kubeconfig context and credentials. Only built with the `k8s` feature.
+#[derive(Clone, Debug, Parser)]
+pub struct K8s {
+    #[command(subcommand)]
+    pub command: K8sCommand,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+pub enum K8sCommand {
+    Secrets(K8sSecrets),
+}
+
+/// Show when the certs in TLS secrets expire, soonest first, like `pls
+/// expiry`. Runs `kubectl`, so whatever it's logged in as and can read is
+/// what's checked.
+#[derive(Clone, Debug, Parser)]
+pub struct K8sSecrets {
+    /// Only this secret, rather than every TLS secret in the namespace.
+    pub name: Option<String>,
+
+    /// The namespace to read. Defaults to the context's.
+    #[arg(long, short)]
+    pub namespace: Option<String>,
+
+    /// Read the TLS secrets of every namespace.
+    #[arg(long, short = 'A', conflicts_with_all = ["namespace", "name"])]
+    pub all_namespaces: bool,
+
+    /// The kubeconfig context to use instead of the current one.
+    #[arg(long)]
+    pub context: Option<String>,
+
+    /// Only show the certs that expire within this long from now, e.g.
+    /// `30d`. Expired certs and secrets that couldn't be read are always
+    /// shown.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub expiring: Option<Duration>,
+
+    /// The `kubectl` to run.
+    #[arg(long, default_value = "kubectl", value_name = "PATH")]
+    pub kubectl: String,
+}
+
+impl CommandExt for K8s {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        match self.command {
+            K8sCommand::Secrets(secrets) => secrets.run(format, options).await,
+        }
+    }
+}
+
+impl CommandExt for K8sSecrets {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let now = options.now.timestamp();
+        let json = self.kubectl()?;
+        let mut entries = secret_entries(&json, now)?;
+
+        if let Some(expiring) = self.expiring {
+            let seconds = expiring.as_secs() as i64;
+            entries.retain(|entry| entry.error.is_some() || entry.expires_within(seconds, now));
+        }
+        expiry::sort(&mut entries);
+        print_expiry(entries, format, &options)
+    }
+}
+
+impl K8sSecrets {
+    /// The secrets as `kubectl get -o json` prints them.
+    fn kubectl(&self) -> Result<Vec<u8>> {
+        let mut command = Command::new(&self.kubectl);
+        command.args(["get", "secrets", "--output", "json"]);
+        match &self.name {
+            Some(name) => command.arg(name),
+            None => command.arg(format!("--field-selector=type={TLS_SECRET}")),
+        };
+        if let Some(namespace) = &self.namespace {
+            command.args(["--namespace", namespace]);
+        }
+        if self.all_namespaces {
+            command.arg("--all-namespaces");
+        }
+        if let Some(context) = &self.context {
+            command.args(["--context", context]);
+        }
+
+        tracing::info!("running {command:?}");
+        let output = command
+            .output()
+            .with_context(|| format!("running {}, is it installed?", self.kubectl))?;
+        if !output.status.success() {
+            bail!(
+                "kubectl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// An entry for each cert in the `tls.crt` of each secret in `json`, a
+/// secret or a list of them, named `namespace/name`.
+fn secret_entries(json: &[u8], now: Timestamp) -> Result<Vec<ExpiryEntry>> {
+    let json: Value = serde_json::from_slice(json).context("parsing kubectl's output")?;
+    let secrets = match json["items"].as_array() {
+        Some(items) => items.iter().collect(),
+        None => vec![&json],
+    };
+
+    let mut entries = Vec::new();
+    for secret in secrets {
+        let metadata = &secret["metadata"];
+        let input = format!(
+            "{}/{}",
+            metadata["namespace"].as_str().unwrap_or("default"),
+            metadata["name"].as_str().unwrap_or("?")
+        );
+        match secret_certs(secret, &input) {
+            Ok(certs) => {
+                entries.extend(certs.iter().map(|cert| ExpiryEntry::new(&input, cert, now)))
+            }
+            Err(err) => entries.push(ExpiryEntry::failed(&input, format!("{err:#}"))),
+        }
+    }
+    Ok(entries)
+}
+
+fn secret_certs(secret: &Value, input: &str) -> Result<Vec<SimpleCert>> {
+    let crt = secret["data"]["tls.crt"]
+        .as_str()
+        .ok_or_else(|| eyre!("no tls.crt in the secret"))?;
+    let pem = boring::base64::decode_block(crt).context("decoding tls.crt")?;
+    certs_in(&pem, input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_secret_lists() {
+        let pem = include_str!("../../test-data/certs/chain.pem");
+        let secret = |name: &str, data: Value| {
+            serde_json::json!({
+                "kind": "Secret",
+                "type": TLS_SECRET,
+                "metadata": { "name": name, "namespace": "prod" },
+                "data": data,
+            })
+        };
+        let list = serde_json::json!({
+            "kind": "List",
+            "items": [
+                secret("web-tls", serde_json::json!({
+                    "tls.crt": boring::base64::encode_block(pem.as_bytes()),
+                })),
+                secret("empty", serde_json::json!({})),
+            ],
+        });
+
+        let now = Timestamp::now();
+        let entries = secret_entries(list.to_string().as_bytes(), now).unwrap();
+        let inputs: Vec<&str> = entries.iter().map(|entry| entry.input.as_str()).collect();
+        assert_eq!(
+            inputs,
+            ["prod/web-tls", "prod/web-tls", "prod/web-tls", "prod/empty"]
+        );
+        assert_eq!(
+            entries[3].error.as_deref(),
+            Some("no tls.crt in the secret")
+        );
+
+        // `kubectl get secret NAME` prints the secret itself
+        let one = secret(
+            "web-tls",
+            serde_json::json!({
+                "tls.crt": boring::base64::encode_block(pem.as_bytes()),
+            }),
+        );
+        assert_eq!(
+            secret_entries(one.to_string().as_bytes(), now)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use std::fs;
+use std::io::{stdin, stdout, IsTerminal, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+
+use boring::pkey::PKey;
+use clap::Parser;
+use color_eyre::eyre::{bail, Context, Result};
+
+use crate::{
+    crl::pem_encode,
+    der::{self, tag, Tlv},
+    pem::{parse_pems, pem_blocks, ParsedPem},
+    x509::SpkiHash,
+};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Remove the private keys from certs and keys, e.g. a debugging bundle or a
+/// config file, so it can be shared publicly. Each private key PEM is
+/// replaced by a placeholder with the hash of its public key, so it can still
+/// be matched to its cert, and everything else is passed through as it is.
+///
+/// The result is always printed as text, whatever the output format.
+#[derive(Clone, Debug, Parser)]
+pub struct Mask {
+    /// File to read data from. Defaults to `stdin`.
+    pub file: Option<PathBuf>,
+
+    /// Also replace the names in certs (the subject, issuer and SANs) with
+    /// pseudonyms like `host-1a2b3c4d.example.com`. The same name gets the
+    /// same pseudonym everywhere in the input, so chains still link up, but
+    /// the signatures no longer verify. The country is kept.
+    #[arg(long)]
+    pub pseudonymize: bool,
+}
+
+impl CommandExt for Mask {
+    async fn run(self, _format: Format, _options: RenderOptions) -> Result<()> {
+        let data = match &self.file {
+            Some(path) => fs::read(path).with_context(|| format!("Reading {}", path.display()))?,
+            None => {
+                let stdin = stdin();
+                if stdin.is_terminal() {
+                    bail!("stdin is a TTY, please provide a file or pipe data into stdin");
+                }
+                let mut data = Vec::new();
+                stdin
+                    .lock()
+                    .read_to_end(&mut data)
+                    .context("Reading stdin")?;
+                data
+            }
+        };
+
+        let pseudonyms = self.pseudonymize.then(Pseudonyms::new);
+        let masked = mask(&data, pseudonyms.as_ref())?;
+        stdout().write_all(&masked).context("Writing stdout")
+    }
+}
+
+/// `data` with its private key PEMs replaced and, with `pseudonyms`, the
+/// names in its certs replaced too. Only the PEMs are touched: the text
+/// around them is kept byte for byte.
+fn mask(data: &[u8], pseudonyms: Option<&Pseudonyms>) -> Result<Vec<u8>> {
+    let mut masked = Vec::with_capacity(data.len());
+    let mut end = 0;
+    for (span, label) in pem_blocks(data) {
+        let block = &data[span.clone()];
+        let replacement = if label.contains("PRIVATE KEY") {
+            Some(placeholder(block))
+        } else if let (Some(pseudonyms), "CERTIFICATE") = (pseudonyms, label.as_str()) {
+            let pem = parse_pems(block)
+                .next()
+                .and_then(|pem| pem.ok()?.into_cert());
+            match pem {
+                Some(cert) => {
+                    let der = pseudonyms.cert(&cert.to_der()?).with_context(|| {
+                        format!("Pseudonymizing the cert at byte {}", span.start)
+                    })?;
+                    Some(reformat(data, span.start, block, &pem_encode(&label, &der)))
+                }
+                None => None,
+            }
+        } else {
+            None
+        };
+
+        if let Some(replacement) = replacement {
+            masked.extend(&data[end..span.start]);
+            masked.extend(replacement.as_bytes());
+            end = span.end;
+        }
+    }
+    masked.extend(&data[end..]);
+    Ok(masked)
+}
+
+/// What a private key PEM is replaced with.
+fn placeholder(block: &[u8]) -> String {
+    let spki = parse_pems(block)
+        .next()
+        .and_then(|pem| match pem.ok()?.into_parsed_pem() {
+            ParsedPem::PrivateKey(pkey) => SpkiHash::new(&pkey).ok(),
+            ParsedPem::RsaPrivateKey(rsa) => SpkiHash::new(&PKey::from_rsa(rsa).ok()?).ok(),
+            ParsedPem::ECPrivateKey(ec) => SpkiHash::new(&PKey::from_ec_key(ec).ok()?).ok(),
+            _ => None,
+        });
+    match spki {
+        Some(spki) => format!(
+            "[private key removed by pls mask, spki sha256 {}]",
+            spki.spki_sha256
+        ),
+        None => "[private key removed by pls mask]".to_string(),
+    }
+}
+
+/// Lay out the new `pem` like the `block` it replaces at `start` of `data`:
+/// with the same indentation, and with `\n` escapes in JSON and the like.
+fn reformat(data: &[u8], start: usize, block: &[u8], pem: &str) -> String {
+    let line_start = memchr::memrchr(b'\n', &data[..start]).map_or(0, |i| i + 1);
+    let indent = String::from_utf8_lossy(&data[line_start..start]);
+    let indent = if indent.chars().all(char::is_whitespace) {
+        indent
+    } else {
+        "".into()
+    };
+    let separator =
+        if memchr::memchr(b'\n', block).is_none() && block.windows(2).any(|w| w == b"\\n") {
+            "\\n".to_string()
+        } else {
+            format!("\n{indent}")
+        };
+    pem.trim_end().lines().collect::<Vec<_>>().join(&separator)
+}
+
+/// Consistent pseudonyms for the names in certs. The same value always maps
+/// to the same pseudonym within a run, but a random salt keeps them from
+/// being reversed by hashing guesses.
+struct Pseudonyms {
+    salt: [u8; 16],
+}
+
+impl Pseudonyms {
+    fn new() -> Self {
+        let mut salt = [0; 16];
+        boring::rand::rand_bytes(&mut salt).expect("generating random bytes");
+        Self { salt }
+    }
+
+    fn digest(&self, value: &[u8]) -> [u8; 32] {
+        let mut hasher = boring::sha::Sha256::new();
+        hasher.update(&self.salt);
+        hasher.update(value);
+        hasher.finish()
+    }
+
+    fn id(&self, value: &[u8]) -> String {
+        hex::encode(&self.digest(value)[..4])
+    }
+
+    fn domain(&self, value: &str) -> String {
+        match value.strip_prefix("*.") {
+            Some(domain) => format!("*.host-{}.example.com", self.id(domain.as_bytes())),
+            None => format!("host-{}.example.com", self.id(value.as_bytes())),
+        }
+    }
+
+    fn email(&self, value: &str) -> String {
+        format!("user-{}@example.com", self.id(value.as_bytes()))
+    }
+
+    /// A pseudonym of the same kind as `value`: a domain, an email address
+    /// or an opaque name.
+    fn text(&self, value: &str) -> String {
+        let domain_like = value.contains('.')
+            && value
+                .trim_start_matches("*.")
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+        if value.contains('@') {
+            self.email(value)
+        } else if domain_like {
+            self.domain(value)
+        } else {
+            format!("redacted-{}", self.id(value.as_bytes()))
+        }
+    }
+
+    /// The cert `der` with its issuer, subject and alt names replaced. The
+    /// signature is kept as it is.
+    fn cert(&self, der: &[u8]) -> Result<Vec<u8>> {
+        let (cert, _) = der::read_tlv(der)?;
+        let mut fields = cert.reader();
+        let tbs = fields.read_tag(tag::SEQUENCE)?;
+
+        let mut tbs_fields = tbs.reader();
+        let mut rewritten = Vec::with_capacity(tbs.value.len());
+        if let Some(version) = tbs_fields.read_optional(tag::context(0))? {
+            rewritten.extend(version.raw);
+        }
+        rewritten.extend(tbs_fields.read_tag(tag::INTEGER)?.raw); // serialNumber
+        rewritten.extend(tbs_fields.read_tag(tag::SEQUENCE)?.raw); // signature
+        rewritten.extend(self.name(tbs_fields.read_tag(tag::SEQUENCE)?)?); // issuer
+        rewritten.extend(tbs_fields.read_tag(tag::SEQUENCE)?.raw); // validity
+        rewritten.extend(self.name(tbs_fields.read_tag(tag::SEQUENCE)?)?); // subject
+        for field in tbs_fields {
+            let field = field?;
+            if field.tag == tag::context(3) {
+                let extensions = field.reader().read_tag(tag::SEQUENCE)?;
+                let extensions = der::encode_tlv(tag::SEQUENCE, &self.extensions(extensions)?);
+                rewritten.extend(der::encode_tlv(tag::context(3), &extensions));
+            } else {
+                rewritten.extend(field.raw);
+            }
+        }
+
+        let mut cert = der::encode_tlv(tag::SEQUENCE, &rewritten);
+        for field in fields {
+            cert.extend(field?.raw); // signatureAlgorithm, signatureValue
+        }
+        Ok(der::encode_tlv(tag::SEQUENCE, &cert))
+    }
+
+    /// A `Name` with every attribute value but the country replaced.
+    fn name(&self, name: Tlv) -> Result<Vec<u8>> {
+        let mut rdns = Vec::with_capacity(name.value.len());
+        for rdn in name.reader() {
+            let mut attributes = Vec::new();
+            for attribute in rdn?.reader() {
+                let attribute = attribute?;
+                let mut fields = attribute.reader();
+                let oid = fields.read_tag(tag::OID)?;
+                let value = fields.read()?;
+                let value = match value.string() {
+                    Some(text) if oid.oid()? != "2.5.4.6" => {
+                        let pseudonym = self.text(&text);
+                        let tag = match value.tag {
+                            tag::PRINTABLE_STRING if is_printable(&pseudonym) => {
+                                tag::PRINTABLE_STRING
+                            }
+                            tag::IA5_STRING => tag::IA5_STRING,
+                            _ => tag::UTF8_STRING,
+                        };
+                        der::encode_tlv(tag, pseudonym.as_bytes())
+                    }
+                    _ => value.raw.to_vec(),
+                };
+                attributes.extend(der::encode_tlv(
+                    tag::SEQUENCE,
+                    &[oid.raw, &value[..]].concat(),
+                ));
+            }
+            rdns.extend(der::encode_tlv(tag::SET, &attributes));
+        }
+        Ok(der::encode_tlv(tag::SEQUENCE, &rdns))
+    }
+
+    /// The contents of an `Extensions` with the names in subjectAltName and
+    /// issuerAltName replaced.
+    fn extensions(&self, extensions: Tlv) -> Result<Vec<u8>> {
+        let mut rewritten = Vec::with_capacity(extensions.value.len());
+        for extension in extensions.reader() {
+            let extension = extension?;
+            let mut fields = extension.reader();
+            let oid = fields.read_tag(tag::OID)?;
+            if !matches!(
+                oid.oid()?.as_str(),
+                der::oid::SUBJECT_ALT_NAME | "2.5.29.18"
+            ) {
+                rewritten.extend(extension.raw);
+                continue;
+            }
+
+            let critical = fields.read_optional(tag::BOOLEAN)?;
+            let names = fields.read_tag(tag::OCTET_STRING)?;
+            let (names, _) = der::read_tlv(names.value)?;
+            let names = der::encode_tlv(tag::SEQUENCE, &self.general_names(names)?);
+
+            let mut fields = oid.raw.to_vec();
+            if let Some(critical) = critical {
+                fields.extend(critical.raw);
+            }
+            fields.extend(der::encode_tlv(tag::OCTET_STRING, &names));
+            rewritten.extend(der::encode_tlv(tag::SEQUENCE, &fields));
+        }
+        Ok(rewritten)
+    }
+
+    /// The contents of a `GeneralNames` with the names replaced. otherNames
+    /// and the rarer forms are kept as they are.
+    fn general_names(&self, names: Tlv) -> Result<Vec<u8>> {
+        use der::tag::{context, context_primitive};
+
+        let mut rewritten = Vec::with_capacity(names.value.len());
+        for name in names.reader() {
+            let name = name?;
+            let text = || String::from_utf8_lossy(name.value).into_owned();
+            let digest = self.digest(name.value);
+            let value = match name.tag {
+                tag if tag == context_primitive(1) => self.email(&text()).into_bytes(),
+                tag if tag == context_primitive(2) => self.domain(&text()).into_bytes(),
+                tag if tag == context_primitive(6) => {
+                    format!("https://host-{}.example.com/", self.id(name.value)).into_bytes()
+                }
+                // documentation addresses, from RFC 5737 and RFC 3849
+                tag if tag == context_primitive(7) && name.value.len() == 4 => {
+                    Ipv4Addr::new(192, 0, 2, digest[0]).octets().to_vec()
+                }
+                tag if tag == context_primitive(7) && name.value.len() == 16 => {
+                    let suffix = u16::from_be_bytes([digest[0], digest[1]]);
+                    Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, suffix)
+                        .octets()
+                        .to_vec()
+                }
+                tag if tag == context(4) => self.name(name.reader().read_tag(tag::SEQUENCE)?)?,
+                _ => {
+                    rewritten.extend(name.raw);
+                    continue;
+                }
+            };
+            rewritten.extend(der::encode_tlv(name.tag, &value));
+        }
+        Ok(rewritten)
+    }
+}
+
+/// Whether `text` fits in an ASN.1 PrintableString.
+fn is_printable(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_alphanumeric() || " '()+,-./:=?".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+    use crate::x509::SimpleCert;
+
+    #[test]
+    fn removes_private_keys() {
+        let data = include_bytes!("../../test-data/private-keys/ed25519.pem");
+        let input = [b"key:\n  ".as_slice(), data, b"trailing text\n"].concat();
+
+        let masked = String::from_utf8(mask(&input, None).unwrap()).unwrap();
+        assert!(masked.starts_with("key:\n  [private key removed by pls mask, spki sha256 "));
+        assert!(masked.ends_with("]\ntrailing text\n"), "{masked}");
+        assert!(!masked.contains("PRIVATE KEY"));
+    }
+
+    #[test]
+    fn pseudonymizes_certs() {
+        let data = include_bytes!("../../test-data/certs/lan-fish.pem");
+        let original = SimpleCert::from(X509::from_pem(data).unwrap());
+
+        let masked = mask(data, Some(&Pseudonyms::new())).unwrap();
+        let cert = SimpleCert::from(X509::from_pem(&masked).unwrap());
+        assert_ne!(cert.subject.name, original.subject.name);
+        assert_eq!(cert.subject.sans.dns.len(), original.subject.sans.dns.len());
+        for (dns, original) in cert.subject.sans.dns.iter().zip(&original.subject.sans.dns) {
+            assert!(dns.ends_with(".example.com"), "{dns}");
+            assert_ne!(dns, original);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use color_eyre::eyre::{bail, Result};
+
+use crate::{components::match_hostname::print_matches, hostname, idna};
+
+use super::{expiry::cert_file, CommandExt, Format, RenderOptions};
+
+/// Check whether a cert is valid for hostnames, as RFC 6125 and browsers
+/// match them, and explain why each of the cert's names does or doesn't
+/// match: wildcards cover exactly one label, IP addresses only match IP
+/// SANs and the subject CN is ignored. Fails when a hostname doesn't match.
+#[derive(Clone, Debug, Parser)]
+pub struct MatchHostname {
+    /// The cert (PEM, DER or PKCS#7). Only the first cert in the file is
+    /// checked, which is the leaf in a chain.
+    pub file: PathBuf,
+
+    /// The hostnames or IP addresses to check, e.g. `www.example.com`.
+    /// Internationalized names can be given in Unicode, e.g.
+    /// `bücher.example`.
+    #[arg(required = true)]
+    pub hostnames: Vec<String>,
+}
+
+impl CommandExt for MatchHostname {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let certs = cert_file(&self.file)?;
+        let Some(cert) = certs.first() else {
+            bail!("no cert in {}", self.file.display());
+        };
+
+        // Unicode names are matched as the `xn--` form certs have
+        let results: Vec<_> = self
+            .hostnames
+            .iter()
+            .map(|hostname| Ok(hostname::check(cert, &idna::to_ascii(hostname)?)))
+            .collect::<Result<_>>()?;
+        let failed: Vec<String> = results
+            .iter()
+            .filter(|result| !result.matches)
+            .map(|result| result.hostname.clone())
+            .collect();
+
+        print_matches(results, format, &options)?;
+        if !failed.is_empty() {
+            bail!("the cert doesn't cover {}", failed.join(", "));
+        }
+        Ok(())
+    }
+}
@@ -1,25 +1,442 @@
 use std::io::IsTerminal as _;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 
+use iocraft::{element, AnyElement, Context, ContextProvider, ElementExt};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use url::Url;
+
+use crate::{
+    dns::Resolver,
+    idna,
+    jobs::Runner,
+    theme::Theme,
+    x509::{Name, Serial},
+};
+
+pub mod acme;
+pub mod caa;
+pub mod capabilities;
+pub mod check_pair;
 pub mod connect;
+pub mod convert;
+pub mod csr;
+pub mod doctor;
+pub mod expiry;
+pub mod explain_expr;
+pub mod find;
+pub mod inspect;
+pub mod jwt;
+#[cfg(feature = "k8s")]
+pub mod k8s;
+pub mod mask;
+pub mod match_hostname;
 pub mod parse;
+pub mod pin;
+pub mod random;
+pub mod same_key;
+pub mod schema;
+pub mod serve;
+pub mod trust;
+pub mod verify;
+pub mod watch;
+
+/// The version of the JSON output's schema, printed by `pls schema`. Bumped
+/// when a field is renamed, removed or changes type.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A JSON document tagged with the [`SCHEMA_VERSION`] it follows.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Versioned<T> {
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub value: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            value,
+        }
+    }
+}
 
 #[allow(async_fn_in_trait)]
 pub trait CommandExt {
-    async fn run(self, format: Format) -> color_eyre::Result<()>;
+    async fn run(self, format: Format, options: RenderOptions) -> color_eyre::Result<()>;
+}
+
+/// How text output is rendered, threaded from the CLI down into the
+/// components so views never look at the clock or terminal themselves.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// The time relative phrases like "in 2 months" are computed against.
+    pub now: jiff::Zoned,
+    /// Golden-file friendly output: no relative times or timings, sorted
+    /// collections and no color, even on a TTY.
+    pub stable: bool,
+    /// Whether to style the output with ANSI escapes, resolved from
+    /// `--color`, `NO_COLOR` and whether stdout is a TTY.
+    pub color: bool,
+    pub theme: Theme,
+    /// The terminal width, when stdout is one. Long hex fields are wrapped
+    /// to fit it.
+    pub width: Option<usize>,
+    /// Print long hex fields (moduli, keys, signatures) in full rather than
+    /// shortened to `ab12…89ef (256 bytes)`.
+    pub full: bool,
+    /// Show certs in a few lines each, from `--short`.
+    pub short: bool,
+    /// How subject and issuer names are shown.
+    pub name_format: NameFormat,
+    /// How serial numbers are shown.
+    pub serial_format: SerialFormat,
+    /// Show `xn--` DNS names as Unicode, with the punycode alongside.
+    pub unicode_names: bool,
+    /// Annotate fields with plain-language notes, see [`crate::explain`].
+    pub explain: bool,
+    /// Show where each entity was found in the input, from `parse
+    /// --locations`.
+    pub locations: bool,
+    /// Put a `# 1: ...` comment before each PEM of the `--pem` output.
+    pub pem_headers: bool,
+    /// A line to print between the PEMs of the `--pem` output.
+    pub pem_separator: Option<String>,
+    /// How many PEMs have been printed, shared by the clones so the headers
+    /// are numbered across the whole output.
+    pub pems_printed: Arc<AtomicUsize>,
+    /// Don't warn when an unencrypted private key is written, from
+    /// `--insecure-output`.
+    pub insecure_output: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            now: jiff::Zoned::now(),
+            stable: false,
+            color: ColorChoice::Auto.enabled(),
+            theme: Theme::default(),
+            width: None,
+            full: false,
+            short: false,
+            name_format: NameFormat::default(),
+            serial_format: SerialFormat::default(),
+            unicode_names: false,
+            explain: false,
+            locations: false,
+            pem_headers: false,
+            pem_separator: None,
+            pems_printed: Arc::default(),
+            insecure_output: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// How wide to wrap hex fields: the terminal width minus room for the
+    /// field names and indentation, or a fixed width when it's unknown.
+    pub fn key_width(&self) -> u32 {
+        match self.width {
+            Some(width) => width.saturating_sub(26).clamp(32, 128) as u32,
+            None => 54,
+        }
+    }
+
+    /// The subject or issuer name to show, in the chosen `--name-format`.
+    pub fn name(&self, oneline: &str, dn: &Name) -> String {
+        let name = match self.name_format {
+            NameFormat::Oneline => oneline.to_string(),
+            NameFormat::Rfc4514 => dn.rfc4514.clone(),
+            // fall back to the full name for CN-less names, e.g. some roots
+            NameFormat::Cn => dn
+                .common_name
+                .clone()
+                .unwrap_or_else(|| oneline.to_string()),
+        };
+        match &dn.common_name {
+            Some(cn) if self.unicode_names && name.contains(cn.as_str()) => {
+                name.replacen(cn.as_str(), &self.dns_name(cn), 1)
+            }
+            _ => name,
+        }
+    }
+
+    /// A DNS name to show: as it is, or with `--unicode-names` as Unicode
+    /// with the punycode alongside, e.g. `bücher.example
+    /// (xn--bcher-kva.example)`.
+    pub fn dns_name(&self, name: &str) -> String {
+        match self.unicode_names.then(|| idna::to_unicode(name)).flatten() {
+            Some(unicode) => format!("{unicode} ({name})"),
+            None => name.to_string(),
+        }
+    }
+
+    /// The serial number to show, in the chosen `--serial-format`.
+    pub fn serial(&self, serial: &Serial) -> String {
+        match self.serial_format {
+            SerialFormat::Hex => serial.hex.clone(),
+            SerialFormat::Colon => serial.colon_hex.clone(),
+            SerialFormat::Decimal => serial.decimal.clone(),
+        }
+    }
+
+    /// Shorten hex strings longer than two wrapped lines, unless `--full`.
+    pub fn hex(&self, hex: &str) -> String {
+        let max = self.key_width() as usize * 2;
+        if self.full || hex.len() <= max || !hex.is_ascii() {
+            return hex.to_string();
+        }
+
+        format!(
+            "{}…{} ({} bytes)",
+            &hex[..8],
+            &hex[hex.len() - 8..],
+            hex.len() / 2
+        )
+    }
+
+    /// Print a rendered element, providing the theme to every component.
+    pub fn print(&self, element: impl Into<AnyElement<'static>>) {
+        let mut element = element! {
+            ContextProvider(value: Context::owned(self.theme)) {
+                #(element.into())
+            }
+        };
+
+        if !self.color {
+            print!("{}", element.to_string());
+        } else if std::io::stdout().is_terminal() {
+            element.print();
+        } else {
+            // `--color=always` into a pipe, e.g. `less -R`
+            let _ = element.render(None).write_ansi(std::io::stdout());
+        }
+    }
+
+    /// Print one entity of the `--pem` output, with the `--separator` before
+    /// it and the `--pem-headers` comment with its `header` when asked for.
+    pub fn print_pem(&self, header: impl FnOnce() -> String, pem: &str) {
+        let index = self.pems_printed.fetch_add(1, Ordering::Relaxed);
+        if let Some(separator) = self.pem_separator.as_ref().filter(|_| index > 0) {
+            println!("{separator}");
+        }
+        if self.pem_headers {
+            println!("# {}: {}", index + 1, header());
+        }
+        print!("{pem}");
+    }
+
+    /// Print a rendered element to stderr, e.g. an error.
+    pub fn eprint(&self, element: impl Into<AnyElement<'static>>) {
+        let mut element = element! {
+            ContextProvider(value: Context::owned(self.theme)) {
+                #(element.into())
+            }
+        };
+
+        if self.color {
+            let _ = element.render(self.width).write_ansi(std::io::stderr());
+        } else {
+            eprint!("{}", element.to_string());
+        }
+    }
+}
+
+/// How to show distinguished names, from `--name-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum NameFormat {
+    /// OpenSSL's one line format, `C=US, O=Example, CN=example.com`.
+    #[default]
+    Oneline,
+    /// RFC 4514, most specific first: `CN=example.com,O=Example,C=US`.
+    Rfc4514,
+    /// Just the common name.
+    Cn,
+}
+
+/// How to show serial numbers, from `--serial-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SerialFormat {
+    /// Bare hex, `3A1F`.
+    #[default]
+    Hex,
+    /// Colon delimited hex bytes, `3a:1f`.
+    Colon,
+    /// Decimal, `14879`.
+    Decimal,
+}
+
+/// When to style the output, from `--color`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a TTY and `NO_COLOR` isn't set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Where DNS lookups go, for commands that do their own.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct DnsArgs {
+    /// Send DNS queries to this nameserver, e.g. `1.1.1.1` or
+    /// `[2606:4700::1111]:53`, rather than using the system resolver.
+    #[arg(long, value_name = "IP", value_parser = parse_nameserver, conflicts_with = "doh")]
+    pub dns: Option<SocketAddr>,
+
+    /// Resolve with DNS-over-HTTPS, e.g.
+    /// `https://cloudflare-dns.com/dns-query`.
+    #[arg(long, value_name = "URL")]
+    pub doh: Option<Url>,
+}
+
+impl DnsArgs {
+    pub(crate) fn resolver(&self) -> Resolver {
+        match (&self.dns, &self.doh) {
+            (Some(addr), _) => Resolver::Server(*addr),
+            (None, Some(url)) => Resolver::Doh(url.clone()),
+            (None, None) => Resolver::System,
+        }
+    }
+}
+
+/// How commands that work on many hosts at once run them.
+#[derive(Debug, Clone, clap::Args)]
+pub struct JobArgs {
+    /// How long each host gets before it's given up on and reported as a
+    /// `timeout`, e.g. `30s` or `2m`. The other hosts carry on.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "30s")]
+    pub timeout: std::time::Duration,
+
+    /// Stop at the first host that fails or times out, and exit with an
+    /// error. The hosts that hadn't finished by then are reported as
+    /// `skipped`.
+    #[arg(long)]
+    pub fail_fast: bool,
+}
+
+impl Default for JobArgs {
+    fn default() -> Self {
+        Self {
+            timeout: std::time::Duration::from_secs(30),
+            fail_fast: false,
+        }
+    }
+}
+
+impl JobArgs {
+    pub(crate) fn runner(&self) -> Runner {
+        Runner {
+            timeout: self.timeout,
+            fail_fast: self.fail_fast,
+        }
+    }
+}
+
+/// A duration like `30d`: a number of `s`econds, `m`inutes, `h`ours, `d`ays
+/// or `w`eeks.
+pub(crate) fn parse_duration(value: &str) -> Result<std::time::Duration, String> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected a number and a unit, e.g. `30d`, got {value:?}"))?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit {unit:?}, expected s, m, h, d or w")),
+    };
+    Ok(std::time::Duration::from_secs(number * unit))
+}
+
+/// A point in time: an RFC 3339 timestamp like `2026-03-01T00:00:00Z`, a
+/// date (midnight UTC), or a [duration](parse_duration) from now like
+/// `+90d`.
+pub(crate) fn parse_time(value: &str) -> Result<jiff::Timestamp, String> {
+    if let Some(duration) = value.strip_prefix('+') {
+        let duration = parse_duration(duration)?;
+        return jiff::Timestamp::now()
+            .checked_add(duration)
+            .map_err(|err| format!("{value:?} is too far out: {err}"));
+    }
+    if let Ok(date) = value.parse::<jiff::civil::Date>() {
+        return date
+            .to_zoned(jiff::tz::TimeZone::UTC)
+            .map(|zoned| zoned.timestamp())
+            .map_err(|err| err.to_string());
+    }
+    value.parse().map_err(|_| {
+        format!(
+            "expected a time like `2026-03-01T00:00:00Z`, `2026-03-01` or `+90d`, got {value:?}"
+        )
+    })
+}
+
+/// Whether `path` is something to read, rather than a directory or a host:
+/// a file, FIFO or device, like the `/dev/fd/63` of `pls parse <(...)`.
+/// Those `/dev/fd` paths are taken as files even when they can't be stat-ed,
+/// which fails on some platforms, so reading them gives a useful error.
+pub(crate) fn is_input_file(path: &Path) -> bool {
+    match std::fs::metadata(path) {
+        Ok(metadata) => !metadata.is_dir(),
+        Err(_) => path.starts_with("/dev/fd") || path.starts_with("/proc/self/fd"),
+    }
+}
+
+/// A nameserver address, port 53 unless given.
+fn parse_nameserver(value: &str) -> Result<SocketAddr, String> {
+    value
+        .parse()
+        .or_else(|_| value.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .map_err(|_| format!("{value:?} isn't an IP address"))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
     Text,
     Json,
+    /// JSON on a single line.
+    JsonCompact,
+    /// Newline delimited JSON: one compact object per entity.
+    Ndjson,
     Pem,
 }
 
 impl Format {
-    pub fn from_args(text: bool, json: bool, pem: bool) -> Self {
+    pub fn from_args(text: bool, json: bool, json_compact: bool, ndjson: bool, pem: bool) -> Self {
         let print_json = json || (!text && !pem && !std::io::stdout().is_terminal());
 
-        if print_json {
+        if json_compact {
+            Self::JsonCompact
+        } else if ndjson {
+            Self::Ndjson
+        } else if print_json {
             Self::Json
         } else if pem {
             Self::Pem
@@ -28,11 +445,199 @@ impl Format {
         }
     }
 
-    /// Returns `true` if the format is [`Json`].
+    /// The format for an `--output` file given without a format flag, from
+    /// its extension: JSON for `.json`, NDJSON for `.ndjson` and `.jsonl`,
+    /// PEM for `.pem`, `.crt` and `.cer`, and text otherwise.
+    pub fn for_file(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("json") => Self::Json,
+            Some("ndjson" | "jsonl") => Self::Ndjson,
+            Some("pem" | "crt" | "cer") => Self::Pem,
+            _ => Self::Text,
+        }
+    }
+
+    /// Returns `true` if the format is [`Json`], [`JsonCompact`] or [`Ndjson`].
     ///
     /// [`Json`]: Format::Json
+    /// [`JsonCompact`]: Format::JsonCompact
+    /// [`Ndjson`]: Format::Ndjson
     #[must_use]
     pub fn is_json(&self) -> bool {
-        matches!(self, Self::Json)
+        matches!(self, Self::Json | Self::JsonCompact | Self::Ndjson)
+    }
+
+    /// Serialize a single JSON document in this format: pretty printed for
+    /// [`Json`], on one line otherwise. The document is tagged with the
+    /// [`SCHEMA_VERSION`].
+    ///
+    /// [`Json`]: Format::Json
+    pub fn to_json<T: serde::Serialize>(&self, value: &T) -> serde_json::Result<String> {
+        self.serialize(&Versioned::new(value))
+    }
+
+    fn serialize<T: serde::Serialize>(&self, value: &T) -> serde_json::Result<String> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(value),
+            _ => serde_json::to_string(value),
+        }
+    }
+
+    /// Print a list of entities: one document for [`Json`] and
+    /// [`JsonCompact`], one line per entity for [`Ndjson`].
+    ///
+    /// [`Json`]: Format::Json
+    /// [`JsonCompact`]: Format::JsonCompact
+    /// [`Ndjson`]: Format::Ndjson
+    pub fn print_json_list<T: serde::Serialize>(&self, items: &[T]) -> serde_json::Result<()> {
+        println!("{}", self.json_list(items)?);
+        Ok(())
+    }
+
+    fn json_list<T: serde::Serialize>(&self, items: &[T]) -> serde_json::Result<String> {
+        // a list has nowhere to put the version, so every entity carries it
+        let items: Vec<_> = items.iter().map(Versioned::new).collect();
+        if *self == Self::Ndjson {
+            let lines = items
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<serde_json::Result<Vec<_>>>()?;
+            Ok(lines.join("\n"))
+        } else {
+            self.serialize(&items)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(
+            parse_duration("30d"),
+            Ok(std::time::Duration::from_secs(30 * 86400))
+        );
+        assert_eq!(
+            parse_duration("12h"),
+            Ok(std::time::Duration::from_secs(12 * 3600))
+        );
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn times() {
+        let at: jiff::Timestamp = "2026-03-01T00:00:00Z".parse().unwrap();
+        assert_eq!(parse_time("2026-03-01T00:00:00Z"), Ok(at));
+        assert_eq!(parse_time("2026-03-01"), Ok(at));
+
+        let later = parse_time("+90d").unwrap();
+        let days = jiff::Timestamp::now().until(later).unwrap().get_seconds() / 86400;
+        assert!((89..=90).contains(&days));
+        assert!(parse_time("next quarter").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn input_files() {
+        let dir = std::env::temp_dir().join(format!("pls-input-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("fifo");
+        let path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o600) }, 0);
+
+        assert!(is_input_file(&fifo));
+        assert!(!is_input_file(&dir));
+        assert!(!is_input_file(Path::new("example.com")));
+        assert!(is_input_file(Path::new("/dev/fd/1234567")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hex_truncation() {
+        let options = RenderOptions {
+            width: Some(80),
+            ..Default::default()
+        };
+        let modulus = "ab".repeat(256);
+
+        assert_eq!(options.key_width(), 54);
+        assert_eq!(options.hex("abcd"), "abcd");
+        assert_eq!(options.hex(&modulus), "abababab…abababab (256 bytes)");
+
+        let full = RenderOptions {
+            full: true,
+            ..options
+        };
+        assert_eq!(full.hex(&modulus), modulus);
+    }
+
+    #[test]
+    fn key_width_is_clamped() {
+        let width = |width| RenderOptions {
+            width,
+            ..Default::default()
+        };
+
+        assert_eq!(width(Some(40)).key_width(), 32);
+        assert_eq!(width(Some(300)).key_width(), 128);
+        assert_eq!(width(None).key_width(), 54);
+    }
+
+    #[test]
+    fn json_output_is_versioned() {
+        #[derive(serde::Serialize)]
+        struct Entity {
+            name: &'static str,
+        }
+
+        let json = Format::JsonCompact.to_json(&Entity { name: "a" }).unwrap();
+        assert_eq!(json, r#"{"schema_version":1,"name":"a"}"#);
+    }
+
+    #[test]
+    fn json_list_formats() {
+        #[derive(serde::Serialize)]
+        struct Entity {
+            name: &'static str,
+            sans: Vec<&'static str>,
+        }
+
+        let items = [
+            Entity {
+                name: "a",
+                sans: vec!["a.example"],
+            },
+            Entity {
+                name: "b",
+                sans: vec![],
+            },
+        ];
+
+        let ndjson = Format::Ndjson.json_list(&items).unwrap();
+        let lines: Vec<_> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (line, name) in lines.iter().zip(["a", "b"]) {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["name"], name);
+            assert_eq!(value["schema_version"], 1);
+        }
+
+        let compact = Format::JsonCompact.json_list(&items).unwrap();
+        assert!(!compact.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&compact).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 2);
+
+        let single = Format::JsonCompact.to_json(&items[0]).unwrap();
+        assert!(!single.contains('\n'));
+
+        let pretty = Format::Json.json_list(&items).unwrap();
+        assert!(pretty.lines().count() > 2);
     }
 }
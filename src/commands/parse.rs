@@ -1,13 +1,50 @@
 use std::io::{self, IsTerminal, Read};
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use std::{fs, io::stdin};
+use std::{
+    fs::{self, File},
+    io::stdin,
+};
 
+use boring::x509::X509;
 use clap::{CommandFactory, Parser};
-use color_eyre::eyre::{Context, Result};
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use jiff::Timestamp;
 
-use crate::{components::print_pems, pem::parse_pems};
+use crate::{
+    asn1::Asn1Dump,
+    chain::{self, ChainIssue},
+    clipboard::{self, Artifact},
+    components::{
+        asn1::print_asn1,
+        check_strict,
+        compare::{print_comparison, ChainComparison},
+        jwt::{print_x5c_chains, X5cChain},
+        keylog::print_keylog,
+        paths::print_paths,
+        print_pems,
+        stats::{print_stats, CertStats},
+        x509::{print_carved_certs, CarvedCert},
+        PemOptions,
+    },
+    dns::Resolver,
+    field::FieldPath,
+    filter::Expr,
+    jose,
+    keylog::Keylog,
+    output,
+    pem::{decode_pems, scan_der_certs, sniff_bare_base64, Carved, Pem, PemReader},
+    pkcs7::Pkcs7,
+    progress::Progress,
+    secret::Zeroizing,
+    snippet::{self, Language},
+    x509::{check_issuer_signatures, SimpleCert, TrustStore},
+};
 
-use super::{CommandExt, Format};
+use super::{
+    connect::{fetch_chain, Target},
+    parse_time, CommandExt, Format, RenderOptions,
+};
 
 /// Parse and report all discoverable x509 or DER encoded entities from a file
 /// or stdin. The `--json` output for this command will output an object of:
@@ -17,7 +54,8 @@ use super::{CommandExt, Format};
 /// ```
 ///
 /// Each of the fields will be an array of objects, even if there is only one
-/// e.g. cert. DER discovery is not well supported at the moment.
+/// e.g. cert. DER discovery is not well supported at the moment, but
+/// `--der-scan` carves DER certs out of any binary input.
 ///
 /// Supports:
 ///
@@ -25,24 +63,208 @@ use super::{CommandExt, Format};
 /// 2. x509 csrs
 /// 3. private keys
 /// 4. public keys
-/// 5. DER encoded entities (kinda)
+/// 5. PKCS#7 bundles (`.p7b`), PEM or DER, with their certs and CRLs
+/// 6. DER encoded entities (kinda)
+/// 7. certs, CSRs and keys as bare base64, without the PEM armor
+/// 8. TLS key logs (`SSLKEYLOGFILE`), with `--keylog`
+/// 9. the ASN.1 of anything DER encoded, with `--asn1`
+///
+/// With `--emit-snippet`, the certs are printed as code to embed them.
 #[derive(Default, Clone, Debug, Parser)]
 pub struct Parse {
-    /// File to read data from. Defaults to `stdin`.
+    /// File to read data from, which can be a FIFO or a process
+    /// substitution like `<(kubectl get secret ...)`. Defaults to `stdin`,
+    /// as does `-`.
     pub file: Option<PathBuf>,
+
+    /// Read stdin even when it looks like a terminal, which some pagers and
+    /// terminal emulators make pipes look like. Without it, `pls parse`
+    /// with nothing piped in prints its help.
+    #[arg(long, conflicts_with = "file")]
+    pub stdin: bool,
+
+    /// Parse this instead of a file, e.g. a PEM from the clipboard or an
+    /// environment variable: `--data "$CERT"`. Newlines can be real or
+    /// escaped as `\n`, as in JSON.
+    #[arg(long, value_name = "PEM", conflicts_with_all = ["file", "stdin"])]
+    pub data: Option<String>,
+
+    /// Parse the base64 of a PEM or DER instead of a file, e.g. a cert as
+    /// Kubernetes secrets and CI variables hold them.
+    #[arg(long, value_name = "BASE64", conflicts_with_all = ["file", "stdin", "data"])]
+    pub data_base64: Option<String>,
+
+    /// Parse what's on the clipboard instead of a file, e.g. a cert pasted
+    /// from a ticket.
+    #[arg(long, conflicts_with_all = ["file", "stdin", "data", "data_base64"])]
+    pub from_clipboard: bool,
+
+    /// Put the certs' fingerprints, their PEMs or the JSON output on the
+    /// clipboard once they're printed.
+    #[arg(long, value_name = "WHAT", value_enum, conflicts_with_all = ["x5c", "der_scan", "keylog", "asn1", "compare_to", "stats", "emit_snippet", "paths"])]
+    pub copy: Option<Artifact>,
+
+    /// Print only this field of each cert, CSR or key, one value per line,
+    /// e.g. `fingerprints.sha256` or `validity.not_after`. The path is the
+    /// keys into an entity of the `--ndjson` output, with list items by
+    /// index, e.g. `subject.sans.dns.0`. Repeatable.
+    #[arg(long, value_name = "PATH", value_parser = FieldPath::parse, conflicts_with_all = ["x5c", "der_scan", "keylog", "asn1", "compare_to", "stats", "emit_snippet", "paths", "copy"])]
+    pub field: Vec<FieldPath>,
+
+    /// Fail on the first PEM or field that can't be parsed, instead of
+    /// skipping it with a warning.
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Leave the PEM of private keys out of the JSON output, and don't hold
+    /// on to it while the input is parsed. `--pem` encodes the keys again.
+    #[arg(long)]
+    pub no_keep_pem: bool,
+
+    /// Read the input as JSON (a JOSE header, JWK or JWKS document) or a
+    /// compact JWT, and parse the certificate chains in its `x5c` arrays.
+    #[arg(long, conflicts_with = "der_scan")]
+    pub x5c: bool,
+
+    /// Treat the input as an arbitrary binary blob (a firmware image, memory
+    /// dump, ...) and carve out every DER encoded certificate in it, reporting
+    /// the offset each was found at.
+    #[arg(long)]
+    pub der_scan: bool,
+
+    /// Read the input as a TLS key log, the `SSLKEYLOGFILE` format browsers,
+    /// curl and `connect --keylog` write for Wireshark, and summarize its
+    /// sessions: their client randoms, which secrets are there and whether
+    /// any needed to decrypt them are missing. The secrets are redacted.
+    #[arg(long, conflicts_with_all = ["x5c", "der_scan", "compare_to", "stats", "fail_if"])]
+    pub keylog: bool,
+
+    /// Print the ASN.1 tree of each PEM in the input, or of the input itself
+    /// when it's DER, instead of parsing it: the offset, header and contents
+    /// length and tag of every element, indented by depth, with OIDs named,
+    /// strings decoded and the DER inside OCTET and BIT STRINGs expanded.
+    /// Works on any PEM, whatever its label.
+    #[arg(long, conflicts_with_all = ["x5c", "der_scan", "keylog", "compare_to", "stats", "fail_if"])]
+    pub asn1: bool,
+
+    /// Print the secrets of `--keylog` instead of redacting them.
+    #[arg(long, requires = "keylog")]
+    pub show_secrets: bool,
+
+    /// How many threads to convert the parsed entities on. Defaults to the
+    /// number of CPUs.
+    #[arg(long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// A PEM bundle of trusted roots. Self-signed CAs are only marked as
+    /// roots if they're in it.
+    #[arg(long)]
+    pub trust_store: Option<PathBuf>,
+
+    /// A PEM bundle of issuer certs. Each cert's signature is checked with
+    /// its issuer's key, whether the issuer is in this bundle or the input.
+    #[arg(long)]
+    pub issuer: Option<PathBuf>,
+
+    /// Download the issuers of certs whose chain can't be completed from
+    /// the input or `--issuer`, from the caIssuers URL in their
+    /// authorityInfoAccess extension. Followed up to a few levels; fetched
+    /// certs are marked as such.
+    #[arg(long, conflicts_with = "der_scan")]
+    pub aia: bool,
+
+    /// Show where each PEM was found in the input in the text output, e.g.
+    /// `(from bundle.pem:120-160)`. The JSON output always has it as
+    /// `source`.
+    #[arg(long)]
+    pub locations: bool,
+
+    /// Connect to this host and compare the chain it serves with the certs
+    /// in the input, e.g. the `fullchain.pem` it should be serving: which
+    /// certs are missing, extra or in another order. Fails when they differ.
+    #[arg(long, value_name = "HOST", conflicts_with_all = ["x5c", "der_scan"])]
+    pub compare_to: Option<String>,
+
+    /// Check the certs' validity at this time instead of now, e.g.
+    /// `2026-03-01T00:00:00Z`, `2026-03-01` or `+90d` to see what will have
+    /// expired by then. Also makes the `expires_in` and `valid_in` of the
+    /// JSON output reproducible.
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub at: Option<Timestamp>,
+
+    /// Print statistics of the certs instead of the certs: counts by key
+    /// type, signature algorithm, issuer and the year they expire, and how
+    /// many are duplicates or expired. For auditing trust stores and big
+    /// bundles.
+    #[arg(long, conflicts_with_all = ["x5c", "der_scan", "compare_to"])]
+    pub stats: bool,
+
+    /// Fail, after printing the certs as usual, when any cert matches this
+    /// expression, e.g. `expires < 30d or key = rsa and bits < 2048`. See
+    /// `pls find` for the fields and `pls explain-expr` for how an expression
+    /// is read.
+    #[arg(long, value_name = "EXPR", value_parser = Expr::parse, conflicts_with_all = ["x5c", "der_scan", "compare_to"])]
+    pub fail_if: Option<Expr>,
+
+    /// Print the certs as code to paste into a program instead, e.g. to
+    /// embed a private root: a `&[u8]` constant of each cert's DER in Rust,
+    /// a `[]byte` in Go or `bytes` in Python, named after its subject. `pls
+    /// pin generate --emit-snippet` writes the code to pin a key.
+    #[arg(long, value_name = "LANGUAGE", value_enum, conflicts_with_all = ["x5c", "der_scan", "keylog", "asn1", "compare_to", "stats", "fail_if"])]
+    pub emit_snippet: Option<Language>,
+
+    /// Print every path from the leaf up to a root instead of the certs, as
+    /// a tree with each cert's expiry and when each path stops working, e.g.
+    /// for an intermediate cross-signed by an old and a new root. Paths go
+    /// through the certs in the input, `--issuer` and the bundled roots.
+    #[arg(long, conflicts_with_all = ["x5c", "der_scan", "keylog", "asn1", "compare_to", "stats", "fail_if", "emit_snippet"])]
+    pub paths: bool,
 }
 
 impl CommandExt for Parse {
-    async fn run(self, format: Format) -> Result<()> {
-        let data = if let Some(path) = self.file {
-            tracing::info!("parsing certificates from file: {}", path.display());
-            fs::read(&path).with_context(|| format!("Reading {}", path.display()))?
+    async fn run(self, format: Format, mut options: RenderOptions) -> Result<()> {
+        options.locations = self.locations;
+        if let Some(at) = self.at {
+            options.now = at.to_zoned(options.now.time_zone().clone());
+        }
+        let trust_store = self
+            .trust_store
+            .as_ref()
+            .map(|path| {
+                let pem = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+                TrustStore::from_pem(&pem)
+                    .with_context(|| format!("Parsing trust store {}", path.display()))
+            })
+            .transpose()?;
+        let issuers: Vec<SimpleCert> = match &self.issuer {
+            Some(path) => {
+                let pem = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+                X509::stack_from_pem(&pem)
+                    .with_context(|| format!("Parsing issuers {}", path.display()))?
+                    .into_iter()
+                    .map(SimpleCert::from)
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        let file = self.file.as_ref().filter(|path| path.as_os_str() != "-");
+        let (mut input, input_name): (Box<dyn Read>, String) = if let Some((data, name)) =
+            self.inline_data()?
+        {
+            tracing::info!("parsing certificates from {name}");
+            let data = Zeroizing::new(data);
+            (Box::new(io::Cursor::new(data)), name.to_string())
+        } else if let Some(path) = file {
+            tracing::info!(path = %path.display(), "parsing certificates from file");
+            let file = File::open(path).with_context(|| format!("Reading {}", path.display()))?;
+            (Box::new(file), path.display().to_string())
         } else {
             tracing::info!("parsing certificates from stdin");
-            let mut buffer = Vec::new();
 
             let stdin = stdin();
-            if stdin.is_terminal() {
+            // `-` and `--stdin` ask for stdin explicitly, whatever it looks like
+            if stdin.is_terminal() && !self.stdin && self.file.is_none() {
                 // todo: tracing / terminal support
                 tracing::error!("stdin is a TTY, please provide a file or pipe data into stdin");
                 let mut clap_command = <crate::Cli as CommandFactory>::command();
@@ -50,15 +272,394 @@ impl CommandExt for Parse {
                 return Ok(()); // should this be an error?
             }
 
-            io::stdin()
-                .read_to_end(&mut buffer)
-                .context("Reading stdin")?;
-            buffer
+            (Box::new(stdin.lock()), "stdin".to_string())
         };
 
-        let pems = parse_pems(&data).flatten();
-        print_pems(format, pems)?;
+        if !self.x5c && !self.der_scan && !self.keylog && !self.asn1 {
+            let mut head = Vec::with_capacity(16);
+            (&mut input)
+                .take(16)
+                .read_to_end(&mut head)
+                .with_context(|| format!("Reading {input_name}"))?;
+
+            let read_span = tracing::info_span!("read", input = %input_name).entered();
+            let mut pems = Vec::new();
+            let bare_base64 = sniff_bare_base64(&head);
+            if Pkcs7::sniff(&head) {
+                // a DER bundle, which has to be read whole
+                let mut data = head;
+                input
+                    .read_to_end(&mut data)
+                    .with_context(|| format!("Reading {input_name}"))?;
+                let bundle = Pkcs7::from_der(&data)?;
+                pems.push(Pem::from_pkcs7(bundle, 0..data.len()));
+            } else {
+                let mut input: Box<dyn Read> = Box::new(io::Cursor::new(head).chain(input));
+                if bare_base64 {
+                    // one base64 blob without armor has to be read whole to
+                    // be decoded, and is searched for PEMs as usual otherwise
+                    let mut data = Zeroizing::new(Vec::new());
+                    input
+                        .read_to_end(&mut data)
+                        .with_context(|| format!("Reading {input_name}"))?;
+                    pems.extend(Pem::from_bare_base64(&data));
+                    if pems.is_empty() {
+                        tracing::debug!("the input looks like base64 but isn't a cert, CSR or key");
+                    }
+                    input = Box::new(io::Cursor::new(data));
+                }
+
+                if pems.is_empty() {
+                    // PEMs are found a chunk at a time, so a huge log with a few PEMs
+                    // in it doesn't have to fit in memory
+                    let reader = PemReader::new(input);
+                    for pem in reader {
+                        match pem {
+                            Ok(pem) => pems.push(pem),
+                            Err(err) if err.is::<io::Error>() => {
+                                bail!("Reading {input_name}: {err}")
+                            }
+                            Err(err) if self.strict => bail!("Parsing PEM: {err:#}"),
+                            Err(err) => tracing::warn!("skipping PEM: {err:#}"),
+                        }
+                    }
+                }
+            }
+            tracing::debug!(pems = pems.len(), "read");
+            // not held over the AIA fetches
+            read_span.exit();
+
+            if let Some(host) = &self.compare_to {
+                return compare_to(host, &pems, input_name, format, &options).await;
+            }
+
+            if let Some(language) = self.emit_snippet {
+                let certs: Vec<SimpleCert> = pems
+                    .iter()
+                    .filter_map(Pem::as_cert)
+                    .map(|cert| SimpleCert::from(cert.clone()))
+                    .collect();
+                if certs.is_empty() {
+                    bail!("no certs found in {input_name}");
+                }
+                print!("{}", snippet::embed_certs(language, &certs)?);
+                return Ok(());
+            }
+
+            if self.paths {
+                let certs: Vec<SimpleCert> = pems
+                    .iter()
+                    .filter_map(Pem::as_cert)
+                    .map(|cert| SimpleCert::from(cert.clone()))
+                    .chain(issuers.iter().cloned())
+                    .collect();
+                if certs.is_empty() {
+                    bail!("no certs found in {input_name}");
+                }
+                let tree = chain::paths(&certs, chain::DEFAULT_MAX_DEPTH)?;
+                return print_paths(tree, format, &options);
+            }
+
+            // counted before the PEMs are consumed, failed on once they're
+            // printed
+            let failing = match &self.fail_if {
+                Some(expr) => pems
+                    .iter()
+                    .filter_map(Pem::as_cert)
+                    .filter(|cert| {
+                        let cert = SimpleCert::from((*cert).clone());
+                        expr.matches(&cert, options.now.timestamp())
+                    })
+                    .count(),
+                None => 0,
+            };
+            let fail_if = |printed: Result<()>| -> Result<()> {
+                printed?;
+                if let Some(expr) = &self.fail_if {
+                    if failing > 0 {
+                        bail!("{failing} cert(s) matched `{expr}`");
+                    }
+                }
+                Ok(())
+            };
 
-        Ok(())
+            if self.stats {
+                let certs: Vec<SimpleCert> = pems
+                    .iter()
+                    .filter_map(Pem::as_cert)
+                    .cloned()
+                    .map(SimpleCert::from)
+                    .collect();
+                let stats = CertStats::new(&certs, options.now.timestamp());
+                return fail_if(print_stats(stats, format, &options));
+            }
+
+            let jobs = self
+                .jobs
+                .or_else(|| std::thread::available_parallelism().ok())
+                .map_or(1, NonZeroUsize::get);
+            let fetched = if self.aia {
+                let certs: Vec<SimpleCert> = pems
+                    .iter()
+                    .filter_map(Pem::as_cert)
+                    .cloned()
+                    .map(SimpleCert::from)
+                    .collect();
+                chain::fetch_issuers(&certs, &issuers).await
+            } else {
+                Vec::new()
+            };
+            let pem_options = PemOptions {
+                strict: self.strict,
+                jobs,
+                trust_store: trust_store.as_ref(),
+                issuers: &issuers,
+                fetched: &fetched,
+                file: Some(&input_name),
+                keep_pem: !self.no_keep_pem,
+                copy: self.copy,
+                fields: &self.field,
+            };
+            return fail_if(print_pems(format, &options, pems, &pem_options));
+        }
+
+        // the input may be a private key or a keylog
+        let mut data = Zeroizing::new(Vec::new());
+        input
+            .read_to_end(&mut data)
+            .with_context(|| format!("Reading {input_name}"))?;
+
+        if self.asn1 {
+            return asn1(&data, self.strict, format, &options);
+        }
+
+        if self.keylog {
+            let mut keylog = Keylog::parse(&data);
+            if self.strict {
+                if let Some(issue) = keylog.issues.first() {
+                    bail!("line {} of {input_name}: {}", issue.line, issue.message);
+                }
+            }
+            if keylog.sessions.is_empty() {
+                bail!("no TLS secrets found in {input_name}");
+            }
+            if self.show_secrets {
+                output::mark_private();
+            } else {
+                keylog.redact();
+            }
+            return print_keylog(keylog, format, &options);
+        }
+
+        if self.x5c {
+            let source = jose::x5c_source(&data)?;
+            let chains = jose::x5c_chains(&source)?;
+            if chains.is_empty() {
+                bail!("no `x5c` certificate chain found in the input");
+            }
+
+            let mut x5c = Vec::with_capacity(chains.len());
+            for chain in chains {
+                let mut certs: Vec<SimpleCert> =
+                    chain.certs.into_iter().map(SimpleCert::from).collect();
+                for cert in &mut certs {
+                    check_strict(self.strict, "certificate", &cert.issues)?;
+                    if let Some(store) = &trust_store {
+                        cert.apply_trust_store(store);
+                    }
+                }
+                if self.aia {
+                    let fetched = chain::fetch_issuers(&certs, &issuers).await;
+                    certs.extend(fetched);
+                }
+                check_issuer_signatures(&mut certs, &issuers);
+                x5c.push(X5cChain {
+                    kid: chain.kid,
+                    certs,
+                });
+            }
+
+            return print_x5c_chains(x5c, format, &options);
+        }
+
+        der_scan(
+            &data,
+            self.strict,
+            trust_store.as_ref(),
+            &issuers,
+            format,
+            &options,
+        )
+    }
+}
+
+impl Parse {
+    /// The input given with `--data`, `--data-base64` or
+    /// `--from-clipboard`, and which it was.
+    fn inline_data(&self) -> Result<Option<(Vec<u8>, &'static str)>> {
+        if self.from_clipboard {
+            return Ok(Some((clipboard::read()?, "the clipboard")));
+        }
+        if let Some(data) = &self.data {
+            return Ok(Some((unescape_newlines(data).into_bytes(), "--data")));
+        }
+        let Some(base64) = &self.data_base64 else {
+            return Ok(None);
+        };
+        let base64: String = unescape_newlines(base64).split_whitespace().collect();
+        let data = boring::base64::decode_block(&base64)
+            .map_err(|_| eyre!("--data-base64 isn't valid base64"))?;
+        Ok(Some((data, "--data-base64")))
+    }
+}
+
+/// `data` with the `\n` and `\r\n` escapes of a PEM pasted from JSON or a
+/// one-line environment variable turned into newlines.
+fn unescape_newlines(data: &str) -> String {
+    data.replace("\\r\\n", "\n").replace("\\n", "\n")
+}
+
+/// Compare the certs in `pems` with the chain `host` serves, for
+/// `--compare-to`.
+async fn compare_to(
+    host: &str,
+    pems: &[Pem],
+    input_name: String,
+    format: Format,
+    options: &RenderOptions,
+) -> Result<()> {
+    let expected: Vec<SimpleCert> = pems
+        .iter()
+        .filter_map(Pem::as_cert)
+        .cloned()
+        .map(SimpleCert::from)
+        .collect();
+    if expected.is_empty() {
+        bail!("no certs in {input_name} to compare with {host}");
+    }
+    // the order of the file doesn't matter, only the order the server sends
+    let (expected, issues) = chain::order(expected);
+
+    let target = Target::parse(host, None)?;
+    let deployed = fetch_chain(&target, &Resolver::default()).await?;
+    let mut comparison = ChainComparison::new(input_name, target.to_string(), &expected, &deployed);
+    comparison.expected_reordered = issues
+        .into_iter()
+        .find(|issue| matches!(issue, ChainIssue::OutOfOrder { .. }));
+    let matches = comparison.matches();
+    print_comparison(comparison, format, options)?;
+    if !matches {
+        bail!("{target} doesn't serve the expected chain");
+    }
+    Ok(())
+}
+
+/// Dump the ASN.1 of every PEM in `data`, or of `data` itself when it has
+/// none, for `--asn1`.
+fn asn1(data: &[u8], strict: bool, format: Format, options: &RenderOptions) -> Result<()> {
+    let mut dumps = Vec::new();
+    for pem in decode_pems(data) {
+        match pem {
+            Ok((label, der)) => dumps.push(Asn1Dump::new(label, &der)),
+            Err(err) if strict => bail!("Decoding PEM: {err:#}"),
+            Err(err) => tracing::warn!("skipping PEM: {err:#}"),
+        }
+    }
+    if dumps.is_empty() {
+        let head = &data[..data.len().min(16)];
+        let der = if sniff_bare_base64(head) {
+            let base64: String = String::from_utf8_lossy(data).split_whitespace().collect();
+            boring::base64::decode_block(&base64).context("Decoding base64")?
+        } else {
+            data.to_vec()
+        };
+        dumps.push(Asn1Dump::new("DER".to_string(), &der));
+    }
+
+    if strict {
+        if let Some(error) = dumps.iter().find_map(Asn1Dump::first_error) {
+            bail!("Reading the ASN.1: {error}");
+        }
+    }
+    print_asn1(dumps, format, options)
+}
+
+/// Inputs at least this big get a progress line on stderr while scanning.
+const DER_SCAN_PROGRESS_MIN: usize = 16 << 20;
+
+fn der_scan(
+    data: &[u8],
+    strict: bool,
+    trust_store: Option<&TrustStore>,
+    issuers: &[SimpleCert],
+    format: Format,
+    options: &RenderOptions,
+) -> Result<()> {
+    let progress = (data.len() >= DER_SCAN_PROGRESS_MIN).then(Progress::new);
+    let carved = scan_der_certs(data, |scanned| {
+        if let Some(progress) = &progress {
+            progress.set(format!("scanning: {}%", scanned * 100 / data.len()));
+        }
+    });
+    drop(progress);
+    tracing::info!(certs = carved.len(), bytes = data.len(), "carved certs");
+    if carved.is_empty() {
+        bail!("no DER encoded certificate found in the input");
+    }
+
+    let mut spans = Vec::with_capacity(carved.len());
+    let mut certs = Vec::with_capacity(carved.len());
+    for Carved { span, cert } in carved {
+        let mut cert = SimpleCert::from(cert);
+        check_strict(strict, "certificate", &cert.issues)?;
+        if let Some(store) = trust_store {
+            cert.apply_trust_store(store);
+        }
+        spans.push(span);
+        certs.push(cert);
+    }
+    check_issuer_signatures(&mut certs, issuers);
+
+    let certs = spans
+        .into_iter()
+        .zip(certs)
+        .map(|(span, cert)| CarvedCert {
+            offset: span.start,
+            length: span.len(),
+            cert,
+        })
+        .collect();
+    print_carved_certs(certs, format, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_data() {
+        let pem = include_str!("../../test-data/certs/lan-fish.pem");
+        let escaped = pem.replace('\n', "\\n");
+        assert!(!escaped.contains('\n'));
+        let parse = Parse {
+            data: Some(escaped),
+            ..Default::default()
+        };
+        let (data, name) = parse.inline_data().unwrap().unwrap();
+        assert_eq!(data, pem.as_bytes());
+        assert_eq!(name, "--data");
+
+        let parse = Parse {
+            data_base64: Some(boring::base64::encode_block(pem.as_bytes())),
+            ..Default::default()
+        };
+        assert_eq!(parse.inline_data().unwrap().unwrap().0, pem.as_bytes());
+
+        let parse = Parse {
+            data_base64: Some("not base64!".to_string()),
+            ..Default::default()
+        };
+        assert!(parse.inline_data().is_err());
+        assert!(Parse::default().inline_data().unwrap().is_none());
     }
 }
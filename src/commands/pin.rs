@@ -0,0 +1,291 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use color_eyre::eyre::{bail, Context, Result};
+
+use crate::{
+    components::{
+        pin::{print_pin_check, print_pins, PinCheckReport, PinnedCert},
+        same_key::{KeyGroup, SameKeyReport},
+    },
+    snippet::{self, Language},
+    x509::SimpleCert,
+};
+
+use super::{
+    connect::{fetch_chain, Target},
+    same_key::key_users,
+    CommandExt, DnsArgs, Format, RenderOptions,
+};
+
+/// Make and check public key pins: the base64 SHA-256 of a
+/// SubjectPublicKeyInfo, as HPKP, Android's network security config,
+/// OkHttp's `CertificatePinner` and most pinning code use them.
+#[derive(Clone, Debug, Parser)]
+pub struct Pin {
+    #[command(subcommand)]
+    pub command: PinCommand,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+pub enum PinCommand {
+    Generate(PinGenerate),
+    Check(PinCheck),
+}
+
+/// The pin of every cert, CSR and key in the files. A cert and its key have
+/// the same pin, so they're listed together.
+#[derive(Clone, Debug, Parser)]
+pub struct PinGenerate {
+    /// PEM files with certs, CSRs, private or public keys.
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+
+    /// Print the pins as a snippet to paste into a config or code instead:
+    /// an HPKP header, an Android network security config or an OkHttp
+    /// `CertificatePinner`.
+    #[arg(long, value_enum)]
+    pub snippet: Option<Snippet>,
+
+    /// Print code that checks a server's key against the pins instead: a
+    /// rustls-friendly `is_pinned` in Rust, a `tls.Config`
+    /// `VerifyConnection` in Go or an `is_pinned` in Python.
+    #[arg(long, value_name = "LANGUAGE", value_enum, conflicts_with = "snippet")]
+    pub emit_snippet: Option<Language>,
+
+    /// The host the pins are for, in the Android and OkHttp snippets.
+    #[arg(long, default_value = "example.com", requires = "snippet")]
+    pub host: String,
+}
+
+/// Where the pins of `pin generate --snippet` go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Snippet {
+    /// A `Public-Key-Pins` header.
+    Hpkp,
+    /// A `network_security_config.xml`.
+    Android,
+    /// An OkHttp `CertificatePinner`, in Kotlin.
+    Okhttp,
+}
+
+/// Connect to a host and check whether any cert it sends matches one of the
+/// pins, as a pinning client would. Fails when none does.
+#[derive(Clone, Debug, Parser)]
+pub struct PinCheck {
+    /// The host, as `connect` takes it: `example.com`, `example.com:8443`,
+    /// ...
+    pub host: String,
+
+    /// The port to connect to. Defaults to the port in the host, or 443.
+    #[arg(long, short)]
+    pub port: Option<u16>,
+
+    /// A pin to accept: `sha256/<base64>` as OkHttp writes them, bare
+    /// base64, or `pin-sha256="<base64>"` from an HPKP header.
+    #[arg(long = "pin", value_name = "PIN", required = true, value_parser = parse_pin)]
+    pub pins: Vec<String>,
+
+    #[command(flatten)]
+    dns: DnsArgs,
+}
+
+impl CommandExt for Pin {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        match self.command {
+            PinCommand::Generate(generate) => generate.run(format, options).await,
+            PinCommand::Check(check) => check.run(format, options).await,
+        }
+    }
+}
+
+impl CommandExt for PinGenerate {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let mut users = Vec::new();
+        for path in &self.files {
+            let data = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+            let found = key_users(&path.display().to_string(), &data)?;
+            if found.is_empty() {
+                bail!("no cert, CSR or key found in {}", path.display());
+            }
+            users.extend(found);
+        }
+        let keys = SameKeyReport::new(users).keys;
+
+        if let Some(language) = self.emit_snippet {
+            let pins: Vec<&str> = keys
+                .iter()
+                .map(|group| group.spki.pin_sha256.as_str())
+                .collect();
+            print!("{}", snippet::pins(language, &pins)?);
+            return Ok(());
+        }
+        match self.snippet {
+            Some(snippet) => print!("{}", snippet.render(&keys, &self.host)),
+            None => print_pins(keys, format, &options)?,
+        }
+        Ok(())
+    }
+}
+
+impl CommandExt for PinCheck {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let target = Target::parse(&self.host, self.port)?;
+        let certs = fetch_chain(&target, &self.dns.resolver()).await?;
+
+        let report = check_pins(target.to_string(), &certs, self.pins);
+        let matched = report.matched;
+        print_pin_check(report, format, &options)?;
+        if !matched {
+            bail!("{target} doesn't match any of the pins");
+        }
+        Ok(())
+    }
+}
+
+/// Check the `certs` a server sent against the base64 `pins`.
+fn check_pins(target: String, certs: &[SimpleCert], pins: Vec<String>) -> PinCheckReport {
+    let certs: Vec<PinnedCert> = certs
+        .iter()
+        .map(|cert| {
+            let pin_sha256 = cert.public_key.spki.pin_sha256.clone();
+            PinnedCert {
+                subject: cert.subject.name.clone(),
+                pinned: pins.contains(&pin_sha256),
+                pin_sha256,
+            }
+        })
+        .collect();
+
+    PinCheckReport {
+        target,
+        matched: certs.iter().any(|cert| cert.pinned),
+        pins,
+        certs,
+    }
+}
+
+/// The base64 of a pin in any of the forms `--pin` takes.
+fn parse_pin(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let pin = value
+        .strip_prefix("sha256/")
+        .or_else(|| {
+            value
+                .strip_prefix("pin-sha256=")
+                .map(|pin| pin.trim_matches('"'))
+        })
+        .unwrap_or(value);
+
+    match boring::base64::decode_block(pin) {
+        Ok(hash) if hash.len() == 32 => Ok(pin.to_string()),
+        Ok(hash) => Err(format!(
+            "a SHA-256 pin is 32 bytes, {pin:?} is {}",
+            hash.len()
+        )),
+        Err(_) => Err(format!("{pin:?} isn't base64")),
+    }
+}
+
+impl Snippet {
+    fn render(self, keys: &[KeyGroup], host: &str) -> String {
+        let pins = keys.iter().map(|group| &group.spki.pin_sha256);
+        match self {
+            Snippet::Hpkp => {
+                let pins: Vec<String> = pins.map(|pin| format!("pin-sha256=\"{pin}\"")).collect();
+                // 60 days
+                format!("Public-Key-Pins: {}; max-age=5184000\n", pins.join("; "))
+            }
+            Snippet::Android => {
+                let pins: String = pins
+                    .map(|pin| format!("            <pin digest=\"SHA-256\">{pin}</pin>\n"))
+                    .collect();
+                format!(
+                    "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<network-security-config>
+    <domain-config>
+        <domain includeSubdomains=\"true\">{host}</domain>
+        <pin-set>
+{pins}        </pin-set>
+    </domain-config>
+</network-security-config>
+"
+                )
+            }
+            Snippet::Okhttp => {
+                let pins: String = pins
+                    .map(|pin| format!("    .add(\"{host}\", \"sha256/{pin}\")\n"))
+                    .collect();
+                format!("val certificatePinner = CertificatePinner.Builder()\n{pins}    .build()\n")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn pin_forms() {
+        let pin = "YLh1dUR9y6Kja30RrAn7JKnbQG/uEtLMkBgFF2Fuihg=";
+        assert_eq!(parse_pin(pin).unwrap(), pin);
+        assert_eq!(parse_pin(&format!("sha256/{pin}")).unwrap(), pin);
+        assert_eq!(parse_pin(&format!("pin-sha256=\"{pin}\"")).unwrap(), pin);
+
+        assert!(parse_pin("sha256/bm90IGEgcGlu").is_err());
+        assert!(parse_pin("not base64!").is_err());
+    }
+
+    #[test]
+    fn snippets_list_each_key_once() {
+        let data = [
+            &include_bytes!("../../test-data/certs/rsa-self-signed.pem")[..],
+            include_bytes!("../../test-data/private-keys/begin-rsa-private-key.pem"),
+        ]
+        .concat();
+        let keys = SameKeyReport::new(key_users("bundle.pem", &data).unwrap()).keys;
+        assert_eq!(keys.len(), 1);
+
+        let pin = &keys[0].spki.pin_sha256;
+        assert_eq!(
+            Snippet::Okhttp.render(&keys, "example.com"),
+            format!("val certificatePinner = CertificatePinner.Builder()\n    .add(\"example.com\", \"sha256/{pin}\")\n    .build()\n")
+        );
+        assert_eq!(
+            Snippet::Hpkp.render(&keys, "example.com"),
+            format!("Public-Key-Pins: pin-sha256=\"{pin}\"; max-age=5184000\n")
+        );
+        assert!(Snippet::Android
+            .render(&keys, "example.com")
+            .contains(&format!("<pin digest=\"SHA-256\">{pin}</pin>")));
+    }
+
+    #[test]
+    fn any_cert_can_match() {
+        let certs: Vec<SimpleCert> =
+            X509::stack_from_pem(include_bytes!("../../test-data/certs/chain.pem"))
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect();
+        let intermediate = certs[1].public_key.spki.pin_sha256.clone();
+
+        let report = check_pins("lan.fish:443".into(), &certs, vec![intermediate]);
+        assert!(report.matched);
+        assert_eq!(
+            report
+                .certs
+                .iter()
+                .map(|cert| cert.pinned)
+                .collect::<Vec<_>>(),
+            [false, true, false]
+        );
+
+        let report = check_pins("lan.fish:443".into(), &certs, vec![]);
+        assert!(!report.matched);
+    }
+}
@@ -0,0 +1,124 @@
+use boring::bn::BigNum;
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::x509::Serial;
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Generate random values for PKI workflows, e.g. the serial number of a cert
+/// made by hand, without an `openssl rand | head` pipeline. Everything comes
+/// from boring's CSPRNG.
+#[derive(Clone, Debug, Parser)]
+pub struct Random {
+    #[command(subcommand)]
+    pub command: RandomCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum RandomCommand {
+    Serial(RandomSerial),
+    Bytes(RandomBytes),
+}
+
+/// A random, positive serial number, in the `--serial-format` (bare hex by
+/// default). The JSON output has every form.
+///
+/// The top bit is cleared so the number is positive, and the one after it set
+/// so it's always `--bytes` long: `8 * bytes - 2` random bits.
+#[derive(Clone, Debug, Parser)]
+pub struct RandomSerial {
+    /// How many bytes long the serial is. RFC 5280 allows at most 20, and the
+    /// Baseline Requirements want at least 64 random bits.
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u8).range(9..=20))]
+    pub bytes: u8,
+}
+
+/// Random bytes, hex encoded unless `--base64`.
+#[derive(Clone, Debug, Parser)]
+pub struct RandomBytes {
+    /// How many bytes to generate.
+    #[arg(default_value_t = 32)]
+    pub length: usize,
+
+    /// Print them base64 encoded instead of hex.
+    #[arg(long)]
+    pub base64: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Bytes {
+    length: usize,
+    hex: String,
+    base64: String,
+}
+
+impl CommandExt for Random {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        match self.command {
+            RandomCommand::Serial(serial) => serial.run(format, options).await,
+            RandomCommand::Bytes(bytes) => bytes.run(format, options).await,
+        }
+    }
+}
+
+impl CommandExt for RandomSerial {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let serial = random_serial(self.bytes.into())?;
+        if format.is_json() {
+            println!("{}", format.to_json(&serial)?);
+        } else {
+            println!("{}", options.serial(&serial));
+        }
+        Ok(())
+    }
+}
+
+impl CommandExt for RandomBytes {
+    async fn run(self, format: Format, _options: RenderOptions) -> Result<()> {
+        let mut bytes = vec![0; self.length];
+        boring::rand::rand_bytes(&mut bytes)?;
+        let bytes = Bytes {
+            length: bytes.len(),
+            hex: hex::encode(&bytes),
+            base64: boring::base64::encode_block(&bytes),
+        };
+
+        if format.is_json() {
+            println!("{}", format.to_json(&bytes)?);
+        } else if self.base64 {
+            println!("{}", bytes.base64);
+        } else {
+            println!("{}", bytes.hex);
+        }
+        Ok(())
+    }
+}
+
+/// A positive serial number exactly `len` bytes long.
+fn random_serial(len: usize) -> Result<Serial> {
+    let mut bytes = vec![0; len];
+    boring::rand::rand_bytes(&mut bytes)?;
+    bytes[0] = (bytes[0] & 0x7f) | 0x40;
+    Serial::from_bn(&BigNum::from_slice(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serials_are_positive_and_full_length() {
+        for len in [9, 16, 20] {
+            let serial = random_serial(len).unwrap();
+            assert_eq!(serial.hex.len(), len * 2, "{serial:?}");
+            assert!(!serial.colon_hex.starts_with('-'), "{serial:?}");
+            assert!(serial.colon_hex.as_bytes()[0] < b'8', "{serial:?}");
+        }
+        assert_ne!(
+            random_serial(16).unwrap().hex,
+            random_serial(16).unwrap().hex
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::PathBuf;
+
+use boring::pkey::PKey;
+use clap::Parser;
+use color_eyre::eyre::{bail, eyre, Context, Result};
+
+use crate::{
+    components::same_key::{print_same_key, KeyUser, KeyUserKind, SameKeyReport},
+    pem::{parse_pems, ParsedPem},
+    x509::{print_name, SpkiHash},
+};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Check whether certs, CSRs and keys use the same key pair, e.g. that a CSR
+/// was generated from the key you think it was. Every PEM in the files is
+/// grouped by the SHA-256 of its SubjectPublicKeyInfo.
+///
+/// Fails when the inputs use more than one key.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct SameKey {
+    /// PEM files with the certs, CSRs, private or public keys to compare.
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+}
+
+impl CommandExt for SameKey {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let mut users = Vec::new();
+        for path in &self.files {
+            let data = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+            let found = key_users(&path.display().to_string(), &data)?;
+            if found.is_empty() {
+                bail!("no cert, CSR or key found in {}", path.display());
+            }
+            users.extend(found);
+        }
+
+        let report = SameKeyReport::new(users);
+        let keys = report.keys.len();
+        print_same_key(report, format, &options)?;
+
+        if keys > 1 {
+            bail!("the inputs use {keys} different keys");
+        }
+        Ok(())
+    }
+}
+
+/// The SPKI hash of every cert, CSR and key in `data`, read from `file`.
+pub(crate) fn key_users(file: &str, data: &[u8]) -> Result<Vec<(SpkiHash, KeyUser)>> {
+    let mut users = Vec::new();
+    for pem in parse_pems(data) {
+        let pem = pem.map_err(|err| eyre!("Parsing PEM in {file}: {err:#}"))?;
+
+        let (spki, kind, name) = match pem.into_parsed_pem() {
+            ParsedPem::Cert(cert) => (
+                SpkiHash::new(&cert.public_key()?)?,
+                KeyUserKind::Cert,
+                Some(print_name(cert.subject_name())?),
+            ),
+            ParsedPem::CertReq(csr) => (
+                SpkiHash::new(&csr.public_key()?)?,
+                KeyUserKind::Csr,
+                Some(print_name(csr.subject_name())?),
+            ),
+            ParsedPem::PublicKey(key) => (SpkiHash::new(&key)?, KeyUserKind::PublicKey, None),
+            ParsedPem::RsaPublicKey(rsa) => (
+                SpkiHash::new(&PKey::from_rsa(rsa)?)?,
+                KeyUserKind::PublicKey,
+                None,
+            ),
+            ParsedPem::PrivateKey(key) => (SpkiHash::new(&key)?, KeyUserKind::PrivateKey, None),
+            ParsedPem::RsaPrivateKey(rsa) => (
+                SpkiHash::new(&PKey::from_rsa(rsa)?)?,
+                KeyUserKind::PrivateKey,
+                None,
+            ),
+            ParsedPem::ECPrivateKey(ec) => (
+                SpkiHash::new(&PKey::from_ec_key(ec)?)?,
+                KeyUserKind::PrivateKey,
+                None,
+            ),
+            ParsedPem::Pkcs7(bundle) => {
+                // each cert in a bundle is compared on its own
+                for cert in bundle.certs {
+                    let user = KeyUser {
+                        file: file.to_string(),
+                        kind: KeyUserKind::Cert,
+                        name: Some(print_name(cert.subject_name())?),
+                    };
+                    users.push((SpkiHash::new(&cert.public_key()?)?, user));
+                }
+                continue;
+            }
+            ParsedPem::Crl(_) => continue,
+        };
+
+        let user = KeyUser {
+            file: file.to_string(),
+            kind,
+            name,
+        };
+        users.push((spki, user));
+    }
+
+    Ok(users)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_by_key() {
+        let inputs: [(&str, &[u8]); 3] = [
+            (
+                "cert.pem",
+                include_bytes!("../../test-data/certs/rsa-self-signed.pem"),
+            ),
+            (
+                "key.pem",
+                include_bytes!("../../test-data/private-keys/begin-rsa-private-key.pem"),
+            ),
+            (
+                "other.pem",
+                include_bytes!("../../test-data/public-keys/ed25519.pem"),
+            ),
+        ];
+        let users = inputs
+            .into_iter()
+            .flat_map(|(file, data)| key_users(file, data).unwrap());
+
+        let report = SameKeyReport::new(users);
+        assert!(!report.same_key);
+        assert_eq!(report.keys.len(), 2);
+
+        let kinds: Vec<_> = report.keys[0]
+            .used_by
+            .iter()
+            .map(|user| (user.file.as_str(), user.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("cert.pem", KeyUserKind::Cert),
+                ("key.pem", KeyUserKind::PrivateKey)
+            ]
+        );
+        assert_eq!(
+            report.keys[0].spki.spki_sha256,
+            "3d83e477b30d2233029d9009b13b195b72c0eda46bcabee27b2ec9cd02005f1f"
+        );
+        assert_eq!(report.keys[1].used_by[0].kind, KeyUserKind::PublicKey);
+    }
+}
@@ -0,0 +1,79 @@
+use clap::{Parser, ValueEnum};
+use color_eyre::eyre::Result;
+use schemars::schema_for;
+
+use crate::{
+    components::connection::ConnectionWithCerts,
+    x509::{SimpleCert, SimpleCsr, SimplePrivateKey, SimplePublicKey},
+};
+
+use super::{CommandExt, Format, RenderOptions, Versioned};
+
+/// Print the JSON Schema of an entity in the `--json` output, so integrations
+/// can validate what `pls` prints. Every JSON document (or entity, in a list)
+/// has a `schema_version` that's bumped on breaking changes.
+///
+/// The schema is always printed as JSON, whatever the output format.
+#[derive(Clone, Debug, Parser)]
+pub struct Schema {
+    /// The entity to print the schema of.
+    #[arg(value_enum)]
+    pub kind: SchemaKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// A certificate, as in `parse` or `connect`.
+    Cert,
+    /// A certificate signing request.
+    Csr,
+    /// A private key.
+    Key,
+    /// A public key.
+    PublicKey,
+    /// The result of `connect`: the TLS connection and the server's certs.
+    Connection,
+}
+
+impl SchemaKind {
+    pub fn schema(self) -> schemars::Schema {
+        match self {
+            SchemaKind::Cert => schema_for!(Versioned<SimpleCert>),
+            SchemaKind::Csr => schema_for!(Versioned<SimpleCsr>),
+            SchemaKind::Key => schema_for!(Versioned<SimplePrivateKey>),
+            SchemaKind::PublicKey => schema_for!(Versioned<SimplePublicKey>),
+            SchemaKind::Connection => schema_for!(Versioned<ConnectionWithCerts>),
+        }
+    }
+}
+
+impl CommandExt for Schema {
+    async fn run(self, _format: Format, _options: RenderOptions) -> Result<()> {
+        println!("{}", serde_json::to_string_pretty(&self.kind.schema())?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schemas_are_versioned() {
+        for kind in SchemaKind::value_variants() {
+            let schema = serde_json::to_value(kind.schema()).unwrap();
+            let properties = &schema["properties"];
+            assert!(
+                properties["schema_version"].is_object(),
+                "{kind:?}: {schema}"
+            );
+        }
+
+        let cert = serde_json::to_value(SchemaKind::Cert.schema()).unwrap();
+        for field in ["subject", "issuer", "not_after", "serial", "sha256", "pem"] {
+            assert!(cert["properties"][field].is_object(), "{field}: {cert}");
+        }
+        // skipped fields stay out of the schema
+        assert!(cert["properties"]["_cert"].is_null(), "{cert}");
+    }
+}
@@ -0,0 +1,119 @@
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Parser};
+use color_eyre::eyre::{Context, Result};
+use tokio::net::TcpListener;
+
+use crate::{
+    components::serve::{print_handshake, print_server_info, ServerInfo},
+    server::{self, ClientAuth, Identity, Scenario},
+    x509::SimpleCert,
+};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Run a TLS server to point clients at: a quick local endpoint to try a
+/// client, proxy or load balancer against. It answers HTTP/1.1 requests with
+/// what was negotiated, and prints every handshake, including the ones that
+/// fail. Runs until it's stopped.
+///
+/// With `--self-signed` it presents a throwaway chain made at startup, a
+/// root, an intermediate and a leaf for `--name`. `--write-root` saves the
+/// root to trust it, e.g. with `curl --cacert`.
+///
+/// `--scenario` misbehaves on purpose, to check that a client notices: an
+/// expired or not yet valid leaf, a leaf for the wrong host, a missing
+/// intermediate, a self-signed leaf or only TLS 1.0.
+#[derive(Clone, Debug, Parser)]
+#[command(group(ArgGroup::new("identity").required(true).args(["self_signed", "cert"])))]
+pub struct Serve {
+    /// Present a freshly generated chain.
+    #[arg(long)]
+    pub self_signed: bool,
+
+    /// Present this PEM cert, followed by its chain, instead.
+    #[arg(long, value_name = "FILE", requires = "key")]
+    pub cert: Option<PathBuf>,
+
+    /// The private key of `--cert`.
+    #[arg(long, value_name = "FILE", requires = "cert")]
+    pub key: Option<PathBuf>,
+
+    /// A DNS name or IP address for the generated leaf. Repeat it for more.
+    #[arg(
+        long = "name",
+        value_name = "NAME",
+        default_values = ["localhost", "127.0.0.1", "::1"],
+        conflicts_with = "cert"
+    )]
+    pub names: Vec<String>,
+
+    /// The address to listen on. Port 0 picks a free port.
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8443")]
+    pub listen: SocketAddr,
+
+    /// The ALPN protocols to select from, comma separated, most preferred
+    /// first.
+    #[arg(long, value_delimiter = ',', default_value = "http/1.1")]
+    pub alpn: Vec<String>,
+
+    /// Whether to ask clients for a cert, to try mTLS clients.
+    #[arg(long, value_enum, default_value_t)]
+    pub client_auth: ClientAuth,
+
+    /// Misbehave in this way.
+    #[arg(long, value_enum, conflicts_with = "cert")]
+    pub scenario: Option<Scenario>,
+
+    /// Write the generated root to this file as PEM.
+    #[arg(long, value_name = "FILE", requires = "self_signed")]
+    pub write_root: Option<PathBuf>,
+}
+
+impl CommandExt for Serve {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let identity = match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => Identity::from_files(cert, key)?,
+            _ => Identity::generate(&self.names, self.scenario)?,
+        };
+        let acceptor = server::acceptor(&identity, &self.alpn, self.client_auth, self.scenario)?;
+        let listener = TcpListener::bind(self.listen)
+            .await
+            .with_context(|| format!("Listening on {}", self.listen))?;
+
+        let root = identity.root.clone().map(|root| SimpleCert::from(root).pem);
+        if let (Some(file), Some(root)) = (&self.write_root, &root) {
+            fs::write(file, root).with_context(|| format!("Writing {}", file.display()))?;
+        }
+        let leaf = SimpleCert::from(identity.chain[0].clone());
+        let sans = &leaf.subject.sans;
+        let names = sans
+            .dns
+            .iter()
+            .cloned()
+            .chain(sans.ip.iter().map(ToString::to_string))
+            .collect();
+        let info = ServerInfo {
+            address: listener.local_addr()?.to_string(),
+            subject: leaf.subject.name.clone(),
+            names,
+            sha256: leaf.fingerprints.sha256.clone(),
+            alpn: self.alpn.clone(),
+            client_auth: self.client_auth,
+            scenario: self.scenario,
+            root,
+            root_file: self.write_root.clone(),
+        };
+        print_server_info(info, format, &options)?;
+
+        server::serve(listener, acceptor, move |handshake| {
+            if let Err(err) = print_handshake(handshake, format, &options) {
+                tracing::warn!("failed to print a handshake: {err}");
+            }
+        })
+        .await;
+        Ok(())
+    }
+}
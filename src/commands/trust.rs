@@ -0,0 +1,530 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::{bail, eyre, Context, Result};
+
+use crate::{
+    components::{
+        trust::{print_trust_changes, Outcome, TrustChange},
+        x509::pem_header,
+    },
+    pem::{parse_pems, ParsedPem},
+    verifier,
+    x509::SimpleCert,
+};
+
+use super::{CommandExt, Format, RenderOptions};
+
+/// Work with trust stores: export the roots `pls` trusts, or add a CA, e.g.
+/// one for local development, to the OS and browser trust stores without
+/// looking up how each of them does it.
+#[derive(Clone, Debug, Parser)]
+pub struct Trust {
+    #[command(subcommand)]
+    pub command: TrustCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum TrustCommand {
+    Export(TrustExport),
+    Add(TrustAdd),
+}
+
+/// Print the Mozilla roots bundled into `pls` (the roots of `--verifier
+/// bundled`) as a PEM bundle, e.g. for a container without a CA bundle.
+/// `--json` prints them parsed.
+#[derive(Clone, Debug, Parser)]
+pub struct TrustExport {}
+
+/// Trust a CA cert: add it to the system trust store, or with `--local` to
+/// the current user's.
+///
+/// On Linux the system store is updated with `update-ca-certificates` (or
+/// `update-ca-trust`), and `--local` adds the cert to the user's NSS
+/// database (`~/.pki/nssdb`, used by Chrome) with NSS's `certutil`. macOS
+/// uses `security add-trusted-cert` and Windows `certutil`. Every store that
+/// was changed is listed.
+#[derive(Clone, Debug, Parser)]
+pub struct TrustAdd {
+    /// A PEM or DER file with the CA cert. Other PEMs in it (e.g. its key)
+    /// are ignored.
+    pub file: PathBuf,
+
+    /// Add the cert to the current user's trust store rather than the
+    /// system's, which doesn't need root (or an administrator).
+    #[arg(long)]
+    pub local: bool,
+
+    /// Only show the commands that would change the trust stores.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl CommandExt for Trust {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        match self.command {
+            TrustCommand::Export(export) => export.run(format, options).await,
+            TrustCommand::Add(add) => add.run(format, options).await,
+        }
+    }
+}
+
+impl CommandExt for TrustExport {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let roots: Vec<SimpleCert> = verifier::bundled_roots()?
+            .into_iter()
+            .map(SimpleCert::from)
+            .collect();
+
+        if format.is_json() {
+            format.print_json_list(&roots)?;
+        } else {
+            // exporting is all about the PEMs
+            for root in &roots {
+                options.print_pem(|| pem_header(root, &options), &root.pem);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CommandExt for TrustAdd {
+    async fn run(self, format: Format, options: RenderOptions) -> Result<()> {
+        let cert = read_ca(&self.file)?;
+        let name = cert
+            .subject
+            .dn
+            .common_name
+            .clone()
+            .unwrap_or_else(|| "pls local CA".to_string());
+        let file = format!("pls-{}.crt", &cert.fingerprints.sha256[..16]);
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+
+        // the stores get the cert alone, as PEM, whatever else the file has
+        let dir = PrivateDir::new()?;
+        let pem = dir.write(&file, cert.pem.as_bytes())?;
+
+        let steps = steps(
+            Os::current(),
+            &pem,
+            &name,
+            &file,
+            &cert.fingerprints.sha1,
+            self.local,
+            home.as_deref(),
+        )?;
+        let (changes, failed) = apply(&steps, self.dry_run);
+
+        // what was changed before a step failed is listed either way
+        print_trust_changes(name, changes, format, &options)?;
+        match failed {
+            Some(err) => Err(err.wrap_err("Not every trust store was changed, see above")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Run `steps` in order, up to the first that fails, which is returned too.
+fn apply(steps: &[Step], dry_run: bool) -> (Vec<TrustChange>, Option<color_eyre::Report>) {
+    let mut changes = Vec::with_capacity(steps.len());
+    let mut failed = None;
+    for step in steps {
+        let outcome = match (&failed, dry_run) {
+            (Some(_), _) => Outcome::Skipped,
+            (None, true) => Outcome::DryRun,
+            (None, false) => match step.run() {
+                Ok(()) => Outcome::Done,
+                Err(err) => {
+                    let error = format!("{err:#}");
+                    failed = Some(err);
+                    Outcome::Failed { error }
+                }
+            },
+        };
+        changes.push(TrustChange {
+            store: step.store.to_string(),
+            command: step.to_string(),
+            undo: step.undo.clone(),
+            outcome,
+        });
+    }
+    (changes, failed)
+}
+
+/// A new directory in the temp dir that only the current user can use, so
+/// nobody can swap the cert for another before the stores read it. It's
+/// removed with everything in it when dropped.
+struct PrivateDir(PathBuf);
+
+impl PrivateDir {
+    fn new() -> Result<Self> {
+        let mut suffix = [0; 8];
+        boring::rand::rand_bytes(&mut suffix)?;
+        let path = std::env::temp_dir().join(format!("pls-trust-{}", hex::encode(suffix)));
+
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        // not recursive, so an existing directory is an error
+        builder
+            .create(&path)
+            .with_context(|| format!("Creating {}", path.display()))?;
+        Ok(Self(path))
+    }
+
+    /// Write `data` to a new file `name` in the directory, readable only by
+    /// its owner.
+    fn write(&self, name: &str, data: &[u8]) -> Result<PathBuf> {
+        use std::io::Write as _;
+
+        let path = self.0.join(name);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(data))
+            .with_context(|| format!("Writing {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The one CA cert in the file at `path`, PEM or DER.
+fn read_ca(path: &Path) -> Result<SimpleCert> {
+    let data = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+    let mut certs = Vec::new();
+    if let Ok(cert) = boring::x509::X509::from_der(&data) {
+        certs.push(cert);
+    } else {
+        for pem in parse_pems(&data) {
+            if let Ok(ParsedPem::Cert(cert)) = pem.map(|pem| pem.into_parsed_pem()) {
+                certs.push(cert);
+            }
+        }
+    }
+
+    let cert = match <[_; 1]>::try_from(certs) {
+        Ok([cert]) => SimpleCert::from(cert),
+        Err(certs) if certs.is_empty() => bail!("no cert found in {}", path.display()),
+        Err(certs) => bail!(
+            "{} has {} certs, trust them one at a time",
+            path.display(),
+            certs.len()
+        ),
+    };
+    if !cert.is_ca {
+        bail!(
+            "{} isn't a CA cert (basicConstraints doesn't allow it to issue certs)",
+            cert.subject.name
+        );
+    }
+    Ok(cert)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Os {
+    Linux,
+    Macos,
+    Windows,
+    Other,
+}
+
+impl Os {
+    fn current() -> Self {
+        match std::env::consts::OS {
+            "linux" => Os::Linux,
+            "macos" => Os::Macos,
+            "windows" => Os::Windows,
+            _ => Os::Other,
+        }
+    }
+}
+
+/// One command changing a trust store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    /// What the command changes, e.g. `system (Debian)`.
+    store: &'static str,
+    program: &'static str,
+    args: Vec<String>,
+    /// The command that takes the change back out.
+    undo: Option<String>,
+}
+
+impl Step {
+    fn new(store: &'static str, program: &'static str, args: &[&str]) -> Self {
+        Self {
+            store,
+            program,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            undo: None,
+        }
+    }
+
+    fn undo(mut self, program: &'static str, args: &[&str]) -> Self {
+        self.undo = Some(Step::new(self.store, program, args).to_string());
+        self
+    }
+
+    fn run(&self) -> Result<()> {
+        tracing::info!("running {self}");
+        let status = Command::new(self.program)
+            .args(&self.args)
+            .status()
+            .with_context(|| format!("running {}, is it installed?", self.program))?;
+        if !status.success() {
+            bail!("`{self}` failed with {status}");
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            match arg.contains([' ', ',', '\'']) {
+                true => write!(f, " '{}'", arg.replace('\'', "'\\''"))?,
+                false => write!(f, " {arg}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The commands that add the cert at `path` to the trust store of `os`.
+/// `name` is its nickname in the stores that want one and `file` the file
+/// name it's copied to in the ones that are directories. `sha1` is its
+/// SHA-1 fingerprint, which finds it again to undo a change.
+fn steps(
+    os: Os,
+    path: &Path,
+    name: &str,
+    file: &str,
+    sha1: &str,
+    local: bool,
+    home: Option<&Path>,
+) -> Result<Vec<Step>> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| eyre!("{} isn't valid UTF-8", path.display()))?;
+
+    Ok(match (os, local) {
+        (Os::Linux, true) => {
+            let home = home.ok_or_else(|| eyre!("$HOME isn't set, so there's no NSS database"))?;
+            let db = format!("sql:{}", home.join(".pki/nssdb").display());
+            vec![Step::new(
+                "NSS database (~/.pki/nssdb)",
+                "certutil",
+                &["-d", &db, "-A", "-t", "C,,", "-n", name, "-i", path],
+            )
+            .undo("certutil", &["-d", &db, "-D", "-n", name])]
+        }
+        (Os::Linux, false) if Path::new("/usr/local/share/ca-certificates").is_dir() => {
+            let dest = format!("/usr/local/share/ca-certificates/{file}");
+            vec![
+                Step::new("system (Debian)", "install", &["-m", "644", path, &dest])
+                    .undo("rm", &[&dest]),
+                Step::new("system (Debian)", "update-ca-certificates", &[])
+                    .undo("update-ca-certificates", &["--fresh"]),
+            ]
+        }
+        (Os::Linux, false) if Path::new("/etc/pki/ca-trust/source/anchors").is_dir() => {
+            let dest = format!("/etc/pki/ca-trust/source/anchors/{file}");
+            vec![
+                Step::new("system (Fedora)", "install", &["-m", "644", path, &dest])
+                    .undo("rm", &[&dest]),
+                Step::new("system (Fedora)", "update-ca-trust", &["extract"])
+                    .undo("update-ca-trust", &["extract"]),
+            ]
+        }
+        (Os::Linux, false) => {
+            bail!("no known system trust store on this Linux distribution, try `--local`")
+        }
+        (Os::Macos, true) => {
+            let home =
+                home.ok_or_else(|| eyre!("$HOME isn't set, so there's no login keychain"))?;
+            let keychain = home.join("Library/Keychains/login.keychain-db");
+            let keychain = keychain.display().to_string();
+            vec![Step::new(
+                "login keychain",
+                "security",
+                &["add-trusted-cert", "-r", "trustRoot", "-k", &keychain, path],
+            )
+            .undo("security", &["delete-certificate", "-Z", sha1, &keychain])]
+        }
+        (Os::Macos, false) => vec![Step::new(
+            "system keychain",
+            "security",
+            &[
+                "add-trusted-cert",
+                "-d",
+                "-r",
+                "trustRoot",
+                "-k",
+                "/Library/Keychains/System.keychain",
+                path,
+            ],
+        )
+        .undo(
+            "security",
+            &[
+                "delete-certificate",
+                "-Z",
+                sha1,
+                "/Library/Keychains/System.keychain",
+            ],
+        )],
+        (Os::Windows, true) => vec![Step::new(
+            "current user's Root store",
+            "certutil",
+            &["-user", "-addstore", "Root", path],
+        )
+        .undo("certutil", &["-user", "-delstore", "Root", sha1])],
+        (Os::Windows, false) => vec![Step::new(
+            "local machine's Root store",
+            "certutil",
+            &["-addstore", "Root", path],
+        )
+        .undo("certutil", &["-delstore", "Root", sha1])],
+        (Os::Other, _) => bail!("adding to the trust store isn't supported on this OS"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_stores() {
+        let home = Path::new("/home/alice");
+        let path = Path::new("ca.pem");
+
+        let linux = steps(
+            Os::Linux,
+            path,
+            "Dev CA",
+            "pls-ab.crt",
+            "ab12",
+            true,
+            Some(home),
+        )
+        .unwrap();
+        assert_eq!(
+            linux[0].to_string(),
+            "certutil -d sql:/home/alice/.pki/nssdb -A -t 'C,,' -n 'Dev CA' -i ca.pem"
+        );
+
+        let macos = steps(
+            Os::Macos,
+            path,
+            "Dev CA",
+            "pls-ab.crt",
+            "ab12",
+            true,
+            Some(home),
+        )
+        .unwrap();
+        assert_eq!(
+            macos[0].to_string(),
+            "security add-trusted-cert -r trustRoot -k /home/alice/Library/Keychains/login.keychain-db ca.pem"
+        );
+
+        let windows = steps(
+            Os::Windows,
+            path,
+            "Dev CA",
+            "pls-ab.crt",
+            "ab12",
+            false,
+            None,
+        )
+        .unwrap();
+        assert_eq!(windows[0].to_string(), "certutil -addstore Root ca.pem");
+        assert_eq!(
+            windows[0].undo.as_deref(),
+            Some("certutil -delstore Root ab12")
+        );
+
+        assert!(steps(Os::Linux, path, "Dev CA", "pls-ab.crt", "ab12", true, None).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_step() {
+        let steps = [
+            Step::new("system (Debian)", "true", &[]).undo("rm", &["ca.crt"]),
+            Step::new("system (Debian)", "false", &[]),
+            Step::new("system (Debian)", "true", &[]),
+        ];
+
+        let (changes, failed) = apply(&steps, false);
+        assert!(failed.is_some());
+        let outcomes: Vec<&Outcome> = changes.iter().map(|change| &change.outcome).collect();
+        let [Outcome::Done, Outcome::Failed { error }, Outcome::Skipped] = &outcomes[..] else {
+            panic!("expected the second step to fail, got {outcomes:?}");
+        };
+        assert!(error.contains("`false` failed"));
+        assert_eq!(changes[0].undo.as_deref(), Some("rm ca.crt"));
+
+        let (changes, failed) = apply(&steps, true);
+        assert!(failed.is_none());
+        assert!(changes
+            .iter()
+            .all(|change| change.outcome == Outcome::DryRun));
+    }
+
+    #[test]
+    fn only_one_ca() {
+        let dir = std::env::temp_dir().join(format!("pls-trust-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let root = dir.join("root.pem");
+        std::fs::write(
+            &root,
+            include_bytes!("../../test-data/certs/isrg-root-x2.pem"),
+        )
+        .unwrap();
+        assert!(read_ca(&root).unwrap().is_ca);
+
+        let leaf = dir.join("leaf.pem");
+        std::fs::write(&leaf, include_bytes!("../../test-data/certs/lan-fish.pem")).unwrap();
+        assert!(read_ca(&leaf).is_err());
+
+        let chain = dir.join("chain.pem");
+        std::fs::write(&chain, include_bytes!("../../test-data/certs/chain.pem")).unwrap();
+        assert!(read_ca(&chain).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn private_dir() {
+        let dir = PrivateDir::new().unwrap();
+        let path = dir.write("pls-ab.crt", b"cert").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"cert");
+        // the name is taken
+        assert!(dir.write("pls-ab.crt", b"other").is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&dir.0), 0o700);
+            assert_eq!(mode(&path), 0o600);
+        }
+
+        let root = dir.0.clone();
+        drop(dir);
+        assert!(!root.exists());
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use color_eyre::eyre::{bail, Context, Result};
+use jiff::Timestamp;
+
+use crate::{
+    chain::{self, ChainIssue},
+    components::{
+        par_map,
+        verify::{print_verify, VerifyEntry, VerifyReport, VerifyStatus, VerifySummary},
+    },
+    verifier,
+    x509::SimpleCert,
+};
+
+use super::{expiry::cert_file, parse_time, CommandExt, Format, RenderOptions};
+
+/// Verify the certs in many files against a CA, e.g. every cert an internal
+/// CA has issued, and fail if any doesn't verify. Each file has a leaf and
+/// any intermediates, in any order.
+#[derive(Default, Clone, Debug, Parser)]
+pub struct Verify {
+    /// Files with certs (PEM, DER or PKCS#7), or directories of them.
+    #[arg(required = true, value_name = "FILE|DIR")]
+    pub files: Vec<PathBuf>,
+
+    /// The CA to verify against: its root, and the intermediates between it
+    /// and the leaves if they aren't in each file.
+    #[arg(long, value_name = "FILE")]
+    pub ca: PathBuf,
+
+    /// Print the counts of each result and the files that failed, rather
+    /// than every file.
+    #[arg(long)]
+    pub summary: bool,
+
+    /// How many threads to verify on. Defaults to the number of CPUs.
+    #[arg(long)]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Verify at this time instead of now, e.g. `2026-03-01` or `+90d` to
+    /// see which certs will have expired by then.
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub at: Option<Timestamp>,
+}
+
+impl CommandExt for Verify {
+    async fn run(self, format: Format, mut options: RenderOptions) -> Result<()> {
+        if let Some(at) = self.at {
+            options.now = at.to_zoned(options.now.time_zone().clone());
+        }
+        let ca = cert_file(&self.ca).with_context(|| format!("Reading {}", self.ca.display()))?;
+        let (roots, intermediates): (Vec<SimpleCert>, Vec<SimpleCert>) =
+            ca.into_iter().partition(|cert| cert.is_self_signed);
+        if roots.is_empty() {
+            bail!(
+                "{} has no self-signed root to verify against, add the root the CA chains to",
+                self.ca.display()
+            );
+        }
+        let store = verifier::store_of(roots.into_iter().map(|root| root._cert))?;
+
+        let files = expand(&self.files)?;
+        let jobs = self
+            .jobs
+            .or_else(|| std::thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get);
+        let entries = par_map(files, jobs, |file| {
+            let name = file.display().to_string();
+            let (mut certs, issues) = match cert_file(&file) {
+                Ok(certs) => chain::order(certs),
+                Err(err) => return failed(name, format!("{err:#}")),
+            };
+            let subject = Some(certs[0].subject.name.clone());
+            let reordered = issues
+                .into_iter()
+                .find(|issue| matches!(issue, ChainIssue::OutOfOrder { .. }));
+            certs.extend(intermediates.iter().cloned());
+            match verifier::verify_result_at(&store, None, &certs, self.at) {
+                Ok(result) => VerifyEntry {
+                    file: name,
+                    subject,
+                    status: VerifyStatus::of(&result),
+                    error: result.err().map(|err| err.to_string()),
+                    reordered,
+                },
+                Err(err) => failed(name, format!("{err:#}")),
+            }
+        });
+
+        let summary = VerifySummary::of(&entries);
+        let failed = summary.failed();
+        let total = summary.total;
+        let results = match self.summary {
+            true => entries
+                .into_iter()
+                .filter(|entry| entry.status != VerifyStatus::Ok)
+                .collect(),
+            false => entries,
+        };
+        print_verify(
+            VerifyReport {
+                ca: self.ca.display().to_string(),
+                summary,
+                results,
+            },
+            format,
+            &options,
+        )?;
+
+        if failed > 0 {
+            bail!("{failed} of {total} certs failed to verify");
+        }
+        Ok(())
+    }
+}
+
+fn failed(file: String, error: String) -> VerifyEntry {
+    VerifyEntry {
+        file,
+        subject: None,
+        status: VerifyStatus::Error,
+        error: Some(error),
+        reordered: None,
+    }
+}
+
+/// `paths` with each directory replaced by the files in it, sorted by name.
+fn expand(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries: Vec<PathBuf> = fs::read_dir(path)
+            .with_context(|| format!("Reading {}", path.display()))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.is_file() && !is_hidden(path))
+            .collect();
+        entries.sort();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn classifies_failures() {
+        let chain: Vec<SimpleCert> =
+            X509::stack_from_pem(include_bytes!("../../test-data/certs/chain.pem"))
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect();
+        let store = verifier::store_of([chain[2]._cert.clone()]).unwrap();
+
+        // the chain has long expired
+        let result = verifier::verify_result(&store, None, &chain).unwrap();
+        assert_eq!(VerifyStatus::of(&result), VerifyStatus::Expired);
+
+        // without its intermediate, the leaf doesn't lead to the root
+        let result = verifier::verify_result(&store, None, &chain[..1]).unwrap();
+        assert_eq!(VerifyStatus::of(&result), VerifyStatus::UnknownIssuer);
+
+        // a root first bundle is put leaf first before verifying
+        let (reordered, issues) = chain::order(chain.iter().rev().cloned().collect());
+        let result = verifier::verify_result(&store, None, &reordered).unwrap();
+        assert_eq!(VerifyStatus::of(&result), VerifyStatus::Expired);
+        assert!(matches!(issues[0], ChainIssue::OutOfOrder { .. }));
+
+        // but was fine while it was valid
+        let at = Some("2025-02-01T00:00:00Z".parse().unwrap());
+        let result = verifier::verify_result_at(&store, None, &chain, at).unwrap();
+        assert_eq!(VerifyStatus::of(&result), VerifyStatus::Ok);
+    }
+}
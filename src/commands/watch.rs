@@ -0,0 +1,200 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use color_eyre::eyre::{bail, Context, Result};
+use tokio::{net::TcpListener, time::MissedTickBehavior};
+use url::Url;
+
+use crate::{
+    components::{
+        expiry::Status,
+        watch::{print_round, WatchResult},
+    },
+    jobs::Outcome,
+    metrics::{self, Latest},
+    notify::{Notifier, Tracker},
+    state::State,
+};
+
+use super::{
+    connect::{fetch_leaf, Target},
+    parse_duration, CommandExt, DnsArgs, Format, JobArgs, RenderOptions,
+};
+
+/// Check the certs hosts serve over and over: every `--interval`, connect to
+/// all the hosts at once and print when each one's cert expires and how long
+/// the handshake took. Runs until it's stopped.
+///
+/// With `--listen`, the latest results are also served as Prometheus metrics
+/// on `/metrics`, so `pls` can double as a small cert exporter:
+///
+/// ```text
+/// pls_check_success{host="example.com:443"} 1
+/// pls_cert_expiry_seconds{host="example.com:443"} 5270400
+/// pls_handshake_duration_seconds{host="example.com:443"} 0.084
+/// ```
+///
+/// `--notify-webhook` and `--notify-cmd` send an alert when a cert gets
+/// close to expiring or changes:
+///
+/// ```text
+/// {"event":"expiring","host":"example.com:443","severity":"warning",...}
+/// ```
+#[derive(Clone, Debug, Parser)]
+pub struct Watch {
+    /// The hosts to check, as `connect` takes them: `example.com`,
+    /// `example.com:8443`, `ldaps://ldap.example.com`, ...
+    #[arg(required = true, value_name = "HOST")]
+    pub hosts: Vec<String>,
+
+    /// How long to wait between checks, e.g. `30s`, `5m` or `1h`.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "5m")]
+    pub interval: Duration,
+
+    /// Serve the latest results as Prometheus metrics on `/metrics` at this
+    /// address, e.g. `0.0.0.0:9753`.
+    #[arg(long, value_name = "ADDR")]
+    pub listen: Option<SocketAddr>,
+
+    /// POST a JSON event to this URL when a host's cert enters the warning
+    /// window (30 days) or a more urgent one, or is replaced by a cert with
+    /// another fingerprint. Each change is sent once.
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Option<Url>,
+
+    /// Run this shell command for the same events, with the JSON event on
+    /// its stdin.
+    #[arg(long, value_name = "COMMAND")]
+    pub notify_cmd: Option<String>,
+
+    /// Compare each host's cert with the one it served on the last run with
+    /// the same state file, e.g. `~/.cache/pls/state.json`, as `connect
+    /// --state` does, and update the file after every check.
+    #[arg(long, value_name = "FILE")]
+    pub state: Option<PathBuf>,
+
+    #[command(flatten)]
+    dns: DnsArgs,
+
+    #[command(flatten)]
+    jobs: JobArgs,
+}
+
+impl CommandExt for Watch {
+    async fn run(self, format: Format, mut options: RenderOptions) -> Result<()> {
+        let targets = self
+            .hosts
+            .iter()
+            .map(|host| Target::parse(host, None))
+            .collect::<Result<Vec<_>>>()?;
+
+        let latest: Latest = Arc::new(Mutex::new(Vec::new()));
+        if let Some(addr) = self.listen {
+            let listener = TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("Listening on {addr}"))?;
+            tracing::info!(%addr, "serving metrics on /metrics");
+            tokio::spawn(metrics::serve(listener, latest.clone()));
+        }
+
+        let notifier = Notifier {
+            webhook: self.notify_webhook.clone(),
+            command: self.notify_cmd.clone(),
+        };
+        let mut tracker = Tracker::default();
+
+        let mut interval = tokio::time::interval(self.interval);
+        // a slow round pushes the next one back rather than bunching them up
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let mut results = self.check(&targets).await?;
+            if let Some(path) = &self.state {
+                record(path, &mut results)?;
+            }
+
+            options.now = jiff::Zoned::now();
+            print_round(&results, format, &options)?;
+            for event in tracker.update(&results, options.now.timestamp()) {
+                tracing::info!(host = %event.host, event = ?event.event, "alerting");
+                if !notifier.is_empty() {
+                    notifier.send(&event).await;
+                }
+            }
+            let failed = results
+                .iter()
+                .find(|result| result.status != Status::Ok)
+                .map(|result| result.host.clone());
+            if let Ok(mut latest) = latest.lock() {
+                *latest = results;
+            }
+
+            if let Some(host) = failed.filter(|_| self.jobs.fail_fast) {
+                bail!("{host} failed, stopping (--fail-fast)");
+            }
+        }
+    }
+}
+
+/// Record the certs of `results` in the state file at `path`, and fill in
+/// how they changed. Read every time, so several watches can share a file.
+fn record(path: &Path, results: &mut [WatchResult]) -> Result<()> {
+    let mut state = State::load(path)?;
+    for result in results.iter_mut() {
+        if let Some(snapshot) = result.snapshot.take() {
+            result.changes = state.record(&result.host, snapshot);
+        }
+    }
+    state.save(path)
+}
+
+impl Watch {
+    /// Check every host once.
+    async fn check(&self, targets: &[Target]) -> Result<Vec<WatchResult>> {
+        let outcomes = self
+            .jobs
+            .runner()
+            .run(
+                targets.to_vec(),
+                |target| {
+                    let target = target.clone();
+                    let resolver = self.dns.resolver();
+                    async move {
+                        let host = target.to_string();
+                        let start = Instant::now();
+                        match fetch_leaf(&target, &resolver).await {
+                            Ok(cert) => WatchResult::new(&host, &cert, start.elapsed()),
+                            Err(err) => {
+                                WatchResult::failed(&host, Status::Error, format!("{err:#}"))
+                            }
+                        }
+                    }
+                },
+                |result: &WatchResult| result.status != Status::Ok,
+            )
+            .await?;
+
+        Ok(outcomes
+            .into_iter()
+            .map(|(target, outcome)| {
+                let host = target.to_string();
+                match outcome {
+                    Outcome::Done(result) => result,
+                    Outcome::TimedOut(timeout) => WatchResult::failed(
+                        &host,
+                        Status::Timeout,
+                        format!("timed out after {}s", timeout.as_secs()),
+                    ),
+                    Outcome::Skipped => WatchResult::failed(
+                        &host,
+                        Status::Skipped,
+                        "skipped after another host failed".to_string(),
+                    ),
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, Color, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    acme::{Authorization, Order},
+    commands::{Format, RenderOptions},
+    components::x509::{pem_header, MultipleCertView},
+    theme::{Theme, UseTheme},
+    x509::SimpleCert,
+};
+
+/// An ACME order or authorization, with the resources it links to that could
+/// be fetched.
+#[derive(Debug, Default, Serialize)]
+pub struct AcmeReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<Order>,
+    pub authorizations: Vec<Authorization>,
+    /// The issued chain, for a valid order.
+    pub certs: Vec<SimpleCert>,
+}
+
+fn status_color(theme: Theme, status: &str) -> Color {
+    match status {
+        "valid" => theme.good,
+        "invalid" | "revoked" | "deactivated" | "expired" => theme.bad,
+        _ => theme.warning,
+    }
+}
+
+#[derive(Default, Props)]
+pub struct OrderProps {
+    pub order: Option<Order>,
+}
+
+#[component]
+pub fn OrderView(mut hooks: Hooks, props: &OrderProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let Some(order) = &props.order else {
+        return element! { View() };
+    };
+
+    let identifiers = order
+        .identifiers
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "order:", color: theme.top_level)
+                Text(content: order.status.clone(), color: status_color(theme, &order.status))
+                Text(content: format!("({})", order.progress()))
+            }
+            View(margin_left: 4) {
+                Text(content: "identifiers: ")
+                Text(content: identifiers)
+            }
+            #(order.expires.clone().map(|expires| element! {
+                View(margin_left: 4) {
+                    Text(content: "expires: ")
+                    Text(content: expires)
+                }
+            }))
+            #(order.error.as_ref().map(|error| element! {
+                View(margin_left: 4) {
+                    Text(content: "error: ")
+                    Text(content: error.to_string(), color: theme.bad)
+                }
+            }))
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct AuthorizationProps {
+    pub authorization: Option<Authorization>,
+}
+
+#[component]
+pub fn AuthorizationView(
+    mut hooks: Hooks,
+    props: &AuthorizationProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let Some(authz) = &props.authorization else {
+        return element! { View() };
+    };
+    let wildcard = if authz.wildcard { " (wildcard)" } else { "" };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "authorization:", color: theme.top_level)
+                Text(content: format!("{}{wildcard}", authz.identifier))
+                Text(content: authz.status.clone(), color: status_color(theme, &authz.status))
+            }
+            #(authz.expires.clone().map(|expires| element! {
+                View(margin_left: 4) {
+                    Text(content: "expires: ")
+                    Text(content: expires)
+                }
+            }))
+            #(authz.challenges.iter().map(|challenge| element! {
+                View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                    View(gap: 1) {
+                        Text(content: format!("{}:", challenge.kind))
+                        Text(content: challenge.status.clone(), color: status_color(theme, &challenge.status))
+                        #(challenge.validated.clone().map(|validated| element! {
+                            Text(content: format!("(validated {validated})"))
+                        }))
+                    }
+                    #(challenge.error.as_ref().map(|error| element! {
+                        View(margin_left: 4) {
+                            Text(content: error.to_string(), color: theme.bad)
+                        }
+                    }))
+                }
+            }))
+        }
+    }
+}
+
+pub fn print_acme(
+    mut report: AcmeReport,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    if options.stable {
+        report.certs.iter_mut().for_each(SimpleCert::make_stable);
+    }
+
+    match format {
+        Format::Text => {
+            options.print(element! {
+                View(flex_direction: FlexDirection::Column, gap: 1, margin: 1) {
+                    OrderView(order: report.order)
+                    #(report.authorizations.into_iter().map(|authorization| element! {
+                        AuthorizationView(authorization: Some(authorization))
+                    }))
+                    #((!report.certs.is_empty()).then(|| element! {
+                        View(flex_direction: FlexDirection::Column) {
+                            Text(content: "certificate:", color: options.theme.top_level)
+                            View(margin_left: 4) {
+                                MultipleCertView(certs: report.certs.into_iter().map(Arc::new).collect::<Vec<_>>(), options: options.clone())
+                            }
+                        }
+                    }))
+                }
+            });
+        }
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            println!("{}", format.to_json(&report)?);
+        }
+        Format::Pem => {
+            for cert in report.certs {
+                options.print_pem(|| pem_header(&cert, options), &cert.pem);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props, Weight,
+};
+
+use crate::{
+    asn1::Asn1Dump,
+    commands::{Format, RenderOptions},
+    components::print_report_list,
+    theme::UseTheme,
+};
+
+/// Values longer than this many characters are cut short in the text
+/// output. The JSON output has them whole.
+const MAX_VALUE_LEN: usize = 64;
+
+#[derive(Default, Props)]
+pub struct Asn1Props {
+    pub dump: Asn1Dump,
+}
+
+/// One line per element, like `openssl asn1parse -i`:
+/// `  4+4  1000    SEQUENCE`, the offset, header and contents lengths, then
+/// the tag indented by its depth and its value.
+#[component]
+pub fn Asn1View(mut hooks: Hooks, props: &Asn1Props) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let dump = &props.dump;
+
+    let lines = dump.flatten().into_iter().map(|(depth, node)| {
+        let value = node.value.as_ref().map(|value| match value.char_indices().nth(MAX_VALUE_LEN) {
+            Some((end, _)) => format!("{}… ({} bytes)", &value[..end], node.len),
+            None => value.clone(),
+        });
+        element! {
+            View(gap: 1) {
+                Text(content: format!("{:>6}+{:<2} {:>6}", node.offset, node.header_len, node.len), weight: Weight::Light)
+                Text(content: format!("{}{}", "  ".repeat(depth), node.tag), color: theme.heading)
+                #(value.map(|value| element! {
+                    Text(content: value, color: theme.highlight)
+                }))
+                #(node.error.as_ref().map(|error| element! {
+                    Text(content: format!("⚠️  {error}"), color: theme.warning)
+                }))
+            }
+        }
+    });
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: format!("{}:", dump.label), color: theme.top_level)
+            #(lines)
+            #(dump.error.as_ref().map(|error| element! {
+                Text(content: format!("⚠️  {error}"), color: theme.warning)
+            }))
+        }
+    }
+}
+
+pub fn print_asn1(
+    dumps: Vec<Asn1Dump>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report_list(dumps, format, options, |dumps| {
+        element! {
+            View(flex_direction: FlexDirection::Column, gap: 1) {
+                #(dumps.into_iter().map(|dump| element! {
+                    Asn1View(dump)
+                }))
+            }
+        }
+        .into()
+    })
+}
@@ -0,0 +1,156 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    caa::{CaaSet, Issuers},
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+};
+
+/// The CAA records of a domain, and how the CA of the cert it serves now
+/// compares with them.
+#[derive(Debug, Default, Serialize)]
+pub struct CaaReport {
+    #[serde(flatten)]
+    pub caa: CaaSet,
+    pub issuers: Issuers,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert: Option<CurrentIssuer>,
+}
+
+/// The issuer of the cert a domain serves.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CurrentIssuer {
+    /// The issuer's name.
+    pub name: String,
+    /// The CA's CAA domain, when it's a known CA.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca: Option<String>,
+    /// Whether the CAA records allow the CA to issue, when it's known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized: Option<bool>,
+}
+
+#[derive(Default, Props)]
+pub struct CaaProps {
+    pub report: CaaReport,
+}
+
+#[component]
+pub fn CaaView(mut hooks: Hooks, props: &CaaProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let caa = &props.report.caa;
+
+    let found = match &caa.found_at {
+        None => format!("no records (checked {})", caa.checked.join(", ")),
+        Some(name) if *name == caa.domain.trim_start_matches("*.") => {
+            format!("{} records", caa.records.len())
+        }
+        Some(name) => format!("{} records, inherited from {name}", caa.records.len()),
+    };
+
+    let verdict = match &props.report.issuers {
+        Issuers::Any => element! {
+            View() {
+                Text(content: "✅ any CA may issue", color: theme.good)
+            }
+        },
+        Issuers::Only { cas } if cas.is_empty() => element! {
+            View() {
+                Text(content: "🚨 no CA may issue", color: theme.bad, decoration: TextDecoration::Underline)
+            }
+        },
+        Issuers::Only { cas } => element! {
+            View(gap: 1) {
+                Text(content: "may issue:")
+                Text(content: cas.join(", "), color: theme.highlight)
+            }
+        },
+        Issuers::UnknownCritical { tag } => element! {
+            View() {
+                Text(content: format!("🚨 no CA may issue: unknown critical tag {tag:?}"), color: theme.bad, decoration: TextDecoration::Underline)
+            }
+        },
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            View(flex_direction: FlexDirection::Column) {
+                View(gap: 1) {
+                    Text(content: "caa:", color: theme.top_level)
+                    Text(content: caa.domain.clone(), color: theme.highlight)
+                    Text(content: format!("({found}, via {})", caa.resolver))
+                }
+                View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                    #(caa.records.iter().map(|record| element! {
+                        Text(content: record.to_string())
+                    }))
+                    #(verdict)
+                    #((caa.found_at.is_some() && !caa.authenticated).then(|| element! {
+                        Text(content: "⚠️  not validated with DNSSEC by the resolver", color: theme.warning)
+                    }))
+                }
+            }
+            #(props.report.cert.as_ref().map(|cert| element! {
+                CurrentIssuerView(cert: cert.clone())
+            }))
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct CurrentIssuerProps {
+    pub cert: CurrentIssuer,
+}
+
+#[component]
+pub fn CurrentIssuerView(
+    mut hooks: Hooks,
+    props: &CurrentIssuerProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let cert = &props.cert;
+
+    let verdict = match (&cert.ca, cert.authorized) {
+        (Some(ca), Some(true)) => element! {
+            Text(content: format!("✅ {ca} is authorized"), color: theme.good)
+        },
+        (Some(ca), _) => element! {
+            Text(content: format!("🚨 {ca} isn't authorized, it can't renew this cert"), color: theme.bad, decoration: TextDecoration::Underline)
+        },
+        (None, _) => element! {
+            Text(content: "⚠️  unknown CA, look up the CAA domain it uses", color: theme.warning)
+        },
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: "current cert:", color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                View(gap: 1) {
+                    Text(content: "issuer:")
+                    Text(content: cert.name.clone(), color: theme.highlight)
+                    #(cert.ca.clone().map(|ca| element! {
+                        Text(content: format!("({ca})"))
+                    }))
+                }
+                #(verdict)
+            }
+        }
+    }
+}
+
+pub fn print_caa(
+    report: CaaReport,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(report, format, options, |report| {
+        element! { CaaView(report) }.into()
+    })
+}
@@ -0,0 +1,121 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+};
+
+/// What the linked TLS library supports, for `pls capabilities`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CapabilitiesReport {
+    /// e.g. `TLSv1.2`.
+    pub versions: Vec<&'static str>,
+    pub curves: Vec<Curve>,
+    /// The TLS 1.3 suites, always offered.
+    pub tls13_ciphers: Vec<&'static str>,
+    /// The TLS 1.2 and older suites, OpenSSL names.
+    pub tls12_ciphers: Vec<String>,
+    pub features: Vec<Feature>,
+}
+
+/// A curve (group) `connect --curves` takes.
+#[derive(Debug, Clone, Serialize)]
+pub struct Curve {
+    pub name: &'static str,
+    /// A hybrid with a post-quantum KEM.
+    pub post_quantum: bool,
+    /// Offered when `--curves` isn't given.
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Feature {
+    pub name: &'static str,
+    pub enabled: bool,
+    /// The flag that uses it.
+    pub detail: &'static str,
+}
+
+#[derive(Default, Props)]
+pub struct CapabilitiesProps {
+    pub report: CapabilitiesReport,
+}
+
+#[component]
+pub fn CapabilitiesView(
+    mut hooks: Hooks,
+    props: &CapabilitiesProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let report = &props.report;
+
+    let list = |heading: &str, items: Vec<String>| {
+        element! {
+            View(flex_direction: FlexDirection::Column) {
+                Text(content: format!("{heading}:"), color: theme.heading)
+                View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                    #(items.into_iter().map(|item| element! { Text(content: item) }))
+                }
+            }
+        }
+    };
+
+    let curves = report
+        .curves
+        .iter()
+        .map(|curve| {
+            let mut tags = Vec::new();
+            if curve.post_quantum {
+                tags.push("post-quantum");
+            }
+            if curve.default {
+                tags.push("default");
+            }
+            match tags.is_empty() {
+                true => curve.name.to_string(),
+                false => format!("{} ({})", curve.name, tags.join(", ")),
+            }
+        })
+        .collect();
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: "capabilities:", color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(list("versions", report.versions.iter().map(|v| v.to_string()).collect()))
+                #(list("curves", curves))
+                #(list("TLS 1.3 ciphers", report.tls13_ciphers.iter().map(|c| c.to_string()).collect()))
+                #(list("TLS 1.2 ciphers", report.tls12_ciphers.clone()))
+                Text(content: "features:", color: theme.heading)
+                View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                    #(report.features.iter().map(|feature| element! {
+                        View(gap: 1) {
+                            #(if feature.enabled {
+                                element! { Text(content: format!("✅ {}", feature.name), color: theme.good) }
+                            } else {
+                                element! { Text(content: format!("❌ {}", feature.name), color: theme.bad) }
+                            })
+                            Text(content: feature.detail, color: theme.accent)
+                        }
+                    }))
+                }
+            }
+        }
+    }
+}
+
+pub fn print_capabilities(
+    report: CapabilitiesReport,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(report, format, options, |report| {
+        element! { CapabilitiesView(report) }.into()
+    })
+}
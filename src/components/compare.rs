@@ -0,0 +1,144 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props, Weight,
+};
+use serde::Serialize;
+
+use crate::{
+    chain::{ChainIssue, Difference},
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+    x509::SimpleCert,
+};
+
+/// One cert of either chain, as far as the comparison goes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparedCert {
+    pub subject: String,
+    pub sha256: String,
+    /// Whether the other chain has the cert too.
+    pub in_both: bool,
+}
+
+/// The chain a server serves next to the one it's expected to, from
+/// `parse --compare-to`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ChainComparison {
+    /// The file the expected chain was read from.
+    pub expected_from: String,
+    /// The host and port, e.g. `example.com:443`.
+    pub deployed_at: String,
+    pub expected: Vec<ComparedCert>,
+    pub deployed: Vec<ComparedCert>,
+    /// Empty when the server serves the expected chain.
+    pub differences: Vec<Difference>,
+    /// How the expected certs were put in order, when the file didn't have
+    /// them leaf first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_reordered: Option<ChainIssue>,
+}
+
+impl ChainComparison {
+    pub fn new(
+        expected_from: String,
+        deployed_at: String,
+        expected: &[SimpleCert],
+        deployed: &[SimpleCert],
+    ) -> Self {
+        let summarize = |certs: &[SimpleCert], other: &[SimpleCert]| {
+            certs
+                .iter()
+                .map(|cert| ComparedCert {
+                    subject: cert.subject.name.clone(),
+                    sha256: cert.fingerprints.sha256.clone(),
+                    in_both: other
+                        .iter()
+                        .any(|other| other.fingerprints.sha256 == cert.fingerprints.sha256),
+                })
+                .collect()
+        };
+
+        Self {
+            expected_from,
+            deployed_at,
+            expected: summarize(expected, deployed),
+            deployed: summarize(deployed, expected),
+            differences: crate::chain::compare(expected, deployed),
+            expected_reordered: None,
+        }
+    }
+
+    pub fn matches(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+#[derive(Default, Props)]
+pub struct ChainComparisonProps {
+    pub comparison: ChainComparison,
+}
+
+#[component]
+pub fn ChainComparisonView(
+    mut hooks: Hooks,
+    props: &ChainComparisonProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let comparison = &props.comparison;
+
+    let chain = |heading: String, certs: &[ComparedCert]| {
+        element! {
+            View(flex_direction: FlexDirection::Column) {
+                Text(content: heading, color: theme.heading)
+                View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                    #(certs.iter().enumerate().map(|(i, cert)| element! {
+                        View(gap: 1) {
+                            Text(content: format!("{}.", i + 1))
+                            Text(content: cert.subject.clone(), color: if cert.in_both { theme.highlight } else { theme.bad })
+                            Text(content: format!("(sha256 {})", &cert.sha256[..cert.sha256.len().min(16)]))
+                        }
+                    }))
+                }
+            }
+        }
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            View(flex_direction: FlexDirection::Column) {
+                Text(content: "deployed vs. expected:", color: theme.top_level)
+                View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                    #(if comparison.matches() {
+                        element! {
+                            Text(content: format!("✅ {} serves the chain in {}", comparison.deployed_at, comparison.expected_from), color: theme.good)
+                        }
+                    } else {
+                        element! {
+                            Text(content: format!("🚨 {} doesn't serve the chain in {}", comparison.deployed_at, comparison.expected_from), color: theme.bad, decoration: TextDecoration::Underline)
+                        }
+                    })
+                    #(comparison.differences.iter().map(|difference| element! {
+                        Text(content: format!("- {difference}"), color: theme.warning)
+                    }))
+                    #(comparison.expected_reordered.as_ref().map(|issue| element! {
+                        Text(content: format!("{} {issue}", comparison.expected_from), weight: Weight::Light)
+                    }))
+                }
+            }
+            #(chain(format!("expected ({}):", comparison.expected_from), &comparison.expected))
+            #(chain(format!("deployed ({}):", comparison.deployed_at), &comparison.deployed))
+        }
+    }
+}
+
+pub fn print_comparison(
+    comparison: ChainComparison,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(comparison, format, options, |comparison| {
+        element! { ChainComparisonView(comparison) }.into()
+    })
+}
@@ -1,29 +1,47 @@
+use std::sync::Arc;
+
 use iocraft::{
     component, element,
     prelude::{Text, TextDecoration, View},
-    AnyElement, Color, ElementExt, FlexDirection, Props,
+    AnyElement, FlexDirection, Hooks, Props, Weight,
 };
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::{
-    commands::Format,
-    components::x509::{MultipleCertView, SurroundText},
-    connection::Connection,
-    theme::{HIGHLIGHT_COLOR, TOP_LEVEL_COLOR},
+    chain::{ChainIssue, PathNode},
+    commands::{Format, RenderOptions},
+    components::{
+        paths::PathsView,
+        state::{CertChanges, CertChangesView},
+        x509::{pem_header, MultipleCertView, SurroundText},
+    },
+    connection::{Connection, H2Probe, HttpProbe, Transcript},
+    dane::{Dane, TlsaStatus},
+    dns::Resolution,
+    registry::{DockerCerts, DockerFileKind, RegistryProbe},
+    revocation::{Revocation, RevocationStatus},
+    theme::UseTheme,
+    verifier::Verifier,
     x509::SimpleCert,
 };
 
 #[derive(Default, Props)]
 pub struct TlsConnectionProps {
     pub tls: Connection,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn TlsConnectionView(props: &TlsConnectionProps) -> impl Into<AnyElement<'static>> {
+pub fn TlsConnectionView(
+    mut hooks: Hooks,
+    props: &TlsConnectionProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     element! {
         View(flex_direction: FlexDirection::Column) {
             View(gap: 1) {
-                Text(content: format!("{}:", props.tls.version), color: TOP_LEVEL_COLOR)
+                Text(content: format!("{}:", props.tls.version), color: theme.top_level)
                 View() {
                     SurroundText(left: "(", text: format!("{:?}", props.tls.transport), right: ")")
                 }
@@ -31,76 +49,517 @@ pub fn TlsConnectionView(props: &TlsConnectionProps) -> impl Into<AnyElement<'st
             View(flex_direction: FlexDirection::Column, margin_left: 4) {
                 #(if props.tls.valid {
                     element! {
-                        Text(content: "✅ connection secure", color: Color::Green)
+                        Text(content: format!("✅ connection secure{}", verified_by(props.tls.verifier)), color: theme.good)
                     }
                 } else {
                     element! {
-                        Text(content: format!("🚨 connection insecure: {}", props.tls.verify_result.clone().unwrap_or_default()), color: Color::Red, decoration: TextDecoration::Underline)
+                        Text(content: format!("🚨 connection insecure{}: {}", verified_by(props.tls.verifier), props.tls.verify_result.clone().unwrap_or_default()), color: theme.bad, decoration: TextDecoration::Underline)
                     }
                 })
                 View(gap: 1) {
                     Text(content: "curve:")
-                    Text(content: props.tls.curve.clone(), color: HIGHLIGHT_COLOR)
+                    Text(content: props.tls.curve.clone(), color: theme.highlight)
                     #(props.tls.is_pqc.then(|| element! {
-                        Text(content: "(🔒 post-quantum secure)", color: Color::Green)
+                        Text(content: "(🔒 post-quantum secure)", color: theme.good)
                     }))
                 }
+                #(props.tls.time.round_trips.hello_retry_request.then(|| element! {
+                    Text(content: "⚠️  HelloRetryRequest: the server wanted a key share for another curve (+1 round trip)", color: theme.warning)
+                }))
+                // timings differ on every run
+                #((!props.options.stable).then(|| element! {
                 View(gap: 1) {
-                    Text(content: format!("dns: {:.2?},", props.tls.time.dns))
+                    Text(content: format!("dns: {:.2?}{},", props.tls.time.dns, resolved_by(props.tls.dns.as_ref())))
+                    #(props.tls.time.proxy.map(|proxy| element! {
+                        Text(content: format!("proxy: {:.2?},", proxy))
+                    }))
                     #(match props.tls.time.connect {
                         Some(connect) => element! {
                             View(gap: 1) {
                                 Text(content: format!("connect: {:.2?},", connect))
                                 Text(content: format!("secure: {:.2?}", props.tls.time.tls))
+                                Text(content: round_trips(props.tls.time.round_trips.tls))
                             }
                         },
                         None => element! {
                             View() {
-                                Text(content: format!("handshake: {:.2?}", props.tls.time.tls))
+                                Text(content: format!("handshake: {:.2?} ", props.tls.time.tls))
+                                Text(content: round_trips(props.tls.time.round_trips.tls))
                             }
                         },
                     })
                 }
+                }))
+                #(props.tls.transcript.clone().map(|transcript| element! {
+                    TranscriptView(transcript)
+                }))
+            }
+        }
+
+    }
+}
+
+/// ` (via 1.1.1.1:53, ttl 300s)`, for a host that was looked up.
+fn resolved_by(dns: Option<&Resolution>) -> String {
+    match dns {
+        Some(Resolution {
+            resolver,
+            ttl: Some(ttl),
+        }) => format!(" (via {resolver}, ttl {ttl}s)"),
+        Some(Resolution {
+            resolver,
+            ttl: None,
+        }) => format!(" (via {resolver})"),
+        None => String::new(),
+    }
+}
+
+/// ` (macOS Security framework)`, when `--verifier` picked another verifier
+/// than boring's.
+fn verified_by(verifier: Verifier) -> String {
+    match verifier {
+        Verifier::Boring => String::new(),
+        verifier => format!(" ({verifier})"),
+    }
+}
+
+fn round_trips(count: u32) -> String {
+    match count {
+        1 => "(1 round trip)".to_string(),
+        count => format!("({count} round trips)"),
+    }
+}
+
+#[derive(Default, Props)]
+pub struct DaneProps {
+    pub dane: Dane,
+}
+
+#[component]
+pub fn DaneView(mut hooks: Hooks, props: &DaneProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let dane = &props.dane;
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "dane:", color: theme.top_level)
+                Text(content: dane.name.clone(), color: theme.highlight)
+                SurroundText(left: "(via ", text: dane.resolver.clone(), right: ")")
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(if dane.records.is_empty() {
+                    element! {
+                        Text(content: "⚠️  no TLSA records", color: theme.warning)
+                    }
+                } else if dane.valid() {
+                    element! {
+                        Text(content: "✅ chain matches the TLSA records", color: theme.good)
+                    }
+                } else if !dane.matched() {
+                    element! {
+                        Text(content: "🚨 no TLSA record matches the chain", color: theme.bad, decoration: TextDecoration::Underline)
+                    }
+                } else {
+                    element! {
+                        Text(content: "⚠️  the TLSA records match, but DANE clients ignore them", color: theme.warning)
+                    }
+                })
+                #((!dane.authenticated && !dane.records.is_empty()).then(|| element! {
+                    Text(content: "⚠️  not validated with DNSSEC by the resolver", color: theme.warning)
+                }))
+                #(dane.records.iter().map(|record| {
+                    let color = match record.status {
+                        TlsaStatus::Match { .. } => theme.good,
+                        TlsaStatus::PkixFailed { .. } | TlsaStatus::Mismatch => theme.bad,
+                        TlsaStatus::Unsupported => theme.warning,
+                    };
+                    element! {
+                        Text(content: format!("- {record}"), color: color)
+                    }
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct RevocationProps {
+    pub revocation: Revocation,
+}
+
+#[component]
+pub fn RevocationView(mut hooks: Hooks, props: &RevocationProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let revocation = &props.revocation;
+    let (status, color) = match &revocation.status {
+        RevocationStatus::Good => ("✅ not revoked".to_string(), theme.good),
+        RevocationStatus::Revoked { revoked_at, reason } => {
+            let reason = reason
+                .as_ref()
+                .map(|reason| format!(" ({reason})"))
+                .unwrap_or_default();
+            (format!("🚨 revoked at {revoked_at}{reason}"), theme.bad)
+        }
+        RevocationStatus::Unknown { error } => (format!("⚠️  unknown: {error}"), theme.warning),
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "revocation:", color: theme.top_level)
+                Text(content: revocation.cert.clone(), color: theme.highlight)
+                #(revocation.source.as_ref().map(|source| element! {
+                    SurroundText(left: "(from ", text: source.clone(), right: ")")
+                }))
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                Text(content: status, color)
+                #(revocation.next_update.map(|next_update| element! {
+                    Text(content: format!("next update: {next_update}"))
+                }))
             }
         }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct TranscriptProps {
+    pub transcript: Transcript,
+}
+
+#[component]
+pub fn TranscriptView(mut hooks: Hooks, props: &TranscriptProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let offered = &props.transcript.offered;
+    let selected = &props.transcript.selected;
+    let rows = [
+        (
+            "version:",
+            offered.versions.join(", "),
+            Some(selected.version.clone()),
+        ),
+        (
+            "cipher:",
+            offered.ciphers.join(", "),
+            selected.cipher.clone(),
+        ),
+        ("curve:", offered.curves.join(", "), selected.curve.clone()),
+        ("alpn:", offered.alpn.join(", "), selected.alpn.clone()),
+    ];
 
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: "transcript:", color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(rows.into_iter().map(|(name, offered, selected)| element! {
+                    View(gap: 1) {
+                        View(width: 8) {
+                            Text(content: name)
+                        }
+                        Text(content: selected.unwrap_or_else(|| "none".to_string()), color: theme.highlight)
+                        SurroundText(left: "(offered: ", text: offered, right: ")")
+                    }
+                }))
+            }
+        }
     }
 }
 
-#[derive(Default, Debug, Serialize)]
+#[derive(Default, Props)]
+pub struct HttpProbeProps {
+    pub http: HttpProbe,
+}
+
+#[component]
+pub fn HttpProbeView(mut hooks: Hooks, props: &HttpProbeProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let http = &props.http;
+    let status_color = match http.status {
+        200..=399 => theme.good,
+        _ => theme.bad,
+    };
+    let header = |name: &'static str, value: Option<String>| {
+        value.map(|value| {
+            element! {
+                View(gap: 1) {
+                    Text(content: format!("{name}:"))
+                    Text(content: value, color: theme.highlight)
+                }
+            }
+        })
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "http:", color: theme.top_level)
+                Text(content: format!("{} / {}", http.method, http.version))
+                Text(content: http.status.to_string(), color: status_color)
+                SurroundText(left: "(alpn: ", text: http.alpn.clone().unwrap_or_else(|| "none".to_string()), right: ")")
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(match &http.strict_transport_security {
+                    Some(hsts) => element! {
+                        View(gap: 1) {
+                            Text(content: "strict-transport-security:")
+                            Text(content: hsts.clone(), color: theme.good)
+                        }
+                    },
+                    None => element! {
+                        View() {
+                            Text(content: "⚠️  no Strict-Transport-Security header", color: theme.warning)
+                        }
+                    },
+                })
+                #(header("expect-ct", http.expect_ct.clone()))
+                #(header("alt-svc", http.alt_svc.clone()))
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct H2ProbeProps {
+    pub h2: H2Probe,
+}
+
+#[component]
+pub fn H2ProbeView(mut hooks: Hooks, props: &H2ProbeProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let h2 = &props.h2;
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "h2:", color: theme.top_level)
+                Text(content: "✅ the server speaks HTTP/2", color: theme.good)
+                SurroundText(left: "(alpn: ", text: h2.alpn.clone(), right: ")")
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(h2.settings.is_empty().then(|| element! {
+                    Text(content: "empty SETTINGS, every setting has its default")
+                }))
+                #(h2.settings.iter().map(|setting| element! {
+                    View(gap: 1) {
+                        Text(content: format!("{}:", setting.name))
+                        Text(content: setting.value.to_string(), color: theme.highlight)
+                    }
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct RegistryProbeProps {
+    pub registry: RegistryProbe,
+}
+
+#[component]
+pub fn RegistryProbeView(
+    mut hooks: Hooks,
+    props: &RegistryProbeProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let registry = &props.registry;
+    let (verdict, color) = match (registry.speaks_v2(), registry.status) {
+        (true, 401) => ("✅ speaks the registry API, login required", theme.good),
+        (true, _) => ("✅ speaks the registry API", theme.good),
+        (false, _) => ("❌ doesn't speak the registry API", theme.bad),
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "registry:", color: theme.top_level)
+                Text(content: verdict, color)
+                Text(content: format!("(GET /v2/: {})", registry.status), weight: Weight::Light)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(registry.api_version.clone().map(|version| element! {
+                    View(gap: 1) {
+                        Text(content: "api version:")
+                        Text(content: version, color: theme.highlight)
+                    }
+                }))
+                #(registry.authenticate.clone().map(|authenticate| element! {
+                    View(gap: 1) {
+                        Text(content: "authenticate:")
+                        Text(content: authenticate, color: theme.highlight)
+                    }
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct DockerCertsProps {
+    pub docker_certs: DockerCerts,
+}
+
+#[component]
+pub fn DockerCertsView(
+    mut hooks: Hooks,
+    props: &DockerCertsProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let docker_certs = &props.docker_certs;
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "docker certs:", color: theme.top_level)
+                Text(content: docker_certs.dir.clone(), color: theme.highlight)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(match &docker_certs.verify_error {
+                    None if docker_certs.verified => element! {
+                        View() {
+                            Text(content: "✅ the chain verifies against these CAs and the system roots", color: theme.good)
+                        }
+                    },
+                    None => element! { View() },
+                    Some(error) => element! {
+                        View() {
+                            Text(content: format!("❌ the chain doesn't verify for Docker: {error}"), color: theme.bad)
+                        }
+                    },
+                })
+                #(docker_certs.problems.iter().map(|problem| element! {
+                    Text(content: format!("⚠️  {problem}"), color: theme.warning)
+                }))
+                #(docker_certs.files.is_empty().then(|| element! {
+                    Text(content: "no .crt, .cert or .key files", weight: Weight::Light)
+                }))
+                #(docker_certs.files.iter().map(|file| {
+                    let kind = match file.kind {
+                        DockerFileKind::Ca => "ca",
+                        DockerFileKind::ClientCert => "client cert",
+                        DockerFileKind::ClientKey => "client key",
+                    };
+                    element! {
+                        View(gap: 1) {
+                            Text(content: format!("- {}", file.name))
+                            Text(content: format!("({kind})"), weight: Weight::Light)
+                            #(file.subject.clone().map(|subject| element! {
+                                Text(content: subject, color: theme.highlight)
+                            }))
+                            #(file.not_after.map(|not_after| element! {
+                                Text(content: format!("expires {}", not_after.strftime("%Y-%m-%d")), color: theme.accent)
+                            }))
+                        }
+                    }
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Default, Debug, Serialize, JsonSchema)]
 pub struct ConnectionWithCerts {
     pub tls: Connection,
     pub certs: Vec<SimpleCert>,
+    /// Problems with the chain the server sent, with `--chain`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chain_issues: Vec<ChainIssue>,
+    /// Every path from the leaf to a root, with `--paths`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paths: Option<PathNode>,
+    /// The chain checked against the TLSA records, with `--dane`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dane: Option<Dane>,
+    /// Whether the leaf is revoked, with `--check-revocation`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<Revocation>,
+    /// The response to the request sent with `--http`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http: Option<HttpProbe>,
+    /// The server's SETTINGS, with `--h2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub h2: Option<H2Probe>,
+    /// The response to `GET /v2/`, with `--registry`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<RegistryProbe>,
+    /// The certs Docker uses for the registry, with `--docker-certs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docker_certs: Option<DockerCerts>,
+    /// How the leaf differs from the one seen on the last run, with
+    /// `--state`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<CertChanges>,
 }
 
 pub fn print_tls_connection_with_certs(
-    connection: ConnectionWithCerts,
+    mut connection: ConnectionWithCerts,
     format: Format,
+    options: &RenderOptions,
 ) -> color_eyre::Result<()> {
+    if options.stable {
+        connection.tls.make_stable();
+        connection
+            .certs
+            .iter_mut()
+            .for_each(SimpleCert::make_stable);
+    }
+
     match format {
         Format::Text => {
-            element! {
+            options.print(element! {
                 View(flex_direction: FlexDirection::Column, gap: 1, margin: 1) {
-                    TlsConnectionView(tls: connection.tls)
+                    TlsConnectionView(tls: connection.tls, options: options.clone())
+                    #((!connection.chain_issues.is_empty()).then(|| element! {
+                        View(flex_direction: FlexDirection::Column) {
+                            Text(content: "chain:", color: options.theme.top_level)
+                            #(connection.chain_issues.iter().map(|issue| element! {
+                                View(margin_left: 4) {
+                                    Text(content: format!("⚠️  {issue}"), color: options.theme.warning)
+                                }
+                            }))
+                        }
+                    }))
+                    #(connection.paths.clone().map(|tree| element! {
+                        PathsView(tree: Some(tree), options: options.clone())
+                    }))
+                    #(connection.dane.as_ref().map(|dane| element! {
+                        DaneView(dane: dane.clone())
+                    }))
+                    #(connection.revocation.as_ref().map(|revocation| element! {
+                        RevocationView(revocation: revocation.clone())
+                    }))
+                    #(connection.http.as_ref().map(|http| element! {
+                        HttpProbeView(http: http.clone())
+                    }))
+                    #(connection.h2.as_ref().map(|h2| element! {
+                        H2ProbeView(h2: h2.clone())
+                    }))
+                    #(connection.registry.as_ref().map(|registry| element! {
+                        RegistryProbeView(registry: registry.clone())
+                    }))
+                    #(connection.docker_certs.as_ref().map(|docker_certs| element! {
+                        DockerCertsView(docker_certs: docker_certs.clone())
+                    }))
+                    #(connection.changes.as_ref().map(|changes| element! {
+                        CertChangesView(changes: changes.clone())
+                    }))
                     // only print certs if there are any
                     #((!connection.certs.is_empty()).then(|| element! {
                         View(flex_direction: FlexDirection::Column) {
-                            Text(content: "certs:", color: TOP_LEVEL_COLOR)
+                            Text(content: "certs:", color: options.theme.top_level)
                             View(margin_left: 4) {
-                                MultipleCertView(certs: connection.certs)
+                                MultipleCertView(certs: connection.certs.into_iter().map(Arc::new).collect::<Vec<_>>(), options: options.clone())
                             }
                         }
                     }))
                 }
-            }
-            .print();
+            });
         }
-        Format::Json => {
-            println!("{}", serde_json::to_string_pretty(&connection)?);
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            println!("{}", format.to_json(&connection)?);
         }
         Format::Pem => {
             for cert in connection.certs {
-                print!("{}", cert.pem);
+                options.print_pem(|| pem_header(&cert, options), &cert.pem);
             }
         }
     }
@@ -0,0 +1,117 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::x509::located,
+    crl::SimpleCrl,
+    theme::UseTheme,
+};
+
+#[derive(Default, Props)]
+pub struct CrlProps {
+    crl: SimpleCrl,
+    options: RenderOptions,
+}
+
+#[component]
+pub fn CrlView(mut hooks: Hooks, props: &CrlProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let crl = &props.crl;
+    let issuer = props.options.name(&crl.issuer.rfc4514, &crl.issuer);
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(located(&props.options, crl.source.as_ref()))
+            View(gap: 1) {
+                Text(content: "issuer:")
+                Text(content: issuer, color: theme.highlight)
+            }
+            View(gap: 1) {
+                Text(content: "this update:")
+                Text(content: crl.this_update.to_string())
+            }
+            #(crl.next_update.map(|next_update| element! {
+                View(gap: 1) {
+                    Text(content: "next update:")
+                    Text(content: next_update.to_string())
+                }
+            }))
+            Text(content: format!("revoked: {}", crl.revoked.len()))
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(crl.revoked.iter().map(|revoked| element! {
+                    View(gap: 1) {
+                        Text(content: props.options.serial(&revoked.serial), color: theme.accent)
+                        Text(content: format!("({})", revoked.revoked_at))
+                        #(revoked.reason.as_ref().map(|reason| element! {
+                            Text(content: reason.clone(), color: theme.warning)
+                        }))
+                    }
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct MultipleCrlViewProps {
+    pub crls: Vec<SimpleCrl>,
+    pub options: RenderOptions,
+}
+
+#[component]
+pub fn MultipleCrlView(
+    mut hooks: Hooks,
+    props: &MultipleCrlViewProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            #(props.crls.iter().cloned().enumerate().map(|(i, crl)| element!(
+                View(flex_direction: FlexDirection::Column) {
+                    Text(content: format!("crl #{}:", i + 1), color: theme.heading)
+                    View(margin_left: 4) {
+                        CrlView(crl, options: props.options.clone())
+                    }
+                }
+            )))
+        }
+    }
+}
+
+pub fn print_crls(
+    crls: Vec<SimpleCrl>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    tracing::info!("printing {} crls in {format:?} format", crls.len());
+
+    match format {
+        Format::Text => {
+            options.print(element! {
+                View(margin: 1) {
+                    MultipleCrlView(crls, options: options.clone())
+                }
+            });
+        }
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&crls)?;
+        }
+        Format::Pem => {
+            for crl in crls {
+                let header = || {
+                    format!(
+                        "CRL from {}",
+                        options.name(&crl.issuer.rfc4514, &crl.issuer)
+                    )
+                };
+                options.print_pem(header, &crl.pem);
+            }
+        }
+    }
+
+    Ok(())
+}
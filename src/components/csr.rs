@@ -1,27 +1,95 @@
 use iocraft::{
     component, element,
-    prelude::{Text, View},
-    AnyElement, Color, ElementExt, FlexDirection, Props,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props,
 };
+use serde::Serialize;
 
 use crate::{
-    commands::Format,
-    components::x509::{PublicKeyView, SignatureView, SubjectView},
-    x509::SimpleCsr,
+    commands::{Format, RenderOptions},
+    components::x509::{located, IssuesView, PublicKeyView, SignatureView, SubjectView, UsageView},
+    theme::UseTheme,
+    x509::{CsrAttributes, CsrCheck, SimpleCsr, SimplePublicKeyKind},
 };
 
 #[derive(Default, Props)]
 pub struct CsrProps {
     csr: SimpleCsr,
+    options: RenderOptions,
 }
 
 #[component]
 pub fn CsrView(props: &CsrProps) -> impl Into<AnyElement<'static>> {
     element! {
         View(flex_direction: FlexDirection::Column) {
-            SubjectView(subject: props.csr.subject.clone(), serial: None)
-            PublicKeyView(public_key: props.csr.public_key.clone())
-            SignatureView(signature: props.csr.signature.clone(), top_level: true)
+            #(located(&props.options, props.csr.source.as_ref()))
+            IssuesView(issues: props.csr.issues.clone())
+            SubjectView(subject: props.csr.subject.clone(), serial: None, options: props.options.clone())
+            PublicKeyView(public_key: props.csr.public_key.clone(), options: props.options.clone())
+            CsrAttributesView(attributes: props.csr.attributes.clone(), options: props.options.clone())
+            SignatureView(signature: props.csr.signature.clone(), top_level: true, options: props.options.clone())
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct CsrAttributesProps {
+    pub attributes: CsrAttributes,
+    pub options: RenderOptions,
+}
+
+/// The extensions and attributes a CSR requests. Renders nothing when there
+/// are none besides SANs, which are shown with the subject.
+#[component]
+pub fn CsrAttributesView(
+    mut hooks: Hooks,
+    props: &CsrAttributesProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let attributes = &props.attributes;
+
+    let mut rows = Vec::new();
+    if let Some(key_usage) = &attributes.key_usage {
+        let extended = key_usage.extended.names();
+        if !extended.is_empty() {
+            rows.push(("extended usage:", extended.join(", ")));
+        }
+    }
+    if let Some(constraints) = &attributes.basic_constraints {
+        let mut text = format!("CA:{}", if constraints.ca { "TRUE" } else { "FALSE" });
+        if let Some(path_len) = constraints.path_len {
+            text.push_str(&format!(", path length {path_len}"));
+        }
+        rows.push(("basic constraints:", text));
+    }
+    if !attributes.other_extensions.is_empty() {
+        rows.push(("other extensions:", attributes.other_extensions.join(", ")));
+    }
+    if attributes.challenge_password {
+        rows.push(("challenge password:", "present".to_string()));
+    }
+    if let Some(name) = &attributes.unstructured_name {
+        rows.push(("unstructured name:", name.clone()));
+    }
+
+    if attributes.key_usage.is_none() && rows.is_empty() {
+        return element! { View() };
+    }
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: "requested:", color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(attributes.key_usage.clone().map(|key_usage| element! {
+                    UsageView(key_usage, options: props.options.clone())
+                }))
+                #(rows.into_iter().map(|(name, value)| element! {
+                    View(gap: 1) {
+                        Text(content: name)
+                        Text(content: value)
+                    }
+                }))
+            }
         }
     }
 }
@@ -29,39 +97,203 @@ pub fn CsrView(props: &CsrProps) -> impl Into<AnyElement<'static>> {
 #[derive(Default, Props)]
 pub struct MultipleCsrViewProps {
     pub csrs: Vec<SimpleCsr>,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn MultipleCsrView(props: &MultipleCsrViewProps) -> impl Into<AnyElement<'static>> {
+pub fn MultipleCsrView(
+    mut hooks: Hooks,
+    props: &MultipleCsrViewProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     element! {
         View(flex_direction: FlexDirection::Column, gap: 1) {
             #(props.csrs.iter().cloned().enumerate().map(|(i, csr)| element!(
                 View(flex_direction: FlexDirection::Column) {
-                    Text(content: format!("csr #{}:", i + 1), color: Color::Magenta)
-                    CsrView(csr)
+                    Text(content: format!("csr #{}:", i + 1), color: theme.heading)
+                    CsrView(csr, options: props.options.clone())
                 }
             )))
         }
     }
 }
 
-pub fn print_csrs(csrs: Vec<SimpleCsr>, format: Format) -> color_eyre::Result<()> {
+pub fn print_csrs(
+    mut csrs: Vec<SimpleCsr>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
     tracing::info!("printing {} csrs in {format:?} format", csrs.len());
+    if options.stable {
+        csrs.iter_mut().for_each(|csr| csr.subject.sans.sort());
+    }
+
     match format {
         Format::Text => {
-            element! {
+            options.print(element! {
                 View(margin: 1) {
-                    MultipleCsrView(csrs)
+                    MultipleCsrView(csrs, options: options.clone())
                 }
-            }
-            .print();
+            });
         }
-        Format::Json => {
-            println!("{}", serde_json::to_string_pretty(&csrs)?);
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&csrs)?;
         }
         Format::Pem => {
             for csr in csrs {
-                print!("{}", csr.pem);
+                let header = || {
+                    format!(
+                        "CSR for {}",
+                        options.name(&csr.subject.name, &csr.subject.dn)
+                    )
+                };
+                options.print_pem(header, &csr.pem);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A CSR and the result of checking it, for `pls csr verify`.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedCsr {
+    pub subject: String,
+    #[serde(flatten)]
+    pub check: CsrCheck,
+    #[serde(skip)]
+    pub csr: SimpleCsr,
+}
+
+#[derive(Default, Props)]
+pub struct VerifiedCsrProps {
+    pub verified: Option<VerifiedCsr>,
+    pub options: RenderOptions,
+}
+
+#[component]
+pub fn VerifiedCsrView(
+    mut hooks: Hooks,
+    props: &VerifiedCsrProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let Some(verified) = &props.verified else {
+        return element! { View() };
+    };
+    let VerifiedCsr { check, csr, .. } = verified;
+
+    let signature = if check.signature_valid {
+        element! {
+            Text(content: format!("✅ valid ({})", csr.signature.describe()), color: theme.good)
+        }
+    } else {
+        element! {
+            Text(content: format!("🚨 invalid ({})", csr.signature.describe()), color: theme.bad, decoration: TextDecoration::Underline)
+        }
+    };
+    let key = match &csr.public_key.kind {
+        SimplePublicKeyKind::RSA { size, .. } => format!("RSA {size} bits"),
+        SimplePublicKeyKind::RsaPss { size, .. } => format!("RSA-PSS {size} bits"),
+        SimplePublicKeyKind::DSA { size, .. } => format!("DSA {size} bits"),
+        SimplePublicKeyKind::EC { group, .. } => format!(
+            "EC {}",
+            group
+                .and_then(|group| group.short_name().ok())
+                .unwrap_or("unknown curve")
+        ),
+        SimplePublicKeyKind::Ed25519 { .. } => "Ed25519".to_string(),
+        SimplePublicKeyKind::Ed448 { .. } => "Ed448".to_string(),
+        SimplePublicKeyKind::X25519 { .. } => "X25519".to_string(),
+        SimplePublicKeyKind::X448 { .. } => "X448".to_string(),
+        SimplePublicKeyKind::Other {
+            algorithm,
+            parameters,
+            ..
+        } => match parameters {
+            Some(parameters) => format!("{algorithm} {parameters}"),
+            None => algorithm.clone(),
+        },
+    };
+    let sans = csr.subject.sans.dns.iter().cloned();
+    let sans = sans
+        .chain(csr.subject.sans.ip.iter().map(ToString::to_string))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let requested = if check.requested_extensions.is_empty() {
+        "none".to_string()
+    } else {
+        check.requested_extensions.join(", ")
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "signature:", color: theme.top_level)
+                #(signature)
+            }
+            View(gap: 1) {
+                Text(content: "key:", color: theme.top_level)
+                Text(content: key)
+            }
+            View(gap: 1) {
+                Text(content: "requested extensions:", color: theme.top_level)
+                Text(content: requested)
+            }
+            #((!sans.is_empty()).then(|| element! {
+                View(gap: 1) {
+                    Text(content: "sans:", color: theme.top_level)
+                    Text(content: sans)
+                }
+            }))
+            #(check.challenge_password.then(|| element! {
+                View(gap: 1) {
+                    Text(content: "challenge password:", color: theme.top_level)
+                    Text(content: "present")
+                }
+            }))
+            #(check.errors.iter().map(|error| element! {
+                Text(content: format!("🚨 {error}"), color: theme.bad)
+            }))
+            #(check.warnings.iter().map(|warning| element! {
+                Text(content: format!("⚠️  {warning}"), color: theme.warning)
+            }))
+        }
+    }
+}
+
+pub fn print_verified_csrs(
+    verified: Vec<VerifiedCsr>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    match format {
+        Format::Text => {
+            options.print(element! {
+                View(flex_direction: FlexDirection::Column, gap: 1, margin: 1) {
+                    #(verified.into_iter().enumerate().map(|(i, verified)| element! {
+                        View(flex_direction: FlexDirection::Column) {
+                            Text(content: format!("csr #{}: {}", i + 1, verified.subject), color: options.theme.heading)
+                            View(margin_left: 4) {
+                                VerifiedCsrView(verified: Some(verified), options: options.clone())
+                            }
+                        }
+                    }))
+                }
+            });
+        }
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&verified)?;
+        }
+        Format::Pem => {
+            for verified in verified {
+                let csr = &verified.csr;
+                let header = || {
+                    format!(
+                        "CSR for {}",
+                        options.name(&csr.subject.name, &csr.subject.dn)
+                    )
+                };
+                options.print_pem(header, &csr.pem);
             }
         }
     }
@@ -0,0 +1,165 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+};
+
+/// The outcome of one check of `pls doctor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pass,
+    /// Works, but should be fixed.
+    Warn,
+    Fail,
+    /// Couldn't be checked, e.g. because an earlier check failed.
+    Skip,
+}
+
+/// One line of the scorecard.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    /// What was checked, e.g. `tls handshake`.
+    pub name: &'static str,
+    pub status: Status,
+    /// What was found, e.g. `TLSv1.3 with X25519MLKEM768`.
+    pub detail: String,
+    /// How to fix a warning or failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Check {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self::new(name, Status::Pass, detail, None)
+    }
+
+    pub fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self::new(name, Status::Warn, detail, Some(hint.into()))
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self::new(name, Status::Fail, detail, Some(hint.into()))
+    }
+
+    pub fn skip(name: &'static str, detail: impl Into<String>) -> Self {
+        Self::new(name, Status::Skip, detail, None)
+    }
+
+    fn new(
+        name: &'static str,
+        status: Status,
+        detail: impl Into<String>,
+        hint: Option<String>,
+    ) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            hint,
+        }
+    }
+}
+
+/// Everything `pls doctor` found out about an endpoint, or `inspect
+/// tls-config` about a config.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DoctorReport {
+    /// What the checks were run by, e.g. `doctor`.
+    #[serde(skip)]
+    pub heading: &'static str,
+    /// What was checked: the host and port, e.g. `example.com:443`, or the
+    /// config file.
+    pub target: String,
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    pub fn failed(&self) -> usize {
+        self.count(Status::Fail)
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+}
+
+#[derive(Default, Props)]
+pub struct DoctorProps {
+    pub report: DoctorReport,
+}
+
+#[component]
+pub fn DoctorView(mut hooks: Hooks, props: &DoctorProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let report = &props.report;
+
+    let row = |check: &Check| {
+        let (mark, color) = match check.status {
+            Status::Pass => ("✅", theme.good),
+            Status::Warn => ("⚠️ ", theme.warning),
+            Status::Fail => ("🚨", theme.bad),
+            Status::Skip => ("➖", theme.highlight),
+        };
+        element! {
+            View(flex_direction: FlexDirection::Column) {
+                View(gap: 1) {
+                    Text(content: mark)
+                    View(width: 16) {
+                        Text(content: check.name, color: color)
+                    }
+                    Text(content: check.detail.clone())
+                }
+                #(check.hint.clone().map(|hint| element! {
+                    View(margin_left: 4) {
+                        Text(content: format!("hint: {hint}"), color: theme.highlight)
+                    }
+                }))
+            }
+        }
+    };
+
+    let summary = format!(
+        "{} passed, {} warnings, {} failed",
+        report.count(Status::Pass),
+        report.count(Status::Warn),
+        report.count(Status::Fail),
+    );
+
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            View(gap: 1) {
+                Text(content: format!("{}:", report.heading), color: theme.top_level)
+                Text(content: report.target.clone(), color: theme.highlight)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(report.checks.iter().map(row))
+            }
+            Text(
+                content: summary,
+                color: if report.failed() > 0 { theme.bad } else { theme.good },
+                decoration: if report.failed() > 0 { TextDecoration::Underline } else { TextDecoration::None },
+            )
+        }
+    }
+}
+
+pub fn print_doctor(
+    report: DoctorReport,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(report, format, options, |report| {
+        element! { DoctorView(report) }.into()
+    })
+}
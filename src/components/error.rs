@@ -0,0 +1,183 @@
+use std::io;
+
+use color_eyre::Report;
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+use tokio::net::TcpStream;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    theme::UseTheme,
+};
+
+/// What went wrong, for scripts to branch on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// A file or directory doesn't exist.
+    NotFound,
+    PermissionDenied,
+    /// Nothing is listening on the port.
+    ConnectionRefused,
+    TimedOut,
+    /// Any other I/O or network failure.
+    Io,
+    /// The TLS handshake failed.
+    Tls,
+    /// The input couldn't be parsed.
+    Parse,
+    #[default]
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify `err` by the first cause in its chain that's recognized.
+    pub fn of(err: &Report) -> Self {
+        for cause in err.chain() {
+            if let Some(err) = cause.downcast_ref::<io::Error>() {
+                return match err.kind() {
+                    io::ErrorKind::NotFound => Self::NotFound,
+                    io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+                    io::ErrorKind::ConnectionRefused => Self::ConnectionRefused,
+                    io::ErrorKind::TimedOut => Self::TimedOut,
+                    io::ErrorKind::InvalidData => Self::Parse,
+                    _ => Self::Io,
+                };
+            }
+            if cause.is::<tokio_boring::HandshakeError<TcpStream>>()
+                || cause.is::<boring::ssl::Error>()
+            {
+                return Self::Tls;
+            }
+            if cause.is::<boring::error::ErrorStack>() || cause.is::<serde_json::Error>() {
+                return Self::Parse;
+            }
+        }
+        Self::Other
+    }
+
+    /// What to try next.
+    fn hint(self) -> Option<&'static str> {
+        Some(match self {
+            Self::NotFound => "check the path, or pipe the input into stdin",
+            Self::PermissionDenied => "check the permissions of the file",
+            Self::ConnectionRefused => "nothing is listening there, check the host and --port",
+            Self::TimedOut => "the host may be down or firewalled, or need a --proxy",
+            Self::Tls => "the server may not speak TLS on this port, run with -vvv for details",
+            Self::Parse => "check the input is PEM or DER in the format the command expects",
+            Self::Io | Self::Other => return None,
+        })
+    }
+}
+
+/// A failure, as shown to the user instead of a full report with its
+/// backtrace.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ErrorDetails {
+    pub kind: ErrorKind,
+    /// The outermost message, e.g. `Reading cert.pem`.
+    pub message: String,
+    /// What led to it, outermost first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<&'static str>,
+}
+
+impl From<&Report> for ErrorDetails {
+    fn from(err: &Report) -> Self {
+        let kind = ErrorKind::of(err);
+        ErrorDetails {
+            kind,
+            message: err.to_string(),
+            causes: err.chain().skip(1).map(ToString::to_string).collect(),
+            hint: kind.hint(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorOutput<'a> {
+    error: &'a ErrorDetails,
+}
+
+#[derive(Default, Props)]
+pub struct ErrorProps {
+    pub error: ErrorDetails,
+}
+
+#[component]
+pub fn ErrorView(mut hooks: Hooks, props: &ErrorProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let error = &props.error;
+    element! {
+        View(flex_direction: FlexDirection::Column, margin: 1) {
+            Text(content: format!("🚨 error: {}", error.message), color: theme.bad, decoration: TextDecoration::Underline)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(error.causes.iter().map(|cause| element! {
+                    Text(content: format!("caused by: {cause}"))
+                }))
+                #(error.hint.map(|hint| element! {
+                    Text(content: format!("hint: {hint}"), color: theme.highlight)
+                }))
+            }
+        }
+    }
+}
+
+/// Show `err` concisely: a JSON `error` object on stdout in the JSON formats,
+/// where scripts read the output, and an [`ErrorView`] on stderr otherwise.
+pub fn print_error(err: &Report, format: Format, options: &RenderOptions) {
+    let error = ErrorDetails::from(err);
+    if format.is_json() {
+        if let Ok(json) = format.to_json(&ErrorOutput { error: &error }) {
+            println!("{json}");
+            return;
+        }
+    }
+
+    options.eprint(element! {
+        ErrorView(error)
+    });
+}
+
+/// Show a warning on stderr, whatever the format, since stdout may be the
+/// data. Like errors, warnings aren't silenced by `--quiet`.
+pub fn print_warning(message: impl Into<String>, options: &RenderOptions) {
+    let message = message.into();
+    options.eprint(element! {
+        View(margin_left: 1) {
+            Text(content: format!("⚠️  warning: {message}"), color: options.theme.warning)
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::{eyre, WrapErr};
+
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        let missing = std::fs::read("/nonexistent/cert.pem")
+            .wrap_err("Reading /nonexistent/cert.pem")
+            .unwrap_err();
+        let details = ErrorDetails::from(&missing);
+        assert_eq!(details.kind, ErrorKind::NotFound);
+        assert_eq!(details.message, "Reading /nonexistent/cert.pem");
+        assert_eq!(details.causes.len(), 1);
+        assert!(details.hint.is_some());
+
+        let parse = boring::x509::X509::from_pem(b"nope")
+            .wrap_err("Parsing issuers")
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&parse), ErrorKind::Parse);
+
+        assert_eq!(ErrorKind::of(&eyre!("no `x5c` found")), ErrorKind::Other);
+    }
+}
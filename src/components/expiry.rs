@@ -0,0 +1,273 @@
+use std::time::Duration;
+
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use jiff::Timestamp;
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::{print_report_list, round_relative_human},
+    theme::UseTheme,
+    x509::{NotAfter, SimpleCert},
+};
+
+/// Certs expiring in fewer days than this are critical.
+pub const CRITICAL_DAYS: i64 = 7;
+/// Certs expiring in fewer days than this need renewing soon.
+pub const WARNING_DAYS: i64 = 30;
+
+/// How urgently a cert needs renewing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Expired,
+    /// Expires within [`CRITICAL_DAYS`].
+    Critical,
+    /// Expires within [`WARNING_DAYS`].
+    Warning,
+    Ok,
+    /// No expiry to go by: the cert has none, it couldn't be parsed or the
+    /// input couldn't be read.
+    Unknown,
+}
+
+impl Severity {
+    /// The severity of a cert expiring at `not_after`, seen at `now`.
+    pub fn at(not_after: Option<Timestamp>, now: Timestamp) -> Self {
+        let seconds_left = not_after.map(|not_after| not_after.as_second() - now.as_second());
+        match seconds_left {
+            None => Severity::Unknown,
+            Some(seconds) if seconds <= 0 => Severity::Expired,
+            Some(seconds) if seconds < CRITICAL_DAYS * 86400 => Severity::Critical,
+            Some(seconds) if seconds < WARNING_DAYS * 86400 => Severity::Warning,
+            Some(_) => Severity::Ok,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Expired => "expired",
+            Severity::Critical => "critical",
+            Severity::Warning => "warning",
+            Severity::Ok => "ok",
+            Severity::Unknown => "unknown",
+        }
+    }
+}
+
+/// Whether an input's cert could be checked, so partial results stand out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    #[default]
+    Ok,
+    /// The input couldn't be read, or the host couldn't be reached.
+    Error,
+    /// The host didn't answer within `--timeout`.
+    Timeout,
+    /// Stopped by `--fail-fast` before the host answered.
+    Skipped,
+}
+
+/// When one cert expires.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpiryEntry {
+    /// The file or host the cert came from.
+    pub input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<Timestamp>,
+    /// Whole days until `not_after`, negative once it has passed. Left out
+    /// with `--stable`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days_left: Option<i64>,
+    pub severity: Severity,
+    pub status: Status,
+    /// Why there's no cert to check, e.g. the host couldn't be reached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ExpiryEntry {
+    pub fn new(input: &str, cert: &SimpleCert, now: Timestamp) -> Self {
+        let not_after = cert.validity.not_after.and_then(NotAfter::timestamp);
+        let seconds_left = not_after.map(|not_after| not_after.as_second() - now.as_second());
+
+        ExpiryEntry {
+            input: input.to_string(),
+            subject: Some(cert.subject.name.clone()),
+            not_after,
+            days_left: seconds_left.map(|seconds| seconds.div_euclid(86400)),
+            severity: Severity::at(not_after, now),
+            status: Status::Ok,
+            error: None,
+        }
+    }
+
+    /// An input that had no cert to check.
+    pub fn failed(input: &str, error: String) -> Self {
+        ExpiryEntry {
+            input: input.to_string(),
+            subject: None,
+            not_after: None,
+            days_left: None,
+            severity: Severity::Unknown,
+            status: Status::Error,
+            error: Some(error),
+        }
+    }
+
+    /// A host that didn't answer within `timeout`.
+    pub fn timed_out(input: &str, timeout: Duration) -> Self {
+        ExpiryEntry {
+            status: Status::Timeout,
+            ..Self::failed(input, format!("timed out after {}s", timeout.as_secs()))
+        }
+    }
+
+    /// A host that was given up on because another failed first.
+    pub fn skipped(input: &str) -> Self {
+        ExpiryEntry {
+            status: Status::Skipped,
+            ..Self::failed(input, "skipped after another input failed".to_string())
+        }
+    }
+
+    /// Whether the cert expires within `seconds` from `now`, or has expired.
+    pub fn expires_within(&self, seconds: i64, now: Timestamp) -> bool {
+        self.not_after
+            .is_some_and(|not_after| not_after.as_second() - now.as_second() <= seconds)
+    }
+}
+
+/// Sort `entries` soonest expiry first, with the ones that have no expiry
+/// last.
+pub fn sort(entries: &mut [ExpiryEntry]) {
+    entries.sort_by_key(|entry| (entry.not_after.is_none(), entry.not_after));
+}
+
+#[derive(Default, Props)]
+pub struct ExpiryProps {
+    pub entries: Vec<ExpiryEntry>,
+    pub options: RenderOptions,
+}
+
+#[component]
+pub fn ExpiryView(mut hooks: Hooks, props: &ExpiryProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let options = &props.options;
+
+    let row = |entry: &ExpiryEntry| {
+        let color = match entry.severity {
+            Severity::Expired | Severity::Critical => theme.bad,
+            Severity::Warning => theme.warning,
+            Severity::Ok => theme.good,
+            Severity::Unknown => theme.highlight,
+        };
+        let when = match (&entry.error, entry.not_after) {
+            (Some(_), _) if entry.status == Status::Timeout => "timeout".to_string(),
+            (Some(_), _) if entry.status == Status::Skipped => "skipped".to_string(),
+            (Some(_), _) => "error".to_string(),
+            (None, None) => "no expiry".to_string(),
+            // no relative times
+            (None, Some(_)) if options.stable => entry.severity.label().to_string(),
+            (None, Some(not_after)) => {
+                let left = options.now.timestamp().until(not_after).unwrap_or_default();
+                let rounded = round_relative_human(left, options.now.clone());
+                if left.signum() < 0 {
+                    format!("expired {rounded:#}")
+                } else {
+                    format!("in {rounded:#}")
+                }
+            }
+        };
+        let date = entry
+            .not_after
+            .map(|not_after| not_after.strftime("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let urgent = matches!(entry.severity, Severity::Expired | Severity::Critical);
+
+        element! {
+            View(gap: 1) {
+                View(width: 22) {
+                    Text(content: when, color: color, decoration: if urgent { TextDecoration::Underline } else { TextDecoration::None })
+                }
+                View(width: 10) {
+                    Text(content: date)
+                }
+                #(entry.subject.clone().map(|subject| element! {
+                    Text(content: subject, color: theme.highlight)
+                }))
+                Text(content: format!("({})", entry.input))
+                #(entry.error.clone().map(|error| element! {
+                    Text(content: error, color: theme.bad)
+                }))
+            }
+        }
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(props.entries.iter().map(row))
+            #(props.entries.is_empty().then(|| element! {
+                Text(content: "✅ nothing expires in that time", color: theme.good)
+            }))
+        }
+    }
+}
+
+pub fn print_expiry(
+    mut entries: Vec<ExpiryEntry>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    if options.stable {
+        entries.iter_mut().for_each(|entry| entry.days_left = None);
+    }
+
+    print_report_list(entries, format, options, |entries| {
+        element! { ExpiryView(entries, options: options.clone()) }.into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn severities() {
+        let cert = SimpleCert::from(
+            X509::from_pem(include_bytes!("../../test-data/certs/lan-fish.pem")).unwrap(),
+        );
+        let not_after: Timestamp = "2025-04-03T13:18:46Z".parse().unwrap();
+        let days_before = |days: i64| not_after - jiff::Span::new().hours(24 * days);
+
+        let entry = |now| ExpiryEntry::new("cert.pem", &cert, now);
+        assert_eq!(entry(days_before(90)).severity, Severity::Ok);
+        assert_eq!(entry(days_before(20)).severity, Severity::Warning);
+        assert_eq!(entry(days_before(3)).severity, Severity::Critical);
+        assert_eq!(entry(not_after).severity, Severity::Expired);
+        assert_eq!(entry(days_before(20)).days_left, Some(20));
+
+        assert!(entry(days_before(20)).expires_within(30 * 86400, days_before(20)));
+        assert!(!entry(days_before(90)).expires_within(30 * 86400, days_before(90)));
+
+        let mut entries = vec![
+            ExpiryEntry::failed("down.example.com:443", "refused".into()),
+            entry(days_before(3)),
+        ];
+        sort(&mut entries);
+        assert_eq!(entries[0].input, "cert.pem");
+
+        let timed_out = ExpiryEntry::timed_out("slow.example.com:443", Duration::from_secs(30));
+        assert_eq!(timed_out.status, Status::Timeout);
+        assert_eq!(timed_out.error.as_deref(), Some("timed out after 30s"));
+    }
+}
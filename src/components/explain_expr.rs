@@ -0,0 +1,42 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report,
+    filter::Expr,
+    theme::UseTheme,
+};
+
+#[derive(Default, Props)]
+pub struct ExprProps {
+    /// The expression with the parentheses it needs.
+    pub expr: String,
+    pub tree: Vec<String>,
+}
+
+#[component]
+pub fn ExprView(mut hooks: Hooks, props: &ExprProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: "expression:", color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                Text(content: props.expr.clone(), color: theme.highlight)
+                View(flex_direction: FlexDirection::Column, margin_top: 1) {
+                    #(props.tree.iter().map(|line| element! { Text(content: line.clone()) }))
+                }
+            }
+        }
+    }
+}
+
+pub fn print_expr(expr: &Expr, format: Format, options: &RenderOptions) -> color_eyre::Result<()> {
+    print_report(expr, format, options, |expr| {
+        element! { ExprView(expr: expr.to_string(), tree: expr.tree()) }.into()
+    })
+}
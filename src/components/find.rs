@@ -0,0 +1,92 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::x509::pem_header,
+    pem::Source,
+    theme::UseTheme,
+    x509::{NotAfter, SimpleCert},
+};
+
+/// A cert `find` found, with where it is.
+#[derive(Debug, Clone, Serialize)]
+pub struct FoundCert {
+    pub source: Option<Source>,
+    pub subject: String,
+    pub issuer: String,
+    pub not_after: Option<NotAfter>,
+    pub sha256: String,
+}
+
+impl FoundCert {
+    pub fn new(cert: &SimpleCert) -> Self {
+        Self {
+            source: cert.source.clone(),
+            subject: cert.subject.name.clone(),
+            issuer: cert.issuer.name.clone(),
+            not_after: cert.validity.not_after,
+            sha256: cert.fingerprints.sha256.clone(),
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct FoundCertProps {
+    pub location: String,
+    pub name: String,
+    pub expires: String,
+}
+
+/// One line of `find`: `certs/site.pem:1-21  CN=example.com  (expires ...)`.
+#[component]
+pub fn FoundCertView(mut hooks: Hooks, props: &FoundCertProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+
+    element! {
+        View(gap: 2) {
+            Text(content: props.location.clone(), color: theme.accent)
+            Text(content: props.name.clone(), color: theme.highlight)
+            Text(content: props.expires.clone())
+        }
+    }
+}
+
+/// Print one match as it's found: a line of text, an NDJSON line or the
+/// cert's PEM.
+pub fn print_found(cert: &SimpleCert, format: Format, options: &RenderOptions) {
+    match format {
+        Format::Pem => options.print_pem(|| pem_header(cert, options), &cert.pem),
+        Format::Ndjson => {
+            if let Err(err) = format.print_json_list(&[FoundCert::new(cert)]) {
+                tracing::warn!("failed to print a match: {err}");
+            }
+        }
+        _ => {
+            let location = cert
+                .source
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            let expires = match cert.validity.not_after {
+                Some(NotAfter::At(time)) => format!("(expires {})", time.strftime("%Y-%m-%d")),
+                Some(NotAfter::NoExpiry) => "(never expires)".to_string(),
+                None => String::new(),
+            };
+            let name = options.name(&cert.subject.name, &cert.subject.dn);
+            options.print(element! {
+                FoundCertView(location, name, expires)
+            });
+        }
+    }
+}
+
+/// Print the matches as one JSON document.
+pub fn print_found_list(found: &[FoundCert], format: Format) -> color_eyre::Result<()> {
+    format.print_json_list(found)?;
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, Color, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::{
+        round_relative_human,
+        x509::{pem_header, MultipleCertView},
+    },
+    jose::{DecodedJwt, SignatureCheck},
+    theme::UseTheme,
+    x509::SimpleCert,
+};
+
+#[derive(Default, Props)]
+pub struct JwtProps {
+    pub jwt: DecodedJwt,
+    pub options: RenderOptions,
+}
+
+#[component]
+pub fn JwtView(mut hooks: Hooks, props: &JwtProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let jwt = &props.jwt;
+    let zoned_now = props.options.now.clone();
+    let now = zoned_now.timestamp();
+
+    let times = [
+        ("issued:", jwt.issued_at),
+        ("not before:", jwt.not_before),
+        ("expires:", jwt.expires),
+    ]
+    .into_iter()
+    .filter_map(|(name, time)| Some((name, time?)))
+    .map(|(name, time)| {
+        let relative = now
+            .until(time)
+            .ok()
+            .filter(|_| !props.options.stable)
+            .map(|span| {
+                let rounded = round_relative_human(span, zoned_now.clone());
+                if span.signum() < 0 {
+                    format!("({:#})", rounded)
+                } else {
+                    format!("(in {:#})", rounded)
+                }
+            })
+            .unwrap_or_default();
+        let color = if name == "expires:" && time < now {
+            theme.bad
+        } else {
+            Color::Reset
+        };
+
+        element! {
+            View(gap: 1) {
+                Text(content: name)
+                Text(content: time.to_string())
+                Text(content: relative, color: color)
+            }
+        }
+    });
+
+    let signature = match &jwt.signature {
+        SignatureCheck::Unverified => element! {
+            Text(content: "signature not verified, pass `--key` to check it", color: theme.warning)
+        },
+        SignatureCheck::Valid => element! {
+            Text(content: "✅ signature valid", color: theme.good)
+        },
+        SignatureCheck::Invalid(reason) => element! {
+            Text(content: format!("🚨 signature invalid: {reason}"), color: theme.bad, decoration: TextDecoration::Underline)
+        },
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "jwt:", color: theme.top_level)
+                Text(content: jwt.alg.clone().unwrap_or_else(|| "no alg".to_string()), color: theme.highlight)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(signature)
+                #(jwt.kid.clone().map(|kid| element! {
+                    View(gap: 1) {
+                        Text(content: "kid:")
+                        Text(content: kid)
+                    }
+                }))
+                #(times)
+            }
+            Text(content: "claims:", color: theme.top_level)
+            View(margin_left: 4) {
+                Text(content: serde_json::to_string_pretty(&jwt.claims).unwrap_or_default())
+            }
+        }
+    }
+}
+
+#[derive(Default, Debug, Serialize)]
+pub struct JwtWithCerts {
+    pub jwt: DecodedJwt,
+    /// The chain from the header's `x5c`, if there is one.
+    pub certs: Vec<SimpleCert>,
+}
+
+/// The certs of one `x5c` array, e.g. one key of a JWKS document.
+#[derive(Default, Debug, Serialize)]
+pub struct X5cChain {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    pub certs: Vec<SimpleCert>,
+}
+
+pub fn print_x5c_chains(
+    mut chains: Vec<X5cChain>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    for cert in chains.iter_mut().flat_map(|chain| chain.certs.iter_mut()) {
+        cert.validity.evaluate_at(options.now.timestamp());
+    }
+    if options.stable {
+        chains
+            .iter_mut()
+            .flat_map(|chain| chain.certs.iter_mut())
+            .for_each(SimpleCert::make_stable);
+    }
+
+    match format {
+        Format::Text => {
+            let count = chains.len();
+            options.print(element! {
+                View(flex_direction: FlexDirection::Column, gap: 1, margin: 1) {
+                    #(chains.into_iter().enumerate().map(|(i, chain)| {
+                        let mut heading = if count > 1 {
+                            format!("x5c #{}", i + 1)
+                        } else {
+                            "x5c".to_string()
+                        };
+                        if let Some(kid) = &chain.kid {
+                            heading.push_str(&format!(" (kid: {kid})"));
+                        }
+                        element! {
+                            View(flex_direction: FlexDirection::Column) {
+                                Text(content: format!("{heading}:"), color: options.theme.top_level)
+                                View(margin_left: 4) {
+                                    MultipleCertView(certs: chain.certs.into_iter().map(Arc::new).collect::<Vec<_>>(), options: options.clone())
+                                }
+                            }
+                        }
+                    }))
+                }
+            });
+        }
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&chains)?;
+        }
+        Format::Pem => {
+            for cert in chains.iter().flat_map(|chain| &chain.certs) {
+                options.print_pem(|| pem_header(cert, options), &cert.pem);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn print_jwt(
+    mut jwt: JwtWithCerts,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    if options.stable {
+        jwt.certs.iter_mut().for_each(SimpleCert::make_stable);
+    }
+
+    match format {
+        Format::Text => {
+            options.print(element! {
+                View(flex_direction: FlexDirection::Column, gap: 1, margin: 1) {
+                    JwtView(jwt: jwt.jwt, options: options.clone())
+                    #((!jwt.certs.is_empty()).then(|| element! {
+                        View(flex_direction: FlexDirection::Column) {
+                            Text(content: "x5c:", color: options.theme.top_level)
+                            View(margin_left: 4) {
+                                MultipleCertView(certs: jwt.certs.into_iter().map(Arc::new).collect::<Vec<_>>(), options: options.clone())
+                            }
+                        }
+                    }))
+                }
+            });
+        }
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            println!("{}", format.to_json(&jwt)?);
+        }
+        Format::Pem => {
+            for cert in jwt.certs {
+                options.print_pem(|| pem_header(&cert, options), &cert.pem);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report,
+    keylog::{Keylog, KeylogSession},
+    theme::UseTheme,
+};
+
+#[derive(Default, Props)]
+pub struct KeylogSessionProps {
+    pub session: KeylogSession,
+}
+
+#[component]
+pub fn KeylogSessionView(
+    mut hooks: Hooks,
+    props: &KeylogSessionProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let session = &props.session;
+
+    let (status, color) = match session.missing.is_empty() {
+        true => ("complete".to_string(), theme.good),
+        false => (format!("missing {}", session.missing.join(", ")), theme.bad),
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 2) {
+                Text(content: session.client_random.clone(), color: theme.accent)
+                Text(content: session.version.clone())
+                Text(content: status, color)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(session.secrets.iter().map(|secret| element! {
+                    View(gap: 1) {
+                        Text(content: format!("{}:", secret.label), color: theme.heading)
+                        Text(content: match &secret.secret {
+                            Some(hex) => hex.clone(),
+                            None => format!("{} bytes, redacted", secret.bytes),
+                        })
+                        Text(content: format!("(line {})", secret.line))
+                    }
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct KeylogProps {
+    pub keylog: Keylog,
+}
+
+#[component]
+pub fn KeylogView(mut hooks: Hooks, props: &KeylogProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let keylog = &props.keylog;
+
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            View(gap: 1) {
+                Text(content: "keylog:", color: theme.top_level)
+                Text(content: format!(
+                    "{} sessions, {} complete, from {} lines",
+                    keylog.sessions.len(),
+                    keylog.complete(),
+                    keylog.lines,
+                ))
+            }
+            #(keylog.sessions.iter().cloned().map(|session| element! {
+                KeylogSessionView(session)
+            }))
+            #((!keylog.issues.is_empty()).then(|| element! {
+                View(flex_direction: FlexDirection::Column) {
+                    Text(content: "skipped:", color: theme.warning)
+                    View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                        #(keylog.issues.iter().map(|issue| element! {
+                            Text(content: format!("line {}: {}", issue.line, issue.message))
+                        }))
+                    }
+                }
+            }))
+        }
+    }
+}
+
+pub fn print_keylog(
+    keylog: Keylog,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    tracing::info!(
+        "printing {} keylog sessions in {format:?} format",
+        keylog.sessions.len()
+    );
+
+    print_report(keylog, format, options, |keylog| {
+        element! { KeylogView(keylog) }.into()
+    })
+}
@@ -0,0 +1,81 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report_list,
+    hostname::{HostnameMatch, NameKind},
+    theme::UseTheme,
+};
+
+#[derive(Default, Props)]
+pub struct HostnameMatchProps {
+    pub result: HostnameMatch,
+    pub options: RenderOptions,
+}
+
+/// Whether a hostname matches, with a line per name of the cert.
+#[component]
+pub fn HostnameMatchView(
+    mut hooks: Hooks,
+    props: &HostnameMatchProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let result = &props.result;
+    let (verdict, color) = match result.matches {
+        true => ("✅ matches", theme.good),
+        false => ("❌ doesn't match", theme.bad),
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: format!("{}:", props.options.dns_name(&result.hostname)), color: theme.top_level)
+                Text(content: verdict, color: color)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(result.names.iter().map(|name| {
+                    let (mark, color) = match name.matches {
+                        true => ("✅", theme.good),
+                        false => ("❌", theme.highlight),
+                    };
+                    let label = match name.kind {
+                        NameKind::Dns => props.options.dns_name(&name.name),
+                        NameKind::Ip => format!("IP:{}", name.name),
+                        NameKind::Cn => format!("CN={}", name.name),
+                    };
+                    element! {
+                        View(gap: 1) {
+                            Text(content: mark)
+                            Text(content: label, color: color)
+                            Text(content: name.reason.clone())
+                        }
+                    }
+                }))
+                #(result.names.is_empty().then(|| element! {
+                    Text(content: "the cert has no names", color: theme.bad)
+                }))
+            }
+        }
+    }
+}
+
+pub fn print_matches(
+    results: Vec<HostnameMatch>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report_list(results, format, options, |results| {
+        element! {
+            View(flex_direction: FlexDirection::Column, gap: 1) {
+                #(results.into_iter().map(|result| element! {
+                    HostnameMatchView(result, options: options.clone())
+                }))
+            }
+        }
+        .into()
+    })
+}
@@ -1,4 +1,8 @@
+use color_eyre::eyre::bail;
+use crl::print_crls;
 use csr::print_csrs;
+use error::print_warning;
+use iocraft::{element, prelude::View, AnyElement};
 use jiff::{Span, SpanRound, Unit, Zoned};
 use private_key::print_private_keys;
 use public_key::print_public_keys;
@@ -6,16 +10,45 @@ use serde::Serialize;
 use x509::print_certs;
 
 use crate::{
-    commands::Format,
-    pem::{ParsedPem, Pem},
-    x509::{SimpleCert, SimpleCsr, SimplePrivateKey, SimplePublicKey},
+    clipboard::{self, Artifact},
+    commands::{Format, RenderOptions},
+    crl::SimpleCrl,
+    field::{self, FieldPath},
+    pem::{ParsedPem, Pem, Source},
+    progress,
+    x509::{
+        check_issuer_signatures, ParseIssue, SimpleCert, SimpleCsr, SimplePrivateKey,
+        SimplePublicKey, TrustStore,
+    },
 };
 
+pub mod acme;
+pub mod asn1;
+pub mod caa;
+pub mod capabilities;
+pub mod compare;
 pub mod connection;
+pub mod crl;
 pub mod csr;
-pub mod keys;
+pub mod doctor;
+pub mod error;
+pub mod expiry;
+pub mod explain_expr;
+pub mod find;
+pub mod jwt;
+pub mod keylog;
+pub mod match_hostname;
+pub mod paths;
+pub mod pin;
 pub mod private_key;
 pub mod public_key;
+pub mod same_key;
+pub mod serve;
+pub mod state;
+pub mod stats;
+pub mod trust;
+pub mod verify;
+pub mod watch;
 pub mod x509;
 
 pub(crate) fn round_relative_human(span: Span, relative_to: Zoned) -> Span {
@@ -41,61 +74,466 @@ pub(crate) fn round_relative_human(span: Span, relative_to: Zoned) -> Span {
     span.round(round_config).expect("unable to round span")
 }
 
+/// What [`print_pems`] checks the entities against, besides printing them.
+pub(crate) struct PemOptions<'a> {
+    /// Fail on the first entity with parse issues, see [`check_strict`].
+    pub strict: bool,
+    /// How many threads to convert on.
+    pub jobs: usize,
+    pub trust_store: Option<&'a TrustStore>,
+    /// Certs from outside the input that may have issued the ones in it.
+    pub issuers: &'a [SimpleCert],
+    /// Issuers downloaded with `--aia`, printed after the input's certs.
+    pub fetched: &'a [SimpleCert],
+    /// The name of the input, to record where each entity came from.
+    pub file: Option<&'a str>,
+    /// Keep the PEM of private keys in their model and the JSON output, see
+    /// `parse --no-keep-pem`.
+    pub keep_pem: bool,
+    /// What to put on the clipboard once the entities are printed, see
+    /// `parse --copy`.
+    pub copy: Option<Artifact>,
+    /// Print only these fields of the entities, see `parse --field`.
+    pub fields: &'a [FieldPath],
+}
+
+impl Default for PemOptions<'_> {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            jobs: 1,
+            trust_store: None,
+            issuers: &[],
+            fetched: &[],
+            file: None,
+            keep_pem: true,
+            copy: None,
+            fields: &[],
+        }
+    }
+}
+
 pub(crate) fn print_pems(
     format: Format,
+    options: &RenderOptions,
     pems: impl IntoIterator<Item = Pem>,
+    pem_options: &PemOptions,
 ) -> Result<(), color_eyre::eyre::Error> {
+    let PemOptions {
+        strict,
+        jobs,
+        trust_store,
+        issuers,
+        fetched,
+        file,
+        keep_pem,
+        copy,
+        fields,
+    } = *pem_options;
+
     #[derive(Debug, Default, Serialize)]
     struct ParseResult {
         pub certs: Vec<SimpleCert>,
         pub csrs: Vec<SimpleCsr>,
         pub private_keys: Vec<SimplePrivateKey>,
         pub public_keys: Vec<SimplePublicKey>,
+        /// From PKCS#7 bundles.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub crls: Vec<SimpleCrl>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    enum Entity<'a> {
+        Cert(&'a SimpleCert),
+        Csr(&'a SimpleCsr),
+        PrivateKey(&'a SimplePrivateKey),
+        PublicKey(&'a SimplePublicKey),
+        Crl(&'a SimpleCrl),
+    }
+
+    /// Every entity, tagged with what it is, as they're no longer grouped
+    /// under a key.
+    fn entities(result: &ParseResult) -> Vec<Entity<'_>> {
+        result
+            .certs
+            .iter()
+            .map(Entity::Cert)
+            .chain(result.csrs.iter().map(Entity::Csr))
+            .chain(result.private_keys.iter().map(Entity::PrivateKey))
+            .chain(result.public_keys.iter().map(Entity::PublicKey))
+            .chain(result.crls.iter().map(Entity::Crl))
+            .collect()
     }
 
+    // converting is most of the work for big bundles (fingerprints, PEM
+    // re-encoding, ...), so it's spread over threads. The results are kept in
+    // input order and checked in that order, so `--strict` fails on the same
+    // entity no matter how many jobs there are.
+    let pems: Vec<Pem> = pems.into_iter().collect();
+    let converted = tracing::info_span!("convert", pems = pems.len(), jobs).in_scope(|| {
+        par_map(pems, jobs, |pem| {
+            let source = file.map(|file| pem.source(file));
+            convert_pem(pem).with_source(source)
+        })
+    });
+
     let mut parse_result = ParseResult::default();
-    for pem in pems {
-        tracing::debug!("parsing pem: {:?}", pem);
-
-        match pem.into_parsed_pem() {
-            ParsedPem::Cert(cert) => parse_result.certs.push(SimpleCert::from(cert)),
-            ParsedPem::CertReq(csr) => parse_result.csrs.push(SimpleCsr::from(csr)),
-            ParsedPem::PrivateKey(key) => {
-                parse_result.private_keys.push(SimplePrivateKey::from(key))
+    for converted in converted {
+        match converted {
+            Converted::Cert(mut cert) => {
+                check_strict(strict, "certificate", &cert.issues)?;
+                if let Some(store) = trust_store {
+                    cert.apply_trust_store(store);
+                }
+                parse_result.certs.push(cert);
+            }
+            Converted::Csr(csr) => {
+                check_strict(strict, "csr", &csr.issues)?;
+                parse_result.csrs.push(csr);
+            }
+            Converted::PrivateKey(key) => {
+                if let Some(key) = keep(strict, "private key", key)? {
+                    // the PEM is wiped as it's dropped
+                    let key = if keep_pem { key } else { key.without_pem() };
+                    parse_result.private_keys.push(key);
+                }
             }
-            ParsedPem::RsaPrivateKey(key) => {
-                parse_result.private_keys.push(SimplePrivateKey::from(key))
+            Converted::PublicKey(key) => {
+                if let Some(key) = keep(strict, "public key", key)? {
+                    parse_result.public_keys.push(key);
+                }
             }
-            ParsedPem::PublicKey(key) => parse_result.public_keys.push(SimplePublicKey::from(key)),
-            variant => {
+            Converted::Pkcs7 { certs, crls } => {
+                for mut cert in certs {
+                    check_strict(strict, "certificate", &cert.issues)?;
+                    if let Some(store) = trust_store {
+                        cert.apply_trust_store(store);
+                    }
+                    parse_result.certs.push(cert);
+                }
+                parse_result.crls.extend(crls);
+            }
+            Converted::Crl(crl) => parse_result.crls.push(crl),
+            Converted::Unsupported(variant) => {
                 tracing::warn!("unsupported pem variant: {:?}", variant);
             }
         }
     }
 
+    parse_result.certs.extend(fetched.iter().cloned());
+    check_issuer_signatures(&mut parse_result.certs, issuers);
+    // count from the views' clock, which `parse --at` sets
+    for cert in &mut parse_result.certs {
+        cert.validity.evaluate_at(options.now.timestamp());
+    }
+
+    if options.stable {
+        parse_result
+            .certs
+            .iter_mut()
+            .for_each(SimpleCert::make_stable);
+        parse_result
+            .csrs
+            .iter_mut()
+            .for_each(|csr| csr.subject.sans.sort());
+    }
+
+    if !fields.is_empty() {
+        let entities = entities(&parse_result)
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        for line in field::extract(&entities, fields)? {
+            println!("{line}");
+        }
+        return Ok(());
+    }
+
+    // made before the entities are moved into their views
+    let copied: Option<String> = match copy {
+        Some(Artifact::Fingerprint) if parse_result.certs.is_empty() => {
+            bail!("no cert to copy the fingerprint of")
+        }
+        Some(Artifact::Fingerprint) => Some(
+            parse_result
+                .certs
+                .iter()
+                .map(|cert| format!("{}\n", cert.fingerprints.sha256))
+                .collect(),
+        ),
+        Some(Artifact::Pem) if parse_result.certs.is_empty() => bail!("no cert to copy"),
+        Some(Artifact::Pem) => Some(
+            parse_result
+                .certs
+                .iter()
+                .map(|cert| cert.pem.as_str())
+                .collect(),
+        ),
+        Some(Artifact::Json) => Some(Format::Json.to_json(&parse_result)?),
+        None => None,
+    };
+
     match format {
-        Format::Json => {
-            println!("{}", serde_json::to_string_pretty(&parse_result)?);
+        Format::Json | Format::JsonCompact => {
+            println!("{}", format.to_json(&parse_result)?);
+        }
+        Format::Ndjson => {
+            format.print_json_list(&entities(&parse_result))?;
         }
         Format::Text | Format::Pem => {
             if !parse_result.certs.is_empty() {
-                print_certs(parse_result.certs, format)?;
+                print_certs(parse_result.certs, format, options)?;
             }
 
             if !parse_result.csrs.is_empty() {
-                print_csrs(parse_result.csrs, format)?;
+                print_csrs(parse_result.csrs, format, options)?;
             }
 
             if !parse_result.public_keys.is_empty() {
                 tracing::info!("{:?} public keys", parse_result.public_keys);
-                print_public_keys(parse_result.public_keys, format)?;
+                print_public_keys(parse_result.public_keys, format, options)?;
             }
 
             if !parse_result.private_keys.is_empty() {
-                print_private_keys(parse_result.private_keys, format)?;
+                print_private_keys(parse_result.private_keys, format, options)?;
+            }
+
+            if !parse_result.crls.is_empty() {
+                print_crls(parse_result.crls, format, options)?;
             }
         }
     }
 
+    if let (Some(artifact), Some(copied)) = (copy, copied) {
+        if clipboard::write(&copied)? {
+            progress::status(format!("Copied the {} to the clipboard", artifact.label()));
+        } else {
+            // the output is already printed, so rather than fail, hand over
+            // what would have been copied on stderr
+            print_warning(
+                format!(
+                    "{}, so the {} is printed below instead of copied",
+                    clipboard::no_tool(),
+                    artifact.label()
+                ),
+                options,
+            );
+            eprintln!("{}", copied.trim_end());
+        }
+    }
     Ok(())
 }
+
+/// A PEM converted to the entity it's shown as.
+enum Converted {
+    Cert(SimpleCert),
+    Csr(SimpleCsr),
+    PrivateKey(color_eyre::Result<SimplePrivateKey>),
+    PublicKey(color_eyre::Result<SimplePublicKey>),
+    /// The contents of a PKCS#7 bundle.
+    Pkcs7 {
+        certs: Vec<SimpleCert>,
+        crls: Vec<SimpleCrl>,
+    },
+    Crl(SimpleCrl),
+    Unsupported(ParsedPem),
+}
+
+impl Converted {
+    /// Record where the PEM was found on everything converted from it.
+    fn with_source(mut self, source: Option<Source>) -> Self {
+        if source.is_none() {
+            return self;
+        }
+        match &mut self {
+            Converted::Cert(cert) => cert.source = source,
+            Converted::Csr(csr) => csr.source = source,
+            Converted::PrivateKey(Ok(key)) => key.source = source,
+            Converted::PublicKey(Ok(key)) => key.source = source,
+            Converted::Pkcs7 { certs, crls } => {
+                certs
+                    .iter_mut()
+                    .for_each(|cert| cert.source.clone_from(&source));
+                crls.iter_mut()
+                    .for_each(|crl| crl.source.clone_from(&source));
+            }
+            Converted::Crl(crl) => crl.source = source,
+            Converted::PrivateKey(Err(_))
+            | Converted::PublicKey(Err(_))
+            | Converted::Unsupported(_) => {}
+        }
+        self
+    }
+}
+
+fn convert_pem(pem: Pem) -> Converted {
+    tracing::debug!(?pem, "parsing pem");
+
+    let lints = pem.lints().to_vec();
+    match pem.into_parsed_pem() {
+        ParsedPem::Cert(cert) => {
+            let mut cert = SimpleCert::from(cert);
+            cert.lints.splice(0..0, lints);
+            Converted::Cert(cert)
+        }
+        ParsedPem::CertReq(csr) => Converted::Csr(SimpleCsr::from(csr)),
+        ParsedPem::PrivateKey(key) => Converted::PrivateKey(SimplePrivateKey::try_from(key)),
+        ParsedPem::RsaPrivateKey(key) => Converted::PrivateKey(SimplePrivateKey::try_from(key)),
+        ParsedPem::PublicKey(key) => Converted::PublicKey(SimplePublicKey::try_from(key)),
+        ParsedPem::Pkcs7(bundle) => Converted::Pkcs7 {
+            certs: bundle.certs.into_iter().map(SimpleCert::from).collect(),
+            crls: bundle.crls,
+        },
+        ParsedPem::Crl(crl) => Converted::Crl(crl),
+        variant => Converted::Unsupported(variant),
+    }
+}
+
+/// Map `items` on up to `jobs` threads, returning the results in the same
+/// order as the items.
+pub(crate) fn par_map<T: Send, U: Send>(
+    items: Vec<T>,
+    jobs: usize,
+    f: impl Fn(T) -> U + Sync,
+) -> Vec<U> {
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.into_iter().map(f).collect();
+    }
+
+    // contiguous chunks, one per thread, so putting the results back
+    // together is a concat
+    let chunk_len = items.len().div_ceil(jobs);
+    let mut items = items.into_iter();
+    let chunks: Vec<Vec<T>> = (0..jobs)
+        .map(|_| items.by_ref().take(chunk_len).collect())
+        .collect();
+
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(move || chunk.into_iter().map(f).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("conversion thread panicked"))
+            .collect()
+    })
+}
+
+/// Print `report`, the result of a command that isn't certs: as JSON, or as
+/// the `view` of it otherwise. There's nothing in it to re-encode, so `--pem`
+/// gets the view too.
+pub(crate) fn print_report<T: Serialize>(
+    report: T,
+    format: Format,
+    options: &RenderOptions,
+    view: impl FnOnce(T) -> AnyElement<'static>,
+) -> color_eyre::Result<()> {
+    match format {
+        Format::Text | Format::Pem => options.print(element! {
+            View(margin: 1) {
+                #(view(report))
+            }
+        }),
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            println!("{}", format.to_json(&report)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// [`print_report`] for a report that's a list, which is one line per item
+/// with `--ndjson`.
+pub(crate) fn print_report_list<T: Serialize>(
+    items: Vec<T>,
+    format: Format,
+    options: &RenderOptions,
+    view: impl FnOnce(Vec<T>) -> AnyElement<'static>,
+) -> color_eyre::Result<()> {
+    match format {
+        Format::Text | Format::Pem => options.print(element! {
+            View(margin: 1) {
+                #(view(items))
+            }
+        }),
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&items)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// In `--strict` mode, turn the [`ParseIssue`]s recorded for an entity into a
+/// hard error.
+pub(crate) fn check_strict(
+    strict: bool,
+    what: &str,
+    issues: &[ParseIssue],
+) -> color_eyre::Result<()> {
+    if strict && !issues.is_empty() {
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        bail!("{what} has parse issues: {}", issues.join("; "));
+    }
+
+    Ok(())
+}
+
+/// Keep the converted entity, skipping it with a warning if the conversion
+/// failed (or failing outright in `--strict` mode).
+fn keep<T>(
+    strict: bool,
+    what: &str,
+    result: color_eyre::Result<T>,
+) -> color_eyre::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if strict => Err(err.wrap_err(format!("Parsing {what}"))),
+        Err(err) => {
+            tracing::warn!("skipping {what}: {err:#}");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pem::parse_pems;
+
+    #[test]
+    fn par_map_keeps_order() {
+        let items: Vec<u32> = (0..103).collect();
+        let expected: Vec<u32> = items.iter().map(|i| i * 2).collect();
+        for jobs in [0, 1, 2, 7, 103, 500] {
+            assert_eq!(
+                par_map(items.clone(), jobs, |i| i * 2),
+                expected,
+                "{jobs} jobs"
+            );
+        }
+        assert!(par_map(Vec::<u32>::new(), 4, |i| i).is_empty());
+    }
+
+    #[test]
+    fn strict_fails_on_parse_issues() {
+        let pems =
+            || parse_pems(include_bytes!("../../test-data/certs/malformed-san.pem")).flatten();
+        let cert = match pems().next().unwrap().into_parsed_pem() {
+            ParsedPem::Cert(cert) => SimpleCert::from(cert),
+            pem => panic!("expected a cert, got {pem:?}"),
+        };
+
+        assert!(check_strict(false, "certificate", &cert.issues).is_ok());
+        assert!(check_strict(true, "certificate", &cert.issues).is_err());
+        let strict = PemOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(print_pems(Format::Json, &RenderOptions::default(), pems(), &strict).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props, Weight,
+};
+use jiff::Timestamp;
+
+use crate::{
+    chain::{PathEnd, PathNode},
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+};
+
+/// One line of the tree: how it's indented and the cert on it.
+struct Line<'a> {
+    prefix: String,
+    node: &'a PathNode,
+}
+
+/// The tree as lines, each issuer under the cert it issued, with `├─` and
+/// `└─` connecting them.
+fn lines(node: &PathNode) -> Vec<Line<'_>> {
+    fn walk<'a>(node: &'a PathNode, indent: &str, out: &mut Vec<Line<'a>>) {
+        for (i, issuer) in node.issuers.iter().enumerate() {
+            let last = i + 1 == node.issuers.len();
+            out.push(Line {
+                prefix: format!("{indent}{}", if last { "└─ " } else { "├─ " }),
+                node: issuer,
+            });
+            walk(
+                issuer,
+                &format!("{indent}{}", if last { "   " } else { "│  " }),
+                out,
+            );
+        }
+    }
+
+    let mut out = vec![Line {
+        prefix: String::new(),
+        node,
+    }];
+    walk(node, "", &mut out);
+    out
+}
+
+fn date(time: Timestamp) -> String {
+    time.strftime("%Y-%m-%d").to_string()
+}
+
+/// How many paths end somewhere in the tree.
+fn count(node: &PathNode) -> usize {
+    node.end.is_some() as usize + node.issuers.iter().map(count).sum::<usize>()
+}
+
+#[derive(Default, Props)]
+pub struct PathsProps {
+    pub tree: Option<PathNode>,
+    pub options: RenderOptions,
+}
+
+#[component]
+pub fn PathsView(mut hooks: Hooks, props: &PathsProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let Some(tree) = &props.tree else {
+        return element! { View() };
+    };
+    let now = props.options.now.timestamp();
+    let paths = count(tree);
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "paths:", color: theme.top_level)
+                Text(content: match paths {
+                    1 => "1 path".to_string(),
+                    n => format!("{n} paths, clients build whichever ends at a root they trust"),
+                }, weight: Weight::Light)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(lines(tree).into_iter().map(|Line { prefix, node }| {
+                    let expired = node.not_after.is_some_and(|not_after| not_after <= now);
+                    let expires = match node.not_after {
+                        Some(not_after) if expired => format!("expired {}", date(not_after)),
+                        Some(not_after) => format!("expires {}", date(not_after)),
+                        None => "no expiry".to_string(),
+                    };
+                    let end = node.end.map(|end| {
+                        let (text, color) = match (end, node.path_expires) {
+                            (PathEnd::Root, _) if !node.trusted => ("→ root not in the bundled store".to_string(), theme.warning),
+                            (PathEnd::IssuerNotFound, _) => ("→ issuer not found".to_string(), theme.warning),
+                            (PathEnd::Loop, _) => ("→ loops back, stopped".to_string(), theme.bad),
+                            (PathEnd::TooDeep, _) => ("→ deeper than --max-depth, stopped".to_string(), theme.bad),
+                            (PathEnd::Root, Some(dies)) if dies <= now => (format!("→ path dead since {}", date(dies)), theme.bad),
+                            (PathEnd::Root, Some(dies)) => (format!("→ path valid until {}", date(dies)), theme.good),
+                            (PathEnd::Root, None) => ("→ path never expires".to_string(), theme.good),
+                        };
+                        element! {
+                            Text(content: text, color)
+                        }
+                    });
+
+                    element! {
+                        View(gap: 1) {
+                            Text(content: format!("{prefix}{}", node.subject), color: theme.highlight)
+                            Text(content: expires, color: if expired { theme.bad } else { theme.accent })
+                            #(node.trusted.then(|| element! {
+                                Text(content: "[trusted root]", color: theme.good)
+                            }))
+                            #(end)
+                        }
+                    }
+                }))
+            }
+        }
+    }
+}
+
+pub fn print_paths(
+    tree: Option<PathNode>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(tree, format, options, |tree| {
+        element! { PathsView(tree, options: options.clone()) }.into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(subject: &str, issuers: Vec<PathNode>, end: Option<PathEnd>) -> PathNode {
+        PathNode {
+            subject: subject.to_string(),
+            sha256: subject.to_string(),
+            not_after: None,
+            trusted: false,
+            issuers,
+            end,
+            path_expires: None,
+        }
+    }
+
+    #[test]
+    fn tree_lines() {
+        let root = |name| node(name, Vec::new(), Some(PathEnd::Root));
+        let tree = node(
+            "leaf",
+            vec![
+                node("R3", vec![root("ISRG Root X1")], None),
+                node("R3 cross-signed", vec![root("DST Root CA X3")], None),
+            ],
+            None,
+        );
+        let lines: Vec<String> = lines(&tree)
+            .iter()
+            .map(|line| format!("{}{}", line.prefix, line.node.subject))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "leaf",
+                "├─ R3",
+                "│  └─ ISRG Root X1",
+                "└─ R3 cross-signed",
+                "   └─ DST Root CA X3",
+            ]
+        );
+        assert_eq!(count(&tree), 2);
+    }
+}
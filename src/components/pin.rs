@@ -0,0 +1,120 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::{print_report, print_report_list, same_key::KeyGroup},
+    theme::UseTheme,
+};
+
+#[derive(Default, Props)]
+pub struct PinsProps {
+    pub keys: Vec<KeyGroup>,
+}
+
+#[component]
+pub fn PinsView(mut hooks: Hooks, props: &PinsProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            #(props.keys.iter().map(|group| element! {
+                View(flex_direction: FlexDirection::Column) {
+                    Text(content: format!("sha256/{}", group.spki.pin_sha256), color: theme.top_level)
+                    #(group.used_by.iter().map(|user| element! {
+                        View(margin_left: 4, gap: 1) {
+                            Text(content: format!("{}:", user.file), color: theme.heading)
+                            Text(content: user.kind.label(), color: theme.highlight)
+                            Text(content: user.name.clone().unwrap_or_default())
+                        }
+                    }))
+                }
+            }))
+        }
+    }
+}
+
+pub fn print_pins(
+    keys: Vec<KeyGroup>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report_list(keys, format, options, |keys| {
+        element! { PinsView(keys) }.into()
+    })
+}
+
+/// One cert a server sent, checked against the pins.
+#[derive(Debug, Clone, Serialize)]
+pub struct PinnedCert {
+    pub subject: String,
+    pub pin_sha256: String,
+    /// One of the pins is this cert's.
+    pub pinned: bool,
+}
+
+/// Whether a server's chain satisfies a pin set, from `pin check`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PinCheckReport {
+    /// The host and port, e.g. `example.com:443`.
+    pub target: String,
+    /// Any cert of the chain matching a pin is enough, as with every
+    /// pinning implementation.
+    pub matched: bool,
+    /// The pins checked, as base64.
+    pub pins: Vec<String>,
+    /// In the order the server sent them.
+    pub certs: Vec<PinnedCert>,
+}
+
+#[derive(Default, Props)]
+pub struct PinCheckProps {
+    pub report: PinCheckReport,
+}
+
+#[component]
+pub fn PinCheckView(mut hooks: Hooks, props: &PinCheckProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let report = &props.report;
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "pins:", color: theme.top_level)
+                Text(content: report.target.clone(), color: theme.highlight)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(if report.matched {
+                    element! {
+                        Text(content: "✅ the chain matches a pin", color: theme.good)
+                    }
+                } else {
+                    element! {
+                        Text(content: "🚨 no cert in the chain matches a pin", color: theme.bad, decoration: TextDecoration::Underline)
+                    }
+                })
+                #(report.certs.iter().map(|cert| element! {
+                    View(gap: 1) {
+                        Text(content: if cert.pinned { "✅" } else { "➖" })
+                        Text(content: format!("sha256/{}", cert.pin_sha256), color: if cert.pinned { theme.good } else { theme.highlight })
+                        Text(content: cert.subject.clone())
+                    }
+                }))
+            }
+        }
+    }
+}
+
+pub fn print_pin_check(
+    report: PinCheckReport,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(report, format, options, |report| {
+        element! { PinCheckView(report) }.into()
+    })
+}
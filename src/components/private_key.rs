@@ -1,23 +1,27 @@
 use iocraft::{
     component, element,
     prelude::{Text, View},
-    AnyElement, Color, ElementExt, FlexDirection, Props,
+    AnyElement, FlexDirection, Hooks, Props,
 };
 
 use crate::{
-    commands::Format,
-    theme::{HIGHLIGHT_COLOR, TOP_LEVEL_COLOR},
+    commands::{Format, RenderOptions},
+    components::{error::print_warning, x509::located},
+    output,
+    theme::UseTheme,
     x509::{SimplePrivateKey, SimplePrivateKeyKind},
 };
 
 #[derive(Default, Props)]
 pub struct PrivateKeyProps {
     priv_key: SimplePrivateKey,
+    pub(crate) options: RenderOptions,
 }
 
 #[component]
-pub fn PrivateKeyView(props: &PrivateKeyProps) -> impl Into<AnyElement<'static>> {
-    match &props.priv_key.kind {
+pub fn PrivateKeyView(mut hooks: Hooks, props: &PrivateKeyProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let details = match &props.priv_key.kind {
         SimplePrivateKeyKind::RSA {
             size,
             modulus,
@@ -29,32 +33,32 @@ pub fn PrivateKeyView(props: &PrivateKeyProps) -> impl Into<AnyElement<'static>>
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "RSA Private Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: "RSA Private Key", color: theme.highlight)
                     }
                     View() {
-                        Text(content: "size: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "size: ", color: theme.top_level)
                         Text(content: format!("{}", size), )
                     }
                     View() {
-                        Text(content: "exponent: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "exponent: ", color: theme.top_level)
                         Text(content: format!("{}", exponent), )
                     }
                     View() {
-                        Text(content: "modulus: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", modulus), )
+                        Text(content: "modulus: ", color: theme.top_level)
+                        Text(content: props.options.hex(modulus))
                     }
                     View() {
-                        Text(content: "p: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", p), )
+                        Text(content: "p: ", color: theme.top_level)
+                        Text(content: props.options.hex(p))
                     }
                     View() {
-                        Text(content: "q: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", q), )
+                        Text(content: "q: ", color: theme.top_level)
+                        Text(content: props.options.hex(q))
                     }
                     View() {
-                        Text(content: "key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", key), )
+                        Text(content: "key: ", color: theme.top_level)
+                        Text(content: props.options.hex(key))
                     }
                 }
             }
@@ -70,32 +74,32 @@ pub fn PrivateKeyView(props: &PrivateKeyProps) -> impl Into<AnyElement<'static>>
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "DSA Private Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: "DSA Private Key", color: theme.highlight)
                     }
                     View() {
-                        Text(content: "size: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "size: ", color: theme.top_level)
                         Text(content: format!("{}", size), )
                     }
                     View() {
-                        Text(content: "p: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", p), )
+                        Text(content: "p: ", color: theme.top_level)
+                        Text(content: props.options.hex(p))
                     }
                     View() {
-                        Text(content: "q: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", q), )
+                        Text(content: "q: ", color: theme.top_level)
+                        Text(content: props.options.hex(q))
                     }
                     View() {
-                        Text(content: "g: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", g), )
+                        Text(content: "g: ", color: theme.top_level)
+                        Text(content: props.options.hex(g))
                     }
                     View() {
-                        Text(content: "pub_key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", pub_key), )
+                        Text(content: "pub_key: ", color: theme.top_level)
+                        Text(content: props.options.hex(pub_key))
                     }
                     View() {
-                        Text(content: "key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", key), )
+                        Text(content: "key: ", color: theme.top_level)
+                        Text(content: props.options.hex(key))
                     }
                 }
             }
@@ -110,113 +114,121 @@ pub fn PrivateKeyView(props: &PrivateKeyProps) -> impl Into<AnyElement<'static>>
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "EC Private Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: "EC Private Key", color: theme.highlight)
                     }
                     View() {
-                        Text(content: "group: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "group: ", color: theme.top_level)
                         Text(content: format!("{}", group), )
                     }
                     View() {
-                        Text(content: "pub_key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", pub_key), )
+                        Text(content: "pub_key: ", color: theme.top_level)
+                        Text(content: props.options.hex(pub_key))
                     }
                     View() {
-                        Text(content: "key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", key), )
+                        Text(content: "key: ", color: theme.top_level)
+                        Text(content: props.options.hex(key))
                     }
                 }
             }
         }
-        SimplePrivateKeyKind::Ed25519 { pub_key, key } => {
+        SimplePrivateKeyKind::Ed25519 { pub_key, key }
+        | SimplePrivateKeyKind::Ed448 { pub_key, key }
+        | SimplePrivateKeyKind::X25519 { pub_key, key }
+        | SimplePrivateKeyKind::X448 { pub_key, key } => {
+            let name = match &props.priv_key.kind {
+                SimplePrivateKeyKind::Ed25519 { .. } => "Ed25519",
+                SimplePrivateKeyKind::Ed448 { .. } => "Ed448",
+                SimplePrivateKeyKind::X25519 { .. } => "X25519",
+                _ => "X448",
+            };
+
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "Ed25519 Private Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: format!("{name} Private Key"), color: theme.highlight)
                     }
                     View() {
-                        Text(content: "pub_key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", pub_key), )
+                        Text(content: "pub_key: ", color: theme.top_level)
+                        Text(content: props.options.hex(pub_key))
                     }
                     View() {
-                        Text(content: "key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", key), )
+                        Text(content: "key: ", color: theme.top_level)
+                        Text(content: props.options.hex(key))
                     }
                 }
             }
         }
-        SimplePrivateKeyKind::Ed448 { pub_key, key } => {
-            element! {
-                View(flex_direction: FlexDirection::Column) {
-                    View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "Ed448 Private Key", color: HIGHLIGHT_COLOR)
-                    }
-                    View() {
-                        Text(content: "pub_key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", pub_key), )
-                    }
-                    View() {
-                        Text(content: "key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", key), )
-                    }
-                }
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(located(&props.options, props.priv_key.source.as_ref()))
+            #(details)
+            View() {
+                Text(content: "spki sha256: ", color: theme.top_level)
+                Text(content: props.priv_key.spki.spki_sha256.clone())
+            }
+            View() {
+                Text(content: "pin-sha256: ", color: theme.top_level)
+                Text(content: props.priv_key.spki.pin_sha256.clone())
             }
         }
     }
 }
 
-#[derive(Default, Props)]
-pub struct MultiplePrivateKeyViewProps {
-    pub priv_keys: Vec<SimplePrivateKey>,
-}
-
-#[component]
-pub fn MultiplePrivateKeyView(
-    props: &MultiplePrivateKeyViewProps,
-) -> impl Into<AnyElement<'static>> {
-    let keys = props
-        .priv_keys
-        .iter()
-        .cloned()
-        .enumerate()
-        .map(|(i, priv_key)| {
-            element! {
-                View(flex_direction: FlexDirection::Column) {
-                        Text(content: format!("private key #{}:", i + 1), color: Color::Magenta)
-                    PrivateKeyView(priv_key)
-                }
+/// The keys, numbered. They're moved into their views rather than cloned, so
+/// the secrets aren't copied.
+fn private_keys_view(
+    priv_keys: Vec<SimplePrivateKey>,
+    options: &RenderOptions,
+) -> AnyElement<'static> {
+    let keys = priv_keys.into_iter().enumerate().map(|(i, priv_key)| {
+        element! {
+            View(flex_direction: FlexDirection::Column) {
+                Text(content: format!("private key #{}:", i + 1), color: options.theme.heading)
+                PrivateKeyView(priv_key, options: options.clone())
             }
-        });
+        }
+    });
 
     element! {
         View(flex_direction: FlexDirection::Column, gap: 1) {
             #(keys)
         }
     }
+    .into()
 }
 
 pub fn print_private_keys(
     priv_keys: Vec<SimplePrivateKey>,
     format: Format,
+    options: &RenderOptions,
 ) -> color_eyre::Result<()> {
     tracing::info!("printing {} keys in {format:?} format", priv_keys.len());
+    output::mark_private();
     match format {
         Format::Text => {
-            element! {
+            options.print(element! {
                 View(margin: 1) {
-                    MultiplePrivateKeyView(priv_keys)
+                    #(private_keys_view(priv_keys, options))
                 }
-            }
-            .print();
+            });
         }
-        Format::Json => {
-            println!("{}", serde_json::to_string_pretty(&priv_keys)?);
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&priv_keys)?;
         }
         Format::Pem => {
+            if !options.insecure_output && !priv_keys.is_empty() {
+                print_warning(
+                    "writing unencrypted private keys, protect them with `pls convert --encrypt` (or pass --insecure-output)",
+                    options,
+                );
+            }
             for priv_key in priv_keys {
-                print!("{}", priv_key.pem);
+                let header = || format!("private key, spki sha256 {}", priv_key.spki.spki_sha256);
+                options.print_pem(header, &priv_key.to_pem()?);
             }
         }
     }
@@ -1,25 +1,31 @@
 use iocraft::{
     component, element,
     prelude::{Text, View},
-    AnyElement, Color, ElementExt, FlexDirection, Props,
+    AnyElement, FlexDirection, Hooks, Props,
 };
 
 use crate::{
-    commands::Format,
-    theme::{HIGHLIGHT_COLOR, TOP_LEVEL_COLOR},
+    commands::{Format, RenderOptions},
+    components::x509::located,
+    theme::UseTheme,
     x509::{SimplePublicKey, SimplePublicKeyKind},
 };
 
 #[derive(Default, Props)]
 pub struct PublicKeyProps {
-    pub_key: SimplePublicKey,
+    pub(crate) pub_key: SimplePublicKey,
+    /// Rendered as part of a cert, whose fingerprints already show the SPKI
+    /// hash.
+    pub(crate) in_cert: bool,
+    pub(crate) options: RenderOptions,
 }
 
 #[component]
-pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
+pub fn PublicKeyView(mut hooks: Hooks, props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     tracing::info!("public key: {:?}", props.pub_key);
 
-    match &props.pub_key.kind {
+    let details = match &props.pub_key.kind {
         SimplePublicKeyKind::RSA {
             size,
             modulus,
@@ -28,20 +34,53 @@ pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "RSA Public Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: "RSA Public Key", color: theme.highlight)
                     }
                     View() {
-                        Text(content: "size: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "size: ", color: theme.top_level)
                         Text(content: format!("{} bits", size), )
                     }
                     View() {
-                        Text(content: "exponent: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "exponent: ", color: theme.top_level)
                         Text(content: format!("{}", exponent), )
                     }
                     View() {
-                        Text(content: "modulus: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", modulus), )
+                        Text(content: "modulus: ", color: theme.top_level)
+                        Text(content: props.options.hex(modulus))
+                    }
+                }
+            }
+        }
+        SimplePublicKeyKind::RsaPss {
+            size,
+            modulus,
+            exponent,
+            parameters,
+        } => {
+            element! {
+                View(flex_direction: FlexDirection::Column) {
+                    View() {
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: "RSA-PSS Public Key", color: theme.highlight)
+                    }
+                    View() {
+                        Text(content: "size: ", color: theme.top_level)
+                        Text(content: format!("{} bits", size), )
+                    }
+                    #(parameters.as_ref().map(|parameters| element! {
+                        View() {
+                            Text(content: "restricted to: ", color: theme.top_level)
+                            Text(content: parameters.to_string())
+                        }
+                    }))
+                    View() {
+                        Text(content: "exponent: ", color: theme.top_level)
+                        Text(content: format!("{}", exponent), )
+                    }
+                    View() {
+                        Text(content: "modulus: ", color: theme.top_level)
+                        Text(content: props.options.hex(modulus))
                     }
                 }
             }
@@ -50,28 +89,28 @@ pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "DSA Public Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: "DSA Public Key", color: theme.highlight)
                     }
                     View() {
-                        Text(content: "size: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "size: ", color: theme.top_level)
                         Text(content: format!("{} bits", size), )
                     }
                     View() {
-                        Text(content: "p: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", p), )
+                        Text(content: "p: ", color: theme.top_level)
+                        Text(content: props.options.hex(p))
                     }
                     View() {
-                        Text(content: "q: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", q), )
+                        Text(content: "q: ", color: theme.top_level)
+                        Text(content: props.options.hex(q))
                     }
                     View() {
-                        Text(content: "g: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", g), )
+                        Text(content: "g: ", color: theme.top_level)
+                        Text(content: props.options.hex(g))
                     }
                     View() {
-                        Text(content: "key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", key), )
+                        Text(content: "key: ", color: theme.top_level)
+                        Text(content: props.options.hex(key))
                     }
                 }
             }
@@ -82,58 +121,114 @@ pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "EC Public Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: "EC Public Key", color: theme.highlight)
                     }
                     View() {
-                        Text(content: "group: ", color: TOP_LEVEL_COLOR)
+                        Text(content: "group: ", color: theme.top_level)
                         Text(content: format!("{}", group), )
                     }
                     View() {
-                        Text(content: "pub_key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", key), )
+                        Text(content: "pub_key: ", color: theme.top_level)
+                        Text(content: props.options.hex(key))
                     }
                 }
             }
         }
-        SimplePublicKeyKind::Ed25519 { pub_key } => {
+        SimplePublicKeyKind::Ed25519 { pub_key }
+        | SimplePublicKeyKind::Ed448 { pub_key }
+        | SimplePublicKeyKind::X25519 { pub_key }
+        | SimplePublicKeyKind::X448 { pub_key } => {
+            let name = match &props.pub_key.kind {
+                SimplePublicKeyKind::Ed25519 { .. } => "Ed25519",
+                SimplePublicKeyKind::Ed448 { .. } => "Ed448",
+                SimplePublicKeyKind::X25519 { .. } => "X25519",
+                _ => "X448",
+            };
+
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "Ed25519 Public Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: format!("{name} Public Key"), color: theme.highlight)
                     }
                     View() {
-                        Text(content: "pub_key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", pub_key), )
+                        Text(content: "pub_key: ", color: theme.top_level)
+                        Text(content: props.options.hex(pub_key))
                     }
                 }
             }
         }
-        SimplePublicKeyKind::Ed448 { pub_key } => {
+        // boring can't parse these, so there's only what the SPKI says
+        SimplePublicKeyKind::Other {
+            algorithm,
+            oid,
+            parameters,
+            key,
+        } => {
             element! {
                 View(flex_direction: FlexDirection::Column) {
                     View() {
-                        Text(content: "type: ", color: TOP_LEVEL_COLOR)
-                        Text(content: "Ed448 Public Key", color: HIGHLIGHT_COLOR)
+                        Text(content: "type: ", color: theme.top_level)
+                        Text(content: format!("{algorithm} Public Key"), color: theme.highlight)
                     }
                     View() {
-                        Text(content: "pub_key: ", color: TOP_LEVEL_COLOR)
-                        Text(content: format!("{}", pub_key), )
+                        Text(content: "oid: ", color: theme.top_level)
+                        Text(content: oid.clone())
+                    }
+                    #(parameters.clone().map(|parameters| element! {
+                        View() {
+                            Text(content: "parameters: ", color: theme.top_level)
+                            Text(content: parameters)
+                        }
+                    }))
+                    #((props.pub_key.bits > 0).then(|| element! {
+                        View() {
+                            Text(content: "size: ", color: theme.top_level)
+                            Text(content: format!("{} bits", props.pub_key.bits))
+                        }
+                    }))
+                    View() {
+                        Text(content: "key: ", color: theme.top_level)
+                        Text(content: props.options.hex(key))
                     }
                 }
             }
         }
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(located(&props.options, props.pub_key.source.as_ref()))
+            #(details)
+            #((!props.in_cert).then(|| element! {
+                View(flex_direction: FlexDirection::Column) {
+                    View() {
+                        Text(content: "spki sha256: ", color: theme.top_level)
+                        Text(content: props.pub_key.spki.spki_sha256.clone())
+                    }
+                    View() {
+                        Text(content: "pin-sha256: ", color: theme.top_level)
+                        Text(content: props.pub_key.spki.pin_sha256.clone())
+                    }
+                }
+            }))
+        }
     }
 }
 
 #[derive(Default, Props)]
 pub struct MultiplePublicKeyViewProps {
     pub pub_keys: Vec<SimplePublicKey>,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn MultiplePublicKeyView(props: &MultiplePublicKeyViewProps) -> impl Into<AnyElement<'static>> {
+pub fn MultiplePublicKeyView(
+    mut hooks: Hooks,
+    props: &MultiplePublicKeyViewProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     tracing::info!("public keys: {:?}", props.pub_keys);
 
     element!(
@@ -141,35 +236,39 @@ pub fn MultiplePublicKeyView(props: &MultiplePublicKeyViewProps) -> impl Into<An
             #(props.pub_keys.iter().cloned().enumerate().map(|(i, pub_key)| element! {
                 View(flex_direction: FlexDirection::Column) {
                     #((props.pub_keys.len() > 1).then(|| element! {
-                        Text(content: format!("public key #{}:", i + 1), color: Color::Magenta)
+                        Text(content: format!("public key #{}:", i + 1), color: theme.heading)
                     }))
-                    PublicKeyView(pub_key)
+                    PublicKeyView(pub_key, options: props.options.clone())
                 }
             }))
         }
     )
 }
 
-pub fn print_public_keys(pub_keys: Vec<SimplePublicKey>, format: Format) -> color_eyre::Result<()> {
+pub fn print_public_keys(
+    pub_keys: Vec<SimplePublicKey>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
     tracing::info!(
         "printing {} public keys in {format:?} format",
         pub_keys.len()
     );
     match format {
         Format::Text => {
-            element! {
+            options.print(element! {
                 View(margin: 1) {
-                    MultiplePublicKeyView(pub_keys)
+                    MultiplePublicKeyView(pub_keys, options: options.clone())
                 }
-            }
-            .print();
+            });
         }
-        Format::Json => {
-            println!("{}", serde_json::to_string_pretty(&pub_keys)?);
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&pub_keys)?;
         }
         Format::Pem => {
             for pub_key in pub_keys {
-                print!("{}", pub_key.pem);
+                let header = || format!("public key, spki sha256 {}", pub_key.spki.spki_sha256);
+                options.print_pem(header, &pub_key.pem);
             }
         }
     }
@@ -0,0 +1,130 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, TextDecoration, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+    x509::SpkiHash,
+};
+
+/// What kind of entity uses a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyUserKind {
+    Cert,
+    Csr,
+    PrivateKey,
+    PublicKey,
+}
+
+impl KeyUserKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Cert => "cert",
+            Self::Csr => "csr",
+            Self::PrivateKey => "private key",
+            Self::PublicKey => "public key",
+        }
+    }
+}
+
+/// A cert, CSR or key found in one of the inputs.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyUser {
+    /// The file it was read from.
+    pub file: String,
+    pub kind: KeyUserKind,
+    /// The subject of a cert or CSR.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Everything in the inputs that uses one key.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyGroup {
+    #[serde(flatten)]
+    pub spki: SpkiHash,
+    pub used_by: Vec<KeyUser>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SameKeyReport {
+    pub same_key: bool,
+    /// In the order each key was first seen.
+    pub keys: Vec<KeyGroup>,
+}
+
+impl SameKeyReport {
+    pub fn new(users: impl IntoIterator<Item = (SpkiHash, KeyUser)>) -> Self {
+        let mut keys: Vec<KeyGroup> = Vec::new();
+        for (spki, user) in users {
+            match keys.iter_mut().find(|group| group.spki == spki) {
+                Some(group) => group.used_by.push(user),
+                None => keys.push(KeyGroup {
+                    spki,
+                    used_by: vec![user],
+                }),
+            }
+        }
+
+        Self {
+            same_key: keys.len() == 1,
+            keys,
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct SameKeyProps {
+    pub keys: Vec<KeyGroup>,
+}
+
+#[component]
+pub fn SameKeyView(mut hooks: Hooks, props: &SameKeyProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let verdict = if props.keys.len() == 1 {
+        element! {
+            Text(content: "✅ same key", color: theme.good)
+        }
+    } else {
+        element! {
+            Text(content: format!("🚨 {} different keys", props.keys.len()), color: theme.bad, decoration: TextDecoration::Underline)
+        }
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            #(verdict)
+            #(props.keys.iter().map(|group| element! {
+                View(flex_direction: FlexDirection::Column) {
+                    View() {
+                        Text(content: "spki sha256: ", color: theme.top_level)
+                        Text(content: group.spki.spki_sha256.clone())
+                    }
+                    #(group.used_by.iter().map(|user| element! {
+                        View(margin_left: 4, gap: 1) {
+                            Text(content: format!("{}:", user.file), color: theme.heading)
+                            Text(content: user.kind.label(), color: theme.highlight)
+                            Text(content: user.name.clone().unwrap_or_default())
+                        }
+                    }))
+                }
+            }))
+        }
+    }
+}
+
+pub fn print_same_key(
+    report: SameKeyReport,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(report, format, options, |report| {
+        element! { SameKeyView(keys: report.keys) }.into()
+    })
+}
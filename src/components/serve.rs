@@ -0,0 +1,226 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    server::{ClientAuth, Scenario},
+    theme::UseTheme,
+};
+
+/// Where `serve` listens and what it presents.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServerInfo {
+    /// The address it listens on, e.g. `127.0.0.1:8443`.
+    pub address: String,
+    /// The leaf's subject.
+    pub subject: String,
+    /// The leaf's DNS and IP SANs.
+    pub names: Vec<String>,
+    pub sha256: String,
+    pub alpn: Vec<String>,
+    pub client_auth: ClientAuth,
+    /// How the server misbehaves, with `--scenario`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<Scenario>,
+    /// The root of a generated chain, as PEM, for clients to trust.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// Where the root was written, with `--write-root`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_file: Option<PathBuf>,
+}
+
+/// One handshake with a client, as `serve` saw it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Handshake {
+    /// The client's address.
+    pub peer: String,
+    pub version: Option<String>,
+    pub cipher: Option<String>,
+    pub alpn: Option<String>,
+    /// The server name the client asked for.
+    pub sni: Option<String>,
+    /// Whether the client resumed an earlier session.
+    pub resumed: bool,
+    /// The subject of the client's cert, with `--client-auth`.
+    pub client_cert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Handshake {
+    /// A handshake that didn't complete.
+    pub fn failed(peer: String, error: String) -> Self {
+        Handshake {
+            peer,
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+}
+
+/// The summary sent back to HTTP clients.
+impl fmt::Display for Handshake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_none = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".into());
+        writeln!(f, "hello from pls serve, {}", self.peer)?;
+        writeln!(f, "version: {}", or_none(&self.version))?;
+        writeln!(f, "cipher: {}", or_none(&self.cipher))?;
+        writeln!(f, "alpn: {}", or_none(&self.alpn))?;
+        writeln!(f, "sni: {}", or_none(&self.sni))?;
+        writeln!(f, "resumed: {}", self.resumed)?;
+        writeln!(f, "client cert: {}", or_none(&self.client_cert))
+    }
+}
+
+#[derive(Default, Props)]
+pub struct ServerInfoProps {
+    pub info: ServerInfo,
+}
+
+#[component]
+pub fn ServerInfoView(mut hooks: Hooks, props: &ServerInfoProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let info = &props.info;
+
+    let field = |name: &str, value: String| {
+        element! {
+            View(gap: 1) {
+                Text(content: format!("{name}:"), color: theme.heading)
+                Text(content: value)
+            }
+        }
+    };
+    let alpn = match info.alpn.is_empty() {
+        true => "none".to_string(),
+        false => info.alpn.join(", "),
+    };
+    let client_auth = match info.client_auth {
+        ClientAuth::None => "not asked for",
+        ClientAuth::Request => "requested",
+        ClientAuth::Require => "required",
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "listening on:", color: theme.top_level)
+                Text(content: info.address.clone(), color: theme.highlight)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(field("subject", info.subject.clone()))
+                #(field("names", info.names.join(", ")))
+                #(field("sha256", info.sha256.clone()))
+                #(field("alpn", alpn))
+                #(field("client certs", client_auth.to_string()))
+                #(info.root_file.as_ref().map(|file| field("root", file.display().to_string())))
+                #(info.scenario.map(|scenario| element! {
+                    View(gap: 1) {
+                        Text(content: "scenario:", color: theme.heading)
+                        Text(content: format!("clients should fail: {}", scenario.expected()), color: theme.warning)
+                    }
+                }))
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct HandshakeProps {
+    pub handshake: Handshake,
+}
+
+/// One line per handshake: `127.0.0.1:50312  TLSv1.3  alpn=h2  sni=localhost`.
+#[component]
+pub fn HandshakeView(mut hooks: Hooks, props: &HandshakeProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let handshake = &props.handshake;
+
+    let mut details = Vec::new();
+    if let Some(version) = &handshake.version {
+        details.push(version.clone());
+    }
+    if let Some(cipher) = &handshake.cipher {
+        details.push(cipher.clone());
+    }
+    if let Some(alpn) = &handshake.alpn {
+        details.push(format!("alpn={alpn}"));
+    }
+    if let Some(sni) = &handshake.sni {
+        details.push(format!("sni={sni}"));
+    }
+    if handshake.resumed {
+        details.push("resumed".to_string());
+    }
+    if let Some(client_cert) = &handshake.client_cert {
+        details.push(format!("client cert={client_cert}"));
+    }
+
+    element! {
+        View(gap: 2) {
+            Text(content: handshake.peer.clone(), color: theme.accent)
+            #(match &handshake.error {
+                Some(error) => element! {
+                    Text(content: format!("failed: {error}"), color: theme.bad)
+                },
+                None => element! {
+                    Text(content: details.join("  "), color: theme.good)
+                },
+            })
+        }
+    }
+}
+
+pub fn print_server_info(
+    info: ServerInfo,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    match format {
+        Format::Text => {
+            options.print(element! {
+                View(margin: 1) {
+                    ServerInfoView(info)
+                }
+            });
+        }
+        Format::Pem => match &info.root {
+            Some(root) => options.print_pem(|| "pls serve root".to_string(), root),
+            None => tracing::warn!("there's no generated root to print"),
+        },
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            println!("{}", format.to_json(&info)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a handshake as it happens.
+pub fn print_handshake(
+    handshake: Handshake,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    match format {
+        Format::Text => {
+            options.print(element! {
+                HandshakeView(handshake)
+            });
+        }
+        // stdout only has the root
+        Format::Pem => {}
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            println!("{}", format.to_json(&handshake)?);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use jiff::Timestamp;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::theme::UseTheme;
+
+/// A field of a host's cert that changed since the last run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// How a host's cert differs from the one recorded in the `--state` file.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct CertChanges {
+    /// When the previous cert was first seen.
+    #[schemars(with = "String")]
+    pub since: Timestamp,
+    pub fields: Vec<FieldChange>,
+    pub added_sans: Vec<String>,
+    pub removed_sans: Vec<String>,
+}
+
+impl CertChanges {
+    /// Whether it's the same cert.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.added_sans.is_empty() && self.removed_sans.is_empty()
+    }
+}
+
+#[derive(Default, Props)]
+pub struct CertChangesProps {
+    pub changes: CertChanges,
+}
+
+#[component]
+pub fn CertChangesView(
+    mut hooks: Hooks,
+    props: &CertChangesProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let changes = &props.changes;
+    let since = changes.since.strftime("%Y-%m-%d %H:%M UTC").to_string();
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: "state:", color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(if changes.is_empty() {
+                    element! {
+                        Text(content: format!("✅ same cert as on {since}"), color: theme.good)
+                    }
+                } else {
+                    element! {
+                        Text(content: format!("⚠️  the cert changed since {since}"), color: theme.warning)
+                    }
+                })
+                #(changes.fields.iter().map(|change| element! {
+                    View(gap: 1) {
+                        Text(content: format!("{}:", change.field), color: theme.heading)
+                        Text(content: change.before.clone(), color: theme.bad)
+                        Text(content: "→")
+                        Text(content: change.after.clone(), color: theme.good)
+                    }
+                }))
+                #(changes.added_sans.iter().map(|san| element! {
+                    Text(content: format!("+ {san}"), color: theme.good)
+                }))
+                #(changes.removed_sans.iter().map(|san| element! {
+                    Text(content: format!("- {san}"), color: theme.bad)
+                }))
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use jiff::{tz::TimeZone, Timestamp};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+    x509::{NotAfter, SimpleCert},
+};
+
+/// How many certs share a value, e.g. a signature algorithm.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Count {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Aggregate numbers for a bundle of certs, from `parse --stats`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CertStats {
+    pub certs: usize,
+    /// Certs that are in the input more than once, not counting the first.
+    pub duplicates: usize,
+    pub expired: usize,
+    pub self_signed: usize,
+    pub cas: usize,
+    /// e.g. `RSA (2048 bits)`. The counts are sorted most common first.
+    pub key_types: Vec<Count>,
+    pub signature_algorithms: Vec<Count>,
+    pub issuers: Vec<Count>,
+    /// The year the certs expire, oldest first.
+    pub expiry_years: Vec<Count>,
+}
+
+impl CertStats {
+    /// Count `certs`, taking the ones that expired before `now` as expired.
+    /// Duplicates are only counted as such, not in the other numbers.
+    pub fn new(certs: &[SimpleCert], now: Timestamp) -> Self {
+        let mut seen = HashSet::new();
+        let mut stats = CertStats::default();
+        let mut key_types = HashMap::new();
+        let mut signature_algorithms = HashMap::new();
+        let mut issuers = HashMap::new();
+        let mut expiry_years = BTreeMap::new();
+
+        for cert in certs {
+            stats.certs += 1;
+            if !seen.insert(&cert.fingerprints.sha256) {
+                stats.duplicates += 1;
+                continue;
+            }
+
+            let key = &cert.public_key;
+            let key_type = format!("{} ({} bits)", key.name(), key.bits);
+            *key_types.entry(key_type).or_default() += 1;
+            *signature_algorithms
+                .entry(cert.signature.algorithm.clone())
+                .or_default() += 1;
+            *issuers.entry(cert.issuer.name.clone()).or_default() += 1;
+
+            // sorted as a number, so `unknown` and `no expiry` go last
+            let year = match cert.validity.not_after {
+                Some(NotAfter::NoExpiry) => (i16::MAX, "no expiry".to_string()),
+                Some(not_after) => {
+                    let year = not_after
+                        .timestamp()
+                        .map_or(i16::MAX - 1, |time| time.to_zoned(TimeZone::UTC).year());
+                    (year, year.to_string())
+                }
+                None => (i16::MAX - 1, "unknown".to_string()),
+            };
+            *expiry_years.entry(year).or_insert(0) += 1;
+
+            let expired = cert
+                .validity
+                .not_after
+                .and_then(NotAfter::timestamp)
+                .is_some_and(|not_after| not_after <= now);
+            stats.expired += usize::from(expired);
+            stats.self_signed += usize::from(cert.is_self_signed);
+            stats.cas += usize::from(cert.is_ca);
+        }
+
+        stats.key_types = by_count(key_types);
+        stats.signature_algorithms = by_count(signature_algorithms);
+        stats.issuers = by_count(issuers);
+        stats.expiry_years = expiry_years
+            .into_iter()
+            .map(|((_, value), count)| Count { value, count })
+            .collect();
+        stats
+    }
+}
+
+/// The counts, most common first and then by value, so the order is stable.
+fn by_count(counts: HashMap<String, usize>) -> Vec<Count> {
+    let mut counts: Vec<Count> = counts
+        .into_iter()
+        .map(|(value, count)| Count { value, count })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
+
+#[derive(Default, Props)]
+pub struct CertStatsProps {
+    pub stats: CertStats,
+}
+
+#[component]
+pub fn CertStatsView(mut hooks: Hooks, props: &CertStatsProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let stats = &props.stats;
+
+    let table = |heading: &str, counts: &[Count], bars: bool| {
+        let max = counts.iter().map(|count| count.count).max().unwrap_or(1);
+        element! {
+            View(flex_direction: FlexDirection::Column) {
+                Text(content: format!("{heading}:"), color: theme.heading)
+                View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                    #(counts.iter().map(|count| {
+                        // a histogram, at most 40 wide
+                        let bar = bars.then(|| "█".repeat((count.count * 40).div_ceil(max)));
+                        element! {
+                            View(gap: 1) {
+                                Text(content: format!("{:>6}", count.count), color: theme.highlight)
+                                Text(content: count.value.clone())
+                                #(bar.map(|bar| element! { Text(content: bar, color: theme.accent) }))
+                            }
+                        }
+                    }))
+                }
+            }
+        }
+    };
+
+    let total = |label: &str, count: usize| {
+        element! {
+            View(gap: 1) {
+                Text(content: format!("{label}:"), color: theme.heading)
+                Text(content: count.to_string(), color: theme.highlight)
+            }
+        }
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: "stats:", color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(total("certs", stats.certs))
+                #(total("duplicates", stats.duplicates))
+                #(total("expired", stats.expired))
+                #(total("self-signed", stats.self_signed))
+                #(total("CAs", stats.cas))
+                #(table("key types", &stats.key_types, false))
+                #(table("signature algorithms", &stats.signature_algorithms, false))
+                #(table("expires in", &stats.expiry_years, true))
+                #(table("issuers", &stats.issuers, false))
+            }
+        }
+    }
+}
+
+pub fn print_stats(
+    stats: CertStats,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(stats, format, options, |stats| {
+        element! { CertStatsView(stats) }.into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn counts_a_bundle() {
+        let mut certs: Vec<SimpleCert> =
+            X509::stack_from_pem(include_bytes!("../../test-data/certs/chain.pem"))
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect();
+        certs.push(certs[0].clone());
+
+        let stats = CertStats::new(&certs, Timestamp::UNIX_EPOCH);
+        assert_eq!(stats.certs, 4);
+        assert_eq!(stats.duplicates, 1);
+        assert_eq!(stats.expired, 0);
+        assert_eq!(stats.self_signed, 1);
+        assert_eq!(stats.issuers.iter().map(|c| c.count).sum::<usize>(), 3);
+        assert_eq!(stats.expiry_years.iter().map(|c| c.count).sum::<usize>(), 3);
+
+        // everything has expired by the year 9000
+        let later = "9000-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(CertStats::new(&certs, later).expired, 3);
+    }
+}
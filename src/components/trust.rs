@@ -0,0 +1,127 @@
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+};
+
+/// What happened to one change of `trust add`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Done,
+    /// Not run because of `--dry-run`.
+    DryRun,
+    Failed {
+        error: String,
+    },
+    /// Not run because an earlier change failed.
+    Skipped,
+}
+
+/// A trust store `trust add` changed, or would change with `--dry-run`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrustChange {
+    /// e.g. `NSS database (~/.pki/nssdb)`.
+    pub store: String,
+    /// The command that changes it.
+    pub command: String,
+    /// The command that takes the change back out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo: Option<String>,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TrustReport {
+    /// The CA's common name.
+    ca: String,
+    changes: Vec<TrustChange>,
+}
+
+#[derive(Default, Props)]
+pub struct TrustChangesProps {
+    pub ca: String,
+    pub changes: Vec<TrustChange>,
+}
+
+#[component]
+pub fn TrustChangesView(
+    mut hooks: Hooks,
+    props: &TrustChangesProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let failed = props
+        .changes
+        .iter()
+        .any(|change| matches!(change.outcome, Outcome::Failed { .. }));
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                Text(content: "trust:", color: theme.top_level)
+                Text(content: props.ca.clone(), color: theme.highlight)
+            }
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(props.changes.iter().map(|change| {
+                    let color = match change.outcome {
+                        Outcome::Done => theme.good,
+                        Outcome::Failed { .. } => theme.bad,
+                        Outcome::DryRun | Outcome::Skipped => theme.warning,
+                    };
+                    // what's already changed is only worth undoing when the
+                    // whole thing didn't go through
+                    let undo = change
+                        .undo
+                        .clone()
+                        .filter(|_| failed && change.outcome == Outcome::Done);
+                    element! {
+                        View(flex_direction: FlexDirection::Column) {
+                            Text(content: outcome_line(change), color)
+                            View(margin_left: 3) {
+                                Text(content: format!("$ {}", change.command))
+                            }
+                            #(undo.map(|undo| element! {
+                                View(margin_left: 3) {
+                                    Text(
+                                        content: format!("undo with: $ {undo}"),
+                                        color: theme.warning,
+                                    )
+                                }
+                            }))
+                        }
+                    }
+                }))
+            }
+        }
+    }
+}
+
+/// What happened to the store of `change`.
+fn outcome_line(change: &TrustChange) -> String {
+    let store = &change.store;
+    match &change.outcome {
+        Outcome::Done => format!("✅ added to the {store}"),
+        Outcome::DryRun => format!("would add to the {store}"),
+        Outcome::Failed { error } => format!("❌ failed to add to the {store}: {error}"),
+        Outcome::Skipped => format!("not added to the {store}, an earlier step failed"),
+    }
+}
+
+pub fn print_trust_changes(
+    ca: String,
+    changes: Vec<TrustChange>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(TrustReport { ca, changes }, format, options, |report| {
+        element! { TrustChangesView(ca: report.ca, changes: report.changes) }.into()
+    })
+}
@@ -0,0 +1,256 @@
+use boring::x509::{X509VerifyError, X509VerifyResult};
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props, Weight,
+};
+use serde::Serialize;
+
+use crate::{
+    chain::ChainIssue,
+    commands::{Format, RenderOptions},
+    components::print_report,
+    theme::UseTheme,
+};
+
+/// What verifying a file's cert against the CA found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    Ok,
+    Expired,
+    NotYetValid,
+    /// A signature in the chain doesn't verify with its issuer's key.
+    BadSignature,
+    /// The chain doesn't lead to the CA.
+    UnknownIssuer,
+    /// Any other reason the chain was rejected, e.g. a path length
+    /// constraint.
+    Invalid,
+    /// The file couldn't be read or has no cert.
+    Error,
+}
+
+impl VerifyStatus {
+    pub fn of(result: &X509VerifyResult) -> Self {
+        let Err(err) = result else {
+            return VerifyStatus::Ok;
+        };
+        if *err == X509VerifyError::CERT_HAS_EXPIRED {
+            VerifyStatus::Expired
+        } else if *err == X509VerifyError::CERT_NOT_YET_VALID {
+            VerifyStatus::NotYetValid
+        } else if *err == X509VerifyError::CERT_SIGNATURE_FAILURE
+            || *err == X509VerifyError::UNABLE_TO_DECRYPT_CERT_SIGNATURE
+        {
+            VerifyStatus::BadSignature
+        } else if *err == X509VerifyError::UNABLE_TO_GET_ISSUER_CERT
+            || *err == X509VerifyError::UNABLE_TO_GET_ISSUER_CERT_LOCALLY
+            || *err == X509VerifyError::UNABLE_TO_VERIFY_LEAF_SIGNATURE
+            || *err == X509VerifyError::SELF_SIGNED_CERT_IN_CHAIN
+            || *err == X509VerifyError::DEPTH_ZERO_SELF_SIGNED_CERT
+        {
+            VerifyStatus::UnknownIssuer
+        } else {
+            VerifyStatus::Invalid
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            VerifyStatus::Ok => "ok",
+            VerifyStatus::Expired => "expired",
+            VerifyStatus::NotYetValid => "not yet valid",
+            VerifyStatus::BadSignature => "bad signature",
+            VerifyStatus::UnknownIssuer => "unknown issuer",
+            VerifyStatus::Invalid => "invalid",
+            VerifyStatus::Error => "error",
+        }
+    }
+}
+
+/// The verdict on one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyEntry {
+    pub file: String,
+    /// The subject of the leaf.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub status: VerifyStatus,
+    /// boring's reason for rejecting the chain, or why the file couldn't be
+    /// checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// How the certs were put in order, when the file didn't have them leaf
+    /// first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reordered: Option<ChainIssue>,
+}
+
+/// How many files ended with each status.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VerifySummary {
+    pub total: usize,
+    pub ok: usize,
+    pub expired: usize,
+    pub not_yet_valid: usize,
+    pub bad_signature: usize,
+    pub unknown_issuer: usize,
+    pub invalid: usize,
+    pub error: usize,
+}
+
+impl VerifySummary {
+    pub fn of(entries: &[VerifyEntry]) -> Self {
+        let mut summary = VerifySummary {
+            total: entries.len(),
+            ..Default::default()
+        };
+        for entry in entries {
+            *match entry.status {
+                VerifyStatus::Ok => &mut summary.ok,
+                VerifyStatus::Expired => &mut summary.expired,
+                VerifyStatus::NotYetValid => &mut summary.not_yet_valid,
+                VerifyStatus::BadSignature => &mut summary.bad_signature,
+                VerifyStatus::UnknownIssuer => &mut summary.unknown_issuer,
+                VerifyStatus::Invalid => &mut summary.invalid,
+                VerifyStatus::Error => &mut summary.error,
+            } += 1;
+        }
+        summary
+    }
+
+    pub fn failed(&self) -> usize {
+        self.total - self.ok
+    }
+
+    /// The non-zero counts, e.g. `98 ok, 2 expired`.
+    fn counts(&self) -> String {
+        let counts = [
+            (self.ok, VerifyStatus::Ok),
+            (self.expired, VerifyStatus::Expired),
+            (self.not_yet_valid, VerifyStatus::NotYetValid),
+            (self.bad_signature, VerifyStatus::BadSignature),
+            (self.unknown_issuer, VerifyStatus::UnknownIssuer),
+            (self.invalid, VerifyStatus::Invalid),
+            (self.error, VerifyStatus::Error),
+        ];
+        counts
+            .into_iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, status)| format!("{count} {}", status.label()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// The verdicts on every file, or with `--summary` only the ones that
+/// failed, after the counts.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    /// The CA bundle the files were verified against.
+    pub ca: String,
+    pub summary: VerifySummary,
+    pub results: Vec<VerifyEntry>,
+}
+
+#[derive(Default, Props)]
+pub struct VerifyProps {
+    pub report: VerifyReport,
+}
+
+#[component]
+pub fn VerifyView(mut hooks: Hooks, props: &VerifyProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let report = &props.report;
+    let summary = &report.summary;
+
+    let (verdict, color) = match summary.failed() {
+        0 => (format!("✅ all {} verified", summary.total), theme.good),
+        failed => (
+            format!("❌ {failed} of {} failed", summary.total),
+            theme.bad,
+        ),
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column, gap: 1) {
+            View(flex_direction: FlexDirection::Column) {
+                View(gap: 1) {
+                    Text(content: "verify:", color: theme.top_level)
+                    Text(content: verdict, color)
+                    Text(content: format!("against {}", report.ca), weight: Weight::Light)
+                }
+                Text(content: summary.counts())
+            }
+            #((!report.results.is_empty()).then(|| element! {
+                View(flex_direction: FlexDirection::Column) {
+                    #(report.results.iter().map(|entry| {
+                        let color = match entry.status {
+                            VerifyStatus::Ok => theme.good,
+                            _ => theme.bad,
+                        };
+                        element! {
+                            View(gap: 1) {
+                                View(width: 16) {
+                                    Text(content: entry.status.label(), color)
+                                }
+                                Text(content: entry.file.clone())
+                                #(entry.subject.clone().map(|subject| element! {
+                                    Text(content: subject, color: theme.highlight)
+                                }))
+                                #(entry.error.clone().map(|error| element! {
+                                    Text(content: format!("({error})"), weight: Weight::Light)
+                                }))
+                                #(entry.reordered.as_ref().map(|issue| element! {
+                                    Text(content: issue.to_string(), color: theme.warning)
+                                }))
+                            }
+                        }
+                    }))
+                }
+            }))
+        }
+    }
+}
+
+pub fn print_verify(
+    report: VerifyReport,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report(report, format, options, |report| {
+        element! { VerifyView(report) }.into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_counts() {
+        let entry = |status| VerifyEntry {
+            file: "cert.pem".into(),
+            subject: None,
+            status,
+            error: None,
+            reordered: None,
+        };
+        assert_eq!(
+            VerifyStatus::of(&Err(X509VerifyError::CERT_HAS_EXPIRED)),
+            VerifyStatus::Expired
+        );
+        assert_eq!(VerifyStatus::of(&Ok(())), VerifyStatus::Ok);
+
+        let summary = VerifySummary::of(&[
+            entry(VerifyStatus::Ok),
+            entry(VerifyStatus::Ok),
+            entry(VerifyStatus::Expired),
+            entry(VerifyStatus::BadSignature),
+        ]);
+        assert_eq!((summary.total, summary.ok, summary.expired), (4, 2, 1));
+        assert_eq!(summary.failed(), 2);
+        assert_eq!(summary.counts(), "2 ok, 1 expired, 1 bad signature");
+    }
+}
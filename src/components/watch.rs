@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use iocraft::{
+    component, element,
+    prelude::{Text, View},
+    AnyElement, FlexDirection, Hooks, Props,
+};
+use jiff::Timestamp;
+use serde::Serialize;
+
+use crate::{
+    commands::{Format, RenderOptions},
+    components::{expiry::Status, print_report_list, round_relative_human, state::CertChanges},
+    state::Snapshot,
+    theme::UseTheme,
+    x509::{NotAfter, SimpleCert},
+};
+
+/// One check of one host by `watch`.
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchResult {
+    /// The host and port checked, e.g. `example.com:443`.
+    pub host: String,
+    pub checked_at: Timestamp,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// How long resolving, connecting and the handshake took.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handshake_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// How the cert differs from the one in the `--state` file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<CertChanges>,
+    /// The cert, to record in the `--state` file.
+    #[serde(skip)]
+    pub snapshot: Option<Snapshot>,
+}
+
+impl WatchResult {
+    pub fn new(host: &str, cert: &SimpleCert, handshake: Duration) -> Self {
+        WatchResult {
+            host: host.to_string(),
+            checked_at: Timestamp::now(),
+            status: Status::Ok,
+            subject: Some(cert.subject.name.clone()),
+            not_after: cert.validity.not_after.and_then(NotAfter::timestamp),
+            sha256: Some(cert.fingerprints.sha256.clone()),
+            handshake_seconds: Some(handshake.as_secs_f64()),
+            error: None,
+            changes: None,
+            snapshot: Some(Snapshot::new(cert, Timestamp::now())),
+        }
+    }
+
+    /// A check that didn't get a cert.
+    pub fn failed(host: &str, status: Status, error: String) -> Self {
+        WatchResult {
+            host: host.to_string(),
+            checked_at: Timestamp::now(),
+            status,
+            subject: None,
+            not_after: None,
+            sha256: None,
+            handshake_seconds: None,
+            error: Some(error),
+            changes: None,
+            snapshot: None,
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct WatchRoundProps {
+    pub results: Vec<WatchResult>,
+    pub options: RenderOptions,
+}
+
+/// One round of `watch`: a line per host.
+#[component]
+pub fn WatchRoundView(mut hooks: Hooks, props: &WatchRoundProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let options = &props.options;
+
+    let row = |result: &WatchResult| {
+        let (when, color) = match (result.status, result.not_after) {
+            (Status::Ok, Some(not_after)) => {
+                let left = options.now.timestamp().until(not_after).unwrap_or_default();
+                let rounded = round_relative_human(left, options.now.clone());
+                match left.signum() < 0 {
+                    true => (format!("expired {rounded:#}"), theme.bad),
+                    false => (format!("expires in {rounded:#}"), theme.good),
+                }
+            }
+            (Status::Ok, None) => ("no expiry".to_string(), theme.good),
+            (Status::Timeout, _) => ("timeout".to_string(), theme.bad),
+            (Status::Skipped, _) => ("skipped".to_string(), theme.warning),
+            (Status::Error, _) => ("error".to_string(), theme.bad),
+        };
+        let changed = result
+            .changes
+            .as_ref()
+            .filter(|changes| !changes.is_empty())
+            .map(|changes| {
+                let fields: Vec<&str> = changes.fields.iter().map(|c| c.field.as_str()).collect();
+                match fields.is_empty() {
+                    true => "(SANs changed)".to_string(),
+                    false => format!("({} changed)", fields.join(", ")),
+                }
+            });
+        let handshake = result
+            .handshake_seconds
+            .map(|seconds| format!("{:.0}ms", seconds * 1000.0))
+            .unwrap_or_default();
+
+        element! {
+            View(gap: 1) {
+                Text(content: result.host.clone(), color: theme.highlight)
+                Text(content: when, color: color)
+                Text(content: handshake)
+                #(changed.map(|changed| element! {
+                    Text(content: changed, color: theme.warning)
+                }))
+                #(result.error.clone().map(|error| element! {
+                    Text(content: error, color: theme.bad)
+                }))
+            }
+        }
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            Text(content: format!("{}:", options.now.strftime("%Y-%m-%d %H:%M:%S")), color: theme.top_level)
+            View(flex_direction: FlexDirection::Column, margin_left: 4) {
+                #(props.results.iter().map(row))
+            }
+        }
+    }
+}
+
+/// Print a round of checks as it finishes.
+pub fn print_round(
+    results: &[WatchResult],
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    print_report_list(results.to_vec(), format, options, |results| {
+        element! { WatchRoundView(results, options: options.clone()) }.into()
+    })
+}
@@ -1,31 +1,242 @@
+use std::sync::Arc;
+
 use iocraft::prelude::*;
-use jiff::Zoned;
+use jiff::Timestamp;
+use serde::Serialize;
 
 use crate::{
-    commands::Format,
-    components::round_relative_human,
-    theme::{HIGHLIGHT_COLOR, KEY_WIDTH, TOP_LEVEL_COLOR},
+    commands::{Format, RenderOptions},
+    components::{public_key::PublicKeyView as KeyDetailsView, round_relative_human},
+    der, explain,
+    lint::Lint,
+    pem::Source,
+    theme::UseTheme,
     x509::{
-        BasicConstraints, Fingerprints, Issuer, Signature, SimpleCert, SimpleKeyUsage,
-        SimplePublicKey, SimplePublicKeyKind, Subject, Validity,
+        BasicConstraints, NotAfter, ParseIssue, Signature, SignedBy, SimpleCert, SimpleKeyUsage,
+        SimplePublicKey, SimplePublicKeyKind, Subject, Validity, BROWSER_MAX_VALIDITY_DAYS,
     },
 };
 
+/// Certs are shared with the views rather than cloned into them, they carry
+/// a lot of hex and PEM text and there can be thousands of them.
 #[derive(Default, Props)]
 pub struct Props {
-    pub cert: SimpleCert,
+    pub cert: Arc<SimpleCert>,
+    pub options: RenderOptions,
 }
 
 #[component]
 pub fn X509View(props: &Props) -> impl Into<AnyElement<'static>> {
+    if props.options.short {
+        return element! {
+            ShortX509View(cert: props.cert.clone(), options: props.options.clone())
+        }
+        .into_any();
+    }
+
     element! {
         View(flex_direction: FlexDirection::Column) {
-            SubjectView(subject: props.cert.subject.clone(), serial: props.cert.serial.clone())
-            ValidityView(validity: props.cert.validity.clone())
-            PublicKeyView(public_key: props.cert.public_key.clone())
-            UsageView(key_usage: props.cert.key_usage.clone(), basic_constraints: props.cert.extensions.basic_constraints.clone())
-            IssuerView(issuer: props.cert.issuer.clone(), id: props.cert.aki.clone(), signature: props.cert.signature.clone())
-            FingerprintsView(fingerprints: props.cert.fingerprints.clone())
+            #(located(&props.options, props.cert.source.as_ref()))
+            IssuesView(issues: props.cert.issues.clone())
+            LintsView(lints: props.cert.lints.clone())
+            BadgesView(cert: props.cert.clone(), options: props.options.clone())
+            SubjectView(subject: props.cert.subject.clone(), serial: Some(props.options.serial(&props.cert.serial)), options: props.options.clone())
+            ValidityView(validity: props.cert.validity.clone(), options: props.options.clone())
+            PublicKeyView(public_key: props.cert.public_key.clone(), options: props.options.clone())
+            UsageView(key_usage: props.cert.key_usage.clone(), basic_constraints: props.cert.extensions.basic_constraints.clone(), options: props.options.clone())
+            IssuerView(cert: props.cert.clone(), options: props.options.clone())
+            UrlsView(cert: props.cert.clone(), options: props.options.clone())
+            FingerprintsView(cert: props.cert.clone(), options: props.options.clone())
+        }
+    }
+    .into_any()
+}
+
+/// A cert in a few lines, with `--short`: its subject, issuer, validity, key
+/// and SHA-256 fingerprint.
+#[component]
+pub fn ShortX509View(mut hooks: Hooks, props: &Props) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let cert = &props.cert;
+    let options = &props.options;
+
+    let date = |time: Option<Timestamp>| {
+        time.map_or("unknown".to_string(), |time| {
+            time.strftime("%Y-%m-%d").to_string()
+        })
+    };
+    let not_after = match cert.validity.not_after {
+        Some(NotAfter::NoExpiry) => "no expiry".to_string(),
+        not_after => date(not_after.and_then(NotAfter::timestamp)),
+    };
+    let validity = format!("{} → {not_after}", date(cert.validity.not_before));
+
+    // no relative times with `--stable`, they change from run to run
+    let now = options.now.timestamp();
+    let relative = |time: Timestamp| {
+        round_relative_human(now.until(time).unwrap_or_default(), options.now.clone())
+    };
+    let status = match (
+        cert.validity.not_before,
+        cert.validity.not_after.and_then(NotAfter::timestamp),
+    ) {
+        _ if options.stable => None,
+        (Some(not_before), _) if now < not_before => Some((
+            format!("(valid in {:#})", relative(not_before)),
+            theme.warning,
+        )),
+        // a negative span reads `3d ago`
+        (_, Some(not_after)) if now >= not_after => {
+            Some((format!("expired {:#}", relative(not_after)), theme.bad))
+        }
+        (_, Some(not_after)) => Some((
+            format!("(expires in {:#})", relative(not_after)),
+            theme.good,
+        )),
+        _ => None,
+    };
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(located(options, cert.source.as_ref()))
+            IssuesView(issues: cert.issues.clone())
+            View(gap: 1) {
+                Text(content: "subject:", color: theme.top_level)
+                Text(content: options.name(&cert.subject.name, &cert.subject.dn))
+            }
+            View(gap: 1) {
+                Text(content: "issuer: ", color: theme.top_level)
+                Text(content: options.name(&cert.issuer.name, &cert.issuer.dn))
+            }
+            View(gap: 1) {
+                Text(content: "valid:  ", color: theme.top_level)
+                Text(content: validity)
+                #(status.map(|(status, color)| element! {
+                    Text(content: status, color: color)
+                }))
+            }
+            View(gap: 1) {
+                Text(content: "key:    ", color: theme.top_level)
+                Text(content: format!("{} ({} bits)", cert.public_key.name(), cert.public_key.bits))
+            }
+            View(gap: 1) {
+                Text(content: "sha256: ", color: theme.top_level)
+                Text(content: cert.fingerprints.sha256.clone())
+            }
+        }
+    }
+}
+
+/// `(from bundle.pem:120-160)`, with `parse --locations`.
+pub(crate) fn located(
+    options: &RenderOptions,
+    source: Option<&Source>,
+) -> Option<AnyElement<'static>> {
+    source.filter(|_| options.locations).map(|source| {
+        element! {
+            Text(content: format!("(from {source})"), weight: Weight::Light)
+        }
+        .into_any()
+    })
+}
+
+/// A `--explain` note under a field, or nothing without `--explain`.
+pub(crate) fn note(
+    options: &RenderOptions,
+    note: Option<&'static str>,
+) -> Option<AnyElement<'static>> {
+    note.filter(|_| options.explain).map(|note| {
+        element! {
+            View(margin_left: 4) {
+                Text(content: format!("↳ {note}"), weight: Weight::Light)
+            }
+        }
+        .into_any()
+    })
+}
+
+#[derive(Default, Props)]
+pub struct IssuesProps {
+    pub issues: Vec<ParseIssue>,
+}
+
+/// Warnings for fields that could not be parsed. Renders nothing when there
+/// are none.
+#[component]
+pub fn IssuesView(mut hooks: Hooks, props: &IssuesProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(props.issues.iter().map(|issue| element! {
+                Text(content: format!("⚠️  {issue}"), color: theme.warning)
+            }))
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct LintsProps {
+    pub lints: Vec<Lint>,
+}
+
+/// Encoding problems in a cert that still parsed, e.g. a negative serial.
+/// Renders nothing when there are none.
+#[component]
+pub fn LintsView(mut hooks: Hooks, props: &LintsProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(props.lints.iter().map(|lint| element! {
+                View(gap: 1) {
+                    Text(content: format!("⚠️  {}", lint.message), color: theme.warning)
+                    Text(content: format!("[{}]", lint.code), weight: Weight::Light)
+                }
+            }))
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct BadgesProps {
+    pub cert: Arc<SimpleCert>,
+    pub options: RenderOptions,
+}
+
+/// `[root]`, `[ca]`, `[self-signed]` and `[fetched via AIA]` markers, and the
+/// validation level of a leaf, e.g. `[DV]`, or `[OV?]` when it's a guess.
+#[component]
+pub fn BadgesView(mut hooks: Hooks, props: &BadgesProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let badges = [
+        (props.cert.is_root, "root"),
+        (props.cert.is_ca, "ca"),
+        (props.cert.is_self_signed, "self-signed"),
+        (props.cert.fetched_from.is_some(), "fetched via AIA"),
+    ];
+    let badges: Vec<_> = badges
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, badge)| badge)
+        .collect();
+    let validation = props.cert.validation.as_ref().map(|validation| {
+        let guess = if validation.policy.is_some() { "" } else { "?" };
+        (
+            format!("[{}{guess}]", validation.level),
+            explain::validation(validation.level),
+        )
+    });
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            View(gap: 1) {
+                #(badges.iter().map(|badge| element! {
+                    Text(content: format!("[{badge}]"), color: theme.accent, weight: Weight::Bold)
+                }))
+                #(validation.as_ref().map(|(badge, _)| element! {
+                    Text(content: badge.clone(), color: theme.accent, weight: Weight::Bold)
+                }))
+            }
+            #(badges.iter().filter_map(|badge| note(&props.options, explain::field(badge))))
+            #(validation.and_then(|(_, explanation)| note(&props.options, Some(explanation))))
         }
     }
 }
@@ -34,16 +245,18 @@ pub fn X509View(props: &Props) -> impl Into<AnyElement<'static>> {
 pub struct SubjectProps {
     pub subject: Subject,
     pub serial: Option<String>,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn SubjectView(props: &SubjectProps) -> impl Into<AnyElement<'static>> {
+pub fn SubjectView(mut hooks: Hooks, props: &SubjectProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     let dns = (!props.subject.sans.dns.is_empty()).then(|| {
         element! {
             View(gap: 1) {
                 Text(content: "dns:") {}
                 #(props.subject.sans.dns.iter().map(|dns| {
-                    element! { Text(content: dns, color: HIGHLIGHT_COLOR, decoration: TextDecoration::Underline) }
+                    element! { Text(content: props.options.dns_name(dns), color: theme.highlight, decoration: TextDecoration::Underline) }
                 }))
             }
         }
@@ -54,7 +267,7 @@ pub fn SubjectView(props: &SubjectProps) -> impl Into<AnyElement<'static>> {
             View(gap: 1) {
                 Text(content: "ip:") {}
                 #(props.subject.sans.ip.iter().map(|ip| {
-                    element! { Text(content: ip.to_string(), decoration: TextDecoration::Underline, color: Color::Cyan) }
+                    element! { Text(content: ip.to_string(), decoration: TextDecoration::Underline, color: theme.accent) }
                 }))
             }
         }
@@ -63,7 +276,7 @@ pub fn SubjectView(props: &SubjectProps) -> impl Into<AnyElement<'static>> {
     let email = (!props.subject.sans.email.is_empty()).then(|| {
         element! {
             View(gap: 1) {
-                Text(content: "email:", color: Color::Yellow) {}
+                Text(content: "email:", color: theme.warning) {}
                 #(props.subject.sans.email.iter().map(|email| {
                     element! { Text(content: email, decoration: TextDecoration::Underline) }
                 }))
@@ -74,7 +287,7 @@ pub fn SubjectView(props: &SubjectProps) -> impl Into<AnyElement<'static>> {
     let uri = (!props.subject.sans.uri.is_empty()).then(|| {
         element! {
             View(gap: 1) {
-                Text(content: "uri:", color: Color::Green) {}
+                Text(content: "uri:", color: theme.good) {}
                 #(props.subject.sans.uri.iter().map(|uri| {
                     element! { Text(content: uri, decoration: TextDecoration::Underline) }
                 }))
@@ -82,31 +295,78 @@ pub fn SubjectView(props: &SubjectProps) -> impl Into<AnyElement<'static>> {
         }
     });
 
+    // the less common SAN types, rendered as `label: value, value`
+    let other_sans = [
+        (
+            "other name:",
+            props
+                .subject
+                .sans
+                .other_name
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        ),
+        ("directory name:", props.subject.sans.directory_name.clone()),
+        ("registered id:", props.subject.sans.registered_id.clone()),
+        (
+            "unknown:",
+            props
+                .subject
+                .sans
+                .unknown
+                .iter()
+                .map(|san| format!("[{}] {}", san.tag, san.value))
+                .collect(),
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, values)| !values.is_empty())
+    .map(|(label, values)| {
+        element! {
+            View(gap: 1) {
+                Text(content: label) {}
+                #(values.into_iter().map(|value| {
+                    element! { Text(content: value, decoration: TextDecoration::Underline) }
+                }))
+            }
+        }
+    });
+
     element! {
         View(flex_direction: FlexDirection::Column) {
             View(gap: 1) {
-                Text(content: "subject:", color: TOP_LEVEL_COLOR) {}
-                Text(content: &props.subject.name)
+                Text(content: "subject:", color: theme.top_level) {}
+                Text(content: props.options.name(&props.subject.name, &props.subject.dn))
             }
+            #(note(&props.options, explain::field("subject")))
             View(margin_left: 4, flex_direction: FlexDirection::Column) {
                 #(dns)
                 #(ip)
                 #(email)
                 #(uri)
+                #(other_sans)
+                #((!props.subject.sans.is_empty()).then(|| note(&props.options, explain::extension(der::oid::SUBJECT_ALT_NAME))).flatten())
             }
             #(props.subject.ski.clone().map(|ski| {
                 element! {
-                    View(margin_left: 4) {
-                        Text(content: "ski: ") {}
-                        Text(content: ski)
+                    View(margin_left: 4, flex_direction: FlexDirection::Column) {
+                        View() {
+                            Text(content: "ski: ") {}
+                            Text(content: ski)
+                        }
+                        #(note(&props.options, explain::extension(der::oid::SUBJECT_KEY_IDENTIFIER)))
                     }
                 }
             }))
             #(props.serial.clone().map(|serial| {
                 element! {
-                    View(margin_left: 4) {
-                        Text(content: "serial: ") {}
-                        Text(content: serial)
+                    View(margin_left: 4, flex_direction: FlexDirection::Column) {
+                        View() {
+                            Text(content: "serial: ") {}
+                            Text(content: serial)
+                        }
+                        #(note(&props.options, explain::field("serial")))
                     }
                 }
             }))
@@ -117,56 +377,91 @@ pub fn SubjectView(props: &SubjectProps) -> impl Into<AnyElement<'static>> {
 #[derive(Default, Props)]
 pub struct ValidityProps {
     pub validity: Validity,
+    pub options: RenderOptions,
 }
 
 #[component]
-fn ValidityView(props: &ValidityProps) -> impl Into<AnyElement<'static>> {
-    let zoned_now = Zoned::now();
+fn ValidityView(mut hooks: Hooks, props: &ValidityProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let zoned_now = props.options.now.clone();
     let now = zoned_now.timestamp();
-    let expires_in = now.until(props.validity.not_after).unwrap();
-    let valid_in = now.until(props.validity.not_before).unwrap();
+    // no relative times with `--stable`, they change from run to run
+    let relative = !props.options.stable;
 
-    let rounded_valid_in = round_relative_human(valid_in, zoned_now.clone());
-    let not_before_text = if valid_in.signum() < 0 {
-        // it's became valid in the past
-        element! {
-            SurroundText(
-                left: "(",
-                text: format!("{:#}", rounded_valid_in),
-                right: ")"
-            )
-        }
-    } else {
-        // it's not valid yet
-        element! {
-            SurroundText(
-                left: "(in ",
-                text: format!("{:#}", rounded_valid_in),
-                right: ")  "
-            )
-        }
+    let time_text = |time: Option<String>| match time {
+        Some(time) => element! { Text(content: time) },
+        None => element! { Text(content: "unknown", color: theme.warning) },
     };
 
-    let rounded_expires_in = round_relative_human(expires_in, zoned_now);
-    let expires_in_text = if expires_in.signum() < 0 {
-        // it expired in the future, so it's still valid
-        element! {
-            Text(content: format!("expired {:#}", rounded_expires_in), color: Color::Red, decoration: TextDecoration::Underline, weight: Weight::Bold)
-        }
-        .into_any()
+    let not_before_text = props
+        .validity
+        .not_before
+        .filter(|_| relative)
+        .map(|not_before| {
+            let valid_in = now.until(not_before).unwrap_or_default();
+            let rounded_valid_in = round_relative_human(valid_in, zoned_now.clone());
+            if valid_in.signum() < 0 {
+                // it's became valid in the past
+                element! {
+                    SurroundText(
+                        left: "(",
+                        text: format!("{:#}", rounded_valid_in),
+                        right: ")"
+                    )
+                }
+            } else {
+                // it's not valid yet
+                element! {
+                    SurroundText(
+                        left: "(in ",
+                        text: format!("{:#}", rounded_valid_in),
+                        right: ")  "
+                    )
+                }
+            }
+        });
+
+    let expires_in_text = if props.validity.no_expiry {
+        // the 99991231235959Z sentinel is too far out to round relative to now
+        Some(
+            element! {
+                SurroundText(left: "(", text: "no well-defined expiration", right: ")")
+            }
+            .into_any(),
+        )
     } else {
-        // it expired in the future, so it's still valid
-        element! {
-            SurroundText(
-                left: "(in ",
-                text: format!("{:#}", rounded_expires_in),
-                right: ")    "
-            )
-        }
-        .into_any()
+        props
+            .validity
+            .not_after
+            .and_then(NotAfter::timestamp)
+            .filter(|_| relative)
+            .map(|not_after| {
+            let expires_in = now.until(not_after).unwrap_or_default();
+            let rounded_expires_in = round_relative_human(expires_in, zoned_now.clone());
+            if expires_in.signum() < 0 {
+                element! {
+                    Text(content: format!("expired {:#}", rounded_expires_in), color: theme.bad, decoration: TextDecoration::Underline, weight: Weight::Bold)
+                }
+                .into_any()
+            } else {
+                // it expired in the future, so it's still valid
+                element! {
+                    SurroundText(
+                        left: "(in ",
+                        text: format!("{:#}", rounded_expires_in),
+                        right: ")    "
+                    )
+                }
+                .into_any()
+            }
+        })
     };
 
-    let expired = now >= props.validity.not_after;
+    let expired = props
+        .validity
+        .not_after
+        .and_then(NotAfter::timestamp)
+        .is_some_and(|not_after| now >= not_after);
 
     // todo: add time validity:
     let _is_valid_text = if props.validity.valid.unwrap_or(!expired) {
@@ -178,14 +473,32 @@ fn ValidityView(props: &ValidityProps) -> impl Into<AnyElement<'static>> {
     } else {
         {
             element! {
-                Text(content: format!("🚨 {}", props.validity.verify_result.clone().unwrap_or_default()), color: Color::Red, decoration: TextDecoration::Underline)
+                Text(content: format!("🚨 {}", props.validity.verify_result.clone().unwrap_or_default()), color: theme.bad, decoration: TextDecoration::Underline)
             }
         }
     };
 
+    let lifetime = props.validity.validity_period_days.map(|days| {
+        let limit = props.validity.exceeds_browser_limit.then(|| {
+            element! {
+                Text(content: format!("(over the {BROWSER_MAX_VALIDITY_DAYS} day browser limit)"), color: theme.warning)
+            }
+        });
+        element! {
+            View(flex_direction: FlexDirection::Column) {
+                View(gap: 1, flex_direction: FlexDirection::Row) {
+                    Text(content: "lifetime:  ", color: theme.top_level)
+                    Text(content: format!("{days} days"))
+                    #(limit)
+                }
+                #(note(&props.options, explain::field("lifetime")))
+            }
+        }
+    });
+
     let verify_result_text = props.validity.verify_result.clone().map(|result| {
         element! {
-            Text(content: format!("🚨 {result}"), color: Color::Red, decoration: TextDecoration::Underline)
+            Text(content: format!("🚨 {result}"), color: theme.bad, decoration: TextDecoration::Underline)
         }
     });
 
@@ -193,15 +506,18 @@ fn ValidityView(props: &ValidityProps) -> impl Into<AnyElement<'static>> {
         View(flex_direction: FlexDirection::Column) {
             #(verify_result_text)
             View(gap: 1, flex_direction: FlexDirection::Row) {
-                Text(content: "not before:", color: TOP_LEVEL_COLOR)
-                Text(content: props.validity.not_before.to_string())
+                Text(content: "not before:", color: theme.top_level)
+                #(time_text(props.validity.not_before.map(|time| time.to_string())))
                 #(not_before_text)
             }
+            #(note(&props.options, explain::field("not before")))
             View(gap: 1, flex_direction: FlexDirection::Row) {
-                Text(content: "not after: ", color: TOP_LEVEL_COLOR)
-                Text(content: props.validity.not_after.to_string())
+                Text(content: "not after: ", color: theme.top_level)
+                #(time_text(props.validity.not_after.map(|time| time.to_string())))
                 #(expires_in_text)
             }
+            #(note(&props.options, explain::field("not after")))
+            #(lifetime)
         }
     }
 }
@@ -232,10 +548,13 @@ pub fn SurroundText(props: &SurroundTextProps) -> impl Into<AnyElement<'static>>
 #[derive(Default, Props)]
 pub struct PublicKeyProps {
     pub(crate) public_key: SimplePublicKey,
+    pub(crate) options: RenderOptions,
 }
 
 #[component]
-pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
+pub fn PublicKeyView(mut hooks: Hooks, props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let key_width = props.options.key_width();
     let public_key_element = match &props.public_key.kind {
         SimplePublicKeyKind::EC { group, key } => {
             element! {
@@ -244,14 +563,14 @@ pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
                         element! {
                             View() {
                                 Text(content: "group: ") {}
-                                Text(content: nid.short_name().unwrap(), color: HIGHLIGHT_COLOR)
+                                Text(content: nid.short_name().unwrap_or("unknown"), color: theme.highlight)
                             }
                         }
                     }))
                     View(gap: 1) {
                         Text(content: "key:") {}
-                        View(width: KEY_WIDTH) {
-                            Text(content: key.clone()) {}
+                        View(width: key_width) {
+                            Text(content: props.options.hex(key)) {}
                         }
                     }
                 }
@@ -268,23 +587,31 @@ pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
                     }
                     View(gap: 1) {
                         Text(content: "modulus:") {}
-                        View(width: KEY_WIDTH) {
-                            Text(content: modulus.clone())
+                        View(width: key_width) {
+                            Text(content: props.options.hex(modulus))
                         }
                     }
                 }
             }
         }
-        // todo: the rest of the key types
-        key => todo!("{:?} not implemented", key),
+        // DSA, EdDSA, XDH, RSA-PSS and keys boring can't parse render the
+        // same as a standalone public key
+        _ => {
+            element! {
+                View(flex_direction: FlexDirection::Column) {
+                    KeyDetailsView(pub_key: props.public_key.clone(), in_cert: true, options: props.options.clone())
+                }
+            }
+        }
     };
 
     element! {
         View(flex_direction: FlexDirection::Column) {
             View(gap: 1) {
-                Text(content: "public key:", color: TOP_LEVEL_COLOR) {}
-                Text(content: format!("{} ({} bits)", props.public_key.curve.nid().short_name().unwrap(), props.public_key.bits))
+                Text(content: "public key:", color: theme.top_level) {}
+                Text(content: format!("{} ({} bits)", props.public_key.name(), props.public_key.bits))
             }
+            #(note(&props.options, explain::field("public key")))
             View(margin_left: 4) {
                 #(public_key_element)
             }
@@ -295,23 +622,39 @@ pub fn PublicKeyView(props: &PublicKeyProps) -> impl Into<AnyElement<'static>> {
 #[derive(Default, Props)]
 pub struct SignatureProps {
     pub signature: Signature,
+    pub signed_by: Option<SignedBy>,
     pub top_level: bool,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn SignatureView(props: &SignatureProps) -> impl Into<AnyElement<'static>> {
+pub fn SignatureView(mut hooks: Hooks, props: &SignatureProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let status = props.signed_by.as_ref().map(|signed_by| {
+        if signed_by.valid {
+            element! {
+                Text(content: format!("✅ valid (signed by {})", signed_by.name), color: theme.good)
+            }
+        } else {
+            element! {
+                Text(content: format!("🚨 invalid (not signed by {})", signed_by.name), color: theme.bad, decoration: TextDecoration::Underline)
+            }
+        }
+    });
     element! {
         View(flex_direction: FlexDirection::Column) {
             View(gap: 1) {
                 #(if props.top_level { element! {
-                    Text(content: "signature:", color: TOP_LEVEL_COLOR)
+                    Text(content: "signature:", color: theme.top_level)
                 } } else { element! {
                     Text(content: "signature:")
                 } })
-                Text(content: props.signature.algorithm.clone())
+                Text(content: props.signature.describe())
+                #(status)
             }
-            View(margin_left: 4, width: KEY_WIDTH) {
-                Text(content: props.signature.value.clone(), wrap: TextWrap::Wrap)
+            #(note(&props.options, explain::field("signature")))
+            View(margin_left: 4, width: props.options.key_width()) {
+                Text(content: props.options.hex(&props.signature.value), wrap: TextWrap::Wrap)
             }
         }
     }
@@ -319,29 +662,86 @@ pub fn SignatureView(props: &SignatureProps) -> impl Into<AnyElement<'static>> {
 
 #[derive(Default, Props)]
 pub struct IssuerProps {
-    pub issuer: Issuer,
-    pub id: Option<String>,
-    pub signature: Signature,
+    pub cert: Arc<SimpleCert>,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn IssuerView(props: &IssuerProps) -> impl Into<AnyElement<'static>> {
+pub fn IssuerView(mut hooks: Hooks, props: &IssuerProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     element! {
         View(flex_direction: FlexDirection::Column) {
             View() {
-                Text(content: "issuer: ", color: TOP_LEVEL_COLOR) {}
-                Text(content: format!("{}", props.issuer.name))
+                Text(content: "issuer: ", color: theme.top_level) {}
+                Text(content: props.options.name(&props.cert.issuer.name, &props.cert.issuer.dn))
             }
-            #(props.id.clone().map(|id| {
+            #(note(&props.options, explain::field("issuer")))
+            #(props.cert.aki.clone().map(|id| {
                 element! {
-                    View(margin_left: 4) {
-                        Text(content: "aki: ") {}
-                        Text(content: id)
+                    View(margin_left: 4, flex_direction: FlexDirection::Column) {
+                        View() {
+                            Text(content: "aki: ") {}
+                            Text(content: id)
+                        }
+                        #(note(&props.options, explain::extension(der::oid::AUTHORITY_KEY_IDENTIFIER)))
                     }
                 }
             }))
             View(margin_left: 4) {
-                SignatureView(signature: props.signature.clone())
+                SignatureView(
+                    signature: props.cert.signature.clone(),
+                    signed_by: props.cert.signed_by.clone(),
+                    options: props.options.clone(),
+                )
+            }
+        }
+    }
+}
+
+#[derive(Default, Props)]
+pub struct UrlsProps {
+    pub cert: Arc<SimpleCert>,
+    pub options: RenderOptions,
+}
+
+/// The OCSP, caIssuers and CRL URLs of the cert, which aren't fetched here.
+/// Renders nothing when there are none.
+#[component]
+pub fn UrlsView(mut hooks: Hooks, props: &UrlsProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let extensions = &props.cert.extensions;
+    let rows = [
+        ("ocsp:", &extensions.ocsp, None),
+        // both are in authorityInfoAccess, which gets one note
+        (
+            "ca issuers:",
+            &extensions.ca_issuers,
+            Some(der::oid::AUTHORITY_INFO_ACCESS),
+        ),
+        (
+            "crl:",
+            &extensions.crl_distribution_points,
+            Some(der::oid::CRL_DISTRIBUTION_POINTS),
+        ),
+    ];
+
+    element! {
+        View(flex_direction: FlexDirection::Column) {
+            #(extensions.has_urls().then(|| element! {
+                Text(content: "urls:", color: theme.top_level)
+            }))
+            View(margin_left: 4, flex_direction: FlexDirection::Column) {
+                #(rows.into_iter().filter(|(_, urls, _)| !urls.is_empty()).map(|(label, urls, oid)| element! {
+                    View(flex_direction: FlexDirection::Column) {
+                        View(gap: 1) {
+                            Text(content: label)
+                            #(urls.iter().map(|url| element! {
+                                Text(content: url.clone(), decoration: TextDecoration::Underline)
+                            }))
+                        }
+                        #(note(&props.options, oid.and_then(explain::extension)))
+                    }
+                }))
             }
         }
     }
@@ -351,10 +751,12 @@ pub fn IssuerView(props: &IssuerProps) -> impl Into<AnyElement<'static>> {
 pub struct UsageProps {
     pub key_usage: SimpleKeyUsage,
     pub basic_constraints: Option<BasicConstraints>,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn UsageView(props: &UsageProps) -> impl Into<AnyElement<'static>> {
+pub fn UsageView(mut hooks: Hooks, props: &UsageProps) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     let mut key_usage_text = String::new();
     if props.key_usage.digital_signature {
         key_usage_text.push_str("digital signature");
@@ -412,20 +814,33 @@ pub fn UsageView(props: &UsageProps) -> impl Into<AnyElement<'static>> {
         View(gap: 1) {
             #(if props.key_usage.critical { element! {
                 View(gap: 1) {
-                    Text(content: "usage:", color: TOP_LEVEL_COLOR)
+                    Text(content: "usage:", color: theme.top_level)
                     Text(content: "(critical)")
                 }
             }.into_any() } else { element! {
-                Text(content: "usage: ", color: TOP_LEVEL_COLOR)
+                Text(content: "usage: ", color: theme.top_level)
             }.into_any() })
-            Text(content: key_usage_text, color: HIGHLIGHT_COLOR)
+            Text(content: key_usage_text, color: theme.highlight)
         }
     };
 
+    let key_usage_note = (!key_usage_text.is_empty())
+        .then(|| note(&props.options, explain::extension(der::oid::KEY_USAGE)));
+    // the purposes aren't listed without `--explain`, only explained
+    let purpose_notes: Vec<_> = props
+        .key_usage
+        .extended
+        .oids()
+        .iter()
+        .filter_map(|oid| note(&props.options, explain::purpose(oid)))
+        .collect();
+
     // todo: implement basic constraints
     element! {
         View(flex_direction: FlexDirection::Column) {
             #(key_usage)
+            #(key_usage_note.flatten())
+            #(purpose_notes)
             // #(basic_constraints)
         }
     }
@@ -433,18 +848,30 @@ pub fn UsageView(props: &UsageProps) -> impl Into<AnyElement<'static>> {
 
 #[derive(Default, Props)]
 pub struct FingerprintsProps {
-    pub fingerprints: Fingerprints,
+    pub cert: Arc<SimpleCert>,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn FingerprintsView(props: &FingerprintsProps) -> impl Into<AnyElement<'static>> {
+pub fn FingerprintsView(
+    mut hooks: Hooks,
+    props: &FingerprintsProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
+    let fingerprints = &props.cert.fingerprints;
+    let spki = &props.cert.public_key.spki;
     element! {
         View(flex_direction: FlexDirection::Column) {
-            Text(content: "fingerprints:", color: TOP_LEVEL_COLOR)
+            Text(content: "fingerprints:", color: theme.top_level)
+            #(note(&props.options, explain::field("fingerprints")))
             View(flex_direction: FlexDirection::Column, margin_left: 4) {
-                Text(content: format!("sha256: {}", props.fingerprints.sha256))
-                Text(content: format!("sha1:   {}", props.fingerprints.sha1))
-                Text(content: format!("md5:    {}", props.fingerprints.md5))
+                Text(content: format!("sha256: {}", fingerprints.sha256))
+                Text(content: format!("sha1:   {}", fingerprints.sha1))
+                Text(content: format!("md5:    {}", fingerprints.md5))
+                Text(content: format!("spki:   {}", spki.spki_sha256))
+                #(note(&props.options, explain::field("spki")))
+                Text(content: format!("pin:    {}", spki.pin_sha256))
+                #(note(&props.options, explain::field("pin")))
             }
         }
     }
@@ -452,43 +879,323 @@ pub fn FingerprintsView(props: &FingerprintsProps) -> impl Into<AnyElement<'stat
 
 #[derive(Default, Props)]
 pub struct MultipleCertViewProps {
-    pub certs: Vec<SimpleCert>,
+    pub certs: Vec<Arc<SimpleCert>>,
+    pub options: RenderOptions,
 }
 
 #[component]
-pub fn MultipleCertView(props: &MultipleCertViewProps) -> impl Into<AnyElement<'static>> {
+pub fn MultipleCertView(
+    mut hooks: Hooks,
+    props: &MultipleCertViewProps,
+) -> impl Into<AnyElement<'static>> {
+    let theme = hooks.use_theme();
     element! {
         View(gap: 1, flex_direction: FlexDirection::Column) {
-            #(props.certs.iter().cloned().enumerate().map(|(i, cert)| element!(
+            #(props.certs.iter().map(Arc::clone).enumerate().map(|(i, cert)| element!(
                 View(flex_direction: FlexDirection::Column) {
-                    Text(content: format!("cert #{}:", i + 1), color: Color::Magenta)
-                    X509View(cert)
+                    Text(content: format!("cert #{}:", i + 1), color: theme.heading)
+                    X509View(cert, options: props.options.clone())
                 }
             )))
         }
     }
 }
 
-pub fn print_certs(certs: Vec<SimpleCert>, format: Format) -> color_eyre::Result<()> {
+pub fn print_certs(
+    mut certs: Vec<SimpleCert>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
     tracing::info!("printing {} certs in {format:?} format", certs.len());
+    if options.stable {
+        certs.iter_mut().for_each(SimpleCert::make_stable);
+    }
+
     match format {
         Format::Text => {
-            element! {
+            options.print(element! {
                 View(margin: 1) {
-                    MultipleCertView(certs)
+                    MultipleCertView(certs: certs.into_iter().map(Arc::new).collect::<Vec<_>>(), options: options.clone())
                 }
-            }
-            .print();
+            });
         }
-        Format::Json => {
-            println!("{}", serde_json::to_string_pretty(&certs)?);
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&certs)?;
         }
         Format::Pem => {
             for cert in certs {
-                print!("{}", cert.pem);
+                options.print_pem(|| pem_header(&cert, options), &cert.pem);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The `--pem-headers` comment for a cert: its subject and when it expires.
+pub(crate) fn pem_header(cert: &SimpleCert, options: &RenderOptions) -> String {
+    let name = options.name(&cert.subject.name, &cert.subject.dn);
+    match &cert.validity.not_after {
+        Some(NotAfter::At(time)) => format!("{name}, expires {}", time.strftime("%Y-%m-%d")),
+        Some(NotAfter::NoExpiry) => format!("{name}, never expires"),
+        None => name,
+    }
+}
+
+/// A cert found by `parse --der-scan`, with where it was in the input.
+#[derive(Debug, Serialize)]
+pub struct CarvedCert {
+    /// Byte offset of the cert's DER encoding in the input.
+    pub offset: usize,
+    /// Length of the DER encoding in bytes.
+    pub length: usize,
+    #[serde(flatten)]
+    pub cert: SimpleCert,
+}
+
+pub fn print_carved_certs(
+    mut certs: Vec<CarvedCert>,
+    format: Format,
+    options: &RenderOptions,
+) -> color_eyre::Result<()> {
+    for carved in &mut certs {
+        carved.cert.validity.evaluate_at(options.now.timestamp());
+    }
+    if options.stable {
+        certs
+            .iter_mut()
+            .for_each(|carved| carved.cert.make_stable());
+    }
+
+    match format {
+        Format::Text => {
+            options.print(element! {
+                View(flex_direction: FlexDirection::Column, gap: 1, margin: 1) {
+                    #(certs.into_iter().map(|carved| element! {
+                        View(flex_direction: FlexDirection::Column) {
+                            Text(
+                                content: format!("offset {:#x} ({} bytes):", carved.offset, carved.length),
+                                color: options.theme.heading,
+                            )
+                            X509View(cert: Arc::new(carved.cert), options: options.clone())
+                        }
+                    }))
+                }
+            });
+        }
+        Format::Json | Format::JsonCompact | Format::Ndjson => {
+            format.print_json_list(&certs)?;
+        }
+        Format::Pem => {
+            for carved in certs {
+                options.print_pem(|| pem_header(&carved.cert, options), &carved.cert.pem);
             }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    fn render(pem: &[u8]) -> String {
+        let cert = Arc::new(SimpleCert::from(X509::from_pem(pem).unwrap()));
+        element! {
+            X509View(cert)
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn stable_output_has_no_relative_times() {
+        let mut cert = SimpleCert::from(
+            X509::from_pem(include_bytes!("../../test-data/certs/lan-fish.pem")).unwrap(),
+        );
+        cert.make_stable();
+        let options = RenderOptions {
+            stable: true,
+            ..Default::default()
+        };
+
+        let cert = Arc::new(cert);
+        let output = element! {
+            X509View(cert, options)
+        }
+        .to_string();
+        assert!(!output.contains("expired"), "{output}");
+        assert!(!output.contains("(in "), "{output}");
+    }
+
+    #[test]
+    fn short_output() {
+        let cert = Arc::new(SimpleCert::from(
+            X509::from_pem(include_bytes!("../../test-data/certs/cloudflare.com.pem")).unwrap(),
+        ));
+        let options = RenderOptions {
+            short: true,
+            stable: true,
+            ..Default::default()
+        };
+        let output = element! {
+            X509View(cert: cert.clone(), options)
+        }
+        .to_string();
+        let lines: Vec<&str> = output
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        assert_eq!(lines.len(), 5, "{output}");
+        assert!(lines[0].starts_with("subject:"), "{output}");
+        assert!(lines[4].ends_with(&cert.fingerprints.sha256), "{output}");
+        assert!(!output.contains("expire"), "{output}");
+
+        let options = RenderOptions {
+            short: true,
+            now: "2100-01-01T00:00:00Z"
+                .parse::<Timestamp>()
+                .unwrap()
+                .to_zoned(jiff::tz::TimeZone::UTC),
+            ..Default::default()
+        };
+        let output = element! {
+            X509View(cert, options)
+        }
+        .to_string();
+        assert!(output.contains(" ago"), "{output}");
+    }
+
+    #[test]
+    fn long_hex_wraps_to_the_terminal() {
+        let signature = Signature {
+            algorithm: "ecdsa-with-SHA256".to_string(),
+            value: "ab".repeat(72),
+            ..Default::default()
+        };
+        let options = RenderOptions {
+            width: Some(80),
+            full: true,
+            ..Default::default()
+        };
+        let key_width = options.key_width() as usize;
+
+        let output = element! {
+            SignatureView(signature, options)
+        }
+        .to_string();
+        let lines: Vec<_> = output.lines().skip(1).map(str::trim_end).collect();
+        assert!(lines.len() > 1, "{output}");
+        assert!(
+            lines
+                .iter()
+                .all(|line| line.chars().count() <= key_width + 4),
+            "{output}"
+        );
+        assert_eq!(lines.concat().replace(' ', ""), "ab".repeat(72));
+    }
+
+    #[test]
+    fn unparsed_validity_is_unknown() {
+        let validity = Validity::new(None, None);
+        let output = element! {
+            ValidityView(validity)
+        }
+        .to_string();
+        assert!(output.contains("not before: unknown"), "{output}");
+        assert!(!output.contains("expired"), "{output}");
+    }
+
+    #[test]
+    fn long_lifetimes_are_flagged() {
+        let validity = Validity::new(
+            Some("2025-01-01T00:00:00Z".parse().unwrap()),
+            Some(NotAfter::At("2027-01-01T00:00:00Z".parse().unwrap())),
+        );
+        let output = element! {
+            ValidityView(validity)
+        }
+        .to_string();
+        assert!(output.contains("lifetime:   730 days"), "{output}");
+        assert!(
+            output.contains("over the 398 day browser limit"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn no_expiry_sentinel_is_shown_as_written() {
+        let validity = Validity::new(None, Some(NotAfter::NoExpiry));
+        let output = element! {
+            ValidityView(validity)
+        }
+        .to_string();
+        assert!(output.contains("9999-12-31T23:59:59Z"), "{output}");
+        assert!(output.contains("no well-defined expiration"), "{output}");
+    }
+
+    #[test]
+    fn pem_headers() {
+        let cert = SimpleCert::from(
+            X509::from_pem(include_bytes!("../../test-data/certs/lan-fish.pem")).unwrap(),
+        );
+        assert_eq!(
+            pem_header(&cert, &RenderOptions::default()),
+            "CN=lan.fish, expires 2025-04-03"
+        );
+    }
+
+    #[test]
+    fn root_badges() {
+        let output = render(include_bytes!("../../test-data/certs/isrg-root-x2.pem"));
+        assert!(output.contains("[root] [ca] [self-signed]"), "{output}");
+
+        let output = render(include_bytes!("../../test-data/certs/lan-fish.pem"));
+        assert!(!output.contains("[ca]"), "{output}");
+    }
+
+    #[test]
+    fn explain_annotates_fields() {
+        let cert = Arc::new(SimpleCert::from(
+            X509::from_pem(include_bytes!("../../test-data/certs/lan-fish.pem")).unwrap(),
+        ));
+        let options = RenderOptions {
+            explain: true,
+            ..Default::default()
+        };
+        let output = element! {
+            X509View(cert: cert.clone(), options)
+        }
+        .to_string();
+        assert!(
+            output.contains("should match the issuer cert's SKI"),
+            "{output}"
+        );
+        assert!(
+            output.contains("serverAuth: usable for TLS servers"),
+            "{output}"
+        );
+
+        let output = element! {
+            X509View(cert)
+        }
+        .to_string();
+        assert!(!output.contains('↳'), "{output}");
+    }
+
+    #[test]
+    fn dsa_public_key() {
+        let output = render(include_bytes!("../../test-data/certs/dsa.pem"));
+        assert!(output.contains("DSA Public Key"), "{output}");
+        // only under fingerprints, not again in the key details
+        assert_eq!(output.matches("spki").count(), 1, "{output}");
+    }
+
+    #[test]
+    fn ed25519_public_key() {
+        let output = render(include_bytes!("../../test-data/certs/ed25519.pem"));
+        assert!(output.contains("Ed25519 Public Key"), "{output}");
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::{Context, Result};
+use serde::Deserialize;
+
+use crate::theme::ThemeOverrides;
+
+/// The optional config file, read from `$PLS_CONFIG` or
+/// `$XDG_CONFIG_HOME/pls/config.toml` (`~/.config/pls/config.toml`).
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub theme: ThemeOverrides,
+}
+
+impl Config {
+    /// Load the config file, or the defaults if there isn't one.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        let data = match std::fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Reading config {}", path.display()))
+            }
+        };
+        tracing::debug!("loading config from {}", path.display());
+
+        toml::from_str(&data).with_context(|| format!("Parsing config {}", path.display()))
+    }
+
+    fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("PLS_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("pls").join("config.toml"))
+    }
+}
@@ -3,10 +3,17 @@ use std::{
     time::Duration,
 };
 
-use boring::ssl::SslRef;
+use boring::ssl::{SslRef, SslVersion};
+use schemars::JsonSchema;
 use serde::Serialize;
 
-#[derive(Debug, Default, Clone, Serialize)]
+use crate::{
+    dns::Resolution,
+    h2::Setting,
+    verifier::{Verification, Verifier},
+};
+
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
 pub struct Connection {
     pub curve: String,
     pub is_pqc: bool,
@@ -14,23 +21,93 @@ pub struct Connection {
     pub transport: Transport,
     pub valid: bool,
     pub verify_result: Option<String>,
+    /// Who `valid` and `verify_result` come from, see `--verifier`.
+    pub verifier: Verifier,
     pub time: Time,
+    /// How the host was resolved. Not set for IP addresses and `--proxy`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns: Option<Resolution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<Transcript>,
+}
+
+impl Connection {
+    /// Replace boring's verdict from the handshake with `verifier`'s.
+    pub fn apply_verification(&mut self, verifier: Verifier, verification: &Verification) {
+        self.verifier = verifier;
+        self.valid = verification.valid;
+        self.verify_result = verification.error.clone();
+    }
+
+    /// Zero the timings and DNS TTLs for `--stable` output, they differ on
+    /// every run. The round trips are kept.
+    pub fn make_stable(&mut self) {
+        if let Some(dns) = &mut self.dns {
+            dns.ttl = None;
+        }
+        self.time = Time {
+            round_trips: std::mem::take(&mut self.time.round_trips),
+            ..Default::default()
+        };
+    }
 }
 
-#[derive(Debug, Default, Clone, Serialize)]
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
 pub struct Time {
     #[serde(serialize_with = "serialize_duration")]
+    #[schemars(with = "f64")]
     pub dns: Duration,
+    /// Time spent opening the tunnel through `--proxy`, after connecting to it.
+    #[serde(
+        serialize_with = "serialize_opt_duration",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(with = "Option<f64>")]
+    pub proxy: Option<Duration>,
     #[serde(
         serialize_with = "serialize_opt_duration",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(with = "Option<f64>")]
     pub connect: Option<Duration>,
     #[serde(serialize_with = "serialize_duration")]
+    #[schemars(with = "f64")]
     pub tls: Duration,
+    pub round_trips: RoundTrips,
 }
 
-#[derive(Debug, Default, Copy, Clone, Serialize)]
+/// How many round trips setting up the connection took.
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct RoundTrips {
+    /// The TCP handshake, for connections over TCP.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp: Option<u32>,
+    /// The TLS (or QUIC) handshake: 1 for TLS 1.3, one more after a
+    /// HelloRetryRequest, and 2 for TLS 1.2 and older.
+    pub tls: u32,
+    /// The server answered the ClientHello with a HelloRetryRequest, asking
+    /// for a key share on another curve. Usually none of the offered key
+    /// shares were for a curve it supports, e.g. with `--pqc`.
+    pub hello_retry_request: bool,
+}
+
+impl RoundTrips {
+    fn new(transport: Transport, ssl: &SslRef) -> Self {
+        let hello_retry_request = ssl.used_hello_retry_request();
+        let tls = match ssl.version_str() {
+            "TLSv1.3" => 1 + u32::from(hello_retry_request),
+            _ => 2,
+        };
+
+        Self {
+            tcp: matches!(transport, Transport::TCP).then_some(1),
+            tls,
+            hello_retry_request,
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, Serialize, JsonSchema)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum Transport {
     #[default]
@@ -38,6 +115,85 @@ pub enum Transport {
     QUIC,
 }
 
+/// The handshake parameters `pls` offered in its ClientHello next to what the
+/// server selected in its ServerHello.
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct Transcript {
+    pub offered: Offered,
+    pub selected: Selected,
+}
+
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct Offered {
+    pub versions: Vec<String>,
+    pub ciphers: Vec<String>,
+    pub curves: Vec<String>,
+    pub alpn: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct Selected {
+    pub version: String,
+    pub cipher: Option<String>,
+    pub curve: Option<String>,
+    pub alpn: Option<String>,
+}
+
+impl Transcript {
+    pub fn new(offered: Offered, ssl: &SslRef) -> Self {
+        let selected = Selected {
+            version: ssl.version_str().to_string(),
+            cipher: ssl.current_cipher().map(|c| c.name().to_string()),
+            curve: ssl.curve().and_then(|c| c.name()).map(str::to_string),
+            alpn: ssl
+                .selected_alpn_protocol()
+                .map(|p| String::from_utf8_lossy(p).into_owned()),
+        };
+
+        Self { offered, selected }
+    }
+}
+
+/// The response to the request `connect --http` sent over the connection,
+/// with the headers that matter for TLS.
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct HttpProbe {
+    /// `GET` or `HEAD`.
+    pub method: String,
+    /// The protocol the server selected with ALPN, e.g. `http/1.1`.
+    pub alpn: Option<String>,
+    /// The version from the status line, e.g. `HTTP/1.1`.
+    pub version: String,
+    pub status: u16,
+    pub strict_transport_security: Option<String>,
+    /// Deprecated, browsers ignore it now that CT is enforced for every cert.
+    pub expect_ct: Option<String>,
+    pub alt_svc: Option<String>,
+}
+
+/// What the server sent back to the HTTP/2 connection preface of
+/// `connect --h2`.
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct H2Probe {
+    /// The protocol the server selected with ALPN, `h2`.
+    pub alpn: String,
+    /// The server's SETTINGS, in the order it sent them. Settings left out
+    /// have their default value.
+    pub settings: Vec<Setting>,
+}
+
+/// The human readable name of a protocol version, e.g. `TLSv1.3`.
+pub fn version_name(version: SslVersion) -> &'static str {
+    match version {
+        SslVersion::SSL3 => "SSLv3",
+        SslVersion::TLS1 => "TLSv1",
+        SslVersion::TLS1_1 => "TLSv1.1",
+        SslVersion::TLS1_2 => "TLSv1.2",
+        SslVersion::TLS1_3 => "TLSv1.3",
+        _ => "unknown",
+    }
+}
+
 /// serialize a duration as a number in microseconds
 fn serialize_duration<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -59,7 +215,7 @@ where
 }
 
 impl From<(Transport, Time, &SslRef)> for Connection {
-    fn from((transport, time, ssl): (Transport, Time, &SslRef)) -> Self {
+    fn from((transport, mut time, ssl): (Transport, Time, &SslRef)) -> Self {
         let curve = ssl
             .curve()
             .and_then(|c| c.name())
@@ -68,6 +224,7 @@ impl From<(Transport, Time, &SslRef)> for Connection {
 
         // todo(fix): poor man's PQC check
         let is_pqc = curve.contains("Kyber") || curve.contains("MLKEM");
+        time.round_trips = RoundTrips::new(transport, ssl);
 
         Self {
             curve,
@@ -76,7 +233,43 @@ impl From<(Transport, Time, &SslRef)> for Connection {
             transport,
             valid: ssl.verify_result().is_ok(),
             verify_result: ssl.verify_result().map_err(|v| v.to_string()).err(),
+            verifier: Verifier::Boring,
             time,
+            dns: None,
+            transcript: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_connection_has_no_timings() {
+        let mut connection = Connection {
+            time: Time {
+                dns: Duration::from_millis(3),
+                proxy: Some(Duration::from_millis(5)),
+                connect: Some(Duration::from_millis(7)),
+                tls: Duration::from_millis(11),
+                round_trips: RoundTrips {
+                    tcp: Some(1),
+                    tls: 2,
+                    hello_retry_request: true,
+                },
+            },
+            ..Default::default()
+        };
+        connection.make_stable();
+
+        assert_eq!(
+            serde_json::to_value(&connection).unwrap()["time"],
+            serde_json::json!({
+                "dns": 0.0,
+                "tls": 0.0,
+                "round_trips": { "tcp": 1, "tls": 2, "hello_retry_request": true }
+            })
+        );
+    }
+}
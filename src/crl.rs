@@ -0,0 +1,145 @@
+//! Certificate revocation lists, as found in PKCS#7 bundles and at the
+//! cRLDistributionPoints of certs.
+
+use boring::bn::BigNum;
+use boring::hash::MessageDigest;
+use boring::pkey::{PKeyRef, Public};
+use boring::sign::Verifier;
+use color_eyre::eyre::{bail, Context, Result};
+use jiff::Timestamp;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::der;
+use crate::pem::Source;
+use crate::x509::{parse_asn1_time, Name, Serial};
+
+#[derive(Default, Debug, Clone, Serialize, JsonSchema)]
+pub struct SimpleCrl {
+    pub issuer: Name,
+    #[schemars(with = "String")]
+    pub this_update: Timestamp,
+    /// When the next CRL is due. CRLs without one are rare.
+    #[schemars(with = "Option<String>")]
+    pub next_update: Option<Timestamp>,
+    pub revoked: Vec<RevokedCert>,
+    pub pem: String,
+    /// Where the CRL was found in the input, see [`Source`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, JsonSchema)]
+pub struct RevokedCert {
+    #[serde(flatten)]
+    pub serial: Serial,
+    #[schemars(with = "String")]
+    pub revoked_at: Timestamp,
+    /// Why it was revoked, e.g. `keyCompromise`, when the CRL says.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl SimpleCrl {
+    /// Parse a DER encoded CRL.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let fields = der::crl_fields(der).context("parsing CRL")?;
+        let time = |tlv: der::Tlv| parse_asn1_time(&String::from_utf8_lossy(tlv.value));
+
+        let revoked = fields
+            .revoked
+            .into_iter()
+            .map(|entry| {
+                Ok(RevokedCert {
+                    serial: Serial::from_bn(&BigNum::from_slice(entry.serial.value)?)?,
+                    revoked_at: time(entry.date)?,
+                    reason: entry.reason.map(reason_name),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(SimpleCrl {
+            issuer: Name::from_der(fields.issuer.value)?,
+            this_update: time(fields.this_update)?,
+            next_update: fields.next_update.map(time).transpose()?,
+            revoked,
+            pem: pem_encode("X509 CRL", der),
+            source: None,
+        })
+    }
+}
+
+/// Whether a DER encoded CRL's signature verifies with `key`, its issuer's
+/// public key.
+pub fn verify_signature(der: &[u8], key: &PKeyRef<Public>) -> Result<bool> {
+    let fields = der::crl_fields(der).context("parsing CRL")?;
+    let digest = match fields.signature_algorithm.as_str() {
+        "1.2.840.113549.1.1.5" | "1.2.840.10045.4.1" => MessageDigest::sha1(),
+        "1.2.840.113549.1.1.11" | "1.2.840.10045.4.3.2" => MessageDigest::sha256(),
+        "1.2.840.113549.1.1.12" | "1.2.840.10045.4.3.3" => MessageDigest::sha384(),
+        "1.2.840.113549.1.1.13" | "1.2.840.10045.4.3.4" => MessageDigest::sha512(),
+        // Ed25519
+        "1.3.101.112" => {
+            let mut verifier = Verifier::new_without_digest(key)?;
+            return Ok(verifier.verify_oneshot(fields.signature, fields.tbs.raw)?);
+        }
+        oid => bail!("unsupported CRL signature algorithm {oid}"),
+    };
+
+    let mut verifier = Verifier::new(digest, key)?;
+    Ok(verifier.verify_oneshot(fields.signature, fields.tbs.raw)?)
+}
+
+/// The name of a `CRLReason` code (RFC 5280 section 5.3.1).
+pub fn reason_name(code: u8) -> String {
+    match code {
+        0 => "unspecified",
+        1 => "keyCompromise",
+        2 => "cACompromise",
+        3 => "affiliationChanged",
+        4 => "superseded",
+        5 => "cessationOfOperation",
+        6 => "certificateHold",
+        8 => "removeFromCRL",
+        9 => "privilegeWithdrawn",
+        10 => "aACompromise",
+        code => return format!("unknown reason {code}"),
+    }
+    .to_string()
+}
+
+/// PEM encode `der` under `label`, with 64 character lines.
+pub(crate) fn pem_encode(label: &str, der: &[u8]) -> String {
+    let base64 = boring::base64::encode_block(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in base64.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+    pem
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn signature() {
+        let bundle = include_bytes!("../test-data/pkcs7/chain-and-crl.p7b");
+        let (_, crls) = der::pkcs7_signed_data(bundle).unwrap();
+        let issuer = X509::from_pem(include_bytes!("../test-data/certs/rsa-self-signed.pem"))
+            .unwrap()
+            .public_key()
+            .unwrap();
+        assert!(verify_signature(crls[0], &issuer).unwrap());
+
+        let other = X509::from_pem(include_bytes!("../test-data/certs/isrg-root-x2.pem"))
+            .unwrap()
+            .public_key()
+            .unwrap();
+        assert!(!verify_signature(crls[0], &other).unwrap_or(false));
+    }
+}
@@ -0,0 +1,277 @@
+//! DANE (RFC 6698, RFC 7671): checking the certs a server sent against its
+//! TLSA records.
+
+use std::fmt;
+
+use color_eyre::eyre::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::dns::{RecordData, RecordType, Resolver};
+use crate::x509::SimpleCert;
+
+/// A TLSA record: which cert in the chain to match, and what to match it
+/// with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Tlsa {
+    /// 0 PKIX-TA, 1 PKIX-EE, 2 DANE-TA, 3 DANE-EE.
+    pub usage: u8,
+    /// 0 the whole cert, 1 its SubjectPublicKeyInfo.
+    pub selector: u8,
+    /// 0 exact, 1 SHA-256, 2 SHA-512.
+    pub matching_type: u8,
+    /// Hex.
+    pub data: String,
+}
+
+impl Tlsa {
+    /// Whether the record is for the leaf (end entity) rather than one of
+    /// its issuers (trust anchor).
+    fn is_end_entity(&self) -> bool {
+        matches!(self.usage, 1 | 3)
+    }
+
+    /// Whether the chain also has to pass the usual WebPKI validation.
+    fn requires_pkix(&self) -> bool {
+        matches!(self.usage, 0 | 1)
+    }
+
+    fn usage_name(&self) -> &'static str {
+        match self.usage {
+            0 => "PKIX-TA",
+            1 => "PKIX-EE",
+            2 => "DANE-TA",
+            3 => "DANE-EE",
+            _ => "unknown usage",
+        }
+    }
+}
+
+impl fmt::Display for Tlsa {
+    /// The zone file form, e.g. `3 1 1 0b9fa5a5...`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.usage, self.selector, self.matching_type, self.data
+        )
+    }
+}
+
+/// How a TLSA record compares with the chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TlsaStatus {
+    /// `cert` is the subject of the cert that matched.
+    Match {
+        cert: String,
+    },
+    /// A cert matched, but the chain isn't trusted, which usages 0 and 1
+    /// require.
+    PkixFailed {
+        cert: String,
+    },
+    Mismatch,
+    /// A usage, selector or matching type that isn't defined.
+    Unsupported,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct TlsaMatch {
+    #[serde(flatten)]
+    pub record: Tlsa,
+    #[serde(flatten)]
+    pub status: TlsaStatus,
+}
+
+/// The result of `connect --dane`.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Dane {
+    /// The name the records were looked up at, e.g. `_443._tcp.example.com`.
+    pub name: String,
+    pub resolver: String,
+    /// The resolver validated the records with DNSSEC. Without it they could
+    /// have been spoofed, and DANE clients ignore them.
+    pub authenticated: bool,
+    pub records: Vec<TlsaMatch>,
+}
+
+impl Dane {
+    /// The chain is valid under DANE: the records are signed and one of them
+    /// matched.
+    pub fn valid(&self) -> bool {
+        self.authenticated && self.matched()
+    }
+
+    /// One of the records matched, signed or not.
+    pub fn matched(&self) -> bool {
+        self.records
+            .iter()
+            .any(|record| matches!(record.status, TlsaStatus::Match { .. }))
+    }
+}
+
+/// The name a service's TLSA records are at, e.g. `_443._tcp.example.com`.
+pub fn tlsa_name(host: &str, port: u16, protocol: &str) -> String {
+    format!("_{port}._{protocol}.{}", host.trim_end_matches('.'))
+}
+
+/// Look up the TLSA records at `name` with `resolver` and check `certs`, the
+/// chain in order with the leaf first, against them. `pkix_valid` is whether
+/// the chain verified against the trust store.
+pub async fn check(
+    resolver: &Resolver,
+    name: String,
+    certs: &[SimpleCert],
+    pkix_valid: bool,
+) -> Result<Dane> {
+    let answer = resolver.query(&name, RecordType::Tlsa).await?;
+    let records = answer
+        .records
+        .into_iter()
+        .filter_map(|record| match record.data {
+            RecordData::Tlsa {
+                usage,
+                selector,
+                matching_type,
+                data,
+            } => Some(Tlsa {
+                usage,
+                selector,
+                matching_type,
+                data,
+            }),
+            _ => None,
+        })
+        .map(|record| {
+            let status = status(&record, certs, pkix_valid)?;
+            Ok(TlsaMatch { record, status })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Dane {
+        name,
+        resolver: resolver.to_string(),
+        authenticated: answer.authenticated,
+        records,
+    })
+}
+
+fn status(record: &Tlsa, certs: &[SimpleCert], pkix_valid: bool) -> Result<TlsaStatus> {
+    if record.usage > 3 || record.selector > 1 || record.matching_type > 2 {
+        return Ok(TlsaStatus::Unsupported);
+    }
+    // end entity records only match the leaf, trust anchor records any of
+    // its issuers
+    let candidates = if record.is_end_entity() {
+        certs.get(..1).unwrap_or_default()
+    } else {
+        certs.get(1..).unwrap_or_default()
+    };
+
+    for cert in candidates {
+        let data = cert.tlsa_data(record.selector, record.matching_type)?;
+        if !data.is_some_and(|data| hex::encode(data).eq_ignore_ascii_case(&record.data)) {
+            continue;
+        }
+
+        let cert = cert.subject.name.clone();
+        if record.requires_pkix() && !pkix_valid {
+            return Ok(TlsaStatus::PkixFailed { cert });
+        }
+        return Ok(TlsaStatus::Match { cert });
+    }
+    Ok(TlsaStatus::Mismatch)
+}
+
+impl fmt::Display for TlsaMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = &self.record;
+        match &self.status {
+            TlsaStatus::Match { cert } => {
+                write!(f, "{record} ({}) matches {cert}", record.usage_name())
+            }
+            TlsaStatus::PkixFailed { cert } => write!(
+                f,
+                "{record} ({}) matches {cert}, but the chain isn't trusted",
+                record.usage_name()
+            ),
+            TlsaStatus::Mismatch => {
+                write!(f, "{record} ({}) doesn't match", record.usage_name())
+            }
+            TlsaStatus::Unsupported => write!(f, "{record} isn't supported"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::chain;
+
+    fn record(usage: u8, selector: u8, cert: &SimpleCert) -> Tlsa {
+        Tlsa {
+            usage,
+            selector,
+            matching_type: 1,
+            data: hex::encode(cert.tlsa_data(selector, 1).unwrap().unwrap()),
+        }
+    }
+
+    #[test]
+    fn matches() {
+        let certs = chain();
+        let leaf = &certs[0].subject.name;
+        let issuer = &certs[1].subject.name;
+
+        let cases = [
+            (
+                record(3, 1, &certs[0]),
+                true,
+                TlsaStatus::Match { cert: leaf.clone() },
+            ),
+            (
+                record(2, 0, &certs[1]),
+                false,
+                TlsaStatus::Match {
+                    cert: issuer.clone(),
+                },
+            ),
+            (
+                record(1, 1, &certs[0]),
+                false,
+                TlsaStatus::PkixFailed { cert: leaf.clone() },
+            ),
+            // a DANE-EE record for an intermediate
+            (record(3, 1, &certs[1]), true, TlsaStatus::Mismatch),
+            // and a DANE-TA record for the leaf
+            (record(2, 1, &certs[0]), true, TlsaStatus::Mismatch),
+        ];
+        for (record, pkix_valid, expected) in cases {
+            assert_eq!(
+                status(&record, &certs, pkix_valid).unwrap(),
+                expected,
+                "{record}"
+            );
+        }
+
+        let mut record = record(3, 1, &certs[0]);
+        record.matching_type = 9;
+        assert_eq!(
+            status(&record, &certs, true).unwrap(),
+            TlsaStatus::Unsupported
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            tlsa_name("example.com.", 443, "tcp"),
+            "_443._tcp.example.com"
+        );
+        assert_eq!(
+            tlsa_name("mail.example.com", 25, "tcp"),
+            "_25._tcp.mail.example.com"
+        );
+    }
+}
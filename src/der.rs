@@ -0,0 +1,783 @@
+//! A minimal DER reader for the parts of certificates, CSRs, CRLs and PKCS#7
+//! bundles that boring doesn't expose, e.g. every `GeneralName` form in a
+//! subjectAltName.
+
+use color_eyre::eyre::{bail, eyre, Result};
+
+pub mod tag {
+    pub const BOOLEAN: u8 = 0x01;
+    pub const INTEGER: u8 = 0x02;
+    pub const BIT_STRING: u8 = 0x03;
+    pub const OCTET_STRING: u8 = 0x04;
+    pub const NULL: u8 = 0x05;
+    pub const OID: u8 = 0x06;
+    pub const UTF8_STRING: u8 = 0x0c;
+    pub const PRINTABLE_STRING: u8 = 0x13;
+    pub const T61_STRING: u8 = 0x14;
+    pub const IA5_STRING: u8 = 0x16;
+    pub const UTC_TIME: u8 = 0x17;
+    pub const GENERALIZED_TIME: u8 = 0x18;
+    pub const VISIBLE_STRING: u8 = 0x1a;
+    pub const UNIVERSAL_STRING: u8 = 0x1c;
+    pub const BMP_STRING: u8 = 0x1e;
+    pub const ENUMERATED: u8 = 0x0a;
+    pub const SEQUENCE: u8 = 0x30;
+    pub const SET: u8 = 0x31;
+
+    /// A context-specific, constructed tag: `[n]` wrapping other TLVs.
+    pub const fn context(n: u8) -> u8 {
+        0xa0 | n
+    }
+
+    /// A context-specific, primitive tag: `[n] IMPLICIT` of a primitive type.
+    pub const fn context_primitive(n: u8) -> u8 {
+        0x80 | n
+    }
+}
+
+pub mod oid {
+    pub const SUBJECT_KEY_IDENTIFIER: &str = "2.5.29.14";
+    pub const AUTHORITY_KEY_IDENTIFIER: &str = "2.5.29.35";
+    pub const SUBJECT_ALT_NAME: &str = "2.5.29.17";
+    pub const BASIC_CONSTRAINTS: &str = "2.5.29.19";
+    pub const CERTIFICATE_POLICIES: &str = "2.5.29.32";
+    pub const EXTENSION_REQUEST: &str = "1.2.840.113549.1.9.14";
+    pub const CHALLENGE_PASSWORD: &str = "1.2.840.113549.1.9.7";
+    pub const UNSTRUCTURED_NAME: &str = "1.2.840.113549.1.9.2";
+    pub const KEY_USAGE: &str = "2.5.29.15";
+    pub const EXTENDED_KEY_USAGE: &str = "2.5.29.37";
+    pub const AUTHORITY_INFO_ACCESS: &str = "1.3.6.1.5.5.7.1.1";
+    pub const CA_ISSUERS: &str = "1.3.6.1.5.5.7.48.2";
+    pub const OCSP: &str = "1.3.6.1.5.5.7.48.1";
+    pub const CRL_DISTRIBUTION_POINTS: &str = "2.5.29.31";
+    pub const CRL_REASON: &str = "2.5.29.21";
+    pub const SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+    pub const RSASSA_PSS: &str = "1.2.840.113549.1.1.10";
+}
+
+/// A single tag-length-value element.
+#[derive(Debug, Clone, Copy)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    /// The contents, without the tag and length.
+    pub value: &'a [u8],
+    /// The whole encoding, including the tag and length.
+    pub raw: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// A reader over the elements inside a constructed TLV.
+    pub fn reader(&self) -> Reader<'a> {
+        Reader::new(self.value)
+    }
+
+    /// Decode the value as an OBJECT IDENTIFIER in dotted form.
+    pub fn oid(&self) -> Result<String> {
+        if self.tag != tag::OID {
+            bail!("expected OID, found tag {:#04x}", self.tag);
+        }
+        oid_to_string(self.value)
+    }
+
+    /// Decode the value as one of the ASN.1 string types, if it is one.
+    pub fn string(&self) -> Option<String> {
+        match self.tag {
+            tag::UTF8_STRING
+            | tag::PRINTABLE_STRING
+            | tag::IA5_STRING
+            | tag::VISIBLE_STRING
+            | tag::T61_STRING => Some(String::from_utf8_lossy(self.value).into_owned()),
+            tag::BMP_STRING => {
+                let units: Vec<u16> = self
+                    .value
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            tag::UNIVERSAL_STRING => Some(
+                self.value
+                    .chunks_exact(4)
+                    .filter_map(|c| char::from_u32(u32::from_be_bytes([c[0], c[1], c[2], c[3]])))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The value as a string if it is one, otherwise hex encoded.
+    pub fn display_value(&self) -> String {
+        self.string().unwrap_or_else(|| hex::encode(self.value))
+    }
+}
+
+/// Reads consecutive TLVs out of a buffer.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    /// Read the next element, whatever its tag.
+    pub fn read(&mut self) -> Result<Tlv<'a>> {
+        let (tlv, len) = read_tlv(&self.data[self.pos..])?;
+        self.pos += len;
+        Ok(tlv)
+    }
+
+    /// Read the next element, failing if it doesn't have the `expected` tag.
+    pub fn read_tag(&mut self, expected: u8) -> Result<Tlv<'a>> {
+        let tlv = self.read()?;
+        if tlv.tag != expected {
+            bail!("expected tag {expected:#04x}, found {:#04x}", tlv.tag);
+        }
+        Ok(tlv)
+    }
+
+    /// Read the next element only if it has the `expected` tag.
+    pub fn read_optional(&mut self, expected: u8) -> Result<Option<Tlv<'a>>> {
+        if self.peek_tag() == Some(expected) {
+            self.read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<Tlv<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_empty() {
+            return None;
+        }
+        let next = self.read();
+        if next.is_err() {
+            // don't keep yielding the same error
+            self.pos = self.data.len();
+        }
+        Some(next)
+    }
+}
+
+/// Read a single TLV from the start of `data`, returning it and the number of
+/// bytes it occupies.
+pub fn read_tlv(data: &[u8]) -> Result<(Tlv<'_>, usize)> {
+    let (&tag, rest) = data
+        .split_first()
+        .ok_or_else(|| eyre!("unexpected end of data"))?;
+    if tag & 0x1f == 0x1f {
+        bail!("high tag numbers are not supported");
+    }
+
+    let (&first, rest) = rest.split_first().ok_or_else(|| eyre!("missing length"))?;
+    let (len, len_bytes) = if first & 0x80 == 0 {
+        (first as usize, 0)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 {
+            bail!("indefinite lengths are not allowed in DER");
+        }
+        if count > 4 || count > rest.len() {
+            bail!("length of {count} bytes is too long");
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |len, &b| (len << 8) | b as usize);
+        (len, count)
+    };
+
+    let header_len = 2 + len_bytes;
+    let total = header_len
+        .checked_add(len)
+        .filter(|&total| total <= data.len())
+        .ok_or_else(|| eyre!("element of {len} bytes overruns the input"))?;
+
+    Ok((
+        Tlv {
+            tag,
+            value: &data[header_len..total],
+            raw: &data[..total],
+        },
+        total,
+    ))
+}
+
+/// Encode a TLV with `tag` around `value`, e.g. to rebuild a structure after
+/// changing one of its elements.
+pub fn encode_tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let len = value.len();
+    let mut tlv = vec![tag];
+    if len < 0x80 {
+        tlv.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        tlv.push(0x80 | (bytes.len() - skip) as u8);
+        tlv.extend(&bytes[skip..]);
+    }
+    tlv.extend(value);
+    tlv
+}
+
+/// Decode the contents of an OBJECT IDENTIFIER into dotted form.
+pub fn oid_to_string(value: &[u8]) -> Result<String> {
+    if value.is_empty() {
+        bail!("empty OID");
+    }
+
+    let mut arcs: Vec<u64> = Vec::new();
+    let mut arc: u64 = 0;
+    for (i, &byte) in value.iter().enumerate() {
+        arc = arc
+            .checked_mul(128)
+            .ok_or_else(|| eyre!("OID arc is too large"))?
+            | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        } else if i == value.len() - 1 {
+            bail!("truncated OID");
+        }
+    }
+
+    Ok(arcs
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+/// A parsed `Extension ::= SEQUENCE { extnID, critical, extnValue }`.
+#[derive(Debug, Clone)]
+pub struct Extension<'a> {
+    pub oid: String,
+    pub critical: bool,
+    /// The contents of the `extnValue` OCTET STRING.
+    pub value: &'a [u8],
+}
+
+/// Parse the contents of an `Extensions ::= SEQUENCE OF Extension`.
+pub fn parse_extensions(value: &[u8]) -> Result<Vec<Extension<'_>>> {
+    let mut extensions = Vec::new();
+    for ext in Reader::new(value) {
+        let ext = ext?;
+        let mut fields = ext.reader();
+        let oid = fields.read()?.oid()?;
+        let critical = fields
+            .read_optional(tag::BOOLEAN)?
+            .is_some_and(|b| b.value.first().is_some_and(|&v| v != 0));
+        let value = fields.read_tag(tag::OCTET_STRING)?.value;
+        extensions.push(Extension {
+            oid,
+            critical,
+            value,
+        });
+    }
+    Ok(extensions)
+}
+
+/// The `tbsCertificate` of a DER encoded certificate.
+pub fn tbs_certificate(cert: &[u8]) -> Result<Tlv<'_>> {
+    let (cert, _) = read_tlv(cert)?;
+    cert.reader().read_tag(tag::SEQUENCE)
+}
+
+/// The `signatureAlgorithm` of a DER encoded certificate or CSR.
+pub fn signature_algorithm(signed: &[u8]) -> Result<Tlv<'_>> {
+    let (signed, _) = read_tlv(signed)?;
+    let mut fields = signed.reader();
+    fields.read_tag(tag::SEQUENCE)?; // tbsCertificate or certificationRequestInfo
+    fields.read_tag(tag::SEQUENCE)
+}
+
+/// The issuer and subject `Name`s of a DER encoded certificate.
+pub fn cert_names(cert: &[u8]) -> Result<(Tlv<'_>, Tlv<'_>)> {
+    let mut fields = tbs_certificate(cert)?.reader();
+    fields.read_optional(tag::context(0))?; // version
+    fields.read_tag(tag::INTEGER)?; // serialNumber
+    fields.read_tag(tag::SEQUENCE)?; // signature
+    let issuer = fields.read_tag(tag::SEQUENCE)?;
+    fields.read_tag(tag::SEQUENCE)?; // validity
+    let subject = fields.read_tag(tag::SEQUENCE)?;
+    Ok((issuer, subject))
+}
+
+/// The `subjectPublicKeyInfo` of a DER encoded certificate.
+pub fn cert_spki(cert: &[u8]) -> Result<Tlv<'_>> {
+    let mut fields = tbs_certificate(cert)?.reader();
+    fields.read_optional(tag::context(0))?; // version
+    fields.read_tag(tag::INTEGER)?; // serialNumber
+    for _ in 0..4 {
+        // signature, issuer, validity and subject
+        fields.read_tag(tag::SEQUENCE)?;
+    }
+    fields.read_tag(tag::SEQUENCE)
+}
+
+/// The subject `Name` of a DER encoded CSR.
+pub fn csr_subject(csr: &[u8]) -> Result<Tlv<'_>> {
+    let (csr, _) = read_tlv(csr)?;
+    let info = csr.reader().read_tag(tag::SEQUENCE)?;
+    let mut fields = info.reader();
+    fields.read_tag(tag::INTEGER)?; // version
+    fields.read_tag(tag::SEQUENCE)
+}
+
+/// The `subjectPKInfo` of a DER encoded CSR.
+pub fn csr_spki(csr: &[u8]) -> Result<Tlv<'_>> {
+    let (csr, _) = read_tlv(csr)?;
+    let info = csr.reader().read_tag(tag::SEQUENCE)?;
+    let mut fields = info.reader();
+    fields.read_tag(tag::INTEGER)?; // version
+    fields.read_tag(tag::SEQUENCE)?; // subject
+    fields.read_tag(tag::SEQUENCE)
+}
+
+/// The extensions of a DER encoded certificate.
+pub fn cert_extensions(cert: &[u8]) -> Result<Vec<Extension<'_>>> {
+    for field in tbs_certificate(cert)?.reader() {
+        let field = field?;
+        if field.tag == tag::context(3) {
+            let extensions = field.reader().read_tag(tag::SEQUENCE)?;
+            return parse_extensions(extensions.value);
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// The caIssuers URIs of a DER encoded certificate's authorityInfoAccess
+/// extension: where its issuer's cert can be downloaded from.
+pub fn ca_issuers(cert: &[u8]) -> Result<Vec<String>> {
+    access_locations(cert, oid::CA_ISSUERS)
+}
+
+/// The OCSP responder URIs of a DER encoded certificate's
+/// authorityInfoAccess extension.
+pub fn ocsp_responders(cert: &[u8]) -> Result<Vec<String>> {
+    access_locations(cert, oid::OCSP)
+}
+
+/// The URIs of the access descriptions with `access_method` in a DER encoded
+/// certificate's authorityInfoAccess extension.
+fn access_locations(cert: &[u8], access_method: &str) -> Result<Vec<String>> {
+    let Some(aia) = cert_extensions(cert)?
+        .into_iter()
+        .find(|ext| ext.oid == oid::AUTHORITY_INFO_ACCESS)
+    else {
+        return Ok(Vec::new());
+    };
+
+    let (descriptions, _) = read_tlv(aia.value)?;
+    let mut uris = Vec::new();
+    for description in descriptions.reader() {
+        let mut description = description?.reader();
+        let method = description.read()?.oid()?;
+        // uniformResourceIdentifier [6] IA5String
+        let location = description.read()?;
+        if method == access_method && location.tag == tag::context_primitive(6) {
+            uris.push(String::from_utf8_lossy(location.value).into_owned());
+        }
+    }
+    Ok(uris)
+}
+
+/// The URIs of a DER encoded certificate's cRLDistributionPoints extension:
+/// where the CRLs that would list it can be downloaded from. Distribution
+/// points named relative to the issuer are left out.
+pub fn crl_distribution_points(cert: &[u8]) -> Result<Vec<String>> {
+    let Some(cdp) = cert_extensions(cert)?
+        .into_iter()
+        .find(|ext| ext.oid == oid::CRL_DISTRIBUTION_POINTS)
+    else {
+        return Ok(Vec::new());
+    };
+
+    let (points, _) = read_tlv(cdp.value)?;
+    let mut uris = Vec::new();
+    for point in points.reader() {
+        // distributionPoint [0] DistributionPointName OPTIONAL
+        let Some(name) = point?.reader().read_optional(tag::context(0))? else {
+            continue;
+        };
+        // fullName [0] IMPLICIT GeneralNames
+        let Some(full_name) = name.reader().read_optional(tag::context(0))? else {
+            continue;
+        };
+        for general_name in full_name.reader() {
+            let general_name = general_name?;
+            // uniformResourceIdentifier [6] IA5String
+            if general_name.tag == tag::context_primitive(6) {
+                uris.push(String::from_utf8_lossy(general_name.value).into_owned());
+            }
+        }
+    }
+    Ok(uris)
+}
+
+/// The policy OIDs in the contents of a certificatePolicies extension
+/// (`SEQUENCE OF PolicyInformation`), without their qualifiers.
+pub fn certificate_policies(value: &[u8]) -> Result<Vec<String>> {
+    let (policies, _) = read_tlv(value)?;
+    policies
+        .reader()
+        .map(|policy| policy?.reader().read()?.oid())
+        .collect()
+}
+
+/// The certificates and CRLs of a DER encoded PKCS#7 / CMS `ContentInfo`
+/// holding `SignedData` (RFC 5652), each as its whole DER encoding.
+pub fn pkcs7_signed_data(der: &[u8]) -> Result<(Vec<&[u8]>, Vec<&[u8]>)> {
+    let (content_info, _) = read_tlv(der)?;
+    let mut content_info = content_info.reader();
+    let content_type = content_info.read()?.oid()?;
+    if content_type != oid::SIGNED_DATA {
+        bail!("expected PKCS#7 signedData, found content type {content_type}");
+    }
+
+    let signed_data = content_info
+        .read_tag(tag::context(0))?
+        .reader()
+        .read_tag(tag::SEQUENCE)?;
+    let mut fields = signed_data.reader();
+    fields.read_tag(tag::INTEGER)?; // version
+    fields.read_tag(tag::SET)?; // digestAlgorithms
+    fields.read_tag(tag::SEQUENCE)?; // encapContentInfo
+
+    // certificates [0] IMPLICIT SET OF, crls [1] IMPLICIT SET OF
+    let mut read_set = |n| -> Result<Vec<&[u8]>> {
+        match fields.read_optional(tag::context(n))? {
+            Some(set) => set.reader().map(|tlv| Ok(tlv?.raw)).collect(),
+            None => Ok(Vec::new()),
+        }
+    };
+    let certs = read_set(0)?;
+    let crls = read_set(1)?;
+    Ok((certs, crls))
+}
+
+/// The fields of a DER encoded CRL (RFC 5280 section 5.1) that are shown,
+/// and the ones needed to check its signature.
+#[derive(Debug, Clone)]
+pub struct CrlFields<'a> {
+    /// The whole `tbsCertList`, which the signature is over.
+    pub tbs: Tlv<'a>,
+    pub issuer: Tlv<'a>,
+    pub this_update: Tlv<'a>,
+    pub next_update: Option<Tlv<'a>>,
+    pub revoked: Vec<RevokedEntry<'a>>,
+    pub signature_algorithm: String,
+    /// The signature, without the BIT STRING's unused bits byte.
+    pub signature: &'a [u8],
+}
+
+/// A `revokedCertificates` entry of a CRL.
+#[derive(Debug, Clone)]
+pub struct RevokedEntry<'a> {
+    pub serial: Tlv<'a>,
+    pub date: Tlv<'a>,
+    /// The `CRLReason` code of its reasonCode extension, if it has one.
+    pub reason: Option<u8>,
+}
+
+pub fn crl_fields(crl: &[u8]) -> Result<CrlFields<'_>> {
+    let (crl, _) = read_tlv(crl)?;
+    let mut crl = crl.reader();
+    let tbs = crl.read_tag(tag::SEQUENCE)?;
+    let signature_algorithm = crl.read_tag(tag::SEQUENCE)?.reader().read()?.oid()?;
+    let signature = match crl.read_tag(tag::BIT_STRING)?.value {
+        [0, signature @ ..] => signature,
+        _ => bail!("the CRL's signature isn't a whole number of bytes"),
+    };
+
+    let mut fields = tbs.reader();
+    fields.read_optional(tag::INTEGER)?; // version
+    fields.read_tag(tag::SEQUENCE)?; // signature
+    let issuer = fields.read_tag(tag::SEQUENCE)?;
+    let this_update = fields.read()?;
+    let next_update = match fields.peek_tag() {
+        Some(tag::UTC_TIME | tag::GENERALIZED_TIME) => Some(fields.read()?),
+        _ => None,
+    };
+
+    let mut revoked = Vec::new();
+    if let Some(entries) = fields.read_optional(tag::SEQUENCE)? {
+        for entry in entries.reader() {
+            let mut entry = entry?.reader();
+            let serial = entry.read_tag(tag::INTEGER)?;
+            let date = entry.read()?;
+            let mut reason = None;
+            if let Some(extensions) = entry.read_optional(tag::SEQUENCE)? {
+                for extension in parse_extensions(extensions.value)? {
+                    if extension.oid == oid::CRL_REASON {
+                        let (code, _) = read_tlv(extension.value)?;
+                        if code.tag == tag::ENUMERATED {
+                            reason = code.value.last().copied();
+                        }
+                    }
+                }
+            }
+            revoked.push(RevokedEntry {
+                serial,
+                date,
+                reason,
+            });
+        }
+    }
+
+    Ok(CrlFields {
+        tbs,
+        issuer,
+        this_update,
+        next_update,
+        revoked,
+        signature_algorithm,
+        signature,
+    })
+}
+
+/// A parsed `Attribute ::= SEQUENCE { type, values SET OF ANY }`.
+#[derive(Debug, Clone)]
+pub struct Attribute<'a> {
+    pub oid: String,
+    /// The `values` SET.
+    pub values: Tlv<'a>,
+}
+
+/// The attributes of a DER encoded CSR, e.g. its `challengePassword`.
+pub fn csr_attributes(csr: &[u8]) -> Result<Vec<Attribute<'_>>> {
+    let (csr, _) = read_tlv(csr)?;
+    let info = csr.reader().read_tag(tag::SEQUENCE)?;
+    let mut fields = info.reader();
+    fields.read_tag(tag::INTEGER)?; // version
+    fields.read_tag(tag::SEQUENCE)?; // subject
+    fields.read_tag(tag::SEQUENCE)?; // subjectPKInfo
+
+    let Some(attributes) = fields.read_optional(tag::context(0))? else {
+        return Ok(Vec::new());
+    };
+
+    attributes
+        .reader()
+        .map(|attribute| {
+            let mut attribute = attribute?.reader();
+            Ok(Attribute {
+                oid: attribute.read()?.oid()?,
+                values: attribute.read_tag(tag::SET)?,
+            })
+        })
+        .collect()
+}
+
+/// The extensions requested in a DER encoded CSR's `extensionRequest`
+/// attribute.
+pub fn csr_extensions(csr: &[u8]) -> Result<Vec<Extension<'_>>> {
+    for attribute in csr_attributes(csr)? {
+        if attribute.oid == oid::EXTENSION_REQUEST {
+            let extensions = attribute.values.reader().read_tag(tag::SEQUENCE)?;
+            return parse_extensions(extensions.value);
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// The name of a well-known extension, e.g. `subjectAltName`.
+pub fn extension_name(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "2.5.29.14" => "subjectKeyIdentifier",
+        "2.5.29.15" => "keyUsage",
+        "2.5.29.17" => "subjectAltName",
+        "2.5.29.18" => "issuerAltName",
+        "2.5.29.19" => "basicConstraints",
+        "2.5.29.30" => "nameConstraints",
+        "2.5.29.31" => "cRLDistributionPoints",
+        "2.5.29.32" => "certificatePolicies",
+        "2.5.29.35" => "authorityKeyIdentifier",
+        "2.5.29.37" => "extendedKeyUsage",
+        "1.3.6.1.5.5.7.1.1" => "authorityInfoAccess",
+        "1.3.6.1.5.5.7.1.24" => "tlsFeature",
+        "1.3.6.1.4.1.11129.2.4.2" => "signedCertificateTimestampList",
+        "1.3.6.1.4.1.311.20.2" => "certificateTemplateName",
+        "1.3.6.1.4.1.311.21.7" => "certificateTemplate",
+        _ => return None,
+    })
+}
+
+/// The short name of a distinguished name attribute type, e.g. `CN`.
+pub fn attribute_short_name(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "2.5.4.3" => "CN",
+        "2.5.4.4" => "SN",
+        "2.5.4.5" => "serialNumber",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "ST",
+        "2.5.4.9" => "street",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "2.5.4.12" => "title",
+        "2.5.4.13" => "description",
+        "2.5.4.15" => "businessCategory",
+        "2.5.4.17" => "postalCode",
+        "2.5.4.41" => "name",
+        "2.5.4.42" => "GN",
+        "2.5.4.43" => "initials",
+        "2.5.4.44" => "generationQualifier",
+        "2.5.4.46" => "dnQualifier",
+        "2.5.4.65" => "pseudonym",
+        "2.5.4.97" => "organizationIdentifier",
+        "1.3.6.1.4.1.311.60.2.1.1" => "jurisdictionL",
+        "1.3.6.1.4.1.311.60.2.1.2" => "jurisdictionST",
+        "1.3.6.1.4.1.311.60.2.1.3" => "jurisdictionC",
+        "0.9.2342.19200300.100.1.1" => "UID",
+        "0.9.2342.19200300.100.1.25" => "DC",
+        "1.2.840.113549.1.9.1" => "emailAddress",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oids() {
+        // 2.5.29.17, subjectAltName
+        assert_eq!(oid_to_string(&[0x55, 0x1d, 0x11]).unwrap(), "2.5.29.17");
+        // 1.2.840.113549.1.1.11, sha256WithRSAEncryption
+        assert_eq!(
+            oid_to_string(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b]).unwrap(),
+            "1.2.840.113549.1.1.11"
+        );
+        assert!(oid_to_string(&[0x2a, 0x86]).is_err());
+    }
+
+    #[test]
+    fn lengths() {
+        let (tlv, len) = read_tlv(&[0x04, 0x02, 0xab, 0xcd, 0xff]).unwrap();
+        assert_eq!((tlv.tag, tlv.value, len), (0x04, &[0xab, 0xcd][..], 4));
+
+        let mut long = vec![0x04, 0x81, 0x80];
+        long.extend([0u8; 0x80]);
+        let (tlv, len) = read_tlv(&long).unwrap();
+        assert_eq!((tlv.value.len(), len), (0x80, 0x83));
+
+        assert!(read_tlv(&[0x04, 0x80]).is_err());
+        assert!(read_tlv(&[0x04, 0x05, 0x00]).is_err());
+
+        for len in [0, 0x7f, 0x80, 0x1234] {
+            let value = vec![0xab; len];
+            let tlv = encode_tlv(tag::OCTET_STRING, &value);
+            let (read, total) = read_tlv(&tlv).unwrap();
+            assert_eq!((read.value, total), (&value[..], tlv.len()));
+        }
+    }
+
+    #[test]
+    fn aia_ca_issuers() {
+        let pem = include_bytes!("../test-data/certs/letsencrypt.org.pem");
+        let der = boring::x509::X509::from_pem(pem).unwrap().to_der().unwrap();
+        // the OCSP URI is left out
+        assert_eq!(ca_issuers(&der).unwrap(), ["http://e6.i.lencr.org/"]);
+        assert_eq!(ocsp_responders(&der).unwrap(), ["http://e6.o.lencr.org"]);
+
+        let pem = include_bytes!("../test-data/certs/rsa-self-signed.pem");
+        let der = boring::x509::X509::from_pem(pem).unwrap().to_der().unwrap();
+        assert!(ca_issuers(&der).unwrap().is_empty());
+        assert!(crl_distribution_points(&der).unwrap().is_empty());
+    }
+
+    #[test]
+    fn crl_distribution_point_uris() {
+        let pem = include_bytes!("../test-data/certs/cloudflare.com.pem");
+        let der = boring::x509::X509::from_pem(pem).unwrap().to_der().unwrap();
+        assert_eq!(
+            crl_distribution_points(&der).unwrap(),
+            ["http://c.pki.goog/we1/OuqGbJkzwhU.crl"]
+        );
+    }
+
+    #[test]
+    fn cert_sans() {
+        let pem = include_bytes!("../test-data/certs/other-sans.pem");
+        let der = boring::x509::X509::from_pem(pem).unwrap().to_der().unwrap();
+        let extensions = cert_extensions(&der).unwrap();
+        let san = extensions
+            .iter()
+            .find(|e| e.oid == oid::SUBJECT_ALT_NAME)
+            .unwrap();
+        assert!(!san.critical);
+
+        let (names, _) = read_tlv(san.value).unwrap();
+        let tags: Vec<u8> = names.reader().map(|name| name.unwrap().tag).collect();
+        assert_eq!(tags, [0x82, 0xa0, 0xa4, 0x88, 0xa5, 0xa0]);
+    }
+
+    #[test]
+    fn csr_attributes_and_extensions() {
+        let pem = include_bytes!("../test-data/csr/challenge-password.csr");
+        let der = boring::x509::X509Req::from_pem(pem)
+            .unwrap()
+            .to_der()
+            .unwrap();
+
+        let oids: Vec<String> = csr_attributes(&der)
+            .unwrap()
+            .into_iter()
+            .map(|attribute| attribute.oid)
+            .collect();
+        assert_eq!(
+            oids,
+            [
+                oid::CHALLENGE_PASSWORD,
+                "1.2.840.113549.1.9.2",
+                oid::EXTENSION_REQUEST
+            ]
+        );
+
+        let names: Vec<_> = csr_extensions(&der)
+            .unwrap()
+            .iter()
+            .map(|ext| extension_name(&ext.oid).unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "subjectAltName",
+                "keyUsage",
+                "extendedKeyUsage",
+                "basicConstraints"
+            ]
+        );
+    }
+
+    #[test]
+    fn names() {
+        let pem = include_bytes!("../test-data/certs/other-sans.pem");
+        let der = boring::x509::X509::from_pem(pem).unwrap().to_der().unwrap();
+        let (issuer, subject) = cert_names(&der).unwrap();
+        // self-signed
+        assert_eq!(issuer.raw, subject.raw);
+        assert_eq!(subject.tag, tag::SEQUENCE);
+    }
+}
@@ -0,0 +1,564 @@
+//! A small DNS client: A/AAAA lookups for `connect --dns/--doh`, the TLSA
+//! records of `connect --dane` and the CAA records of `pls caa`.
+//!
+//! Queries go to a nameserver over UDP (falling back to TCP when the answer
+//! is truncated) or to a DNS-over-HTTPS endpoint (RFC 8484).
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use url::Url;
+
+use crate::http;
+
+/// How long to wait for a nameserver to answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The UDP payload size advertised with EDNS(0), so big answers fit without
+/// falling back to TCP.
+const UDP_PAYLOAD_SIZE: u16 = 4096;
+
+/// The record types `pls` looks up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    A,
+    Aaaa,
+    Tlsa,
+    Caa,
+}
+
+impl RecordType {
+    fn code(self) -> u16 {
+        match self {
+            Self::A => 1,
+            Self::Aaaa => 28,
+            Self::Tlsa => 52,
+            Self::Caa => 257,
+        }
+    }
+}
+
+/// Where DNS queries are sent.
+#[derive(Debug, Clone, Default)]
+pub enum Resolver {
+    /// The system's resolver (`getaddrinfo`) for addresses, and the first
+    /// `nameserver` in `/etc/resolv.conf` for other records.
+    #[default]
+    System,
+    /// A nameserver, e.g. `1.1.1.1:53`.
+    Server(SocketAddr),
+    /// A DNS-over-HTTPS endpoint, e.g. `https://cloudflare-dns.com/dns-query`.
+    Doh(Url),
+}
+
+impl fmt::Display for Resolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::System => write!(f, "system"),
+            Self::Server(addr) => write!(f, "{addr}"),
+            Self::Doh(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+/// A record in an answer. Only the data of the types `pls` understands is
+/// decoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Record {
+    pub name: String,
+    pub ttl: u32,
+    #[serde(flatten)]
+    pub data: RecordData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "UPPERCASE")]
+pub enum RecordData {
+    A {
+        address: Ipv4Addr,
+    },
+    Aaaa {
+        address: Ipv6Addr,
+    },
+    Cname {
+        target: String,
+    },
+    /// A DANE TLSA record (RFC 6698).
+    Tlsa {
+        usage: u8,
+        selector: u8,
+        matching_type: u8,
+        /// Hex.
+        data: String,
+    },
+    /// A CAA record (RFC 8659).
+    Caa {
+        critical: bool,
+        tag: String,
+        value: String,
+    },
+    Other {
+        code: u16,
+        /// Hex.
+        data: String,
+    },
+}
+
+/// The answer to a query.
+#[derive(Debug, Clone, Default)]
+pub struct Answer {
+    /// Every record in the answer section, including CNAMEs followed.
+    pub records: Vec<Record>,
+    /// The resolver validated the answer with DNSSEC (the AD bit).
+    pub authenticated: bool,
+}
+
+impl Answer {
+    /// The smallest TTL of the records, how long the answer can be cached.
+    pub fn ttl(&self) -> Option<u32> {
+        self.records.iter().map(|record| record.ttl).min()
+    }
+}
+
+/// Which resolver looked a host up and how long its answer is good for.
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct Resolution {
+    pub resolver: String,
+    /// Unknown for the system resolver.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+}
+
+impl Resolver {
+    /// Look up `name`'s `rtype` records.
+    #[tracing::instrument(name = "dns", skip(self), fields(resolver = %self))]
+    pub async fn query(&self, name: &str, rtype: RecordType) -> Result<Answer> {
+        tracing::debug!("querying");
+        let answer = match self {
+            Self::System => query_server(system_nameserver()?, name, rtype).await,
+            Self::Server(addr) => query_server(*addr, name, rtype).await,
+            Self::Doh(url) => query_doh(url, name, rtype).await,
+        };
+        answer.with_context(|| format!("looking up {name} {rtype:?} via {self}"))
+    }
+
+    /// Resolve `host` to an address, preferring IPv4 like most clients do.
+    pub async fn lookup_host(&self, host: &str, port: u16) -> Result<(SocketAddr, Resolution)> {
+        if let Self::System = self {
+            let addr = tokio::net::lookup_host((host, port))
+                .await
+                .with_context(|| format!("resolving {host}:{port}"))?
+                .next()
+                .ok_or_else(|| eyre!("{host}:{port} resolved to no addresses"))?;
+            let resolution = Resolution {
+                resolver: self.to_string(),
+                ttl: None,
+            };
+            return Ok((addr, resolution));
+        }
+
+        for rtype in [RecordType::A, RecordType::Aaaa] {
+            let answer = self.query(host, rtype).await?;
+            let ip = answer.records.iter().find_map(|record| match record.data {
+                RecordData::A { address } => Some(IpAddr::V4(address)),
+                RecordData::Aaaa { address } => Some(IpAddr::V6(address)),
+                _ => None,
+            });
+            if let Some(ip) = ip {
+                let resolution = Resolution {
+                    resolver: self.to_string(),
+                    ttl: answer.ttl(),
+                };
+                return Ok((SocketAddr::new(ip, port), resolution));
+            }
+        }
+        bail!("{host} has no A or AAAA records (via {self})")
+    }
+}
+
+/// The first `nameserver` in `/etc/resolv.conf`.
+fn system_nameserver() -> Result<SocketAddr> {
+    let conf = std::fs::read_to_string("/etc/resolv.conf")
+        .context("reading /etc/resolv.conf, pass --dns or --doh instead")?;
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        // drop the scope of link-local nameservers, they're rare
+        .filter_map(|ip| ip.trim().split('%').next()?.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53))
+        .next()
+        .ok_or_else(|| eyre!("no nameserver in /etc/resolv.conf, pass --dns or --doh"))
+}
+
+async fn query_server(addr: SocketAddr, name: &str, rtype: RecordType) -> Result<Answer> {
+    let id = u16::from_be_bytes(random_bytes());
+    let query = build_query(id, name, rtype)?;
+
+    let bind = if addr.is_ipv6() {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(addr).await?;
+    socket.send(&query).await?;
+
+    let mut buf = vec![0; UDP_PAYLOAD_SIZE as usize];
+    let len = loop {
+        let len = tokio::time::timeout(TIMEOUT, socket.recv(&mut buf))
+            .await
+            .map_err(|_| eyre!("no answer from {addr} after {}s", TIMEOUT.as_secs()))??;
+        // ignore stray datagrams
+        if buf[..len].starts_with(&id.to_be_bytes()) {
+            break len;
+        }
+    };
+    let response = &buf[..len];
+
+    if response.len() > 2 && response[2] & 0x02 != 0 {
+        tracing::debug!("answer from {addr} is truncated, retrying over TCP");
+        return query_tcp(addr, &query).await;
+    }
+    parse_response(response, id)
+}
+
+async fn query_tcp(addr: SocketAddr, query: &[u8]) -> Result<Answer> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(addr).await?;
+        let mut message = (query.len() as u16).to_be_bytes().to_vec();
+        message.extend_from_slice(query);
+        stream.write_all(&message).await?;
+
+        let len = stream.read_u16().await?;
+        let mut response = vec![0; len as usize];
+        stream.read_exact(&mut response).await?;
+        Ok::<_, std::io::Error>(response)
+    };
+    let response = tokio::time::timeout(TIMEOUT, exchange)
+        .await
+        .map_err(|_| eyre!("no answer from {addr} after {}s", TIMEOUT.as_secs()))??;
+    parse_response(&response, u16::from_be_bytes([query[0], query[1]]))
+}
+
+async fn query_doh(endpoint: &Url, name: &str, rtype: RecordType) -> Result<Answer> {
+    // RFC 8484 asks for an ID of 0, so responses can be cached
+    let query = build_query(0, name, rtype)?;
+    let mut url = endpoint.clone();
+    url.query_pairs_mut().append_pair("dns", &base64url(&query));
+
+    let response = http::get_accepting(&url, "application/dns-message")
+        .await?
+        .error_for_status(&url)?;
+    parse_response(&response.body, 0)
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    boring::rand::rand_bytes(&mut bytes).expect("generating random bytes");
+    bytes
+}
+
+/// Unpadded base64url, as DoH GET requests carry the query in.
+fn base64url(data: &[u8]) -> String {
+    boring::base64::encode_block(data)
+        .trim_end_matches('=')
+        .replace('+', "-")
+        .replace('/', "_")
+}
+
+/// A recursive query for `name`, with EDNS(0) for bigger answers over UDP
+/// and the AD bit set to ask for the DNSSEC status.
+fn build_query(id: u16, name: &str, rtype: RecordType) -> Result<Vec<u8>> {
+    let mut query = Vec::with_capacity(64);
+    query.extend_from_slice(&id.to_be_bytes());
+    // RD | AD
+    query.extend_from_slice(&0x0120u16.to_be_bytes());
+    // one question, and the OPT record
+    query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 1]);
+
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!("invalid DNS name {name:?}");
+        }
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&rtype.code().to_be_bytes());
+    query.extend_from_slice(&1u16.to_be_bytes()); // IN
+
+    // OPT: root name, type 41, UDP payload size as the class, no options
+    query.push(0);
+    query.extend_from_slice(&41u16.to_be_bytes());
+    query.extend_from_slice(&UDP_PAYLOAD_SIZE.to_be_bytes());
+    query.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    Ok(query)
+}
+
+fn parse_response(message: &[u8], id: u16) -> Result<Answer> {
+    let mut reader = MessageReader { message, pos: 0 };
+    if reader.u16()? != id {
+        bail!("answer is for another query");
+    }
+    let flags = reader.u16()?;
+    let questions = reader.u16()?;
+    let answers = reader.u16()?;
+    reader.u16()?; // authority
+    reader.u16()?; // additional
+
+    match flags & 0x000f {
+        // NXDOMAIN is an answer without records
+        0 | 3 => {}
+        2 => bail!("the resolver failed to answer (SERVFAIL)"),
+        5 => bail!("the resolver refused the query (REFUSED)"),
+        rcode => bail!("error response code {rcode}"),
+    }
+
+    for _ in 0..questions {
+        reader.name()?;
+        reader.take(4)?; // type, class
+    }
+
+    let mut records = Vec::with_capacity(answers as usize);
+    for _ in 0..answers {
+        let name = reader.name()?;
+        let code = reader.u16()?;
+        reader.u16()?; // class
+        let ttl = u32::from_be_bytes(reader.take(4)?.try_into().unwrap());
+        let len = reader.u16()? as usize;
+        let start = reader.pos;
+        let data = reader.take(len)?;
+
+        let data = match (code, data) {
+            (1, &[a, b, c, d]) => RecordData::A {
+                address: Ipv4Addr::new(a, b, c, d),
+            },
+            (28, data) if data.len() == 16 => RecordData::Aaaa {
+                address: Ipv6Addr::from(<[u8; 16]>::try_from(data).unwrap()),
+            },
+            (5, _) => RecordData::Cname {
+                target: MessageReader {
+                    message,
+                    pos: start,
+                }
+                .name()?,
+            },
+            (52, &[usage, selector, matching_type, ref data @ ..]) => RecordData::Tlsa {
+                usage,
+                selector,
+                matching_type,
+                data: hex::encode(data),
+            },
+            (257, &[flags, tag_len, ref rest @ ..]) if rest.len() >= tag_len as usize => {
+                let (tag, value) = rest.split_at(tag_len as usize);
+                RecordData::Caa {
+                    critical: flags & 0x80 != 0,
+                    tag: String::from_utf8_lossy(tag).into_owned(),
+                    value: String::from_utf8_lossy(value).into_owned(),
+                }
+            }
+            (code, data) => RecordData::Other {
+                code,
+                data: hex::encode(data),
+            },
+        };
+        records.push(Record { name, ttl, data });
+    }
+
+    Ok(Answer {
+        records,
+        authenticated: flags & 0x0020 != 0,
+    })
+}
+
+struct MessageReader<'a> {
+    message: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> MessageReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let data = self
+            .message
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| eyre!("DNS message is truncated"))?;
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let data = self.take(2)?;
+        Ok(u16::from_be_bytes([data[0], data[1]]))
+    }
+
+    /// A possibly compressed name (RFC 1035 section 4.1.4).
+    fn name(&mut self) -> Result<String> {
+        let mut labels: Vec<String> = Vec::new();
+        let mut pos = self.pos;
+        let mut jumped = false;
+        // every pointer has to go backwards, so this many jumps is a loop
+        for _ in 0..self.message.len() {
+            let len = *self
+                .message
+                .get(pos)
+                .ok_or_else(|| eyre!("DNS message is truncated"))?;
+            match len {
+                0 => {
+                    if !jumped {
+                        self.pos = pos + 1;
+                    }
+                    return Ok(labels.join("."));
+                }
+                len if len & 0xc0 == 0xc0 => {
+                    let low = *self
+                        .message
+                        .get(pos + 1)
+                        .ok_or_else(|| eyre!("DNS message is truncated"))?;
+                    let target = (usize::from(len & 0x3f) << 8) | usize::from(low);
+                    if target >= pos {
+                        bail!("invalid DNS name compression pointer");
+                    }
+                    if !jumped {
+                        self.pos = pos + 2;
+                        jumped = true;
+                    }
+                    pos = target;
+                }
+                len => {
+                    let label = self
+                        .message
+                        .get(pos + 1..pos + 1 + len as usize)
+                        .ok_or_else(|| eyre!("DNS message is truncated"))?;
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                    pos += 1 + len as usize;
+                }
+            }
+        }
+        bail!("invalid DNS name")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query() {
+        let query = build_query(0xabcd, "example.com.", RecordType::Aaaa).unwrap();
+        let expected = [
+            &[0xab, 0xcd, 0x01, 0x20, 0, 1, 0, 0, 0, 0, 0, 1][..],
+            b"\x07example\x03com\x00",
+            &[0, 28, 0, 1],
+            &[0, 0, 41, 0x10, 0, 0, 0, 0, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(query, expected);
+
+        assert!(build_query(0, "a..b", RecordType::A).is_err());
+        assert_eq!(base64url(&[0xfb, 0xff]), "-_8");
+    }
+
+    #[test]
+    fn response() {
+        let mut message = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 3, 0, 0, 0, 0];
+        // question: www.example.com A
+        message.extend_from_slice(b"\x03www\x07example\x03com\x00\x00\x01\x00\x01");
+        // www.example.com CNAME example.com, both compressed
+        message.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 0xc0, 16]);
+        // example.com A 192.0.2.1
+        message.extend_from_slice(&[0xc0, 16, 0, 1, 0, 1, 0, 0, 1, 44, 0, 4, 192, 0, 2, 1]);
+        // example.com TXT, uncompressed
+        message.extend_from_slice(b"\x07example\x03com\x00");
+        message.extend_from_slice(&[0, 16, 0, 1, 0, 0, 0, 30, 0, 3, 2, b'h', b'i']);
+
+        let answer = parse_response(&message, 0x1234).unwrap();
+        assert_eq!(answer.ttl(), Some(30));
+        let data: Vec<_> = answer
+            .records
+            .iter()
+            .map(|record| (record.name.as_str(), &record.data))
+            .collect();
+        assert_eq!(
+            data,
+            [
+                (
+                    "www.example.com",
+                    &RecordData::Cname {
+                        target: "example.com".to_string()
+                    }
+                ),
+                (
+                    "example.com",
+                    &RecordData::A {
+                        address: Ipv4Addr::new(192, 0, 2, 1)
+                    }
+                ),
+                (
+                    "example.com",
+                    &RecordData::Other {
+                        code: 16,
+                        data: "026869".to_string()
+                    }
+                ),
+            ]
+        );
+
+        assert!(parse_response(&message, 0x4321).is_err());
+        // SERVFAIL
+        message[3] = 0x82;
+        assert!(parse_response(&message, 0x1234).is_err());
+    }
+
+    #[test]
+    fn tlsa() {
+        // AD set: the resolver validated the answer
+        let mut message = vec![0, 0, 0x81, 0xa0, 0, 0, 0, 1, 0, 0, 0, 0];
+        message.extend_from_slice(b"\x04_443\x04_tcp\x07example\x03com\x00");
+        message.extend_from_slice(&[0, 52, 0, 1, 0, 0, 14, 16, 0, 5, 3, 1, 1, 0xab, 0xcd]);
+
+        let answer = parse_response(&message, 0).unwrap();
+        assert!(answer.authenticated);
+        assert_eq!(answer.records[0].name, "_443._tcp.example.com");
+        assert_eq!(
+            answer.records[0].data,
+            RecordData::Tlsa {
+                usage: 3,
+                selector: 1,
+                matching_type: 1,
+                data: "abcd".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn caa() {
+        let mut message = vec![0, 0, 0x81, 0x80, 0, 0, 0, 1, 0, 0, 0, 0];
+        message
+            .extend_from_slice(b"\x07example\x03com\x00\x01\x01\x00\x01\x00\x00\x00\x3c\x00\x16");
+        message.extend_from_slice(b"\x80\x05issueletsencrypt.org");
+
+        let answer = parse_response(&message, 0).unwrap();
+        assert!(!answer.authenticated);
+        assert_eq!(
+            answer.records[0].data,
+            RecordData::Caa {
+                critical: true,
+                tag: "issue".to_string(),
+                value: "letsencrypt.org".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn compression_loops() {
+        let mut message = vec![0, 0, 0x81, 0x80, 0, 0, 0, 1, 0, 0, 0, 0];
+        // a name pointing at itself
+        message.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0]);
+        assert!(parse_response(&message, 0).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Plain-language notes on certificate fields for `--explain`, for people
+//! learning PKI. Fields are looked up by the label they're shown under in the
+//! text output, extensions and extendedKeyUsage purposes by OID.
+
+use crate::policy::ValidationLevel;
+
+/// A note on a field of the text output, by its label, e.g. `serial`.
+pub fn field(label: &str) -> Option<&'static str> {
+    Some(match label {
+        "subject" => "who the cert was issued to. Browsers ignore it for TLS and only look at the SANs",
+        "serial" => "unique among the certs its issuer signed; CRLs and OCSP identify the cert by it",
+        "not before" => "the cert isn't valid before this time",
+        "not after" => "the cert expires at this time and must be renewed before it",
+        "lifetime" => "how long the cert is valid for. Publicly trusted TLS certs may be valid for at most 398 days",
+        "public key" => "the key the cert vouches for; the server proves it has the matching private key in the handshake",
+        "issuer" => "the CA that signed the cert. Its subject must match this name",
+        "signature" => "the issuer's signature over the cert, made with the issuer's private key",
+        "fingerprints" => "hashes of the whole cert, to identify it. They aren't part of the cert itself",
+        "spki" => "the SHA-256 of the public key (SubjectPublicKeyInfo); stays the same when a cert is renewed with the same key",
+        "pin" => "the SPKI hash in base64, the format key pinning configs use",
+        "root" => "a self-signed CA that clients trust directly, from their trust store",
+        "ca" => "basicConstraints allows the cert to sign other certs",
+        "self-signed" => "signed with its own key, so nothing vouches for it unless it's in the trust store",
+        _ => return None,
+    })
+}
+
+/// A note on a leaf's validation level badge.
+pub fn validation(level: ValidationLevel) -> &'static str {
+    match level {
+        ValidationLevel::Ev => "extended validation: the CA checked the legal identity of the organization in depth. Browsers no longer show it differently",
+        ValidationLevel::Ov => "organization validation: the CA checked the organization in the subject; a `?` means it's a guess from the subject",
+        ValidationLevel::Iv => "individual validation: the CA checked the person in the subject",
+        ValidationLevel::Dv => "domain validation: the CA only checked control of the names; a `?` means it's a guess from the subject",
+    }
+}
+
+/// A note on an extension, by OID.
+pub fn extension(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "2.5.29.14" => "subjectKeyIdentifier: identifies the cert's key; certs it issued carry it as their AKI",
+        "2.5.29.15" => "keyUsage: the cryptographic operations the key may be used for",
+        "2.5.29.17" => "subjectAltName: the names the cert is valid for. TLS clients match the hostname against these",
+        "2.5.29.18" => "issuerAltName: alternative names for the issuer",
+        "2.5.29.19" => "basicConstraints: whether the cert is a CA, and how many CAs may follow it in a chain",
+        "2.5.29.30" => "nameConstraints: limits the names a CA may issue certs for",
+        "2.5.29.31" => "cRLDistributionPoints: where to download the issuer's list of revoked certs",
+        "2.5.29.32" => "certificatePolicies: the policies the cert was issued under, e.g. domain or extended validation",
+        "2.5.29.35" => "authorityKeyIdentifier: identifies the issuer's key; should match the issuer cert's SKI",
+        "2.5.29.37" => "extendedKeyUsage: the purposes the cert may be used for, e.g. TLS servers",
+        "1.3.6.1.5.5.7.1.1" => "authorityInfoAccess: where to download the issuer's cert and check revocation with OCSP",
+        "1.3.6.1.5.5.7.1.24" => "tlsFeature: the server must staple an OCSP response to the handshake",
+        "1.3.6.1.4.1.11129.2.4.2" => "signedCertificateTimestampList: proof the cert was logged to Certificate Transparency logs",
+        "1.3.6.1.4.1.311.20.2" => "certificateTemplateName: the Active Directory CS template the cert was issued from",
+        "1.3.6.1.4.1.311.21.7" => "certificateTemplate: the Active Directory CS template the cert was issued from",
+        _ => return None,
+    })
+}
+
+/// A note on an extendedKeyUsage purpose, by OID.
+pub fn purpose(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "1.3.6.1.5.5.7.3.1" => "serverAuth: usable for TLS servers",
+        "1.3.6.1.5.5.7.3.2" => "clientAuth: usable for TLS clients, e.g. mTLS",
+        "1.3.6.1.5.5.7.3.3" => "codeSigning: usable to sign software",
+        "1.3.6.1.5.5.7.3.4" => "emailProtection: usable for S/MIME email",
+        "1.3.6.1.5.5.7.3.8" => "timeStamping: usable by a timestamping authority",
+        "1.3.6.1.5.5.7.3.9" => "OCSPSigning: usable to sign OCSP responses for its issuer",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der;
+
+    #[test]
+    fn named_extensions_are_explained() {
+        for oid in [
+            "2.5.29.14",
+            "2.5.29.15",
+            "2.5.29.17",
+            "2.5.29.19",
+            "2.5.29.35",
+            "2.5.29.37",
+            der::oid::AUTHORITY_INFO_ACCESS,
+        ] {
+            let name = der::extension_name(oid).unwrap();
+            let note = extension(oid).unwrap_or_else(|| panic!("no note for {name}"));
+            assert!(note.starts_with(&format!("{name}: ")), "{note}");
+        }
+        assert_eq!(extension("1.2.3.4"), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! `parse --field`: the values at paths into the JSON output, one per line,
+//! e.g. `validity.not_after`, so scripts can skip `jq` for simple lookups.
+//!
+//! A path is the keys from an entity of the `--ndjson` output down to the
+//! value, separated by dots. Lists are indexed by number, `sans.dns.0` or
+//! `sans.dns[0]`.
+
+use std::fmt;
+
+use color_eyre::eyre::{bail, Result};
+use serde_json::Value;
+
+/// A path into the JSON of an entity, e.g. `fingerprints.sha256`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPath(Vec<String>);
+
+impl FieldPath {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        // `.a.b` as jq writes it, and `a[0]` for `a.0`
+        let normalized = path
+            .trim()
+            .trim_start_matches('.')
+            .replace('[', ".")
+            .replace(']', "");
+        let keys: Vec<String> = normalized.split('.').map(str::to_string).collect();
+        if keys.iter().any(String::is_empty) {
+            return Err(format!(
+                "{path:?} isn't a path like `validity.not_after` or `subject.sans.dns.0`"
+            ));
+        }
+        Ok(Self(keys))
+    }
+
+    /// The value at the path in `value`, if there's one.
+    pub fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.0.iter().try_fold(value, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join("."))
+    }
+}
+
+/// `value` as lines: strings without quotes, every item of a list on its own
+/// line, objects as compact JSON and `null` as an empty line.
+fn lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::Null => vec![String::new()],
+        Value::String(string) => vec![string.clone()],
+        Value::Array(items) => items.iter().flat_map(lines).collect(),
+        _ => vec![value.to_string()],
+    }
+}
+
+/// The lines of `paths` for each of `entities`, in order. Entities with none
+/// of the paths are left out, e.g. the keys in a bundle when the paths are
+/// cert fields, and a path one of them doesn't have is an empty line.
+///
+/// Fails when a path isn't in any of them, it's likely misspelled.
+pub fn extract(entities: &[Value], paths: &[FieldPath]) -> Result<Vec<String>> {
+    if let Some(path) = paths
+        .iter()
+        .find(|path| entities.iter().all(|entity| path.get(entity).is_none()))
+    {
+        bail!("nothing has a `{path}` field, `pls schema` lists them");
+    }
+
+    let mut output = Vec::new();
+    for entity in entities {
+        let values: Vec<Option<&Value>> = paths.iter().map(|path| path.get(entity)).collect();
+        if values.iter().all(Option::is_none) {
+            continue;
+        }
+        for value in values {
+            output.extend(value.map_or_else(|| vec![String::new()], lines));
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn path(path: &str) -> FieldPath {
+        FieldPath::parse(path).unwrap()
+    }
+
+    #[test]
+    fn extracts_fields() {
+        let cert = json!({
+            "kind": "cert",
+            "validity": { "not_after": "2026-01-01T00:00:00Z", "expires_in": 12 },
+            "subject": { "sans": { "dns": ["example.com", "www.example.com"] } },
+            "aki": null,
+        });
+        let key = json!({ "kind": "private_key", "bits": 256 });
+
+        assert_eq!(path(".subject.sans.dns[1]"), path("subject.sans.dns.1"));
+        assert_eq!(
+            path("subject.sans.dns[1]").to_string(),
+            "subject.sans.dns.1"
+        );
+        assert!(FieldPath::parse("validity..not_after").is_err());
+        assert!(FieldPath::parse("").is_err());
+
+        let entities = [cert, key];
+        assert_eq!(
+            extract(&entities, &[path("validity.not_after")]).unwrap(),
+            ["2026-01-01T00:00:00Z"]
+        );
+        assert_eq!(
+            extract(&entities, &[path("subject.sans.dns"), path("aki")]).unwrap(),
+            ["example.com", "www.example.com", ""]
+        );
+        assert_eq!(
+            extract(&entities, &[path("kind"), path("bits")]).unwrap(),
+            ["cert", "", "private_key", "256"]
+        );
+        assert_eq!(
+            extract(&entities, &[path("subject.sans")]).unwrap(),
+            [r#"{"dns":["example.com","www.example.com"]}"#]
+        );
+        assert!(extract(&entities, &[path("validity.not_afterr")]).is_err());
+    }
+}
@@ -0,0 +1,824 @@
+//! Expressions on certs, shared by `find -e`, `parse --fail-if` and `pls
+//! explain-expr`: filters combined with `and`, `or`, `not` and parentheses,
+//! e.g. `san contains internal.corp and (expires < 60d or bits < 2048)`.
+//! `and` binds tighter than `or`; `&&`, `||` and `!` work too.
+//!
+//! A filter is a field, an operator and a value. Values with spaces,
+//! parentheses or operator characters in them need quotes.
+//!
+//! Text fields (`subject`, `issuer`, `san`, `serial`, `sha256`, `key`,
+//! `signature`) take `=`, `!=`, `contains` and `matches` (a regex), all
+//! case-insensitive. A field with many values, like `san`, matches when any
+//! of them does, and `!=` when none is equal.
+//!
+//! Time fields (`expires`, `issued`) and `bits` take `=`, `!=`, `<`, `<=`,
+//! `>` and `>=`. Times are dates, RFC 3339 timestamps or durations from now,
+//! negative for the past: `expires < 60d` are the certs expiring within 60
+//! days and `issued > -7d` the ones issued in the last week.
+//!
+//! Flags (`ca`, `self-signed`, `expired`) take `= true` or `= false`, or
+//! nothing at all for `= true`.
+
+use std::fmt;
+
+use jiff::{SignedDuration, Timestamp};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+use crate::{
+    commands::{parse_duration, parse_time},
+    x509::{NotAfter, SimpleCert, SimplePublicKeyKind},
+};
+
+/// A field of a cert that can be filtered on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Field {
+    Subject,
+    Issuer,
+    San,
+    Serial,
+    Sha256,
+    Key,
+    Signature,
+    Expires,
+    Issued,
+    Bits,
+    Ca,
+    SelfSigned,
+    Expired,
+}
+
+/// What values a field has, which decides the operators it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Time,
+    Number,
+    Flag,
+}
+
+impl Field {
+    const ALL: [Field; 13] = [
+        Field::Subject,
+        Field::Issuer,
+        Field::San,
+        Field::Serial,
+        Field::Sha256,
+        Field::Key,
+        Field::Signature,
+        Field::Expires,
+        Field::Issued,
+        Field::Bits,
+        Field::Ca,
+        Field::SelfSigned,
+        Field::Expired,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Subject => "subject",
+            Field::Issuer => "issuer",
+            Field::San => "san",
+            Field::Serial => "serial",
+            Field::Sha256 => "sha256",
+            Field::Key => "key",
+            Field::Signature => "signature",
+            Field::Expires => "expires",
+            Field::Issued => "issued",
+            Field::Bits => "bits",
+            Field::Ca => "ca",
+            Field::SelfSigned => "self-signed",
+            Field::Expired => "expired",
+        }
+    }
+
+    fn kind(self) -> Kind {
+        match self {
+            Field::Subject
+            | Field::Issuer
+            | Field::San
+            | Field::Serial
+            | Field::Sha256
+            | Field::Key
+            | Field::Signature => Kind::Text,
+            Field::Expires | Field::Issued => Kind::Time,
+            Field::Bits => Kind::Number,
+            Field::Ca | Field::SelfSigned | Field::Expired => Kind::Flag,
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self, String> {
+        let name = name.to_ascii_lowercase().replace('_', "-");
+        // a few names people reach for first
+        let name = match name.as_str() {
+            "cn" | "subject-name" => "subject",
+            "sans" | "dns" | "name" | "host" => "san",
+            "not-after" => "expires",
+            "not-before" => "issued",
+            "fingerprint" => "sha256",
+            other => other,
+        };
+        Field::ALL
+            .into_iter()
+            .find(|field| field.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Field::ALL.iter().map(|field| field.name()).collect();
+                format!(
+                    "unknown field {name:?}, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// The text values of a cert for this field.
+    fn texts(self, cert: &SimpleCert) -> Vec<String> {
+        match self {
+            Field::Subject => vec![cert.subject.name.clone()],
+            Field::Issuer => vec![cert.issuer.name.clone()],
+            Field::San => {
+                let sans = &cert.subject.sans;
+                sans.dns
+                    .iter()
+                    .cloned()
+                    .chain(sans.ip.iter().map(ToString::to_string))
+                    .chain(sans.email.iter().cloned())
+                    .chain(sans.uri.iter().cloned())
+                    .collect()
+            }
+            Field::Serial => vec![cert.serial.hex.clone()],
+            Field::Sha256 => vec![cert.fingerprints.sha256.clone()],
+            Field::Key => {
+                let key = &cert.public_key;
+                let kind = match &key.kind {
+                    SimplePublicKeyKind::RSA { .. } => "RSA",
+                    SimplePublicKeyKind::RsaPss { .. } => "RSA-PSS",
+                    SimplePublicKeyKind::DSA { .. } => "DSA",
+                    SimplePublicKeyKind::EC { .. } => "EC",
+                    SimplePublicKeyKind::Ed25519 { .. } => "Ed25519",
+                    SimplePublicKeyKind::Ed448 { .. } => "Ed448",
+                    SimplePublicKeyKind::X25519 { .. } => "X25519",
+                    SimplePublicKeyKind::X448 { .. } => "X448",
+                    // e.g. `id-ecPublicKey` and `brainpoolP256r1`
+                    SimplePublicKeyKind::Other {
+                        algorithm,
+                        parameters,
+                        ..
+                    } => {
+                        return [Some(algorithm), parameters.as_ref()]
+                            .into_iter()
+                            .flatten()
+                            .cloned()
+                            .collect()
+                    }
+                };
+                let mut texts = vec![kind.to_string()];
+                // the curve, e.g. `prime256v1`
+                if let Ok(curve) = key.curve.nid().short_name() {
+                    if !curve.eq_ignore_ascii_case(kind) {
+                        texts.push(curve.to_string());
+                    }
+                }
+                texts
+            }
+            Field::Signature => vec![cert.signature.algorithm.clone()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Op {
+    #[serde(rename = "=")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "contains")]
+    Contains,
+    #[serde(rename = "matches")]
+    Matches,
+}
+
+impl Op {
+    fn parse(op: &str) -> Result<Self, String> {
+        Ok(match op.to_ascii_lowercase().as_str() {
+            "=" | "==" | "is" => Op::Eq,
+            "!=" | "is-not" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "contains" | "has" => Op::Contains,
+            "matches" | "~" => Op::Matches,
+            _ => return Err(format!("unknown operator {op:?}")),
+        })
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "contains",
+            Op::Matches => "matches",
+        }
+    }
+
+    fn compare<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Contains | Op::Matches => false,
+        }
+    }
+}
+
+/// The value a field is compared with, parsed for the field's kind.
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Regex(Regex),
+    /// A point in time, or an offset from the time the filter is checked at.
+    Time(TimeValue),
+    Number(u64),
+    Flag(bool),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TimeValue {
+    At(Timestamp),
+    FromNow(SignedDuration),
+}
+
+impl Value {
+    /// How `written` was read, for `explain-expr`.
+    fn meaning(&self, written: &str) -> String {
+        match self {
+            Value::Text(_) => "text, ignoring case".to_string(),
+            Value::Regex(_) => "a regex, ignoring case".to_string(),
+            Value::Time(TimeValue::At(at)) => at.to_string(),
+            Value::Time(TimeValue::FromNow(duration)) if duration.is_negative() => {
+                format!("{} ago", written.trim_start_matches('-'))
+            }
+            Value::Time(TimeValue::FromNow(_)) => {
+                format!("{} from now", written.trim_start_matches('+'))
+            }
+            Value::Number(number) => number.to_string(),
+            Value::Flag(flag) => flag.to_string(),
+        }
+    }
+}
+
+/// One filter: `FIELD OP VALUE`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Filter {
+    field: Field,
+    op: Op,
+    #[serde(skip)]
+    value: Value,
+    /// The value as written, for messages.
+    #[serde(rename = "value")]
+    written: String,
+    /// How the value was read, e.g. `60d from now`.
+    meaning: String,
+}
+
+impl Filter {
+    /// A filter on `field`. Only flags can go without an operator and value.
+    pub fn new(field: &str, op: Option<&str>, written: Option<&str>) -> Result<Self, String> {
+        let field = Field::parse(field)?;
+        let (op, value, written) = match (field.kind(), op, written) {
+            (Kind::Flag, None, _) => (Op::Eq, Value::Flag(true), String::new()),
+            (_, None, _) => return Err(format!("expected an operator after {}", field.name())),
+            (_, Some(op), None) => {
+                return Err(format!("expected a value after {} {op}", field.name()))
+            }
+            (kind, Some(op), Some(written)) => {
+                let op = Op::parse(op)?;
+                let value = Self::value(field, kind, op, written)?;
+                (op, value, written.to_string())
+            }
+        };
+
+        Ok(Filter {
+            meaning: value.meaning(&written),
+            field,
+            op,
+            value,
+            written,
+        })
+    }
+
+    fn value(field: Field, kind: Kind, op: Op, written: &str) -> Result<Value, String> {
+        let name = field.name();
+        let unsupported = || format!("{name} can't be compared with {}", op.symbol());
+        match kind {
+            Kind::Text => match op {
+                Op::Eq | Op::Ne | Op::Contains => Ok(Value::Text(written.to_lowercase())),
+                Op::Matches => RegexBuilder::new(written)
+                    .case_insensitive(true)
+                    .build()
+                    .map(Value::Regex)
+                    .map_err(|err| format!("{written:?} isn't a regex: {err}")),
+                _ => Err(unsupported()),
+            },
+            Kind::Time if matches!(op, Op::Contains | Op::Matches) => Err(unsupported()),
+            Kind::Time => {
+                let (negative, duration) = match written.strip_prefix('-') {
+                    Some(duration) => (true, duration),
+                    None => (false, written.strip_prefix('+').unwrap_or(written)),
+                };
+                if let Ok(duration) = parse_duration(duration) {
+                    let duration = SignedDuration::try_from(duration).map_err(|e| e.to_string())?;
+                    let duration = if negative { -duration } else { duration };
+                    return Ok(Value::Time(TimeValue::FromNow(duration)));
+                }
+                parse_time(written).map(|at| Value::Time(TimeValue::At(at)))
+            }
+            Kind::Number if matches!(op, Op::Contains | Op::Matches) => Err(unsupported()),
+            Kind::Number => written
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| format!("{name} is a number, got {written:?}")),
+            Kind::Flag => match (op, written.to_ascii_lowercase().as_str()) {
+                (Op::Eq | Op::Ne, "true" | "yes") => Ok(Value::Flag(true)),
+                (Op::Eq | Op::Ne, "false" | "no") => Ok(Value::Flag(false)),
+                (Op::Eq | Op::Ne, _) => Err(format!("{name} is true or false, got {written:?}")),
+                _ => Err(unsupported()),
+            },
+        }
+    }
+
+    /// Whether `cert` passes the filter, with times counted from `now`.
+    pub fn matches(&self, cert: &SimpleCert, now: Timestamp) -> bool {
+        match &self.value {
+            Value::Text(value) => {
+                let texts = self.field.texts(cert);
+                let mut texts = texts.iter().map(|text| text.to_lowercase());
+                match self.op {
+                    Op::Ne => !texts.any(|text| text == *value),
+                    Op::Contains => texts.any(|text| text.contains(value.as_str())),
+                    _ => texts.any(|text| text == *value),
+                }
+            }
+            Value::Regex(regex) => self
+                .field
+                .texts(cert)
+                .iter()
+                .any(|text| regex.is_match(text)),
+            Value::Time(value) => {
+                let at = match *value {
+                    TimeValue::At(at) => at,
+                    TimeValue::FromNow(duration) => match now.checked_add(duration) {
+                        Ok(at) => at,
+                        Err(_) => return false,
+                    },
+                };
+                let time = match self.field {
+                    Field::Expires => cert.validity.not_after.and_then(NotAfter::timestamp),
+                    _ => cert.validity.not_before,
+                };
+                // certs without a usable time (or without an expiry) don't
+                // match any comparison but `!=`
+                match time {
+                    Some(time) => self.op.compare(time, at),
+                    None => self.op == Op::Ne,
+                }
+            }
+            Value::Number(value) => self.op.compare(cert.public_key.bits as u64, *value),
+            Value::Flag(value) => {
+                let flag = match self.field {
+                    Field::Ca => cert.is_ca,
+                    Field::SelfSigned => cert.is_self_signed,
+                    _ => cert
+                        .validity
+                        .not_after
+                        .and_then(NotAfter::timestamp)
+                        .is_some_and(|not_after| not_after <= now),
+                };
+                self.op.compare(flag, *value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.value, self.written.is_empty()) {
+            (Value::Flag(true), true) => write!(f, "{}", self.field.name()),
+            _ => {
+                write!(f, "{} {} ", self.field.name(), self.op.symbol())?;
+                match needs_quotes(&self.written) {
+                    true if self.written.contains('\'') => write!(f, "\"{}\"", self.written),
+                    true => write!(f, "'{}'", self.written),
+                    false => write!(f, "{}", self.written),
+                }
+            }
+        }
+    }
+}
+
+/// Whether a value has to be quoted to be read back as one token.
+fn needs_quotes(value: &str) -> bool {
+    value.is_empty() || value.contains(|c: char| c.is_whitespace() || "()<>=!~&|\"'".contains(c))
+}
+
+/// Filters combined with `and`, `or` and `not`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expr {
+    Filter(Filter),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    /// Parse an expression like `san contains corp and not ca`.
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let tokens = tokenize(expr)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let parsed = parser.or()?;
+        match parser.peek() {
+            None => Ok(parsed),
+            Some(token) => Err(format!("unexpected {token} after {parsed}")),
+        }
+    }
+
+    /// Whether `cert` passes, with times counted from `now`.
+    pub fn matches(&self, cert: &SimpleCert, now: Timestamp) -> bool {
+        match self {
+            Expr::Filter(filter) => filter.matches(cert, now),
+            Expr::Not(expr) => !expr.matches(cert, now),
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.matches(cert, now)),
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.matches(cert, now)),
+        }
+    }
+
+    /// The expression as an indented tree, one node per line, for
+    /// `explain-expr`.
+    pub fn tree(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.push_tree(&mut lines, "", "");
+        lines
+    }
+
+    fn push_tree(&self, lines: &mut Vec<String>, first: &str, rest: &str) {
+        let children: &[Expr] = match self {
+            Expr::Filter(filter) => {
+                lines.push(format!("{first}{filter}  ({})", filter.meaning));
+                return;
+            }
+            Expr::Not(expr) => {
+                lines.push(format!("{first}not"));
+                std::slice::from_ref(expr.as_ref())
+            }
+            Expr::And(exprs) => {
+                lines.push(format!("{first}and"));
+                exprs
+            }
+            Expr::Or(exprs) => {
+                lines.push(format!("{first}or"));
+                exprs
+            }
+        };
+        for (i, child) in children.iter().enumerate() {
+            match i + 1 == children.len() {
+                true => child.push_tree(lines, &format!("{rest}└─ "), &format!("{rest}   ")),
+                false => child.push_tree(lines, &format!("{rest}├─ "), &format!("{rest}│  ")),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    /// The expression with the parentheses it needs, e.g. `a and (b or c)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, exprs: &[Expr], op: &str| {
+            for (i, expr) in exprs.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {op} ")?;
+                }
+                match (op, expr) {
+                    ("and", Expr::Or(_)) => write!(f, "({expr})")?,
+                    _ => write!(f, "{expr}")?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Expr::Filter(filter) => write!(f, "{filter}"),
+            Expr::Not(expr) => match expr.as_ref() {
+                Expr::And(_) | Expr::Or(_) => write!(f, "not ({expr})"),
+                _ => write!(f, "not {expr}"),
+            },
+            Expr::And(exprs) => join(f, exprs, "and"),
+            Expr::Or(exprs) => join(f, exprs, "or"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    /// A field, operator, value or keyword. Quoted words are never keywords
+    /// or operators.
+    Word {
+        text: String,
+        quoted: bool,
+    },
+}
+
+impl Token {
+    /// The word, unless it was quoted.
+    fn bare(&self) -> Option<&str> {
+        match self {
+            Token::Word {
+                text,
+                quoted: false,
+            } => Some(text),
+            _ => None,
+        }
+    }
+
+    fn is_keyword(&self, keywords: &[&str]) -> bool {
+        self.bare()
+            .is_some_and(|word| keywords.iter().any(|k| word.eq_ignore_ascii_case(k)))
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+            Token::Word { text, .. } => write!(f, "`{text}`"),
+        }
+    }
+}
+
+/// Characters that make up symbolic operators, which don't need spaces
+/// around them: `bits>=2048`.
+const OPERATOR_CHARS: &str = "<>=!~&|";
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let text: String = chars.by_ref().take_while(|&next| next != c).collect();
+                tokens.push(Token::Word { text, quoted: true });
+            }
+            _ => {
+                let symbolic = OPERATOR_CHARS.contains(c);
+                let mut text = String::new();
+                while let Some(&next) = chars.peek() {
+                    let ends = next.is_whitespace()
+                        || matches!(next, '(' | ')' | '"' | '\'')
+                        || OPERATOR_CHARS.contains(next) != symbolic;
+                    if ends {
+                        break;
+                    }
+                    text.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Word {
+                    text,
+                    quoted: false,
+                });
+            }
+        }
+    }
+    if tokens.is_empty() {
+        return Err("empty expression".to_string());
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens:
+///
+/// ```text
+/// or     = and ("or" and)*
+/// and    = unary ("and" unary)*
+/// unary  = "not" unary | "(" or ")" | filter
+/// filter = FIELD [OP VALUE]
+/// ```
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn take(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut exprs = vec![self.and()?];
+        while self.peek().is_some_and(|t| t.is_keyword(&["or", "||"])) {
+            self.next += 1;
+            exprs.push(self.and()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expr::Or(exprs),
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut exprs = vec![self.unary()?];
+        while self.peek().is_some_and(|t| t.is_keyword(&["and", "&&"])) {
+            self.next += 1;
+            exprs.push(self.unary()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expr::And(exprs),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.take() {
+            Some(token) if token.is_keyword(&["not", "!"]) => {
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.take() {
+                    Some(Token::Close) => Ok(expr),
+                    Some(token) => Err(format!("expected `)` after {expr}, got {token}")),
+                    None => Err(format!("expected `)` after {expr}")),
+                }
+            }
+            Some(token) if token.is_keyword(&["and", "or", "&&", "||"]) => {
+                Err(format!("expected a filter, got {token}"))
+            }
+            Some(Token::Word {
+                text,
+                quoted: false,
+            }) => self.filter(&text),
+            Some(token) => Err(format!("expected a field, got {token}")),
+            None => Err("expected a filter at the end".to_string()),
+        }
+    }
+
+    fn filter(&mut self, field: &str) -> Result<Expr, String> {
+        let op = self
+            .peek()
+            .and_then(Token::bare)
+            .filter(|word| Op::parse(word).is_ok())
+            .map(str::to_string);
+        let Some(op) = op else {
+            return Filter::new(field, None, None).map(Expr::Filter);
+        };
+        self.next += 1;
+
+        let value = match self.take() {
+            Some(Token::Word { text, .. }) => Some(text),
+            _ => None,
+        };
+        Filter::new(field, Some(&op), value.as_deref()).map(Expr::Filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::chain;
+
+    fn matching(expr: &str, now: &str) -> Vec<bool> {
+        let expr = Expr::parse(expr).unwrap();
+        let now: Timestamp = now.parse().unwrap();
+        chain().iter().map(|cert| expr.matches(cert, now)).collect()
+    }
+
+    #[test]
+    fn text_fields() {
+        let now = "2025-01-01T00:00:00Z";
+        assert_eq!(matching("san contains LAN.fish", now), [true, false, false]);
+        assert_eq!(matching("san = lan.fish", now), [true, false, false]);
+        assert_eq!(matching("san != lan.fish", now), [false, true, true]);
+        assert_eq!(
+            matching("subject matches ^.*ISRG", now),
+            [false, false, true]
+        );
+        assert_eq!(
+            matching("issuer contains 'root x2'", now),
+            [false, true, true]
+        );
+        assert_eq!(matching("key = ec", now), [true, true, true]);
+    }
+
+    #[test]
+    fn times_and_flags() {
+        let not_after = chain()[0]
+            .validity
+            .not_after
+            .and_then(NotAfter::timestamp)
+            .unwrap();
+        let before = not_after
+            .checked_sub(SignedDuration::from_hours(24))
+            .unwrap();
+        let before = before.to_string();
+
+        assert_eq!(matching("expires < 2d", &before), [true, false, false]);
+        assert_eq!(matching("expired", &before), [false, false, false]);
+        assert_eq!(matching("expired = false", &before), [true, true, true]);
+        assert_eq!(matching("issued > -7d", &before), [false, false, false]);
+        assert_eq!(matching("ca", &before), [false, true, true]);
+        assert_eq!(matching("self-signed", &before), [false, false, true]);
+        assert_eq!(matching("bits >= 384", &before), [false, true, true]);
+    }
+
+    #[test]
+    fn combinations() {
+        let now = "2025-01-01T00:00:00Z";
+        assert_eq!(
+            matching("ca and not self-signed", now),
+            [false, true, false]
+        );
+        assert_eq!(
+            matching("san = lan.fish or subject contains ISRG", now),
+            [true, false, true]
+        );
+        assert_eq!(
+            matching("!(ca && bits>=384) || expires<2d", now),
+            [true, false, false]
+        );
+
+        // `and` binds tighter than `or`
+        let expr = Expr::parse("ca or self-signed and not expired").unwrap();
+        assert_eq!(expr.to_string(), "ca or self-signed and not expired");
+        let expr = Expr::parse("(ca or self-signed) and issuer matches 'Root X\\d'").unwrap();
+        assert_eq!(
+            expr.to_string(),
+            "(ca or self-signed) and issuer matches 'Root X\\d'"
+        );
+        assert_eq!(
+            expr.tree(),
+            [
+                "and",
+                "├─ or",
+                "│  ├─ ca  (true)",
+                "│  └─ self-signed  (true)",
+                "└─ issuer matches 'Root X\\d'  (a regex, ignoring case)",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_nonsense() {
+        for expr in [
+            "",
+            "colour = blue",
+            "san",
+            "san =",
+            "bits contains 2",
+            "expires < soon",
+            "ca > true",
+            "subject matches (",
+            "ca and",
+            "(ca or expired",
+            "ca expired",
+            "'ca'",
+        ] {
+            assert!(Expr::parse(expr).is_err(), "{expr:?} parsed");
+        }
+        assert_eq!(
+            Expr::parse("expires  <  60d").unwrap().to_string(),
+            "expires < 60d"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Just enough HTTP/2 (RFC 9113) to check a server speaks it: the connection
+//! preface and the SETTINGS exchange, no streams.
+
+use std::time::Duration;
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// What a client sends first, before its SETTINGS frame.
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// The largest frame a server may send before it has seen our SETTINGS.
+const MAX_FRAME_LEN: usize = 16 << 10;
+
+/// How long to wait for the server's SETTINGS.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const DATA: u8 = 0x0;
+const SETTINGS: u8 = 0x4;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+
+const ACK: u8 = 0x1;
+
+/// One parameter of the server's SETTINGS frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Setting {
+    /// The name from the RFC, e.g. `MAX_CONCURRENT_STREAMS`, or the
+    /// identifier in hex for ones it doesn't define.
+    pub name: String,
+    pub value: u32,
+}
+
+impl Setting {
+    fn new(id: u16, value: u32) -> Self {
+        let name = match id {
+            0x1 => "HEADER_TABLE_SIZE".to_string(),
+            0x2 => "ENABLE_PUSH".to_string(),
+            0x3 => "MAX_CONCURRENT_STREAMS".to_string(),
+            0x4 => "INITIAL_WINDOW_SIZE".to_string(),
+            0x5 => "MAX_FRAME_SIZE".to_string(),
+            0x6 => "MAX_HEADER_LIST_SIZE".to_string(),
+            0x8 => "ENABLE_CONNECT_PROTOCOL".to_string(),
+            0x9 => "NO_RFC7540_PRIORITIES".to_string(),
+            id => format!("{id:#06x}"),
+        };
+        Self { name, value }
+    }
+}
+
+/// Send the connection preface and an empty SETTINGS frame over `stream`,
+/// then read the server's first frame, which must be its SETTINGS, and
+/// acknowledge them.
+pub(crate) async fn handshake(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+) -> Result<Vec<Setting>> {
+    let mut hello = PREFACE.to_vec();
+    hello.extend(frame_header(0, SETTINGS, 0));
+    stream
+        .write_all(&hello)
+        .await
+        .context("sending the HTTP/2 preface")?;
+
+    let (kind, flags, payload) = tokio::time::timeout(TIMEOUT, read_frame(&mut stream))
+        .await
+        .map_err(|_| eyre!("no SETTINGS from the server within {}s", TIMEOUT.as_secs()))??;
+    match kind {
+        SETTINGS if flags & ACK == 0 => {}
+        GOAWAY => bail!("the server sent GOAWAY: {}", goaway(&payload)),
+        kind => bail!(
+            "the server's first frame was {}, not SETTINGS",
+            frame_name(kind)
+        ),
+    }
+    let settings = parse_settings(&payload)?;
+
+    stream
+        .write_all(&frame_header(0, SETTINGS, ACK))
+        .await
+        .context("acknowledging the server's SETTINGS")?;
+    Ok(settings)
+}
+
+/// Read one frame: its type, flags and payload.
+async fn read_frame(stream: &mut (impl AsyncRead + Unpin)) -> Result<(u8, u8, Vec<u8>)> {
+    let mut header = [0; 9];
+    stream.read_exact(&mut header).await.map_err(|err| {
+        if err.kind() == std::io::ErrorKind::UnexpectedEof {
+            eyre!("the server closed the connection, it likely doesn't speak HTTP/2")
+        } else {
+            eyre!(err).wrap_err("reading an HTTP/2 frame")
+        }
+    })?;
+
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    if len > MAX_FRAME_LEN {
+        bail!("the server sent a {len} byte frame, over the {MAX_FRAME_LEN} byte limit");
+    }
+    let mut payload = vec![0; len];
+    stream
+        .read_exact(&mut payload)
+        .await
+        .context("reading an HTTP/2 frame")?;
+    Ok((header[3], header[4], payload))
+}
+
+fn frame_header(len: u32, kind: u8, flags: u8) -> [u8; 9] {
+    let len = len.to_be_bytes();
+    [len[1], len[2], len[3], kind, flags, 0, 0, 0, 0]
+}
+
+fn parse_settings(payload: &[u8]) -> Result<Vec<Setting>> {
+    if payload.len() % 6 != 0 {
+        bail!(
+            "SETTINGS payload of {} bytes isn't a multiple of 6",
+            payload.len()
+        );
+    }
+    Ok(payload
+        .chunks_exact(6)
+        .map(|setting| {
+            let id = u16::from_be_bytes([setting[0], setting[1]]);
+            let value = u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]);
+            Setting::new(id, value)
+        })
+        .collect())
+}
+
+/// The error code and debug data of a GOAWAY frame.
+fn goaway(payload: &[u8]) -> String {
+    let Some(code) = payload.get(4..8) else {
+        return "(truncated)".to_string();
+    };
+    let code = u32::from_be_bytes([code[0], code[1], code[2], code[3]]);
+    let name = match code {
+        0x0 => "NO_ERROR",
+        0x1 => "PROTOCOL_ERROR",
+        0x2 => "INTERNAL_ERROR",
+        0x3 => "FLOW_CONTROL_ERROR",
+        0x4 => "SETTINGS_TIMEOUT",
+        0x6 => "FRAME_SIZE_ERROR",
+        0x7 => "REFUSED_STREAM",
+        0xb => "ENHANCE_YOUR_CALM",
+        0xc => "INADEQUATE_SECURITY",
+        0xd => "HTTP_1_1_REQUIRED",
+        _ => "unknown error",
+    };
+    match String::from_utf8_lossy(&payload[8..]).trim() {
+        "" => format!("{name} ({code:#x})"),
+        debug => format!("{name} ({code:#x}): {debug}"),
+    }
+}
+
+fn frame_name(kind: u8) -> String {
+    match kind {
+        DATA => "DATA".to_string(),
+        0x1 => "HEADERS".to_string(),
+        SETTINGS => "a SETTINGS ACK".to_string(),
+        0x6 => "PING".to_string(),
+        WINDOW_UPDATE => "WINDOW_UPDATE".to_string(),
+        kind => format!("an unknown frame ({kind:#x})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::duplex;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_settings() {
+        let (client, mut server) = duplex(1024);
+        let server = tokio::spawn(async move {
+            let mut hello = [0; PREFACE.len() + 9];
+            server.read_exact(&mut hello).await.unwrap();
+            assert!(hello.starts_with(PREFACE));
+
+            let mut settings = frame_header(12, SETTINGS, 0).to_vec();
+            settings.extend([0, 3, 0, 0, 0, 100, 0xf0, 0x0d, 0, 0, 0, 1]);
+            server.write_all(&settings).await.unwrap();
+
+            let mut ack = [0; 9];
+            server.read_exact(&mut ack).await.unwrap();
+            ack
+        });
+
+        let settings = handshake(client).await.unwrap();
+        assert_eq!(
+            settings,
+            [
+                Setting::new(0x3, 100),
+                Setting {
+                    name: "0xf00d".to_string(),
+                    value: 1
+                }
+            ]
+        );
+        assert_eq!(server.await.unwrap(), frame_header(0, SETTINGS, ACK));
+    }
+
+    #[tokio::test]
+    async fn goaway_instead_of_settings() {
+        let (client, mut server) = duplex(1024);
+        tokio::spawn(async move {
+            let mut hello = [0; PREFACE.len() + 9];
+            server.read_exact(&mut hello).await.unwrap();
+
+            let mut goaway = frame_header(13, GOAWAY, 0).to_vec();
+            goaway.extend([0, 0, 0, 0, 0, 0, 0, 0xd]);
+            goaway.extend(b"h1!!!");
+            server.write_all(&goaway).await.unwrap();
+        });
+
+        let err = handshake(client).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the server sent GOAWAY: HTTP_1_1_REQUIRED (0xd): h1!!!"
+        );
+    }
+
+    #[tokio::test]
+    async fn http1_server() {
+        let (client, mut server) = duplex(1024);
+        tokio::spawn(async move {
+            let mut hello = [0; PREFACE.len() + 9];
+            server.read_exact(&mut hello).await.unwrap();
+            server
+                .write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        // "HTTP/1.1 " is read as a huge frame header
+        assert!(handshake(client).await.is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! Matching a hostname against a cert's names as RFC 6125 (and browsers)
+//! do, with the reason each name does or doesn't match, for `pls
+//! match-hostname`.
+//!
+//! DNS names compare without case or a trailing dot. A wildcard is a whole
+//! leftmost `*` label that covers exactly one label, and isn't accepted for
+//! a top-level domain. IP addresses only match IP SANs. The subject CN is
+//! never used when the cert has SANs, and modern clients don't use it at
+//! all.
+
+use std::net::IpAddr;
+
+use serde::Serialize;
+
+use crate::x509::SimpleCert;
+
+/// Which kind of name a cert presents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameKind {
+    Dns,
+    Ip,
+    /// The subject's common name.
+    Cn,
+}
+
+/// How one of the cert's names compares with the hostname.
+#[derive(Debug, Clone, Serialize)]
+pub struct NameVerdict {
+    pub name: String,
+    pub kind: NameKind,
+    pub matches: bool,
+    pub reason: String,
+}
+
+/// Whether a hostname matches a cert, and why.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HostnameMatch {
+    pub hostname: String,
+    pub matches: bool,
+    pub names: Vec<NameVerdict>,
+}
+
+/// Match `hostname`, a DNS name or an IP address, against `cert`.
+pub(crate) fn check(cert: &SimpleCert, hostname: &str) -> HostnameMatch {
+    let sans = &cert.subject.sans;
+    let mut names = Vec::new();
+    let address = hostname
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>();
+
+    match address {
+        Ok(address) => {
+            for ip in &sans.ip {
+                let matches = *ip == address;
+                let reason = match matches {
+                    true => "the same address",
+                    false => "a different address",
+                };
+                names.push(verdict(ip.to_string(), NameKind::Ip, matches, reason));
+            }
+            for dns in &sans.dns {
+                let reason = "IP addresses only match IP SANs, not DNS names";
+                names.push(verdict(dns.clone(), NameKind::Dns, false, reason));
+            }
+        }
+        Err(_) => {
+            let reference = hostname.trim_end_matches('.').to_ascii_lowercase();
+            for dns in &sans.dns {
+                let (matches, reason) = match_dns(dns, &reference);
+                names.push(verdict(dns.clone(), NameKind::Dns, matches, reason));
+            }
+            for ip in &sans.ip {
+                let reason = "IP SANs only match IP addresses";
+                names.push(verdict(ip.to_string(), NameKind::Ip, false, reason));
+            }
+        }
+    }
+
+    if let Some(cn) = &cert.subject.dn.common_name {
+        let reason = match sans.dns.is_empty() && sans.ip.is_empty() {
+            true => "the cert has no SANs, and clients no longer fall back to the CN",
+            false => "the CN is ignored when the cert has SANs",
+        };
+        names.push(verdict(cn.clone(), NameKind::Cn, false, reason));
+    }
+
+    HostnameMatch {
+        hostname: hostname.to_string(),
+        matches: names.iter().any(|name| name.matches),
+        names,
+    }
+}
+
+fn verdict(name: String, kind: NameKind, matches: bool, reason: impl Into<String>) -> NameVerdict {
+    NameVerdict {
+        name,
+        kind,
+        matches,
+        reason: reason.into(),
+    }
+}
+
+/// Whether the DNS SAN `presented` covers `reference`, a lowercase name
+/// without a trailing dot, and why.
+fn match_dns(presented: &str, reference: &str) -> (bool, String) {
+    let presented = presented.trim_end_matches('.').to_ascii_lowercase();
+    if !presented.contains('*') {
+        return match presented == reference {
+            true => (true, "the same name".to_string()),
+            false => (false, "a different name".to_string()),
+        };
+    }
+
+    let Some((left, parent)) = presented.split_once('.') else {
+        return (false, "a wildcard needs a domain after it".to_string());
+    };
+    if parent.contains('*') {
+        return (
+            false,
+            "`*` is only allowed in the leftmost label".to_string(),
+        );
+    }
+    if left != "*" {
+        let reason = match left.starts_with("xn--") {
+            true => "a wildcard can't be part of an IDN A-label (`xn--`)".to_string(),
+            false => format!(
+                "partial wildcards like `{left}` aren't accepted by browsers, only a whole `*` label"
+            ),
+        };
+        return (false, reason);
+    }
+    if !parent.contains('.') {
+        return (
+            false,
+            format!("`*.{parent}` would cover a whole top-level domain, which clients refuse"),
+        );
+    }
+
+    match reference.split_once('.') {
+        Some((label, rest)) if rest == parent && !label.is_empty() => {
+            (true, format!("`*` covers the label `{label}`"))
+        }
+        _ if reference == parent => (
+            false,
+            format!("`*` needs a label before `{parent}`, it doesn't cover `{parent}` itself"),
+        ),
+        _ if reference.ends_with(&format!(".{parent}")) => (
+            false,
+            format!("`*` covers exactly one label, and `{reference}` has more before `{parent}`"),
+        ),
+        _ => (false, format!("not under `{parent}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        let matches = |presented, reference| match_dns(presented, reference).0;
+        assert!(matches("*.example.com", "www.example.com"));
+        assert!(matches("*.Example.com.", "www.example.com"));
+        assert!(!matches("*.example.com", "example.com"));
+        assert!(!matches("*.example.com", "a.b.example.com"));
+        assert!(!matches("*.example.com", "www.example.net"));
+        assert!(!matches("w*.example.com", "www.example.com"));
+        assert!(!matches("xn--*.example.com", "xn--bcher-kva.example.com"));
+        assert!(!matches("*.com", "example.com"));
+        assert!(!matches("www.*.com", "www.example.com"));
+        assert_eq!(
+            match_dns("*.example.com", "a.b.example.com").1,
+            "`*` covers exactly one label, and `a.b.example.com` has more before `example.com`"
+        );
+    }
+
+    #[test]
+    fn cert_names() {
+        let cert = SimpleCert::from(
+            X509::from_pem(include_bytes!("../test-data/certs/lan-fish.pem")).unwrap(),
+        );
+        let result = check(&cert, "P2P.lan.fish.");
+        assert!(result.matches);
+        let matched: Vec<&str> = result
+            .names
+            .iter()
+            .filter(|name| name.matches)
+            .map(|name| name.name.as_str())
+            .collect();
+        assert_eq!(matched, ["p2p.lan.fish"]);
+
+        let result = check(&cert, "192.168.1.1");
+        assert!(!result.matches);
+        assert!(result
+            .names
+            .iter()
+            .all(|name| name.reason.contains("IP") || name.kind == NameKind::Cn));
+    }
+}
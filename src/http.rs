@@ -0,0 +1,446 @@
+use std::time::Duration;
+
+use boring::ssl::{SslConnector, SslMethod};
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use url::Url;
+
+/// Responses bigger than this are cut off with an error. Everything fetched
+/// (ACME resources, certs) is a few KiB.
+const MAX_RESPONSE_LEN: usize = 16 << 20;
+
+/// Requests bigger than this are answered without being read further, see
+/// [`read_request_head`].
+const MAX_REQUEST_LEN: usize = 8 << 10;
+
+/// How long a request, including following redirects, may take.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+const MAX_REDIRECTS: usize = 5;
+
+/// A response to a [`get`].
+#[derive(Debug)]
+pub(crate) struct Response {
+    /// The version from the status line, e.g. `HTTP/1.1`.
+    pub version: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// The first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Fail unless the status is 2xx, with the body in the error for small
+    /// text bodies (e.g. an RFC 7807 problem document).
+    pub fn error_for_status(self, url: &Url) -> Result<Self> {
+        if (200..300).contains(&self.status) {
+            return Ok(self);
+        }
+
+        match std::str::from_utf8(&self.body) {
+            Ok(body) if !body.is_empty() && body.len() < 1024 => {
+                bail!("GET {url}: HTTP {}: {}", self.status, body.trim())
+            }
+            _ => bail!("GET {url}: HTTP {}", self.status),
+        }
+    }
+}
+
+/// GET `url` over HTTP/1.1, following redirects. `https` URLs are verified
+/// against the system's roots.
+///
+/// This is just enough HTTP to fetch the odd document (ACME resources,
+/// issuer certs, ...) without pulling in a full HTTP stack: one request per
+/// connection, no compression.
+pub(crate) async fn get(url: &Url) -> Result<Response> {
+    get_accepting(url, "*/*").await
+}
+
+/// [`get`] with an `Accept` header, for servers that pick the response
+/// format by it (e.g. DNS-over-HTTPS).
+pub(crate) async fn get_accepting(url: &Url, accept: &str) -> Result<Response> {
+    tokio::time::timeout(TIMEOUT, follow(url.clone(), accept))
+        .await
+        .map_err(|_| eyre!("GET {url}: timed out after {}s", TIMEOUT.as_secs()))?
+}
+
+/// [`get`] without following redirects, to see where a server sends
+/// clients.
+pub(crate) async fn get_once(url: &Url) -> Result<Response> {
+    tokio::time::timeout(TIMEOUT, fetch(url, "*/*"))
+        .await
+        .map_err(|_| eyre!("GET {url}: timed out after {}s", TIMEOUT.as_secs()))?
+}
+
+async fn follow(mut url: Url, accept: &str) -> Result<Response> {
+    for _ in 0..=MAX_REDIRECTS {
+        let response = fetch(&url, accept).await?;
+        if !matches!(response.status, 301 | 302 | 303 | 307 | 308) {
+            return Ok(response);
+        }
+
+        let location = response
+            .header("location")
+            .ok_or_else(|| eyre!("GET {url}: HTTP {} without a location", response.status))?;
+        let next = url
+            .join(location)
+            .with_context(|| format!("GET {url}: invalid redirect to {location:?}"))?;
+        tracing::debug!("{url} redirected to {next}");
+        url = next;
+    }
+
+    bail!("GET {url}: more than {MAX_REDIRECTS} redirects")
+}
+
+#[tracing::instrument(name = "http", skip(accept), fields(%url))]
+async fn fetch(url: &Url, accept: &str) -> Result<Response> {
+    send(url, "GET", |path, authority| {
+        build_request("GET", path, authority, accept).into_bytes()
+    })
+    .await
+}
+
+/// POST `body`, a JSON document, to `url` without following redirects, e.g.
+/// to a webhook.
+pub(crate) async fn post_json(url: &Url, body: &[u8]) -> Result<Response> {
+    let post = send(url, "POST", |path, authority| {
+        let mut request = format!(
+            "POST {path} HTTP/1.1\r\nHost: {authority}\r\nUser-Agent: pls/{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            env!("CARGO_PKG_VERSION"),
+            body.len()
+        )
+        .into_bytes();
+        request.extend_from_slice(body);
+        request
+    });
+    tokio::time::timeout(TIMEOUT, post)
+        .await
+        .map_err(|_| eyre!("POST {url}: timed out after {}s", TIMEOUT.as_secs()))?
+}
+
+/// Connect to `url`'s host and send the request `request` builds from the
+/// path and the `Host` header.
+async fn send(
+    url: &Url,
+    method: &str,
+    request: impl FnOnce(&str, &str) -> Vec<u8>,
+) -> Result<Response> {
+    let host = match url.host() {
+        Some(url::Host::Ipv6(ip)) => ip.to_string(),
+        Some(host) => host.to_string(),
+        None => bail!("{url} has no host"),
+    };
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| eyre!("{url} has no port"))?;
+
+    let mut authority = url.host_str().unwrap_or_default().to_string();
+    if let Some(port) = url.port() {
+        authority.push_str(&format!(":{port}"));
+    }
+    let request = request(
+        &url[url::Position::BeforePath..url::Position::AfterQuery],
+        &authority,
+    );
+
+    tracing::debug!("{method}");
+    let stream = TcpStream::connect((host.as_str(), port))
+        .await
+        .with_context(|| format!("connecting to {host}:{port}"))?;
+
+    let response = match url.scheme() {
+        "http" => exchange(stream, &request, false).await,
+        "https" => {
+            let connector = SslConnector::builder(SslMethod::tls_client())
+                .context("building SSL connector")?
+                .build();
+            let config = connector
+                .configure()
+                .context("configuring TLS connection")?;
+            let stream = tokio_boring::connect(config, &host, stream)
+                .await
+                .with_context(|| format!("TLS handshake with {host}"))?;
+            exchange(stream, &request, false).await
+        }
+        scheme => bail!("unsupported URL scheme {scheme:?} in {url}"),
+    };
+    response.with_context(|| format!("{method} {url}"))
+}
+
+/// Send a `method` request for `path` over `stream`, a connection that is
+/// already set up, e.g. the TLS connection of `connect --http`. `host` is
+/// the `Host` header. Responses to `HEAD` are read up to their headers.
+pub(crate) async fn request(
+    stream: impl AsyncRead + AsyncWrite + Unpin,
+    method: &str,
+    path: &str,
+    host: &str,
+) -> Result<Response> {
+    let request = build_request(method, path, host, "*/*");
+    tokio::time::timeout(
+        TIMEOUT,
+        exchange(stream, request.as_bytes(), method == "HEAD"),
+    )
+    .await
+    .map_err(|_| eyre!("{method} {path}: timed out after {}s", TIMEOUT.as_secs()))?
+    .with_context(|| format!("{method} {path} on {host}"))
+}
+
+/// An HTTP/1.1 request without a body, closing the connection after it.
+fn build_request(method: &str, path: &str, host: &str, accept: &str) -> String {
+    format!(
+        "{method} {path} HTTP/1.1\r\nHost: {host}\r\nUser-Agent: pls/{}\r\nAccept: {accept}\r\nConnection: close\r\n\r\n",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// Send `request` and read the response. Reading stops as soon as the
+/// response is complete, as plenty of servers close TLS connections without a
+/// `close_notify`. With `head`, the response has no body, whatever its
+/// headers say.
+async fn exchange(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    request: &[u8],
+    head: bool,
+) -> Result<Response> {
+    stream.write_all(request).await?;
+
+    let mut buf = Vec::new();
+    let mut chunk = [0; 16 << 10];
+    loop {
+        let read = match stream.read(&mut chunk).await {
+            Ok(read) => read,
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => 0,
+            Err(err) => return Err(err.into()),
+        };
+        if read == 0 {
+            let response = match head {
+                true => parse_head(&buf)?.map(|(response, _)| response),
+                false => parse_response(&buf, true)?,
+            };
+            return response.ok_or_else(|| eyre!("connection closed mid-response"));
+        }
+
+        buf.extend_from_slice(&chunk[..read]);
+        if buf.len() > MAX_RESPONSE_LEN {
+            bail!("response is over {MAX_RESPONSE_LEN} bytes");
+        }
+        if head {
+            if let Some((response, _)) = parse_head(&buf)? {
+                return Ok(response);
+            }
+        } else if let Some(response) = parse_response(&buf, false)? {
+            return Ok(response);
+        }
+    }
+}
+
+/// Parse a response out of `buf`, or `None` if more of it has to be read.
+/// At `eof`, a response without a length ends with the connection.
+fn parse_response(buf: &[u8], eof: bool) -> Result<Option<Response>> {
+    let Some((mut response, body)) = parse_head(buf)? else {
+        return Ok(None);
+    };
+
+    let body = &buf[body..];
+    let chunked = response
+        .header("transfer-encoding")
+        .is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"));
+    response.body = if chunked {
+        match decode_chunked(body)? {
+            Some(body) => body,
+            None => return Ok(None),
+        }
+    } else if let Some(len) = response.header("content-length") {
+        let len: usize = len
+            .parse()
+            .map_err(|_| eyre!("invalid content-length {len:?}"))?;
+        match body.get(..len) {
+            Some(body) => body.to_vec(),
+            None => return Ok(None),
+        }
+    } else if eof {
+        body.to_vec()
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(response))
+}
+
+/// Parse the status line and headers out of `buf`, with where the body
+/// starts, or `None` if more of them has to be read.
+fn parse_head(buf: &[u8]) -> Result<Option<(Response, usize)>> {
+    let Some(end) = memchr::memmem::find(buf, b"\r\n\r\n") else {
+        return Ok(None);
+    };
+    let head = std::str::from_utf8(&buf[..end]).context("response headers aren't UTF-8")?;
+    let mut lines = head.split("\r\n");
+
+    let status_line = lines.next().unwrap_or_default();
+    let mut parts = status_line.split_whitespace();
+    let version = parts.next().unwrap_or_default().to_string();
+    let status = parts
+        .next()
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| eyre!("invalid status line {status_line:?}"))?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let response = Response {
+        version,
+        status,
+        headers,
+        body: Vec::new(),
+    };
+
+    Ok(Some((response, end + 4)))
+}
+
+/// Read a request up to the end of its headers, for the servers `pls` runs.
+/// It's cut off after [`MAX_REQUEST_LEN`] bytes, and `None` when the client
+/// goes away first.
+pub(crate) async fn read_request_head(stream: &mut (impl AsyncRead + Unpin)) -> Option<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+        if request.len() > MAX_REQUEST_LEN {
+            break;
+        }
+    }
+    Some(request)
+}
+
+/// Decode a `Transfer-Encoding: chunked` body, or `None` if it isn't all
+/// there yet. Trailers are ignored.
+fn decode_chunked(mut data: &[u8]) -> Result<Option<Vec<u8>>> {
+    let mut body = Vec::new();
+    loop {
+        let Some(line_end) = memchr::memmem::find(data, b"\r\n") else {
+            return Ok(None);
+        };
+        let line = std::str::from_utf8(&data[..line_end]).context("invalid chunk size")?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size =
+            usize::from_str_radix(size, 16).map_err(|_| eyre!("invalid chunk size {size:?}"))?;
+        if size == 0 {
+            return Ok(Some(body));
+        }
+        // no chunk can be bigger than a whole response, which also keeps
+        // `chunk + size` from overflowing
+        if size > MAX_RESPONSE_LEN {
+            bail!("chunk is over {MAX_RESPONSE_LEN} bytes");
+        }
+
+        let chunk = line_end + 2;
+        let Some(data_chunk) = data.get(chunk..chunk + size) else {
+            return Ok(None);
+        };
+        body.extend_from_slice(data_chunk);
+        data = data.get(chunk + size + 2..).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_length() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello";
+        assert!(parse_response(&raw[..raw.len() - 1], false)
+            .unwrap()
+            .is_none());
+
+        let response = parse_response(raw, false).unwrap().unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("content-type"), Some("text/plain"));
+        assert_eq!(response.body, b"hello");
+    }
+
+    #[test]
+    fn chunked() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\n\r\n";
+        assert!(parse_response(&raw[..raw.len() - 8], false)
+            .unwrap()
+            .is_none());
+
+        let response = parse_response(raw, false).unwrap().unwrap();
+        assert_eq!(response.body, b"hello, world");
+
+        let huge =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\nhello\r\n";
+        assert!(parse_response(huge, false).is_err());
+    }
+
+    #[tokio::test]
+    async fn request_heads() {
+        let mut request: &[u8] = b"GET /metrics HTTP/1.1\r\nhost: a\r\n\r\n";
+        let head = read_request_head(&mut request).await.unwrap();
+        assert!(head.starts_with(b"GET /metrics "));
+
+        let mut cut_off: &[u8] = b"GET / HTTP/1.1\r\n";
+        assert!(read_request_head(&mut cut_off).await.is_none());
+
+        let huge = vec![b'a'; 3 * MAX_REQUEST_LEN];
+        let head = read_request_head(&mut huge.as_slice()).await.unwrap();
+        assert!(head.len() <= MAX_REQUEST_LEN + 1024);
+    }
+
+    #[test]
+    fn read_to_close() {
+        let raw = b"HTTP/1.0 404 Not Found\r\n\r\nmissing";
+        assert!(parse_response(raw, false).unwrap().is_none());
+
+        let response = parse_response(raw, true).unwrap().unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.version, "HTTP/1.0");
+        let url = Url::parse("https://example.com/x").unwrap();
+        let err = response.error_for_status(&url).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "GET https://example.com/x: HTTP 404: missing"
+        );
+    }
+
+    #[tokio::test]
+    async fn head_request() {
+        use tokio::io::duplex;
+
+        let (client, mut server) = duplex(1024);
+        let server = tokio::spawn(async move {
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(server.read_u8().await.unwrap());
+            }
+            // no body follows, despite the length, and the connection stays open
+            server
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 1256\r\nAlt-Svc: h3=\":443\"\r\n\r\n",
+                )
+                .await
+                .unwrap();
+            (server, String::from_utf8(request).unwrap())
+        });
+
+        let response = request(client, "HEAD", "/", "example.com").await.unwrap();
+        let (_server, request) = server.await.unwrap();
+        assert!(request.starts_with("HEAD / HTTP/1.1\r\nHost: example.com\r\n"));
+        assert_eq!(response.version, "HTTP/1.1");
+        assert_eq!(response.header("alt-svc"), Some("h3=\":443\""));
+        assert!(response.body.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+//! Internationalized domain names: the punycode of RFC 3492 and the `xn--`
+//! labels of IDNA, to show `xn--bcher-kva.example` as `bücher.example` and
+//! to send `bücher.example` as the former in the SNI.
+//!
+//! Labels are only lowercased, not run through the full UTS #46 mapping and
+//! normalization, which covers the names people type.
+
+use color_eyre::eyre::{bail, eyre, Result};
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 0x80;
+
+/// The prefix of a label that's punycode.
+const ACE_PREFIX: &str = "xn--";
+
+/// `host` with every non-ASCII label encoded as an `xn--` label. ASCII
+/// hosts, including IP addresses, are returned as they are.
+pub(crate) fn to_ascii(host: &str) -> Result<String> {
+    if host.is_ascii() {
+        return Ok(host.to_string());
+    }
+
+    let labels = host.split('.').map(|label| {
+        if label.is_ascii() {
+            return Ok(label.to_string());
+        }
+        let lower: Vec<char> = label.chars().flat_map(char::to_lowercase).collect();
+        let encoded = encode(&lower).ok_or_else(|| eyre!("can't encode {label:?} as punycode"))?;
+        let label = format!("{ACE_PREFIX}{encoded}");
+        if label.len() > 63 {
+            bail!("{label} is longer than the 63 bytes a DNS label can be");
+        }
+        Ok(label)
+    });
+    Ok(labels.collect::<Result<Vec<_>>>()?.join("."))
+}
+
+/// `name` with its `xn--` labels decoded, or `None` when it has none (or
+/// none that decode), so there's nothing to show besides the name itself.
+pub(crate) fn to_unicode(name: &str) -> Option<String> {
+    let mut decoded_any = false;
+    let labels: Vec<String> = name
+        .split('.')
+        .map(|label| {
+            let decoded = label
+                .get(..ACE_PREFIX.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(ACE_PREFIX))
+                .and_then(|_| decode(&label[ACE_PREFIX.len()..]));
+            match decoded {
+                Some(decoded) => {
+                    decoded_any = true;
+                    decoded
+                }
+                None => label.to_string(),
+            }
+        })
+        .collect();
+    decoded_any.then(|| labels.join("."))
+}
+
+/// The bias after a code point, RFC 3492 section 6.1.
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first { delta / DAMP } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias {
+        T_MIN
+    } else if k >= bias + T_MAX {
+        T_MAX
+    } else {
+        k - bias
+    }
+}
+
+fn digit(value: u32) -> char {
+    match value {
+        0..=25 => (b'a' + value as u8) as char,
+        _ => (b'0' + (value - 26) as u8) as char,
+    }
+}
+
+fn digit_value(byte: u8) -> Option<u32> {
+    match byte {
+        b'a'..=b'z' => Some(u32::from(byte - b'a')),
+        b'A'..=b'Z' => Some(u32::from(byte - b'A')),
+        b'0'..=b'9' => Some(u32::from(byte - b'0') + 26),
+        _ => None,
+    }
+}
+
+/// Punycode encode `input`, without the `xn--` prefix. `None` on overflow.
+fn encode(input: &[char]) -> Option<String> {
+    let mut output: String = input.iter().filter(|c| c.is_ascii()).collect();
+    let basic = output.len() as u32;
+    if basic > 0 {
+        output.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut handled = basic;
+    while (handled as usize) < input.len() {
+        let next = input.iter().map(|&c| c as u32).filter(|&c| c >= n).min()?;
+        delta = delta.checked_add((next - n).checked_mul(handled + 1)?)?;
+        n = next;
+
+        for c in input.iter().map(|&c| c as u32) {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c != n {
+                continue;
+            }
+            let mut q = delta;
+            let mut k = BASE;
+            loop {
+                let t = threshold(k, bias);
+                if q < t {
+                    break;
+                }
+                output.push(digit(t + (q - t) % (BASE - t)));
+                q = (q - t) / (BASE - t);
+                k += BASE;
+            }
+            output.push(digit(q));
+            bias = adapt(delta, handled + 1, handled == basic);
+            delta = 0;
+            handled += 1;
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+/// Decode punycode, without the `xn--` prefix. `None` when it isn't valid.
+fn decode(input: &str) -> Option<String> {
+    let (basic, digits) = match input.rfind('-') {
+        Some(at) => (&input[..at], &input[at + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() || digits.is_empty() {
+        return None;
+    }
+
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = digits.bytes().peekable();
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let value = digit_value(digits.next()?)?;
+            i = i.checked_add(value.checked_mul(weight)?)?;
+            let t = threshold(k, bias);
+            if value < t {
+                break;
+            }
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let cases = [
+            ("bücher.example", "xn--bcher-kva.example"),
+            ("München.de", "xn--mnchen-3ya.de"),
+            ("*.bücher.example", "*.xn--bcher-kva.example"),
+            ("他们为什么不说中文", "xn--ihqwcrb4cv8a8dqg056pqjye"),
+        ];
+        for (unicode, ascii) in cases {
+            assert_eq!(to_ascii(unicode).unwrap(), ascii);
+            assert_eq!(to_unicode(ascii).unwrap(), unicode.to_lowercase());
+        }
+
+        assert_eq!(to_ascii("example.com").unwrap(), "example.com");
+        assert_eq!(to_ascii("2001:db8::1").unwrap(), "2001:db8::1");
+        assert_eq!(to_unicode("example.com"), None);
+        // not valid punycode, so shown as it is
+        assert_eq!(to_unicode("xn--.example"), None);
+        assert_eq!(
+            to_unicode("xn--mnchen-3ya.xn--!!").unwrap(),
+            "münchen.xn--!!"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Running one job per target at once, for the commands that fan out over
+//! many hosts. Each job gets its own timeout, so one slow host doesn't hold
+//! up the rest, and `--fail-fast` stops the run at the first failure.
+
+use std::{future::Future, time::Duration};
+
+use color_eyre::eyre::{Context, Result};
+use tokio::task::JoinSet;
+
+/// How a target's job ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Outcome<T> {
+    Done(T),
+    /// The job didn't finish within the timeout.
+    TimedOut(Duration),
+    /// The job was stopped, or never started, because another target failed
+    /// first with `--fail-fast`.
+    Skipped,
+}
+
+/// Runs a job on every target at once.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Runner {
+    pub timeout: Duration,
+    pub fail_fast: bool,
+}
+
+impl Runner {
+    /// Run `job` on each of `targets`, returning every target with how its
+    /// job ended, in the order of `targets`. With `fail_fast`, the first job
+    /// that times out or whose output is `failed` stops the others.
+    pub async fn run<I, T, Fut>(
+        &self,
+        targets: Vec<I>,
+        job: impl Fn(&I) -> Fut,
+        failed: impl Fn(&T) -> bool,
+    ) -> Result<Vec<(I, Outcome<T>)>>
+    where
+        T: Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        let mut jobs = JoinSet::new();
+        for (i, target) in targets.iter().enumerate() {
+            let job = tokio::time::timeout(self.timeout, job(target));
+            jobs.spawn(async move { (i, job.await) });
+        }
+
+        let mut outcomes: Vec<Option<Outcome<T>>> = targets.iter().map(|_| None).collect();
+        while let Some(joined) = jobs.join_next().await {
+            let (i, output) = joined.context("running a job")?;
+            let outcome = match output {
+                Ok(output) => Outcome::Done(output),
+                Err(_) => Outcome::TimedOut(self.timeout),
+            };
+            let stop = match &outcome {
+                Outcome::Done(output) => failed(output),
+                _ => true,
+            };
+            outcomes[i] = Some(outcome);
+            if self.fail_fast && stop {
+                jobs.abort_all();
+                break;
+            }
+        }
+
+        Ok(targets
+            .into_iter()
+            .zip(outcomes)
+            .map(|(target, outcome)| (target, outcome.unwrap_or(Outcome::Skipped)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A job that takes `millis` milliseconds.
+    async fn sleep(millis: u64) -> u64 {
+        tokio::time::sleep(Duration::from_millis(millis)).await;
+        millis
+    }
+
+    #[tokio::test]
+    async fn timeouts_and_fail_fast() {
+        let runner = Runner {
+            timeout: Duration::from_millis(200),
+            fail_fast: false,
+        };
+        let targets = vec![10, 5000, 20];
+        let outcomes = runner
+            .run(targets.clone(), |millis| sleep(*millis), |_| false)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcomes,
+            [
+                (10, Outcome::Done(10)),
+                (5000, Outcome::TimedOut(Duration::from_millis(200))),
+                (20, Outcome::Done(20)),
+            ]
+        );
+
+        // the 11ms job fails, so the slow one is stopped
+        let runner = Runner {
+            fail_fast: true,
+            ..runner
+        };
+        let outcomes = runner
+            .run(
+                vec![11, 5000],
+                |millis| sleep(*millis),
+                |millis| millis % 2 == 1,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            outcomes,
+            [(11, Outcome::Done(11)), (5000, Outcome::Skipped)]
+        );
+    }
+}
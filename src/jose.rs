@@ -0,0 +1,485 @@
+//! Helpers for the JOSE (JWT/JWS/JWK) side of WebPKI: decoding compact
+//! tokens and pulling `x5c` certificate chains out of headers and JWKS
+//! documents.
+
+use boring::{
+    bn::BigNum,
+    ec::{EcGroup, EcKey},
+    ecdsa::EcdsaSig,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{Id, PKey, PKeyRef, Public},
+    rsa::{Padding, Rsa},
+    sign::{RsaPssSaltlen, Verifier},
+    x509::X509,
+};
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use jiff::Timestamp;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Decode unpadded base64url, as used by every segment of a compact JWT.
+pub(crate) fn decode_base64url(data: &str) -> Result<Vec<u8>> {
+    let mut standard: String = data
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    while standard.len() % 4 != 0 {
+        standard.push('=');
+    }
+
+    Ok(boring::base64::decode_block(&standard)?)
+}
+
+/// Split a compact JWT/JWS into its (header, payload, signature) segments.
+pub(crate) fn split_compact(token: &str) -> Result<(&str, &str, &str)> {
+    let token = token.trim();
+    let token = token.strip_prefix("Bearer ").unwrap_or(token);
+
+    match token.split('.').collect::<Vec<_>>()[..] {
+        [header, payload, signature] => Ok((header, payload, signature)),
+        ref segments => bail!(
+            "expected a compact JWT with 3 `.` separated segments, found {}",
+            segments.len()
+        ),
+    }
+}
+
+/// Decode one base64url JSON segment of a compact JWT.
+pub(crate) fn decode_json_segment(segment: &str, what: &str) -> Result<Value> {
+    let data = decode_base64url(segment).with_context(|| format!("decoding JWT {what}"))?;
+    serde_json::from_slice(&data).with_context(|| format!("JWT {what} is not JSON"))
+}
+
+/// One decoded `x5c` array.
+#[derive(Debug, Clone)]
+pub(crate) struct X5c {
+    /// The `kid` of the JWK (or JWS header) the chain was found in.
+    pub kid: Option<String>,
+    pub certs: Vec<X509>,
+}
+
+/// Find every `x5c` array in `value`, e.g. a JWS header or each key of a JWKS
+/// document, and decode its certificates in order. Each array is kept as its
+/// own chain.
+pub(crate) fn x5c_chains(value: &Value) -> Result<Vec<X5c>> {
+    let mut chains = Vec::new();
+    collect_x5c(value, &mut chains)?;
+    Ok(chains)
+}
+
+fn collect_x5c(value: &Value, chains: &mut Vec<X5c>) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if key == "x5c" {
+                    let entries = value
+                        .as_array()
+                        .ok_or_else(|| eyre!("`x5c` is not an array"))?;
+                    let certs = entries
+                        .iter()
+                        .enumerate()
+                        .map(|(i, entry)| x5c_entry(entry).with_context(|| format!("x5c[{i}]")))
+                        .collect::<Result<_>>()?;
+                    chains.push(X5c {
+                        kid: map.get("kid").and_then(Value::as_str).map(String::from),
+                        certs,
+                    });
+                } else {
+                    collect_x5c(value, chains)?;
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_x5c(value, chains)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// `x5c` entries are standard (not url-safe) padded base64 DER.
+fn x5c_entry(entry: &Value) -> Result<X509> {
+    let encoded = entry
+        .as_str()
+        .ok_or_else(|| eyre!("entry is not a string"))?;
+    let der = boring::base64::decode_block(encoded.trim()).context("decoding base64")?;
+    Ok(X509::from_der(&der)?)
+}
+
+/// A decoded, but not necessarily verified, compact JWT.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DecodedJwt {
+    pub header: Value,
+    pub claims: Value,
+    pub alg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_before: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<Timestamp>,
+    pub signature: SignatureCheck,
+    #[serde(skip)]
+    signing_input: String,
+    #[serde(skip)]
+    signature_bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "reason")]
+pub enum SignatureCheck {
+    /// No key was given to verify against.
+    #[default]
+    Unverified,
+    Valid,
+    Invalid(String),
+}
+
+impl DecodedJwt {
+    pub fn decode(token: &str) -> Result<Self> {
+        let (header, claims, signature) = split_compact(token)?;
+        let signing_input = format!("{header}.{claims}");
+        let header = decode_json_segment(header, "header")?;
+        let claims = decode_json_segment(claims, "claims")?;
+        let signature_bytes = decode_base64url(signature).context("decoding JWT signature")?;
+
+        let string = |value: &Value, key: &str| value.get(key)?.as_str().map(String::from);
+        let time = |key: &str| Timestamp::from_second(claims.get(key)?.as_i64()?).ok();
+
+        Ok(Self {
+            alg: string(&header, "alg"),
+            kid: string(&header, "kid"),
+            issued_at: time("iat"),
+            not_before: time("nbf"),
+            expires: time("exp"),
+            signature: SignatureCheck::Unverified,
+            header,
+            claims,
+            signing_input,
+            signature_bytes,
+        })
+    }
+
+    /// Verify the signature against `key`, recording the outcome in
+    /// [`DecodedJwt::signature`].
+    pub fn verify(&mut self, key: &PKeyRef<Public>) {
+        let alg = self.alg.as_deref().unwrap_or("none");
+        self.signature = match verify_signature(
+            alg,
+            key,
+            self.signing_input.as_bytes(),
+            &self.signature_bytes,
+        ) {
+            Ok(true) => SignatureCheck::Valid,
+            Ok(false) => SignatureCheck::Invalid("signature does not match".to_string()),
+            Err(err) => SignatureCheck::Invalid(format!("{err:#}")),
+        };
+    }
+}
+
+/// How a JWS `alg` signs, see RFC 7518 section 3.1.
+enum Scheme {
+    Rsa,
+    RsaPss,
+    /// ECDSA on a fixed curve, with the signature as the raw `r || s`, each
+    /// the size of the curve's field.
+    Ecdsa {
+        curve: Nid,
+        field_size: usize,
+    },
+}
+
+fn verify_signature(
+    alg: &str,
+    key: &PKeyRef<Public>,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    let (digest, scheme) = match alg {
+        "RS256" => (MessageDigest::sha256(), Scheme::Rsa),
+        "RS384" => (MessageDigest::sha384(), Scheme::Rsa),
+        "RS512" => (MessageDigest::sha512(), Scheme::Rsa),
+        "PS256" => (MessageDigest::sha256(), Scheme::RsaPss),
+        "PS384" => (MessageDigest::sha384(), Scheme::RsaPss),
+        "PS512" => (MessageDigest::sha512(), Scheme::RsaPss),
+        "ES256" => (
+            MessageDigest::sha256(),
+            Scheme::Ecdsa {
+                curve: Nid::X9_62_PRIME256V1,
+                field_size: 32,
+            },
+        ),
+        "ES384" => (
+            MessageDigest::sha384(),
+            Scheme::Ecdsa {
+                curve: Nid::SECP384R1,
+                field_size: 48,
+            },
+        ),
+        "ES512" => (
+            MessageDigest::sha512(),
+            Scheme::Ecdsa {
+                curve: Nid::SECP521R1,
+                field_size: 66,
+            },
+        ),
+        "EdDSA" => {
+            let mut verifier = Verifier::new_without_digest(key)?;
+            return Ok(verifier.verify_oneshot(signature, data)?);
+        }
+        "HS256" | "HS384" | "HS512" => {
+            bail!("{alg} is an HMAC, it can't be verified with a public key")
+        }
+        _ => bail!("unsupported JWT algorithm {alg:?}"),
+    };
+
+    match scheme {
+        Scheme::Rsa | Scheme::RsaPss => {
+            if key.id() != Id::RSA {
+                bail!("{alg} needs an RSA key");
+            }
+            let mut verifier = Verifier::new(digest, key)?;
+            if let Scheme::RsaPss = scheme {
+                verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+                verifier.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+            }
+            Ok(verifier.verify_oneshot(signature, data)?)
+        }
+        Scheme::Ecdsa { curve, field_size } => {
+            if key.id() != Id::EC || key.ec_key()?.group().curve_name() != Some(curve) {
+                bail!(
+                    "{alg} needs a {} key",
+                    curve.short_name().unwrap_or("matching EC")
+                );
+            }
+            if signature.len() != 2 * field_size {
+                bail!(
+                    "{alg} signatures are {} bytes, found {}",
+                    2 * field_size,
+                    signature.len()
+                );
+            }
+
+            // JWS ECDSA signatures are the raw `r || s`, boring wants DER.
+            let (r, s) = signature.split_at(field_size);
+            let signature =
+                EcdsaSig::from_private_components(BigNum::from_slice(r)?, BigNum::from_slice(s)?)?
+                    .to_der()?;
+            let mut verifier = Verifier::new(digest, key)?;
+            Ok(verifier.verify_oneshot(&signature, data)?)
+        }
+    }
+}
+
+/// Convert a public JWK (RFC 7517) into a key.
+pub(crate) fn jwk_to_pkey(jwk: &Value) -> Result<PKey<Public>> {
+    let field = |name: &str| -> Result<Vec<u8>> {
+        let value = jwk
+            .get(name)
+            .and_then(Value::as_str)
+            .ok_or_else(|| eyre!("JWK is missing `{name}`"))?;
+        decode_base64url(value)
+    };
+
+    match jwk.get("kty").and_then(Value::as_str) {
+        Some("RSA") => {
+            let rsa = Rsa::from_public_components(
+                BigNum::from_slice(&field("n")?)?,
+                BigNum::from_slice(&field("e")?)?,
+            )?;
+            Ok(PKey::from_rsa(rsa)?)
+        }
+        Some("EC") => {
+            let nid = match jwk.get("crv").and_then(Value::as_str) {
+                Some("P-256") => Nid::X9_62_PRIME256V1,
+                Some("P-384") => Nid::SECP384R1,
+                Some("P-521") => Nid::SECP521R1,
+                crv => bail!("unsupported JWK curve {crv:?}"),
+            };
+            let group = EcGroup::from_curve_name(nid)?;
+            let ec = EcKey::from_public_key_affine_coordinates(
+                &group,
+                &BigNum::from_slice(&field("x")?)?,
+                &BigNum::from_slice(&field("y")?)?,
+            )?;
+            Ok(PKey::from_ec_key(ec)?)
+        }
+        Some("OKP") if jwk.get("crv").and_then(Value::as_str) == Some("Ed25519") => {
+            // wrap the raw key in a SubjectPublicKeyInfo for id-Ed25519
+            let mut spki = hex::decode("302a300506032b6570032100")?;
+            spki.extend_from_slice(&field("x")?);
+            Ok(PKey::public_key_from_der(&spki)?)
+        }
+        kty => bail!("unsupported JWK key type {kty:?}"),
+    }
+}
+
+/// Pick the key to verify with out of a JWK or JWKS document: the one with a
+/// matching `kid` if there is one, otherwise the only key.
+pub(crate) fn select_jwk<'a>(document: &'a Value, kid: Option<&str>) -> Result<&'a Value> {
+    let Some(keys) = document.get("keys").and_then(Value::as_array) else {
+        return Ok(document);
+    };
+
+    if let Some(kid) = kid {
+        if let Some(key) = keys
+            .iter()
+            .find(|key| key.get("kid").and_then(Value::as_str) == Some(kid))
+        {
+            return Ok(key);
+        }
+    }
+
+    match &keys[..] {
+        [key] => Ok(key),
+        [] => bail!("JWKS has no keys"),
+        _ => bail!("JWKS has {} keys and none match kid {kid:?}", keys.len()),
+    }
+}
+
+/// Get the JSON to search for `x5c` chains in: `data` itself when it's a JSON
+/// document, otherwise the header of the compact JWT it holds.
+pub(crate) fn x5c_source(data: &[u8]) -> Result<Value> {
+    if let Ok(value) = serde_json::from_slice(data) {
+        return Ok(value);
+    }
+
+    let token = std::str::from_utf8(data).context("input is neither JSON nor a JWT")?;
+    let (header, _, _) = split_compact(token)?;
+    decode_json_segment(header, "header")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64url_without_padding() {
+        assert_eq!(
+            decode_base64url("eyJhbGciOiJub25lIn0").unwrap(),
+            br#"{"alg":"none"}"#
+        );
+        assert_eq!(decode_base64url("-_8").unwrap(), [0xfb, 0xff]);
+    }
+
+    /// A compact ES256 token signed by a fresh P-256 key, and that key.
+    fn es256_token() -> (String, PKey<Public>) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let header = "eyJhbGciOiJFUzI1NiJ9"; // {"alg":"ES256"}
+        let claims = "eyJzdWIiOiJwbHMifQ"; // {"sub":"pls"}
+        let mut signer = boring::sign::Signer::new(MessageDigest::sha256(), &key).unwrap();
+        let der = signer
+            .sign_oneshot_to_vec(format!("{header}.{claims}").as_bytes())
+            .unwrap();
+        let sig = EcdsaSig::from_der(&der).unwrap();
+        let pad = |n: &boring::bn::BigNumRef| {
+            let n = n.to_vec();
+            [vec![0; 32 - n.len()], n].concat()
+        };
+        let raw = [pad(sig.r()), pad(sig.s())].concat();
+        let signature = boring::base64::encode_block(&raw)
+            .trim_end_matches('=')
+            .replace('+', "-")
+            .replace('/', "_");
+
+        let public = PKey::public_key_from_der(&key.public_key_to_der().unwrap()).unwrap();
+        (format!("{header}.{claims}.{signature}"), public)
+    }
+
+    #[test]
+    fn verify_es256() {
+        let (token, public) = es256_token();
+        let mut jwt = DecodedJwt::decode(&token).unwrap();
+        jwt.verify(&public);
+        assert_eq!(jwt.signature, SignatureCheck::Valid);
+        assert_eq!(jwt.claims["sub"], "pls");
+    }
+
+    #[test]
+    fn es256_needs_a_p256_key() {
+        let (token, _) = es256_token();
+        let group = EcGroup::from_curve_name(Nid::SECP384R1).unwrap();
+        let p384 = EcKey::generate(&group).unwrap();
+        let p384 = PKey::public_key_from_der(
+            &PKey::from_ec_key(p384)
+                .unwrap()
+                .public_key_to_der()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let mut jwt = DecodedJwt::decode(&token).unwrap();
+        jwt.verify(&p384);
+        assert!(matches!(jwt.signature, SignatureCheck::Invalid(_)));
+    }
+
+    #[test]
+    fn es256_signature_length() {
+        let (token, public) = es256_token();
+        let (header, claims, _) = split_compact(&token).unwrap();
+        // 32 byte r || s padded out to the length of an ES384 signature
+        let signature = boring::base64::encode_block(&[1u8; 96])
+            .replace('+', "-")
+            .replace('/', "_");
+
+        let mut jwt = DecodedJwt::decode(&format!("{header}.{claims}.{signature}")).unwrap();
+        jwt.verify(&public);
+        assert_eq!(
+            jwt.signature,
+            SignatureCheck::Invalid("ES256 signatures are 64 bytes, found 96".to_string())
+        );
+    }
+
+    #[test]
+    fn non_ascii_alg_does_not_panic() {
+        let (token, public) = es256_token();
+        let (_, claims, signature) = split_compact(&token).unwrap();
+        for alg in ["éx5", "€256", "E"] {
+            let header = boring::base64::encode_block(
+                serde_json::json!({ "alg": alg }).to_string().as_bytes(),
+            )
+            .trim_end_matches('=')
+            .replace('+', "-")
+            .replace('/', "_");
+
+            let mut jwt = DecodedJwt::decode(&format!("{header}.{claims}.{signature}")).unwrap();
+            jwt.verify(&public);
+            assert!(matches!(jwt.signature, SignatureCheck::Invalid(_)));
+        }
+    }
+
+    #[test]
+    fn x5c_in_jwks() {
+        let pem = include_bytes!("../test-data/certs/lan-fish.pem");
+        let der = X509::from_pem(pem).unwrap().to_der().unwrap();
+        let entry = boring::base64::encode_block(&der);
+        let jwks = serde_json::json!({
+            "keys": [
+                { "kty": "EC", "kid": "a", "x5c": [entry, entry] },
+                { "kty": "EC", "kid": "b", "x5c": [entry] },
+            ]
+        });
+
+        // one chain per key, not one merged list
+        let chains = x5c_chains(&jwks).unwrap();
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0].kid.as_deref(), Some("a"));
+        assert_eq!(chains[0].certs.len(), 2);
+        assert_eq!(chains[1].kid.as_deref(), Some("b"));
+        assert_eq!(chains[1].certs.len(), 1);
+        assert_eq!(chains[1].certs[0].to_der().unwrap(), der);
+    }
+}
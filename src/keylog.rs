@@ -0,0 +1,263 @@
+//! `SSLKEYLOGFILE`s, the NSS key log format that browsers, curl and `connect
+//! --keylog` write TLS secrets in for Wireshark: a `LABEL CLIENT_RANDOM
+//! SECRET` line per secret, both in hex, and `#` comments.
+//!
+//! The secrets are grouped by the client random they're for, one session per
+//! handshake, to tell whether Wireshark has everything it needs to decrypt
+//! each session.
+
+use serde::Serialize;
+
+/// The TLS 1.3 secrets Wireshark needs to decrypt a whole session.
+const TLS13_REQUIRED: [&str; 4] = [
+    "CLIENT_HANDSHAKE_TRAFFIC_SECRET",
+    "SERVER_HANDSHAKE_TRAFFIC_SECRET",
+    "CLIENT_TRAFFIC_SECRET_0",
+    "SERVER_TRAFFIC_SECRET_0",
+];
+
+/// The other TLS 1.3 labels, which only some sessions have.
+const TLS13_OPTIONAL: [&str; 5] = [
+    "CLIENT_EARLY_TRAFFIC_SECRET",
+    "EARLY_EXPORTER_SECRET",
+    "EXPORTER_SECRET",
+    "ECH_SECRET",
+    "ECH_CONFIG",
+];
+
+/// The TLS 1.2 (and earlier) master secret.
+const CLIENT_RANDOM: &str = "CLIENT_RANDOM";
+
+/// An RSA key exchange's premaster secret, keyed by the first 8 bytes of
+/// the encrypted premaster secret rather than the client random.
+const RSA: &str = "RSA";
+
+/// The secrets in a key log file.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Keylog {
+    /// Lines with a secret, not counting comments and blank lines.
+    pub lines: usize,
+    /// In the order they first appear.
+    pub sessions: Vec<KeylogSession>,
+    pub issues: Vec<KeylogIssue>,
+}
+
+/// The secrets for one handshake.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeylogSession {
+    /// The client random in hex, or the start of the encrypted premaster
+    /// secret for `RSA` lines.
+    pub client_random: String,
+    /// `TLS 1.3`, `TLS 1.2` or `unknown`, from the labels.
+    pub version: String,
+    pub secrets: Vec<KeylogSecret>,
+    /// The labels needed to decrypt the session that aren't there.
+    pub missing: Vec<String>,
+}
+
+/// One line of the file.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeylogSecret {
+    pub label: String,
+    /// The line it's on, from 1.
+    pub line: usize,
+    pub bytes: usize,
+    /// The secret in hex. Left out unless asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+/// A line that isn't a valid secret.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeylogIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Keylog {
+    /// Parse a key log. Lines that aren't valid secrets are skipped and
+    /// reported as issues.
+    pub fn parse(data: &[u8]) -> Self {
+        let mut keylog = Keylog::default();
+        let text = String::from_utf8_lossy(data);
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            keylog.lines += 1;
+            match parse_line(line) {
+                Ok((label, client_random, secret)) => {
+                    keylog.add(label, client_random, secret, line_number)
+                }
+                Err(message) => keylog.issues.push(KeylogIssue {
+                    line: line_number,
+                    message,
+                }),
+            }
+        }
+
+        for session in &mut keylog.sessions {
+            session.classify();
+        }
+        keylog
+    }
+
+    fn add(&mut self, label: &str, client_random: &str, secret: &str, line: usize) {
+        let client_random = client_random.to_ascii_lowercase();
+        let index = match self
+            .sessions
+            .iter()
+            .position(|session| session.client_random == client_random)
+        {
+            Some(index) => index,
+            None => {
+                self.sessions.push(KeylogSession {
+                    client_random,
+                    ..Default::default()
+                });
+                self.sessions.len() - 1
+            }
+        };
+        self.sessions[index].secrets.push(KeylogSecret {
+            label: label.to_string(),
+            line,
+            bytes: secret.len() / 2,
+            secret: Some(secret.to_ascii_lowercase()),
+        });
+    }
+
+    /// Drop the secrets themselves, keeping what they are.
+    pub fn redact(&mut self) {
+        for session in &mut self.sessions {
+            for secret in &mut session.secrets {
+                secret.secret = None;
+            }
+        }
+    }
+
+    /// How many sessions have everything needed to decrypt them.
+    pub fn complete(&self) -> usize {
+        self.sessions
+            .iter()
+            .filter(|session| session.missing.is_empty())
+            .count()
+    }
+}
+
+impl KeylogSession {
+    /// Work out the version and the missing labels from the labels there.
+    fn classify(&mut self) {
+        let has = |label: &str| self.secrets.iter().any(|secret| secret.label == label);
+        let tls13 = TLS13_REQUIRED
+            .iter()
+            .chain(&TLS13_OPTIONAL)
+            .any(|label| has(label));
+
+        (self.version, self.missing) = if has(CLIENT_RANDOM) || has(RSA) {
+            ("TLS 1.2".to_string(), Vec::new())
+        } else if tls13 {
+            let missing = TLS13_REQUIRED
+                .iter()
+                .filter(|label| !has(label))
+                .map(|label| label.to_string())
+                .collect();
+            ("TLS 1.3".to_string(), missing)
+        } else {
+            // only labels this doesn't know
+            ("unknown".to_string(), Vec::new())
+        };
+    }
+}
+
+/// Split and check a line: its label, client random and secret.
+fn parse_line(line: &str) -> Result<(&str, &str, &str), String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [label, client_random, secret] = fields[..] else {
+        return Err(format!(
+            "expected `LABEL CLIENT_RANDOM SECRET`, got {} fields",
+            fields.len()
+        ));
+    };
+
+    let known = label == CLIENT_RANDOM
+        || label == RSA
+        || TLS13_REQUIRED.contains(&label)
+        || TLS13_OPTIONAL.contains(&label);
+    if !known {
+        return Err(format!("unknown label {label}"));
+    }
+
+    let random_len = if label == RSA { 8 } else { 32 };
+    if !is_hex(client_random) || client_random.len() != random_len * 2 {
+        return Err(match label {
+            RSA => "the encrypted premaster secret prefix isn't 8 bytes of hex".to_string(),
+            _ => "the client random isn't 32 bytes of hex".to_string(),
+        });
+    }
+    if !is_hex(secret) || secret.len() % 2 != 0 {
+        return Err(format!("the {label} secret isn't hex"));
+    }
+    let bytes = secret.len() / 2;
+    match label {
+        CLIENT_RANDOM | RSA if bytes != 48 => {
+            Err(format!("the {label} secret is {bytes} bytes, not 48"))
+        }
+        // the size of the hash of the cipher suite, SHA-256 or SHA-384
+        _ if TLS13_REQUIRED.contains(&label) && bytes != 32 && bytes != 48 => {
+            Err(format!("the {label} secret is {bytes} bytes, not 32 or 48"))
+        }
+        _ => Ok((label, client_random, secret)),
+    }
+}
+
+fn is_hex(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_secrets_by_session() {
+        let random13 = "ab".repeat(32);
+        let random12 = "CD".repeat(32);
+        let keylog = format!(
+            "# SSL/TLS secrets log file, generated by NSS\n\
+             CLIENT_HANDSHAKE_TRAFFIC_SECRET {random13} {s32}\n\
+             SERVER_HANDSHAKE_TRAFFIC_SECRET {random13} {s32}\n\
+             CLIENT_TRAFFIC_SECRET_0 {random13} {s32}\n\
+             CLIENT_RANDOM {random12} {s48}\n\
+             \n\
+             CLIENT_RANDOM {random12}\n\
+             SERVER_TRAFFIC_SECRET_1 {random13} {s32}\n\
+             CLIENT_RANDOM abcd {s48}\n",
+            s32 = "01".repeat(32),
+            s48 = "02".repeat(48),
+        );
+
+        let mut keylog = Keylog::parse(keylog.as_bytes());
+        assert_eq!(keylog.lines, 7);
+        assert_eq!(keylog.sessions.len(), 2);
+
+        let tls13 = &keylog.sessions[0];
+        assert_eq!(tls13.version, "TLS 1.3");
+        assert_eq!(tls13.secrets.len(), 3);
+        assert_eq!(tls13.missing, ["SERVER_TRAFFIC_SECRET_0"]);
+
+        let tls12 = &keylog.sessions[1];
+        assert_eq!(tls12.client_random, random12.to_lowercase());
+        assert_eq!(tls12.version, "TLS 1.2");
+        assert!(tls12.missing.is_empty());
+        assert_eq!(keylog.complete(), 1);
+
+        let issues: Vec<usize> = keylog.issues.iter().map(|issue| issue.line).collect();
+        assert_eq!(issues, [7, 8, 9]);
+
+        assert_eq!(keylog.sessions[1].secrets[0].bytes, 48);
+        keylog.redact();
+        assert!(keylog.sessions[1].secrets[0].secret.is_none());
+    }
+}
@@ -1,10 +1,54 @@
+mod acme;
 mod args;
+mod asn1;
+mod caa;
+mod cache;
+mod chain;
+mod clipboard;
 pub mod commands;
 mod components;
+mod config;
 mod connection;
+mod crl;
+mod dane;
+mod der;
+mod dns;
+mod explain;
+mod field;
+mod filter;
+mod h2;
+mod hostname;
+mod http;
+mod idna;
+mod jobs;
+mod jose;
+mod keylog;
+mod lint;
+mod metrics;
+mod notify;
+mod oid;
+mod output;
+mod passphrase;
 mod pem;
+mod pkcs7;
+mod pkcs8;
+mod policy;
+mod progress;
+mod registry;
+mod revocation;
+mod secret;
+mod server;
+mod snippet;
+mod state;
+#[cfg(test)]
+mod test_data;
 mod theme;
+mod tls_config;
+mod verifier;
 mod x509;
 
-pub use args::{Cli, Command};
+pub use args::{Cli, Command, TraceOutput};
 pub use commands::CommandExt;
+pub use components::error::print_error;
+pub use output::Output;
+pub use progress::stderr as progress_stderr;
@@ -0,0 +1,267 @@
+//! Encoding problems that don't stop a cert from being parsed, or that are
+//! why it can't be: the things CAs have been caught out by, e.g. negative
+//! serials, long-form lengths that should be short and PrintableStrings with
+//! `@` in them. Reported alongside the cert rather than as errors.
+
+use std::collections::HashSet;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::der::{self, tag, Tlv};
+
+/// Deeper nesting than this isn't checked, so crafted input can't exhaust
+/// the stack.
+const MAX_DEPTH: usize = 64;
+
+/// One problem with how an entity is encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Lint {
+    /// What kind of problem it is, e.g. `non-canonical-length`, for
+    /// filtering.
+    pub code: String,
+    pub message: String,
+}
+
+impl Lint {
+    fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Problems with the DER itself, which boring either rejects or silently
+/// ignores: lengths not in their shortest form and bytes after the end of the
+/// first element.
+pub fn der_encoding(data: &[u8]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    // otherwise the parser's own error says what's wrong
+    if let Ok((tlv, len)) = der::read_tlv(data) {
+        check_lengths(tlv, 0, 0, &mut lints);
+        if len < data.len() {
+            lints.push(Lint::new(
+                "trailing-data",
+                format!(
+                    "{} bytes after the end of the DER, at offset {len}",
+                    data.len() - len
+                ),
+            ));
+        }
+    }
+    lints
+}
+
+/// Flag the lengths in `tlv`, which starts at `offset`, that aren't in their
+/// shortest form, as DER requires.
+fn check_lengths(tlv: Tlv, offset: usize, depth: usize, lints: &mut Vec<Lint>) {
+    let header = &tlv.raw[..tlv.raw.len() - tlv.value.len()];
+    let non_canonical = match header[1..] {
+        // long form with a leading zero byte, e.g. `82 00 80`
+        [first, 0, ..] if first & 0x80 != 0 => true,
+        // long form for a length that fits the short form, e.g. `81 05`
+        [0x81, len] => len < 0x80,
+        _ => false,
+    };
+    if non_canonical {
+        lints.push(Lint::new(
+            "non-canonical-length",
+            format!(
+                "the length of the element at offset {offset} is {}, for {} bytes",
+                hex::encode(&header[1..]),
+                tlv.value.len()
+            ),
+        ));
+    }
+
+    // only constructed elements have elements inside
+    if tlv.tag & 0x20 == 0 || depth >= MAX_DEPTH {
+        return;
+    }
+    let mut pos = 0;
+    while pos < tlv.value.len() {
+        let Ok((inner, len)) = der::read_tlv(&tlv.value[pos..]) else {
+            return;
+        };
+        check_lengths(inner, offset + header.len() + pos, depth + 1, lints);
+        pos += len;
+    }
+}
+
+/// Problems with the fields of a DER encoded cert: its serial, repeated
+/// extensions and the string types in its issuer and subject.
+pub fn cert(der: &[u8]) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let Ok(tbs) = der::tbs_certificate(der) else {
+        return lints;
+    };
+
+    let mut fields = tbs.reader();
+    let serial = fields
+        .read_optional(tag::context(0))
+        .and_then(|_| fields.read_tag(tag::INTEGER));
+    if let Ok(serial) = serial {
+        lints.extend(check_serial(serial.value));
+    }
+
+    if let Ok(extensions) = der::cert_extensions(der) {
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        for ext in extensions {
+            if !seen.insert(ext.oid.clone()) && reported.insert(ext.oid.clone()) {
+                let name = der::extension_name(&ext.oid).unwrap_or(&ext.oid);
+                lints.push(Lint::new(
+                    "duplicate-extension",
+                    format!("the {name} extension appears more than once"),
+                ));
+            }
+        }
+    }
+
+    if let Ok((issuer, subject)) = der::cert_names(der) {
+        check_name("issuer", issuer, &mut lints);
+        check_name("subject", subject, &mut lints);
+    }
+
+    lints
+}
+
+/// Serials have to be positive, RFC 5280 4.1.2.2.
+fn check_serial(value: &[u8]) -> Option<Lint> {
+    if value.first().is_some_and(|&first| first & 0x80 != 0) {
+        Some(Lint::new(
+            "negative-serial",
+            "the serial number is negative",
+        ))
+    } else if value.iter().all(|&byte| byte == 0) {
+        Some(Lint::new("zero-serial", "the serial number is zero"))
+    } else {
+        None
+    }
+}
+
+/// Check the string type of each attribute of a `Name ::= SEQUENCE OF SET OF
+/// SEQUENCE { type, value }`.
+fn check_name(which: &str, name: Tlv, lints: &mut Vec<Lint>) {
+    for rdn in name.reader().flatten() {
+        for attribute in rdn.reader().flatten() {
+            let mut fields = attribute.reader();
+            let (Ok(oid), Ok(value)) = (fields.read().and_then(|oid| oid.oid()), fields.read())
+            else {
+                continue;
+            };
+            let attribute = der::attribute_short_name(&oid).unwrap_or(&oid);
+            lints.extend(check_string(which, attribute, &oid, value));
+        }
+    }
+}
+
+fn check_string(which: &str, attribute: &str, oid: &str, value: Tlv) -> Option<Lint> {
+    // attributes RFC 5280 and the CA/B Forum pin to one type
+    let required = match oid {
+        // countryName, serialNumber and dnQualifier
+        "2.5.4.6" | "2.5.4.5" | "2.5.4.46" => Some(tag::PRINTABLE_STRING),
+        // emailAddress and domainComponent
+        "1.2.840.113549.1.9.1" | "0.9.2342.19200300.100.1.25" => Some(tag::IA5_STRING),
+        _ => None,
+    };
+    if value.tag == tag::PRINTABLE_STRING && !value.value.iter().all(|&byte| is_printable(byte)) {
+        let bad: String = String::from_utf8_lossy(value.value)
+            .chars()
+            .filter(|&c| !c.is_ascii() || !is_printable(c as u8))
+            .collect();
+        return Some(Lint::new(
+            "invalid-printable-string",
+            format!("the {which} {attribute} is a PrintableString with characters it can't hold: {bad:?}"),
+        ));
+    }
+
+    let message = match (value.tag, required) {
+        (found, Some(required)) if found != required => format!(
+            "the {which} {attribute} is a {}, not a {}",
+            string_type(found),
+            string_type(required),
+        ),
+        (tag::T61_STRING | tag::BMP_STRING | tag::UNIVERSAL_STRING, _) => format!(
+            "the {which} {attribute} is a {}, which is deprecated for UTF8String",
+            string_type(value.tag)
+        ),
+        _ => return None,
+    };
+    Some(Lint::new("wrong-string-type", message))
+}
+
+/// The characters a PrintableString can hold.
+fn is_printable(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b" '()+,-./:=?".contains(&byte)
+}
+
+fn string_type(tag: u8) -> &'static str {
+    match tag {
+        tag::UTF8_STRING => "UTF8String",
+        tag::PRINTABLE_STRING => "PrintableString",
+        tag::T61_STRING => "T61String",
+        tag::IA5_STRING => "IA5String",
+        tag::VISIBLE_STRING => "VisibleString",
+        tag::UNIVERSAL_STRING => "UniversalString",
+        tag::BMP_STRING => "BMPString",
+        _ => "non-string",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(lints: &[Lint]) -> Vec<&str> {
+        lints.iter().map(|lint| lint.code.as_str()).collect()
+    }
+
+    #[test]
+    fn real_certs_are_clean() {
+        let pem = include_bytes!("../test-data/certs/letsencrypt.org.pem");
+        let der = boring::x509::X509::from_pem(pem).unwrap().to_der().unwrap();
+        assert_eq!(der_encoding(&der), []);
+        assert_eq!(cert(&der), []);
+
+        let mut trailing = der.clone();
+        trailing.extend_from_slice(&[0, 0]);
+        assert_eq!(codes(&der_encoding(&trailing)), ["trailing-data"]);
+    }
+
+    #[test]
+    fn encoding_problems() {
+        // a SEQUENCE with a long-form length of 3, around an INTEGER with a
+        // zero-padded one
+        let der = [0x30, 0x81, 0x05, 0x02, 0x82, 0x00, 0x01, 0x07];
+        let lints = der_encoding(&der);
+        assert_eq!(
+            codes(&lints),
+            ["non-canonical-length", "non-canonical-length"]
+        );
+        assert!(lints[1].message.contains("offset 3"));
+
+        assert_eq!(check_serial(&[0xff]).unwrap().code, "negative-serial");
+        assert_eq!(check_serial(&[0x00]).unwrap().code, "zero-serial");
+        assert_eq!(check_serial(&[0x00, 0x80]), None);
+
+        let printable = Tlv {
+            tag: tag::PRINTABLE_STRING,
+            value: b"a@b",
+            raw: &[],
+        };
+        let lint = check_string("subject", "CN", "2.5.4.3", printable).unwrap();
+        assert_eq!(lint.code, "invalid-printable-string");
+        let country = Tlv {
+            tag: tag::UTF8_STRING,
+            value: b"US",
+            raw: &[],
+        };
+        let lint = check_string("issuer", "C", "2.5.4.6", country).unwrap();
+        assert_eq!(
+            lint.message,
+            "the issuer C is a UTF8String, not a PrintableString"
+        );
+    }
+}
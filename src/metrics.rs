@@ -0,0 +1,168 @@
+//! A `/metrics` endpoint in the Prometheus text format, for `watch
+//! --listen`.
+//!
+//! Like [`crate::http`], this is just enough HTTP for the job: one request
+//! per connection, and only `GET /metrics`.
+
+use std::{
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use jiff::Timestamp;
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    components::{expiry::Status, watch::WatchResult},
+    http,
+};
+
+/// The latest result for every host, shared with the server.
+pub(crate) type Latest = Arc<Mutex<Vec<WatchResult>>>;
+
+/// How long a client gets to send its request.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Answer scrapes on `listener` until the process exits.
+pub(crate) async fn serve(listener: TcpListener, latest: Latest) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                tracing::warn!("accepting a metrics connection: {err}");
+                continue;
+            }
+        };
+        let latest = latest.clone();
+        tokio::spawn(async move {
+            if let Err(err) = tokio::time::timeout(TIMEOUT, respond(stream, &latest)).await {
+                tracing::debug!(%peer, "metrics request timed out: {err}");
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, latest: &Latest) {
+    let Some(request) = http::read_request_head(&mut stream).await else {
+        return;
+    };
+
+    let line = String::from_utf8_lossy(&request);
+    let mut parts = line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let results = latest
+                .lock()
+                .map(|latest| latest.clone())
+                .unwrap_or_default();
+            ("200 OK", render(&results, Timestamp::now()))
+        }
+        (Some("GET"), _) => ("404 Not Found", "only /metrics is served\n".to_string()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: text/plain; version=0.0.4\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    if let Err(err) = stream.write_all(response.as_bytes()).await {
+        tracing::debug!("writing metrics: {err}");
+    }
+}
+
+/// The metrics for `results`, with expiries counted from `now`.
+pub(crate) fn render(results: &[WatchResult], now: Timestamp) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, value: &dyn Fn(&WatchResult) -> Option<f64>| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        for result in results {
+            if let Some(value) = value(result) {
+                let host = escape(&result.host);
+                let _ = writeln!(out, "{name}{{host=\"{host}\"}} {value}");
+            }
+        }
+    };
+
+    gauge(
+        "pls_check_success",
+        "Whether the last check of the host got a cert.",
+        &|result| Some(f64::from(u8::from(result.status == Status::Ok))),
+    );
+    gauge(
+        "pls_cert_expiry_seconds",
+        "Seconds until the host's cert expires, negative once it has.",
+        &|result| {
+            let not_after = result.not_after?;
+            Some((not_after.as_second() - now.as_second()) as f64)
+        },
+    );
+    gauge(
+        "pls_cert_not_after_timestamp_seconds",
+        "When the host's cert expires, as a Unix timestamp.",
+        &|result| Some(result.not_after?.as_second() as f64),
+    );
+    gauge(
+        "pls_handshake_duration_seconds",
+        "How long resolving, connecting to and the TLS handshake with the host took.",
+        &|result| result.handshake_seconds,
+    );
+    gauge(
+        "pls_last_check_timestamp_seconds",
+        "When the host was last checked, as a Unix timestamp.",
+        &|result| Some(result.checked_at.as_second() as f64),
+    );
+    out
+}
+
+/// Escape a label value: backslashes, quotes and newlines.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_gauges() {
+        let now: Timestamp = "2025-01-01T00:00:00Z".parse().unwrap();
+        let ok = WatchResult {
+            host: "example.com:443".to_string(),
+            checked_at: now,
+            status: Status::Ok,
+            subject: Some("example.com".to_string()),
+            not_after: Some("2025-01-02T00:00:00Z".parse().unwrap()),
+            sha256: None,
+            handshake_seconds: Some(0.25),
+            error: None,
+            changes: None,
+            snapshot: None,
+        };
+        let down = WatchResult {
+            host: "down\".example.com:443".to_string(),
+            checked_at: now,
+            ..WatchResult::failed("", Status::Timeout, "timed out".to_string())
+        };
+
+        let metrics = render(&[ok, down], now);
+        for line in [
+            "# TYPE pls_cert_expiry_seconds gauge",
+            "pls_check_success{host=\"example.com:443\"} 1",
+            "pls_check_success{host=\"down\\\".example.com:443\"} 0",
+            "pls_cert_expiry_seconds{host=\"example.com:443\"} 86400",
+            "pls_cert_not_after_timestamp_seconds{host=\"example.com:443\"} 1735776000",
+            "pls_handshake_duration_seconds{host=\"example.com:443\"} 0.25",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "{line} in\n{metrics}");
+        }
+        // no cert, no expiry
+        assert!(!metrics.contains("pls_cert_expiry_seconds{host=\"down"));
+    }
+}
@@ -0,0 +1,233 @@
+//! Alerts from `watch`: a JSON event POSTed to a webhook or piped into a
+//! command when a host's cert gets closer to expiring or is replaced. Each
+//! change is reported once, not on every check.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write as _},
+    process::{Command, Stdio},
+};
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use jiff::Timestamp;
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    components::{
+        expiry::{Severity, Status},
+        watch::WatchResult,
+    },
+    http,
+};
+
+/// What happened to a host's cert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EventKind {
+    /// The cert entered the warning window, or a more urgent one: see
+    /// [`Severity`].
+    Expiring,
+    /// The host serves a cert with another fingerprint.
+    Changed,
+}
+
+/// The JSON sent for an alert.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Event {
+    pub event: EventKind,
+    pub host: String,
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// The fingerprint of the cert the host served before, for `changed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_sha256: Option<String>,
+    pub checked_at: Timestamp,
+}
+
+/// What was last seen of a host.
+#[derive(Debug, Clone)]
+struct Seen {
+    severity: Severity,
+    sha256: Option<String>,
+}
+
+/// Remembers each host's last state, to report changes once.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    hosts: HashMap<String, Seen>,
+}
+
+impl Tracker {
+    /// The events `results` cause, given what was seen before, with
+    /// severities as of `now`. Failed checks change nothing, so a flaky host
+    /// doesn't alert again when it comes back.
+    pub fn update(&mut self, results: &[WatchResult], now: Timestamp) -> Vec<Event> {
+        let mut events = Vec::new();
+        for result in results.iter().filter(|result| result.status == Status::Ok) {
+            let severity = Severity::at(result.not_after, now);
+            let previous = self.hosts.insert(
+                result.host.clone(),
+                Seen {
+                    severity,
+                    sha256: result.sha256.clone(),
+                },
+            );
+            let event = |event, previous_sha256| Event {
+                event,
+                host: result.host.clone(),
+                severity,
+                subject: result.subject.clone(),
+                not_after: result.not_after,
+                sha256: result.sha256.clone(),
+                previous_sha256,
+                checked_at: result.checked_at,
+            };
+
+            // the first check of a host is only a baseline for changes
+            let previous_sha256 = previous.as_ref().and_then(|seen| seen.sha256.clone());
+            if previous_sha256.is_some() && previous_sha256 != result.sha256 {
+                events.push(event(EventKind::Changed, previous_sha256));
+            }
+            let previous_urgency = previous.map_or(0, |seen| urgency(seen.severity));
+            if urgency(severity) > previous_urgency {
+                events.push(event(EventKind::Expiring, None));
+            }
+        }
+        events
+    }
+}
+
+/// How urgent a severity is to alert on, 0 for not at all.
+fn urgency(severity: Severity) -> u8 {
+    match severity {
+        Severity::Ok | Severity::Unknown => 0,
+        Severity::Warning => 1,
+        Severity::Critical => 2,
+        Severity::Expired => 3,
+    }
+}
+
+/// Where alerts go.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Notifier {
+    pub webhook: Option<Url>,
+    /// Run with `sh -c` (`cmd /C` on Windows), with the event on stdin.
+    pub command: Option<String>,
+}
+
+impl Notifier {
+    pub fn is_empty(&self) -> bool {
+        self.webhook.is_none() && self.command.is_none()
+    }
+
+    /// Send `event` everywhere. Failures are logged, not returned, so a
+    /// broken webhook doesn't stop the watch.
+    pub async fn send(&self, event: &Event) {
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::warn!("serializing an alert: {err}");
+                return;
+            }
+        };
+        if let Some(url) = &self.webhook {
+            if let Err(err) = post(url, &body).await {
+                tracing::warn!("alerting {url}: {err:#}");
+            }
+        }
+        if let Some(command) = &self.command {
+            let command = command.clone();
+            let run = tokio::task::spawn_blocking(move || run(&command, &body));
+            match run.await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => tracing::warn!("running the alert command: {err:#}"),
+                Err(err) => tracing::warn!("running the alert command: {err}"),
+            }
+        }
+    }
+}
+
+async fn post(url: &Url, body: &[u8]) -> Result<()> {
+    let response = http::post_json(url, body).await?;
+    if !(200..300).contains(&response.status) {
+        bail!("POST {url}: HTTP {}", response.status);
+    }
+    Ok(())
+}
+
+/// Run `command` through the shell with `input` on its stdin. Its stdout goes
+/// to stderr, as stdout is the watch's own output.
+fn run(command: &str, input: &[u8]) -> Result<()> {
+    let (shell, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .stdout(io::stderr())
+        .spawn()
+        .with_context(|| format!("starting {command:?}"))?;
+
+    let mut stdin = child.stdin.take().ok_or_else(|| eyre!("no stdin"))?;
+    // a command that doesn't read its input isn't an error
+    let _ = stdin.write_all(input);
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .with_context(|| format!("running {command:?}"))?;
+    if !status.success() {
+        bail!("{command:?} exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(sha256: &str, days_left: i64, now: Timestamp) -> WatchResult {
+        WatchResult {
+            host: "example.com:443".to_string(),
+            checked_at: now,
+            status: Status::Ok,
+            subject: None,
+            not_after: Some(now + jiff::Span::new().hours(24 * days_left)),
+            sha256: Some(sha256.to_string()),
+            handshake_seconds: None,
+            error: None,
+            changes: None,
+            snapshot: None,
+        }
+    }
+
+    #[test]
+    fn alerts_once_per_change() {
+        let now: Timestamp = "2025-01-01T00:00:00Z".parse().unwrap();
+        let mut tracker = Tracker::default();
+        let mut events = |result: WatchResult| -> Vec<EventKind> {
+            tracker
+                .update(&[result], now)
+                .into_iter()
+                .map(|event| event.event)
+                .collect()
+        };
+
+        assert_eq!(events(check("a", 60, now)), []);
+        assert_eq!(events(check("a", 20, now)), [EventKind::Expiring]);
+        assert_eq!(events(check("a", 19, now)), []);
+        assert_eq!(events(check("a", 3, now)), [EventKind::Expiring]);
+        let failed = WatchResult::failed("example.com:443", Status::Timeout, "slow".into());
+        assert_eq!(events(failed), []);
+        // renewed
+        assert_eq!(events(check("b", 90, now)), [EventKind::Changed]);
+        assert_eq!(events(check("b", 20, now)), [EventKind::Expiring]);
+    }
+}
@@ -0,0 +1,146 @@
+//! Names for OIDs boring doesn't know, so exotic certs show something better
+//! than a bare number (or nothing at all).
+
+use crate::der;
+
+/// The name of a well-known OID: algorithms, certificate policies, extended
+/// key usage purposes and extensions, including the Microsoft and CA/Browser
+/// Forum ones.
+pub fn name(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        // signature and key algorithms
+        "1.2.840.113549.1.1.1" => "rsaEncryption",
+        "1.2.840.113549.1.1.10" => "RSASSA-PSS",
+        "1.2.840.113549.1.1.7" => "RSAES-OAEP",
+        "1.2.840.113549.1.1.8" => "mgf1",
+        "1.3.14.3.2.26" => "sha1",
+        "2.16.840.1.101.3.4.2.4" => "sha224",
+        "2.16.840.1.101.3.4.2.1" => "sha256",
+        "2.16.840.1.101.3.4.2.2" => "sha384",
+        "2.16.840.1.101.3.4.2.3" => "sha512",
+        "1.2.840.10045.2.1" => "id-ecPublicKey",
+        "1.2.840.10040.4.1" => "dsa",
+        "1.3.101.110" => "X25519",
+        "1.3.101.111" => "X448",
+        "1.3.101.112" => "Ed25519",
+        "1.3.101.113" => "Ed448",
+        "1.2.156.10197.1.301" => "SM2",
+        "1.2.156.10197.1.501" => "SM2-with-SM3",
+        "1.2.643.2.2.19" => "GOST R 34.10-2001",
+        "1.2.643.7.1.1.1.1" => "GOST R 34.10-2012 (256 bit)",
+        "1.2.643.7.1.1.1.2" => "GOST R 34.10-2012 (512 bit)",
+        "1.2.643.2.2.3" => "GOST R 34.11-94 with GOST R 34.10-2001",
+        "1.2.643.7.1.1.3.2" => "GOST R 34.10-2012 with GOST R 34.11-2012 (256 bit)",
+        "1.2.643.7.1.1.3.3" => "GOST R 34.10-2012 with GOST R 34.11-2012 (512 bit)",
+        "1.2.156.10197.1.401" => "SM3",
+        "1.2.643.2.2.30.1" => "GOST R 34.11-94 CryptoPro",
+        "1.2.643.7.1.1.2.2" => "GOST R 34.11-2012 (256 bit)",
+        "1.2.643.7.1.1.2.3" => "GOST R 34.11-2012 (512 bit)",
+        "1.2.643.2.2.35.1" => "id-GostR3410-2001-CryptoPro-A-ParamSet",
+        "1.2.643.2.2.35.2" => "id-GostR3410-2001-CryptoPro-B-ParamSet",
+        "1.2.643.2.2.35.3" => "id-GostR3410-2001-CryptoPro-C-ParamSet",
+        "1.2.643.2.2.36.0" => "id-GostR3410-2001-CryptoPro-XchA-ParamSet",
+        "1.2.643.2.2.36.1" => "id-GostR3410-2001-CryptoPro-XchB-ParamSet",
+        "1.2.643.7.1.2.1.1.1" => "id-tc26-gost-3410-2012-256-paramSetA",
+        "1.2.643.7.1.2.1.2.1" => "id-tc26-gost-3410-2012-512-paramSetA",
+        "1.2.643.7.1.2.1.2.2" => "id-tc26-gost-3410-2012-512-paramSetB",
+        "2.16.840.1.101.3.4.3.17" => "ML-DSA-44",
+        "2.16.840.1.101.3.4.3.18" => "ML-DSA-65",
+        "2.16.840.1.101.3.4.3.19" => "ML-DSA-87",
+        "1.3.36.3.3.2.8.1.1.5" => "brainpoolP224r1",
+        "1.3.36.3.3.2.8.1.1.7" => "brainpoolP256r1",
+        "1.3.36.3.3.2.8.1.1.9" => "brainpoolP320r1",
+        "1.3.36.3.3.2.8.1.1.11" => "brainpoolP384r1",
+        "1.3.36.3.3.2.8.1.1.13" => "brainpoolP512r1",
+
+        // certificate policies
+        "2.5.29.32.0" => "anyPolicy",
+        "2.23.140.1.1" => "CA/B Forum Extended Validation",
+        "2.23.140.1.2.1" => "CA/B Forum Domain Validated",
+        "2.23.140.1.2.2" => "CA/B Forum Organization Validated",
+        "2.23.140.1.2.3" => "CA/B Forum Individual Validated",
+        "2.23.140.1.3" => "CA/B Forum Extended Validation Code Signing",
+        "2.23.140.1.4.1" => "CA/B Forum Code Signing",
+        "2.23.140.1.5.1.1" => "CA/B Forum S/MIME Mailbox Validated",
+        "2.23.140.1.31" => "CA/B Forum Onion EV",
+        "1.3.6.1.4.1.44947.1.1.1" => "ISRG Domain Validated",
+        "1.3.6.1.4.1.311.21.10" => "Microsoft Application Policies",
+        "1.3.6.1.4.1.311.10.3.12" => "Microsoft Document Signing",
+
+        // extended key usage purposes
+        "1.3.6.1.5.5.7.3.1" => "serverAuth",
+        "1.3.6.1.5.5.7.3.2" => "clientAuth",
+        "1.3.6.1.5.5.7.3.3" => "codeSigning",
+        "1.3.6.1.5.5.7.3.4" => "emailProtection",
+        "1.3.6.1.5.5.7.3.5" => "ipsecEndSystem",
+        "1.3.6.1.5.5.7.3.6" => "ipsecTunnel",
+        "1.3.6.1.5.5.7.3.7" => "ipsecUser",
+        "1.3.6.1.5.5.7.3.8" => "timeStamping",
+        "1.3.6.1.5.5.7.3.9" => "OCSPSigning",
+        "1.3.6.1.5.5.7.3.17" => "ipsecIKE",
+        "1.3.6.1.5.5.7.3.36" => "documentSigning",
+        "2.5.29.37.0" => "anyExtendedKeyUsage",
+        "1.3.6.1.4.1.311.10.3.1" => "Microsoft Trust List Signing",
+        "1.3.6.1.4.1.311.10.3.3" => "Microsoft Server Gated Crypto",
+        "1.3.6.1.4.1.311.10.3.4" => "Microsoft Encrypted File System",
+        "1.3.6.1.4.1.311.20.2.2" => "Microsoft Smart Card Logon",
+        "1.3.6.1.4.1.311.21.5" => "Microsoft CA Exchange",
+        "1.3.6.1.4.1.311.21.6" => "Microsoft Key Recovery Agent",
+        "1.3.6.1.4.1.311.54.1.2" => "Microsoft Remote Desktop",
+        "2.16.840.1.113730.4.1" => "Netscape Server Gated Crypto",
+        "1.3.6.1.5.2.3.5" => "Kerberos KDC",
+
+        // extensions
+        "1.3.6.1.4.1.311.21.1" => "Microsoft CA Version",
+        "1.3.6.1.4.1.311.21.2" => "Microsoft Previous CA Cert Hash",
+        "1.3.6.1.4.1.311.25.2" => "Microsoft NTDS CA Security",
+        "1.3.6.1.5.5.7.1.3" => "qcStatements",
+        "1.3.6.1.5.5.7.48.1.5" => "OCSP No Check",
+        "2.5.29.9" => "subjectDirectoryAttributes",
+        "2.5.29.16" => "privateKeyUsagePeriod",
+        "2.5.29.33" => "policyMappings",
+        "2.5.29.36" => "policyConstraints",
+        "2.5.29.46" => "freshestCRL",
+        "2.5.29.54" => "inhibitAnyPolicy",
+        "2.16.840.1.113730.1.1" => "Netscape Cert Type",
+        "2.16.840.1.113730.1.13" => "Netscape Comment",
+        oid => return der::extension_name(oid),
+    })
+}
+
+/// `oid` with its name when it has one, e.g. `SM2-with-SM3
+/// (1.2.156.10197.1.501)`.
+pub fn describe(oid: &str) -> String {
+    match name(oid) {
+        Some(name) => format!("{name} ({oid})"),
+        None => oid.to_string(),
+    }
+}
+
+/// The name to show for an extension: its usual name when it's a well-known
+/// one, [`describe`]d otherwise.
+pub fn extension(oid: &str) -> String {
+    der::extension_name(oid).map_or_else(|| describe(oid), str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            describe("1.2.156.10197.1.501"),
+            "SM2-with-SM3 (1.2.156.10197.1.501)"
+        );
+        assert_eq!(
+            describe("2.23.140.1.1"),
+            "CA/B Forum Extended Validation (2.23.140.1.1)"
+        );
+        assert_eq!(describe("1.2.3.4"), "1.2.3.4");
+
+        assert_eq!(extension("2.5.29.17"), "subjectAltName");
+        assert_eq!(extension("2.5.29.36"), "policyConstraints (2.5.29.36)");
+        assert_eq!(extension("1.2.3.4"), "1.2.3.4");
+    }
+}
@@ -0,0 +1,151 @@
+//! `--output FILE`: the command's stdout goes to a temporary file next to
+//! `FILE`, which replaces `FILE` only when the command succeeds, so a failed
+//! or interrupted run never leaves a truncated report behind. Output with a
+//! private key in it is only readable by its owner.
+
+use std::fs::{self, File};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use memchr::memmem;
+
+/// Whether a private key has been printed, see [`mark_private`].
+static PRIVATE: AtomicBool = AtomicBool::new(false);
+
+/// Note that the output has a private key in it, so an `--output` file gets
+/// mode 0600. PEM private keys are spotted anyway, this is for the text and
+/// JSON views of them.
+pub(crate) fn mark_private() {
+    PRIVATE.store(true, Ordering::Relaxed);
+}
+
+/// stdout, redirected to a temporary file until [`Output::finish`].
+pub struct Output {
+    path: PathBuf,
+    tmp: PathBuf,
+    file: File,
+    #[cfg(unix)]
+    stdout: std::os::fd::OwnedFd,
+}
+
+impl Output {
+    /// Start sending stdout to a temporary file beside `path`.
+    #[cfg(unix)]
+    pub fn redirect(path: &Path) -> Result<Self> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let tmp = tmp_path(path)?;
+        // private until it's known there's no key in it
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp)
+            .with_context(|| format!("Creating {}", tmp.display()))?;
+
+        io::stdout().flush()?;
+        // SAFETY: dup and dup2 only take file descriptors, and a descriptor
+        // dup returns is owned by nothing else
+        let stdout = unsafe {
+            let saved = libc::dup(libc::STDOUT_FILENO);
+            if saved < 0 || libc::dup2(file.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+                let err = io::Error::last_os_error();
+                let _ = fs::remove_file(&tmp);
+                return Err(err).context("Redirecting stdout");
+            }
+            OwnedFd::from_raw_fd(saved)
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp,
+            file,
+            stdout,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn redirect(_path: &Path) -> Result<Self> {
+        bail!("--output isn't supported on this platform, redirect stdout instead")
+    }
+
+    /// Put stdout back, then move the output into place when the command
+    /// `succeeded`, or throw it away.
+    pub fn finish(self, succeeded: bool) -> Result<()> {
+        let _ = io::stdout().flush();
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+            // SAFETY: both are open descriptors
+            unsafe { libc::dup2(self.stdout.as_raw_fd(), libc::STDOUT_FILENO) };
+        }
+
+        if !succeeded {
+            let _ = fs::remove_file(&self.tmp);
+            return Ok(());
+        }
+        let result = self.commit();
+        if result.is_err() {
+            let _ = fs::remove_file(&self.tmp);
+        }
+        result
+    }
+
+    fn commit(&self) -> Result<()> {
+        self.file
+            .sync_all()
+            .with_context(|| format!("Writing {}", self.tmp.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let data = fs::read(&self.tmp)?;
+            let private = PRIVATE.load(Ordering::Relaxed)
+                || memmem::find(&data, b"PRIVATE KEY-----").is_some();
+            // a file that's replaced keeps its mode, unless a key makes it
+            // private
+            let mode = match fs::metadata(&self.path) {
+                _ if private => 0o600,
+                Ok(metadata) => metadata.permissions().mode() & 0o7777,
+                Err(_) => 0o644,
+            };
+            fs::set_permissions(&self.tmp, fs::Permissions::from_mode(mode))?;
+        }
+
+        fs::rename(&self.tmp, &self.path)
+            .with_context(|| format!("Writing {}", self.path.display()))
+    }
+}
+
+/// A hidden file in the same directory as `path`, so the rename is atomic.
+fn tmp_path(path: &Path) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| eyre!("--output needs a file name, not {}", path.display()))?;
+    if path.is_dir() {
+        bail!("{} is a directory", path.display());
+    }
+    let tmp = format!(".{}.{}.tmp", name.to_string_lossy(), std::process::id());
+    Ok(path.with_file_name(tmp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tmp_files_are_beside_the_output() {
+        let tmp = tmp_path(Path::new("reports/today.json")).unwrap();
+        assert_eq!(tmp.parent(), Some(Path::new("reports")));
+        let name = tmp.file_name().unwrap().to_string_lossy();
+        assert!(
+            name.starts_with(".today.json.") && name.ends_with(".tmp"),
+            "{name}"
+        );
+        assert!(tmp_path(Path::new("..")).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Asking for a passphrase on the terminal, without echoing it. The terminal
+//! is opened directly, so it works with stdin and stdout piped.
+
+use color_eyre::eyre::{bail, Result};
+
+use crate::secret::Zeroizing;
+
+/// Ask for a new passphrase for `what`, twice to catch typos.
+pub(crate) fn prompt_new(what: &str) -> Result<Zeroizing<String>> {
+    let passphrase = read_hidden(&format!("Passphrase for {what}: "))?;
+    if passphrase.is_empty() {
+        bail!("an empty passphrase doesn't protect anything");
+    }
+    if *read_hidden("Again: ")? != *passphrase {
+        bail!("the passphrases don't match");
+    }
+    Ok(passphrase)
+}
+
+/// Print `prompt` and read a line from the terminal with echo off.
+#[cfg(unix)]
+fn read_hidden(prompt: &str) -> Result<Zeroizing<String>> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+
+    use color_eyre::eyre::Context;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .context("Opening the terminal to ask for a passphrase")?;
+    let fd = tty.as_raw_fd();
+
+    // SAFETY: `fd` is open for as long as `tty` is, and `termios` is only
+    // read after tcgetattr filled it in
+    let saved = unsafe {
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return Err(std::io::Error::last_os_error()).context("Reading the terminal's settings");
+        }
+        termios
+    };
+    let mut hidden = saved;
+    hidden.c_lflag &= !libc::ECHO;
+    hidden.c_lflag |= libc::ECHONL;
+
+    write!(tty, "{prompt}")?;
+    tty.flush()?;
+    // SAFETY: as above
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &hidden) };
+    // a byte at a time, so no buffer but `line` ever holds the passphrase,
+    // and big enough that it isn't copied somewhere bigger as it grows
+    let mut line = Zeroizing::new(Vec::with_capacity(1024));
+    let mut read_line = || -> std::io::Result<()> {
+        let mut byte = [0];
+        while tty.read(&mut byte)? == 1 && byte[0] != b'\n' {
+            line.push(byte[0]);
+        }
+        Ok(())
+    };
+    let read = read_line();
+    // SAFETY: as above
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &saved) };
+    read.context("Reading the passphrase")?;
+
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    if std::str::from_utf8(&line).is_err() {
+        bail!("the passphrase isn't UTF-8");
+    }
+    let line = String::from_utf8(std::mem::take(&mut *line)).expect("checked above");
+    Ok(Zeroizing::new(line))
+}
+
+#[cfg(not(unix))]
+fn read_hidden(_prompt: &str) -> Result<Zeroizing<String>> {
+    bail!("asking for a passphrase isn't supported on this platform")
+}
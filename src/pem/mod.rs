@@ -1,3 +1,7 @@
 mod parser;
+mod scan;
 
-pub(crate) use parser::{parse_pems, ParsedPem, Pem};
+pub(crate) use parser::{
+    decode_pems, parse_pems, pem_blocks, sniff_bare_base64, ParsedPem, Pem, PemReader, Source,
+};
+pub(crate) use scan::{scan_der_certs, Carved};
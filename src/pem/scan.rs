@@ -0,0 +1,114 @@
+use std::ops::Range;
+
+use boring::x509::X509;
+
+use crate::der::{self, tag};
+
+/// Certs larger than this aren't worth trying to parse. The biggest in the
+/// wild (e.g. with embedded SCT lists or huge SAN lists) are well under it.
+const MAX_CERT_LEN: usize = 1 << 20;
+
+/// How often, in bytes scanned, `progress` is called.
+const PROGRESS_INTERVAL: usize = 1 << 20;
+
+/// A DER certificate carved out of a binary blob.
+#[derive(Debug)]
+pub(crate) struct Carved {
+    pub span: Range<usize>,
+    pub cert: X509,
+}
+
+/// Find every DER encoded X509 certificate in `data`, e.g. a firmware image or
+/// memory dump. Each offset that starts a SEQUENCE of up to [`MAX_CERT_LEN`]
+/// bytes, whose first element is also a SEQUENCE (the `tbsCertificate`), is
+/// handed to boring. The scan resumes after each cert it finds, so the
+/// SEQUENCEs nested inside aren't reported again.
+///
+/// `progress` is called with the number of bytes scanned so far, every
+/// [`PROGRESS_INTERVAL`] bytes and once at the end.
+pub(crate) fn scan_der_certs(data: &[u8], mut progress: impl FnMut(usize)) -> Vec<Carved> {
+    let mut carved = Vec::new();
+    let mut next_progress = PROGRESS_INTERVAL;
+    let mut pos = 0;
+
+    while pos + 4 <= data.len() {
+        if pos >= next_progress {
+            progress(pos);
+            next_progress += PROGRESS_INTERVAL;
+        }
+
+        match candidate(&data[pos..]) {
+            Some(len) => match X509::from_der(&data[pos..pos + len]) {
+                Ok(cert) => {
+                    tracing::debug!("found a {len} byte cert at offset {pos}");
+                    carved.push(Carved {
+                        span: pos..pos + len,
+                        cert,
+                    });
+                    pos += len;
+                }
+                Err(_) => pos += 1,
+            },
+            None => pos += 1,
+        }
+    }
+    progress(data.len());
+
+    carved
+}
+
+/// The length of the certificate-shaped SEQUENCE at the start of `data`, if
+/// there is one. Cheap enough to run at every offset.
+fn candidate(data: &[u8]) -> Option<usize> {
+    // every cert is over 127 bytes, so its length is in the long form
+    if data[0] != tag::SEQUENCE || !matches!(data[1], 0x81..=0x83) {
+        return None;
+    }
+
+    let (tlv, len) = der::read_tlv(data).ok()?;
+    if len > MAX_CERT_LEN || tlv.value.first() != Some(&tag::SEQUENCE) {
+        return None;
+    }
+
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carves_certs_out_of_noise() {
+        let chain =
+            X509::stack_from_pem(include_bytes!("../../test-data/certs/chain.pem")).unwrap();
+
+        // DER certs with junk between them, including bytes that look like
+        // the start of a SEQUENCE
+        let mut blob = b"\x7fELF\x30\x82\xff\xff junk".to_vec();
+        let mut offsets = Vec::new();
+        for cert in &chain {
+            offsets.push(blob.len());
+            blob.extend(cert.to_der().unwrap());
+            blob.extend([0x30, 0x82, 0x00, 0x10, 0x00, 0x30]);
+        }
+
+        let mut reported = Vec::new();
+        let carved = scan_der_certs(&blob, |pos| reported.push(pos));
+
+        assert_eq!(
+            carved.iter().map(|c| c.span.start).collect::<Vec<_>>(),
+            offsets
+        );
+        for (carved, cert) in carved.iter().zip(&chain) {
+            assert_eq!(carved.cert.to_der().unwrap(), cert.to_der().unwrap());
+            assert_eq!(carved.span.len(), cert.to_der().unwrap().len());
+        }
+        assert_eq!(reported.last(), Some(&blob.len()));
+    }
+
+    #[test]
+    fn nothing_to_carve() {
+        assert!(scan_der_certs(b"", |_| {}).is_empty());
+        assert!(scan_der_certs(&[0x30; 64], |_| {}).is_empty());
+    }
+}
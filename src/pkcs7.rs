@@ -0,0 +1,91 @@
+//! PKCS#7 bundles (`.p7b`, `.p7c`, `-----BEGIN PKCS7-----`): the certs-only
+//! `SignedData` Windows CAs and many appliances export chains as. Only the
+//! certs and CRLs are read; a signature, if there is one, isn't checked.
+
+use boring::x509::X509;
+use color_eyre::eyre::{Context, Result};
+
+use crate::{crl::SimpleCrl, der};
+
+/// The DER encoding of the signedData OID, which follows the outer SEQUENCE
+/// header at the start of a DER bundle.
+const SIGNED_DATA_OID: &[u8] = &[
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02,
+];
+
+pub struct Pkcs7 {
+    pub certs: Vec<X509>,
+    pub crls: Vec<SimpleCrl>,
+}
+
+impl Pkcs7 {
+    pub fn from_der(data: &[u8]) -> Result<Self> {
+        let (certs, crls) = der::pkcs7_signed_data(data).context("parsing PKCS#7")?;
+        Ok(Pkcs7 {
+            certs: certs
+                .into_iter()
+                .map(X509::from_der)
+                .collect::<Result<_, _>>()
+                .context("parsing a PKCS#7 certificate")?,
+            crls: crls
+                .into_iter()
+                .map(SimpleCrl::from_der)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Whether `head`, the first bytes of an input, look like a DER bundle
+    /// rather than text.
+    pub fn sniff(head: &[u8]) -> bool {
+        // SEQUENCE with a long form length of up to 4 bytes, then the OID
+        head.first() == Some(&0x30)
+            && (2..=6).any(|start| head[start.min(head.len())..].starts_with(SIGNED_DATA_OID))
+    }
+}
+
+impl std::fmt::Debug for Pkcs7 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Pkcs7({} certs, {} CRLs)",
+            self.certs.len(),
+            self.crls.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::x509::SimpleCert;
+
+    use super::*;
+
+    #[test]
+    fn bundle() {
+        let der = include_bytes!("../test-data/pkcs7/chain-and-crl.p7b");
+        assert!(Pkcs7::sniff(&der[..16]));
+        assert!(!Pkcs7::sniff(b"-----BEGIN PKCS7-----"));
+
+        let bundle = Pkcs7::from_der(der).unwrap();
+        let names: Vec<String> = bundle
+            .certs
+            .into_iter()
+            .map(|cert| SimpleCert::from(cert).subject.name)
+            .collect();
+        assert_eq!(names.len(), 3);
+        assert!(names[0].contains("lan.fish"), "{names:?}");
+
+        let [crl] = &bundle.crls[..] else {
+            panic!("expected one CRL, got {}", bundle.crls.len());
+        };
+        assert_eq!(crl.issuer.common_name.as_deref(), Some("rsa.example"));
+        assert_eq!(crl.revoked.len(), 1);
+        assert_eq!(crl.revoked[0].serial.hex, "1000");
+        assert_eq!(
+            crl.revoked[0].revoked_at.to_string(),
+            "2025-01-01T00:00:00Z"
+        );
+        assert!(crl.next_update.is_some());
+        assert!(crl.pem.starts_with("-----BEGIN X509 CRL-----\n"));
+    }
+}
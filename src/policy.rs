@@ -0,0 +1,159 @@
+//! The kind of validation a CA did before issuing a cert: extended,
+//! organization, individual or domain validation, from the policies in its
+//! certificatePolicies extension.
+
+use std::fmt::{self, Display, Formatter};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{oid, x509::Name};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationLevel {
+    /// Extended validation: the CA checked the legal identity of the
+    /// organization in depth.
+    Ev,
+    /// Organization validation: the CA checked the organization in the
+    /// subject.
+    Ov,
+    /// Individual validation: the CA checked the person in the subject.
+    Iv,
+    /// Domain validation: the CA only checked control of the names.
+    Dv,
+}
+
+impl Display for ValidationLevel {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ValidationLevel::Ev => "EV",
+            ValidationLevel::Ov => "OV",
+            ValidationLevel::Iv => "IV",
+            ValidationLevel::Dv => "DV",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Validation {
+    #[serde(rename = "validation_level")]
+    pub level: ValidationLevel,
+    /// The policy the level comes from, e.g. `CA/B Forum Domain Validated
+    /// (2.23.140.1.2.1)`. None when the cert asserts no policy for it and the
+    /// level is a guess from the subject.
+    #[serde(rename = "validation_policy")]
+    pub policy: Option<String>,
+}
+
+impl Validation {
+    /// The validation of a leaf cert with `policies` and `subject`.
+    ///
+    /// The CA/Browser Forum policies and the EV policies CAs registered with
+    /// browsers before those existed are definitive. Otherwise a subject with
+    /// an organization is most likely OV and one without DV.
+    pub fn of(policies: &[String], subject: &Name) -> Self {
+        let asserted = |level, matches: fn(&str) -> bool| {
+            policies
+                .iter()
+                .find(|policy| matches(policy))
+                .map(|policy| Validation {
+                    level,
+                    policy: Some(oid::describe(policy)),
+                })
+        };
+
+        asserted(ValidationLevel::Ev, |policy| {
+            policy == "2.23.140.1.1" || ca_ev_policy(policy).is_some()
+        })
+        .or_else(|| asserted(ValidationLevel::Ov, |policy| policy == "2.23.140.1.2.2"))
+        .or_else(|| asserted(ValidationLevel::Iv, |policy| policy == "2.23.140.1.2.3"))
+        .or_else(|| asserted(ValidationLevel::Dv, |policy| policy == "2.23.140.1.2.1"))
+        .unwrap_or_else(|| Validation {
+            level: if subject.attributes.contains_key("O") {
+                ValidationLevel::Ov
+            } else {
+                ValidationLevel::Dv
+            },
+            policy: None,
+        })
+    }
+}
+
+/// The CA an EV policy OID belongs to, for the CA-specific OIDs browsers
+/// recognize as EV alongside `2.23.140.1.1`.
+pub fn ca_ev_policy(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "1.2.156.112559.1.1.6.1" => "CFCA",
+        "1.2.392.200091.100.721.1" => "SECOM",
+        "1.2.616.1.113527.2.5.1.1" => "Certum",
+        "1.3.159.1.17.1" => "Actalis",
+        "1.3.6.1.4.1.14370.1.6" => "GeoTrust",
+        "1.3.6.1.4.1.17326.10.14.2.1.2" => "Camerfirma",
+        "1.3.6.1.4.1.17326.10.8.12.1.2" => "Camerfirma",
+        "1.3.6.1.4.1.22234.2.5.2.3.1" => "Keynectis",
+        "1.3.6.1.4.1.23223.1.1.1" => "StartCom",
+        "1.3.6.1.4.1.34697.2.1" => "AffirmTrust",
+        "1.3.6.1.4.1.34697.2.2" => "AffirmTrust",
+        "1.3.6.1.4.1.34697.2.3" => "AffirmTrust",
+        "1.3.6.1.4.1.34697.2.4" => "AffirmTrust",
+        "1.3.6.1.4.1.40869.1.1.22.3" => "TWCA",
+        "1.3.6.1.4.1.4146.1.1" => "GlobalSign",
+        "1.3.6.1.4.1.6334.1.100.1" => "Cybertrust",
+        "1.3.6.1.4.1.6449.1.2.1.5.1" => "Sectigo",
+        "1.3.6.1.4.1.782.1.2.1.8.1" => "Network Solutions",
+        "1.3.6.1.4.1.8024.0.2.100.1.2" => "QuoVadis",
+        "2.16.528.1.1003.1.2.7" => "Staat der Nederlanden",
+        "2.16.578.1.26.1.3.3" => "Buypass",
+        "2.16.756.1.89.1.2.1.1" => "SwissSign",
+        "2.16.840.1.113733.1.7.23.6" => "VeriSign",
+        "2.16.840.1.113733.1.7.48.1" => "Thawte",
+        "2.16.840.1.114028.10.1.2" => "Entrust",
+        "2.16.840.1.114171.500.9" => "Wells Fargo",
+        "2.16.840.1.114404.1.1.2.4.1" => "Trustwave",
+        "2.16.840.1.114412.1.3.0.2" => "DigiCert",
+        "2.16.840.1.114412.2.1" => "DigiCert",
+        "2.16.840.1.114413.1.7.23.3" => "GoDaddy",
+        "2.16.840.1.114414.1.7.23.3" => "Starfield",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(organization: bool) -> Name {
+        let mut name = Name::default();
+        if organization {
+            name.attributes.insert("O".into(), vec!["Example".into()]);
+        }
+        name
+    }
+
+    #[test]
+    fn levels() {
+        let policies = |oids: &[&str]| oids.iter().map(|oid| oid.to_string()).collect::<Vec<_>>();
+
+        let dv = Validation::of(&policies(&["2.23.140.1.2.1"]), &subject(false));
+        assert_eq!(dv.level, ValidationLevel::Dv);
+        assert_eq!(
+            dv.policy.as_deref(),
+            Some("CA/B Forum Domain Validated (2.23.140.1.2.1)")
+        );
+
+        // the CA-specific EV OID wins over the others
+        let ev = Validation::of(
+            &policies(&["2.23.140.1.2.2", "2.16.840.1.114412.2.1"]),
+            &subject(true),
+        );
+        assert_eq!(ev.level, ValidationLevel::Ev);
+
+        let guessed = Validation::of(&policies(&["1.2.3.4"]), &subject(true));
+        assert_eq!((guessed.level, guessed.policy), (ValidationLevel::Ov, None));
+        assert_eq!(
+            Validation::of(&[], &subject(false)).level,
+            ValidationLevel::Dv
+        );
+    }
+}
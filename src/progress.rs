@@ -0,0 +1,176 @@
+//! Progress and status on stderr: spinners while connecting, the file being
+//! read while walking directories. stdout only ever gets the command's
+//! output, so `--json` and `--pem` can be piped safely, and `--quiet` turns
+//! all of this off.
+//!
+//! Progress lines are only drawn when stderr is a TTY, so they never end up
+//! in logs. [`status`] and log output written through [`stderr`] erase the
+//! current line first; anything else printed to stderr should go through
+//! [`Progress::clear`].
+
+use std::io::{IsTerminal as _, Write as _};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use tokio::task::JoinHandle;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// The most recently created progress line, erased by [`status`] and
+/// [`stderr`].
+static ACTIVE: Mutex<Option<Weak<Mutex<Line>>>> = Mutex::new(None);
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How often a spinner turns, and how often a progress line is redrawn at
+/// most.
+const TICK: Duration = Duration::from_millis(80);
+
+/// Suppress progress and status, from `--quiet`.
+pub(crate) fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Print a one-off status line to stderr, e.g. `Connected to example.com`,
+/// unless `--quiet`. The progress line is erased first and drawn again
+/// below it.
+pub(crate) fn status(message: impl AsRef<str>) {
+    if !QUIET.load(Ordering::Relaxed) {
+        erase_active();
+        eprintln!("{}", message.as_ref());
+    }
+}
+
+/// stderr with the progress line erased, as the tracing writer so log lines
+/// don't land on top of a spinner.
+pub fn stderr() -> std::io::Stderr {
+    erase_active();
+    std::io::stderr()
+}
+
+fn erase_active() {
+    let line = ACTIVE.lock().unwrap().as_ref().and_then(Weak::upgrade);
+    if let Some(line) = line {
+        line.lock().unwrap().erase();
+    }
+}
+
+/// A line of progress on stderr, redrawn in place and erased when dropped.
+/// Does nothing when stderr isn't a TTY or with `--quiet`.
+pub(crate) struct Progress {
+    line: Option<Arc<Mutex<Line>>>,
+    spinner: Option<JoinHandle<()>>,
+}
+
+struct Line {
+    message: String,
+    frame: Option<usize>,
+    drawn: Option<Instant>,
+    done: bool,
+}
+
+impl Line {
+    fn draw(&mut self) {
+        if self.done {
+            return;
+        }
+        let width = terminal_size::terminal_size().map_or(80, |(width, _)| width.0 as usize);
+        let prefix = match self.frame {
+            Some(frame) => format!("{} ", SPINNER[frame % SPINNER.len()]),
+            None => String::new(),
+        };
+        // a line that wraps can't be redrawn with `\r`
+        let message: String = self.message.chars().take(width.saturating_sub(3)).collect();
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{prefix}{message}");
+        let _ = stderr.flush();
+        self.drawn = Some(Instant::now());
+    }
+
+    fn erase(&mut self) {
+        if self.drawn.take().is_some() {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+impl Progress {
+    /// A progress line that changes with [`Progress::set`], e.g. the file
+    /// being read.
+    pub fn new() -> Self {
+        let enabled = !QUIET.load(Ordering::Relaxed) && std::io::stderr().is_terminal();
+        let line = enabled.then(|| {
+            let line = Arc::new(Mutex::new(Line {
+                message: String::new(),
+                frame: None,
+                drawn: None,
+                done: false,
+            }));
+            *ACTIVE.lock().unwrap() = Some(Arc::downgrade(&line));
+            line
+        });
+        Self {
+            line,
+            spinner: None,
+        }
+    }
+
+    /// A spinner with `message`, for waiting on the network.
+    pub fn spinner(message: impl Into<String>) -> Self {
+        let mut progress = Self::new();
+        let Some(line) = progress.line.clone() else {
+            return progress;
+        };
+        {
+            let mut line = line.lock().unwrap();
+            line.message = message.into();
+            line.frame = Some(0);
+        }
+        progress.spinner = Some(tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(TICK);
+            loop {
+                ticks.tick().await;
+                let mut line = line.lock().unwrap();
+                line.frame = line.frame.map(|frame| frame + 1);
+                line.draw();
+            }
+        }));
+        progress
+    }
+
+    /// Change the message. Progress lines are redrawn at most every
+    /// [`TICK`], so calling this for every file is cheap.
+    pub fn set(&self, message: impl Into<String>) {
+        let Some(line) = &self.line else {
+            return;
+        };
+        let mut line = line.lock().unwrap();
+        line.message = message.into();
+        let recent = matches!(line.drawn, Some(drawn) if drawn.elapsed() < TICK);
+        if line.frame.is_none() && !recent {
+            line.draw();
+        }
+    }
+
+    /// Erase the line to print something else. The next [`Progress::set`],
+    /// or turn of the spinner, draws it again.
+    pub fn clear(&self) {
+        if let Some(line) = &self.line {
+            line.lock().unwrap().erase();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(spinner) = self.spinner.take() {
+            spinner.abort();
+        }
+        if let Some(line) = &self.line {
+            let mut line = line.lock().unwrap();
+            line.done = true;
+            line.erase();
+        }
+    }
+}
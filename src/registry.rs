@@ -0,0 +1,366 @@
+//! Container registries, for `connect --registry`: whether the server speaks
+//! the registry API, and whether the certs Docker reads for it from
+//! `/etc/docker/certs.d/` fit together.
+
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, Context, Result};
+use jiff::Timestamp;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    commands::{expiry::certs_in, same_key::key_users},
+    http::Response,
+    verifier,
+    x509::{NotAfter, SimpleCert},
+};
+
+/// Where Docker looks for the certs of each registry, in a directory named
+/// after it.
+pub const DOCKER_CERTS_DIR: &str = "/etc/docker/certs.d";
+
+/// The port `docker run registry:2` listens on.
+const LOCAL_PORT: u16 = 5000;
+
+/// The port to connect to when the registry has none: 443, except for
+/// `localhost`, IP addresses and names without a dot, which are usually a
+/// registry run next to the daemon on [`LOCAL_PORT`].
+pub fn default_port(host: &str) -> u16 {
+    if host.parse::<IpAddr>().is_ok() || !host.contains('.') {
+        LOCAL_PORT
+    } else {
+        443
+    }
+}
+
+/// The directory Docker reads the certs of `host:port` from. Docker leaves
+/// the port out of it when it's 443, as in image names.
+pub fn docker_certs_dir(host: &str, port: u16) -> PathBuf {
+    let host = match host.contains(':') {
+        true => format!("[{host}]"),
+        false => host.to_string(),
+    };
+    let name = match port {
+        443 => host,
+        port => format!("{host}:{port}"),
+    };
+    Path::new(DOCKER_CERTS_DIR).join(name)
+}
+
+/// What the registry answered to `GET /v2/`, the API check every client
+/// starts with.
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct RegistryProbe {
+    pub status: u16,
+    /// `Docker-Distribution-API-Version`, `registry/2.0` from registries.
+    pub api_version: Option<String>,
+    /// `WWW-Authenticate`: where to get a token, for registries that want a
+    /// login.
+    pub authenticate: Option<String>,
+}
+
+impl RegistryProbe {
+    pub fn new(response: &Response) -> Self {
+        Self {
+            status: response.status,
+            api_version: response
+                .header("docker-distribution-api-version")
+                .map(str::to_string),
+            authenticate: response.header("www-authenticate").map(str::to_string),
+        }
+    }
+
+    /// Whether the server speaks the registry API: `200` when it's open, or
+    /// `401` with the API version when it wants a login first.
+    pub fn speaks_v2(&self) -> bool {
+        match self.status {
+            200 => true,
+            401 => self.api_version.is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// The files Docker reads from a registry's certs directory, by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DockerFileKind {
+    /// `*.crt`: CAs trusted for the registry, on top of the system roots.
+    Ca,
+    /// `*.cert`: a client cert, sent with the `*.key` of the same name.
+    ClientCert,
+    /// `*.key`: the key of the `*.cert` of the same name.
+    ClientKey,
+}
+
+/// A file Docker reads from the certs directory.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct DockerFile {
+    pub name: String,
+    pub kind: DockerFileKind,
+    /// The subject of the first cert in the file, for CAs and client certs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[schemars(with = "Option<String>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after: Option<Timestamp>,
+}
+
+/// The certs Docker uses for a registry, with `--docker-certs`.
+#[derive(Debug, Default, Clone, Serialize, JsonSchema)]
+pub struct DockerCerts {
+    pub dir: String,
+    /// The files Docker reads, in name order. Others are ignored.
+    pub files: Vec<DockerFile>,
+    /// Whether the registry's chain verifies against the system roots and
+    /// the CAs here, as it does for Docker.
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_error: Option<String>,
+    /// What's wrong with the files, e.g. a client cert without its key.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+}
+
+/// Check the certs in `dir` the way Docker reads them, and `chain`, what the
+/// registry `host` sent, against its CAs.
+pub fn check_docker_certs(
+    dir: &Path,
+    host: &str,
+    chain: &[SimpleCert],
+    now: Timestamp,
+) -> Result<DockerCerts> {
+    if !dir.is_dir() {
+        bail!(
+            "{} doesn't exist, so Docker only trusts the system roots for this registry",
+            dir.display()
+        );
+    }
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .with_context(|| format!("Reading {}", dir.display()))?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+
+    let mut report = DockerCerts {
+        dir: dir.display().to_string(),
+        ..Default::default()
+    };
+    let mut roots = verifier::bundled_roots()?;
+    for name in &names {
+        let (stem, kind) = if let Some(stem) = name.strip_suffix(".crt") {
+            (stem, DockerFileKind::Ca)
+        } else if let Some(stem) = name.strip_suffix(".cert") {
+            (stem, DockerFileKind::ClientCert)
+        } else if let Some(stem) = name.strip_suffix(".key") {
+            (stem, DockerFileKind::ClientKey)
+        } else {
+            continue;
+        };
+        let path = dir.join(name);
+        let data = fs::read(&path).with_context(|| format!("Reading {}", path.display()))?;
+
+        let mut file = DockerFile {
+            name: name.clone(),
+            kind,
+            subject: None,
+            not_after: None,
+        };
+        let certs = match kind {
+            DockerFileKind::ClientKey => {
+                if !names.contains(&format!("{stem}.cert")) {
+                    report.problems.push(format!(
+                        "{name} has no {stem}.cert, Docker fails every pull from the registry"
+                    ));
+                }
+                report.files.push(file);
+                continue;
+            }
+            _ => match certs_in(&data, name) {
+                Ok(certs) => certs,
+                Err(err) => {
+                    report.problems.push(format!("{err:#}"));
+                    report.files.push(file);
+                    continue;
+                }
+            },
+        };
+        if let Some(first) = certs.first() {
+            file.subject = Some(first.subject.name.clone());
+            file.not_after = first.validity.not_after.and_then(NotAfter::timestamp);
+        }
+        for cert in &certs {
+            if let Some(not_after) = cert.validity.not_after.and_then(NotAfter::timestamp) {
+                if not_after <= now {
+                    report.problems.push(format!(
+                        "{name}: {} expired on {}",
+                        cert.subject.name,
+                        not_after.strftime("%Y-%m-%d")
+                    ));
+                }
+            }
+        }
+
+        if kind == DockerFileKind::Ca {
+            for cert in &certs {
+                if !cert.is_ca {
+                    report.problems.push(format!(
+                        "{name}: {} isn't a CA, client certs go in .cert files",
+                        cert.subject.name
+                    ));
+                }
+            }
+            roots.extend(certs.into_iter().map(|cert| cert._cert));
+        } else {
+            report.problems.extend(client_cert_problems(
+                dir, &names, name, stem, &data, &certs,
+            )?);
+        }
+        report.files.push(file);
+    }
+
+    if !chain.is_empty() {
+        let store = verifier::store_of(roots)?;
+        match verifier::verify_result(&store, Some(host), chain)? {
+            Ok(()) => report.verified = true,
+            Err(err) => report.verify_error = Some(err.to_string()),
+        }
+    }
+    Ok(report)
+}
+
+/// What's wrong with `name`, the client cert `certs`: its key is missing or
+/// doesn't match it, or it can't be used to log in with.
+fn client_cert_problems(
+    dir: &Path,
+    names: &[String],
+    name: &str,
+    stem: &str,
+    data: &[u8],
+    certs: &[SimpleCert],
+) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    if let Some(leaf) = certs.first() {
+        let purposes = leaf.key_usage.extended.names();
+        if !purposes.is_empty() && !purposes.iter().any(|purpose| purpose == "client auth") {
+            problems.push(format!(
+                "{name}: {} isn't for client auth, only {}",
+                leaf.subject.name,
+                purposes.join(", ")
+            ));
+        }
+    }
+
+    let key = format!("{stem}.key");
+    if !names.contains(&key) {
+        problems.push(format!(
+            "{name} has no {key}, Docker fails every pull from the registry (CAs go in .crt files)"
+        ));
+        return Ok(problems);
+    }
+    let path = dir.join(&key);
+    let key_data = fs::read(&path).with_context(|| format!("Reading {}", path.display()))?;
+    let cert_spki = key_users(name, data)?.into_iter().next();
+    let key_spki = key_users(&key, &key_data)?.into_iter().next();
+    match (cert_spki, key_spki) {
+        (Some((cert, _)), Some((key_hash, _))) if cert != key_hash => {
+            problems.push(format!("{key} isn't the key of {name}"))
+        }
+        (_, None) => problems.push(format!("{key} has no key Docker can read")),
+        _ => {}
+    }
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    fn registry_chain() -> Vec<SimpleCert> {
+        X509::stack_from_pem(include_bytes!("../test-data/certs/registry.pem"))
+            .unwrap()
+            .into_iter()
+            .map(SimpleCert::from)
+            .collect()
+    }
+
+    #[test]
+    fn ports_and_dirs() {
+        assert_eq!(default_port("localhost"), 5000);
+        assert_eq!(default_port("10.0.0.5"), 5000);
+        assert_eq!(default_port("::1"), 5000);
+        assert_eq!(default_port("registry"), 5000);
+        assert_eq!(default_port("ghcr.io"), 443);
+
+        assert_eq!(
+            docker_certs_dir("ghcr.io", 443),
+            Path::new("/etc/docker/certs.d/ghcr.io")
+        );
+        assert_eq!(
+            docker_certs_dir("localhost", 5000),
+            Path::new("/etc/docker/certs.d/localhost:5000")
+        );
+        assert_eq!(
+            docker_certs_dir("::1", 5000),
+            Path::new("/etc/docker/certs.d/[::1]:5000")
+        );
+    }
+
+    #[test]
+    fn checks_certs_dir() {
+        let good = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test-data/certs.d/registry.example"
+        ));
+        let now = Timestamp::now();
+        let report = check_docker_certs(good, "registry.example", &registry_chain(), now).unwrap();
+        assert_eq!(report.problems, Vec::<String>::new());
+        assert!(report.verified, "{:?}", report.verify_error);
+        let kinds: Vec<DockerFileKind> = report.files.iter().map(|file| file.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DockerFileKind::Ca,
+                DockerFileKind::ClientCert,
+                DockerFileKind::ClientKey
+            ]
+        );
+
+        // the client cert saved as a CA, a key of something else and a stray
+        // key, without the registry's CA
+        let dir = std::env::temp_dir().join(format!("pls-certs-d-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(good.join("client.cert"), dir.join("ca.crt")).unwrap();
+        fs::copy(good.join("client.cert"), dir.join("client.cert")).unwrap();
+        fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/test-data/private-keys/ed25519.pem"
+            ),
+            dir.join("client.key"),
+        )
+        .unwrap();
+        fs::copy(good.join("client.key"), dir.join("old.key")).unwrap();
+        fs::write(dir.join("README"), "ignored").unwrap();
+
+        let report = check_docker_certs(&dir, "registry.example", &registry_chain(), now).unwrap();
+        assert_eq!(
+            report.problems,
+            [
+                "ca.crt: CN=registry client isn't a CA, client certs go in .cert files",
+                "client.key isn't the key of client.cert",
+                "old.key has no old.cert, Docker fails every pull from the registry",
+            ]
+        );
+        assert!(!report.verified);
+        assert_eq!(report.files.len(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(check_docker_certs(&dir, "registry.example", &[], now).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Checking whether a cert has been revoked. Only CRLs are checked so far;
+//! [`Revocation`] is what any method answers with, so OCSP can share it.
+
+use std::time::Duration;
+
+use color_eyre::eyre::{bail, eyre, Context, Result};
+use jiff::Timestamp;
+use schemars::JsonSchema;
+use serde::Serialize;
+use url::Url;
+
+use crate::{cache, crl, crl::SimpleCrl, der, x509::SimpleCert};
+
+/// CRLs are usually reissued daily, or more often; an hour old one is fine
+/// for a check.
+const CRL_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How `--check-revocation` finds out whether a cert is revoked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationMethod {
+    /// Download the CRL from the cert's cRLDistributionPoints.
+    #[default]
+    Crl,
+}
+
+/// Whether a cert is revoked, and how that was found out.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Revocation {
+    pub method: RevocationMethod,
+    /// The subject of the cert that was checked.
+    pub cert: String,
+    #[serde(flatten)]
+    pub status: RevocationStatus,
+    /// Where the answer came from, e.g. the CRL's URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// When the answer was issued.
+    #[schemars(with = "Option<String>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub this_update: Option<Timestamp>,
+    /// When the next answer is due.
+    #[schemars(with = "Option<String>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_update: Option<Timestamp>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RevocationStatus {
+    #[default]
+    Good,
+    Revoked {
+        #[schemars(with = "String")]
+        revoked_at: Timestamp,
+        /// Why, e.g. `keyCompromise`, when the issuer says.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// It couldn't be told, e.g. the CRL couldn't be downloaded or isn't
+    /// signed by the issuer.
+    Unknown { error: String },
+}
+
+/// Check whether `cert`, issued by `issuer`, is revoked. Failing to find out
+/// is an [`RevocationStatus::Unknown`] answer rather than an error.
+pub async fn check(
+    method: RevocationMethod,
+    cert: &SimpleCert,
+    issuer: Option<&SimpleCert>,
+) -> Revocation {
+    let revocation = Revocation {
+        method,
+        cert: cert.subject.name.clone(),
+        ..Default::default()
+    };
+    let unknown = |error: String| Revocation {
+        status: RevocationStatus::Unknown { error },
+        ..revocation.clone()
+    };
+
+    let Some(issuer) = issuer else {
+        return unknown("the issuer isn't known, to check the answer is signed by it".into());
+    };
+    match method {
+        RevocationMethod::Crl => match check_crl(cert, issuer).await {
+            Ok(answer) => Revocation {
+                source: answer.source,
+                status: answer.status,
+                this_update: answer.this_update,
+                next_update: answer.next_update,
+                ..revocation
+            },
+            Err(err) => unknown(format!("{err:#}")),
+        },
+    }
+}
+
+/// The parts of a [`Revocation`] a method fills in.
+struct Answer {
+    status: RevocationStatus,
+    source: Option<String>,
+    this_update: Option<Timestamp>,
+    next_update: Option<Timestamp>,
+}
+
+/// Look `cert` up in the first CRL at its cRLDistributionPoints that can be
+/// downloaded.
+async fn check_crl(cert: &SimpleCert, issuer: &SimpleCert) -> Result<Answer> {
+    let cert_der = cert._cert.to_der()?;
+    let urls = der::crl_distribution_points(&cert_der).context("parsing cRLDistributionPoints")?;
+    let urls: Vec<Url> = urls
+        .iter()
+        .filter_map(|url| match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Some(parsed),
+            // e.g. `ldap://` URLs, which are still found in some enterprise CAs
+            _ => {
+                tracing::debug!("skipping CRL URL {url}");
+                None
+            }
+        })
+        .collect();
+    if urls.is_empty() {
+        bail!("the cert has no HTTP CRL distribution point");
+    }
+
+    let mut last_err = None;
+    for url in urls {
+        match crl_answer(&url, &cert_der, cert, issuer).await {
+            Ok(answer) => return Ok(answer),
+            Err(err) => {
+                tracing::warn!("checking the CRL at {url}: {err:#}");
+                last_err = Some(err.wrap_err(format!("checking the CRL at {url}")));
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| eyre!("no CRL could be checked")))
+}
+
+async fn crl_answer(
+    url: &Url,
+    cert_der: &[u8],
+    cert: &SimpleCert,
+    issuer: &SimpleCert,
+) -> Result<Answer> {
+    let body = cache::get(url, CRL_TTL).await?;
+    let der = crl_der(&body)?;
+
+    let fields = der::crl_fields(&der).context("parsing CRL")?;
+    let (cert_issuer, _) = der::cert_names(cert_der)?;
+    if fields.issuer.raw != cert_issuer.raw {
+        bail!("the CRL is for another issuer");
+    }
+    let key = issuer._cert.public_key()?;
+    if !crl::verify_signature(&der, &key)? {
+        bail!("the CRL isn't signed by {}", issuer.subject.name);
+    }
+
+    let crl = SimpleCrl::from_der(&der)?;
+    if let Some(next_update) = crl.next_update {
+        if next_update < Timestamp::now() {
+            bail!("the CRL is out of date, its next update was due {next_update}");
+        }
+    }
+
+    let status = match crl
+        .revoked
+        .into_iter()
+        .find(|revoked| revoked.serial.hex == cert.serial.hex)
+    {
+        Some(revoked) => RevocationStatus::Revoked {
+            revoked_at: revoked.revoked_at,
+            reason: revoked.reason,
+        },
+        None => RevocationStatus::Good,
+    };
+    Ok(Answer {
+        status,
+        source: Some(url.to_string()),
+        this_update: Some(crl.this_update),
+        next_update: crl.next_update,
+    })
+}
+
+/// CRLs are served as DER, though some are PEM.
+fn crl_der(body: &[u8]) -> Result<Vec<u8>> {
+    const BEGIN: &str = "-----BEGIN X509 CRL-----";
+    const END: &str = "-----END X509 CRL-----";
+
+    let text = String::from_utf8_lossy(body);
+    let Some(start) = text.find(BEGIN) else {
+        return Ok(body.to_vec());
+    };
+    let base64 = &text[start + BEGIN.len()..];
+    let base64 = &base64[..base64
+        .find(END)
+        .ok_or_else(|| eyre!("unterminated PEM CRL"))?];
+    let base64: String = base64.split_whitespace().collect();
+    boring::base64::decode_block(&base64).context("decoding PEM CRL")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pem_crls() {
+        let der = [0x30, 0x03, 0x02, 0x01, 0x01];
+        assert_eq!(crl_der(&der).unwrap(), der);
+
+        let pem = crl::pem_encode("X509 CRL", &der);
+        assert_eq!(crl_der(pem.as_bytes()).unwrap(), der);
+    }
+}
@@ -0,0 +1,430 @@
+//! A small TLS server to point clients at, for `pls serve` and the
+//! end-to-end tests of `connect` in `tests/`.
+//!
+//! It presents a throwaway chain, a root, an intermediate and a leaf for
+//! the names asked for, or a given cert and key. Every HTTP/1.1 request is
+//! answered with a summary of the handshake, and every handshake is
+//! reported, including the ones that fail.
+
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use boring::{
+    asn1::Asn1Time,
+    bn::{BigNum, MsbOption},
+    ec::{EcGroup, EcKey},
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{PKey, PKeyRef, Private},
+    ssl::{
+        select_next_proto, AlpnError, NameType, SslAcceptor, SslMethod, SslRef, SslVerifyMode,
+        SslVersion,
+    },
+    x509::{
+        extension::{
+            AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage,
+            SubjectAlternativeName, SubjectKeyIdentifier,
+        },
+        X509Builder, X509NameBuilder, X509Ref, X509,
+    },
+};
+use color_eyre::eyre::{eyre, Context, Result};
+use jiff::{SignedDuration, Timestamp};
+use serde::Serialize;
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    commands::connect::alpn_wire_format, components::serve::Handshake, http, x509::SimpleCert,
+};
+
+/// How long a client gets to finish the handshake and send its request.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether the server asks clients for a cert, from `--client-auth`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientAuth {
+    /// Don't ask for one.
+    #[default]
+    None,
+    /// Ask, but accept clients without one.
+    Request,
+    /// Fail handshakes without one (mTLS). Any cert is accepted.
+    Require,
+}
+
+/// A way to misbehave on purpose, from `--scenario`, to check that clients
+/// notice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scenario {
+    /// The leaf expired yesterday.
+    Expired,
+    /// The leaf is only valid from tomorrow.
+    NotYetValid,
+    /// The leaf is for `wrong.host.invalid`, not the names asked for.
+    WrongHost,
+    /// The intermediate isn't sent, only the leaf.
+    NoIntermediate,
+    /// The leaf is self-signed, with no chain.
+    SelfSigned,
+    /// Only TLS 1.0 is spoken, with CBC ciphers.
+    #[value(name = "tls1.0")]
+    #[serde(rename = "tls1.0")]
+    Tls10,
+}
+
+impl Scenario {
+    /// What a careful client should say about it.
+    pub fn expected(self) -> &'static str {
+        match self {
+            Self::Expired => "the cert has expired",
+            Self::NotYetValid => "the cert isn't valid yet",
+            Self::WrongHost => "the cert is for another hostname",
+            Self::NoIntermediate => "the chain is incomplete, the issuer is missing",
+            Self::SelfSigned => "the cert is self-signed",
+            Self::Tls10 => "the protocol version is too old",
+        }
+    }
+}
+
+/// The cert and key the server presents.
+pub(crate) struct Identity {
+    /// The leaf first, then the intermediates sent with it.
+    pub chain: Vec<X509>,
+    pub key: PKey<Private>,
+    /// The root of a generated chain, for clients to trust.
+    pub root: Option<X509>,
+}
+
+/// What a generated cert is for.
+enum Role<'a> {
+    Root,
+    Intermediate,
+    Leaf(&'a [String]),
+}
+
+impl Identity {
+    /// A fresh chain for `names`, DNS names or IP addresses, with P-256 keys.
+    /// The certs are valid from an hour ago for 30 days, unless the
+    /// `scenario` says otherwise.
+    pub fn generate(names: &[String], scenario: Option<Scenario>) -> Result<Self> {
+        let now = Timestamp::now();
+        let days = |days: i64| SignedDuration::from_hours(days * 24);
+        let validity = (now - SignedDuration::from_hours(1), now + days(30));
+        let leaf_validity = match scenario {
+            Some(Scenario::Expired) => (now - days(31), now - days(1)),
+            Some(Scenario::NotYetValid) => (now + days(1), now + days(31)),
+            _ => validity,
+        };
+        let wrong_host = ["wrong.host.invalid".to_string()];
+        let names = match scenario {
+            Some(Scenario::WrongHost) => &wrong_host[..],
+            _ => names,
+        };
+        let common_name = names.first().map_or("localhost", String::as_str);
+
+        if scenario == Some(Scenario::SelfSigned) {
+            let (leaf, key) = issue(common_name, Role::Leaf(names), None, leaf_validity)?;
+            return Ok(Self {
+                chain: vec![leaf],
+                key,
+                root: None,
+            });
+        }
+
+        let (root, root_key) = issue("pls serve root", Role::Root, None, validity)?;
+        let (intermediate, intermediate_key) = issue(
+            "pls serve intermediate",
+            Role::Intermediate,
+            Some((&*root, &*root_key)),
+            validity,
+        )?;
+        let (leaf, key) = issue(
+            common_name,
+            Role::Leaf(names),
+            Some((&*intermediate, &*intermediate_key)),
+            leaf_validity,
+        )?;
+
+        let chain = match scenario {
+            Some(Scenario::NoIntermediate) => vec![leaf],
+            _ => vec![leaf, intermediate],
+        };
+        Ok(Self {
+            chain,
+            key,
+            root: Some(root),
+        })
+    }
+
+    /// The PEM cert (and chain) at `cert` with the key at `key`.
+    pub fn from_files(cert: &Path, key: &Path) -> Result<Self> {
+        let read = |path: &Path| {
+            std::fs::read(path).with_context(|| format!("Reading {}", path.display()))
+        };
+        let chain = X509::stack_from_pem(&read(cert)?)
+            .with_context(|| format!("Parsing the certs in {}", cert.display()))?;
+        if chain.is_empty() {
+            return Err(eyre!("no certs in {}", cert.display()));
+        }
+        let key = PKey::private_key_from_pem(&read(key)?)
+            .with_context(|| format!("Parsing the key in {}", key.display()))?;
+        Ok(Self {
+            chain,
+            key,
+            root: None,
+        })
+    }
+}
+
+/// Generate a key and a cert for it, signed by `issuer` or self-signed.
+fn issue(
+    common_name: &str,
+    role: Role,
+    issuer: Option<(&X509Ref, &PKeyRef<Private>)>,
+    (not_before, not_after): (Timestamp, Timestamp),
+) -> Result<(X509, PKey<Private>)> {
+    let key = PKey::from_ec_key(EcKey::generate(&EcGroup::from_curve_name(
+        Nid::X9_62_PRIME256V1,
+    )?)?)?;
+
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "pls serve")?;
+    name.append_entry_by_nid(Nid::COMMONNAME, common_name)?;
+    let name = name.build();
+
+    let mut builder = X509Builder::new()?;
+    builder.set_version(2)?;
+    let mut serial = BigNum::new()?;
+    serial.rand(127, MsbOption::MAYBE_ZERO, false)?;
+    builder.set_serial_number(&serial.to_asn1_integer()?)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(issuer.map_or(&*name, |(cert, _)| cert.subject_name()))?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&Asn1Time::from_unix(not_before.as_second() as libc::time_t)?)?;
+    builder.set_not_after(&Asn1Time::from_unix(not_after.as_second() as libc::time_t)?)?;
+
+    match role {
+        Role::Root | Role::Intermediate => {
+            builder.append_extension(BasicConstraints::new().critical().ca().build()?)?;
+            builder.append_extension(
+                KeyUsage::new()
+                    .critical()
+                    .key_cert_sign()
+                    .crl_sign()
+                    .build()?,
+            )?;
+        }
+        Role::Leaf(names) => {
+            builder.append_extension(BasicConstraints::new().critical().build()?)?;
+            builder.append_extension(KeyUsage::new().critical().digital_signature().build()?)?;
+            builder.append_extension(ExtendedKeyUsage::new().server_auth().build()?)?;
+            let mut sans = SubjectAlternativeName::new();
+            for name in names {
+                match name.parse::<IpAddr>() {
+                    Ok(_) => sans.ip(name),
+                    Err(_) => sans.dns(name),
+                };
+            }
+            let sans = sans.build(&builder.x509v3_context(issuer.map(|(cert, _)| cert), None))?;
+            builder.append_extension(sans)?;
+        }
+    }
+    let ski = SubjectKeyIdentifier::new().build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(ski)?;
+    if let Some((cert, _)) = issuer {
+        let aki = AuthorityKeyIdentifier::new()
+            .keyid(true)
+            .build(&builder.x509v3_context(Some(cert), None))?;
+        builder.append_extension(aki)?;
+    }
+
+    let signer = issuer.map_or(&*key, |(_, key)| key);
+    builder.sign(signer, MessageDigest::sha256())?;
+    Ok((builder.build(), key))
+}
+
+/// The TLS config of the server: `identity`, the `alpn` protocols it
+/// selects from, in order of preference, whether it asks for client certs
+/// and the protocol versions of the `scenario`.
+pub(crate) fn acceptor(
+    identity: &Identity,
+    alpn: &[String],
+    client_auth: ClientAuth,
+    scenario: Option<Scenario>,
+) -> Result<SslAcceptor> {
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+    if scenario == Some(Scenario::Tls10) {
+        builder.set_min_proto_version(Some(SslVersion::TLS1))?;
+        builder.set_max_proto_version(Some(SslVersion::TLS1))?;
+        // the intermediate profile only has TLS 1.2 ciphers
+        builder.set_cipher_list("ECDHE-ECDSA-AES128-SHA:ECDHE-RSA-AES128-SHA:AES128-SHA")?;
+    }
+    let (leaf, intermediates) = identity
+        .chain
+        .split_first()
+        .ok_or_else(|| eyre!("no cert to serve"))?;
+    builder.set_certificate(leaf)?;
+    for cert in intermediates {
+        builder.add_extra_chain_cert(cert.clone())?;
+    }
+    builder.set_private_key(&identity.key)?;
+    builder
+        .check_private_key()
+        .context("the key doesn't match the cert")?;
+
+    if !alpn.is_empty() {
+        let wire = alpn_wire_format(alpn)?;
+        builder.set_alpn_select_callback(move |_, client| {
+            select_next_proto(&wire, client).ok_or(AlpnError::NOACK)
+        });
+    }
+    // a server for testing clients takes any client cert
+    match client_auth {
+        ClientAuth::None => {}
+        ClientAuth::Request => builder.set_verify_callback(SslVerifyMode::PEER, |_, _| true),
+        ClientAuth::Require => builder.set_verify_callback(
+            SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT,
+            |_, _| true,
+        ),
+    }
+
+    Ok(builder.build())
+}
+
+/// Accept connections on `listener` until the process exits, calling
+/// `on_handshake` after every handshake, whether it worked or not.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    acceptor: SslAcceptor,
+    on_handshake: impl Fn(Handshake) + Send + Sync + 'static,
+) {
+    let acceptor = Arc::new(acceptor);
+    let on_handshake = Arc::new(on_handshake);
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                tracing::warn!("accepting a connection: {err}");
+                continue;
+            }
+        };
+        let (acceptor, on_handshake) = (acceptor.clone(), on_handshake.clone());
+        tokio::spawn(async move {
+            let handshake = match tokio::time::timeout(TIMEOUT, respond(stream, &acceptor)).await {
+                Ok(handshake) => handshake,
+                Err(_) => Handshake::failed(peer.to_string(), "timed out".to_string()),
+            };
+            on_handshake(handshake);
+        });
+    }
+}
+
+/// Complete the handshake and answer one HTTP/1.1 request, if the client
+/// speaks HTTP/1.1.
+async fn respond(stream: TcpStream, acceptor: &SslAcceptor) -> Handshake {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_string(), |peer| peer.to_string());
+    let mut tls = match tokio_boring::accept(acceptor, stream).await {
+        Ok(tls) => tls,
+        Err(err) => return Handshake::failed(peer, err.to_string()),
+    };
+    let handshake = summarize(peer, tls.ssl());
+    if handshake
+        .alpn
+        .as_deref()
+        .is_some_and(|alpn| alpn != "http/1.1")
+    {
+        let _ = tls.shutdown().await;
+        return handshake;
+    }
+
+    let Some(request) = http::read_request_head(&mut tls).await else {
+        return handshake;
+    };
+
+    let head = request.starts_with(b"HEAD ");
+    let body = handshake.to_string();
+    let response = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        body.len(),
+        if head { "" } else { &body }
+    );
+    if let Err(err) = tls.write_all(response.as_bytes()).await {
+        tracing::debug!("writing the response: {err}");
+    }
+    let _ = tls.shutdown().await;
+    handshake
+}
+
+/// What was negotiated with the client.
+fn summarize(peer: String, ssl: &SslRef) -> Handshake {
+    Handshake {
+        peer,
+        version: Some(ssl.version_str().to_string()),
+        cipher: ssl.current_cipher().map(|cipher| cipher.name().to_string()),
+        alpn: ssl
+            .selected_alpn_protocol()
+            .map(|alpn| String::from_utf8_lossy(alpn).into_owned()),
+        sni: ssl.servername(NameType::HOST_NAME).map(str::to_string),
+        resumed: ssl.session_reused(),
+        client_cert: ssl
+            .peer_certificate()
+            .map(|cert| SimpleCert::from(cert).subject.name),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_a_chain() {
+        let names = ["localhost".to_string(), "127.0.0.1".to_string()];
+        let identity = Identity::generate(&names, None).unwrap();
+        let root = identity.root.as_ref().unwrap();
+        let [leaf, intermediate] = &identity.chain[..] else {
+            panic!("expected a leaf and an intermediate");
+        };
+
+        assert!(intermediate.verify(&root.public_key().unwrap()).unwrap());
+        assert!(leaf.verify(&intermediate.public_key().unwrap()).unwrap());
+        let leaf = SimpleCert::from(leaf.clone());
+        assert_eq!(leaf.subject.sans.dns, ["localhost"]);
+        assert!(leaf.matches_host("127.0.0.1"));
+        assert!(!leaf.is_ca);
+
+        acceptor(&identity, &["h2".to_string()], ClientAuth::Require, None).unwrap();
+    }
+
+    #[test]
+    fn scenarios() {
+        let names = ["localhost".to_string()];
+        let now = Timestamp::now();
+        let leaf = |scenario| {
+            let identity = Identity::generate(&names, Some(scenario)).unwrap();
+            acceptor(&identity, &[], ClientAuth::None, Some(scenario)).unwrap();
+            let chain = identity.chain.len();
+            (SimpleCert::from(identity.chain[0].clone()), chain)
+        };
+
+        let (expired, _) = leaf(Scenario::Expired);
+        let not_after = expired.validity.not_after.unwrap().timestamp().unwrap();
+        assert!(not_after < now);
+        let (wrong, _) = leaf(Scenario::WrongHost);
+        assert!(!wrong.matches_host("localhost"));
+        assert_eq!(leaf(Scenario::NoIntermediate).1, 1);
+        let (self_signed, chain) = leaf(Scenario::SelfSigned);
+        assert!(self_signed.is_self_signed);
+        assert_eq!(chain, 1);
+        assert_eq!(leaf(Scenario::Tls10).1, 2);
+    }
+}
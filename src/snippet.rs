@@ -0,0 +1,263 @@
+//! Code to paste into a program, for `--emit-snippet`: certs as DER
+//! constants, to embed a private root, and key pins with the check that uses
+//! them.
+
+use color_eyre::eyre::{Context, Result};
+
+use crate::x509::SimpleCert;
+
+/// How many bytes go on a line of a byte array.
+const BYTES_PER_LINE: usize = 12;
+
+/// The languages `--emit-snippet` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Language {
+    /// A `&[u8]` constant, e.g. for `reqwest::Certificate::from_der`.
+    Rust,
+    /// A `[]byte` variable, e.g. for `x509.ParseCertificate`.
+    Go,
+    /// A `bytes` constant, e.g. for `ssl.create_default_context(cadata=...)`.
+    Python,
+}
+
+/// Each cert's DER as a constant named after its subject, with how to
+/// trust it.
+pub fn embed_certs(language: Language, certs: &[SimpleCert]) -> Result<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut out = String::new();
+    for cert in certs {
+        let der = cert._cert.to_der().context("encoding cert")?;
+        let name = unique(identifier(language, &cert_words(cert)), &names);
+        let subject = &cert.subject.name;
+        out += &match language {
+            Language::Rust => format!(
+                "/// {subject}\n\
+                 /// Trust it with `reqwest::Certificate::from_der({name})`.\n\
+                 pub const {name}: &[u8] = &[\n{}];\n",
+                byte_lines(&der, "    "),
+            ),
+            Language::Go => format!(
+                "// {name} is the DER of {subject}.\n\
+                 // Trust it with x509.ParseCertificate({name}) and a CertPool's AddCert.\n\
+                 var {name} = []byte{{\n{}}}\n",
+                byte_lines(&der, "\t"),
+            ),
+            Language::Python => format!(
+                "# {subject}\n\
+                 # Trust it with ssl.create_default_context(cadata={name})\n\
+                 {name} = bytes.fromhex(\n{})\n",
+                hex_lines(&der),
+            ),
+        };
+        out.push('\n');
+        names.push(name);
+    }
+    out.pop();
+    Ok(out)
+}
+
+/// A check that a server's key is one of `pins`, the base64 SHA-256 of
+/// SubjectPublicKeyInfos.
+pub fn pins(language: Language, pins: &[&str]) -> Result<String> {
+    Ok(match language {
+        Language::Rust => {
+            let mut arrays = String::new();
+            for pin in pins {
+                let hash = boring::base64::decode_block(pin).context("decoding pin")?;
+                arrays += &format!(
+                    "    // sha256/{pin}\n    [\n{}    ],\n",
+                    byte_lines(&hash, "        ")
+                );
+            }
+            format!(
+                "/// The SHA-256 of each pinned SubjectPublicKeyInfo.\n\
+                 pub const PINS: &[[u8; 32]] = &[\n{arrays}];\n\
+                 \n\
+                 /// Whether `spki`, the DER SubjectPublicKeyInfo of a cert the server sent, is\n\
+                 /// pinned. Call it from a rustls `ServerCertVerifier` for each cert in the chain.\n\
+                 pub fn is_pinned(spki: &[u8]) -> bool {{\n    \
+                     use sha2::{{Digest, Sha256}};\n    \
+                     PINS.contains(&Sha256::digest(spki).into())\n\
+                 }}\n"
+            )
+        }
+        Language::Go => {
+            let pins: String = pins
+                .iter()
+                .map(|pin| format!("\t\"{pin}\": true,\n"))
+                .collect();
+            format!(
+                "// pins are the base64 SHA-256 of each pinned SubjectPublicKeyInfo.\n\
+                 var pins = map[string]bool{{\n{pins}}}\n\
+                 \n\
+                 // checkPins is a tls.Config VerifyConnection that fails unless a cert the\n\
+                 // server sent is pinned.\n\
+                 func checkPins(state tls.ConnectionState) error {{\n\
+                 \tfor _, cert := range state.PeerCertificates {{\n\
+                 \t\tsum := sha256.Sum256(cert.RawSubjectPublicKeyInfo)\n\
+                 \t\tif pins[base64.StdEncoding.EncodeToString(sum[:])] {{\n\
+                 \t\t\treturn nil\n\
+                 \t\t}}\n\
+                 \t}}\n\
+                 \treturn errors.New(\"no cert matches a pin\")\n\
+                 }}\n"
+            )
+        }
+        Language::Python => {
+            let pins: String = pins.iter().map(|pin| format!("    \"{pin}\",\n")).collect();
+            format!(
+                "import base64\n\
+                 import hashlib\n\
+                 \n\
+                 from cryptography import x509\n\
+                 from cryptography.hazmat.primitives import serialization\n\
+                 \n\
+                 # The base64 SHA-256 of each pinned SubjectPublicKeyInfo.\n\
+                 PINS = {{\n{pins}}}\n\
+                 \n\
+                 \n\
+                 def is_pinned(der: bytes) -> bool:\n    \
+                     \"\"\"Whether the key of `der`, e.g. `sock.getpeercert(binary_form=True)`, is pinned.\"\"\"\n    \
+                     spki = x509.load_der_x509_certificate(der).public_key().public_bytes(\n        \
+                         serialization.Encoding.DER,\n        \
+                         serialization.PublicFormat.SubjectPublicKeyInfo,\n    \
+                     )\n    \
+                     return base64.b64encode(hashlib.sha256(spki).digest()).decode() in PINS\n"
+            )
+        }
+    })
+}
+
+/// The words to name a cert's constant after: its CN, or its whole subject.
+fn cert_words(cert: &SimpleCert) -> Vec<String> {
+    let name = cert
+        .subject
+        .dn
+        .common_name
+        .as_deref()
+        .unwrap_or(&cert.subject.name);
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+/// `words` as an identifier in `language`'s style for constants, e.g.
+/// `ISRG_ROOT_X1` or `isrgRootX1` in Go.
+fn identifier(language: Language, words: &[String]) -> String {
+    let words: Vec<&str> = match words.first() {
+        Some(first) if !first.starts_with(|c: char| c.is_ascii_digit()) => {
+            words.iter().map(String::as_str).collect()
+        }
+        // identifiers can't start with a digit
+        _ => std::iter::once("cert")
+            .chain(words.iter().map(String::as_str))
+            .collect(),
+    };
+    match language {
+        Language::Rust | Language::Python => words.join("_").to_ascii_uppercase(),
+        Language::Go => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| match i {
+                0 => word.to_string(),
+                _ => word[..1].to_ascii_uppercase() + &word[1..],
+            })
+            .collect(),
+    }
+}
+
+/// `name`, or with a number after it when it's already taken.
+fn unique(name: String, taken: &[String]) -> String {
+    if !taken.contains(&name) {
+        return name;
+    }
+    (2..)
+        .map(|n| format!("{name}{n}"))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("some number is free")
+}
+
+/// `bytes` as `0x30, 0x82, ...`, [`BYTES_PER_LINE`] to a line.
+fn byte_lines(bytes: &[u8], indent: &str) -> String {
+    bytes
+        .chunks(BYTES_PER_LINE)
+        .map(|line| {
+            let bytes: Vec<String> = line.iter().map(|b| format!("0x{b:02x}")).collect();
+            format!("{indent}{},\n", bytes.join(", "))
+        })
+        .collect()
+}
+
+/// `bytes` as adjacent hex string literals, 32 bytes to a line.
+fn hex_lines(bytes: &[u8]) -> String {
+    bytes
+        .chunks(32)
+        .map(|line| format!("    \"{}\"\n", hex::encode(line)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn names_and_bytes() {
+        let words =
+            |name: &str| -> Vec<String> { name.split(' ').map(str::to_ascii_lowercase).collect() };
+        assert_eq!(
+            identifier(Language::Rust, &words("ISRG Root X1")),
+            "ISRG_ROOT_X1"
+        );
+        assert_eq!(
+            identifier(Language::Go, &words("ISRG Root X1")),
+            "isrgRootX1"
+        );
+        assert_eq!(
+            identifier(Language::Python, &words("1 Root")),
+            "CERT_1_ROOT"
+        );
+        assert_eq!(identifier(Language::Rust, &[]), "CERT");
+        assert_eq!(identifier(Language::Go, &[]), "cert");
+        assert_eq!(unique("CA".into(), &["CA".into(), "CA2".into()]), "CA3");
+
+        assert_eq!(byte_lines(&[0x30, 0x03], "\t"), "\t0x30, 0x03,\n");
+        assert_eq!(byte_lines(&[0; 13], "").lines().count(), 2);
+    }
+
+    #[test]
+    fn pin_checks() {
+        let pin = "YLh1dUR9y6Kja30RrAn7JKnbQG/uEtLMkBgFF2Fuihg=";
+        let rust = pins(Language::Rust, &[pin]).unwrap();
+        assert!(rust.contains(&format!(
+            "    // sha256/{pin}\n    [\n        0x60, 0xb8, 0x75,"
+        )));
+        assert!(pins(Language::Go, &[pin])
+            .unwrap()
+            .contains(&format!("\t\"{pin}\": true,\n")));
+        assert!(pins(Language::Rust, &["not base64!"]).is_err());
+    }
+
+    #[test]
+    fn embeds_each_cert() {
+        let certs: Vec<SimpleCert> =
+            X509::stack_from_pem(include_bytes!("../test-data/certs/chain.pem"))
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect();
+        let rust = embed_certs(Language::Rust, &certs).unwrap();
+        assert_eq!(rust.matches("pub const").count(), certs.len());
+        assert!(rust.contains("pub const ISRG_ROOT_X2: &[u8] = &[\n    0x30, 0x82,"));
+
+        let python = embed_certs(Language::Python, &certs[..1]).unwrap();
+        let der = certs[0]._cert.to_der().unwrap();
+        let hex: String = python
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix('"')?.strip_suffix('"'))
+            .collect();
+        assert_eq!(hex, hex::encode(der));
+    }
+}
@@ -0,0 +1,182 @@
+//! What `connect --state` and `watch --state` saw of each host last time, so
+//! a cert that changed between runs can be reported without a database: the
+//! state is one JSON file of the last cert seen per host.
+
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::Path};
+
+use color_eyre::eyre::{Context, Result};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::state::{CertChanges, FieldChange},
+    x509::{NotAfter, SimpleCert},
+};
+
+/// The parts of a cert that are compared between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub sha256: String,
+    pub serial: String,
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_after: Option<Timestamp>,
+    pub seen_at: Timestamp,
+}
+
+impl Snapshot {
+    pub fn new(cert: &SimpleCert, seen_at: Timestamp) -> Self {
+        let sans = &cert.subject.sans;
+        let mut names: Vec<String> = sans
+            .dns
+            .iter()
+            .cloned()
+            .chain(sans.ip.iter().map(ToString::to_string))
+            .chain(sans.email.iter().cloned())
+            .chain(sans.uri.iter().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        Snapshot {
+            sha256: cert.fingerprints.sha256.clone(),
+            serial: cert.serial.hex.clone(),
+            subject: cert.subject.name.clone(),
+            issuer: cert.issuer.name.clone(),
+            sans: names,
+            not_after: cert.validity.not_after.and_then(NotAfter::timestamp),
+            seen_at,
+        }
+    }
+
+    /// How `self`, the cert seen now, differs from `previous`.
+    fn changes_since(&self, previous: &Snapshot) -> CertChanges {
+        let mut fields = Vec::new();
+        let mut compare = |field: &str, before: &str, after: &str| {
+            if before != after {
+                fields.push(FieldChange {
+                    field: field.to_string(),
+                    before: before.to_string(),
+                    after: after.to_string(),
+                });
+            }
+        };
+        compare("sha256", &previous.sha256, &self.sha256);
+        compare("serial", &previous.serial, &self.serial);
+        compare("subject", &previous.subject, &self.subject);
+        compare("issuer", &previous.issuer, &self.issuer);
+        let time = |time: Option<Timestamp>| time.map(|time| time.to_string()).unwrap_or_default();
+        compare(
+            "not_after",
+            &time(previous.not_after),
+            &time(self.not_after),
+        );
+
+        CertChanges {
+            since: previous.seen_at,
+            fields,
+            added_sans: (self.sans.iter())
+                .filter(|san| !previous.sans.contains(san))
+                .cloned()
+                .collect(),
+            removed_sans: (previous.sans.iter())
+                .filter(|san| !self.sans.contains(san))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// The state file: the last cert seen for each host.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct State {
+    hosts: BTreeMap<String, Snapshot>,
+}
+
+impl State {
+    /// Read the state at `path`. A file that doesn't exist yet is an empty
+    /// state.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Parsing the state file {}", path.display())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(State::default()),
+            Err(err) => Err(err).with_context(|| format!("Reading {}", path.display())),
+        }
+    }
+
+    /// Write the state to `path`, creating its directory. It's written to a
+    /// temporary file first, so an interrupted write can't corrupt it.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("Creating {}", dir.display()))?;
+        }
+        let json = serde_json::to_vec_pretty(self)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json).with_context(|| format!("Writing {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("Writing {}", path.display()))
+    }
+
+    /// Remember `snapshot` as `host`'s cert, returning how it differs from
+    /// the one seen before, or `None` for a host not seen before.
+    pub fn record(&mut self, host: &str, snapshot: Snapshot) -> Option<CertChanges> {
+        let changes = self
+            .hosts
+            .get(host)
+            .map(|previous| snapshot.changes_since(previous));
+        // an unchanged cert keeps when it was first seen, for `since`
+        if changes.as_ref().is_some_and(CertChanges::is_empty) {
+            return changes;
+        }
+        self.hosts.insert(host.to_string(), snapshot);
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boring::x509::X509;
+
+    use super::*;
+
+    #[test]
+    fn reports_changes() {
+        let certs: Vec<SimpleCert> =
+            X509::stack_from_pem(include_bytes!("../test-data/certs/chain.pem"))
+                .unwrap()
+                .into_iter()
+                .map(SimpleCert::from)
+                .collect();
+        let first: Timestamp = "2025-01-01T00:00:00Z".parse().unwrap();
+        let later: Timestamp = "2025-02-01T00:00:00Z".parse().unwrap();
+
+        let mut state = State::default();
+        assert!(state
+            .record("lan.fish:443", Snapshot::new(&certs[0], first))
+            .is_none());
+        let same = state
+            .record("lan.fish:443", Snapshot::new(&certs[0], later))
+            .unwrap();
+        assert!(same.is_empty());
+        assert_eq!(same.since, first);
+
+        // the host starts serving another cert
+        let changed = state
+            .record("lan.fish:443", Snapshot::new(&certs[1], later))
+            .unwrap();
+        let fields: Vec<&str> = changed.fields.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["sha256", "serial", "subject", "issuer", "not_after"]
+        );
+        assert_eq!(changed.removed_sans, ["lan.fish", "p2p.lan.fish"]);
+
+        // round trips through the file
+        let path = std::env::temp_dir().join(format!("pls-state-{}.json", std::process::id()));
+        state.save(&path).unwrap();
+        let loaded = State::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.hosts, state.hosts);
+    }
+}
@@ -0,0 +1,14 @@
+//! Fixtures shared by the unit tests.
+
+use boring::x509::X509;
+
+use crate::x509::SimpleCert;
+
+/// `test-data/certs/chain.pem`, leaf first.
+pub(crate) fn chain() -> Vec<SimpleCert> {
+    X509::stack_from_pem(include_bytes!("../test-data/certs/chain.pem"))
+        .unwrap()
+        .into_iter()
+        .map(SimpleCert::from)
+        .collect()
+}
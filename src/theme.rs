@@ -1,5 +1,199 @@
-use iocraft::Color;
+use color_eyre::eyre::{bail, Result};
+use iocraft::{Color, Hooks, UseContext};
+use serde::Deserialize;
 
-pub const TOP_LEVEL_COLOR: Color = Color::Green;
-pub const HIGHLIGHT_COLOR: Color = Color::Blue;
-pub const KEY_WIDTH: u32 = 54;
+/// The colors the components render with. Selected once per invocation and
+/// handed to the views through an iocraft context, see [`UseTheme`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Top level field names, e.g. `subject:`.
+    pub top_level: Color,
+    /// The important value in a line, e.g. the negotiated curve.
+    pub highlight: Color,
+    /// Numbered headings, e.g. `cert #1:`.
+    pub heading: Color,
+    pub good: Color,
+    pub bad: Color,
+    pub warning: Color,
+    /// Secondary values that should stand out from plain text, e.g. IP SANs.
+    pub accent: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::named(ThemeName::default())
+    }
+}
+
+/// The built in themes, selected with `--theme` or `theme.name` in the config
+/// file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    /// Bright colors for dark terminal backgrounds.
+    #[default]
+    Dark,
+    /// Darker, higher contrast colors for light terminal backgrounds.
+    Light,
+    /// No colors, only bold and underlines.
+    Mono,
+}
+
+impl Theme {
+    pub fn named(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self {
+                top_level: Color::Green,
+                highlight: Color::Blue,
+                heading: Color::Magenta,
+                good: Color::Green,
+                bad: Color::Red,
+                warning: Color::Yellow,
+                accent: Color::Cyan,
+            },
+            ThemeName::Light => Self {
+                top_level: Color::DarkGreen,
+                highlight: Color::DarkBlue,
+                heading: Color::DarkMagenta,
+                good: Color::DarkGreen,
+                bad: Color::DarkRed,
+                warning: Color::DarkYellow,
+                accent: Color::DarkCyan,
+            },
+            ThemeName::Mono => Self {
+                top_level: Color::Reset,
+                highlight: Color::Reset,
+                heading: Color::Reset,
+                good: Color::Reset,
+                bad: Color::Reset,
+                warning: Color::Reset,
+                accent: Color::Reset,
+            },
+        }
+    }
+
+    /// Apply the per-color overrides from the config file.
+    pub fn with_overrides(mut self, overrides: &ThemeOverrides) -> Result<Self> {
+        let slots = [
+            (&mut self.top_level, &overrides.top_level),
+            (&mut self.highlight, &overrides.highlight),
+            (&mut self.heading, &overrides.heading),
+            (&mut self.good, &overrides.good),
+            (&mut self.bad, &overrides.bad),
+            (&mut self.warning, &overrides.warning),
+            (&mut self.accent, &overrides.accent),
+        ];
+        for (slot, value) in slots {
+            if let Some(value) = value {
+                *slot = parse_color(value)?;
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// The `[theme]` table of the config file:
+///
+/// ```toml
+/// [theme]
+/// name = "light"
+/// highlight = "#005f87"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeOverrides {
+    pub name: Option<ThemeName>,
+    pub top_level: Option<String>,
+    pub highlight: Option<String>,
+    pub heading: Option<String>,
+    pub good: Option<String>,
+    pub bad: Option<String>,
+    pub warning: Option<String>,
+    pub accent: Option<String>,
+}
+
+/// Parse a color name (`dark_blue`), an ANSI 256 color index (`33`) or an RGB
+/// hex code (`#005f87`).
+fn parse_color(value: &str) -> Result<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() == 6 {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::Rgb {
+                    r: (rgb >> 16) as u8,
+                    g: (rgb >> 8) as u8,
+                    b: rgb as u8,
+                });
+            }
+        }
+        bail!("invalid hex color {value:?}, expected `#rrggbb`");
+    }
+
+    if let Ok(index) = value.parse::<u8>() {
+        return Ok(Color::AnsiValue(index));
+    }
+
+    Ok(
+        match value.to_ascii_lowercase().replace('-', "_").as_str() {
+            "reset" | "default" | "none" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "dark_red" => Color::DarkRed,
+            "green" => Color::Green,
+            "dark_green" => Color::DarkGreen,
+            "yellow" => Color::Yellow,
+            "dark_yellow" => Color::DarkYellow,
+            "blue" => Color::Blue,
+            "dark_blue" => Color::DarkBlue,
+            "magenta" => Color::Magenta,
+            "dark_magenta" => Color::DarkMagenta,
+            "cyan" => Color::Cyan,
+            "dark_cyan" => Color::DarkCyan,
+            "white" => Color::White,
+            "grey" | "gray" => Color::Grey,
+            "dark_grey" | "dark_gray" => Color::DarkGrey,
+            _ => bail!("unknown color {value:?}"),
+        },
+    )
+}
+
+pub(crate) trait UseTheme {
+    /// The theme for this render, or the default theme when the component is
+    /// rendered outside of [`RenderOptions::print`], e.g. in tests.
+    ///
+    /// [`RenderOptions::print`]: crate::commands::RenderOptions::print
+    fn use_theme(&mut self) -> Theme;
+}
+
+impl UseTheme for Hooks<'_, '_> {
+    fn use_theme(&mut self) -> Theme {
+        self.try_use_context::<Theme>()
+            .map(|theme| *theme)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides() {
+        let overrides: ThemeOverrides =
+            toml::from_str("name = \"light\"\nhighlight = \"#005f87\"\nbad = \"9\"").unwrap();
+        let theme = Theme::named(overrides.name.unwrap())
+            .with_overrides(&overrides)
+            .unwrap();
+
+        assert_eq!(theme.top_level, Color::DarkGreen);
+        assert_eq!(
+            theme.highlight,
+            Color::Rgb {
+                r: 0x00,
+                g: 0x5f,
+                b: 0x87
+            }
+        );
+        assert_eq!(theme.bad, Color::AnsiValue(9));
+    }
+}
@@ -0,0 +1,217 @@
+//! The TLS settings in a web server's config: nginx, Apache httpd and
+//! HAProxy directives pointing at cert, key and chain files, and the
+//! protocols and ciphers they allow. Only what `inspect tls-config` checks
+//! is read, everything else in the config is skipped.
+
+use std::path::{Path, PathBuf};
+
+/// A cert (or full chain) with the key and chain files that go with it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Site {
+    /// The line of the config the cert is configured on.
+    pub line: usize,
+    /// The names of the server block (nginx `server_name`, Apache
+    /// `ServerName`) the cert is in, when known.
+    pub names: Vec<String>,
+    pub cert: PathBuf,
+    /// `None` when the key is in the cert file, as HAProxy wants it.
+    pub key: Option<PathBuf>,
+    /// A separate chain file (Apache `SSLCertificateChainFile`).
+    pub chain: Option<PathBuf>,
+}
+
+/// The TLS settings found in a config.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub sites: Vec<Site>,
+    /// The protocol versions allowed, as written, e.g. `TLSv1.2`.
+    pub protocols: Vec<String>,
+    /// The cipher lists, OpenSSL syntax.
+    pub ciphers: Vec<String>,
+}
+
+impl TlsConfig {
+    /// Read the directives of `config`. Relative paths are taken to be
+    /// relative to `dir`, the config's directory.
+    pub fn parse(config: &str, dir: &Path) -> Self {
+        let mut tls = TlsConfig::default();
+        let mut names: Vec<String> = Vec::new();
+        let path = |value: &str| dir.join(value);
+
+        for (i, line) in config.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let line = line.trim_end_matches(['{', '}', ';']).trim();
+            let mut words = line.split_whitespace().map(unquote);
+            let Some(directive) = words.next() else {
+                continue;
+            };
+            let values: Vec<&str> = words.collect();
+            let value = values.first().copied().unwrap_or_default();
+
+            match directive.to_ascii_lowercase().as_str() {
+                // a new server block
+                "server" | "<virtualhost" => names.clear(),
+                "server_name" | "servername" | "serveralias" => {
+                    names.extend(values.iter().map(|name| name.to_string()))
+                }
+
+                // nginx
+                "ssl_certificate" | "sslcertificatefile" => tls.sites.push(Site {
+                    line: line_number,
+                    names: names.clone(),
+                    cert: path(value),
+                    ..Default::default()
+                }),
+                "ssl_certificate_key" | "sslcertificatekeyfile" => {
+                    if let Some(site) = tls.sites.last_mut() {
+                        site.key = Some(path(value));
+                    }
+                }
+                "sslcertificatechainfile" => {
+                    if let Some(site) = tls.sites.last_mut() {
+                        site.chain = Some(path(value));
+                    }
+                }
+                "ssl_protocols" | "sslprotocol" => {
+                    tls.protocols = protocols(&values);
+                }
+                "ssl_ciphers" | "sslciphersuite" | "ssl-default-bind-ciphers" => {
+                    tls.ciphers.push(values.join(" "))
+                }
+
+                // HAProxy: `bind :443 ssl crt /etc/haproxy/site.pem ...`
+                "bind" => {
+                    let crt = values.windows(2).find(|pair| pair[0] == "crt");
+                    if let Some(crt) = crt {
+                        tls.sites.push(Site {
+                            line: line_number,
+                            names: Vec::new(),
+                            cert: path(crt[1]),
+                            ..Default::default()
+                        });
+                    }
+                    let ciphers = values.windows(2).find(|pair| pair[0] == "ciphers");
+                    tls.ciphers.extend(ciphers.map(|pair| pair[1].to_string()));
+                    let min = values.windows(2).find(|pair| pair[0] == "ssl-min-ver");
+                    if let Some(min) = min {
+                        tls.protocols = haproxy_min_ver(min[1]);
+                    }
+                }
+                "ssl-default-bind-options" => {
+                    if let Some(min) = values.windows(2).find(|pair| pair[0] == "ssl-min-ver") {
+                        tls.protocols = haproxy_min_ver(min[1]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        tls
+    }
+}
+
+fn unquote(word: &str) -> &str {
+    word.trim_matches(['"', '\''])
+}
+
+const VERSIONS: [&str; 5] = ["SSLv3", "TLSv1", "TLSv1.1", "TLSv1.2", "TLSv1.3"];
+
+/// The versions an nginx `ssl_protocols` or Apache `SSLProtocol` list
+/// allows. Apache's can start with `all` and take versions away with `-`.
+fn protocols(values: &[&str]) -> Vec<String> {
+    let mut allowed: Vec<&str> = Vec::new();
+    for value in values {
+        let (remove, version) = match value.strip_prefix('-') {
+            Some(version) => (true, version),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let versions: Vec<&str> = match version.to_ascii_lowercase().as_str() {
+            // Apache's `all` has been TLS 1 and newer since 2.4
+            "all" => VERSIONS[1..].to_vec(),
+            version => VERSIONS
+                .iter()
+                .copied()
+                .filter(|known| known.to_ascii_lowercase() == version)
+                .collect(),
+        };
+        if remove {
+            allowed.retain(|version| !versions.contains(version));
+        } else {
+            allowed.extend(versions.into_iter().filter(|v| !allowed.contains(v)));
+        }
+    }
+    allowed.into_iter().map(str::to_string).collect()
+}
+
+/// The versions HAProxy allows with `ssl-min-ver TLSv1.2`.
+fn haproxy_min_ver(min: &str) -> Vec<String> {
+    let min = min.replace("TLSv1.0", "TLSv1");
+    match VERSIONS.iter().position(|version| *version == min) {
+        Some(start) => VERSIONS[start..].iter().map(|v| v.to_string()).collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nginx() {
+        let config = r#"
+server {
+    listen 443 ssl;
+    server_name example.com www.example.com;
+    ssl_certificate     certs/fullchain.pem;
+    ssl_certificate_key /etc/ssl/private/example.key; # the key
+    ssl_protocols TLSv1.2 TLSv1.3;
+    ssl_ciphers 'HIGH:!aNULL:!MD5';
+}
+"#;
+        let tls = TlsConfig::parse(config, Path::new("/etc/nginx"));
+        assert_eq!(
+            tls.sites,
+            [Site {
+                line: 5,
+                names: vec!["example.com".into(), "www.example.com".into()],
+                cert: "/etc/nginx/certs/fullchain.pem".into(),
+                key: Some("/etc/ssl/private/example.key".into()),
+                chain: None,
+            }]
+        );
+        assert_eq!(tls.protocols, ["TLSv1.2", "TLSv1.3"]);
+        assert_eq!(tls.ciphers, ["HIGH:!aNULL:!MD5"]);
+    }
+
+    #[test]
+    fn apache() {
+        let config = r#"
+<VirtualHost *:443>
+    ServerName example.com
+    SSLCertificateFile "/etc/ssl/example.crt"
+    SSLCertificateKeyFile /etc/ssl/example.key
+    SSLCertificateChainFile /etc/ssl/chain.pem
+    SSLProtocol all -SSLv3 -TLSv1 -TLSv1.1
+</VirtualHost>
+"#;
+        let tls = TlsConfig::parse(config, Path::new("/etc/apache2"));
+        assert_eq!(tls.sites[0].names, ["example.com"]);
+        assert_eq!(tls.sites[0].cert, Path::new("/etc/ssl/example.crt"));
+        assert_eq!(
+            tls.sites[0].chain.as_deref(),
+            Some(Path::new("/etc/ssl/chain.pem"))
+        );
+        assert_eq!(tls.protocols, ["TLSv1.2", "TLSv1.3"]);
+    }
+
+    #[test]
+    fn haproxy() {
+        let config =
+            "frontend https\n    bind :443 ssl crt /etc/haproxy/site.pem ssl-min-ver TLSv1.0\n";
+        let tls = TlsConfig::parse(config, Path::new("/etc/haproxy"));
+        assert_eq!(tls.sites[0].cert, Path::new("/etc/haproxy/site.pem"));
+        assert_eq!(tls.sites[0].key, None);
+        assert_eq!(tls.protocols, ["TLSv1", "TLSv1.1", "TLSv1.2", "TLSv1.3"]);
+    }
+}
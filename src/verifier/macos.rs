@@ -0,0 +1,27 @@
+//! Verifying with the Security framework, as Safari and `URLSession` do.
+
+use color_eyre::eyre::Result;
+use security_framework::{
+    certificate::SecCertificate, policy::SecPolicy, secure_transport::SslProtocolSide,
+    trust::SecTrust,
+};
+
+use super::Verification;
+use crate::x509::SimpleCert;
+
+pub(super) fn verify(host: &str, certs: &[SimpleCert]) -> Result<Verification> {
+    let certs = certs
+        .iter()
+        .map(|cert| Ok(SecCertificate::from_der(&cert._cert.to_der()?)?))
+        .collect::<Result<Vec<_>>>()?;
+
+    // the SSL policy checks the name and the serverAuth usage on top of the
+    // path to a trusted root
+    let policy = SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some(host));
+    let trust = SecTrust::create_with_certificates(&certs, &[policy])?;
+
+    Ok(match trust.evaluate_with_error() {
+        Ok(()) => Verification::trusted(),
+        Err(err) => Verification::rejected(err.description().to_string()),
+    })
+}
@@ -0,0 +1,238 @@
+//! Checking a server's chain the way a particular client would, for
+//! `connect --verifier`.
+//!
+//! boring's own verdict comes from the handshake and only knows its compiled
+//! in trust store and path building. The other verifiers re-check the certs
+//! the server sent: against Mozilla's root program, or with the OS verifier
+//! browsers use, so a result matches what users of the host actually see.
+
+use std::{fmt, net::IpAddr};
+
+use boring::{
+    stack::Stack,
+    x509::{
+        store::{X509Store, X509StoreBuilder},
+        X509StoreContext, X509VerifyResult, X509,
+    },
+};
+use color_eyre::eyre::{bail, Result};
+use jiff::Timestamp;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::x509::SimpleCert;
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+/// Who decides whether the chain is trusted, from `--verifier`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Verifier {
+    /// boring's verdict from the handshake, with its default trust store.
+    #[default]
+    Boring,
+    /// boring's path building against the Mozilla root store bundled into
+    /// `pls`, the same roots Firefox and most Linux distributions trust.
+    Bundled,
+    /// The OS verifier: the Security framework on macOS and CryptoAPI on
+    /// Windows, with the user's and the system's trust settings.
+    Platform,
+}
+
+impl fmt::Display for Verifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Boring => write!(f, "boring"),
+            Self::Bundled => write!(f, "bundled roots"),
+            Self::Platform if cfg!(target_os = "macos") => write!(f, "macOS Security framework"),
+            Self::Platform if cfg!(target_os = "windows") => write!(f, "Windows CryptoAPI"),
+            Self::Platform => write!(f, "platform"),
+        }
+    }
+}
+
+/// A verifier's verdict on a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub valid: bool,
+    /// Why the chain was rejected, in the verifier's words.
+    pub error: Option<String>,
+}
+
+impl Verification {
+    fn trusted() -> Self {
+        Self {
+            valid: true,
+            error: None,
+        }
+    }
+
+    fn rejected(error: impl Into<String>) -> Self {
+        Self {
+            valid: false,
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Check `certs`, the leaf first and then the intermediates the server sent,
+/// for `host` with `verifier`. `None` for [`Verifier::Boring`], whose verdict
+/// the handshake already has.
+pub fn verify(
+    verifier: Verifier,
+    host: &str,
+    certs: &[SimpleCert],
+) -> Result<Option<Verification>> {
+    let verification = match verifier {
+        Verifier::Boring => return Ok(None),
+        _ if certs.is_empty() => bail!("the server sent no certificates to verify"),
+        Verifier::Bundled => verify_with_store(&bundled_store()?, Some(host), certs)?,
+        Verifier::Platform => platform(host, certs)?,
+    };
+    tracing::debug!("{verifier} verifier: {verification:?}");
+    Ok(Some(verification))
+}
+
+#[cfg(target_os = "macos")]
+fn platform(host: &str, certs: &[SimpleCert]) -> Result<Verification> {
+    macos::verify(host, certs)
+}
+
+#[cfg(target_os = "windows")]
+fn platform(host: &str, certs: &[SimpleCert]) -> Result<Verification> {
+    windows::verify(host, certs)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn platform(_host: &str, _certs: &[SimpleCert]) -> Result<Verification> {
+    bail!("`--verifier platform` is only available on macOS and Windows, try `--verifier bundled`")
+}
+
+/// The Mozilla roots in [`webpki_root_certs`].
+pub(crate) fn bundled_roots() -> Result<Vec<X509>> {
+    webpki_root_certs::TLS_SERVER_ROOT_CERTS
+        .iter()
+        .map(|root| Ok(X509::from_der(root)?))
+        .collect()
+}
+
+/// A store of the [`bundled_roots`].
+fn bundled_store() -> Result<X509Store> {
+    store_of(bundled_roots()?)
+}
+
+/// A store that trusts `roots`.
+pub(crate) fn store_of(roots: impl IntoIterator<Item = X509>) -> Result<X509Store> {
+    let mut store = X509StoreBuilder::new()?;
+    for root in roots {
+        store.add_cert(root)?;
+    }
+    Ok(store.build())
+}
+
+/// Check `certs` chain up to one of the [`bundled_roots`], without checking
+/// the leaf is valid for a particular host, e.g. for the chain a server is
+/// configured with.
+pub(crate) fn verify_chain(certs: &[SimpleCert]) -> Result<Verification> {
+    if certs.is_empty() {
+        bail!("no certificates to verify");
+    }
+    verify_with_store(&bundled_store()?, None, certs)
+}
+
+/// Build a path from the leaf in `certs` to a root in `store` with boring,
+/// checking the leaf is valid for `host` when one is given.
+fn verify_with_store(
+    store: &X509Store,
+    host: Option<&str>,
+    certs: &[SimpleCert],
+) -> Result<Verification> {
+    Ok(match verify_result(store, host, certs)? {
+        Ok(()) => Verification::trusted(),
+        Err(err) => Verification::rejected(err.to_string()),
+    })
+}
+
+/// boring's verdict on the path from the leaf in `certs`, through the
+/// intermediates after it, to a root in `store`, for callers that tell the
+/// errors apart.
+pub(crate) fn verify_result(
+    store: &X509Store,
+    host: Option<&str>,
+    certs: &[SimpleCert],
+) -> Result<X509VerifyResult> {
+    verify_result_at(store, host, certs, None)
+}
+
+/// [`verify_result`], checking validity at `at` rather than now.
+pub(crate) fn verify_result_at(
+    store: &X509Store,
+    host: Option<&str>,
+    certs: &[SimpleCert],
+    at: Option<Timestamp>,
+) -> Result<X509VerifyResult> {
+    let Some((leaf, intermediates)) = certs.split_first() else {
+        bail!("no certificates to verify");
+    };
+    let mut chain = Stack::new()?;
+    for cert in intermediates {
+        chain.push(cert._cert.clone())?;
+    }
+
+    let mut context = X509StoreContext::new()?;
+    Ok(context.init(store, &leaf._cert, &chain, |context| {
+        let param = context.verify_param_mut();
+        match host.map(|host| (host, host.parse::<IpAddr>())) {
+            Some((_, Ok(ip))) => param.set_ip(ip)?,
+            Some((host, Err(_))) => param.set_host(host)?,
+            None => {}
+        }
+        if let Some(at) = at {
+            param.set_time(at.as_second() as _);
+        }
+        context.verify_cert()?;
+        Ok(context.verify_result())
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::chain;
+
+    #[test]
+    fn store_verification() {
+        let certs = chain();
+        let root = certs.last().unwrap()._cert.clone();
+        let mut store = X509StoreBuilder::new().unwrap();
+        store.add_cert(root).unwrap();
+        let store = store.build();
+
+        // the chain has long expired, which is the only thing wrong with it
+        let verification = verify_with_store(&store, Some("lan.fish"), &certs).unwrap();
+        assert_eq!(
+            verification.error.as_deref(),
+            Some("certificate has expired")
+        );
+
+        let empty = X509StoreBuilder::new().unwrap().build();
+        let verification = verify_with_store(&empty, Some("lan.fish"), &certs).unwrap();
+        assert!(!verification.valid);
+        assert_ne!(
+            verification.error.as_deref(),
+            Some("certificate has expired")
+        );
+    }
+
+    #[test]
+    fn boring_keeps_the_handshake_verdict() {
+        assert_eq!(
+            verify(Verifier::Boring, "lan.fish", &chain()).unwrap(),
+            None
+        );
+        assert!(verify(Verifier::Bundled, "lan.fish", &[]).is_err());
+    }
+}
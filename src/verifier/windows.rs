@@ -0,0 +1,131 @@
+//! Verifying with CryptoAPI, the chain engine behind Schannel, Edge and
+//! Chrome on Windows.
+
+use std::{ffi::c_void, mem, ptr};
+
+use color_eyre::eyre::{eyre, Result};
+use windows_sys::Win32::Security::Cryptography::{
+    CertAddEncodedCertificateToStore, CertCloseStore, CertFreeCertificateChain,
+    CertFreeCertificateContext, CertGetCertificateChain, CertOpenStore,
+    CertVerifyCertificateChainPolicy, AUTHTYPE_SERVER, CERT_CHAIN_CONTEXT, CERT_CHAIN_PARA,
+    CERT_CHAIN_POLICY_PARA, CERT_CHAIN_POLICY_SSL, CERT_CHAIN_POLICY_STATUS, CERT_CONTEXT,
+    CERT_STORE_ADD_ALWAYS, CERT_STORE_PROV_MEMORY, HCERTSTORE, PKCS_7_ASN_ENCODING,
+    SSL_EXTRA_CERT_CHAIN_POLICY_PARA, X509_ASN_ENCODING,
+};
+
+use super::Verification;
+use crate::x509::SimpleCert;
+
+/// Closes the handles CryptoAPI hands out when they go out of scope.
+struct Store(HCERTSTORE);
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        unsafe { CertCloseStore(self.0, 0) };
+    }
+}
+
+struct Context(*const CERT_CONTEXT);
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { CertFreeCertificateContext(self.0) };
+    }
+}
+
+struct Chain(*const CERT_CHAIN_CONTEXT);
+
+impl Drop for Chain {
+    fn drop(&mut self) {
+        unsafe { CertFreeCertificateChain(self.0) };
+    }
+}
+
+/// The last Win32 error, for a failed call to `what`.
+fn last_error(what: &str) -> color_eyre::Report {
+    eyre!("{what}: {}", std::io::Error::last_os_error())
+}
+
+pub(super) fn verify(host: &str, certs: &[SimpleCert]) -> Result<Verification> {
+    let encoding = X509_ASN_ENCODING | PKCS_7_ASN_ENCODING;
+
+    unsafe {
+        // the intermediates the server sent go in a memory store, which the
+        // chain engine searches next to the system stores
+        let store = Store(CertOpenStore(CERT_STORE_PROV_MEMORY, 0, 0, 0, ptr::null()));
+        if store.0.is_null() {
+            return Err(last_error("CertOpenStore"));
+        }
+
+        let mut leaf: *mut CERT_CONTEXT = ptr::null_mut();
+        for (i, cert) in certs.iter().enumerate() {
+            let der = cert._cert.to_der()?;
+            let context = if i == 0 {
+                &mut leaf as *mut _
+            } else {
+                ptr::null_mut()
+            };
+            if CertAddEncodedCertificateToStore(
+                store.0,
+                encoding,
+                der.as_ptr(),
+                der.len() as u32,
+                CERT_STORE_ADD_ALWAYS,
+                context,
+            ) == 0
+            {
+                return Err(last_error("CertAddEncodedCertificateToStore"));
+            }
+        }
+        let leaf = Context(leaf);
+
+        let mut chain_para: CERT_CHAIN_PARA = mem::zeroed();
+        chain_para.cbSize = mem::size_of::<CERT_CHAIN_PARA>() as u32;
+        let mut chain: *mut CERT_CHAIN_CONTEXT = ptr::null_mut();
+        if CertGetCertificateChain(
+            ptr::null_mut(),
+            leaf.0,
+            ptr::null(),
+            store.0,
+            &chain_para,
+            0,
+            ptr::null(),
+            &mut chain,
+        ) == 0
+        {
+            return Err(last_error("CertGetCertificateChain"));
+        }
+        let chain = Chain(chain);
+
+        // the SSL policy checks the name and the serverAuth usage on top of
+        // the path to a trusted root
+        let mut server_name: Vec<u16> = host.encode_utf16().chain([0]).collect();
+        let mut extra: SSL_EXTRA_CERT_CHAIN_POLICY_PARA = mem::zeroed();
+        extra.Anonymous.cbSize = mem::size_of::<SSL_EXTRA_CERT_CHAIN_POLICY_PARA>() as u32;
+        extra.dwAuthType = AUTHTYPE_SERVER;
+        extra.pwszServerName = server_name.as_mut_ptr();
+
+        let mut policy_para: CERT_CHAIN_POLICY_PARA = mem::zeroed();
+        policy_para.cbSize = mem::size_of::<CERT_CHAIN_POLICY_PARA>() as u32;
+        policy_para.pvExtraPolicyPara = &mut extra as *mut _ as *mut c_void;
+        let mut status: CERT_CHAIN_POLICY_STATUS = mem::zeroed();
+        status.cbSize = mem::size_of::<CERT_CHAIN_POLICY_STATUS>() as u32;
+        if CertVerifyCertificateChainPolicy(
+            CERT_CHAIN_POLICY_SSL,
+            chain.0,
+            &policy_para,
+            &mut status,
+        ) == 0
+        {
+            return Err(last_error("CertVerifyCertificateChainPolicy"));
+        }
+
+        Ok(match status.dwError {
+            0 => Verification::trusted(),
+            // the policy errors are HRESULTs, which Windows can describe
+            error => {
+                Verification::rejected(std::io::Error::from_raw_os_error(error as i32).to_string())
+            }
+        })
+    }
+}
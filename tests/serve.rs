@@ -0,0 +1,210 @@
+//! End-to-end tests of `pls connect` against `pls serve`, both run as the
+//! built binary.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+
+use boring::{
+    pkey::{PKey, Private},
+    ssl::{SslConnector, SslMethod, SslSession, SslSessionCacheMode, SslVerifyMode},
+    x509::X509,
+};
+use serde_json::Value;
+
+/// A `pls serve --self-signed` on a free port, stopped when dropped.
+struct Server {
+    child: Child,
+    /// What it printed when it started.
+    info: Value,
+}
+
+impl Server {
+    fn start(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_pls"))
+            .args(["serve", "--self-signed", "--listen", "127.0.0.1:0"])
+            .args(["--ndjson", "--quiet"])
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("starting pls serve");
+
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        let first = lines.next().expect("pls serve exited").unwrap();
+        let info = serde_json::from_str(&first).unwrap();
+        // keep reading, so the server never blocks on a full pipe
+        std::thread::spawn(move || lines.for_each(drop));
+        Self { child, info }
+    }
+
+    fn address(&self) -> &str {
+        self.info["address"].as_str().unwrap()
+    }
+
+    /// Run `pls connect` against the server, returning its JSON output, or
+    /// `None` when it fails.
+    fn connect(&self, args: &[&str]) -> Option<Value> {
+        let output = Command::new(env!("CARGO_BIN_EXE_pls"))
+            .args(["connect", self.address(), "--json", "--quiet"])
+            .args(args)
+            .output()
+            .expect("running pls connect");
+        output
+            .status
+            .success()
+            .then(|| serde_json::from_slice(&output.stdout).unwrap())
+    }
+
+    /// `GET /` with a TLS client of its own, for what `pls connect` doesn't
+    /// do: present a client cert and resume a session. `None` when the
+    /// handshake or the request fails.
+    fn get(&self, client: &Client) -> Option<Response> {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        // the server's chain is throwaway
+        builder.set_verify(SslVerifyMode::NONE);
+        if let Some((cert, key)) = &client.cert {
+            builder.set_certificate(cert).unwrap();
+            builder.set_private_key(key).unwrap();
+        }
+        let (sessions, session) = mpsc::channel();
+        builder.set_session_cache_mode(SslSessionCacheMode::CLIENT);
+        builder.set_new_session_callback(move |_, session| {
+            let _ = sessions.send(session);
+        });
+
+        let mut config = builder.build().configure().unwrap();
+        if let Some(session) = &client.session {
+            // SAFETY: the session is from a connection to the same server
+            unsafe { config.set_session(session).unwrap() };
+        }
+        let stream = TcpStream::connect(self.address()).unwrap();
+        let mut tls = config.connect("localhost", stream).ok()?;
+        tls.write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n")
+            .ok()?;
+        let mut summary = String::new();
+        tls.read_to_string(&mut summary).ok()?;
+        if !summary.starts_with("HTTP/1.1 200 OK") {
+            return None;
+        }
+
+        Some(Response {
+            summary,
+            resumed: tls.ssl().session_reused(),
+            // TLS 1.3 tickets come after the handshake, so this is read last
+            session: session.try_recv().ok(),
+        })
+    }
+}
+
+/// What the client of [`Server::get`] brings.
+#[derive(Default)]
+struct Client {
+    /// The cert and key to present.
+    cert: Option<(X509, PKey<Private>)>,
+    /// A session of an earlier connection to resume.
+    session: Option<SslSession>,
+}
+
+/// What [`Server::get`] got back.
+struct Response {
+    /// The server's summary of the handshake.
+    summary: String,
+    resumed: bool,
+    /// The session to resume the connection with.
+    session: Option<SslSession>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn connects_and_gets_the_chain() {
+    let server = Server::start(&[]);
+    let connection = server.connect(&["--chain"]).expect("connect failed");
+
+    let certs = connection["certs"].as_array().unwrap();
+    assert_eq!(certs.len(), 2, "the leaf and the intermediate");
+    assert_eq!(
+        certs[0]["fingerprints"]["sha256"], server.info["sha256"],
+        "{connection:#}"
+    );
+    assert_eq!(connection["tls"]["version"], "TLSv1.3");
+}
+
+#[test]
+fn negotiates_alpn() {
+    let server = Server::start(&["--alpn", "h2,http/1.1"]);
+
+    let connection = server
+        .connect(&["--alpn", "http/1.1,h2", "--transcript"])
+        .expect("connect failed");
+    // the server's preference wins
+    assert_eq!(connection["tls"]["transcript"]["selected"]["alpn"], "h2");
+
+    let connection = server.connect(&["--http=get"]).expect("connect failed");
+    assert_eq!(connection["http"]["status"], 200);
+    assert_eq!(connection["http"]["alpn"], "http/1.1");
+}
+
+#[test]
+fn requires_client_certs() {
+    let server = Server::start(&["--client-auth", "require"]);
+    assert!(
+        server.connect(&[]).is_none(),
+        "connected without a client cert"
+    );
+
+    let server = Server::start(&["--client-auth", "request"]);
+    assert!(server.connect(&[]).is_some());
+}
+
+#[test]
+fn accepts_client_certs() {
+    let cert = X509::from_pem(include_bytes!(
+        "../test-data/certs.d/registry.example/client.cert"
+    ))
+    .unwrap();
+    let key = PKey::private_key_from_pem(include_bytes!(
+        "../test-data/certs.d/registry.example/client.key"
+    ))
+    .unwrap();
+    let client = Client {
+        cert: Some((cert, key)),
+        ..Client::default()
+    };
+
+    let server = Server::start(&["--client-auth", "require"]);
+    let response = server.get(&client).expect("the client cert was rejected");
+    assert!(
+        response.summary.contains("client cert: CN=registry client"),
+        "{}",
+        response.summary
+    );
+    assert!(server.get(&Client::default()).is_none());
+}
+
+#[test]
+fn resumes_sessions() {
+    let server = Server::start(&[]);
+    let first = server.get(&Client::default()).expect("get failed");
+    assert!(!first.resumed);
+    let session = first.session.expect("no session ticket");
+
+    let client = Client {
+        session: Some(session),
+        ..Client::default()
+    };
+    let second = server.get(&client).expect("get failed");
+    assert!(second.resumed);
+    assert!(
+        second.summary.contains("resumed: true"),
+        "{}",
+        second.summary
+    );
+}